@@ -3,10 +3,10 @@ use clap::{CommandFactory, Parser, Subcommand};
 use clap_complete::{generate, Shell};
 use colored::Colorize;
 use log;
-use workspace::st8::{St8Config, VersionInfo, detect_project_files, update_version_file, TemplateManager};
+use workspace::st8::{St8Config, DowngradePolicy, VersionInfo, detect_project_files, TemplateManager, BumpDecision, BumpLevel, CommitMode, HookType, VersionStrategy};
 use workspace::workspace_state::WorkspaceState;
 use workspace::entities::EntityManager;
-use workspace::logging::{self, log_operation_start, log_operation_complete, log_operation_error, log_warning, log_version_info};
+use workspace::logging::{self, LogLocation, log_operation_start, log_operation_complete, log_operation_error, log_warning, log_version_info};
 use sqlx::SqlitePool;
 use sqlx::Row;
 use std::collections::HashMap;
@@ -23,6 +23,15 @@ use std::time::Instant;
 #[command(about = "Workspace - All-in-one development tool suite")]
 #[command(after_help = "Shell completions are automatically set up on first run.")]
 struct Args {
+    /// Write span timings (phases, file operations, git calls) to this file in Chrome trace format
+    #[arg(long, global = true, value_name = "FILE")]
+    trace_output: Option<PathBuf>,
+    /// Answer yes to every confirmation prompt
+    #[arg(long, global = true, conflicts_with = "assume_no")]
+    assume_yes: bool,
+    /// Answer no to every confirmation prompt
+    #[arg(long, global = true)]
+    assume_no: bool,
     #[command(subcommand)]
     command: Commands,
 }
@@ -56,26 +65,38 @@ enum Commands {
         /// Automatically add updated files to git staging area
         #[arg(long)]
         git_add: bool,
+        /// Show the next version and the changes to each file without writing or staging anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Git hook running the update (pre-commit, post-commit or pre-push), set by installed hooks
+        #[arg(long, hide = true)]
+        hook: Option<String>,
+        /// Write the next version of this release channel (e.g. beta) to its version file instead
+        #[arg(long)]
+        channel: Option<String>,
     },
     
     /// Local trash can using a .scrap folder for files you want to delete
     Scrap {
         /// Paths to files or directories to move to .scrap folder
         paths: Vec<std::path::PathBuf>,
+        /// Allow scrapping protected paths such as .git or Cargo.toml
+        #[arg(long)]
+        force_protected: bool,
+        /// Also read paths from stdin, one per line or NUL-delimited (find -print0, fd -0)
+        #[arg(long)]
+        stdin: bool,
+        /// Use the scrap folder shared by every directory ($XDG_DATA_HOME/ws/scrap); SCRAP_DIR picks any other
+        #[arg(long, global = true)]
+        global: bool,
         #[command(subcommand)]
         command: Option<ScrapCommands>,
     },
     
     /// Restore files from .scrap folder to their original locations
     Unscrap {
-        /// Name of file/directory in .scrap to restore
-        name: Option<String>,
-        /// Force restore even if destination exists
-        #[arg(short, long)]
-        force: bool,
-        /// Restore to a different location
-        #[arg(short = 't', long)]
-        to: Option<std::path::PathBuf>,
+        #[command(flatten)]
+        args: UnscrapArgs,
     },
     
     /// Process input lines, replacing repeated tokens with a substitute character
@@ -234,18 +255,50 @@ enum Commands {
 
 #[derive(Subcommand, Debug)]
 enum GitCommands {
-    /// Install version management as a pre-commit hook in the current git repository
+    /// Install version management as a git hook in the current git repository
     Install {
         /// Force reinstallation even if already installed
         #[arg(short, long)]
         force: bool,
+        /// Hook that runs the update: pre-commit, post-commit (amends the commit) or pre-push
+        #[arg(long, default_value = "pre-commit", value_parser = ["pre-commit", "post-commit", "pre-push"])]
+        hook: String,
+        /// With the pre-push hook, stop pushes whose version is not newer than the remote's latest release tag
+        #[arg(long)]
+        require_bump: bool,
+        /// Also install the hook in every initialized submodule, nested ones included
+        #[arg(long)]
+        recursive: bool,
+        /// Turn on annotate_commit_msg: a prepare-commit-msg hook appends (version: X.Y.Z) to commit messages
+        #[arg(long)]
+        annotate_commit_msg: bool,
     },
     /// Uninstall version management from the current git repository
-    Uninstall,
+    Uninstall {
+        /// Also delete the config file, lock, cache and logs, and reset the version settings
+        #[arg(long)]
+        purge: bool,
+        /// With --purge, also delete the version file
+        #[arg(long, requires = "purge")]
+        remove_version_file: bool,
+    },
+    /// Check that installed hooks still run a working ws binary
+    Doctor,
     /// Show current version information
-    Show,
+    Show {
+        /// Output format (human, json)
+        #[arg(short, long, default_value = "human")]
+        format: String,
+    },
     /// Show git integration status
-    Status,
+    Status {
+        /// Output format (human, json)
+        #[arg(short, long, default_value = "human")]
+        format: String,
+        /// Exit non-zero when the hook is missing, the config is invalid or project files disagree with the version
+        #[arg(long)]
+        check: bool,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -946,6 +999,27 @@ enum ContinuityAction {
 
 #[derive(Subcommand, Debug)]
 enum VersionAction {
+    /// Set up versioning for this project: settings, project files, git hook and initial tag
+    Init {
+        /// File holding the version (default: version.txt)
+        #[arg(long)]
+        version_file: Option<String>,
+        /// How versions are derived (count, conventional)
+        #[arg(long, value_parser = ["count", "conventional"])]
+        strategy: Option<String>,
+        /// Install this git hook
+        #[arg(long, value_parser = ["pre-commit", "post-commit", "pre-push"])]
+        hook: Option<String>,
+        /// Do not install a git hook
+        #[arg(long, conflicts_with = "hook")]
+        no_hook: bool,
+        /// Tag HEAD as v0.1.0 when the repository has no tags yet
+        #[arg(long)]
+        initial_tag: bool,
+        /// Do not ask; use the flags and defaults
+        #[arg(short, long)]
+        yes: bool,
+    },
     /// Display current version with calculation breakdown
     Show {
         /// Show detailed calculation breakdown
@@ -954,20 +1028,41 @@ enum VersionAction {
         /// Output format (human, json)
         #[arg(short, long, default_value = "human")]
         format: String,
+        /// Show the next version of this release channel (e.g. beta) instead
+        #[arg(long)]
+        channel: Option<String>,
     },
     /// Set the major version number (stored in database)
     Major {
         /// Major version number to set
         version: u32,
     },
-    /// Create git tag with current calculated version
+    /// Create an annotated git tag for the current calculated version
     Tag {
-        /// Tag prefix (default: 'v')
-        #[arg(long, default_value = "v")]
-        prefix: String,
+        /// Tag prefix (default: the tag_prefix setting)
+        #[arg(long)]
+        prefix: Option<String>,
         /// Tag message
         #[arg(short, long)]
         message: Option<String>,
+        /// GPG-sign the tag
+        #[arg(short, long)]
+        sign: bool,
+        /// Push the tag after creating it
+        #[arg(long)]
+        push: bool,
+        /// Remote to push to
+        #[arg(long, default_value = "origin")]
+        remote: String,
+        /// Tag even though tracked files have uncommitted changes
+        #[arg(long)]
+        allow_dirty: bool,
+        /// Tag a monorepo package with its own prefix and version instead
+        #[arg(long)]
+        package: Option<String>,
+        /// Tag the next version of this release channel (e.g. beta) instead
+        #[arg(long, conflicts_with = "package")]
+        channel: Option<String>,
     },
     /// Show version calculation information and git compatibility
     Info {
@@ -975,6 +1070,189 @@ enum VersionAction {
         #[arg(long)]
         include_history: bool,
     },
+    /// Cut a specific version: bump the current one and update the version and project files
+    Bump {
+        /// Level to bump: major, minor, patch or pre
+        #[arg(value_parser = ["major", "minor", "patch", "pre"])]
+        level: String,
+        /// Prerelease label for `pre` (e.g. rc, beta); defaults to the current one
+        label: Option<String>,
+        /// Commit the updated files
+        #[arg(long)]
+        commit: bool,
+        /// Create an annotated tag for the new version
+        #[arg(long)]
+        tag: bool,
+        /// Tag prefix (default: the tag_prefix setting)
+        #[arg(long)]
+        prefix: Option<String>,
+        /// Show the new version without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Write the commits since the last release tag to CHANGELOG.md
+    Changelog {
+        #[command(subcommand)]
+        action: Option<ChangelogAction>,
+        /// Version heading for the section (default: Unreleased)
+        #[arg(long)]
+        release: Option<String>,
+        /// Print the section instead of writing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Show or set how versions are derived (count, conventional)
+    Strategy {
+        /// Strategy to use; shows the current one when omitted
+        strategy: Option<String>,
+    },
+    /// Show or set the version format, e.g. '{major}.{minor}.{patch}' or '{yyyy}.{mm}.{patch}'
+    Format {
+        /// Format to use; shows the current one and the placeholders when omitted
+        format: Option<String>,
+    },
+    /// Show or set the prerelease label appended to computed versions (-<label>.N)
+    Prerelease {
+        /// Label to append, e.g. alpha, beta, rc
+        label: Option<String>,
+        /// Stop appending a prerelease label
+        #[arg(long, conflicts_with = "label")]
+        clear: bool,
+    },
+    /// Show or set build metadata appended to computed versions (+<metadata>)
+    BuildMetadata {
+        /// Metadata template, e.g. '{short_sha}' or 'ci.{commits}'
+        template: Option<String>,
+        /// Stop appending build metadata
+        #[arg(long, conflicts_with = "template")]
+        clear: bool,
+    },
+    /// Hand the version to the CI: GitHub step outputs and env, a GitLab dotenv file, or an env file
+    Ci {
+        /// CI provider (github, gitlab, none); detected from the environment when omitted
+        #[arg(long, value_parser = ["github", "gitlab", "none"])]
+        provider: Option<String>,
+        /// Also write the variables to this file as KEY=VALUE lines
+        #[arg(long)]
+        env_file: Option<PathBuf>,
+    },
+    /// Write the version, commit and build date into a source file (version.rs, version.h, version.ts)
+    Embed {
+        /// File to write; its extension picks the built-in template
+        output: PathBuf,
+        /// Language of the built-in template (rust, c, typescript); detected from the extension when omitted
+        #[arg(long, value_parser = ["rust", "c", "typescript"])]
+        language: Option<String>,
+        /// Tera template to render instead of the built-in one
+        #[arg(long, conflicts_with = "language")]
+        template: Option<PathBuf>,
+    },
+    /// Show or change version settings (enabled, version_file, strategy, format, changelog.*, ...)
+    Config {
+        #[command(subcommand)]
+        action: Option<VersionConfigAction>,
+    },
+    /// Turn automatic version updates back on
+    Enable,
+    /// Turn automatic version updates off; 'ws update' and the git hook then leave versions alone
+    Disable,
+    /// Rewrite the project files to the current version (version file or last release tag) without recomputing it
+    Sync {
+        /// Show the lines each file would change without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Fail unless the current version is a valid semantic version and every project file carries it
+    Check,
+    /// Fail unless the current version is newer than the latest release tag on a remote
+    CheckRemote {
+        /// Remote name or URL
+        #[arg(default_value = "origin")]
+        remote: String,
+    },
+    /// Show the journal of version changes made by updates, bumps and rollbacks
+    History {
+        /// Show at most this many changes
+        #[arg(short = 'n', long)]
+        limit: Option<usize>,
+        /// Output format (human, json)
+        #[arg(short, long, default_value = "human")]
+        format: String,
+    },
+    /// Restore the version before the last change in the version file and project files
+    Rollback,
+    /// Append the version to a commit message file, run by the prepare-commit-msg hook
+    #[command(hide = true)]
+    AnnotateCommitMsg {
+        /// Commit message file
+        file: PathBuf,
+        /// Where the message came from (message, template, merge, squash or commit)
+        source: Option<String>,
+    },
+    /// List monorepo packages with their versions, or add and remove them
+    Packages {
+        #[command(subcommand)]
+        action: Option<PackagesAction>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum VersionConfigAction {
+    /// Show one setting, or all of them
+    Get {
+        /// Setting name
+        key: Option<String>,
+    },
+    /// Change one setting; an empty value clears optional settings
+    Set {
+        /// Setting name
+        key: String,
+        /// New value
+        value: String,
+    },
+    /// List the config files settings are read from, lowest precedence first
+    Files,
+    /// Print the JSON schema of the config files, for editor validation
+    Schema,
+}
+
+#[derive(Subcommand, Debug)]
+enum PackagesAction {
+    /// Version a directory as its own package
+    Add {
+        /// Package name
+        name: String,
+        /// Package directory, relative to the repository root
+        path: String,
+        /// Version file, relative to the package directory
+        #[arg(long, default_value = "version.txt")]
+        version_file: String,
+        /// Release tag prefix (default: '<name>/v')
+        #[arg(long)]
+        tag_prefix: Option<String>,
+        /// Additional project file to update, relative to the package directory
+        #[arg(long = "project-file")]
+        project_files: Vec<String>,
+        /// Don't update project files detected in the package directory
+        #[arg(long)]
+        no_auto_detect: bool,
+    },
+    /// Stop versioning a package separately
+    Remove {
+        /// Package name
+        name: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ChangelogAction {
+    /// Show or change changelog settings (enabled, path, template, exclude)
+    Config {
+        /// Setting name
+        key: Option<String>,
+        /// New value; shows the current one when omitted
+        value: Option<String>,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -985,17 +1263,44 @@ enum ScrapCommands {
         /// Sort by: name, date, size
         #[arg(short, long, default_value = "date")]
         sort: String,
+        
+        /// Reverse the sort order
+        #[arg(short, long)]
+        reverse: bool,
+        
+        /// Show at most N items
+        #[arg(short, long)]
+        limit: Option<usize>,
+        
+        /// Skip the first N items
+        #[arg(long, default_value = "0")]
+        offset: usize,
+        
+        /// Show a table with these columns: name, size, age, date, origin
+        #[arg(short, long, value_delimiter = ',')]
+        columns: Vec<String>,
+        
+        /// Page the output through $PAGER
+        #[arg(short, long)]
+        pager: bool,
+
+        #[command(flatten)]
+        filter: ScrapFilterArgs,
     },
 
     /// Clean old items from .scrap folder
     Clean {
-        /// Remove items older than N days
-        #[arg(short, long, default_value = "30")]
-        days: u64,
+        /// Remove items older than N days (default: scrap.retention_days in ws.toml, or 30)
+        #[arg(short, long)]
+        days: Option<u64>,
         
         /// Show what would be removed without actually removing
         #[arg(short = 'n', long)]
         dry_run: bool,
+        
+        /// Keep items that were never written to a `scrap archive`
+        #[arg(long)]
+        require_archived: bool,
     },
 
     /// Remove all items from .scrap folder
@@ -1003,6 +1308,17 @@ enum ScrapCommands {
         /// Skip confirmation prompt
         #[arg(short, long)]
         force: bool,
+        
+        /// Show what would be removed without actually removing
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+        
+        /// Keep items that were never written to a `scrap archive`
+        #[arg(long)]
+        require_archived: bool,
+        
+        #[command(flatten)]
+        filter: ScrapFilterArgs,
     },
 
     /// Search for files in .scrap
@@ -1016,7 +1332,11 @@ enum ScrapCommands {
     },
 
     /// Create archive of .scrap contents
+    #[command(args_conflicts_with_subcommands = true)]
     Archive {
+        #[command(subcommand)]
+        action: Option<ScrapArchiveAction>,
+
         /// Output archive path
         #[arg(short, long)]
         output: Option<std::path::PathBuf>,
@@ -1024,9 +1344,207 @@ enum ScrapCommands {
         /// Remove files after archiving
         #[arg(short, long)]
         remove: bool,
+        
+        /// Encrypt the archive with a passphrase (read from WS_SCRAP_PASSPHRASE or prompted)
+        #[arg(short, long)]
+        encrypt: bool,
+        
+        #[command(flatten)]
+        filter: ScrapFilterArgs,
+    },
+
+    /// Unpack a scrap archive back into .scrap, decrypting it if needed
+    RestoreArchive {
+        /// Archive created by `scrap archive`
+        archive: std::path::PathBuf,
+    },
+
+    /// Write every entry's restore information to a JSON manifest
+    Export {
+        /// Manifest file to write (default: .scrap/.manifest.json)
+        manifest: Option<std::path::PathBuf>,
+    },
+
+    /// Add the entries of an exported manifest to this .scrap, copying their items in
+    Import {
+        /// Manifest written by `scrap export`, or a scrap folder holding one
+        manifest: std::path::PathBuf,
+
+        /// Directory holding the items (default: the manifest's directory)
+        #[arg(long, value_name = "DIR")]
+        from: Option<std::path::PathBuf>,
+
+        /// Show what would be imported without changing anything
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+    },
+
+    /// Check .metadata.json against the .scrap folder contents
+    Doctor {
+        /// Prune orphaned entries and register untracked items
+        #[arg(long)]
+        fix: bool,
+        /// Skip comparing items with their recorded checksums
+        #[arg(long)]
+        no_verify: bool,
+    },
+
+    /// Hard-link identical files in .scrap and report space saved
+    Dedup {
+        /// Show what would be linked without changing anything
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+    },
+
+    /// Install a systemd timer (Linux) or launchd agent (macOS) that runs `scrap clean` here
+    ScheduleClean {
+        /// Remove items older than N days
+        #[arg(short, long, default_value = "30")]
+        days: u32,
+        
+        /// Run every day
+        #[arg(long, conflicts_with_all = ["weekly", "monthly"])]
+        daily: bool,
+        
+        /// Run every week (default)
+        #[arg(long, conflicts_with = "monthly")]
+        weekly: bool,
+        
+        /// Run every month
+        #[arg(long)]
+        monthly: bool,
+        
+        /// Uninstall the schedule for this directory
+        #[arg(long)]
+        remove: bool,
+        
+        /// Print the generated unit files instead of installing them
+        #[arg(long, conflicts_with = "remove")]
+        print: bool,
+    },
+
+    /// Show or change user-level scrap settings
+    Config {
+        /// Setting name (gitignore: auto, local, repo, exclude or off)
+        key: Option<String>,
+        /// New value; omit to show the current one
+        value: Option<String>,
+    },
+
+    /// List the versions kept of an item scrapped more than once
+    Versions {
+        /// Original path of the item, or the name of any of its versions in .scrap
+        path: String,
+    },
+
+    /// Show the log of scrap, unscrap, clean and purge operations
+    History {
+        /// Show only the N most recent operations
+        #[arg(short, long)]
+        limit: Option<usize>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ScrapArchiveAction {
+    /// List the monthly archives old items were tiered into, or the items of one
+    List {
+        /// Tier to show, e.g. 2024-05
+        tier: Option<String>,
     },
 }
 
+/// Filters limiting `scrap list`, `scrap purge` and `scrap archive` to matching items
+#[derive(clap::Args, Debug)]
+struct ScrapFilterArgs {
+    /// Only items scrapped longer ago than AGE (e.g. 12h, 7d, 2w; a bare number means days)
+    #[arg(long, value_name = "AGE")]
+    older_than: Option<String>,
+    /// Only items larger than SIZE (e.g. 500K, 10M, 1G)
+    #[arg(long, value_name = "SIZE")]
+    larger_than: Option<String>,
+    /// Only items whose name or original path matches this regex
+    #[arg(long, value_name = "PATTERN")]
+    matching: Option<String>,
+    /// Only items that were files
+    #[arg(long, conflicts_with = "dirs_only")]
+    files_only: bool,
+    /// Only items that were directories
+    #[arg(long)]
+    dirs_only: bool,
+}
+
+impl ScrapFilterArgs {
+    fn push_args(self, args: &mut Vec<String>) {
+        if let Some(age) = self.older_than {
+            args.push("--older-than".to_string());
+            args.push(age);
+        }
+        if let Some(size) = self.larger_than {
+            args.push("--larger-than".to_string());
+            args.push(size);
+        }
+        if let Some(pattern) = self.matching {
+            args.push("--matching".to_string());
+            args.push(pattern);
+        }
+        if self.files_only {
+            args.push("--files-only".to_string());
+        }
+        if self.dirs_only {
+            args.push("--dirs-only".to_string());
+        }
+    }
+}
+
+/// Arguments for `ws unscrap`
+#[derive(clap::Args, Debug)]
+struct UnscrapArgs {
+    /// Name of file/directory in .scrap to restore
+    name: Option<String>,
+    /// Force restore even if destination exists (same as --on-conflict overwrite)
+    #[arg(short, long, conflicts_with = "on_conflict")]
+    force: bool,
+    /// What to do when the destination exists: fail, overwrite, rename, backup
+    #[arg(long, value_parser = ["fail", "overwrite", "rename", "backup"])]
+    on_conflict: Option<String>,
+    /// Restore to a different location
+    #[arg(short = 't', long)]
+    to: Option<std::path::PathBuf>,
+    /// Restore every item in .scrap to its original location
+    #[arg(long, conflicts_with_all = ["name", "to", "matching"])]
+    all: bool,
+    /// Restore every item whose name or original path matches this regex
+    #[arg(long, conflicts_with_all = ["name", "to"])]
+    matching: Option<String>,
+    /// Restore the items originally at this path or glob, asking which version
+    /// when one was scrapped several times
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["name", "all", "matching"])]
+    from_origin: Option<String>,
+    /// Restore this version (see `ws scrap versions`) of the named item or of --from-origin
+    #[arg(long, value_name = "N", conflicts_with_all = ["all", "matching"])]
+    version: Option<u32>,
+    /// Merge a scrapped directory into an existing directory file-by-file
+    #[arg(long)]
+    merge: bool,
+    /// Translate original paths under OLD_ROOT to NEW_ROOT before restoring
+    /// (detected automatically when the project itself has moved)
+    #[arg(long, num_args = 2, value_names = ["OLD_ROOT", "NEW_ROOT"])]
+    rebase: Option<Vec<std::path::PathBuf>>,
+    /// Show what would be restored without moving anything
+    #[arg(short = 'n', long)]
+    dry_run: bool,
+    /// Skip comparing the item with the checksum recorded when it was scrapped
+    #[arg(long)]
+    no_verify: bool,
+    /// Restore from the scrap folder shared by every directory ($XDG_DATA_HOME/ws/scrap)
+    #[arg(long)]
+    global: bool,
+    /// Print scrapped item names for shell completion
+    #[arg(long, hide = true)]
+    complete_items: bool,
+}
+
 fn main() {
     // Initialize logging as early as possible
     let debug_mode = std::env::args().any(|arg| arg == "--debug" || arg == "-v" || arg == "--verbose");
@@ -1059,15 +1577,58 @@ fn run() -> Result<()> {
     let start_time = Instant::now();
     let args = Args::parse();
     log::debug!("Parsed command line arguments: {:?}", args);
+    if args.assume_yes || args.assume_no {
+        workspace::prompt::set_assumed_answer(args.assume_yes);
+    }
+    let trace = args.trace_output.as_deref().map(workspace::trace::init_trace_output).transpose()?;
+    let command = format!("{:?}", args.command);
+    let command = command.split(|c: char| !c.is_alphanumeric()).next().unwrap_or_default().to_lowercase();
+    let _span = tracing::info_span!("ws", command = %command).entered();
     
     match args.command {
-        Commands::Refactor { args } => {
+        Commands::Refactor { args } if args.command == Some(workspace::refac::RefacCommand::Schema) => {
+            print!("{}", workspace::refac::output_schema::SCHEMA);
+        }
+
+        Commands::Refactor { args: workspace::refac::Args { command: Some(workspace::refac::RefacCommand::Verify { plan, format }), .. } } => {
+            match workspace::refac::verify::run(&plan, &format) {
+                Ok(true) => {}
+                Ok(false) => {
+                    drop(trace);
+                    process::exit(1);
+                }
+                Err(error) => {
+                    eprintln!("{}: {:#}", "Error".red(), error);
+                    drop(trace);
+                    process::exit(1);
+                }
+            }
+        }
+        
+        Commands::Refactor { mut args } => {
             log_operation_start("refactor", &format!("root: {:?}", args.root_dir));
-            match workspace::run_refac(args) {
+            // Configured excludes come first, --exclude adds to them
+            let config = workspace::config::WsConfig::current();
+            let mut exclude_patterns = config.refactor_excludes();
+            exclude_patterns.append(&mut args.exclude_patterns);
+            args.exclude_patterns = exclude_patterns;
+            let result = match args.serve.clone() {
+                Some(socket) => workspace::refac::server::serve(&socket, args, config.refactor_handlers()),
+                None => workspace::RenameEngine::new(args)
+                    .and_then(|engine| {
+                        engine
+                            .with_handlers(config.refactor_handlers())
+                            .with_import_languages(config.refactor_imports())
+                            .with_legacy_hidden_inference(config.refactor_legacy_hidden_inference())
+                            .execute()
+                    }),
+            };
+            match result {
                 Ok(()) => log_operation_complete("refactor", start_time.elapsed()),
                 Err(error) => {
                     log_operation_error("refactor", &error);
                     eprintln!("{}: {:#}", "Error".red(), error);
+                    drop(trace);
                     process::exit(1);
                 }
             }
@@ -1081,18 +1642,23 @@ fn run() -> Result<()> {
             handle_template_command(action)?;
         }
         
-        Commands::Update { no_git, git_add } => {
-            log_operation_start("update", &format!("no_git: {}, git_add: {}", no_git, git_add));
-            update_state(no_git, git_add)?;
+        Commands::Update { no_git, git_add, dry_run, hook, channel } => {
+            let hook = hook.map(|hook| hook.parse::<HookType>()).transpose()?;
+            let source = hook.map_or_else(|| "command line".to_string(), |hook| format!("{} hook", hook));
+            log_operation_start("update", &format!("from {}, no_git: {}, git_add: {}, dry_run: {}", source, no_git, git_add, dry_run));
+            match channel {
+                Some(channel) => update_channel(&channel, !no_git && git_add, dry_run)?,
+                None => update_state(no_git, git_add, dry_run, hook)?,
+            }
             log_operation_complete("update", start_time.elapsed());
         }
         
-        Commands::Scrap { paths, command } => {
-            run_scrap_command(paths, command)?;
+        Commands::Scrap { paths, force_protected, stdin, global, command } => {
+            run_scrap_command(paths, force_protected, stdin, global, command)?;
         }
         
-        Commands::Unscrap { name, force, to } => {
-            run_unscrap_command(name, force, to)?;
+        Commands::Unscrap { args } => {
+            run_unscrap_command(args)?;
         }
         
         Commands::Ldiff { substitute_char } => {
@@ -1166,10 +1732,36 @@ fn run() -> Result<()> {
 
 fn run_git_command(command: Option<GitCommands>) -> Result<()> {
     match command {
-        Some(GitCommands::Install { force }) => install_hook(force)?,
-        Some(GitCommands::Uninstall) => uninstall_hook()?,
-        Some(GitCommands::Show) => show_version()?,
-        Some(GitCommands::Status) => show_status()?,
+        Some(GitCommands::Install { force, hook, require_bump, recursive, annotate_commit_msg }) => {
+            let hook: HookType = hook.parse()?;
+            if require_bump && hook != HookType::PrePush {
+                anyhow::bail!("--require-bump needs --hook pre-push");
+            }
+            if annotate_commit_msg {
+                let project_root = get_project_root()?;
+                let mut config = St8Config::load(&project_root)?;
+                config.annotate_commit_msg = true;
+                config.save(&project_root)?;
+            }
+            install_hook(force, hook, require_bump)?;
+            if recursive && is_git_repository() {
+                for submodule in workspace::st8::GitRepo::discover()?.submodules()? {
+                    println!("\n{} Submodule {}", "Info".blue(), submodule.display());
+                    let repo = workspace::st8::GitRepo::discover_from(&submodule)?;
+                    install_hook_in(&repo, force, hook, require_bump)?;
+                }
+            }
+        }
+        Some(GitCommands::Uninstall { purge, remove_version_file }) => {
+            let deleted_hooks = uninstall_hook()?;
+            if purge && is_git_repository() {
+                purge_version_artifacts(&get_git_root()?, remove_version_file, &deleted_hooks)?;
+            }
+        }
+        Some(GitCommands::Doctor) => check_hooks()?,
+        Some(GitCommands::Show { format }) => show_version(&format)?,
+        Some(GitCommands::Status { format, check: true }) => check_status(&format)?,
+        Some(GitCommands::Status { format, check: false }) => show_status(&format)?,
         None => {
             // Default behavior: install hook if not installed, otherwise update state
             if !is_git_repository() {
@@ -1183,7 +1775,7 @@ fn run_git_command(command: Option<GitCommands>) -> Result<()> {
                 log::info!("Git hook not installed, installing automatically");
                 eprintln!("{}: Git hook not installed", "Info".blue());
                 eprintln!("{}: Installing pre-commit hook for automatic version management", "Info".blue());
-                install_hook(false)?;
+                install_hook(false, HookType::PreCommit, false)?;
             } else {
                 // Hook is installed, just update state
                 let project_root = get_project_root()?;
@@ -1199,9 +1791,46 @@ fn run_git_command(command: Option<GitCommands>) -> Result<()> {
     Ok(())
 }
 
-fn update_state(no_git: bool, git_add: bool) -> Result<()> {
+#[tracing::instrument]
+fn update_state(no_git: bool, git_add: bool, dry_run: bool, hook: Option<HookType>) -> Result<()> {
     let project_root = get_project_root()?;
     let config = St8Config::load(&project_root)?;
+    if !config.enabled {
+        log::info!("Version updates are disabled, skipping update");
+        println!("{}: Version updates are disabled (enable with 'ws version enable')", "Info".blue());
+        return Ok(());
+    }
+    if let Some(reason) = workspace::st8::policy::skip_reason(&config)? {
+        log::info!("Skipping version update: {}", reason);
+        println!("{}: Skipping version update: {}", "Info".blue(), reason);
+        return Ok(());
+    }
+    if dry_run {
+        return preview_update(&project_root, &config);
+    }
+    // A follow-up commit cannot be made while the commit is in progress
+    let separate_commit = config.commit_mode == CommitMode::Separate && hook.is_some();
+    if separate_commit && hook == Some(HookType::PreCommit) {
+        log::warn!("Skipping version update: commit_mode is separate, which needs the post-commit or pre-push hook");
+        println!("{}: Skipping version update: commit_mode is separate, which needs the post-commit or pre-push hook (ws git install --hook post-commit --force)", "Info".blue());
+        return Ok(());
+    }
+    // A hook over its time budget leaves the update to the next run
+    if let (Some(hook), Some(budget_ms)) = (hook, config.max_hook_ms) {
+        if is_git_repository() {
+            let repo = workspace::st8::GitRepo::discover()?;
+            if workspace::st8::hooks::take_deferred(&repo) {
+                log::info!("Applying the version update deferred by an earlier hook");
+                println!("{}: Applying the version update deferred by an earlier hook", "Info".blue());
+            } else if !version_within_budget(&config, budget_ms) {
+                workspace::st8::hooks::defer_update(&repo)?;
+                finish_version_in_background();
+                log::warn!("Deferring version update: computing it took over {} ms in the {} hook", budget_ms, hook);
+                println!("{}: Deferring version update to the next run (computing it took over max_hook_ms = {})", "Info".blue(), budget_ms);
+                return Ok(());
+            }
+        }
+    }
     let mut workspace_state = WorkspaceState::load(&project_root)?;
     
     // Update version in memory  
@@ -1230,15 +1859,49 @@ fn update_state(no_git: bool, git_add: bool) -> Result<()> {
     let version_info = rt.block_on(async {
         let pool = workspace::entities::database::initialize_database(&db_path).await?;
         let major_version = get_project_major_version(&pool).await?;
-        workspace::st8::VersionInfo::calculate_with_major(major_version)
+        workspace::st8::VersionInfo::calculate_for(&config, major_version)
     })?;
     
-    update_version_file(&version_info, &config)?;
-    if !config.version_file.is_empty() {
-        log::info!("Updated version file: {}", config.version_file);
-        println!("{}: Updated {}", "Info".blue(), config.version_file);
+    // A lower version, e.g. after checking out a branch with an older tag, is
+    // only written when downgrade_policy allows it
+    let downgrades = workspace::st8::st8_common::downgrades(&config, &version_info.full_version);
+    let version = if !downgrades.is_empty() && config.downgrade_policy == DowngradePolicy::Deny {
+        let current = workspace::st8::current_version(&config)?;
+        log::warn!("Keeping {}: computed version {} is older", current, version_info.full_version);
+        println!("{}: Keeping {} (computed {} is older; set downgrade_policy to allow it)", "Warning".yellow(), current, version_info.full_version);
+        for (path, version) in &downgrades {
+            println!("  - {} holds {}", path.display(), version);
+        }
+        current.to_string()
+    } else {
+        if config.downgrade_policy == DowngradePolicy::Warn {
+            for (path, version) in &downgrades {
+                log::warn!("Moving {} back from {} to {}", path.display(), version, version_info.full_version);
+                eprintln!("{}: Moving {} back from {} to {}", "Warning".yellow(), path.display(), version, version_info.full_version);
+            }
+        }
+        workspace::st8::history::apply_version(&project_root, &version_info, &config, workspace::st8::ChangeSource::Update)?;
+        if !config.version_file.is_empty() {
+            log::info!("Updated version file: {}", config.version_file);
+            println!("{}: Updated {}", "Info".blue(), config.version_file);
+        }
+        version_info.full_version.clone()
+    };
+    
+    // Packages only move when commits touched them
+    for (name, version) in workspace::st8::update_packages(&config.packages, config.strategy)? {
+        println!("{}: Updated package {} to {}", "Info".blue(), name, version);
     }
     
+    // Keep the Unreleased changelog section current
+    let changelog_path = if config.changelog.enabled && is_git_repository() {
+        let path = workspace::st8::update_changelog(&config.changelog, &config.tag_scheme(), &get_git_root()?, None)?;
+        log::info!("Updated changelog: {}", path.display());
+        Some(path)
+    } else {
+        None
+    };
+    
     // Add files to git if requested and we're in a git repository
     if !no_git && git_add && is_git_repository() {
         let mut files_to_add = Vec::new();
@@ -1249,6 +1912,10 @@ fn update_state(no_git: bool, git_add: bool) -> Result<()> {
             files_to_add.push(version_file.clone());
         }
         
+        if let Some(path) = changelog_path {
+            files_to_add.push(path.to_string_lossy().to_string());
+        }
+        
         // Add rendered template files
         for file in rendered_files {
             let path_str = file.clone();
@@ -1256,14 +1923,16 @@ fn update_state(no_git: bool, git_add: bool) -> Result<()> {
         }
         
         // Add .ws directory files (logs, database, state files)
-        let ws_files = vec![
-            ".ws/logs/ws.log".to_string(),
+        let mut ws_files = vec![
             ".ws/project.db".to_string(),
             ".ws/project.db-shm".to_string(),
             ".ws/project.db-wal".to_string(),
             ".ws/workspace.db".to_string(),
             ".ws/state.json".to_string(),
         ];
+        if config.logging.location == LogLocation::Workspace {
+            ws_files.push(".ws/logs/ws.log".to_string());
+        }
         
         for ws_file in ws_files {
             let ws_path = project_root.join(&ws_file);
@@ -1282,34 +1951,128 @@ fn update_state(no_git: bool, git_add: bool) -> Result<()> {
                 }
             }
         }
+        
+        if separate_commit {
+            commit_version_update(&config, &version)?;
+        }
+    }
+    
+    Ok(())
+}
+
+/// Compute the version on a thread and report whether it finished within
+/// `budget_ms`. The line change cache it fills makes the update itself fast.
+fn version_within_budget(config: &St8Config, budget_ms: u64) -> bool {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let config = config.clone();
+    std::thread::spawn(move || {
+        let _ = VersionInfo::calculate_for(&config, 0);
+        let _ = sender.send(());
+    });
+    receiver.recv_timeout(std::time::Duration::from_millis(budget_ms)).is_ok()
+}
+
+/// Finish the version computation in a detached `ws update --dry-run`, so
+/// the deferred update finds the line change cache ready
+fn finish_version_in_background() {
+    let spawned = env::current_exe().and_then(|exe| {
+        Command::new(exe)
+            .args(["update", "--dry-run"])
+            .stdin(process::Stdio::null())
+            .stdout(process::Stdio::null())
+            .stderr(process::Stdio::null())
+            .spawn()
+    });
+    if let Err(e) = spawned {
+        log::debug!("Failed to start background version computation: {}", e);
+    }
+}
+
+/// Commit the staged version files on their own, for `commit_mode = "separate"`
+fn commit_version_update(config: &St8Config, version: &str) -> Result<()> {
+    let staged = Command::new("git")
+        .args(["diff", "--cached", "--quiet"])
+        .status()
+        .context("Failed to check staged changes")?;
+    if staged.success() {
+        return Ok(());
     }
     
+    let message = workspace::st8::hooks::bump_commit_message(&config.commit_message, version, config.skip_ci);
+    // The post-commit hook skips commits it makes itself
+    let output = Command::new("git")
+        .args(["commit", "--no-verify", "--quiet", "-m", &message])
+        .env("WS_HOOK_AMENDING", "1")
+        .output()
+        .context("Failed to commit version update")?;
+    if !output.status.success() {
+        anyhow::bail!("Failed to commit version update: {}", String::from_utf8_lossy(&output.stderr));
+    }
+    log::info!("Committed version update: {}", message);
+    println!("{}: Committed {}", "Info".blue(), message);
     Ok(())
 }
 
-fn run_scrap_command(paths: Vec<std::path::PathBuf>, command: Option<ScrapCommands>) -> Result<()> {
+fn run_scrap_command(
+    paths: Vec<std::path::PathBuf>,
+    force_protected: bool,
+    stdin: bool,
+    global: bool,
+    command: Option<ScrapCommands>,
+) -> Result<()> {
     let mut args = Vec::new();
     
     // Convert clap ScrapCommands to original scrap binary arguments
     match command {
-        Some(ScrapCommands::List { sort }) => {
+        Some(ScrapCommands::List { sort, reverse, limit, offset, columns, pager, filter }) => {
             args.push("list".to_string());
             args.push("--sort".to_string());
             args.push(sort);
+            if reverse {
+                args.push("--reverse".to_string());
+            }
+            if let Some(limit) = limit {
+                args.push("--limit".to_string());
+                args.push(limit.to_string());
+            }
+            if offset > 0 {
+                args.push("--offset".to_string());
+                args.push(offset.to_string());
+            }
+            if !columns.is_empty() {
+                args.push("--columns".to_string());
+                args.push(columns.join(","));
+            }
+            if pager {
+                args.push("--pager".to_string());
+            }
+            filter.push_args(&mut args);
         }
-        Some(ScrapCommands::Clean { days, dry_run }) => {
+        Some(ScrapCommands::Clean { days, dry_run, require_archived }) => {
             args.push("clean".to_string());
-            args.push("--days".to_string());
-            args.push(days.to_string());
+            if let Some(days) = days {
+                args.push("--days".to_string());
+                args.push(days.to_string());
+            }
             if dry_run {
                 args.push("--dry-run".to_string());
             }
+            if require_archived {
+                args.push("--require-archived".to_string());
+            }
         }
-        Some(ScrapCommands::Purge { force }) => {
+        Some(ScrapCommands::Purge { force, dry_run, require_archived, filter }) => {
             args.push("purge".to_string());
             if force {
                 args.push("--force".to_string());
             }
+            if dry_run {
+                args.push("--dry-run".to_string());
+            }
+            if require_archived {
+                args.push("--require-archived".to_string());
+            }
+            filter.push_args(&mut args);
         }
         Some(ScrapCommands::Find { pattern, content }) => {
             args.push("find".to_string());
@@ -1318,7 +2081,12 @@ fn run_scrap_command(paths: Vec<std::path::PathBuf>, command: Option<ScrapComman
                 args.push("--content".to_string());
             }
         }
-        Some(ScrapCommands::Archive { output, remove }) => {
+        Some(ScrapCommands::Archive { action: Some(ScrapArchiveAction::List { tier }), .. }) => {
+            args.push("archive".to_string());
+            args.push("list".to_string());
+            args.extend(tier);
+        }
+        Some(ScrapCommands::Archive { action: None, output, remove, encrypt, filter }) => {
             args.push("archive".to_string());
             if let Some(output_path) = output {
                 args.push("--output".to_string());
@@ -1327,34 +2095,159 @@ fn run_scrap_command(paths: Vec<std::path::PathBuf>, command: Option<ScrapComman
             if remove {
                 args.push("--remove".to_string());
             }
+            if encrypt {
+                args.push("--encrypt".to_string());
+            }
+            filter.push_args(&mut args);
         }
-        None => {
-            // Add all paths as arguments
-            for path in paths {
+        Some(ScrapCommands::RestoreArchive { archive }) => {
+            args.push("restore-archive".to_string());
+            args.push(archive.to_string_lossy().to_string());
+        }
+        Some(ScrapCommands::Export { manifest }) => {
+            args.push("export".to_string());
+            if let Some(manifest) = manifest {
+                args.push(manifest.to_string_lossy().to_string());
+            }
+        }
+        Some(ScrapCommands::Import { manifest, from, dry_run }) => {
+            args.push("import".to_string());
+            args.push(manifest.to_string_lossy().to_string());
+            if let Some(from) = from {
+                args.push("--from".to_string());
+                args.push(from.to_string_lossy().to_string());
+            }
+            if dry_run {
+                args.push("--dry-run".to_string());
+            }
+        }
+        Some(ScrapCommands::Doctor { fix, no_verify }) => {
+            args.push("doctor".to_string());
+            if fix {
+                args.push("--fix".to_string());
+            }
+            if no_verify {
+                args.push("--no-verify".to_string());
+            }
+        }
+        Some(ScrapCommands::Dedup { dry_run }) => {
+            args.push("dedup".to_string());
+            if dry_run {
+                args.push("--dry-run".to_string());
+            }
+        }
+        Some(ScrapCommands::ScheduleClean { days, daily, weekly, monthly, remove, print }) => {
+            args.push("schedule-clean".to_string());
+            args.push("--days".to_string());
+            args.push(days.to_string());
+            for (flag, set) in [("--daily", daily), ("--weekly", weekly), ("--monthly", monthly), ("--remove", remove), ("--print", print)] {
+                if set {
+                    args.push(flag.to_string());
+                }
+            }
+        }
+        Some(ScrapCommands::Config { key, value }) => {
+            args.push("config".to_string());
+            args.extend(key);
+            args.extend(value);
+        }
+        Some(ScrapCommands::Versions { path }) => {
+            args.push("versions".to_string());
+            args.push(path);
+        }
+        Some(ScrapCommands::History { limit }) => {
+            args.push("history".to_string());
+            if let Some(limit) = limit {
+                args.push("--limit".to_string());
+                args.push(limit.to_string());
+            }
+        }
+        None => {
+            // Add all paths as arguments
+            for path in paths {
                 args.push(path.to_string_lossy().to_string());
             }
+            if stdin {
+                args.push("--stdin".to_string());
+            }
+            if force_protected && !args.is_empty() {
+                args.push("--force-protected".to_string());
+            }
         }
     }
+    if global {
+        args.push("--global".to_string());
+    }
     
     workspace::run_scrap(args)
 }
 
-fn run_unscrap_command(name: Option<String>, force: bool, to: Option<std::path::PathBuf>) -> Result<()> {
+fn run_unscrap_command(unscrap: UnscrapArgs) -> Result<()> {
     let mut args = Vec::new();
     
-    if let Some(item_name) = name {
+    if unscrap.global {
+        args.push("--global".to_string());
+    }
+    
+    if unscrap.complete_items {
+        args.push("--complete-items".to_string());
+        return workspace::run_unscrap(args);
+    }
+    
+    if let Some(item_name) = unscrap.name {
         args.push(item_name);
     }
     
-    if force {
+    if unscrap.force {
         args.push("--force".to_string());
     }
     
-    if let Some(target_path) = to {
+    if let Some(policy) = unscrap.on_conflict {
+        args.push("--on-conflict".to_string());
+        args.push(policy);
+    }
+    
+    if let Some(target_path) = unscrap.to {
         args.push("--to".to_string());
         args.push(target_path.to_string_lossy().to_string());
     }
     
+    if unscrap.all {
+        args.push("--all".to_string());
+    }
+    
+    if let Some(pattern) = unscrap.matching {
+        args.push("--matching".to_string());
+        args.push(pattern);
+    }
+    
+    if let Some(origin) = unscrap.from_origin {
+        args.push("--from-origin".to_string());
+        args.push(origin);
+    }
+    
+    if let Some(version) = unscrap.version {
+        args.push("--version".to_string());
+        args.push(version.to_string());
+    }
+    
+    if unscrap.merge {
+        args.push("--merge".to_string());
+    }
+    
+    if let Some(roots) = unscrap.rebase {
+        args.push("--rebase".to_string());
+        args.extend(roots.iter().map(|root| root.to_string_lossy().to_string()));
+    }
+    
+    if unscrap.dry_run {
+        args.push("--dry-run".to_string());
+    }
+    
+    if unscrap.no_verify {
+        args.push("--no-verify".to_string());
+    }
+    
     workspace::run_unscrap(args)
 }
 
@@ -1362,7 +2255,7 @@ fn run_ldiff_command(substitute_char: String) -> Result<()> {
     workspace::run_ldiff(vec![substitute_char.clone()])
 }
 
-fn install_hook(force: bool) -> Result<()> {
+fn install_hook(force: bool, hook: HookType, require_bump: bool) -> Result<()> {
     if !is_git_repository() {
         log::warn!("install_hook called outside git repository");
         eprintln!("{}: Not in a git repository", "Error".red());
@@ -1370,9 +2263,15 @@ fn install_hook(force: bool) -> Result<()> {
         return Ok(());
     }
     
-    let git_root = get_git_root()?;
-    let hooks_dir = git_root.join(".git").join("hooks");
-    let hook_file = hooks_dir.join("pre-commit");
+    install_hook_in(&workspace::st8::GitRepo::discover()?, force, hook, require_bump)
+}
+
+/// Install the hook into `repo`'s hooks directory, which linked worktrees
+/// share with their main repository and submodules keep under the
+/// superproject's `.git/modules`
+fn install_hook_in(repo: &workspace::st8::GitRepo, force: bool, hook: HookType, require_bump: bool) -> Result<()> {
+    let hooks_dir = repo.hooks_dir()?;
+    let hook_file = hooks_dir.join(hook.file_name());
     
     // Create hooks directory if it doesn't exist
     if !hooks_dir.exists() {
@@ -1381,111 +2280,327 @@ fn install_hook(force: bool) -> Result<()> {
         log::info!("Created git hooks directory: {}", hooks_dir.display());
     }
     
+    // Get current binary path
+    let current_exe = env::current_exe()
+        .context("Failed to get current executable path")?;
+    
+    // The annotation hook follows the setting on every install
+    let annotate = St8Config::load(repo.root()).map(|config| config.annotate_commit_msg).unwrap_or(false);
+    let annotate_file = hooks_dir.join(workspace::st8::hooks::ANNOTATE_HOOK);
+    if annotate {
+        write_hook_block(&annotate_file, &workspace::st8::hooks::annotate_block(&current_exe))?;
+        println!("{} Commit messages will be annotated with the version", "Info".blue());
+    } else {
+        remove_hook_block(&annotate_file)?;
+    }
+    
     // Check if already installed
-    if !force && is_hook_installed()? {
+    if !force && hooks_installed_in(&hooks_dir)?.contains(&hook) {
         println!("{} Git hook is already installed", "Info".blue());
         println!("{} Use 'ws git install --force' to reinstall", "Tip".yellow());
         return Ok(());
     }
     
-    // Get current binary path
-    let current_exe = env::current_exe()
-        .context("Failed to get current executable path")?;
+    // Only one hook bumps the version, so switching hooks moves the block
+    for other in HookType::ALL.into_iter().filter(|other| *other != hook) {
+        remove_hook_block(&hooks_dir.join(other.file_name()))?;
+    }
     
-    let st8_block = format!(
-        "#!/bin/bash\n# === WS BLOCK START ===\n# DO NOT EDIT THIS BLOCK MANUALLY\n# Use 'ws git uninstall' to remove this hook\n{} ws update --git-add\n# === WS BLOCK END ===\n",
-        current_exe.display()
-    );
+    write_hook_block(&hook_file, &hook.block(&current_exe, require_bump))?;
+    
+    log::info!("Git {} hook installed successfully at {}", hook, hook_file.display());
+    println!("{} Git hook installed successfully", "Success".green());
+    match hook {
+        HookType::PreCommit => println!("{} Version will be updated automatically on each commit", "Info".blue()),
+        HookType::PostCommit => println!("{} Version will be updated and amended into each commit", "Info".blue()),
+        HookType::PrePush if require_bump => println!("{} Version will be checked before each push, and must be newer than the remote's latest release", "Info".blue()),
+        HookType::PrePush => println!("{} Version will be checked before each push", "Info".blue()),
+    }
     
+    Ok(())
+}
+
+/// Put `block` in `hook_file` in place of any WS block, keeping the rest of
+/// the hook, and make the hook executable
+fn write_hook_block(hook_file: &std::path::Path, block: &str) -> Result<()> {
     if hook_file.exists() {
         // Read existing hook content
-        let existing_content = fs::read_to_string(&hook_file)
-            .context("Failed to read existing pre-commit hook")?;
+        let existing_content = fs::read_to_string(hook_file)
+            .with_context(|| format!("Failed to read existing hook {}", hook_file.display()))?;
         
         // Remove any existing st8 block
         let cleaned_content = remove_st8_block(&existing_content);
         
         // Append new st8 block
-        let new_content = if cleaned_content.trim().is_empty() {
-            st8_block
+        let new_content = if only_shebang(&cleaned_content) {
+            format!("{}\n{}", workspace::st8::hooks::SHEBANG, block)
         } else {
-            format!("{}\n{}", cleaned_content.trim_end(), st8_block)
+            format!("{}\n{}", cleaned_content.trim_end(), block)
         };
         
-        fs::write(&hook_file, new_content)
-            .context("Failed to update pre-commit hook")?;
+        fs::write(hook_file, new_content)
+            .with_context(|| format!("Failed to update hook {}", hook_file.display()))?;
         
-        log::info!("Updated existing pre-commit hook: {}", hook_file.display());
+        log::info!("Updated existing hook: {}", hook_file.display());
     } else {
         // Create new hook file
-        fs::write(&hook_file, &st8_block)
-            .context("Failed to create pre-commit hook")?;
+        fs::write(hook_file, format!("{}\n{}", workspace::st8::hooks::SHEBANG, block))
+            .with_context(|| format!("Failed to create hook {}", hook_file.display()))?;
         
-        log::info!("Created new pre-commit hook: {}", hook_file.display());
+        log::info!("Created new hook: {}", hook_file.display());
     }
     
     // Make hook executable on Unix systems
     #[cfg(unix)]
     {
         use std::os::unix::fs::PermissionsExt;
-        let mut perms = fs::metadata(&hook_file)?.permissions();
+        let mut perms = fs::metadata(hook_file)?.permissions();
         perms.set_mode(0o755);
-        fs::set_permissions(&hook_file, perms)?;
+        fs::set_permissions(hook_file, perms)?;
     }
     
-    log::info!("Git pre-commit hook installed successfully at {}", hook_file.display());
-    println!("{} Git hook installed successfully", "Success".green());
-    println!("{} Version will be updated automatically on each commit", "Info".blue());
-    
     Ok(())
 }
 
-fn uninstall_hook() -> Result<()> {
+/// Remove the WS blocks from the hooks; returns the hook files deleted
+/// because nothing else was left in them
+fn uninstall_hook() -> Result<Vec<std::path::PathBuf>> {
     if !is_git_repository() {
         log::warn!("uninstall_hook called outside git repository");
         eprintln!("{}: Not in a git repository", "Error".red());
-        return Ok(());
+        return Ok(Vec::new());
     }
     
-    let git_root = get_git_root()?;
-    let hook_file = git_root.join(".git").join("hooks").join("pre-commit");
+    let hooks_dir = workspace::st8::GitRepo::discover()?.hooks_dir()?;
+    let hook_files: Vec<_> = HookType::ALL.iter()
+        .map(|hook| (hook, hooks_dir.join(hook.file_name())))
+        .filter(|(_, file)| file.exists())
+        .collect();
+    
+    let mut deleted = Vec::new();
+    let annotate_hook = workspace::st8::hooks::ANNOTATE_HOOK;
+    let annotate_file = hooks_dir.join(annotate_hook);
+    let removed_annotation = remove_hook_block(&annotate_file)?;
+    if removed_annotation {
+        println!("{} Removed st8 from {} hook", "Success".green(), annotate_hook);
+        if !annotate_file.exists() {
+            deleted.push(annotate_file);
+        }
+    }
+    
+    if hook_files.is_empty() {
+        if !removed_annotation {
+            println!("{} No pre-commit hook found (nor post-commit or pre-push)", "Info".blue());
+        }
+        return Ok(deleted);
+    }
+    
+    let mut removed_any = removed_annotation;
+    for (hook, hook_file) in hook_files {
+        if remove_hook_block(&hook_file)? {
+            println!("{} Removed st8 from {} hook", "Success".green(), hook);
+            removed_any = true;
+            if !hook_file.exists() {
+                deleted.push(hook_file);
+            }
+        }
+    }
+    
+    if !removed_any {
+        println!("{} No st8 hook block found in git hooks", "Info".blue());
+    }
+    
+    Ok(deleted)
+}
+
+/// Delete what version management left in `repo_root` besides its hooks
+/// and reset the version settings in the project database; `deleted_hooks`
+/// are the hook files uninstalling already removed, listed in the summary
+fn purge_version_artifacts(repo_root: &std::path::Path, remove_version_file: bool, deleted_hooks: &[std::path::PathBuf]) -> Result<()> {
+    let has_database = repo_root.join(".ws/project.db").exists();
+    // A broken config is one more reason to purge, so fall back to the defaults
+    let config = if has_database {
+        St8Config::load(repo_root).unwrap_or_default()
+    } else {
+        St8Config::default()
+    };
     
+    let artifacts = workspace::st8::st8_common::version_artifacts(repo_root, &config, remove_version_file);
+    for path in deleted_hooks {
+        println!("{} Removed {}", "Success".green(), path.strip_prefix(repo_root).unwrap_or(path).display());
+    }
+    for path in &artifacts {
+        if path.is_dir() {
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_file(path)
+        }
+        .with_context(|| format!("Failed to remove {}", path.display()))?;
+        println!("{} Removed {}", "Success".green(), path.strip_prefix(repo_root).unwrap_or(path).display());
+    }
+    
+    if has_database {
+        St8Config::default().save(repo_root)?;
+        println!("{} Reset version settings in .ws/project.db", "Success".green());
+    }
+    
+    let purged = deleted_hooks.len() + artifacts.len();
+    if purged == 0 && !has_database {
+        println!("{} No version management files found", "Info".blue());
+    } else {
+        println!("{} Purged {} version management files", "Info".blue(), purged);
+    }
+    Ok(())
+}
+
+/// Remove the WS block from `hook_file`, deleting the file when nothing else
+/// is left; returns whether there was a block
+fn remove_hook_block(hook_file: &std::path::Path) -> Result<bool> {
     if !hook_file.exists() {
-        println!("{} No pre-commit hook found", "Info".blue());
-        return Ok(());
+        return Ok(false);
     }
     
-    let content = fs::read_to_string(&hook_file)
-        .context("Failed to read pre-commit hook")?;
+    let content = fs::read_to_string(hook_file)
+        .with_context(|| format!("Failed to read {}", hook_file.display()))?;
     
     if !content.contains("=== WS BLOCK START ===") {
-        println!("{} No st8 hook block found in pre-commit hook", "Info".blue());
-        return Ok(());
+        return Ok(false);
     }
     
     let cleaned_content = remove_st8_block(&content);
     
-    if cleaned_content.trim().is_empty() {
+    if only_shebang(&cleaned_content) {
         // Remove the entire hook file if only st8 content
-        fs::remove_file(&hook_file)
-            .context("Failed to remove pre-commit hook")?;
-        println!("{} Removed pre-commit hook", "Success".green());
-        log::info!("Removed pre-commit hook: {}", hook_file.display());
+        fs::remove_file(hook_file)
+            .with_context(|| format!("Failed to remove {}", hook_file.display()))?;
+        log::info!("Removed hook: {}", hook_file.display());
     } else {
         // Write back the cleaned content
-        fs::write(&hook_file, cleaned_content.trim_end())
-            .context("Failed to update pre-commit hook")?;
-        println!("{} Removed st8 from pre-commit hook", "Success".green());
-        log::info!("Removed st8 block from pre-commit hook: {}", hook_file.display());
+        fs::write(hook_file, cleaned_content.trim_end())
+            .with_context(|| format!("Failed to update {}", hook_file.display()))?;
+        log::info!("Removed st8 block from hook: {}", hook_file.display());
+    }
+    
+    Ok(true)
+}
+
+/// Whether a hook holds nothing but blank lines and shebangs, which is what
+/// removing the WS block leaves of a hook ws created
+fn only_shebang(content: &str) -> bool {
+    content.lines().all(|line| line.trim().is_empty() || line.trim_start().starts_with("#!"))
+}
+
+/// Version, configuration and project files as JSON, for `ws git show`
+/// and `ws git status`
+fn version_report_json(project_root: &std::path::Path, config: &St8Config, version_info: &VersionInfo) -> Result<serde_json::Value> {
+    let workspace_state = WorkspaceState::load(project_root)?;
+    let project_files: Vec<serde_json::Value> = detect_project_files(project_root)
+        .unwrap_or_default()
+        .iter()
+        .map(|file| serde_json::json!({
+            "path": file.path.display().to_string(),
+            "type": file.file_type.file_name()
+        }))
+        .collect();
+    let packages: Vec<serde_json::Value> = workspace::st8::analyze_packages(&config.packages, config.strategy)?
+        .iter()
+        .map(package_status_json)
+        .collect();
+    
+    Ok(serde_json::json!({
+        "version": version_info.full_version,
+        "components": {
+            "major": version_info.major_version,
+            "minor": version_info.minor_version,
+            "patch": version_info.patch_version
+        },
+        "project": workspace_state.project_name,
+        "git_repository": is_git_repository(),
+        "config": config,
+        "project_files": project_files,
+        "packages": packages
+    }))
+}
+
+/// What `ws update` would do, without writing or staging anything
+fn preview_update(project_root: &std::path::Path, config: &St8Config) -> Result<()> {
+    let db_path = project_root.join(".ws/project.db");
+    let rt = tokio::runtime::Runtime::new()?;
+    let version_info = rt.block_on(async {
+        let pool = workspace::entities::database::initialize_database(&db_path).await?;
+        let major_version = get_project_major_version(&pool).await?;
+        workspace::st8::VersionInfo::calculate_for(config, major_version)
+    })?;
+    
+    let current = fs::read_to_string(&config.version_file).ok()
+        .map(|content| content.trim().to_string())
+        .filter(|content| !content.is_empty());
+    println!("{} {}", "Current version:".blue(), current.as_deref().unwrap_or("(none)").yellow());
+    println!("{} {}", "Next version:".blue(), version_info.full_version.green().bold());
+    
+    let mut changes = Vec::new();
+    let downgrades = workspace::st8::st8_common::downgrades(config, &version_info.full_version);
+    if !downgrades.is_empty() && config.downgrade_policy == DowngradePolicy::Deny {
+        for (path, version) in &downgrades {
+            println!("{} {} holds {}, which is newer; the version files would be left alone", "Note:".yellow(), path.display(), version);
+        }
+    } else {
+        changes.extend(workspace::st8::preview_version_update(&version_info, config)?);
+    }
+    
+    for (status, package_changes) in workspace::st8::packages::preview_packages(&config.packages, config.strategy)? {
+        println!("{} {} {} → {}", "Package".blue(), status.name, status.current, status.next_version().to_string().green());
+        changes.extend(package_changes);
+    }
+    
+    if config.changelog.enabled && is_git_repository() {
+        let (path, content) = workspace::st8::changelog::changelog_content(&config.changelog, &config.tag_scheme(), &get_git_root()?, None)?;
+        changes.push(workspace::st8::FileChange::new(path, content));
+    }
+    
+    let workspace_state = WorkspaceState::load(project_root)?;
+    let template_manager = TemplateManager::new(&workspace_state)?;
+    let calculated = VersionInfo::calculate()?;
+    for (path, content) in template_manager.preview_all_templates(&calculated, workspace_state.project_name.as_deref())? {
+        changes.push(workspace::st8::FileChange::new(path, content));
     }
     
+    print_file_changes(&changes);
     Ok(())
 }
 
-fn show_version() -> Result<()> {
+/// List each changed file with its removed and added lines
+fn print_file_changes(changes: &[workspace::st8::FileChange]) {
+    let changes: Vec<_> = changes.iter().filter(|change| change.is_change()).collect();
+    if changes.is_empty() {
+        println!("{}", "No files would change".yellow());
+        return;
+    }
+    
+    println!("\n{}", "Files that would change:".blue().bold());
+    for change in changes {
+        println!("  {}", change.path.display().to_string().bold());
+        for line in change.diff_lines() {
+            if line.starts_with('-') {
+                println!("    {}", line.red());
+            } else {
+                println!("    {}", line.green());
+            }
+        }
+    }
+}
+
+fn show_version(format: &str) -> Result<()> {
     let project_root = get_project_root()?;
     let workspace_state = WorkspaceState::load(&project_root)?;
     
+    if format == "json" {
+        let config = St8Config::load(&project_root)?;
+        let report = version_report_json(&project_root, &config, &VersionInfo::calculate()?)?;
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+    
     println!("{}", "Version Information".bold().underline());
     println!();
     let version_info = VersionInfo::calculate()?;
@@ -1519,23 +2634,47 @@ fn show_version() -> Result<()> {
     Ok(())
 }
 
-fn show_status() -> Result<()> {
+fn show_status(format: &str) -> Result<()> {
     if !is_git_repository() {
-        println!("{}: Not in a git repository", "Status".red());
+        if format == "json" {
+            println!("{}", serde_json::to_string_pretty(&serde_json::json!({ "git_repository": false }))?);
+        } else {
+            println!("{}: Not in a git repository", "Status".red());
+        }
         return Ok(());
     }
     
     let git_root = get_git_root()?;
     let config = St8Config::load(&git_root)?;
     
+    if format == "json" {
+        let mut report = version_report_json(&git_root, &config, &VersionInfo::calculate()?)?;
+        let installed: Vec<String> = installed_hooks()?.iter().map(|hook| hook.to_string()).collect();
+        report["hooks"] = serde_json::json!({
+            "installed": installed,
+            "directory": workspace::st8::GitRepo::discover()?.hooks_dir()?.display().to_string()
+        });
+        report["version_file_exists"] = serde_json::json!(git_root.join(&config.version_file).exists());
+        let workspace_state = WorkspaceState::load(&git_root)?;
+        let templates = TemplateManager::new(&workspace_state)?.list_templates();
+        report["templates"] = serde_json::json!({
+            "total": templates.len(),
+            "enabled": templates.iter().filter(|t| t.enabled).count()
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+    
     println!("{}", "Git Integration Status".bold().underline());
     println!();
     
     // Hook status
-    if is_hook_installed()? {
-        println!("{}: Installed ✓", "Pre-commit Hook".green());
+    let installed = installed_hooks()?;
+    if !installed.is_empty() {
+        let names: Vec<String> = installed.iter().map(|hook| hook.to_string()).collect();
+        println!("{}: Installed ✓ ({})", "Git Hook".green(), names.join(", "));
     } else {
-        println!("{}: Not installed ✗", "Pre-commit Hook".red());
+        println!("{}: Not installed ✗", "Git Hook".red());
         println!("{}: Run 'ws git install' to set up automatic version management", "Tip".yellow());
     }
     
@@ -1556,6 +2695,13 @@ fn show_status() -> Result<()> {
     let version_info = VersionInfo::calculate()?;
     println!("{}: {}", "Current Version".blue(), version_info.full_version);
     
+    if !config.packages.is_empty() {
+        println!("{}:", "Packages".blue());
+        for status in workspace::st8::analyze_packages(&config.packages, config.strategy)? {
+            print_package_status(&status);
+        }
+    }
+    
     // Template status
     let template_manager = TemplateManager::new(&workspace_state)?;
     let templates = template_manager.list_templates();
@@ -1570,9 +2716,69 @@ fn show_status() -> Result<()> {
     Ok(())
 }
 
+/// `ws git status --check`: the problems CI should fail on, reported
+/// without the rest of the status so they are easy to read in a log
+fn check_status(format: &str) -> Result<()> {
+    if !is_git_repository() {
+        anyhow::bail!("Not in a git repository");
+    }
+    let git_root = get_git_root()?;
+    let problems = status_problems(&git_root);
+    
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(&serde_json::json!({
+            "passed": problems.is_empty(),
+            "problems": problems,
+        }))?);
+    } else if problems.is_empty() {
+        println!("{} Hook installed, config valid and versions in sync", "✅".green());
+    } else {
+        for problem in &problems {
+            println!("{} {}", "❌".red(), problem);
+        }
+    }
+    
+    if !problems.is_empty() {
+        log::warn!("Status check found {} problems", problems.len());
+        anyhow::bail!("{} status check{} failed", problems.len(), if problems.len() == 1 { "" } else { "s" });
+    }
+    Ok(())
+}
+
+fn status_problems(git_root: &std::path::Path) -> Vec<String> {
+    let mut problems = Vec::new();
+    match installed_hooks() {
+        Ok(hooks) if hooks.is_empty() => problems.push("No ws git hook is installed; run 'ws git install'".to_string()),
+        Ok(_) => {}
+        Err(e) => problems.push(format!("Could not read the git hooks: {:#}", e)),
+    }
+    
+    let config = match St8Config::load(git_root) {
+        Ok(config) => config,
+        Err(e) => {
+            problems.push(format!("Configuration is invalid: {:#}", e));
+            return problems;
+        }
+    };
+    
+    // Without a version file or release tag there is nothing to disagree with
+    if workspace::st8::st8_common::canonical_version(&config).is_ok_and(|version| version.is_some()) {
+        match workspace::st8::st8_common::sync_version_files(&config, true) {
+            Ok((version, changes)) => {
+                for change in changes.iter().filter(|change| change.path != std::path::Path::new(&config.version_file)) {
+                    let path = change.path.strip_prefix(git_root).unwrap_or(&change.path);
+                    problems.push(format!("{} does not carry version {}; run 'ws version sync'", path.display(), version));
+                }
+            }
+            Err(e) => problems.push(format!("Could not compare project file versions: {:#}", e)),
+        }
+    }
+    problems
+}
+
 fn update_version_in_memory(
     _workspace_state: &mut WorkspaceState,
-    _config: &St8Config,
+    config: &St8Config,
     project_root: &std::path::Path,
 ) -> Result<()> {
     if is_git_repository() {
@@ -1581,7 +2787,7 @@ fn update_version_in_memory(
         let version_info = rt.block_on(async {
             let pool = workspace::entities::database::initialize_database(&db_path).await?;
             let major_version = get_project_major_version(&pool).await?;
-            workspace::st8::VersionInfo::calculate_with_major(major_version)
+            workspace::st8::VersionInfo::calculate_for(config, major_version)
         })?;
         
         log::info!("Version updated to: {}", version_info.full_version);
@@ -1905,21 +3111,104 @@ fn get_project_root() -> Result<PathBuf> {
 }
 
 fn is_hook_installed() -> Result<bool> {
+    Ok(!installed_hooks()?.is_empty())
+}
+
+/// Hooks holding a WS block, looked up in `core.hooksPath` when it is set
+fn installed_hooks() -> Result<Vec<HookType>> {
     if !is_git_repository() {
-        return Ok(false);
+        return Ok(Vec::new());
     }
     
-    let git_root = get_git_root()?;
-    let hook_file = git_root.join(".git").join("hooks").join("pre-commit");
-    
-    if !hook_file.exists() {
-        return Ok(false);
+    hooks_installed_in(&workspace::st8::GitRepo::discover()?.hooks_dir()?)
+}
+
+fn hooks_installed_in(hooks_dir: &std::path::Path) -> Result<Vec<HookType>> {
+    let mut installed = Vec::new();
+    for hook in HookType::ALL {
+        let hook_file = hooks_dir.join(hook.file_name());
+        if !hook_file.exists() {
+            continue;
+        }
+        let content = fs::read_to_string(&hook_file)
+            .with_context(|| format!("Failed to read {} hook", hook))?;
+        if content.contains("=== WS BLOCK START ===") {
+            installed.push(hook);
+        }
     }
     
-    let content = fs::read_to_string(&hook_file)
-        .context("Failed to read pre-commit hook")?;
+    Ok(installed)
+}
+
+/// `ws git doctor`: report hooks whose binary is gone, broken or fixed to an
+/// absolute path, and fail when any is found
+fn check_hooks() -> Result<()> {
+    use workspace::st8::hooks::{resolve_binary, HookBinary};
+    
+    if !is_git_repository() {
+        anyhow::bail!("Not in a git repository");
+    }
+    let hooks_dir = workspace::st8::GitRepo::discover()?.hooks_dir()?;
+    let mut problems = 0;
+    let mut checked = 0;
+    
+    for hook in HookType::ALL {
+        let hook_file = hooks_dir.join(hook.file_name());
+        let Ok(content) = fs::read_to_string(&hook_file) else { continue };
+        let binary = match HookBinary::of(&content) {
+            HookBinary::Missing => continue,
+            HookBinary::Fixed(path) => {
+                problems += 1;
+                println!("{} {} hook runs {} by absolute path, which breaks when it moves and with Git for Windows; reinstall it with 'ws git install --hook {} --force'",
+                         "❌".red(), hook, path.display(), hook);
+                path.is_file().then_some(path)
+            }
+            HookBinary::Portable(recorded) => {
+                let binary = resolve_binary(&recorded);
+                if binary.as_ref().is_some_and(|binary| *binary != recorded) {
+                    println!("{} {} is gone; the {} hook falls back to ws on PATH", "Info".blue(), recorded.display(), hook);
+                }
+                binary
+            }
+        };
+        checked += 1;
+        println!("{} {}", format!("{} hook:", hook).blue(), hook_file.display());
+        
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            if fs::metadata(&hook_file)?.permissions().mode() & 0o111 == 0 {
+                problems += 1;
+                println!("{} {} is not executable, so git skips it; run 'chmod +x {}'", "❌".red(), hook_file.display(), hook_file.display());
+            }
+        }
+        
+        match binary {
+            Some(binary) => match Command::new(&binary).arg("--version").output() {
+                Ok(output) if output.status.success() => {
+                    println!("{} Runs {} ({})", "✅".green(), binary.display(), String::from_utf8_lossy(&output.stdout).trim());
+                }
+                _ => {
+                    problems += 1;
+                    println!("{} {} does not run; reinstall ws or the hook", "❌".red(), binary.display());
+                }
+            },
+            None => {
+                problems += 1;
+                println!("{} No ws binary found at the installed location or on PATH; the hook skips version updates", "❌".red());
+            }
+        }
+    }
     
-    Ok(content.contains("=== WS BLOCK START ==="))
+    if checked == 0 {
+        println!("{} No ws hook installed; install one with 'ws git install'", "Info".blue());
+        return Ok(());
+    }
+    if problems > 0 {
+        anyhow::bail!("Found {} problem(s) with the installed hooks", problems);
+    }
+    println!("{} Hooks are working", "✅".green());
+    Ok(())
 }
 
 fn remove_st8_block(content: &str) -> String {
@@ -2071,8 +3360,9 @@ fn generate_and_activate_completions(shell: Shell) -> Result<()> {
         generate(shell, &mut app, name, &mut completion_script);
     }
     
-    let completion_content = String::from_utf8(completion_script)
+    let mut completion_content = String::from_utf8(completion_script)
         .context("Failed to convert completion script to string")?;
+    completion_content.push_str(dynamic_completions(shell));
     
     // Create completion directory if it doesn't exist
     let completion_dir = get_completion_dir(shell)?;
@@ -2090,6 +3380,54 @@ fn generate_and_activate_completions(shell: Shell) -> Result<()> {
     Ok(())
 }
 
+/// Shell code appended to the generated script so `ws unscrap <TAB>` offers
+/// the names of scrapped items, read via the hidden `unscrap --complete-items`
+fn dynamic_completions(shell: Shell) -> &'static str {
+    match shell {
+        Shell::Bash => r#"
+_ws_scrap_items() {
+    WS_COMPLETIONS_LOADED=1 ws unscrap --complete-items 2>/dev/null
+}
+
+_ws_dynamic() {
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    local prev="${COMP_WORDS[COMP_CWORD-1]}"
+    if [[ "${COMP_WORDS[1]}" == "unscrap" && "$cur" != -* ]] \
+        && [[ "$prev" != "--to" && "$prev" != "-t" && "$prev" != "--on-conflict" && "$prev" != "--matching" ]]; then
+        local IFS=$'\n'
+        COMPREPLY=( $(compgen -W "$(_ws_scrap_items)" -- "$cur") )
+        return 0
+    fi
+    _ws "$@"
+}
+
+complete -F _ws_dynamic -o bashdefault -o default ws
+"#,
+        Shell::Zsh => r#"
+_ws_scrap_items() {
+    local -a items
+    items=(${(f)"$(WS_COMPLETIONS_LOADED=1 ws unscrap --complete-items 2>/dev/null)"})
+    compadd -a items
+}
+
+_ws_dynamic() {
+    if [[ "${words[2]}" == "unscrap" && "${words[CURRENT]}" != -* ]] \
+        && [[ "${words[CURRENT-1]}" != (--to|-t|--on-conflict|--matching) ]]; then
+        _ws_scrap_items
+    else
+        _ws "$@"
+    fi
+}
+
+compdef _ws_dynamic ws
+"#,
+        Shell::Fish => r#"
+complete -c ws -n "__fish_seen_subcommand_from unscrap" -f -a "(WS_COMPLETIONS_LOADED=1 ws unscrap --complete-items 2>/dev/null)"
+"#,
+        _ => "",
+    }
+}
+
 fn get_completion_dir(shell: Shell) -> Result<PathBuf> {
     // Check XDG_DATA_HOME first, fallback to ~/.local/share
     let data_home = env::var("XDG_DATA_HOME")
@@ -7722,18 +9060,9 @@ fn unlink_entities(dependency_id: String, force: bool) -> Result<()> {
         let db_path = get_project_root()?.join(".ws/project.db");
         let pool = workspace::entities::database::initialize_database(&db_path).await?;
         
-        if !force {
-            print!("Remove relationship {}? [y/N]: ", dependency_id);
-            use std::io::Write;
-            std::io::stdout().flush()?;
-            
-            let mut input = String::new();
-            std::io::stdin().read_line(&mut input)?;
-            
-            if !input.trim().to_lowercase().starts_with('y') {
-                println!("Cancelled");
-                return Ok(());
-            }
+        if !force && !workspace::prompt::confirm(&format!("Remove relationship {}?", dependency_id), false)? {
+            println!("Cancelled");
+            return Ok(());
         }
         
         // Remove the dependency
@@ -7962,15 +9291,9 @@ fn delete_note(note_id: String, force: bool) -> Result<()> {
         let db_path = get_project_root()?.join(".ws/project.db");
         let pool = workspace::entities::database::initialize_database(&db_path).await?;
 
-        if !force {
-            print!("Delete note {}? (y/N): ", note_id);
-            std::io::stdout().flush()?;
-            let mut input = String::new();
-            std::io::stdin().read_line(&mut input)?;
-            if !input.trim().to_lowercase().starts_with('y') {
-                println!("Cancelled");
-                return Ok(());
-            }
+        if !force && !workspace::prompt::confirm(&format!("Delete note {}?", note_id), false)? {
+            println!("Cancelled");
+            return Ok(());
         }
 
         // TODO: Implement note deletion in new CRUD system
@@ -8124,7 +9447,6 @@ fn list_database_backups(backup_dir: Option<String>, format: String) -> Result<(
 fn restore_database_backup(backup_id: String, target: Option<String>, force: bool) -> Result<()> {
     use workspace::entities::database::{BackupConfig, list_backups, restore_backup};
     use colored::*;
-    use std::io::{self, Write};
     
     let rt = tokio::runtime::Runtime::new()?;
     rt.block_on(async {
@@ -8150,18 +9472,9 @@ fn restore_database_backup(backup_id: String, target: Option<String>, force: boo
             get_project_root()?.join(".ws/project.db")
         };
         
-        if target_path.exists() && !force {
-            print!("{} Database exists at {}. Overwrite? (y/N): ", 
-                   "⚠️".yellow(), target_path.display());
-            io::stdout().flush()?;
-            
-            let mut input = String::new();
-            io::stdin().read_line(&mut input)?;
-            
-            if !input.trim().to_lowercase().starts_with('y') {
-                println!("{} Restore cancelled", "❌".red());
-                return Ok(());
-            }
+        if target_path.exists() && !force && !workspace::prompt::confirm(&format!("{} Database exists at {}. Overwrite?", "⚠️".yellow(), target_path.display()), false)? {
+            println!("{} Restore cancelled", "❌".red());
+            return Ok(());
         }
         
         println!("{} Restoring database from backup...", "⏳".yellow());
@@ -8436,7 +9749,6 @@ fn load_session_continuity_state(session_id: String, format: String) -> Result<(
 fn transfer_session_continuity(from_session: String, to_session: String, force: bool) -> Result<()> {
     use workspace::entities::database::transfer_session_knowledge;
     use colored::*;
-    use std::io::{self, Write};
     
     let rt = tokio::runtime::Runtime::new()?;
     rt.block_on(async {
@@ -8449,18 +9761,9 @@ fn transfer_session_continuity(from_session: String, to_session: String, force:
         
         let pool = workspace::entities::database::initialize_database(&db_path).await?;
         
-        if !force {
-            print!("{} Transfer knowledge from session {} to {}? (y/N): ", 
-                   "⚠️".yellow(), from_session, to_session);
-            io::stdout().flush()?;
-            
-            let mut input = String::new();
-            io::stdin().read_line(&mut input)?;
-            
-            if !input.trim().to_lowercase().starts_with('y') {
-                println!("{} Transfer cancelled", "❌".red());
-                return Ok(());
-            }
+        if !force && !workspace::prompt::confirm(&format!("{} Transfer knowledge from session {} to {}?", "⚠️".yellow(), from_session, to_session), false)? {
+            println!("{} Transfer cancelled", "❌".red());
+            return Ok(());
         }
         
         println!("{} Transferring session knowledge...", "⏳".yellow());
@@ -8642,15 +9945,9 @@ fn unlink_note(link_id: String, force: bool) -> Result<()> {
         let pool = workspace::entities::database::initialize_database(&db_path).await?;
         let entity_manager = workspace::entities::EntityManager::new(pool);
 
-        if !force {
-            print!("Remove link {}? (y/N): ", link_id);
-            std::io::stdout().flush()?;
-            let mut input = String::new();
-            std::io::stdin().read_line(&mut input)?;
-            if !input.trim().to_lowercase().starts_with('y') {
-                println!("Cancelled");
-                return Ok(());
-            }
+        if !force && !workspace::prompt::confirm(&format!("Remove link {}?", link_id), false)? {
+            println!("Cancelled");
+            return Ok(());
         }
 
         let removed = entity_manager.remove_note_link(&link_id).await?;
@@ -9101,22 +10398,745 @@ fn search_artifacts_recursive(
 
 fn run_version_command(action: VersionAction) -> Result<()> {
     match action {
-        VersionAction::Show { verbose, format } => {
+        VersionAction::Init { version_file, strategy, hook, no_hook, initial_tag, yes } => {
+            handle_version_init(VersionInitOptions { version_file, strategy, hook, no_hook, initial_tag, yes })
+        }
+        VersionAction::Show { format, channel: Some(channel), .. } => {
+            handle_channel_show(&channel, &format)
+        }
+        VersionAction::Show { verbose, format, channel: None } => {
             handle_version_show(verbose, format)
         }
         VersionAction::Major { version } => {
             handle_version_major(version)
         }
-        VersionAction::Tag { prefix, message } => {
-            handle_version_tag(prefix, message)
+        VersionAction::Tag { prefix, message, sign, push, remote, allow_dirty, package, channel } => {
+            handle_version_tag(prefix, message, VersionTagOptions { sign, push, remote, allow_dirty, package, channel })
         }
         VersionAction::Info { include_history } => {
             handle_version_info(include_history)
         }
+        VersionAction::Bump { level, label, commit, tag, prefix, dry_run } => {
+            handle_version_bump(&level, label.as_deref(), VersionBumpOptions { commit, tag, prefix, dry_run })
+        }
+        VersionAction::Changelog { action, release, dry_run } => {
+            handle_version_changelog(action, release, dry_run)
+        }
+        VersionAction::Strategy { strategy } => {
+            handle_version_strategy(strategy)
+        }
+        VersionAction::Format { format } => {
+            handle_version_format(format)
+        }
+        VersionAction::Prerelease { label, clear } => {
+            handle_version_suffix("Prerelease label", label, clear, &['-', '+', '.'], |config| &mut config.prerelease)
+        }
+        VersionAction::BuildMetadata { template, clear } => {
+            handle_version_suffix("Build metadata", template, clear, &['+'], |config| &mut config.build_metadata)
+        }
+        VersionAction::Ci { provider, env_file } => {
+            handle_version_ci(provider, env_file)
+        }
+        VersionAction::Embed { output, language, template } => {
+            handle_version_embed(&output, language, template)
+        }
+        VersionAction::Config { action } => {
+            handle_version_config(action)
+        }
+        VersionAction::Enable => {
+            handle_version_enabled(true)
+        }
+        VersionAction::Disable => {
+            handle_version_enabled(false)
+        }
+        VersionAction::Sync { dry_run } => {
+            handle_version_sync(dry_run)
+        }
+        VersionAction::Check => {
+            handle_version_check()
+        }
+        VersionAction::CheckRemote { remote } => {
+            handle_version_check_remote(&remote)
+        }
+        VersionAction::History { limit, format } => {
+            handle_version_history(limit, &format)
+        }
+        VersionAction::Rollback => {
+            handle_version_rollback()
+        }
+        VersionAction::AnnotateCommitMsg { file, source } => {
+            handle_version_annotate_commit_msg(&file, source.as_deref())
+        }
+        VersionAction::Packages { action } => {
+            handle_version_packages(action)
+        }
+    }
+}
+
+/// Flags of `ws version init`; prompts fill in the rest on a terminal
+struct VersionInitOptions {
+    version_file: Option<String>,
+    strategy: Option<String>,
+    hook: Option<String>,
+    no_hook: bool,
+    initial_tag: bool,
+    yes: bool,
+}
+
+const INITIAL_VERSION: &str = "0.1.0";
+
+fn handle_version_init(options: VersionInitOptions) -> Result<()> {
+    let project_root = get_project_root()?;
+    fs::create_dir_all(project_root.join(".ws")).context("Failed to create .ws directory")?;
+    let mut config = St8Config::load(&project_root)?;
+    let interactive = !options.yes && workspace::prompt::is_interactive();
+    
+    println!("{}", "Version Setup".blue().bold());
+    println!();
+    
+    // Settings
+    let version_file = match options.version_file {
+        Some(version_file) => version_file,
+        None if interactive => dialoguer::Input::new()
+            .with_prompt("Version file")
+            .default(config.version_file.clone())
+            .interact_text()?,
+        None => config.version_file.clone(),
+    };
+    let strategies = ["count", "conventional"];
+    let strategy = match options.strategy {
+        Some(strategy) => strategy,
+        None if interactive => {
+            let current = strategies.iter().position(|s| *s == config.strategy.to_string()).unwrap_or(0);
+            let choice = dialoguer::Select::new()
+                .with_prompt("Derive versions from (count: commits and changed lines, conventional: feat/fix commit messages)")
+                .items(&strategies)
+                .default(current)
+                .interact()?;
+            strategies[choice].to_string()
+        }
+        None => config.strategy.to_string(),
+    };
+    
+    // Project files
+    let detected = detect_project_files(&project_root).unwrap_or_default();
+    if detected.is_empty() {
+        println!("{} No project files detected; only {} will carry the version", "Info".blue(), version_file);
+    } else {
+        println!("{}", "Detected project files:".blue());
+        for file in &detected {
+            let path = file.path.strip_prefix(&project_root).unwrap_or(&file.path);
+            println!("  {}", path.display());
+        }
+        if interactive {
+            config.auto_detect_project_files = workspace::prompt::confirm("Update their versions too?", config.auto_detect_project_files)?;
+        }
+    }
+    
+    config.version_file = version_file;
+    config.strategy = strategy.parse()?;
+    config.enabled = true;
+    config.save(&project_root)?;
+    log::info!("Initialized version settings in {}", project_root.display());
+    println!("{} Saved version settings (version file {}, strategy {})", "✅".green(), config.version_file.green(), config.strategy.to_string().green());
+    
+    if !is_git_repository() {
+        println!("{} Not a git repository; skipping the git hook and initial tag", "Info".blue());
+        return Ok(());
+    }
+    
+    // Git hook
+    let hook = match options.hook {
+        Some(hook) => Some(hook.parse::<HookType>()?),
+        None if options.no_hook || !interactive => None,
+        None => {
+            if workspace::prompt::confirm("Install a git hook to update the version automatically?", true)? {
+                let choice = dialoguer::Select::new()
+                    .with_prompt("Run the update")
+                    .items(&["before each commit (pre-commit)", "after each commit, amending it (post-commit)", "before each push (pre-push)"])
+                    .default(0)
+                    .interact()?;
+                Some(HookType::ALL[choice])
+            } else {
+                None
+            }
+        }
+    };
+    if let Some(hook) = hook {
+        install_hook(false, hook, false)?;
+    }
+    
+    // Initial tag
+    let repo = workspace::st8::GitRepo::discover()?;
+    let tags = config.tag_scheme();
+    if repo.tags()?.iter().any(|tag| tags.matches(tag)) {
+        return Ok(());
+    }
+    let initial_tag = tags.tag_name(INITIAL_VERSION);
+    let create_tag = options.initial_tag
+        || (interactive && workspace::prompt::confirm(&format!("The repository has no release tags; tag HEAD as {}?", initial_tag), false)?);
+    if create_tag {
+        if repo.head_commit().is_err() {
+            anyhow::bail!("Cannot tag {}: the repository has no commits yet", initial_tag);
+        }
+        let output = Command::new("git")
+            .args(["tag", "-a", &initial_tag, "-m", "Initial version"])
+            .output()
+            .context("Failed to create git tag")?;
+        if !output.status.success() {
+            anyhow::bail!("Failed to create git tag: {}", String::from_utf8_lossy(&output.stderr));
+        }
+        log::info!("Created initial tag {}", initial_tag);
+        println!("{} Created git tag: {}", "✅".green(), initial_tag.green().bold());
+    }
+    
+    Ok(())
+}
+
+fn handle_version_changelog(action: Option<ChangelogAction>, release: Option<String>, dry_run: bool) -> Result<()> {
+    let project_root = get_project_root()?;
+    let mut config = St8Config::load(&project_root)?;
+    
+    if let Some(ChangelogAction::Config { key, value }) = action {
+        return match (key, value) {
+            (Some(key), Some(value)) => {
+                config.changelog.set(&key, &value)?;
+                config.save(&project_root)?;
+                log::info!("Set changelog {} = {}", key, value);
+                println!("Set changelog {} = {}", key, value);
+                Ok(())
+            }
+            (key, _) => config.changelog.show(key.as_deref()),
+        };
+    }
+    
+    if !is_git_repository() {
+        anyhow::bail!("Not in a git repository");
+    }
+    
+    if dry_run {
+        print!("{}", workspace::st8::changelog::section_since_last_release(&config.changelog, &config.tag_scheme(), release.as_deref())?);
+        return Ok(());
+    }
+    
+    let path = workspace::st8::update_changelog(&config.changelog, &config.tag_scheme(), &get_git_root()?, release.as_deref())?;
+    log::info!("Updated changelog: {}", path.display());
+    println!("{} Updated {}", "✅".green(), path.display());
+    Ok(())
+}
+
+struct VersionBumpOptions {
+    commit: bool,
+    tag: bool,
+    prefix: Option<String>,
+    dry_run: bool,
+}
+
+fn handle_version_bump(level: &str, label: Option<&str>, options: VersionBumpOptions) -> Result<()> {
+    let project_root = get_project_root()?;
+    let config = St8Config::load(&project_root)?;
+    
+    let current = workspace::st8::current_version(&config)?;
+    let next = match (level, label) {
+        ("major", _) => current.bump(BumpLevel::Major),
+        ("minor", _) => current.bump(BumpLevel::Minor),
+        ("patch", _) => current.bump(BumpLevel::Patch),
+        ("pre", Some(label)) => current.prerelease(label),
+        ("pre", None) => match current.pre_label().or(config.prerelease.as_deref()) {
+            Some(label) => current.prerelease(label),
+            None => anyhow::bail!("A prerelease label is required, e.g. 'ws version bump pre rc'"),
+        },
+        (other, _) => anyhow::bail!("Unknown bump level: {}", other),
+    };
+    
+    if options.dry_run {
+        println!("{} {} → {}", "Would bump:".blue(), current.to_string().yellow(), next.to_string().green().bold());
+        let mut changes = workspace::st8::preview_version_update(&VersionInfo::from_semver(&next), &config)?;
+        if config.changelog.enabled && is_git_repository() {
+            let (path, content) = workspace::st8::changelog::changelog_content(&config.changelog, &config.tag_scheme(), &get_git_root()?, Some(&next.to_string()))?;
+            changes.push(workspace::st8::FileChange::new(path, content));
+        }
+        print_file_changes(&changes);
+        return Ok(());
+    }
+    
+    if options.commit || options.tag {
+        let repo = workspace::st8::GitRepo::discover()?;
+        if options.commit {
+            // A release commit on a detached HEAD would be lost on the next checkout
+            repo.current_branch()?;
+        }
+    }
+    
+    let version_info = VersionInfo::from_semver(&next);
+    workspace::st8::history::apply_version(&project_root, &version_info, &config, workspace::st8::ChangeSource::Bump)?;
+    log::info!("Bumped version {} -> {}", current, next);
+    println!("{} {} → {}", "✅".green(), current.to_string().yellow(), next.to_string().green().bold());
+    
+    if config.changelog.enabled && is_git_repository() {
+        let path = workspace::st8::update_changelog(&config.changelog, &config.tag_scheme(), &get_git_root()?, Some(&next.to_string()))?;
+        log::info!("Updated changelog: {}", path.display());
+    }
+    
+    if options.commit {
+        let files = workspace::st8::version_files(&config);
+        let mut add_args = vec!["add".to_string(), "--".to_string()];
+        add_args.extend(files.iter().map(|f| f.to_string_lossy().to_string()));
+        let output = Command::new("git").args(&add_args).output().context("Failed to stage version files")?;
+        if !output.status.success() {
+            anyhow::bail!("Failed to stage version files: {}", String::from_utf8_lossy(&output.stderr));
+        }
+        
+        // Skip the pre-commit hook, which would recalculate the version
+        let message = format!("chore(release): {}", next);
+        let output = Command::new("git")
+            .args(["commit", "--no-verify", "-m", &message])
+            .output()
+            .context("Failed to commit version bump")?;
+        if !output.status.success() {
+            anyhow::bail!("Failed to commit version bump: {}", String::from_utf8_lossy(&output.stderr));
+        }
+        log::info!("Committed version bump: {}", message);
+        println!("{} Committed: {}", "📝".blue(), message);
+    }
+    
+    if options.tag {
+        let prefix = options.prefix.unwrap_or_else(|| config.tag_prefix.clone());
+        let tag_name = format!("{}{}", prefix, next);
+        let tag_message = format!("Release version {}", next);
+        let output = Command::new("git")
+            .args(["tag", "-a", &tag_name, "-m", &tag_message])
+            .output()
+            .context("Failed to create git tag")?;
+        if !output.status.success() {
+            anyhow::bail!("Failed to create git tag: {}", String::from_utf8_lossy(&output.stderr));
+        }
+        log::info!("Created git tag: {}", tag_name);
+        println!("{} Created git tag: {}", "✅".green(), tag_name.green().bold());
+    }
+    
+    Ok(())
+}
+
+fn handle_version_strategy(strategy: Option<String>) -> Result<()> {
+    let project_root = get_project_root()?;
+    let mut config = St8Config::load(&project_root)?;
+    
+    match strategy {
+        Some(strategy) => {
+            config.strategy = strategy.parse()?;
+            config.save(&project_root)?;
+            log::info!("Version strategy set to {}", config.strategy);
+            println!("{} Version strategy set to {}", "✅".green(), config.strategy.to_string().green().bold());
+        }
+        None => println!("{}", config.strategy),
+    }
+    
+    Ok(())
+}
+
+fn handle_version_format(format: Option<String>) -> Result<()> {
+    let project_root = get_project_root()?;
+    let mut config = St8Config::load(&project_root)?;
+    
+    match format {
+        Some(format) => {
+            config.set("format", &format)?;
+            config.save(&project_root)?;
+            log::info!("Version format set to {}", config.format);
+            println!("{} Version format set to {}", "✅".green(), config.format.green().bold());
+        }
+        None => {
+            println!("{}", config.format);
+            println!();
+            println!("{}", "Placeholders:".blue());
+            for (name, meaning) in workspace::st8::format::PLACEHOLDERS {
+                println!("  {:<12} {}", format!("{{{}}}", name), meaning);
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// Show, set or clear one of the optional version suffixes
+fn handle_version_suffix(
+    name: &str,
+    value: Option<String>,
+    clear: bool,
+    forbidden: &[char],
+    field: impl Fn(&mut St8Config) -> &mut Option<String>,
+) -> Result<()> {
+    let project_root = get_project_root()?;
+    let mut config = St8Config::load(&project_root)?;
+    
+    if clear {
+        *field(&mut config) = None;
+        config.save(&project_root)?;
+        log::info!("{} cleared", name);
+        println!("{} {} cleared", "✅".green(), name);
+    } else if let Some(value) = value {
+        workspace::st8::format::validate_template(&value)?;
+        if value.is_empty() || value.contains(char::is_whitespace) || value.contains(forbidden) {
+            let forbidden: Vec<String> = forbidden.iter().map(|c| format!("'{}'", c)).collect();
+            anyhow::bail!("{} must not be empty or contain spaces or {}", name, forbidden.join(", "));
+        }
+        *field(&mut config) = Some(value.clone());
+        if config.semver_strict {
+            workspace::st8::format::validate_semver_template(&config.format, config.build_metadata.as_deref())?;
+        }
+        config.save(&project_root)?;
+        log::info!("{} set to {}", name, value);
+        println!("{} {} set to {}", "✅".green(), name, value.green().bold());
+    } else {
+        println!("{}", field(&mut config).as_deref().unwrap_or("(none)"));
+    }
+    
+    Ok(())
+}
+
+fn handle_version_ci(provider: Option<String>, env_file: Option<PathBuf>) -> Result<()> {
+    let config = St8Config::load(&get_project_root()?)?;
+    let provider = match provider {
+        Some(provider) => provider.parse()?,
+        None => workspace::st8::CiProvider::detect(),
+    };
+    
+    let rt = tokio::runtime::Runtime::new()?;
+    let version_info = rt.block_on(async {
+        let db_path = get_project_root()?.join(".ws/project.db");
+        let pool = workspace::entities::database::initialize_database(&db_path).await?;
+        let major_version = get_project_major_version(&pool).await?;
+        workspace::st8::VersionInfo::calculate_for(&config, major_version)
+    })?;
+    
+    let variables = workspace::st8::ci::version_variables(&version_info.full_version);
+    let written = workspace::st8::ci::write_ci_outputs(provider, &variables, env_file.as_deref())?;
+    
+    for (name, value) in &variables {
+        println!("{}={}", name, value);
+    }
+    if written.is_empty() {
+        eprintln!("{}: No CI provider detected; pass --provider or --env-file to write the variables", "Info".blue());
+    }
+    for file in written {
+        log::info!("Wrote version variables for {} to {}", provider, file.display());
+        eprintln!("{}: Wrote {}", "Info".blue(), file.display());
+    }
+    
+    Ok(())
+}
+
+fn handle_version_embed(output: &Path, language: Option<String>, template: Option<PathBuf>) -> Result<()> {
+    let template = match (template, language) {
+        (Some(path), _) => fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read template: {}", path.display()))?,
+        (None, Some(language)) => language.parse::<workspace::st8::EmbedLanguage>()?.template().to_string(),
+        (None, None) => workspace::st8::EmbedLanguage::from_path(output)
+            .with_context(|| format!("Can't tell the language of {}; pass --language or --template", output.display()))?
+            .template()
+            .to_string(),
+    };
+    
+    let project_root = get_project_root()?;
+    let config = St8Config::load(&project_root)?;
+    let rt = tokio::runtime::Runtime::new()?;
+    let version_info = rt.block_on(async {
+        let db_path = project_root.join(".ws/project.db");
+        let pool = workspace::entities::database::initialize_database(&db_path).await?;
+        let major_version = get_project_major_version(&pool).await?;
+        workspace::st8::VersionInfo::calculate_for(&config, major_version)
+    })?;
+    
+    let info = workspace::st8::BuildInfo::new(version_info.full_version, &project_root);
+    if workspace::st8::embed::write_embed(output, &template, &info)? {
+        log::info!("Embedded version {} in {}", info.version, output.display());
+        println!("{}: Wrote {} ({})", "Info".blue(), output.display(), info.version);
+    } else {
+        println!("{}: {} is up to date ({})", "Info".blue(), output.display(), info.version);
+    }
+    
+    Ok(())
+}
+
+fn handle_version_config(action: Option<VersionConfigAction>) -> Result<()> {
+    let key = match action {
+        // The schema is needed before there is a project to configure
+        Some(VersionConfigAction::Schema) => {
+            print!("{}", workspace::st8::config_file::SCHEMA);
+            return Ok(());
+        }
+        Some(VersionConfigAction::Files) => return print_config_files(&get_project_root()?),
+        Some(VersionConfigAction::Set { key, value }) => return set_version_config(&key, &value),
+        Some(VersionConfigAction::Get { key }) => key,
+        None => None,
+    };
+    
+    St8Config::load(&get_project_root()?)?.show(key.as_deref())
+}
+
+fn set_version_config(key: &str, value: &str) -> Result<()> {
+    let project_root = get_project_root()?;
+    let mut config = St8Config::load(&project_root)?;
+    config.set(key, value)?;
+    config.save(&project_root)?;
+    log::info!("Set version config {} = {}", key, value);
+    println!("Set {} = {}", key, config.get(key)?);
+    if let Some(file) = workspace::st8::config_file::overriding_file(&project_root, key)? {
+        eprintln!("{}: {} also sets {}, which takes precedence; change it there", "Warning".yellow(), file.display(), key);
+    }
+    Ok(())
+}
+
+/// The config layers, lowest precedence first, and which files exist
+fn print_config_files(project_root: &std::path::Path) -> Result<()> {
+    let describe = |file: Option<PathBuf>, pattern: PathBuf| match file {
+        Some(file) => file.display().to_string().green().to_string(),
+        None => format!("{} (not present)", pattern.display()).dimmed().to_string(),
+    };
+    let names = "version.{toml,yaml,yml,json}";
+    let user_file = workspace::config::WsConfig::user_file();
+    println!("1. built-in defaults");
+    println!("2. user defaults, for new projects: {} [version], then {}",
+             describe(Some(user_file.clone()).filter(|file| file.is_file()), user_file.clone()),
+             describe(workspace::st8::config_file::user_config_file(), workspace::st8::config_file::user_config_dir().join(names)));
+    println!("3. project database: {}", project_root.join(".ws/project.db").display());
+    println!("4. project file: {} [version]",
+             describe(workspace::config::WsConfig::project_file(project_root), PathBuf::from(workspace::config::PROJECT_FILE)));
+    println!("5. repository file: {}",
+             describe(workspace::st8::config_file::repo_config_file(project_root), PathBuf::from(".ws").join(names)));
+    let env: Vec<String> = workspace::config::version_env().into_iter()
+        .map(|(key, _)| workspace::config::version_env_var(&key))
+        .collect();
+    println!("6. environment: {}", if env.is_empty() { "no WS_VERSION_* variables set".dimmed().to_string() } else { env.join(", ").green().to_string() });
+    Ok(())
+}
+
+fn handle_version_enabled(enabled: bool) -> Result<()> {
+    let project_root = get_project_root()?;
+    let mut config = St8Config::load(&project_root)?;
+    config.enabled = enabled;
+    config.save(&project_root)?;
+    
+    if enabled {
+        log::info!("Version updates enabled");
+        println!("{} Version updates enabled", "✅".green());
+    } else {
+        log::info!("Version updates disabled");
+        println!("{} Version updates disabled; the git hook stays installed but changes nothing", "✅".green());
+    }
+    Ok(())
+}
+
+fn handle_version_sync(dry_run: bool) -> Result<()> {
+    let config = St8Config::load(&get_project_root()?)?;
+    let (version, changes) = workspace::st8::st8_common::sync_version_files(&config, dry_run)?;
+    
+    if dry_run {
+        println!("{} {}", "Version:".blue(), version.green().bold());
+        print_file_changes(&changes);
+        return Ok(());
+    }
+    
+    if changes.is_empty() {
+        println!("{} All version files already carry {}", "✅".green(), version.green().bold());
+        return Ok(());
+    }
+    log::info!("Synced {} files to version {}", changes.len(), version);
+    println!("{} Synced {} files to {}", "✅".green(), changes.len(), version.green().bold());
+    for change in &changes {
+        println!("  - {}", change.path.display());
+    }
+    
+    Ok(())
+}
+
+fn handle_version_check() -> Result<()> {
+    let config = St8Config::load(&get_project_root()?)?;
+    let check = workspace::st8::st8_common::check_version_files(&config)?;
+    
+    if !check.problems.is_empty() {
+        for problem in &check.problems {
+            println!("{} {}", "❌".red(), problem);
+        }
+        log::warn!("Version check found {} problems", check.problems.len());
+        anyhow::bail!("{} version check{} failed", check.problems.len(), if check.problems.len() == 1 { "" } else { "s" });
+    }
+    let version = check.version.unwrap_or_default();
+    let form = if config.semver_strict { "a strict semantic version" } else { "a semantic version" };
+    println!("{} {} is {} and every project file carries it", "✅".green(), version.green().bold(), form);
+    Ok(())
+}
+
+fn handle_version_check_remote(remote: &str) -> Result<()> {
+    let config = St8Config::load(&get_project_root()?)?;
+    let local = workspace::st8::st8_common::canonical_version(&config)?
+        .ok_or_else(|| anyhow::anyhow!("No version to compare: {} is missing and there are no release tags", config.version_file))?;
+    let local_version = workspace::st8::SemVer::parse(&local)
+        .ok_or_else(|| anyhow::anyhow!("Version {} in {} is not a semantic version", local, config.version_file))?;
+    
+    let Some((tag, released)) = workspace::st8::st8_common::latest_remote_release(&config, remote)? else {
+        println!("{}: No release tags on {}; nothing to compare {} with", "Info".blue(), remote, local.green());
+        return Ok(());
+    };
+    if local_version.cmp_precedence(&released) != std::cmp::Ordering::Greater {
+        log::warn!("Version {} is not newer than {} on {}", local, tag, remote);
+        anyhow::bail!(
+            "Version {} is not newer than the latest release on {} ({}); bump it before pushing, e.g. 'ws version bump patch'",
+            local, remote, tag
+        );
+    }
+    println!("{} Version {} is newer than {} on {}", "✅".green(), local.green().bold(), tag, remote);
+    Ok(())
+}
+
+/// `prepare-commit-msg`: append `(version: X.Y.Z)` to the message when the
+/// commit includes the version files, as the pre-commit hook or the
+/// post-commit hook's amend leaves them staged
+fn handle_version_annotate_commit_msg(file: &Path, source: Option<&str>) -> Result<()> {
+    let project_root = get_project_root()?;
+    let config = St8Config::load(&project_root)?;
+    // Separate version commits carry the version in their own message
+    if !config.annotate_commit_msg || config.commit_mode == CommitMode::Separate || source == Some("merge") {
+        return Ok(());
+    }
+    
+    let repo = workspace::st8::GitRepo::discover()?;
+    let staged = repo.staged_paths()?;
+    let current_dir = env::current_dir()?;
+    let includes_version = workspace::st8::st8_common::version_files(&config).iter().any(|path| {
+        current_dir.join(path).strip_prefix(repo.root())
+            .is_ok_and(|relative| staged.iter().any(|staged| Path::new(staged) == relative))
+    });
+    if !includes_version {
+        return Ok(());
+    }
+    let Some(version) = workspace::st8::st8_common::canonical_version(&config)? else {
+        return Ok(());
+    };
+    
+    let message = fs::read_to_string(file)
+        .with_context(|| format!("Failed to read commit message {}", file.display()))?;
+    if let Some(annotated) = workspace::st8::hooks::annotate_commit_message(&message, &version) {
+        fs::write(file, annotated)
+            .with_context(|| format!("Failed to write commit message {}", file.display()))?;
+        log::info!("Annotated commit message with version {}", version);
+    }
+    Ok(())
+}
+
+fn handle_version_history(limit: Option<usize>, format: &str) -> Result<()> {
+    let history = workspace::st8::history::load_history(&get_project_root()?, limit)?;
+    
+    if format == "json" {
+        let changes: Vec<serde_json::Value> = history.iter().map(|change| change.to_json()).collect();
+        println!("{}", serde_json::to_string_pretty(&changes)?);
+        return Ok(());
+    }
+    
+    if history.is_empty() {
+        println!("No version changes recorded yet");
+        return Ok(());
+    }
+    for change in &history {
+        let commit = change.commit.as_deref().map(|sha| &sha[..sha.len().min(7)]).unwrap_or("-");
+        let mut line = format!("{}  {:<8} {} → {}  {}",
+                               change.recorded_at, change.source.to_string(),
+                               change.old_version.as_deref().unwrap_or("(none)").yellow(),
+                               change.new_version.green(), commit.dimmed());
+        if change.rolled_back {
+            line.push_str(&format!(" {}", "(rolled back)".red()));
+        }
+        println!("{}", line);
+        for file in &change.files {
+            println!("    {}", file);
+        }
+    }
+    
+    Ok(())
+}
+
+fn handle_version_rollback() -> Result<()> {
+    let project_root = get_project_root()?;
+    let config = St8Config::load(&project_root)?;
+    let change = workspace::st8::history::rollback(&project_root, &config)?;
+    
+    let previous = change.old_version.unwrap_or_default();
+    println!("{} Rolled back {} → {}", "✅".green(), change.new_version.yellow(), previous.green().bold());
+    if config.enabled && is_hook_installed()? {
+        println!("{} The git hook recalculates the version on the next commit; run 'ws version disable' to keep {}", "💡".yellow(), previous);
+    }
+    
+    Ok(())
+}
+
+fn handle_version_packages(action: Option<PackagesAction>) -> Result<()> {
+    let project_root = get_project_root()?;
+    let mut config = St8Config::load(&project_root)?;
+    
+    match action {
+        Some(PackagesAction::Add { name, path, version_file, tag_prefix, project_files, no_auto_detect }) => {
+            if config.packages.iter().any(|p| p.name == name) {
+                anyhow::bail!("Package {} already exists", name);
+            }
+            let mut package = workspace::st8::PackageConfig::new(&name, &path);
+            package.version_file = version_file;
+            package.tag_prefix = tag_prefix;
+            package.project_files = project_files;
+            package.auto_detect_project_files = !no_auto_detect;
+            let prefix = package.tag_prefix();
+            config.packages.push(package);
+            config.save(&project_root)?;
+            log::info!("Added package {} at {}", name, path);
+            println!("{} Added package {} ({}, tags {}X.Y.Z)", "✅".green(), name.green().bold(), path, prefix);
+        }
+        Some(PackagesAction::Remove { name }) => {
+            let before = config.packages.len();
+            config.packages.retain(|p| p.name != name);
+            if config.packages.len() == before {
+                anyhow::bail!("No package named {}", name);
+            }
+            config.save(&project_root)?;
+            log::info!("Removed package {}", name);
+            println!("{} Removed package {}", "✅".green(), name);
+        }
+        None => {
+            if config.packages.is_empty() {
+                println!("No packages configured. Add one with 'ws version packages add <name> <path>'");
+                return Ok(());
+            }
+            for status in workspace::st8::analyze_packages(&config.packages, config.strategy)? {
+                print_package_status(&status);
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+fn print_package_status(status: &workspace::st8::PackageVersion) {
+    let next = status.next_version();
+    if status.touched() && next != status.current {
+        println!("  {} ({}): {} → {} ({} commits)", status.name.bold(), status.path,
+                 status.current.to_string().yellow(), next.to_string().green(), status.decision.commits_analyzed);
+    } else {
+        println!("  {} ({}): {}", status.name.bold(), status.path, status.current.to_string().green());
     }
 }
 
+fn package_status_json(status: &workspace::st8::PackageVersion) -> serde_json::Value {
+    serde_json::json!({
+        "name": status.name,
+        "path": status.path,
+        "version": status.current.to_string(),
+        "next": status.next_version().to_string(),
+        "last_tag": status.decision.last_tag,
+        "commits": status.decision.commits_analyzed
+    })
+}
+
 fn handle_version_show(verbose: bool, format: String) -> Result<()> {
+    let config = St8Config::load(&get_project_root()?)?;
     let rt = tokio::runtime::Runtime::new()?;
     rt.block_on(async {
         let db_path = get_project_root()?.join(".ws/project.db");
@@ -9128,12 +11148,17 @@ fn handle_version_show(verbose: bool, format: String) -> Result<()> {
         let major_version = get_project_major_version(&pool).await?;
         
         // Calculate version using new system
-        let version_info = workspace::st8::VersionInfo::calculate_with_major(major_version)?;
+        let version_info = workspace::st8::VersionInfo::calculate_for(&config, major_version)?;
+        let decision = match config.strategy {
+            VersionStrategy::Conventional => Some(BumpDecision::analyze(&config.tag_scheme())?),
+            VersionStrategy::Count => None,
+        };
+        let packages = workspace::st8::analyze_packages(&config.packages, config.strategy)?;
         
         match format.as_str() {
             "json" => {
-                let json_output = if verbose {
-                    let calc_info = workspace::st8::VersionInfo::get_calculation_info(major_version)?;
+                let mut json_output = if verbose {
+                    let calc_info = workspace::st8::VersionInfo::get_calculation_info(&config, major_version)?;
                     serde_json::json!({
                         "version": version_info.full_version,
                         "major": major_version,
@@ -9156,19 +11181,49 @@ fn handle_version_show(verbose: bool, format: String) -> Result<()> {
                         "project": project.map(|p| p.name.clone()).unwrap_or_else(|| "Unknown".to_string())
                     })
                 };
+                json_output["strategy"] = serde_json::json!(config.strategy.to_string());
+                if let Some(decision) = &decision {
+                    json_output["major"] = serde_json::json!(decision.next_version().major);
+                    json_output["bump"] = serde_json::json!({
+                        "level": decision.level.to_string(),
+                        "base_version": decision.base_version.to_string(),
+                        "last_tag": decision.last_tag,
+                        "commits": decision.commits_analyzed,
+                        "reason": decision.describe()
+                    });
+                }
+                if !packages.is_empty() {
+                    json_output["packages"] = packages.iter().map(package_status_json).collect();
+                }
                 println!("{}", serde_json::to_string_pretty(&json_output)?);
             }
             _ => {
                 println!("{} {}", "Version:".blue().bold(), version_info.full_version.green().bold());
                 let project_name = project.map(|p| p.name.clone()).unwrap_or_else(|| "Unknown".to_string());
                 println!("{} {}", "Project:".blue(), project_name);
+                println!("{} {}", "Strategy:".blue(), config.strategy);
+                if let Some(decision) = &decision {
+                    println!("{} {}", "Bump:".blue(), decision.describe());
+                }
+                if !packages.is_empty() {
+                    println!("{}", "Packages:".blue());
+                    for status in &packages {
+                        print_package_status(status);
+                    }
+                }
                 
                 if verbose {
-                    let calc_info = workspace::st8::VersionInfo::get_calculation_info(major_version)?;
+                    let calc_info = workspace::st8::VersionInfo::get_calculation_info(&config, major_version)?;
                     println!("\n{}", "Calculation Breakdown:".blue().bold());
-                    println!("  {} {}", "Major (DB):".blue(), major_version.to_string().yellow());
-                    println!("  {} {}", "Minor (commits):".blue(), version_info.minor_version.to_string().yellow());
-                    println!("  {} {}", "Patch (changes):".blue(), version_info.patch_version.to_string().yellow());
+                    if let Some(decision) = &decision {
+                        println!("  {} {}", "Base (last tag):".blue(), decision.base_version.to_string().yellow());
+                        println!("  {} {}", "Commits analyzed:".blue(), decision.commits_analyzed.to_string().yellow());
+                        println!("  {} {}", "Bump level:".blue(), decision.level.to_string().yellow());
+                    } else {
+                        println!("  {} {}", "Major (DB):".blue(), major_version.to_string().yellow());
+                        println!("  {} {}", "Minor (commits):".blue(), version_info.minor_version.to_string().yellow());
+                        println!("  {} {}", "Patch (changes):".blue(), version_info.patch_version.to_string().yellow());
+                    }
                     
                     if let Some(tag) = calc_info.last_release_tag {
                         println!("  {} {}", "Last release tag:".blue(), tag.green());
@@ -9186,6 +11241,7 @@ fn handle_version_show(verbose: bool, format: String) -> Result<()> {
 }
 
 fn handle_version_major(version: u32) -> Result<()> {
+    let config = St8Config::load(&get_project_root()?)?;
     let rt = tokio::runtime::Runtime::new()?;
     rt.block_on(async {
         let db_path = get_project_root()?.join(".ws/project.db");
@@ -9198,7 +11254,7 @@ fn handle_version_major(version: u32) -> Result<()> {
             .await?;
         
         // Calculate new version
-        let version_info = workspace::st8::VersionInfo::calculate_with_major(version)?;
+        let version_info = workspace::st8::VersionInfo::calculate_for(&config, version)?;
         
         log::info!("Major version set to {}, new version: {}", version, version_info.full_version);
         println!("{} Major version set to {}", "✅".green(), version.to_string().green().bold());
@@ -9211,21 +11267,63 @@ fn handle_version_major(version: u32) -> Result<()> {
     })
 }
 
-fn handle_version_tag(prefix: String, message: Option<String>) -> Result<()> {
+struct VersionTagOptions {
+    sign: bool,
+    push: bool,
+    remote: String,
+    allow_dirty: bool,
+    package: Option<String>,
+    channel: Option<String>,
+}
+
+fn handle_version_tag(prefix: Option<String>, message: Option<String>, options: VersionTagOptions) -> Result<()> {
+    let config = St8Config::load(&get_project_root()?)?;
+    let prefix = prefix.unwrap_or_else(|| config.tag_prefix.clone());
+    let repo = workspace::st8::GitRepo::discover()?;
+    if !options.allow_dirty && repo.is_dirty()? {
+        anyhow::bail!("Working tree has uncommitted changes; commit them first or pass --allow-dirty");
+    }
+    
     let rt = tokio::runtime::Runtime::new()?;
     rt.block_on(async {
         let db_path = get_project_root()?.join(".ws/project.db");
         let pool = workspace::entities::database::initialize_database(&db_path).await?;
         
-        let major_version = get_project_major_version(&pool).await?;
-        let version_info = workspace::st8::VersionInfo::calculate_with_major(major_version)?;
+        let (tag_name, default_message) = match (&options.package, &options.channel) {
+            (_, Some(name)) => {
+                let (channel, next) = next_channel_version(&config, &pool, name).await?;
+                (channel.tag_scheme(&config).tag_name(&next.version), format!("Release {} {}", name, next.version))
+            }
+            (Some(name), None) => {
+                let package = config.packages.iter()
+                    .find(|p| &p.name == name)
+                    .ok_or_else(|| anyhow::anyhow!("No package named {}", name))?;
+                let status = workspace::st8::PackageVersion::analyze(package, repo.root(), config.strategy)?;
+                (package.tag_name(&status.current), format!("Release {} {}", name, status.current))
+            }
+            (None, None) => {
+                // The conventional next version is the last tag's until a commit asks for more
+                if config.strategy == VersionStrategy::Conventional {
+                    let decision = BumpDecision::analyze(&config.tag_scheme())?;
+                    if !decision.release_due() {
+                        anyhow::bail!("Nothing to release: {}", decision.describe());
+                    }
+                }
+                let major_version = get_project_major_version(&pool).await?;
+                let version_info = workspace::st8::VersionInfo::calculate_for(&config, major_version)?;
+                (format!("{}{}", prefix, version_info.full_version), format!("Release version {}", version_info.full_version))
+            }
+        };
         
-        let tag_name = format!("{}{}", prefix, version_info.full_version);
-        let tag_message = message.unwrap_or_else(|| format!("Release version {}", version_info.full_version));
+        if repo.tag_exists(&tag_name)? {
+            anyhow::bail!("Tag {} already exists", tag_name);
+        }
+        let tag_message = message.unwrap_or(default_message);
         
         // Create git tag
+        let kind = if options.sign { "-s" } else { "-a" };
         let output = Command::new("git")
-            .args(["tag", "-a", &tag_name, "-m", &tag_message])
+            .args(["tag", kind, &tag_name, "-m", &tag_message])
             .output()
             .context("Failed to create git tag")?;
         
@@ -9235,35 +11333,128 @@ fn handle_version_tag(prefix: String, message: Option<String>) -> Result<()> {
         }
         
         log::info!("Created git tag: {}", tag_name);
-        println!("{} Created git tag: {}", "✅".green(), tag_name.green().bold());
+        println!("{} Created {}git tag: {}", "✅".green(), if options.sign { "signed " } else { "" }, tag_name.green().bold());
         println!("{} Message: {}", "📝".blue(), tag_message);
-        println!("\n{} To push tag to remote: git push origin {}", "💡".yellow(), tag_name);
+        
+        if options.push {
+            let output = Command::new("git")
+                .args(["push", &options.remote, &format!("refs/tags/{}", tag_name)])
+                .output()
+                .context("Failed to push git tag")?;
+            
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                anyhow::bail!("Created {} but failed to push it to {}: {}", tag_name, options.remote, stderr);
+            }
+            log::info!("Pushed git tag {} to {}", tag_name, options.remote);
+            println!("{} Pushed to {}", "🚀".green(), options.remote);
+        } else {
+            println!("\n{} To push tag to remote: git push {} {}", "💡".yellow(), options.remote, tag_name);
+        }
         
         anyhow::Ok(())
     })
 }
 
+/// The channel called `name` and its next version, built on the stable version
+async fn next_channel_version(config: &St8Config, pool: &SqlitePool, name: &str) -> Result<(workspace::st8::ChannelConfig, workspace::st8::ChannelVersion)> {
+    let channel = workspace::st8::channels::find_channel(config, name)?.clone();
+    let major_version = get_project_major_version(pool).await?;
+    let version_info = workspace::st8::VersionInfo::calculate_for(config, major_version)?;
+    let stable = workspace::st8::SemVer::parse(&version_info.full_version)
+        .ok_or_else(|| anyhow::anyhow!("Release channels need semver versions, but the version is {}", version_info.full_version))?;
+    let next = workspace::st8::channels::channel_version(config, &channel, &stable)?;
+    Ok((channel, next))
+}
+
+fn handle_channel_show(name: &str, format: &str) -> Result<()> {
+    let config = St8Config::load(&get_project_root()?)?;
+    let rt = tokio::runtime::Runtime::new()?;
+    let (channel, next) = rt.block_on(async {
+        let db_path = get_project_root()?.join(".ws/project.db");
+        let pool = workspace::entities::database::initialize_database(&db_path).await?;
+        next_channel_version(&config, &pool, name).await
+    })?;
+
+    match format {
+        "json" => {
+            let json_output = serde_json::json!({
+                "channel": channel.name,
+                "version": next.version.to_string(),
+                "last_tag": next.last_tag,
+                "version_file": channel.version_file(),
+            });
+            println!("{}", serde_json::to_string_pretty(&json_output)?);
+        }
+        _ => {
+            println!("{} {}", "Channel:".bold(), channel.name.cyan());
+            println!("{} {}", "Version:".bold(), next.version.to_string().green().bold());
+            println!("{} {}", "Last tag:".bold(), next.last_tag.as_deref().unwrap_or("no tags yet"));
+            println!("{} {}", "Version file:".bold(), channel.version_file());
+        }
+    }
+    Ok(())
+}
+
+/// Write the channel's next version to its version file, leaving the
+/// stable version and project files alone
+fn update_channel(name: &str, git_add: bool, dry_run: bool) -> Result<()> {
+    let project_root = get_project_root()?;
+    let config = St8Config::load(&project_root)?;
+    let rt = tokio::runtime::Runtime::new()?;
+    let (channel, next) = rt.block_on(async {
+        let db_path = project_root.join(".ws/project.db");
+        let pool = workspace::entities::database::initialize_database(&db_path).await?;
+        next_channel_version(&config, &pool, name).await
+    })?;
+
+    let version_file = channel.version_file();
+    if dry_run {
+        println!("{} Would write {} {} to {}", "Info".blue(), channel.name, next.version, version_file);
+        return Ok(());
+    }
+    let repo_root = workspace::st8::GitRepo::discover()?.root().to_path_buf();
+    if workspace::st8::channels::write_channel_version(&repo_root, &config, &channel, &next.version)? {
+        log::info!("Updated {} channel to {}", channel.name, next.version);
+        println!("{} Updated {} to {} ({})", "✅".green(), channel.name, next.version.to_string().green(), version_file);
+        if git_add {
+            add_files_to_git(&[version_file])?;
+        }
+    } else {
+        println!("{} {} is already at {}", "Info".blue(), channel.name, next.version);
+    }
+    Ok(())
+}
+
 fn handle_version_info(include_history: bool) -> Result<()> {
+    let config = St8Config::load(&get_project_root()?)?;
     let rt = tokio::runtime::Runtime::new()?;
     rt.block_on(async {
         let db_path = get_project_root()?.join(".ws/project.db");
         let pool = workspace::entities::database::initialize_database(&db_path).await?;
         
         let major_version = get_project_major_version(&pool).await?;
-        let calc_info = workspace::st8::VersionInfo::get_calculation_info(major_version)?;
-        let version_info = workspace::st8::VersionInfo::calculate_with_major(major_version)?;
+        let calc_info = workspace::st8::VersionInfo::get_calculation_info(&config, major_version)?;
+        let version_info = workspace::st8::VersionInfo::calculate_for(&config, major_version)?;
         
         println!("{}", "Version Calculation Information".blue().bold());
         println!("=====================================");
         println!();
         
         println!("{}", "Current Version Breakdown:".green().bold());
-        println!("  {} {}.{}.{}", "Version:".blue(), major_version.to_string().green(), 
-                 version_info.minor_version.to_string().yellow(), 
-                 version_info.patch_version.to_string().cyan());
-        println!("  {} {} (stored in database)", "Major:".blue(), major_version.to_string().green());
-        println!("  {} {} (total git commits)", "Minor:".blue(), version_info.minor_version.to_string().yellow());
-        println!("  {} {} (changes since last v{}.* tag)", "Patch:".blue(), version_info.patch_version.to_string().cyan(), major_version);
+        println!("  {} {}", "Strategy:".blue(), config.strategy.to_string().cyan());
+        if let Some(ref decision) = calc_info.bump_decision {
+            println!("  {} {}", "Version:".blue(), version_info.full_version.green());
+            println!("  {} {} (last release tag)", "Base:".blue(), decision.base_version.to_string().yellow());
+            println!("  {} {}", "Bump:".blue(), decision.describe().cyan());
+        } else {
+            println!("  {} {}.{}.{}", "Version:".blue(), major_version.to_string().green(), 
+                     version_info.minor_version.to_string().yellow(), 
+                     version_info.patch_version.to_string().cyan());
+            println!("  {} {} (stored in database)", "Major:".blue(), major_version.to_string().green());
+            println!("  {} {} (total git commits)", "Minor:".blue(), version_info.minor_version.to_string().yellow());
+            println!("  {} {} (changes since last v{}.* tag)", "Patch:".blue(), version_info.patch_version.to_string().cyan(), major_version);
+        }
         println!();
         
         println!("{}", "Git Integration:".green().bold());
@@ -9281,6 +11472,7 @@ fn handle_version_info(include_history: bool) -> Result<()> {
         println!("{}", "Commands:".green().bold());
         println!("  {} Set major version", format!("ws version major <number>").cyan());
         println!("  {} Create release tag", "ws version tag".cyan());
+        println!("  {} Choose count or conventional versioning", "ws version strategy <name>".cyan());
         println!("  {} Show current version", "ws version show".cyan());
         println!();
         
@@ -0,0 +1,317 @@
+//! Settings shared by every ws tool, read from `ws.toml` in the project and
+//! `$XDG_CONFIG_HOME/ws/config.toml` for the user:
+//!
+//! ```toml
+//! [refactor]
+//! exclude = ["target/**", "*.lock"]
+//!
+//! [refactor.handlers]
+//! "*.ipynb" = "ws-ipynb-handler"
+//!
+//! [refactor.imports.python]
+//! extensions = ["py"]
+//! references = ["import {module}", "from {module} import"]
+//!
+//! [scrap]
+//! retention_days = 14
+//! gitignore = "exclude"
+//! compress_over_mb = 50
+//! tier_after_days = 90
+//! on_clean = "notify-send 'Scrap clean' \"$WS_SCRAP_COUNT item(s) removed\""
+//!
+//! [version]
+//! strategy = "conventional"
+//! ```
+//!
+//! Precedence, highest first: command line options, `WS_*` environment
+//! variables, the project file, the user file, then each tool's own settings.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::refac::handlers::ContentHandler;
+use crate::refac::imports::{default_languages, ImportLanguage};
+use crate::scrap::{GitignoreMode, HistoryOperation};
+
+/// Name of the project config file, looked for in the working directory and
+/// its parents
+pub const PROJECT_FILE: &str = "ws.toml";
+
+/// Days `ws scrap clean` keeps items when neither `--days` nor a config sets it
+pub const DEFAULT_RETENTION_DAYS: u32 = 30;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RefactorSettings {
+    /// Glob patterns never renamed or edited, added to `--exclude`
+    #[serde(default)]
+    pub exclude: Option<Vec<String>>,
+    /// Commands doing content replacement for files matching a glob, see
+    /// `refac::handlers`
+    #[serde(default)]
+    pub handlers: Option<BTreeMap<String, String>>,
+    /// Module reference patterns for `--update-imports` by language name,
+    /// replacing or adding to the built-in ones, see `refac::imports`
+    #[serde(default)]
+    pub imports: Option<BTreeMap<String, ImportLanguage>>,
+    /// Process hidden files whenever an `--include` pattern contains `*`,
+    /// as before `--hidden` existed
+    #[serde(default)]
+    pub legacy_hidden_inference: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScrapSettings {
+    /// Age in days after which `ws scrap clean` removes items
+    #[serde(default)]
+    pub retention_days: Option<u32>,
+    /// Where new scrap folders get ignored; overrides `ws scrap config`
+    #[serde(default)]
+    pub gitignore: Option<GitignoreMode>,
+    /// Items larger than this many MiB are stored zstd-compressed
+    #[serde(default)]
+    pub compress_over_mb: Option<u64>,
+    /// Age in days after which `ws scrap` moves items into monthly archives
+    /// under `.scrap/archives`
+    #[serde(default)]
+    pub tier_after_days: Option<u32>,
+    /// Shell commands run after items are scrapped, cleaned or purged, see
+    /// `scrap::hooks`
+    #[serde(default)]
+    pub on_scrap: Option<String>,
+    #[serde(default)]
+    pub on_clean: Option<String>,
+    #[serde(default)]
+    pub on_purge: Option<String>,
+}
+
+/// One config file, or several merged
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WsConfig {
+    #[serde(default)]
+    pub refactor: RefactorSettings,
+    #[serde(default)]
+    pub scrap: ScrapSettings,
+    /// Version settings, as in `.ws/version.toml`
+    #[serde(default)]
+    pub version: Map<String, Value>,
+}
+
+impl WsConfig {
+    /// The user's config file; it need not exist
+    pub fn user_file() -> PathBuf {
+        crate::st8::config_file::user_config_dir().join("config.toml")
+    }
+
+    /// The nearest `ws.toml` in `dir` or its parents
+    pub fn project_file(dir: &Path) -> Option<PathBuf> {
+        dir.ancestors().map(|dir| dir.join(PROJECT_FILE)).find(|path| path.is_file())
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let config: Self = toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))?;
+        crate::st8::config_file::check_settings(&config.version, path)?;
+        Ok(config)
+    }
+
+    /// The user file, then the project file for `dir`, then the environment
+    pub fn load(dir: &Path) -> Result<Self> {
+        let mut config = Self::default();
+        for path in [Some(Self::user_file()), Self::project_file(dir)].into_iter().flatten() {
+            if path.is_file() {
+                config.merge(Self::read(&path)?);
+            }
+        }
+        config.merge(Self::from_env()?);
+        Ok(config)
+    }
+
+    /// Settings for the current directory, falling back to the defaults
+    /// (with a warning) when a file cannot be read
+    pub fn current() -> Self {
+        let dir = std::env::current_dir().unwrap_or_default();
+        Self::load(&dir).unwrap_or_else(|e| {
+            log::warn!("Ignoring unreadable ws config: {:#}", e);
+            Self::default()
+        })
+    }
+
+    /// `WS_REFACTOR_EXCLUDE` (comma-separated), `WS_SCRAP_RETENTION_DAYS`,
+    /// `WS_SCRAP_GITIGNORE`, `WS_SCRAP_COMPRESS_OVER_MB` and
+    /// `WS_SCRAP_TIER_AFTER_DAYS`; version settings are in `version_env`
+    pub fn from_env() -> Result<Self> {
+        let var = |name: &str| std::env::var(name).ok();
+        let mut config = Self::default();
+        if let Some(exclude) = var("WS_REFACTOR_EXCLUDE") {
+            config.refactor.exclude = Some(exclude.split(',').map(str::trim).filter(|p| !p.is_empty()).map(str::to_string).collect());
+        }
+        if let Some(days) = var("WS_SCRAP_RETENTION_DAYS") {
+            config.scrap.retention_days = Some(days.parse().with_context(|| format!("WS_SCRAP_RETENTION_DAYS: invalid number of days: {}", days))?);
+        }
+        if let Some(mode) = var("WS_SCRAP_GITIGNORE") {
+            config.scrap.gitignore = Some(mode.parse().context("WS_SCRAP_GITIGNORE")?);
+        }
+        if let Some(mb) = var("WS_SCRAP_COMPRESS_OVER_MB") {
+            config.scrap.compress_over_mb = Some(mb.parse().with_context(|| format!("WS_SCRAP_COMPRESS_OVER_MB: invalid size: {}", mb))?);
+        }
+        if let Some(days) = var("WS_SCRAP_TIER_AFTER_DAYS") {
+            config.scrap.tier_after_days = Some(days.parse().with_context(|| format!("WS_SCRAP_TIER_AFTER_DAYS: invalid number of days: {}", days))?);
+        }
+        Ok(config)
+    }
+
+    /// Settings of `other` take the place of those it sets
+    pub fn merge(&mut self, other: Self) {
+        if other.refactor.exclude.is_some() {
+            self.refactor.exclude = other.refactor.exclude;
+        }
+        if other.refactor.handlers.is_some() {
+            self.refactor.handlers = other.refactor.handlers;
+        }
+        if other.refactor.imports.is_some() {
+            self.refactor.imports = other.refactor.imports;
+        }
+        if other.refactor.legacy_hidden_inference.is_some() {
+            self.refactor.legacy_hidden_inference = other.refactor.legacy_hidden_inference;
+        }
+        if other.scrap.retention_days.is_some() {
+            self.scrap.retention_days = other.scrap.retention_days;
+        }
+        if other.scrap.gitignore.is_some() {
+            self.scrap.gitignore = other.scrap.gitignore;
+        }
+        if other.scrap.compress_over_mb.is_some() {
+            self.scrap.compress_over_mb = other.scrap.compress_over_mb;
+        }
+        if other.scrap.tier_after_days.is_some() {
+            self.scrap.tier_after_days = other.scrap.tier_after_days;
+        }
+        if other.scrap.on_scrap.is_some() {
+            self.scrap.on_scrap = other.scrap.on_scrap;
+        }
+        if other.scrap.on_clean.is_some() {
+            self.scrap.on_clean = other.scrap.on_clean;
+        }
+        if other.scrap.on_purge.is_some() {
+            self.scrap.on_purge = other.scrap.on_purge;
+        }
+        self.version.extend(other.version);
+    }
+
+    pub fn refactor_excludes(&self) -> Vec<String> {
+        self.refactor.exclude.clone().unwrap_or_default()
+    }
+
+    pub fn refactor_handlers(&self) -> Vec<ContentHandler> {
+        self.refactor.handlers.iter().flatten().map(|(glob, command)| ContentHandler::new(glob, command)).collect()
+    }
+
+    /// The built-in import languages with the configured ones in their place or added
+    pub fn refactor_imports(&self) -> Vec<ImportLanguage> {
+        let mut languages = default_languages();
+        languages.extend(self.refactor.imports.clone().unwrap_or_default());
+        languages.into_values().collect()
+    }
+
+    pub fn refactor_legacy_hidden_inference(&self) -> bool {
+        self.refactor.legacy_hidden_inference.unwrap_or(false)
+    }
+
+    pub fn retention_days(&self) -> u32 {
+        self.scrap.retention_days.unwrap_or(DEFAULT_RETENTION_DAYS)
+    }
+
+    /// Size in bytes above which scrapped items are compressed, if any
+    pub fn scrap_compress_over(&self) -> Option<u64> {
+        self.scrap.compress_over_mb.map(|mb| mb * 1024 * 1024)
+    }
+
+    /// Age in days after which scrapped items are tiered into archives, if set
+    pub fn scrap_tier_after_days(&self) -> Option<u32> {
+        self.scrap.tier_after_days
+    }
+
+    /// The hook command run after `operation`, if one is set
+    pub fn scrap_hook(&self, operation: HistoryOperation) -> Option<&str> {
+        match operation {
+            HistoryOperation::Scrap => self.scrap.on_scrap.as_deref(),
+            HistoryOperation::Clean => self.scrap.on_clean.as_deref(),
+            HistoryOperation::Purge => self.scrap.on_purge.as_deref(),
+            HistoryOperation::Unscrap | HistoryOperation::Tier => None,
+        }
+    }
+}
+
+/// Version settings set by `WS_VERSION_<SETTING>` variables (e.g.
+/// `WS_VERSION_STRATEGY`), as `ws version config set` would take them
+pub fn version_env() -> Vec<(String, String)> {
+    crate::st8::st8_common::all_config_keys()
+        .into_iter()
+        .filter_map(|key| std::env::var(version_env_var(&key)).ok().map(|value| (key, value)))
+        .collect()
+}
+
+/// The environment variable overriding a version setting, e.g.
+/// `changelog.enabled` → `WS_VERSION_CHANGELOG_ENABLED`
+pub fn version_env_var(key: &str) -> String {
+    format!("WS_VERSION_{}", key.replace('.', "_").to_uppercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_file_overrides_user_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let user = temp_dir.path().join("config.toml");
+        fs::write(&user, "[refactor]\nexclude = [\"target/**\"]\n\n[refactor.handlers]\n\"*.ipynb\" = \"nb-handler\"\n\n[scrap]\nretention_days = 7\ngitignore = \"local\"\n").unwrap();
+        let project = temp_dir.path().join(PROJECT_FILE);
+        fs::write(&project, "[scrap]\nretention_days = 14\n\n[version]\nstrategy = \"conventional\"\n").unwrap();
+
+        let mut config = WsConfig::read(&user).unwrap();
+        config.merge(WsConfig::read(&project).unwrap());
+        assert_eq!(config.refactor_excludes(), vec!["target/**"]);
+        assert_eq!(config.refactor_handlers(), vec![ContentHandler::new("*.ipynb", "nb-handler")]);
+        assert_eq!(config.retention_days(), 14);
+        assert_eq!(config.scrap.gitignore, Some(GitignoreMode::Local));
+        assert_eq!(config.version["strategy"], "conventional");
+        assert_eq!(WsConfig::default().retention_days(), DEFAULT_RETENTION_DAYS);
+    }
+
+    #[test]
+    fn test_import_languages_extend_defaults() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join(PROJECT_FILE);
+        fs::write(&path, "[refactor.imports.python]\nextensions = [\"py\"]\nreferences = [\"import {module}\"]\n\n[refactor.imports.go]\nextensions = [\"go\"]\nreferences = [\"/{module}\\\"\"]\n").unwrap();
+
+        let languages = WsConfig::read(&path).unwrap().refactor_imports();
+        assert_eq!(languages.len(), default_languages().len() + 1);
+        assert!(languages.contains(&default_languages()["rust"]));
+        assert!(languages.iter().any(|language| language.extensions == ["py"] && language.references == ["import {module}"]));
+        assert!(languages.iter().any(|language| language.references == ["/{module}\""]));
+    }
+
+    #[test]
+    fn test_unknown_settings_are_rejected() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join(PROJECT_FILE);
+        fs::write(&path, "[scrap]\nkeep = 3\n").unwrap();
+        assert!(WsConfig::read(&path).is_err());
+        fs::write(&path, "[version]\ncolour = \"blue\"\n").unwrap();
+        assert!(WsConfig::read(&path).unwrap_err().to_string().contains("Unknown setting colour"));
+    }
+
+    #[test]
+    fn test_version_env_var() {
+        assert_eq!(version_env_var("strategy"), "WS_VERSION_STRATEGY");
+        assert_eq!(version_env_var("changelog.enabled"), "WS_VERSION_CHANGELOG_ENABLED");
+    }
+}
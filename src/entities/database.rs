@@ -52,9 +52,31 @@ pub async fn initialize_tables(pool: &SqlitePool) -> Result<()> {
             major_version INTEGER NOT NULL DEFAULT 0,
             
             -- St8 Configuration fields (migrated from .st8.json)
+            version_updates_enabled BOOLEAN NOT NULL DEFAULT TRUE,
             version_file TEXT NOT NULL DEFAULT 'version.txt',
             auto_detect_project_files BOOLEAN NOT NULL DEFAULT TRUE,
             project_files TEXT, -- JSON array of manual project files
+            version_strategy TEXT NOT NULL DEFAULT 'count',
+            version_source TEXT DEFAULT 'tag', -- tag, cargo or file
+            semver_strict BOOLEAN DEFAULT FALSE,
+            changelog TEXT, -- JSON changelog settings
+            packages TEXT, -- JSON array of monorepo packages
+            version_format TEXT NOT NULL DEFAULT '{major}.{minor}.{patch}',
+            prerelease TEXT, -- label appended as -<label>.N
+            build_metadata TEXT, -- template appended as +<metadata>
+            version_branches TEXT, -- JSON array of branch patterns to bump on
+            version_ignore_paths TEXT, -- JSON array of path patterns that do not bump
+            tag_prefix TEXT DEFAULT 'v', -- put in front of the version in release tags
+            tag_pattern TEXT, -- glob of release tag names, <tag_prefix>* when NULL
+            commit_mode TEXT DEFAULT 'include', -- include or separate
+            commit_message TEXT, -- template of the separate version commit's message
+            skip_ci BOOLEAN NOT NULL DEFAULT FALSE, -- append [skip ci] to it
+            annotate_commit_msg BOOLEAN NOT NULL DEFAULT FALSE, -- add (version: X.Y.Z) to commit messages
+            max_hook_ms INTEGER, -- time budget of the version hook
+            version_channels TEXT, -- JSON array of prerelease channels
+            npm_workspace_mode TEXT DEFAULT 'root-only', -- root-only, all or independent
+            version_logging TEXT, -- JSON log file settings
+            downgrade_policy TEXT DEFAULT 'deny', -- allow, warn or deny lower versions
             
             created_at TEXT NOT NULL DEFAULT (datetime('now')),
             updated_at TEXT NOT NULL DEFAULT (datetime('now')),
@@ -553,6 +575,25 @@ pub async fn initialize_tables(pool: &SqlitePool) -> Result<()> {
     .execute(pool)
     .await?;
 
+    // Version history table - journal of version file changes, for rollback
+    sqlx::query(r#"
+        CREATE TABLE IF NOT EXISTS version_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            old_version TEXT,
+            new_version TEXT NOT NULL,
+            commit_id TEXT,
+            files TEXT NOT NULL DEFAULT '[]', -- JSON array of files written
+            source TEXT NOT NULL,
+            rolled_back BOOLEAN NOT NULL DEFAULT FALSE,
+            recorded_at TEXT NOT NULL DEFAULT (datetime('now')),
+            
+            -- Check constraints for data integrity
+            CONSTRAINT chk_version_history_source CHECK (source IN ('update', 'bump', 'rollback'))
+        )
+    "#)
+    .execute(pool)
+    .await?;
+
     // Create indexes for performance
     create_indexes(pool).await?;
 
@@ -738,6 +779,46 @@ pub async fn ensure_current_schema(pool: &SqlitePool) -> Result<()> {
         set_schema_version(pool, target_version).await?;
     }
     
+    // Columns added after the initial schema, missing from databases created before them
+    let added_columns = [
+        ("projects", "version_strategy", "TEXT NOT NULL DEFAULT 'count'"),
+        ("projects", "changelog", "TEXT"),
+        ("projects", "packages", "TEXT"),
+        ("projects", "version_format", "TEXT NOT NULL DEFAULT '{major}.{minor}.{patch}'"),
+        ("projects", "prerelease", "TEXT"),
+        ("projects", "build_metadata", "TEXT"),
+        ("projects", "version_updates_enabled", "BOOLEAN NOT NULL DEFAULT TRUE"),
+        ("projects", "version_branches", "TEXT"),
+        ("projects", "version_ignore_paths", "TEXT"),
+        ("projects", "tag_prefix", "TEXT DEFAULT 'v'"),
+        ("projects", "tag_pattern", "TEXT"),
+        ("projects", "commit_mode", "TEXT DEFAULT 'include'"),
+        ("projects", "commit_message", "TEXT"),
+        ("projects", "skip_ci", "BOOLEAN NOT NULL DEFAULT FALSE"),
+        ("projects", "version_channels", "TEXT"),
+        ("projects", "version_logging", "TEXT"),
+        ("projects", "max_hook_ms", "INTEGER"),
+        ("projects", "npm_workspace_mode", "TEXT DEFAULT 'root-only'"),
+        ("projects", "annotate_commit_msg", "BOOLEAN NOT NULL DEFAULT FALSE"),
+        ("projects", "version_source", "TEXT DEFAULT 'tag'"),
+        ("projects", "semver_strict", "BOOLEAN DEFAULT FALSE"),
+        ("projects", "downgrade_policy", "TEXT DEFAULT 'deny'"),
+    ];
+    for (table, column, definition) in added_columns {
+        let exists = sqlx::query_scalar::<_, i32>(
+            "SELECT COUNT(*) FROM pragma_table_info(?) WHERE name = ?"
+        )
+        .bind(table)
+        .bind(column)
+        .fetch_one(pool)
+        .await? > 0;
+        if !exists {
+            sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+                .execute(pool)
+                .await?;
+        }
+    }
+    
     Ok(())
 }
 
@@ -0,0 +1,149 @@
+//! Exclusive advisory locks on lock files, shared by the scrap metadata, the
+//! version writer and `ws refactor`. The lock is taken with the operating
+//! system (`flock` on Unix, `LockFileEx` on Windows), which releases it when
+//! the holder exits, crashed or not, so there is no stale lock to detect or
+//! steal and nothing to refresh while a long operation runs.
+//!
+//! The lock file itself stays in place after release: deleting it would let
+//! a waiting process lock the unlinked file while a newcomer creates and
+//! locks a fresh one. While held it names the holder's process id.
+
+use anyhow::{Context, Result};
+use std::fs::{self, File, TryLockError};
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// An exclusive lock on a lock file, released on drop
+#[derive(Debug)]
+pub struct FileLock {
+    file: File,
+}
+
+impl FileLock {
+    /// Take the lock at `path` if no other process holds it
+    pub fn try_acquire(path: &Path) -> Result<Option<Self>> {
+        let file = open(path)?;
+        match file.try_lock() {
+            Ok(()) => Self::held(file, path).map(Some),
+            Err(TryLockError::WouldBlock) => Ok(None),
+            Err(TryLockError::Error(e)) => Err(e).with_context(|| format!("Failed to lock {}", path.display())),
+        }
+    }
+
+    /// Take the lock at `path`, blocking for as long as another process holds
+    /// it; `on_wait` is called with the holder's process id before blocking
+    pub fn acquire(path: &Path, on_wait: impl FnOnce(&str)) -> Result<Self> {
+        if let Some(lock) = Self::try_acquire(path)? {
+            return Ok(lock);
+        }
+        on_wait(&holder(path));
+        let file = open(path)?;
+        file.lock().with_context(|| format!("Failed to lock {}", path.display()))?;
+        Self::held(file, path)
+    }
+
+    /// Like `acquire`, giving up with `None` once `timeout` has passed
+    pub fn acquire_timeout(path: &Path, timeout: Duration, on_wait: impl FnOnce(&str)) -> Result<Option<Self>> {
+        let started = Instant::now();
+        let mut on_wait = Some(on_wait);
+        loop {
+            if let Some(lock) = Self::try_acquire(path)? {
+                return Ok(Some(lock));
+            }
+            if started.elapsed() > timeout {
+                return Ok(None);
+            }
+            if let Some(on_wait) = on_wait.take() {
+                on_wait(&holder(path));
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    /// Record this process as the holder of the freshly locked `file`
+    fn held(mut file: File, path: &Path) -> Result<Self> {
+        file.set_len(0)
+            .and_then(|_| write!(file, "{}", std::process::id()))
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(Self { file })
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = self.file.set_len(0);
+        let _ = self.file.unlock();
+    }
+}
+
+/// Process id of the holder of the lock at `path`, empty when unknown
+/// (Windows does not let other processes read a locked file)
+pub fn holder(path: &Path) -> String {
+    fs::read_to_string(path).unwrap_or_default().trim().to_string()
+}
+
+/// Open (creating) the lock file without truncating it, which would wipe
+/// the process id of a current holder
+fn open(path: &Path) -> Result<File> {
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .with_context(|| format!("Failed to open lock file {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_is_exclusive_until_dropped() -> Result<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let path = temp_dir.path().join("test.lock");
+        let lock = FileLock::try_acquire(&path)?.expect("the lock is free");
+        assert_eq!(holder(&path), std::process::id().to_string());
+
+        assert!(FileLock::try_acquire(&path)?.is_none());
+        let mut waited_for = None;
+        let timed_out = FileLock::acquire_timeout(&path, Duration::from_millis(100), |pid| waited_for = Some(pid.to_string()))?;
+        assert!(timed_out.is_none());
+        assert_eq!(waited_for, Some(std::process::id().to_string()));
+
+        drop(lock);
+        assert!(path.exists());
+        assert!(FileLock::try_acquire(&path)?.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_acquire_blocks_until_released() -> Result<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let path = temp_dir.path().join("test.lock");
+        let lock = FileLock::try_acquire(&path)?.expect("the lock is free");
+        let release = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(300));
+            drop(lock);
+        });
+
+        let started = Instant::now();
+        let _lock = FileLock::acquire(&path, |_| {})?;
+        assert!(started.elapsed() >= Duration::from_millis(250));
+        release.join().unwrap();
+        Ok(())
+    }
+
+    #[test]
+    fn test_lock_left_by_a_dead_process_is_free() -> Result<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let path = temp_dir.path().join("test.lock");
+        fs::write(&path, "999999")?;
+        assert!(FileLock::try_acquire(&path)?.is_some());
+        Ok(())
+    }
+}
@@ -1,8 +1,12 @@
+pub mod config;
 pub mod refac;
 pub mod scrap;
 pub mod st8;
 pub mod ldiff;
 pub mod logging;
+pub mod file_lock;
+pub mod prompt;
+pub mod trace;
 pub mod workspace_state;
 // Entity system
 pub mod entities;
@@ -26,6 +30,7 @@ pub use refac::rename_engine::RenameEngine;
 
 // Re-export from scrap module
 pub use scrap::scrap_common::{ScrapMetadata, ScrapEntry};
+pub use scrap::ScrapFolder;
 pub use scrap::{run_scrap, run_unscrap};
 
 // Re-export from ldiff module
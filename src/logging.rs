@@ -1,4 +1,4 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use log::LevelFilter;
 use log4rs::{
     append::{
@@ -16,14 +16,133 @@ use log4rs::{
     encode::pattern::PatternEncoder,
     filter::threshold::ThresholdFilter,
 };
+use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 
+/// Where the log file is written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLocation {
+    /// `.ws/logs` in the working tree
+    #[default]
+    Workspace,
+    /// `ws/logs` in the git directory, where it never shows up as untracked
+    Git,
+}
+
+impl std::str::FromStr for LogLocation {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "workspace" => Ok(LogLocation::Workspace),
+            "git" => Ok(LogLocation::Git),
+            _ => anyhow::bail!("Unknown log location: {} (expected workspace or git)", s),
+        }
+    }
+}
+
+impl std::fmt::Display for LogLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogLocation::Workspace => write!(f, "workspace"),
+            LogLocation::Git => write!(f, "git"),
+        }
+    }
+}
+
+/// Log file settings, stored with the project's version settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoggingConfig {
+    /// Least severe level written to the log file for ws itself
+    #[serde(default = "default_level")]
+    pub level: String,
+    /// Size in megabytes at which the log file is rotated
+    #[serde(default = "default_max_size_mb")]
+    pub max_size_mb: u64,
+    /// Rotated log files kept besides the current one
+    #[serde(default = "default_max_files")]
+    pub max_files: u32,
+    #[serde(default)]
+    pub location: LogLocation,
+}
+
+fn default_level() -> String {
+    "debug".to_string()
+}
+
+fn default_max_size_mb() -> u64 {
+    10
+}
+
+fn default_max_files() -> u32 {
+    10
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: default_level(),
+            max_size_mb: default_max_size_mb(),
+            max_files: default_max_files(),
+            location: LogLocation::default(),
+        }
+    }
+}
+
+impl LoggingConfig {
+    pub const KEYS: [&'static str; 4] = ["level", "max_size_mb", "max_files", "location"];
+
+    /// The value of one setting, as `show` prints it
+    pub fn get(&self, key: &str) -> Result<String> {
+        let value = match key {
+            "level" => self.level.clone(),
+            "max_size_mb" => self.max_size_mb.to_string(),
+            "max_files" => self.max_files.to_string(),
+            "location" => self.location.to_string(),
+            other => anyhow::bail!("Unknown logging config key: {}", other),
+        };
+        Ok(value)
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        match key {
+            "level" => {
+                value.parse::<LevelFilter>()
+                    .map_err(|_| anyhow::anyhow!("Unknown log level: {} (expected off, error, warn, info, debug or trace)", value))?;
+                self.level = value.to_lowercase();
+            }
+            "max_size_mb" => self.max_size_mb = value.parse().ok().filter(|size| *size > 0)
+                .with_context(|| format!("Expected a size in megabytes above 0, got {}", value))?,
+            "max_files" => self.max_files = value.parse().ok().filter(|files| *files > 0)
+                .with_context(|| format!("Expected a number of files above 0, got {}", value))?,
+            "location" => self.location = value.parse()?,
+            other => anyhow::bail!("Unknown logging config key: {}", other),
+        }
+        Ok(())
+    }
+
+    fn level_filter(&self) -> LevelFilter {
+        self.level.parse().unwrap_or(LevelFilter::Debug)
+    }
+}
+
+/// The directory holding `ws.log` and its rotated copies
+pub fn log_dir(workspace_root: &Path, location: LogLocation) -> Result<PathBuf> {
+    match location {
+        LogLocation::Workspace => Ok(workspace_root.join(".ws").join("logs")),
+        LogLocation::Git => {
+            let repo = crate::st8::GitRepo::discover_from(workspace_root)?;
+            Ok(repo.git_dir()?.join("ws").join("logs"))
+        }
+    }
+}
+
 /// Initialize the logging system with rotation and archiving
-pub fn init_logging(workspace_root: &Path, debug_mode: bool) -> Result<()> {
-    let ws_dir = workspace_root.join(".ws");
-    std::fs::create_dir_all(&ws_dir)?;
-    
-    let log_dir = ws_dir.join("logs");
+pub fn init_logging(workspace_root: &Path, debug_mode: bool, settings: &LoggingConfig) -> Result<()> {
+    // Outside a git repository there is no git directory to log to
+    let log_dir = log_dir(workspace_root, settings.location)
+        .or_else(|_| log_dir(workspace_root, LogLocation::Workspace))?;
     std::fs::create_dir_all(&log_dir)?;
     
     let log_file = log_dir.join("ws.log");
@@ -48,16 +167,19 @@ pub fn init_logging(workspace_root: &Path, debug_mode: bool) -> Result<()> {
     let file_appender = RollingFileAppender::builder()
         .encoder(Box::new(PatternEncoder::new(log_pattern)))
         .build(
-            log_file,
+            &log_file,
             Box::new(CompoundPolicy::new(
-                Box::new(SizeTrigger::new(10 * 1024 * 1024)), // 10MB per file
+                Box::new(SizeTrigger::new(settings.max_size_mb * 1024 * 1024)),
                 Box::new(
                     FixedWindowRoller::builder()
-                        .build(&archive_pattern.to_string_lossy(), 10)? // Keep 10 archived files
+                        .build(&archive_pattern.to_string_lossy(), settings.max_files)?
                 ),
             )),
         )?;
     
+    // ws itself logs at the configured level, other crates at info at most
+    let level = settings.level_filter();
+    
     // Build configuration
     let config = Config::builder()
         .appender(
@@ -73,7 +195,11 @@ pub fn init_logging(workspace_root: &Path, debug_mode: bool) -> Result<()> {
             Logger::builder()
                 .appender("file")
                 .appender("console")
-                .build("workspace", LevelFilter::Debug),
+                .build("workspace", level.max(console_level)),
+        )
+        .logger(
+            Logger::builder()
+                .build("ws", level.max(console_level)),
         )
         .logger(
             Logger::builder()
@@ -84,12 +210,12 @@ pub fn init_logging(workspace_root: &Path, debug_mode: bool) -> Result<()> {
             Root::builder()
                 .appender("file")
                 .appender("console")
-                .build(LevelFilter::Info),
+                .build(level.min(LevelFilter::Info)),
         )?;
     
     log4rs::init_config(config)?;
     
-    log::info!("Logging initialized: {}", log_dir.join("ws.log").display());
+    log::info!("Logging initialized: {}", log_file.display());
     log::debug!("Debug logging enabled, console level: {:?}", console_level);
     
     Ok(())
@@ -131,10 +257,20 @@ pub fn detect_workspace_root() -> Option<PathBuf> {
     }
 }
 
+/// The project's logging settings, or the defaults before it has any
+fn load_settings(workspace_root: &Path) -> LoggingConfig {
+    if !workspace_root.join(".ws/project.db").exists() {
+        return LoggingConfig::default();
+    }
+    crate::st8::St8Config::load(workspace_root)
+        .map(|config| config.logging)
+        .unwrap_or_default()
+}
+
 /// Initialize logging with automatic workspace detection
 pub fn setup_logging(debug_mode: bool) -> Result<()> {
     if let Some(workspace_root) = detect_workspace_root() {
-        init_logging(&workspace_root, debug_mode)
+        init_logging(&workspace_root, debug_mode, &load_settings(&workspace_root))
     } else {
         init_simple_logging(debug_mode)
     }
@@ -0,0 +1,60 @@
+//! Yes/no questions that never hang. `--assume-yes` and `--assume-no` answer
+//! every question up front; without them, a question asked when stdin is not
+//! a terminal (CI, git hooks, pipes) gets its default answer and says so.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::sync::OnceLock;
+
+static ASSUMED_ANSWER: OnceLock<bool> = OnceLock::new();
+
+/// Answer every later question with `answer` (`--assume-yes`/`--assume-no`);
+/// only the first call counts
+pub fn set_assumed_answer(answer: bool) {
+    let _ = ASSUMED_ANSWER.set(answer);
+}
+
+/// The answer given up front, if any
+pub fn assumed_answer() -> Option<bool> {
+    ASSUMED_ANSWER.get().copied()
+}
+
+/// Whether questions are put to the user: no answer was given up front and
+/// stdin is a terminal
+pub fn is_interactive() -> bool {
+    assumed_answer().is_none() && atty::is(atty::Stream::Stdin)
+}
+
+/// Ask `question`, answering `default` when nobody can be asked
+pub fn confirm(question: &str, default: bool) -> Result<bool> {
+    if let Some(answer) = assumed_answer() {
+        log::debug!("Assumed {} to: {}", if answer { "yes" } else { "no" }, question);
+        return Ok(answer);
+    }
+    if !atty::is(atty::Stream::Stdin) {
+        let (answer, flag) = if default { ("yes", "--assume-no") } else { ("no", "--assume-yes") };
+        eprintln!("{}: {} No terminal to ask, answering {} (pass {} to change this)", "Info".blue(), question, answer, flag);
+        return Ok(default);
+    }
+    dialoguer::Confirm::new()
+        .with_prompt(question)
+        .default(default)
+        .interact()
+        .context("Failed to get user confirmation")
+}
+
+/// Ask the user to pick one of `items`, answering `default` when nobody can
+/// be asked (including under `--assume-yes`/`--assume-no`)
+pub fn select<T: ToString>(question: &str, items: &[T], default: usize) -> Result<usize> {
+    if !is_interactive() {
+        let answer = items.get(default).map(ToString::to_string).unwrap_or_default();
+        eprintln!("{}: {} No terminal to ask, choosing {}", "Info".blue(), question, answer);
+        return Ok(default);
+    }
+    dialoguer::Select::new()
+        .with_prompt(question)
+        .items(items)
+        .default(default)
+        .interact()
+        .context("Failed to get user selection")
+}
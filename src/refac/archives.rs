@@ -0,0 +1,313 @@
+//! `--into-archives`: renames and content replacement on the members of zip,
+//! tar, tar.gz and gz files found during discovery. The archive is unpacked
+//! in memory and repacked in place. Members whose content does not change
+//! keep their compressed bytes (zip) or headers (tar).
+//!
+//! Archives larger than the size limit, or unpacking to more than it, are
+//! skipped. Archives nested in archives are left alone.
+
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use std::fmt;
+use std::io::{Cursor, Read, Write};
+use std::path::Path;
+
+/// Default for `--archive-size-limit`, in MiB
+pub const DEFAULT_SIZE_LIMIT_MIB: u64 = 64;
+
+/// What `--into-archives` changes in archive members
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ArchiveOptions {
+    /// In bytes, for the archive and for its unpacked members
+    pub size_limit: u64,
+    pub rename_members: bool,
+    pub replace_content: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+    /// A single gzip-compressed file
+    Gzip,
+}
+
+impl ArchiveKind {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_lowercase();
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ArchiveKind::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(ArchiveKind::Tar)
+        } else if name.ends_with(".zip") {
+            Some(ArchiveKind::Zip)
+        } else if name.ends_with(".gz") {
+            Some(ArchiveKind::Gzip)
+        } else {
+            None
+        }
+    }
+}
+
+/// What to change in one member
+#[derive(Debug, Default)]
+pub struct MemberEdit {
+    pub new_name: Option<String>,
+    /// The new content and the number of occurrences replaced
+    pub content: Option<(Vec<u8>, usize)>,
+}
+
+/// Decides the edit of a member from its name and content (None for
+/// directories and links)
+pub type Rewrite<'a> = dyn Fn(&str, Option<&[u8]>) -> MemberEdit + 'a;
+
+/// A member changed by the replacement
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemberChange {
+    pub name: String,
+    pub new_name: Option<String>,
+    /// Content occurrences replaced
+    pub occurrences: usize,
+}
+
+impl fmt::Display for MemberChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if let Some(new_name) = &self.new_name {
+            write!(f, " → {}", new_name)?;
+        }
+        if self.occurrences > 0 {
+            write!(f, " ({} occurrence(s))", self.occurrences)?;
+        }
+        Ok(())
+    }
+}
+
+/// The changes `rewrite` would make to the members of the archive at `path`
+pub fn plan(path: &Path, size_limit: u64, rewrite: &Rewrite) -> Result<Vec<MemberChange>> {
+    Ok(process(path, size_limit, rewrite, false)?.1)
+}
+
+/// The archive at `path` repacked with the changes of `rewrite`, or None
+/// when nothing changes
+pub fn repack(path: &Path, size_limit: u64, rewrite: &Rewrite) -> Result<Option<(Vec<u8>, Vec<MemberChange>)>> {
+    let (bytes, changes) = process(path, size_limit, rewrite, true)?;
+    Ok(bytes.filter(|_| !changes.is_empty()).map(|bytes| (bytes, changes)))
+}
+
+/// Error of an archive over the size limit, packed or unpacked
+#[derive(Debug)]
+pub struct TooLarge(String);
+
+impl fmt::Display for TooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for TooLarge {}
+
+/// Bytes left to unpack before the size limit
+struct Budget {
+    left: u64,
+    limit: u64,
+}
+
+impl Budget {
+    fn read(&mut self, reader: impl Read) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        reader.take(self.left + 1).read_to_end(&mut data)?;
+        if data.len() as u64 > self.left {
+            anyhow::bail!(TooLarge(format!("Unpacks to more than the archive size limit of {} MiB", self.limit / (1024 * 1024))));
+        }
+        self.left -= data.len() as u64;
+        Ok(data)
+    }
+}
+
+fn process(path: &Path, size_limit: u64, rewrite: &Rewrite, repack: bool) -> Result<(Option<Vec<u8>>, Vec<MemberChange>)> {
+    let kind = ArchiveKind::from_path(path).with_context(|| format!("Not a supported archive: {}", path.display()))?;
+    let data = std::fs::read(path).with_context(|| format!("Failed to read archive: {}", path.display()))?;
+    if data.len() as u64 > size_limit {
+        anyhow::bail!(TooLarge(format!("Larger than the archive size limit of {} MiB", size_limit / (1024 * 1024))));
+    }
+    let mut budget = Budget { left: size_limit, limit: size_limit };
+
+    match kind {
+        ArchiveKind::Zip => process_zip(&data, &mut budget, rewrite, repack),
+        ArchiveKind::Tar => process_tar(&data, &mut budget, rewrite, repack),
+        ArchiveKind::TarGz => {
+            let tar = budget.read(GzDecoder::new(data.as_slice()))?;
+            // The members are within the unpacked tar, which is within the limit
+            let mut members = Budget { left: size_limit, limit: size_limit };
+            let (bytes, changes) = process_tar(&tar, &mut members, rewrite, repack)?;
+            Ok((bytes.map(|bytes| gzip(&bytes)).transpose()?, changes))
+        }
+        ArchiveKind::Gzip => {
+            let content = budget.read(GzDecoder::new(data.as_slice()))?;
+            // The member is named after the archive, which is renamed like any file
+            let name = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+            let Some((new_content, occurrences)) = rewrite(&name, Some(&content)).content else {
+                return Ok((None, Vec::new()));
+            };
+            let bytes = if repack { Some(gzip(&new_content)?) } else { None };
+            Ok((bytes, vec![MemberChange { name, new_name: None, occurrences }]))
+        }
+    }
+}
+
+fn process_zip(data: &[u8], budget: &mut Budget, rewrite: &Rewrite, repack: bool) -> Result<(Option<Vec<u8>>, Vec<MemberChange>)> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data)).context("Not a valid zip archive")?;
+    let mut writer = repack.then(|| zip::ZipWriter::new(Cursor::new(Vec::new())));
+    let mut changes = Vec::new();
+
+    for index in 0..archive.len() {
+        let (name, content, options) = {
+            let mut file = archive.by_index(index)?;
+            let mut options = zip::write::FileOptions::default()
+                .compression_method(file.compression())
+                .last_modified_time(file.last_modified());
+            if let Some(mode) = file.unix_mode() {
+                options = options.unix_permissions(mode);
+            }
+            let content = if file.is_dir() { None } else { Some(budget.read(&mut file)?) };
+            (file.name().to_string(), content, options)
+        };
+        let edit = rewrite(&name, content.as_deref());
+
+        if let Some(writer) = writer.as_mut() {
+            // Unchanged content is copied still compressed
+            match (&edit.content, &edit.new_name) {
+                (None, None) => writer.raw_copy_file(archive.by_index_raw(index)?)?,
+                (None, Some(new_name)) => writer.raw_copy_file_rename(archive.by_index_raw(index)?, new_name)?,
+                (Some((new_content, _)), new_name) => {
+                    writer.start_file(new_name.as_deref().unwrap_or(&name), options)?;
+                    writer.write_all(new_content)?;
+                }
+            }
+        }
+        record_change(&mut changes, name, edit);
+    }
+
+    let bytes = writer.map(|mut writer| writer.finish().map(Cursor::into_inner)).transpose()?;
+    Ok((bytes, changes))
+}
+
+fn process_tar(data: &[u8], budget: &mut Budget, rewrite: &Rewrite, repack: bool) -> Result<(Option<Vec<u8>>, Vec<MemberChange>)> {
+    let mut archive = tar::Archive::new(data);
+    let mut builder = repack.then(|| tar::Builder::new(Vec::new()));
+    let mut changes = Vec::new();
+
+    for entry in archive.entries().context("Not a valid tar archive")? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().into_owned();
+        let content = if entry.header().entry_type().is_file() { Some(budget.read(&mut entry)?) } else { None };
+        let edit = rewrite(&name, content.as_deref());
+
+        if let Some(builder) = builder.as_mut() {
+            // The original header keeps the mode, owner and times
+            let mut header = entry.header().clone();
+            let data = match &edit.content {
+                Some((new_content, _)) => new_content.as_slice(),
+                None => content.as_deref().unwrap_or_default(),
+            };
+            header.set_size(data.len() as u64);
+            builder.append_data(&mut header, edit.new_name.as_deref().unwrap_or(&name), data)?;
+        }
+        record_change(&mut changes, name, edit);
+    }
+
+    let bytes = builder.map(|builder| builder.into_inner()).transpose()?;
+    Ok((bytes, changes))
+}
+
+fn record_change(changes: &mut Vec<MemberChange>, name: String, edit: MemberEdit) {
+    if edit.new_name.is_some() || edit.content.is_some() {
+        let occurrences = edit.content.map_or(0, |(_, occurrences)| occurrences);
+        changes.push(MemberChange { name, new_name: edit.new_name, occurrences });
+    }
+}
+
+fn gzip(data: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn replace_old(name: &str, content: Option<&[u8]>) -> MemberEdit {
+        let new_name = name.contains("old").then(|| name.replace("old", "new"));
+        let content = content.and_then(|content| {
+            let text = std::str::from_utf8(content).ok()?;
+            let count = text.matches("old").count();
+            (count > 0).then(|| (text.replace("old", "new").into_bytes(), count))
+        });
+        MemberEdit { new_name, content }
+    }
+
+    fn tar_members(data: &[u8]) -> Vec<(String, String)> {
+        let mut archive = tar::Archive::new(data);
+        archive
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                let mut entry = entry.unwrap();
+                let mut content = String::new();
+                entry.read_to_string(&mut content).unwrap();
+                (entry.path().unwrap().to_string_lossy().into_owned(), content)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_archive_kinds() {
+        assert_eq!(ArchiveKind::from_path(Path::new("a.tar.gz")), Some(ArchiveKind::TarGz));
+        assert_eq!(ArchiveKind::from_path(Path::new("a.TGZ")), Some(ArchiveKind::TarGz));
+        assert_eq!(ArchiveKind::from_path(Path::new("a.tar")), Some(ArchiveKind::Tar));
+        assert_eq!(ArchiveKind::from_path(Path::new("a.zip")), Some(ArchiveKind::Zip));
+        assert_eq!(ArchiveKind::from_path(Path::new("a.log.gz")), Some(ArchiveKind::Gzip));
+        assert_eq!(ArchiveKind::from_path(Path::new("a.txt")), None);
+    }
+
+    #[test]
+    fn test_repack_tar() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("bundle.tar");
+        let mut builder = tar::Builder::new(Vec::new());
+        for (name, content) in [("old.txt", "old text"), ("keep.txt", "unchanged")] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            builder.append_data(&mut header, name, content.as_bytes())?;
+        }
+        std::fs::write(&path, builder.into_inner()?)?;
+
+        let changes = plan(&path, 1024 * 1024, &replace_old)?;
+        assert_eq!(changes, vec![MemberChange { name: "old.txt".into(), new_name: Some("new.txt".into()), occurrences: 1 }]);
+        assert_eq!(changes[0].to_string(), "old.txt → new.txt (1 occurrence(s))");
+
+        let (bytes, _) = repack(&path, 1024 * 1024, &replace_old)?.unwrap();
+        assert_eq!(
+            tar_members(&bytes),
+            vec![("new.txt".to_string(), "new text".to_string()), ("keep.txt".to_string(), "unchanged".to_string())]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_size_limit() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("notes.gz");
+        // Compresses to far less than it unpacks to
+        std::fs::write(&path, gzip(&vec![b'o'; 4096])?)?;
+        assert!(plan(&path, 1024, &replace_old).is_err());
+        assert!(plan(&path, 8192, &replace_old)?.is_empty());
+        Ok(())
+    }
+}
@@ -1,8 +1,23 @@
 use anyhow::{Context, Result};
 use content_inspector::{ContentType, inspect};
+use serde::Serialize;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufReader, Read};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Size and modification time a cached verdict was made for
+type Fingerprint = (u64, Option<SystemTime>);
+
+/// How often the detector's cache saved reading a file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct DetectorStats {
+    pub lookups: u64,
+    pub cache_hits: u64,
+}
 
 /// Detects if a file is binary or text
 pub struct BinaryDetector {
@@ -10,14 +25,18 @@ pub struct BinaryDetector {
     max_sample_size: usize,
     /// Threshold for binary detection (percentage of non-printable characters)
     binary_threshold: f64,
+    /// Verdicts by path, reused while the file's size and modification time are unchanged
+    cache: Mutex<HashMap<PathBuf, (Fingerprint, bool)>>,
+    lookups: AtomicU64,
+    cache_hits: AtomicU64,
 }
 
 impl Default for BinaryDetector {
     fn default() -> Self {
-        Self {
-            max_sample_size: 8192, // 8KB sample
-            binary_threshold: 0.3,  // 30% non-printable = binary
-        }
+        Self::new(
+            8192, // 8KB sample
+            0.3,  // 30% non-printable = binary
+        )
     }
 }
 
@@ -26,13 +45,43 @@ impl BinaryDetector {
         Self {
             max_sample_size,
             binary_threshold,
+            cache: Mutex::new(HashMap::new()),
+            lookups: AtomicU64::new(0),
+            cache_hits: AtomicU64::new(0),
         }
     }
 
-    /// Check if a file is binary using multiple detection methods with extension fail-safe
+    pub fn stats(&self) -> DetectorStats {
+        DetectorStats {
+            lookups: self.lookups.load(Ordering::Relaxed),
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Check if a file is binary, reusing an earlier verdict for the unchanged file
     pub fn is_binary<P: AsRef<Path>>(&self, path: P) -> Result<bool> {
         let path = path.as_ref();
-        
+        self.lookups.fetch_add(1, Ordering::Relaxed);
+
+        let fingerprint = std::fs::metadata(path).ok().map(|metadata| (metadata.len(), metadata.modified().ok()));
+        if let Some(fingerprint) = fingerprint {
+            if let Some(&(cached, is_binary)) = self.cache.lock().unwrap().get(path) {
+                if cached == fingerprint {
+                    self.cache_hits.fetch_add(1, Ordering::Relaxed);
+                    return Ok(is_binary);
+                }
+            }
+        }
+
+        let is_binary = self.detect_binary(path)?;
+        if let Some(fingerprint) = fingerprint {
+            self.cache.lock().unwrap().insert(path.to_path_buf(), (fingerprint, is_binary));
+        }
+        Ok(is_binary)
+    }
+
+    /// Check if a file is binary using multiple detection methods with extension fail-safe
+    fn detect_binary(&self, path: &Path) -> Result<bool> {
         // First, check file extension for known binary types (fail-safe for important files)
         if self.is_binary_by_extension(path) {
             return Ok(true);
@@ -485,6 +534,24 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_verdict_cache() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let detector = BinaryDetector::default();
+        let file_path = temp_dir.path().join("data.txt");
+        std::fs::write(&file_path, b"plain text\n")?;
+
+        assert!(!detector.is_binary(&file_path)?);
+        assert!(!detector.is_binary(&file_path)?);
+        assert_eq!(detector.stats(), DetectorStats { lookups: 2, cache_hits: 1 });
+
+        // A changed file is inspected again
+        std::fs::write(&file_path, b"\x00\x01\x02\x03 binary now\x00\x00")?;
+        assert!(detector.is_binary(&file_path)?);
+        assert_eq!(detector.stats().cache_hits, 1);
+        Ok(())
+    }
+
     #[test] 
     fn test_binary_file_detection() -> Result<()> {
         let temp_dir = TempDir::new()?;
@@ -1,21 +1,23 @@
-use clap::{Parser, ValueEnum};
-use std::path::PathBuf;
+use clap::{Parser, Subcommand, ValueEnum};
+use std::path::{Path, PathBuf};
+use crate::scrap::scrap_common::absolute_path;
 
 #[derive(Parser, Debug, Clone)]
 #[command(name = "refac")]
 #[command(version = crate::get_version(), about = "A robust cross-platform tool for recursive string replacement in file/folder names and contents - part of the workspace tool suite")]
 #[command(long_about = None)]
+#[command(subcommand_negates_reqs = true)]
 pub struct Args {
     /// Root directory to search in
-    #[arg(value_name = "ROOT_DIR")]
+    #[arg(value_name = "ROOT_DIR", required_unless_present = "serve", default_value = ".", hide_default_value = true)]
     pub root_dir: PathBuf,
 
     /// Pattern to find and replace
-    #[arg(value_name = "PATTERN")]
+    #[arg(value_name = "PATTERN", required_unless_present = "serve", default_value = "", hide_default_value = true)]
     pub pattern: String,
 
     /// Replacement text
-    #[arg(value_name = "SUBSTITUTE")]
+    #[arg(value_name = "SUBSTITUTE", required_unless_present = "serve", default_value = "", hide_default_value = true)]
     pub substitute: String,
 
 
@@ -31,6 +33,10 @@ pub struct Args {
     #[arg(long = "follow-symlinks")]
     pub follow_symlinks: bool,
 
+    /// With --follow-symlinks, follow at most N symbolic links in a row
+    #[arg(long = "max-symlink-depth", value_name = "N", default_value_t = 8)]
+    pub max_symlink_depth: usize,
+
     /// Create backup files before modifying content
     #[arg(short = 'b', long = "backup")]
     pub backup: bool,
@@ -55,6 +61,10 @@ pub struct Args {
     #[arg(long = "max-depth", default_value = "0")]
     pub max_depth: usize,
 
+    /// Also rename the root directory itself, after everything in it
+    #[arg(long = "include-root")]
+    pub include_root: bool,
+
     /// Exclude files matching these patterns (glob patterns)
     #[arg(long = "exclude", value_name = "PATTERN")]
     pub exclude_patterns: Vec<String>,
@@ -63,6 +73,10 @@ pub struct Args {
     #[arg(long = "include", value_name = "PATTERN")]
     pub include_patterns: Vec<String>,
 
+    /// Leave files alone, names included, whose content matches this regex (e.g. `DO NOT RENAME`)
+    #[arg(long = "exclude-containing", value_name = "REGEX")]
+    pub exclude_containing: Option<String>,
+
     /// Output format
     #[arg(long = "format", default_value = "human")]
     pub format: OutputFormat,
@@ -71,6 +85,14 @@ pub struct Args {
     #[arg(short = 'j', long = "threads", default_value = "0")]
     pub threads: usize,
 
+    /// Storage the tree lives on, setting the automatic thread count: ssd (one per CPU), hdd (2), network (4 per CPU)
+    #[arg(long = "io-profile", value_name = "PROFILE")]
+    pub io_profile: Option<IoProfile>,
+
+    /// Try fewer and more threads on the first files and keep the fastest for the rest of the content phase
+    #[arg(long = "adaptive-threads")]
+    pub adaptive_threads: bool,
+
     /// Progress display mode
     #[arg(long = "progress", default_value = "auto")]
     pub progress: ProgressMode,
@@ -83,13 +105,105 @@ pub struct Args {
     #[arg(short = 'r', long = "regex")]
     pub use_regex: bool,
 
-    /// Include hidden files and directories (starting with '.')
-    #[arg(long = "include-hidden")]
+    /// Process hidden files and directories (starting with '.'), which are skipped by default
+    #[arg(long = "hidden", visible_alias = "include-hidden")]
     pub include_hidden: bool,
 
     /// Include binary file names in renaming operations (content will still be skipped)
     #[arg(long = "binary-names")]
     pub binary_names: bool,
+
+    /// Match the pattern only as a whole identifier (`user` but not `username`)
+    #[arg(long = "token-mode")]
+    pub token_mode: bool,
+
+    /// With --token-mode, skip matches in string literals and comments of Rust, Python and JS/TS files
+    #[arg(long = "skip-literals", requires = "token_mode")]
+    pub skip_literals: bool,
+
+    /// Write the planned changes to a standalone HTML report and exit without changing anything
+    #[arg(long = "report-html", value_name = "FILE")]
+    pub report_html: Option<PathBuf>,
+
+    /// Serve plan/preview/execute/undo requests as JSON-RPC on this Unix socket, for editor plugins
+    #[arg(long = "serve", value_name = "SOCKET", conflicts_with = "report_html")]
+    pub serve: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<RefacCommand>,
+
+    /// Also rename and replace inside zip, tar, tar.gz and gz archives, repacking them
+    #[arg(long = "into-archives")]
+    pub into_archives: bool,
+
+    /// With --into-archives, skip archives larger than this (packed or unpacked)
+    #[arg(long = "archive-size-limit", value_name = "MIB", default_value_t = super::archives::DEFAULT_SIZE_LIMIT_MIB, requires = "into_archives")]
+    pub archive_size_limit: u64,
+
+    /// When a rename changes a module name, also update its imports (`import foo`, `mod foo;`, `require('./foo')`)
+    #[arg(long = "update-imports")]
+    pub update_imports: bool,
+
+    /// How replaced files are written: `safe` syncs them to disk before they replace the originals
+    #[arg(long = "durability", default_value = "safe")]
+    pub durability: Durability,
+
+    /// Print performance counters (files walked, bytes scanned and rewritten, time per phase) as JSON on stderr
+    #[arg(long = "stats")]
+    pub stats: bool,
+
+    /// List every path left alone (hidden, excluded, binary, too large, unreadable) with the reason
+    #[arg(long = "show-skipped")]
+    pub show_skipped: bool,
+
+    /// Retry renames and writes of files locked by another process N times, waiting longer each time
+    #[arg(long = "retry-locked", value_name = "N", default_value_t = 0)]
+    pub retry_locked: u32,
+
+    /// Flag rename targets differing only by case: `always` also on case-sensitive filesystems, `auto` only on macOS and Windows
+    #[arg(long = "case-collision-check", default_value = "auto")]
+    pub case_collision_check: CaseCollisionCheck,
+
+    /// After a failed rename or content change: `skip-dependents` leaves renames of its parent and child directories alone, `abort` stops, `rollback` also undoes what was done
+    #[arg(long = "on-error", default_value = "continue")]
+    pub on_error: OnError,
+
+    /// Stop taking new work after this long (e.g. 90s, 10m, 1h), finish what is in flight, and write a checkpoint of what remains
+    #[arg(long = "time-limit", value_name = "DURATION", value_parser = parse_time_limit, conflicts_with = "serve")]
+    pub time_limit: Option<std::time::Duration>,
+
+    /// With --time-limit, where the checkpoint goes (default: refac-checkpoint-<pid>.json in the temp directory)
+    #[arg(long = "checkpoint", value_name = "FILE", requires = "time_limit")]
+    pub checkpoint: Option<PathBuf>,
+
+    /// Write the refactored tree to this new directory, leaving the root untouched
+    #[arg(long = "output-dir", value_name = "DIR", conflicts_with_all = ["backup", "include_root", "update_imports", "time_limit", "serve", "report_html"])]
+    pub output_dir: Option<PathBuf>,
+
+    /// When another run holds the root, wait for it to finish instead of stopping
+    #[arg(long = "wait")]
+    pub wait: bool,
+
+    /// Don't take the lock that keeps other runs off the root while this one works
+    #[arg(long = "no-lock", conflicts_with = "wait")]
+    pub no_lock: bool,
+}
+
+/// A `--time-limit`: a number of seconds, or of minutes or hours with `m` or `h`
+pub fn parse_time_limit(input: &str) -> Result<std::time::Duration, String> {
+    let split = input.find(|c: char| !c.is_ascii_digit()).unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+    let number: u64 = number.parse().map_err(|_| format!("Invalid time limit: {} (expected e.g. 90s, 10m or 1h)", input))?;
+    let seconds = match unit {
+        "" | "s" => number,
+        "m" => number * 60,
+        "h" => number * 3600,
+        _ => return Err(format!("Invalid time limit unit: {} (expected s, m or h)", unit)),
+    };
+    if seconds == 0 {
+        return Err("Time limit must be greater than zero".to_string());
+    }
+    Ok(std::time::Duration::from_secs(seconds))
 }
 
 #[derive(ValueEnum, Debug, Clone, PartialEq)]
@@ -112,6 +226,79 @@ pub enum ProgressMode {
     Always,
 }
 
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum Durability {
+    /// Write to a temporary file and rename it over the original; survives crashes of ws
+    Fast,
+    /// Also sync the file and its directory to disk; survives power loss
+    Safe,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum IoProfile {
+    /// Local solid-state storage: one thread per CPU
+    Ssd,
+    /// Spinning disks, where parallel reads mostly add seeks
+    Hdd,
+    /// Network filesystems, where threads mostly wait on round trips
+    Network,
+}
+
+impl IoProfile {
+    pub fn thread_count(self, cpus: usize) -> usize {
+        match self {
+            IoProfile::Ssd => cpus,
+            IoProfile::Hdd => cpus.min(2),
+            IoProfile::Network => (cpus * 4).min(64),
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Default)]
+pub enum CaseCollisionCheck {
+    /// Only where the filesystem ignores case (macOS, Windows)
+    #[default]
+    Auto,
+    /// Everywhere, so the tree can be checked out on any platform
+    Always,
+    /// Never
+    Never,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Default)]
+pub enum OnError {
+    /// Report the failure and carry on with every other operation
+    #[default]
+    Continue,
+    /// Also skip renames of the failed path's ancestors and descendants
+    SkipDependents,
+    /// Stop at the first failure, keeping what was already done
+    Abort,
+    /// Stop at the first failure and undo the renames and content changes made so far
+    Rollback,
+}
+
+/// Commands run instead of a refactor
+#[derive(Subcommand, Debug, Clone, PartialEq)]
+pub enum RefacCommand {
+    /// Print the JSON schema of the `--format json` output
+    Schema,
+    /// Review the plan in a local web page and apply the accepted changes
+    Preview {
+        /// Port to serve the page on, on 127.0.0.1
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+    },
+    /// Check that a plan saved from `--format json` was applied: renames done, the pattern gone from changed files
+    Verify {
+        /// The summary document of `--format json`, the result of the plan method of `--serve`, or a `--time-limit` checkpoint
+        plan: PathBuf,
+        /// Output format of the report
+        #[arg(long = "format", default_value = "human")]
+        format: OutputFormat,
+    },
+}
+
 #[derive(Debug, Clone)]
 pub enum Mode {
     /// Process both files and directories, both names and content
@@ -146,6 +333,10 @@ impl Args {
             return Err("Cannot specify more than one mode flag (--files-only, --dirs-only, --names-only, --content-only)".to_string());
         }
 
+        if self.include_root && !(self.should_process_names() && !self.files_only) {
+            return Err("--include-root renames a directory, which --files-only and --content-only leave alone".to_string());
+        }
+
         // Validate root directory exists
         if !self.root_dir.exists() {
             return Err(format!("Root directory does not exist: {}", self.root_dir.display()));
@@ -155,6 +346,10 @@ impl Args {
             return Err(format!("Root path is not a directory: {}", self.root_dir.display()));
         }
 
+        if let Some(output_dir) = &self.output_dir {
+            self.validate_output_dir(output_dir)?;
+        }
+
         // Validate strings
         if self.pattern.is_empty() {
             return Err("Pattern cannot be empty".to_string());
@@ -182,6 +377,31 @@ impl Args {
         Ok(())
     }
 
+    /// The `--output-dir` must be a new or empty directory outside the root
+    fn validate_output_dir(&self, output_dir: &Path) -> Result<(), String> {
+        if matches!(self.command, Some(RefacCommand::Preview { .. })) {
+            return Err("--output-dir can't be used with preview, which applies changes in place".to_string());
+        }
+
+        let root = absolute_path(&self.root_dir);
+        let output = absolute_path(output_dir);
+        if output.starts_with(&root) || root.starts_with(&output) {
+            return Err(format!(
+                "Output directory {} can't be inside the root directory or contain it",
+                output_dir.display()
+            ));
+        }
+
+        if output.exists() {
+            let empty = std::fs::read_dir(&output).map(|mut entries| entries.next().is_none());
+            if !matches!(empty, Ok(true)) {
+                return Err(format!("Output directory is not an empty directory: {}", output_dir.display()));
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn should_process_files(&self) -> bool {
         !self.dirs_only
     }
@@ -200,9 +420,10 @@ impl Args {
 
     pub fn get_thread_count(&self) -> usize {
         if self.threads == 0 {
-            std::thread::available_parallelism()
+            let cpus = std::thread::available_parallelism()
                 .map(|n| n.get())
-                .unwrap_or(4)
+                .unwrap_or(4);
+            self.io_profile.map_or(cpus, |profile| profile.thread_count(cpus))
         } else {
             self.threads
         }
@@ -225,6 +446,10 @@ mod tests {
             assume_yes: false,
             verbose: false,
             follow_symlinks: false,
+            max_symlink_depth: 8,
+            include_root: false,
+            time_limit: None,
+            checkpoint: None,
             backup: false,
             files_only: false,
             dirs_only: false,
@@ -235,11 +460,31 @@ mod tests {
             include_patterns: vec![],
             format: OutputFormat::Human,
             threads: 0,
+            io_profile: None,
+            adaptive_threads: false,
             progress: ProgressMode::Auto,
             ignore_case: false,
             use_regex: false,
             include_hidden: false,
             binary_names: false,
+            token_mode: false,
+            skip_literals: false,
+            report_html: None,
+            serve: None,
+            command: None,
+            into_archives: false,
+            archive_size_limit: 64,
+            update_imports: false,
+            durability: Durability::Safe,
+            stats: false,
+            retry_locked: 0,
+            exclude_containing: None,
+            show_skipped: false,
+            case_collision_check: CaseCollisionCheck::Auto,
+            output_dir: None,
+            wait: false,
+            no_lock: false,
+            on_error: OnError::Continue,
         };
 
         // Valid args should pass
@@ -269,7 +514,16 @@ mod tests {
         args.substitute = "new\\path".to_string();
         assert!(args.validate().is_ok());
         args.substitute = "new".to_string();
+
+        // The root is a directory, left alone in content-only and files-only mode
+        args.include_root = true;
+        assert!(args.validate().is_err());
         args.content_only = false;
+        assert!(args.validate().is_ok());
+        args.files_only = true;
+        assert!(args.validate().is_err());
+        args.files_only = false;
+        args.include_root = false;
 
         // Multiple mode flags should fail
         args.files_only = true;
@@ -288,6 +542,10 @@ mod tests {
             assume_yes: false,
             verbose: false,
             follow_symlinks: false,
+            max_symlink_depth: 8,
+            include_root: false,
+            time_limit: None,
+            checkpoint: None,
             backup: false,
             files_only: false,
             dirs_only: false,
@@ -298,11 +556,31 @@ mod tests {
             include_patterns: vec![],
             format: OutputFormat::Human,
             threads: 0,
+            io_profile: None,
+            adaptive_threads: false,
             progress: ProgressMode::Auto,
             ignore_case: false,
             use_regex: false,
             include_hidden: false,
             binary_names: false,
+            token_mode: false,
+            skip_literals: false,
+            report_html: None,
+            serve: None,
+            command: None,
+            into_archives: false,
+            archive_size_limit: 64,
+            update_imports: false,
+            durability: Durability::Safe,
+            stats: false,
+            retry_locked: 0,
+            exclude_containing: None,
+            show_skipped: false,
+            case_collision_check: CaseCollisionCheck::Auto,
+            output_dir: None,
+            wait: false,
+            no_lock: false,
+            on_error: OnError::Continue,
         };
 
         // Test default mode
@@ -340,6 +618,10 @@ mod tests {
             assume_yes: false,
             verbose: false,
             follow_symlinks: false,
+            max_symlink_depth: 8,
+            include_root: false,
+            time_limit: None,
+            checkpoint: None,
             backup: false,
             files_only: false,
             dirs_only: false,
@@ -350,11 +632,31 @@ mod tests {
             include_patterns: vec![],
             format: OutputFormat::Human,
             threads: 0,
+            io_profile: None,
+            adaptive_threads: false,
             progress: ProgressMode::Auto,
             ignore_case: false,
             use_regex: false,
             include_hidden: false,
             binary_names: false,
+            token_mode: false,
+            skip_literals: false,
+            report_html: None,
+            serve: None,
+            command: None,
+            into_archives: false,
+            archive_size_limit: 64,
+            update_imports: false,
+            durability: Durability::Safe,
+            stats: false,
+            retry_locked: 0,
+            exclude_containing: None,
+            show_skipped: false,
+            case_collision_check: CaseCollisionCheck::Auto,
+            output_dir: None,
+            wait: false,
+            no_lock: false,
+            on_error: OnError::Continue,
         };
 
         // Default should process everything
@@ -373,5 +675,24 @@ mod tests {
         args.content_only = true;
         assert!(args.should_process_content());
         assert!(!args.should_process_names());
+
+        // An explicit thread count wins over the I/O profile
+        args.io_profile = Some(IoProfile::Hdd);
+        assert!(args.get_thread_count() <= 2);
+        args.threads = 6;
+        assert_eq!(args.get_thread_count(), 6);
+        assert_eq!(IoProfile::Network.thread_count(8), 32);
+        assert_eq!(IoProfile::Network.thread_count(32), 64);
+    }
+
+    #[test]
+    fn test_parse_time_limit() {
+        assert_eq!(parse_time_limit("90").unwrap().as_secs(), 90);
+        assert_eq!(parse_time_limit("45s").unwrap().as_secs(), 45);
+        assert_eq!(parse_time_limit("10m").unwrap().as_secs(), 600);
+        assert_eq!(parse_time_limit("2h").unwrap().as_secs(), 7200);
+        assert!(parse_time_limit("0m").is_err());
+        assert!(parse_time_limit("10d").is_err());
+        assert!(parse_time_limit("m").is_err());
     }
 }
\ No newline at end of file
@@ -1,7 +1,9 @@
 use anyhow::{Context, Result};
+use rayon::prelude::*;
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use crate::RenameItem;
+use super::cli::CaseCollisionCheck;
 
 /// Detects naming collisions in rename operations
 #[derive(Debug)]
@@ -12,6 +14,38 @@ pub struct CollisionDetector {
     existing_paths: HashSet<PathBuf>,
     /// Collisions found during detection
     collisions: Vec<Collision>,
+    /// When targets differing only by case count as collisions
+    case_check: CaseCollisionCheck,
+}
+
+/// Paths seen by a directory walk, so the collision check need not read
+/// again the directories it listed in full
+#[derive(Debug, Default)]
+pub struct WalkedPaths {
+    paths: HashSet<PathBuf>,
+    /// Directories the walk descended into
+    listed_dirs: HashSet<PathBuf>,
+    /// Directories with children the walk skipped, e.g. hidden or beyond the maximum depth
+    partial_dirs: HashSet<PathBuf>,
+}
+
+impl WalkedPaths {
+    pub fn add_path(&mut self, path: &Path) {
+        self.paths.insert(path.to_path_buf());
+    }
+
+    pub fn add_listed_dir(&mut self, dir: &Path) {
+        self.listed_dirs.insert(dir.to_path_buf());
+    }
+
+    pub fn add_partial_dir(&mut self, dir: &Path) {
+        self.partial_dirs.insert(dir.to_path_buf());
+    }
+
+    /// Whether the walk saw every child of `dir`
+    pub fn is_complete(&self, dir: &Path) -> bool {
+        self.listed_dirs.contains(dir) && !self.partial_dirs.contains(dir)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -30,7 +64,7 @@ pub enum CollisionType {
     TargetAlreadyExists,
     /// Source and target are the same (no-op)
     SourceEqualsTarget,
-    /// Case-only difference on case-insensitive filesystem
+    /// Targets differing only by case, which clash on case-insensitive filesystems
     CaseOnlyDifference,
     /// Directory trying to rename to existing file path
     DirectoryToFile,
@@ -50,9 +84,15 @@ impl CollisionDetector {
             target_paths: HashMap::new(),
             existing_paths: HashSet::new(),
             collisions: Vec::new(),
+            case_check: CaseCollisionCheck::Auto,
         }
     }
 
+    pub fn with_case_check(mut self, case_check: CaseCollisionCheck) -> Self {
+        self.case_check = case_check;
+        self
+    }
+
     /// Add a rename operation to check for collisions
     pub fn add_rename(&mut self, source: PathBuf, target: PathBuf) {
         self.target_paths.entry(target).or_insert_with(Vec::new).push(source);
@@ -85,6 +125,25 @@ impl CollisionDetector {
         Ok(())
     }
 
+    /// Populate existing paths from the directories receiving rename targets
+    /// only: from `walked` where it listed them in full, otherwise by reading
+    /// them in parallel. Call after adding the renames.
+    pub fn scan_target_directories(&mut self, walked: &WalkedPaths) -> Result<()> {
+        let mut unlisted = HashSet::new();
+        for target in self.target_paths.keys() {
+            let Some(parent) = target.parent() else { continue };
+            if !walked.is_complete(parent) {
+                unlisted.insert(parent.to_path_buf());
+            } else if walked.paths.contains(target) {
+                self.existing_paths.insert(target.clone());
+            }
+        }
+
+        let listings = unlisted.par_iter().map(|dir| list_directory(dir)).collect::<Result<Vec<_>>>()?;
+        self.existing_paths.extend(listings.into_iter().flatten());
+        Ok(())
+    }
+
     /// Detect all collisions
     pub fn detect_collisions(&mut self) -> Result<Vec<Collision>> {
         self.collisions.clear();
@@ -140,8 +199,13 @@ impl CollisionDetector {
             }
         }
 
-        // Check for case-only differences on case-insensitive filesystems
-        if self.is_case_insensitive_filesystem()? {
+        // Check for case-only differences, which clash on case-insensitive filesystems
+        let check_case = match self.case_check {
+            CaseCollisionCheck::Auto => self.is_case_insensitive_filesystem()?,
+            CaseCollisionCheck::Always => true,
+            CaseCollisionCheck::Never => false,
+        };
+        if check_case {
             self.detect_case_collisions()?;
         }
 
@@ -154,7 +218,7 @@ impl CollisionDetector {
         Ok(cfg!(target_os = "macos") || cfg!(target_os = "windows"))
     }
 
-    /// Detect case-only collisions on case-insensitive filesystems
+    /// Detect targets that are equal once case is folded
     fn detect_case_collisions(&mut self) -> Result<()> {
         let mut lowercase_map: HashMap<String, Vec<PathBuf>> = HashMap::new();
         
@@ -176,7 +240,7 @@ impl CollisionDetector {
                             target_path: path.clone(),
                             source_paths: sources.clone(),
                             description: format!(
-                                "Case-only difference detected on case-insensitive filesystem: {}",
+                                "Targets differ only by case and clash on case-insensitive filesystems: {}",
                                 path.display()
                             ),
                         });
@@ -263,6 +327,21 @@ impl CollisionDetector {
     }
 }
 
+/// Paths of the entries of `dir`; none if it doesn't exist (yet)
+fn list_directory(dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to list {} while scanning for existing paths", dir.display())),
+    };
+    entries
+        .map(|entry| {
+            let entry = entry.with_context(|| format!("Failed to read directory entry in {}", dir.display()))?;
+            Ok(entry.path())
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -394,6 +473,27 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_case_collision_check() -> Result<()> {
+        let detector = |case_check| {
+            let mut detector = CollisionDetector::new().with_case_check(case_check);
+            detector.add_rename(PathBuf::from("/test/a.txt"), PathBuf::from("/test/Readme.md"));
+            detector.add_rename(PathBuf::from("/test/b.txt"), PathBuf::from("/test/README.md"));
+            detector
+        };
+
+        let collisions = detector(CaseCollisionCheck::Always).detect_collisions()?;
+        assert_eq!(collisions.len(), 2);
+        assert!(collisions.iter().all(|c| c.collision_type == CollisionType::CaseOnlyDifference));
+
+        assert!(detector(CaseCollisionCheck::Never).detect_collisions()?.is_empty());
+
+        let expected = if cfg!(any(target_os = "macos", target_os = "windows")) { 2 } else { 0 };
+        assert_eq!(detector(CaseCollisionCheck::Auto).detect_collisions()?.len(), expected);
+
+        Ok(())
+    }
+
     #[test]
     fn test_scan_existing_paths() -> Result<()> {
         let temp_dir = TempDir::new()?;
@@ -413,6 +513,36 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_scan_target_directories() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::create_dir(temp_dir.path().join("listed"))?;
+        fs::create_dir(temp_dir.path().join("unlisted"))?;
+        File::create(temp_dir.path().join("unlisted/taken.txt"))?;
+        let listed = temp_dir.path().join("listed");
+
+        // The walk's listing is trusted for directories it saw in full
+        let mut walked = WalkedPaths::default();
+        walked.add_listed_dir(&listed);
+        walked.add_path(&listed.join("taken.txt"));
+
+        let mut detector = CollisionDetector::new();
+        detector.add_rename(listed.join("a.txt"), listed.join("taken.txt"));
+        detector.add_rename(listed.join("b.txt"), listed.join("free.txt"));
+        detector.add_rename(temp_dir.path().join("unlisted/c.txt"), temp_dir.path().join("unlisted/taken.txt"));
+        detector.add_rename(temp_dir.path().join("missing/d.txt"), temp_dir.path().join("missing/e.txt"));
+        detector.scan_target_directories(&walked)?;
+
+        let mut targets: Vec<PathBuf> = detector.detect_collisions()?.into_iter().map(|c| c.target_path).collect();
+        targets.sort();
+        assert_eq!(targets, [listed.join("taken.txt"), temp_dir.path().join("unlisted/taken.txt")]);
+
+        // Skipped children make a listing partial
+        walked.add_partial_dir(&listed);
+        assert!(!walked.is_complete(&listed));
+        Ok(())
+    }
+
     #[test]
     fn test_collision_summary() -> Result<()> {
         let mut detector = CollisionDetector::new();
@@ -2,14 +2,48 @@ use anyhow::{Context, Result};
 use std::fs::{self, File};
 use std::io::{BufRead, BufReader, BufWriter, Write};
 use std::path::{Path, PathBuf};
-use super::binary_detector::BinaryDetector;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use super::archives::{self, ArchiveKind, ArchiveOptions, MemberChange, MemberEdit};
+use super::binary_detector::{BinaryDetector, DetectorStats};
+use super::cli::Durability;
+use super::handlers::{handler_for, ContentHandler};
+use super::stats::Counters;
+use super::tokens::TokenMode;
 use encoding_rs::{Encoding, UTF_8};
+use rayon::prelude::*;
 use chardet::detect;
+use crate::scrap::FileAttributes;
+use metadata::NativeMetadata;
+
+mod metadata;
+
+/// Files at least this large are replaced in chunks on several threads
+pub const CHUNKED_REPLACE_THRESHOLD: u64 = 8 * 1024 * 1024;
+
+/// Size of the chunks of a file replaced in parallel, extended to the next line end
+const REPLACE_CHUNK_BYTES: usize = 1024 * 1024;
+
+/// First wait before retrying an operation on a locked file; doubled on each retry
+const LOCK_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Longest wait between retries on a locked file
+const MAX_LOCK_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Numbers the temporary files of concurrent writes in one process
+static TEMP_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
 
 /// File operations for the refac tool (part of the workspace suite)
 pub struct FileOperations {
     binary_detector: BinaryDetector,
     backup_enabled: bool,
+    handlers: Vec<ContentHandler>,
+    token_mode: Option<TokenMode>,
+    archives: Option<ArchiveOptions>,
+    durability: Durability,
+    counters: Arc<Counters>,
+    retry_locked: u32,
 }
 
 /// Encoding information for a file
@@ -30,6 +64,12 @@ impl FileOperations {
         Self {
             binary_detector: BinaryDetector::default(),
             backup_enabled: false,
+            handlers: Vec::new(),
+            token_mode: None,
+            archives: None,
+            durability: Durability::Safe,
+            counters: Arc::default(),
+            retry_locked: 0,
         }
     }
 
@@ -38,23 +78,235 @@ impl FileOperations {
         self
     }
 
+    /// Hand files matching a handler's glob to the handler instead of the text replacer
+    pub fn with_handlers(mut self, handlers: Vec<ContentHandler>) -> Self {
+        self.handlers = handlers;
+        self
+    }
+
+    pub fn handler_for(&self, path: &Path) -> Option<&ContentHandler> {
+        handler_for(&self.handlers, path)
+    }
+
+    /// Match the pattern as a whole identifier instead of any substring
+    pub fn with_token_mode(mut self, token_mode: Option<TokenMode>) -> Self {
+        self.token_mode = token_mode;
+        self
+    }
+
+    /// Whether replaced files are synced to disk before they take the original's place
+    pub fn with_durability(mut self, durability: Durability) -> Self {
+        self.durability = durability;
+        self
+    }
+
+    /// Count bytes read and written in `counters`
+    pub fn with_counters(mut self, counters: Arc<Counters>) -> Self {
+        self.counters = counters;
+        self
+    }
+
+    /// Retry renames and writes failing because another process has the file
+    /// locked `retries` times, waiting longer before each retry
+    pub fn with_retry_locked(mut self, retries: u32) -> Self {
+        self.retry_locked = retries;
+        self
+    }
+
+    /// Whether another process holds `path` open so that renaming or
+    /// replacing it would fail. Only Windows enforces such locks; elsewhere
+    /// this is always false.
+    pub fn is_locked(&self, path: &Path) -> bool {
+        is_locked(path)
+    }
+
+    /// Run `operation` on `path`, retrying it while the path is locked
+    fn retry_locked<T>(&self, path: &Path, mut operation: impl FnMut() -> std::io::Result<T>) -> Result<T> {
+        let mut delay = LOCK_RETRY_DELAY;
+        let mut attempt = 0;
+        loop {
+            match operation() {
+                Err(e) if is_lock_error(&e) && attempt < self.retry_locked => {
+                    log::debug!("{} is locked, retrying in {:?}: {}", path.display(), delay, e);
+                    std::thread::sleep(delay);
+                    delay = (delay * 2).min(MAX_LOCK_RETRY_DELAY);
+                    attempt += 1;
+                }
+                Err(e) if is_lock_error(&e) => {
+                    return Err(anyhow::Error::new(e).context(format!(
+                        "{} is locked by another process (close it, or pass --retry-locked N to wait for it)",
+                        path.display()
+                    )));
+                }
+                result => return Ok(result?),
+            }
+        }
+    }
+
+    pub fn detector_stats(&self) -> DetectorStats {
+        self.binary_detector.stats()
+    }
+
+    /// Read a file to scan or replace its content
+    pub fn read_file(&self, file_path: &Path) -> Result<Vec<u8>> {
+        let bytes = fs::read(file_path)
+            .with_context(|| format!("Failed to read file: {}", file_path.display()))?;
+        self.counters.add_bytes_scanned(bytes.len() as u64);
+        Ok(bytes)
+    }
+
+    /// Rename and replace inside zip and tar archives instead of skipping them as binary
+    pub fn with_archives(mut self, archives: Option<ArchiveOptions>) -> Self {
+        self.archives = archives;
+        self
+    }
+
+    /// Whether `path` is an archive to descend into
+    pub fn is_archive(&self, path: &Path) -> bool {
+        self.archives.is_some() && ArchiveKind::from_path(path).is_some()
+    }
+
+    /// The member changes replacing `pattern` would make in the archive at `path`
+    pub fn archive_changes(&self, path: &Path, pattern: &str, substitute: &str) -> Result<Vec<MemberChange>> {
+        let options = self.archives.context("Archive processing is not enabled")?;
+        let rewrite = |name: &str, content: Option<&[u8]>| self.archive_edit(&options, name, content, pattern, substitute);
+        archives::plan(path, options.size_limit, &rewrite)
+    }
+
+    fn archive_edit(&self, options: &ArchiveOptions, name: &str, content: Option<&[u8]>, pattern: &str, substitute: &str) -> MemberEdit {
+        let member = Path::new(name);
+        let new_name = Some(self.replace_matches(name, pattern, substitute, member))
+            .filter(|new_name| options.rename_members && new_name != name);
+        // Text members only, like files on disk
+        let content = content
+            .filter(|content| options.replace_content && content_inspector::inspect(content).is_text())
+            .and_then(|content| std::str::from_utf8(content).ok())
+            .and_then(|text| {
+                let occurrences = self.count_matches(text, pattern, member);
+                (occurrences > 0).then(|| (self.replace_matches(text, pattern, substitute, member).into_bytes(), occurrences))
+            });
+        MemberEdit { new_name, content }
+    }
+
+    fn rewrite_archive(&self, file_path: &Path, pattern: &str, substitute: &str) -> Result<Option<Vec<u8>>> {
+        let options = self.archives.context("Archive processing is not enabled")?;
+        let rewrite = |name: &str, content: Option<&[u8]>| self.archive_edit(&options, name, content, pattern, substitute);
+        let repacked = archives::repack(file_path, options.size_limit, &rewrite)
+            .with_context(|| format!("Failed to process archive: {}", file_path.display()))?;
+        Ok(repacked.map(|(bytes, _)| bytes))
+    }
+
+    /// Number of matches of `pattern` in `content`, the text of `path`
+    pub fn count_matches(&self, content: &str, pattern: &str, path: &Path) -> usize {
+        match &self.token_mode {
+            Some(mode) => mode.find(content, pattern, path).len(),
+            None => content.matches(pattern).count(),
+        }
+    }
+
+    /// `content` with every match of `pattern` replaced
+    pub fn replace_matches(&self, content: &str, pattern: &str, substitute: &str, path: &Path) -> String {
+        match &self.token_mode {
+            Some(mode) => mode.replace(content, pattern, substitute, path),
+            None => content.replace(pattern, substitute),
+        }
+    }
+
+    /// Like `replace_matches`, replacing line-aligned chunks of `content` in
+    /// parallel. A match never spans a line end unless the pattern has one, so
+    /// the result is the same; literal skipping needs the whole file and is
+    /// done in one piece.
+    pub fn replace_matches_chunked(&self, content: &str, pattern: &str, substitute: &str, path: &Path) -> String {
+        if pattern.contains('\n') || self.token_mode.is_some_and(|mode| mode.skip_literals) {
+            return self.replace_matches(content, pattern, substitute, path);
+        }
+        line_chunks(content, REPLACE_CHUNK_BYTES)
+            .par_iter()
+            .map(|chunk| self.replace_matches(chunk, pattern, substitute, path))
+            .collect::<Vec<_>>()
+            .concat()
+    }
+
     /// Replace content in a file
+    #[tracing::instrument(level = "debug", skip_all, fields(path = %file_path.as_ref().display()))]
     pub fn replace_content<P: AsRef<Path>>(
         &self,
         file_path: P,
         pattern: &str,
         substitute: &str,
     ) -> Result<bool> {
-        let file_path = file_path.as_ref();
-        
+        self.replace_file_content(file_path.as_ref(), pattern, substitute, false)
+    }
+
+    /// Replace content in a large file, working on chunks of it in parallel
+    #[tracing::instrument(level = "debug", skip_all, fields(path = %file_path.as_ref().display()))]
+    pub fn replace_content_chunked<P: AsRef<Path>>(
+        &self,
+        file_path: P,
+        pattern: &str,
+        substitute: &str,
+    ) -> Result<bool> {
+        self.replace_file_content(file_path.as_ref(), pattern, substitute, true)
+    }
+
+    fn replace_file_content(&self, file_path: &Path, pattern: &str, substitute: &str, chunked: bool) -> Result<bool> {
+        let Some(new_bytes) = self.rewritten_content(file_path, pattern, substitute, chunked)? else {
+            return Ok(false);
+        };
+
+        // Create backup if enabled
+        if self.backup_enabled {
+            self.create_backup(file_path)?;
+        }
+        self.write_file(file_path, &new_bytes)?;
+
+        Ok(true)
+    }
+
+    /// Write `source` to `target` with every match of `pattern` replaced,
+    /// leaving `source` untouched. The copy takes the permissions, extended
+    /// attributes and native metadata of `source`. Returns whether anything
+    /// was replaced; a file without matches is copied as it is.
+    #[tracing::instrument(level = "debug", skip_all, fields(path = %source.display()))]
+    pub fn copy_with_rewrite(&self, source: &Path, target: &Path, pattern: &str, substitute: &str, chunked: bool) -> Result<bool> {
+        let Some(new_bytes) = self.rewritten_content(source, pattern, substitute, chunked)? else {
+            self.copy_file(source, target)?;
+            return Ok(false);
+        };
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create parent directory: {}", parent.display())
+            })?;
+        }
+        fs::write(target, &new_bytes).with_context(|| format!("Failed to write file: {}", target.display()))?;
+        FileAttributes::capture(source)?.apply(target)?;
+        NativeMetadata::capture(source)?.apply(target)?;
+        self.counters.add_bytes_rewritten(new_bytes.len() as u64);
+
+        Ok(true)
+    }
+
+    /// The bytes of `file_path` with every match of `pattern` replaced, or
+    /// `None` when nothing would change. Binary files are never changed.
+    fn rewritten_content(&self, file_path: &Path, pattern: &str, substitute: &str, chunked: bool) -> Result<Option<Vec<u8>>> {
+        if let Some(handler) = self.handler_for(file_path) {
+            let original_bytes = self.read_file(file_path)?;
+            let new_bytes = handler.rewrite(file_path, original_bytes.clone(), pattern, substitute)?;
+            return Ok(Some(new_bytes).filter(|new_bytes| *new_bytes != original_bytes));
+        }
+
+        if self.is_archive(file_path) {
+            return self.rewrite_archive(file_path, pattern, substitute);
+        }
+
         // Skip binary files
         if self.binary_detector.is_binary(file_path)? {
-            return Ok(false);
+            return Ok(None);
         }
 
         // Read file as bytes first
-        let original_bytes = fs::read(file_path)
-            .with_context(|| format!("Failed to read file: {}", file_path.display()))?;
+        let original_bytes = self.read_file(file_path)?;
 
         // Detect the file's encoding
         let file_encoding = self.detect_encoding(&original_bytes)?;
@@ -64,29 +316,26 @@ impl FileOperations {
             .with_context(|| format!("Failed to decode file with detected encoding: {}", file_path.display()))?;
 
         // Check if the file contains the target string
-        if !content.contains(pattern) {
-            return Ok(false);
-        }
-
-        // Create backup if enabled
-        if self.backup_enabled {
-            self.create_backup(file_path)?;
+        if self.count_matches(&content, pattern, file_path) == 0 {
+            return Ok(None);
         }
 
         // Replace content
-        let new_content = content.replace(pattern, substitute);
+        let new_content = if chunked {
+            self.replace_matches_chunked(&content, pattern, substitute, file_path)
+        } else {
+            self.replace_matches(&content, pattern, substitute, file_path)
+        };
 
-        // Encode back to the original encoding and write
+        // Encode back to the original encoding
         let encoded_bytes = self.encode_with_encoding(&new_content, &file_encoding)
             .with_context(|| format!("Failed to encode content back to original encoding: {}", file_path.display()))?;
 
-        fs::write(file_path, encoded_bytes)
-            .with_context(|| format!("Failed to write file: {}", file_path.display()))?;
-
-        Ok(true)
+        Ok(Some(encoded_bytes))
     }
 
     /// Replace content in a file using streaming for large files
+    #[tracing::instrument(level = "debug", skip_all, fields(path = %file_path.as_ref().display()))]
     pub fn replace_content_streaming<P: AsRef<Path>>(
         &self,
         file_path: P,
@@ -105,16 +354,14 @@ impl FileOperations {
             self.create_backup(file_path)?;
         }
 
-        let temp_file_path = file_path.with_extension("tmp");
+        let target = resolve_symlink(file_path)?;
+        let (temp_file_path, output_file) = create_temp_file(&target)?;
         let mut modified = false;
 
-        {
+        let written = (|| -> Result<File> {
             let input_file = File::open(file_path)
                 .with_context(|| format!("Failed to open input file: {}", file_path.display()))?;
             let reader = BufReader::new(input_file);
-
-            let output_file = File::create(&temp_file_path)
-                .with_context(|| format!("Failed to create temp file: {}", temp_file_path.display()))?;
             let mut writer = BufWriter::new(output_file);
 
             for line in reader.lines() {
@@ -134,29 +381,81 @@ impl FileOperations {
                 })?;
             }
 
-            writer.flush().with_context(|| {
+            writer.into_inner().map_err(|e| e.into_error()).with_context(|| {
                 format!("Failed to flush temp file: {}", temp_file_path.display())
-            })?;
-        }
+            })
+        })();
 
-        if modified {
+        match written {
             // Replace the original file with the modified one
-            fs::rename(&temp_file_path, file_path).with_context(|| {
-                format!(
-                    "Failed to replace original file {} with temp file {}",
-                    file_path.display(),
-                    temp_file_path.display()
-                )
-            })?;
-        } else {
-            // Remove the temp file since no changes were made
-            let _ = fs::remove_file(&temp_file_path);
+            Ok(output_file) if modified => self.commit_temp_file(output_file, &temp_file_path, &target)?,
+            result => {
+                // Remove the temp file since no changes were made
+                let _ = fs::remove_file(&temp_file_path);
+                result?;
+            }
         }
 
         Ok(modified)
     }
 
+    /// Replace the contents of `file_path` with `bytes` so that a crash never
+    /// leaves it truncated: the bytes go to a temporary file in the same
+    /// directory, which takes the original's permissions, owner and extended
+    /// attributes (on Windows its readonly, hidden and system attributes and
+    /// ACL) and is then renamed over it. With `Durability::Safe` the
+    /// file and the rename are synced to disk first. A symlink is kept and its
+    /// target replaced.
+    pub fn write_file(&self, file_path: &Path, bytes: &[u8]) -> Result<()> {
+        let target = resolve_symlink(file_path)?;
+        let (temp_path, mut temp_file) = create_temp_file(&target)?;
+        let result = temp_file
+            .write_all(bytes)
+            .with_context(|| format!("Failed to write temp file: {}", temp_path.display()))
+            .and_then(|()| self.commit_temp_file(temp_file, &temp_path, &target));
+        if result.is_err() {
+            let _ = fs::remove_file(&temp_path);
+        }
+        result.with_context(|| format!("Failed to write file: {}", file_path.display()))
+    }
+
+    /// Rename the finished temporary file at `temp_path` over `target`
+    fn commit_temp_file(&self, temp_file: File, temp_path: &Path, target: &Path) -> Result<()> {
+        FileAttributes::capture(target)?.apply(temp_path)?;
+        copy_owner(target, temp_path);
+        let native = NativeMetadata::capture(target)?;
+
+        if self.durability == Durability::Safe {
+            temp_file
+                .sync_all()
+                .with_context(|| format!("Failed to sync temp file: {}", temp_path.display()))?;
+        }
+        let written = temp_file.metadata().map(|metadata| metadata.len()).unwrap_or_default();
+        drop(temp_file);
+
+        // A readonly file can't be replaced on Windows; the temp file brings
+        // the attribute back along with the hidden and system ones and the ACL
+        native.apply(temp_path)?;
+        native.make_replaceable(target)?;
+        let renamed = self.retry_locked(target, || fs::rename(temp_path, target));
+        if renamed.is_err() {
+            native.restore(target);
+            // Let the caller remove the temp file again
+            let _ = native.make_replaceable(temp_path);
+        }
+        renamed.with_context(|| {
+            format!("Failed to replace {} with temp file {}", target.display(), temp_path.display())
+        })?;
+
+        if self.durability == Durability::Safe {
+            sync_parent_dir(target)?;
+        }
+        self.counters.add_bytes_rewritten(written);
+        Ok(())
+    }
+
     /// Move/rename a file or directory
+    #[tracing::instrument(level = "debug", skip_all, fields(path = %from.as_ref().display()))]
     pub fn move_item<P: AsRef<Path>, Q: AsRef<Path>>(
         &self,
         from: P,
@@ -172,7 +471,7 @@ impl FileOperations {
             })?;
         }
 
-        fs::rename(from, to).with_context(|| {
+        self.retry_locked(from, || fs::rename(from, to)).with_context(|| {
             format!(
                 "Failed to move {} to {}",
                 from.display(),
@@ -184,6 +483,7 @@ impl FileOperations {
     }
 
     /// Copy a file
+    #[tracing::instrument(level = "debug", skip_all, fields(path = %from.as_ref().display()))]
     pub fn copy_file<P: AsRef<Path>, Q: AsRef<Path>>(
         &self,
         from: P,
@@ -206,11 +506,33 @@ impl FileOperations {
                 to.display()
             )
         })?;
+        NativeMetadata::capture(from)?.apply(to)?;
 
         Ok(())
     }
 
+    /// Create the directory `to` as a copy of `from` without its entries,
+    /// taking its permissions, extended attributes and native metadata. Call
+    /// it after copying the entries so a read-only directory can be filled.
+    pub fn copy_dir(&self, from: &Path, to: &Path) -> Result<()> {
+        fs::create_dir_all(to).with_context(|| format!("Failed to create directory: {}", to.display()))?;
+        let from = resolve_symlink(from)?;
+        FileAttributes::capture(&from)?.apply(to)?;
+        NativeMetadata::capture(&from)?.apply(to)
+    }
+
+    /// Recreate the symlink `from` at `to`, pointing where it points
+    pub fn copy_symlink(&self, from: &Path, to: &Path) -> Result<()> {
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create parent directory: {}", parent.display())
+            })?;
+        }
+        crate::scrap::file_attributes::copy_path(from, to)
+    }
+
     /// Create a backup of a file
+    #[tracing::instrument(level = "debug", skip_all, fields(path = %file_path.as_ref().display()))]
     pub fn create_backup<P: AsRef<Path>>(&self, file_path: P) -> Result<PathBuf> {
         let file_path = file_path.as_ref();
         let backup_path = self.generate_backup_path(file_path)?;
@@ -270,14 +592,35 @@ impl FileOperations {
         }
 
         // Read file as bytes and detect encoding
-        let bytes = fs::read(file_path)
-            .with_context(|| format!("Failed to read file: {}", file_path.display()))?;
+        let bytes = self.read_file(file_path)?;
             
         let file_encoding = self.detect_encoding(&bytes)?;
         let content = self.decode_with_encoding(&bytes, &file_encoding)
             .with_context(|| format!("Failed to decode file: {}", file_path.display()))?;
 
-        Ok(content.contains(search_string))
+        Ok(self.count_matches(&content, search_string, file_path) > 0)
+    }
+
+    /// Read a text file once to tell whether it contains `search_string` and
+    /// whether `exclude` matches it, in that order. Binary files do neither.
+    pub fn scan_content<P: AsRef<Path>>(
+        &self,
+        file_path: P,
+        search_string: &str,
+        exclude: &regex::Regex,
+    ) -> Result<(bool, bool)> {
+        let file_path = file_path.as_ref();
+
+        if self.binary_detector.is_binary(file_path)? {
+            return Ok((false, false));
+        }
+
+        let bytes = self.read_file(file_path)?;
+        let file_encoding = self.detect_encoding(&bytes)?;
+        let content = self.decode_with_encoding(&bytes, &file_encoding)
+            .with_context(|| format!("Failed to decode file: {}", file_path.display()))?;
+
+        Ok((self.count_matches(&content, search_string, file_path) > 0, exclude.is_match(&content)))
     }
 
     /// Count occurrences of a string in a file
@@ -294,14 +637,13 @@ impl FileOperations {
         }
 
         // Read file as bytes and detect encoding
-        let bytes = fs::read(file_path)
-            .with_context(|| format!("Failed to read file: {}", file_path.display()))?;
+        let bytes = self.read_file(file_path)?;
             
         let file_encoding = self.detect_encoding(&bytes)?;
         let content = self.decode_with_encoding(&bytes, &file_encoding)
             .with_context(|| format!("Failed to decode file: {}", file_path.display()))?;
 
-        Ok(content.matches(search_string).count())
+        Ok(self.count_matches(&content, search_string, file_path))
     }
 
     /// Get file size
@@ -500,6 +842,114 @@ impl FileOperations {
     }
 }
 
+/// `content` split into pieces of at least `chunk_bytes`, each ending at a line end
+/// The file a write to `path` should replace: the target of a symlink, or `path` itself
+fn resolve_symlink(path: &Path) -> Result<PathBuf> {
+    if fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_symlink()) {
+        return fs::canonicalize(path).with_context(|| format!("Failed to resolve symlink: {}", path.display()));
+    }
+    Ok(path.to_path_buf())
+}
+
+/// A new, hidden temporary file next to `path`, on the same filesystem so it can be renamed over it
+fn create_temp_file(path: &Path) -> Result<(PathBuf, File)> {
+    let name = path.file_name().and_then(|name| name.to_str()).unwrap_or("file");
+    let temp_path = path.with_file_name(format!(
+        ".{}.{}-{}.ws-tmp",
+        name,
+        std::process::id(),
+        TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let file = File::options()
+        .write(true)
+        .create_new(true)
+        .open(&temp_path)
+        .with_context(|| format!("Failed to create temp file: {}", temp_path.display()))?;
+    Ok((temp_path, file))
+}
+
+/// Give `to` the owner and group of `from` where allowed; only root can give files away
+#[cfg(unix)]
+fn copy_owner(from: &Path, to: &Path) {
+    use std::os::unix::fs::MetadataExt;
+
+    let (Ok(original), Ok(current)) = (fs::metadata(from), fs::metadata(to)) else {
+        return;
+    };
+    if (original.uid(), original.gid()) != (current.uid(), current.gid()) {
+        if let Err(e) = std::os::unix::fs::chown(to, Some(original.uid()), Some(original.gid())) {
+            log::debug!("Could not keep the owner of {}: {}", from.display(), e);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn copy_owner(_from: &Path, _to: &Path) {}
+
+/// Opening for delete access succeeds only if every open handle shares
+/// delete access, which renaming or replacing the file needs as well
+#[cfg(windows)]
+fn is_locked(path: &Path) -> bool {
+    use std::os::windows::fs::OpenOptionsExt;
+
+    const DELETE: u32 = 0x0001_0000;
+    // Needed to open directories
+    const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x0200_0000;
+    match fs::OpenOptions::new().access_mode(DELETE).custom_flags(FILE_FLAG_BACKUP_SEMANTICS).open(path) {
+        Ok(_) => false,
+        Err(e) => is_lock_error(&e),
+    }
+}
+
+#[cfg(not(windows))]
+fn is_locked(_path: &Path) -> bool {
+    false
+}
+
+/// ERROR_SHARING_VIOLATION or ERROR_LOCK_VIOLATION
+#[cfg(windows)]
+fn is_lock_error(e: &std::io::Error) -> bool {
+    matches!(e.raw_os_error(), Some(32 | 33))
+}
+
+/// EBUSY or ETXTBSY: a mount point, or an executable that is running
+#[cfg(not(windows))]
+fn is_lock_error(e: &std::io::Error) -> bool {
+    matches!(e.raw_os_error(), Some(16 | 26))
+}
+
+/// Make a rename in the directory of `path` durable
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) -> Result<()> {
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    File::open(dir)
+        .and_then(|dir| dir.sync_all())
+        .with_context(|| format!("Failed to sync directory: {}", dir.display()))
+}
+
+/// Directories can't be opened for syncing here; the rename is left to the filesystem
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+fn line_chunks(content: &str, chunk_bytes: usize) -> Vec<&str> {
+    let bytes = content.as_bytes();
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < bytes.len() {
+        let end = (start + chunk_bytes).min(bytes.len());
+        // A newline is a single byte, so the byte after it starts a character
+        let end = bytes[end..].iter().position(|&b| b == b'\n').map_or(bytes.len(), |pos| end + pos + 1);
+        chunks.push(&content[start..end]);
+        start = end;
+    }
+    chunks
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -552,6 +1002,33 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_line_chunks() {
+        let content = "aaa\nbb\n\ncccc";
+        assert_eq!(line_chunks(content, 2), vec!["aaa\n", "bb\n", "\ncccc"]);
+        assert_eq!(line_chunks(content, 100), vec![content]);
+        assert!(line_chunks("", 2).is_empty());
+        // Chunk sizes landing inside a multibyte character
+        assert_eq!(line_chunks("éé\né", 1).concat(), "éé\né");
+    }
+
+    #[test]
+    fn test_replace_matches_chunked() {
+        let line = "oldname aaa oldname_x é\n";
+        let content = line.repeat(REPLACE_CHUNK_BYTES / line.len() * 3);
+        let path = Path::new("big.txt");
+        for file_ops in [FileOperations::new(), FileOperations::new().with_token_mode(Some(TokenMode::default()))] {
+            assert_eq!(
+                file_ops.replace_matches_chunked(&content, "oldname", "newname", path),
+                file_ops.replace_matches(&content, "oldname", "newname", path)
+            );
+        }
+        // Overlapping candidates and patterns spanning lines
+        let file_ops = FileOperations::new();
+        assert_eq!(file_ops.replace_matches_chunked(&content, "aa", "b", path), content.replace("aa", "b"));
+        assert_eq!(file_ops.replace_matches_chunked(&content, "é\nold", "-", path), content.replace("é\nold", "-"));
+    }
+
     #[test]
     fn test_replace_content_streaming() -> Result<()> {
         let temp_dir = TempDir::new()?;
@@ -576,6 +1053,38 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_retry_locked() -> Result<()> {
+        #[cfg(windows)]
+        let locked = || std::io::Error::from_raw_os_error(32);
+        #[cfg(not(windows))]
+        let locked = || std::io::Error::from_raw_os_error(16);
+
+        let mut failures = 2;
+        let value = FileOperations::new().with_retry_locked(2).retry_locked(Path::new("a.txt"), || {
+            if failures > 0 {
+                failures -= 1;
+                return Err(locked());
+            }
+            Ok(7)
+        })?;
+        assert_eq!(value, 7);
+
+        // Out of retries, the error names the lock
+        let error = FileOperations::new().retry_locked(Path::new("a.txt"), || Err::<(), _>(locked())).unwrap_err();
+        assert!(format!("{:#}", error).contains("a.txt is locked by another process"));
+
+        // Other errors fail at once
+        let mut attempts = 0;
+        let result = FileOperations::new().with_retry_locked(3).retry_locked(Path::new("a.txt"), || {
+            attempts += 1;
+            Err::<(), _>(std::io::Error::from(std::io::ErrorKind::NotFound))
+        });
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+        Ok(())
+    }
+
     #[test]
     fn test_move_item() -> Result<()> {
         let temp_dir = TempDir::new()?;
@@ -627,6 +1136,83 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_copy_with_rewrite() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let file_ops = FileOperations::new();
+        let source = temp_dir.path().join("source.txt");
+        fs::write(&source, "old value\n")?;
+
+        let target = temp_dir.path().join("out").join("target.txt");
+        assert!(file_ops.copy_with_rewrite(&source, &target, "old", "new", false)?);
+        assert_eq!(fs::read_to_string(&target)?, "new value\n");
+        assert_eq!(fs::read_to_string(&source)?, "old value\n");
+
+        // Without matches the file is copied as it is
+        let unchanged = temp_dir.path().join("out").join("unchanged.txt");
+        assert!(!file_ops.copy_with_rewrite(&source, &unchanged, "missing", "new", false)?);
+        assert_eq!(fs::read_to_string(&unchanged)?, "old value\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_file_replaces_atomically() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let test_file = temp_dir.path().join("test.txt");
+        fs::write(&test_file, "old content that is longer")?;
+
+        for durability in [Durability::Fast, Durability::Safe] {
+            let file_ops = FileOperations::new().with_durability(durability);
+            file_ops.write_file(&test_file, b"new")?;
+            assert_eq!(fs::read_to_string(&test_file)?, "new");
+        }
+
+        // No temporary files are left behind
+        let names: Vec<_> = fs::read_dir(temp_dir.path())?.map(|entry| entry.unwrap().file_name()).collect();
+        assert_eq!(names, ["test.txt"]);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_file_keeps_permissions_and_symlinks() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new()?;
+        let file_ops = FileOperations::new();
+        let target = temp_dir.path().join("script.sh");
+        fs::write(&target, "echo old\n")?;
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o750))?;
+        let link = temp_dir.path().join("link.sh");
+        std::os::unix::fs::symlink(&target, &link)?;
+
+        file_ops.write_file(&link, b"echo new\n")?;
+        assert!(fs::symlink_metadata(&link)?.file_type().is_symlink());
+        assert_eq!(fs::read_to_string(&target)?, "echo new\n");
+        assert_eq!(fs::metadata(&target)?.permissions().mode() & 0o7777, 0o750);
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_write_file_replaces_readonly_file() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let file_ops = FileOperations::new();
+        let target = temp_dir.path().join("locked.txt");
+        fs::write(&target, "old\n")?;
+        let mut permissions = fs::metadata(&target)?.permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&target, permissions.clone())?;
+
+        file_ops.write_file(&target, b"new\n")?;
+        assert_eq!(fs::read_to_string(&target)?, "new\n");
+        assert!(fs::metadata(&target)?.permissions().readonly());
+
+        permissions.set_readonly(false);
+        fs::set_permissions(&target, permissions)?;
+        Ok(())
+    }
+
     #[test]
     fn test_create_backup() -> Result<()> {
         let temp_dir = TempDir::new()?;
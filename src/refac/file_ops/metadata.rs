@@ -0,0 +1,178 @@
+//! Native metadata a rewrite has to carry over besides what
+//! [`FileAttributes`](crate::scrap::FileAttributes) records. On Windows these
+//! are the readonly, hidden and system attributes and the access control
+//! list; elsewhere permissions, owner and extended attributes cover it all
+//! and this is empty.
+
+use anyhow::Result;
+use std::path::Path;
+
+#[cfg(windows)]
+use anyhow::Context;
+
+/// The attributes a rewritten file keeps
+#[cfg(windows)]
+const KEPT_ATTRIBUTES: u32 = windows_sys::Win32::Storage::FileSystem::FILE_ATTRIBUTE_READONLY
+    | windows_sys::Win32::Storage::FileSystem::FILE_ATTRIBUTE_HIDDEN
+    | windows_sys::Win32::Storage::FileSystem::FILE_ATTRIBUTE_SYSTEM
+    | windows_sys::Win32::Storage::FileSystem::FILE_ATTRIBUTE_NOT_CONTENT_INDEXED;
+
+/// Metadata of a file that is about to be replaced
+#[derive(Debug, Default)]
+pub(super) struct NativeMetadata {
+    #[cfg(windows)]
+    attributes: u32,
+    /// Self-relative security descriptor holding the DACL; `None` on
+    /// filesystems without ACLs. Kept in `u64`s for its alignment.
+    #[cfg(windows)]
+    security_descriptor: Option<Vec<u64>>,
+}
+
+#[cfg(windows)]
+impl NativeMetadata {
+    pub(super) fn capture(path: &Path) -> Result<Self> {
+        use std::os::windows::fs::MetadataExt;
+
+        let attributes = std::fs::metadata(path)
+            .with_context(|| format!("Failed to read metadata of {}", path.display()))?
+            .file_attributes();
+        Ok(Self { attributes, security_descriptor: read_dacl(path) })
+    }
+
+    /// Give `path` the recorded attributes and access control list
+    pub(super) fn apply(&self, path: &Path) -> Result<()> {
+        use std::os::windows::fs::MetadataExt;
+        use windows_sys::Win32::Security::{SetFileSecurityW, DACL_SECURITY_INFORMATION};
+
+        if let Some(descriptor) = &self.security_descriptor {
+            // SAFETY: the descriptor was filled in by GetFileSecurityW and the path is NUL terminated
+            let ok = unsafe { SetFileSecurityW(wide(path).as_ptr(), DACL_SECURITY_INFORMATION, descriptor.as_ptr() as *mut _) };
+            if ok == 0 {
+                return Err(std::io::Error::last_os_error())
+                    .with_context(|| format!("Failed to copy the access control list to {}", path.display()));
+            }
+        }
+
+        let current = std::fs::metadata(path)
+            .with_context(|| format!("Failed to read metadata of {}", path.display()))?
+            .file_attributes();
+        set_attributes(path, (current & !KEPT_ATTRIBUTES) | (self.attributes & KEPT_ATTRIBUTES))
+            .with_context(|| format!("Failed to copy file attributes to {}", path.display()))
+    }
+
+    /// Clear the readonly attribute of `path` so another file can be renamed
+    /// over it; the replacement carries it again through `apply`
+    pub(super) fn make_replaceable(&self, path: &Path) -> Result<()> {
+        use windows_sys::Win32::Storage::FileSystem::FILE_ATTRIBUTE_READONLY;
+
+        if self.attributes & FILE_ATTRIBUTE_READONLY == 0 {
+            return Ok(());
+        }
+        set_attributes(path, self.attributes & !FILE_ATTRIBUTE_READONLY).with_context(|| {
+            format!("{} is read-only and its attributes can't be changed (check its permissions)", path.display())
+        })
+    }
+
+    /// Put the recorded attributes back on `path` after a failed replace
+    pub(super) fn restore(&self, path: &Path) {
+        if let Err(e) = set_attributes(path, self.attributes) {
+            log::warn!("Failed to restore the attributes of {}: {}", path.display(), e);
+        }
+    }
+}
+
+#[cfg(not(windows))]
+impl NativeMetadata {
+    pub(super) fn capture(_path: &Path) -> Result<Self> {
+        Ok(Self::default())
+    }
+
+    pub(super) fn apply(&self, _path: &Path) -> Result<()> {
+        Ok(())
+    }
+
+    /// Renaming over a file needs no more than write access to its directory here
+    pub(super) fn make_replaceable(&self, _path: &Path) -> Result<()> {
+        Ok(())
+    }
+
+    pub(super) fn restore(&self, _path: &Path) {}
+}
+
+/// `path` as a NUL terminated UTF-16 string
+#[cfg(windows)]
+fn wide(path: &Path) -> Vec<u16> {
+    use std::os::windows::ffi::OsStrExt;
+
+    path.as_os_str().encode_wide().chain(std::iter::once(0)).collect()
+}
+
+#[cfg(windows)]
+fn set_attributes(path: &Path, attributes: u32) -> std::io::Result<()> {
+    use windows_sys::Win32::Storage::FileSystem::SetFileAttributesW;
+
+    // SAFETY: the path is NUL terminated
+    if unsafe { SetFileAttributesW(wide(path).as_ptr(), attributes) } == 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// The DACL of `path`, or `None` when the filesystem has none to give
+#[cfg(windows)]
+fn read_dacl(path: &Path) -> Option<Vec<u64>> {
+    use windows_sys::Win32::Security::{GetFileSecurityW, DACL_SECURITY_INFORMATION};
+
+    let name = wide(path);
+    let mut needed = 0u32;
+    // SAFETY: a null buffer of length 0 only asks for the size
+    unsafe { GetFileSecurityW(name.as_ptr(), DACL_SECURITY_INFORMATION, std::ptr::null_mut(), 0, &mut needed) };
+    if needed == 0 {
+        log::debug!("No access control list to keep on {}: {}", path.display(), std::io::Error::last_os_error());
+        return None;
+    }
+    let mut descriptor = vec![0u64; (needed as usize).div_ceil(8)];
+    // SAFETY: the buffer holds at least `needed` bytes
+    let ok = unsafe {
+        GetFileSecurityW(name.as_ptr(), DACL_SECURITY_INFORMATION, descriptor.as_mut_ptr() as *mut _, needed, &mut needed)
+    };
+    if ok == 0 {
+        log::debug!("Failed to read the access control list of {}: {}", path.display(), std::io::Error::last_os_error());
+        return None;
+    }
+    Some(descriptor)
+}
+
+#[cfg(all(test, windows))]
+mod tests {
+    use super::*;
+    use std::os::windows::fs::MetadataExt;
+    use tempfile::TempDir;
+    use windows_sys::Win32::Storage::FileSystem::{FILE_ATTRIBUTE_HIDDEN, FILE_ATTRIBUTE_NORMAL, FILE_ATTRIBUTE_READONLY};
+
+    #[test]
+    fn test_apply_copies_attributes() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let original = temp_dir.path().join("original.txt");
+        let copy = temp_dir.path().join("copy.txt");
+        std::fs::write(&original, "a")?;
+        std::fs::write(&copy, "b")?;
+        set_attributes(&original, FILE_ATTRIBUTE_READONLY | FILE_ATTRIBUTE_HIDDEN)?;
+
+        let metadata = NativeMetadata::capture(&original)?;
+        metadata.apply(&copy)?;
+        let attributes = std::fs::metadata(&copy)?.file_attributes();
+        assert_ne!(attributes & FILE_ATTRIBUTE_READONLY, 0);
+        assert_ne!(attributes & FILE_ATTRIBUTE_HIDDEN, 0);
+
+        metadata.make_replaceable(&original)?;
+        assert!(!std::fs::metadata(&original)?.permissions().readonly());
+        metadata.restore(&original);
+        assert!(std::fs::metadata(&original)?.permissions().readonly());
+
+        for path in [&original, &copy] {
+            set_attributes(path, FILE_ATTRIBUTE_NORMAL)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+//! External programs doing content replacement for formats the built-in text
+//! replacer would corrupt, such as notebooks, minified bundles or PDFs.
+//! Handlers are registered by file name glob in `ws.toml`:
+//!
+//! ```toml
+//! [refactor.handlers]
+//! "*.ipynb" = "ws-ipynb-handler --indent 1"
+//! ```
+//!
+//! The command (split on whitespace) gets the pattern and the substitute as
+//! its last two arguments, the file on stdin and its path in
+//! `WS_REFACTOR_FILE`, and prints the rewritten file on stdout. A nonzero
+//! exit status fails the file, which is then left alone.
+
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Environment variable holding the path of the file being rewritten
+pub const FILE_ENV: &str = "WS_REFACTOR_FILE";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentHandler {
+    /// File name glob (`*` and `?`), e.g. `*.ipynb`
+    pub glob: String,
+    pub command: String,
+}
+
+impl ContentHandler {
+    pub fn new(glob: impl Into<String>, command: impl Into<String>) -> Self {
+        Self { glob: glob.into(), command: command.into() }
+    }
+
+    pub fn matches(&self, path: &Path) -> bool {
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| glob_matches(self.glob.as_bytes(), name.as_bytes()))
+    }
+
+    /// Run the handler on `content`, returning the rewritten content
+    pub fn rewrite(&self, path: &Path, content: Vec<u8>, pattern: &str, substitute: &str) -> Result<Vec<u8>> {
+        let mut words = self.command.split_whitespace();
+        let program = words.next().with_context(|| format!("Empty handler command for {}", self.glob))?;
+        let mut child = Command::new(program)
+            .args(words)
+            .args([pattern, substitute])
+            .env(FILE_ENV, path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to run handler {} for {}", program, path.display()))?;
+
+        // Feed stdin from another thread so a handler that writes as it reads cannot block on a full pipe
+        let mut stdin = child.stdin.take().context("Handler stdin is not piped")?;
+        let writer = std::thread::spawn(move || stdin.write_all(&content));
+        let output = child.wait_with_output().with_context(|| format!("Handler {} failed", program))?;
+        // A handler may exit without reading everything; its exit status decides
+        let _ = writer.join();
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "Handler {} failed for {}: {}",
+                program,
+                path.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(output.stdout)
+    }
+}
+
+/// The first handler whose glob matches the file name of `path`
+pub fn handler_for<'a>(handlers: &'a [ContentHandler], path: &Path) -> Option<&'a ContentHandler> {
+    handlers.iter().find(|handler| handler.matches(path))
+}
+
+fn glob_matches(glob: &[u8], name: &[u8]) -> bool {
+    match (glob.first(), name.first()) {
+        (None, None) => true,
+        (Some(b'*'), _) => glob_matches(&glob[1..], name) || (!name.is_empty() && glob_matches(glob, &name[1..])),
+        (Some(b'?'), Some(_)) => glob_matches(&glob[1..], &name[1..]),
+        (Some(g), Some(n)) if g == n => glob_matches(&glob[1..], &name[1..]),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_matching() {
+        let handler = ContentHandler::new("*.ipynb", "cat");
+        assert!(handler.matches(Path::new("notebooks/analysis.ipynb")));
+        assert!(!handler.matches(Path::new("analysis.ipynb.bak")));
+        assert!(ContentHandler::new("bundle.?.min.js", "cat").matches(Path::new("bundle.1.min.js")));
+        let handlers = vec![ContentHandler::new("*.pdf", "a"), ContentHandler::new("*", "b")];
+        assert_eq!(handler_for(&handlers, Path::new("x.pdf")).unwrap().command, "a");
+        assert_eq!(handler_for(&handlers, Path::new("x.txt")).unwrap().command, "b");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_rewrite_runs_command() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let script = temp_dir.path().join("handler.sh");
+        std::fs::write(&script, "#!/bin/sh\necho \"$WS_REFACTOR_FILE\"\nsed \"s/$1/$2/g\"\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let handler = ContentHandler::new("*.txt", script.to_str().unwrap());
+        let output = handler.rewrite(Path::new("a.txt"), b"old content\n".to_vec(), "old", "new").unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "a.txt\nnew content\n");
+
+        let failing = ContentHandler::new("*.txt", "false");
+        assert!(failing.rewrite(Path::new("a.txt"), b"old".to_vec(), "old", "new").is_err());
+    }
+}
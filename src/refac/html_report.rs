@@ -0,0 +1,179 @@
+//! Standalone HTML report of a planned refactor (`--report-html`), to attach
+//! to a change review: the renames, per-file content diffs and collisions,
+//! with tables sortable by clicking a column header.
+
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use super::collision_detector::Collision;
+use super::rename_engine::{DetailedChangeReport, FileDiff};
+use crate::{ItemType, RenameConfig};
+
+pub(crate) const STYLE: &str = "
+body { font-family: -apple-system, 'Segoe UI', sans-serif; margin: 2em; color: #222; }
+h1 { font-size: 1.4em; }
+table { border-collapse: collapse; margin-bottom: 2em; }
+th, td { border: 1px solid #ddd; padding: 4px 8px; text-align: left; vertical-align: top; }
+th.sortable { cursor: pointer; background: #f4f4f4; }
+th.sortable:hover { background: #e8e8e8; }
+code, .diff td { font-family: ui-monospace, Menlo, monospace; font-size: 0.9em; white-space: pre-wrap; }
+.diff .before { background: #fdd; }
+.diff .after { background: #dfd; }
+.collision { background: #fee; }
+summary { cursor: pointer; margin: 4px 0; }
+";
+
+const SORT_SCRIPT: &str = "
+document.querySelectorAll('th.sortable').forEach(function (th) {
+  th.addEventListener('click', function () {
+    var table = th.closest('table'), body = table.tBodies[0];
+    var index = Array.prototype.indexOf.call(th.parentNode.children, th);
+    var ascending = th.dataset.order !== 'asc';
+    th.dataset.order = ascending ? 'asc' : 'desc';
+    var rows = Array.prototype.slice.call(body.rows);
+    rows.sort(function (a, b) {
+      var x = a.cells[index].textContent, y = b.cells[index].textContent;
+      var order = (isNaN(x) || isNaN(y)) ? x.localeCompare(y) : x - y;
+      return ascending ? order : -order;
+    });
+    rows.forEach(function (row) { body.appendChild(row); });
+  });
+});
+";
+
+/// The report as a complete HTML document
+pub fn render(
+    config: &RenameConfig,
+    report: &DetailedChangeReport,
+    diffs: &[(PathBuf, FileDiff)],
+    collisions: &[Collision],
+) -> String {
+    let relative = |path: &Path| escape(&path.strip_prefix(&config.root_dir).unwrap_or(path).display().to_string());
+    let stats = &report.total_stats;
+    let mut html = String::new();
+
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>Refactor plan: {pattern} → {substitute}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n\
+         <h1>Refactor plan: <code>{pattern}</code> → <code>{substitute}</code></h1>\n\
+         <p>Root <code>{root}</code>, generated {generated}. Nothing has been changed yet.</p>\n\
+         <table>\n<tr><th>Content changes</th><td>{content} file(s)</td></tr>\n<tr><th>File renames</th><td>{files}</td></tr>\n\
+         <tr><th>Directory renames</th><td>{dirs}</td></tr>\n<tr><th>Collisions</th><td>{collisions}</td></tr>\n</table>\n",
+        pattern = escape(&config.pattern),
+        substitute = escape(&config.substitute),
+        root = escape(&config.root_dir.display().to_string()),
+        generated = chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+        content = stats.files_with_content_changes,
+        files = stats.files_renamed,
+        dirs = stats.directories_renamed,
+        collisions = collisions.len(),
+    );
+
+    if !collisions.is_empty() {
+        html.push_str("<h2>Collisions</h2>\n<p>The refactor cannot run until these are resolved.</p>\n");
+        html.push_str(&table_head(&["Type", "Target", "Sources", "Description"]));
+        for collision in collisions {
+            let sources: Vec<String> = collision.source_paths.iter().map(|path| relative(path)).collect();
+            let _ = writeln!(
+                html,
+                "<tr class=\"collision\"><td>{:?}</td><td><code>{}</code></td><td><code>{}</code></td><td>{}</td></tr>",
+                collision.collision_type,
+                relative(&collision.target_path),
+                sources.join("<br>"),
+                escape(&collision.description)
+            );
+        }
+        html.push_str("</tbody>\n</table>\n");
+    }
+
+    let renames: Vec<_> = report.file_changes.iter().filter(|change| change.rename_target.is_some()).collect();
+    if !renames.is_empty() {
+        html.push_str("<h2>Renames</h2>\n");
+        html.push_str(&table_head(&["Type", "From", "To"]));
+        for change in renames {
+            let kind = match change.item_type {
+                ItemType::File => "file",
+                ItemType::Directory => "directory",
+            };
+            let target = change.rename_target.as_deref().map(|path| relative(path)).unwrap_or_default();
+            let _ = writeln!(html, "<tr><td>{}</td><td><code>{}</code></td><td><code>{}</code></td></tr>", kind, relative(&change.path), target);
+        }
+        html.push_str("</tbody>\n</table>\n");
+    }
+
+    let content_changes: Vec<_> = report.file_changes.iter().filter_map(|change| Some((change, change.content_changes?))).collect();
+    if !content_changes.is_empty() {
+        html.push_str("<h2>Content changes</h2>\n");
+        html.push_str(&table_head(&["File", "Occurrences"]));
+        for (change, occurrences) in content_changes {
+            let _ = writeln!(html, "<tr><td><code>{}</code></td><td>{}</td></tr>", relative(&change.path), occurrences);
+        }
+        html.push_str("</tbody>\n</table>\n");
+
+        html.push_str("<h2>Diffs</h2>\n");
+        for (path, diff) in diffs {
+            let _ = write!(html, "<details>\n<summary><code>{}</code>", relative(path));
+            write_diff(&mut html, diff);
+            html.push_str("</details>\n");
+        }
+    }
+
+    let _ = write!(html, "<script>{SORT_SCRIPT}</script>\n</body>\n</html>\n");
+    html
+}
+
+/// The rest of a diff's `<summary>` (its size) and its body, for a
+/// `<details>` element whose summary names the file
+pub(crate) fn write_diff(html: &mut String, diff: &FileDiff) {
+    match diff {
+        FileDiff::Lines(lines) => {
+            let _ = writeln!(html, " ({} line(s))</summary>\n<table class=\"diff\">", lines.len());
+            for change in lines {
+                let _ = writeln!(
+                    html,
+                    "<tr><td>{line}</td><td class=\"before\">- {}</td></tr>\n<tr><td>{line}</td><td class=\"after\">+ {}</td></tr>",
+                    escape(&change.before),
+                    escape(&change.after),
+                    line = change.line
+                );
+            }
+            html.push_str("</table>\n");
+        }
+        FileDiff::Handler(command) => {
+            let _ = writeln!(html, "</summary>\n<p>Rewritten by the handler <code>{}</code>.</p>", escape(command));
+        }
+        FileDiff::Archive(changes) => {
+            let _ = writeln!(html, " ({} member(s))</summary>\n<ul>", changes.len());
+            for change in changes {
+                let _ = writeln!(html, "<li><code>{}</code></li>", escape(&change.to_string()));
+            }
+            html.push_str("</ul>\n");
+        }
+        FileDiff::Unreadable => {
+            html.push_str("</summary>\n<p>Not readable as text; no preview.</p>\n");
+        }
+    }
+}
+
+fn table_head(columns: &[&str]) -> String {
+    let headers: String = columns.iter().map(|column| format!("<th class=\"sortable\">{}</th>", column)).collect();
+    format!("<table>\n<thead><tr>{}</tr></thead>\n<tbody>\n", headers)
+}
+
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("<a href=\"x\">&'"), "&lt;a href=&quot;x&quot;&gt;&amp;&#39;");
+    }
+}
@@ -0,0 +1,177 @@
+//! `--update-imports`: when a rename changes a module's name (`foo.py` →
+//! `bar.py`, `foo/` → `bar/`), references to it such as `import foo`,
+//! `mod foo;` or `require('./foo')` are updated across the tree in the same
+//! run. References are texts with a `{module}` placeholder per language,
+//! matched with identifier boundaries. They can be replaced or extended in
+//! `ws.toml`:
+//!
+//! ```toml
+//! [refactor.imports.go]
+//! extensions = ["go"]
+//! references = ["/{module}\""]
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::tokens::find_identifiers;
+use crate::{ItemType, RenameItem};
+
+/// Stands for the module name in reference patterns
+pub const MODULE_PLACEHOLDER: &str = "{module}";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ImportLanguage {
+    /// Extensions of the language's files, without the dot
+    pub extensions: Vec<String>,
+    /// Texts referring to a module, with `{module}` for its name
+    pub references: Vec<String>,
+}
+
+impl ImportLanguage {
+    fn new(extensions: &[&str], references: &[&str]) -> Self {
+        Self {
+            extensions: extensions.iter().map(|e| e.to_string()).collect(),
+            references: references.iter().map(|r| r.to_string()).collect(),
+        }
+    }
+
+    fn has_extension(&self, extension: &str) -> bool {
+        self.extensions.iter().any(|e| e == extension)
+    }
+}
+
+/// The built-in languages, by name
+pub fn default_languages() -> BTreeMap<String, ImportLanguage> {
+    BTreeMap::from([
+        (
+            "rust".to_string(),
+            ImportLanguage::new(&["rs"], &["mod {module};", "use {module}", "crate::{module}", "super::{module}", "self::{module}"]),
+        ),
+        (
+            "python".to_string(),
+            ImportLanguage::new(&["py", "pyi"], &["import {module}", "from {module} import", "from .{module} import", "from . import {module}"]),
+        ),
+        (
+            "javascript".to_string(),
+            ImportLanguage::new(
+                &["js", "jsx", "mjs", "cjs", "ts", "tsx"],
+                &["/{module}'", "/{module}\"", "/{module}.js'", "/{module}.js\""],
+            ),
+        ),
+    ])
+}
+
+/// A module renamed by a file or directory rename
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleRename {
+    pub old: String,
+    pub new: String,
+    /// The file's extension; directories can be modules of any language
+    pub extension: Option<String>,
+}
+
+impl ModuleRename {
+    pub fn from_rename(item: &RenameItem) -> Option<Self> {
+        let name = |path: &Path| match item.item_type {
+            ItemType::File => path.file_stem(),
+            ItemType::Directory => path.file_name(),
+        }
+        .and_then(|name| name.to_str())
+        .map(str::to_string);
+        let (old, new) = (name(&item.original_path)?, name(&item.new_path)?);
+        let extension = match item.item_type {
+            ItemType::File => Some(item.original_path.extension()?.to_str()?.to_string()),
+            ItemType::Directory => None,
+        };
+        (old != new).then_some(Self { old, new, extension })
+    }
+}
+
+/// The (old, new) reference texts to update in a file with `extension`
+pub fn reference_replacements(languages: &[ImportLanguage], extension: &str, renames: &[ModuleRename]) -> Vec<(String, String)> {
+    let mut replacements = Vec::new();
+    for language in languages.iter().filter(|language| language.has_extension(extension)) {
+        let renames = renames
+            .iter()
+            .filter(|rename| rename.extension.as_deref().is_none_or(|e| language.has_extension(e)));
+        for rename in renames {
+            for reference in &language.references {
+                let replacement = (reference.replace(MODULE_PLACEHOLDER, &rename.old), reference.replace(MODULE_PLACEHOLDER, &rename.new));
+                if !replacements.contains(&replacement) {
+                    replacements.push(replacement);
+                }
+            }
+        }
+    }
+    replacements
+}
+
+/// `content` with the references replaced, and the number replaced
+pub fn update_references(content: &str, replacements: &[(String, String)]) -> (String, usize) {
+    let mut content = content.to_string();
+    let mut count = 0;
+    for (old, new) in replacements {
+        let starts = find_identifiers(&content, old);
+        if starts.is_empty() {
+            continue;
+        }
+        count += starts.len();
+        let mut updated = String::with_capacity(content.len());
+        let mut last = 0;
+        for start in starts {
+            updated.push_str(&content[last..start]);
+            updated.push_str(new);
+            last = start + old.len();
+        }
+        updated.push_str(&content[last..]);
+        content = updated;
+    }
+    (content, count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn rename(from: &str, to: &str, item_type: ItemType) -> Option<ModuleRename> {
+        ModuleRename::from_rename(&RenameItem {
+            original_path: PathBuf::from(from),
+            new_path: PathBuf::from(to),
+            item_type,
+            depth: 1,
+        })
+    }
+
+    #[test]
+    fn test_module_renames() {
+        assert_eq!(
+            rename("src/user.py", "src/account.py", ItemType::File),
+            Some(ModuleRename { old: "user".into(), new: "account".into(), extension: Some("py".into()) })
+        );
+        assert_eq!(rename("src/user", "src/account", ItemType::Directory).unwrap().extension, None);
+        // Names without a module change
+        assert_eq!(rename("README", "README", ItemType::File), None);
+        assert_eq!(rename("Makefile", "Newfile", ItemType::File), None);
+    }
+
+    #[test]
+    fn test_update_references() {
+        let languages: Vec<ImportLanguage> = default_languages().into_values().collect();
+        let renames = vec![rename("user.py", "account.py", ItemType::File).unwrap()];
+        assert!(reference_replacements(&languages, "rs", &renames).is_empty());
+
+        let replacements = reference_replacements(&languages, "py", &renames);
+        let (updated, count) = update_references("import user\nimport username\nfrom user import load\nuser = 1\n", &replacements);
+        assert_eq!(updated, "import account\nimport username\nfrom account import load\nuser = 1\n");
+        assert_eq!(count, 2);
+
+        let renames = vec![rename("src/user", "src/account", ItemType::Directory).unwrap()];
+        let replacements = reference_replacements(&languages, "ts", &renames);
+        let (updated, _) = update_references("import { a } from './user';\nimport b from './user_list';\n", &replacements);
+        assert_eq!(updated, "import { a } from './account';\nimport b from './user_list';\n");
+    }
+}
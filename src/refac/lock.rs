@@ -0,0 +1,102 @@
+//! Lock keeping two `ws refactor` runs off the same root, whose renames
+//! would otherwise interleave. The lock file lives in `$XDG_RUNTIME_DIR` (the
+//! temp directory where that is not set) rather than under the root, where
+//! the run would find it, and is named after a hash of the root's path. The
+//! operating system releases the lock when its owner exits, so a crashed run
+//! never leaves the root locked.
+
+use crate::file_lock::{holder, FileLock};
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+/// Held for the length of a run on a root; released on drop
+#[derive(Debug)]
+pub struct RootLock {
+    _lock: FileLock,
+}
+
+impl RootLock {
+    /// Take the lock of `root`. When another run holds it this fails, or
+    /// with `wait` calls `on_wait` with the holder's process id and blocks
+    /// until the lock is released.
+    pub fn acquire(root: &Path, wait: bool, on_wait: impl FnOnce(&str)) -> Result<Self> {
+        let path = lock_path(root);
+        let lock = if wait {
+            FileLock::acquire(&path, on_wait)?
+        } else {
+            match FileLock::try_acquire(&path)? {
+                Some(lock) => lock,
+                None => anyhow::bail!(
+                    "Another refactor (process {}) is running on {}; pass --wait to wait for it to finish",
+                    holder(&path),
+                    root.display()
+                ),
+            }
+        };
+        Ok(Self { _lock: lock })
+    }
+}
+
+/// The lock file of `root`, which should be canonical so every spelling of
+/// it gets the same lock
+pub fn lock_path(root: &Path) -> PathBuf {
+    use sha2::{Digest, Sha256};
+    let digest = Sha256::digest(root.to_string_lossy().as_bytes());
+    let id: String = digest.iter().take(8).map(|b| format!("{:02x}", b)).collect();
+    let dir = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_dir())
+        .unwrap_or_else(std::env::temp_dir);
+    dir.join(format!("ws-refac-{}.lock", id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::{Duration, Instant};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_lock_is_exclusive_until_dropped() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let lock = RootLock::acquire(temp_dir.path(), false, |_| {})?;
+        assert!(lock_path(temp_dir.path()).exists());
+
+        let message = RootLock::acquire(temp_dir.path(), false, |_| {}).unwrap_err().to_string();
+        assert!(message.contains(&format!("process {}", std::process::id())), "{}", message);
+
+        drop(lock);
+        drop(RootLock::acquire(temp_dir.path(), false, |_| {})?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_wait_for_release() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let lock = RootLock::acquire(temp_dir.path(), false, |_| {})?;
+        let release = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(300));
+            drop(lock);
+        });
+
+        let started = Instant::now();
+        let mut waited_for = None;
+        let _lock = RootLock::acquire(temp_dir.path(), true, |pid| waited_for = Some(pid.to_string()))?;
+        assert!(started.elapsed() >= Duration::from_millis(250));
+        assert_eq!(waited_for, Some(std::process::id().to_string()));
+        release.join().unwrap();
+        Ok(())
+    }
+
+    #[test]
+    fn test_lock_of_a_crashed_run_is_free() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = lock_path(temp_dir.path());
+        fs::write(&path, "999999\n")?;
+
+        let _lock = RootLock::acquire(temp_dir.path(), false, |_| {})?;
+        assert_eq!(fs::read_to_string(&path)?, std::process::id().to_string());
+        Ok(())
+    }
+}
@@ -0,0 +1,433 @@
+//! Message catalog for the human-readable output of `ws refactor`: status
+//! prefixes, progress bars, the summary, the diff preview and the final
+//! report. `--format plain` and `--format json` stay in English so scripts
+//! can parse them.
+//!
+//! The locale comes from `WS_LOCALE`, then `LC_ALL`, `LC_MESSAGES` and `LANG`
+//! (`de_DE.UTF-8` → `de_DE`, then `de`). English and German are built in.
+//! Other locales are read from `<locale>.toml` in `WS_LOCALE_DIR`
+//! (default `/usr/share/ws/locale`), a flat table of message keys to texts
+//! with `{name}` placeholders:
+//!
+//! ```toml
+//! "summary.title" = "=== CHANGEMENTS PRÉVUS ==="
+//! "summary.file_renames" = "Fichiers renommés :   {count}"
+//! ```
+//!
+//! Keys a catalog lacks fall back to English.
+
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Directory of catalog files when `WS_LOCALE_DIR` is not set
+pub const DEFAULT_LOCALE_DIR: &str = "/usr/share/ws/locale";
+
+static CATALOG: OnceLock<Catalog> = OnceLock::new();
+
+const EN: &[(&str, &str)] = &[
+    ("status.error", "ERROR:"),
+    ("status.warning", "WARNING:"),
+    ("status.info", "INFO:"),
+    ("status.success", "SUCCESS:"),
+    ("status.verbose", "VERBOSE:"),
+    ("progress.scanning", "Scanning files and directories..."),
+    ("progress.scanned", "Scanned: {path}"),
+    ("progress.discovery_done", "Discovery complete"),
+    ("progress.content_label", "Content:"),
+    ("progress.content", "Replacing content"),
+    ("progress.content_file", "Processing: {path}"),
+    ("progress.content_done", "Content replacement complete ({count} files)"),
+    ("progress.rename_label", "Rename:"),
+    ("progress.rename", "Renaming files/directories"),
+    ("progress.rename_item", "Renaming: {path}"),
+    ("progress.rename_done", "Rename complete ({successful} successful, {failed} failed)"),
+    ("header.title", "=== WORKSPACE REFAC TOOL ==="),
+    ("header.root", "Root directory: {path}"),
+    ("header.pattern", "Pattern: '{pattern}'"),
+    ("header.substitute", "Substitute: '{substitute}'"),
+    ("header.mode", "Mode: {mode}"),
+    ("header.backup", "Backup mode: Enabled"),
+    ("phase.discovery", "Phase 1: Discovering files and directories..."),
+    ("phase.collisions", "Phase 2: Checking for naming collisions..."),
+    ("phase.validation", "Phase 3: Validating all operations..."),
+    ("run.no_changes", "No changes needed."),
+    ("run.cancelled", "Operation cancelled by user."),
+    ("lock.waiting", "Waiting for another refactor of {path} (process {pid}) to finish..."),
+    ("run.html_report", "Wrote report of {changes} planned change(s) and {collisions} collision(s) to {path}; nothing was changed"),
+    ("preview.serving", "Review the plan at {url}; nothing changes until it is applied there"),
+    ("preview.applied", "Applied {renames} rename(s) and {files} content change(s) accepted in the review"),
+    ("preview.cancelled", "Review cancelled; nothing was changed"),
+    ("verify.passed", "Plan verified: all {count} check(s) passed"),
+    ("verify.failed", "Plan not fully applied: {failed} of {count} check(s) failed"),
+    ("confirm.warning", "This operation will modify your files and directories."),
+    ("confirm.copy_warning", "This operation will write the refactored tree to {path}; the originals are left untouched."),
+    ("confirm.prompt", "Do you want to proceed?"),
+    ("summary.title", "=== PLANNED CHANGES ==="),
+    ("summary.affected", "Total files/directories affected: {count}"),
+    ("summary.content", "Content modifications: {count} file(s)"),
+    ("summary.file_renames", "File renames:         {count} file(s)"),
+    ("summary.directory_renames", "Directory renames:    {count} directory(ies)"),
+    ("summary.details_title", "=== DETAILED CHANGES BY LOCATION ==="),
+    ("summary.content_detail", "   Content: {count} occurrence(s) of '{pattern}' → '{substitute}'"),
+    ("summary.rename_detail", "   Rename:  {from} → {to}"),
+    ("summary.skipped", "Skipped:              {count} path(s) ({reasons})"),
+    ("summary.skipped_hint", "   Run with --show-skipped to list them"),
+    ("skipped.hidden", "hidden"),
+    ("skipped.excluded", "excluded by --exclude"),
+    ("skipped.not_included", "not matched by --include"),
+    ("skipped.excluded_content", "excluded by --exclude-containing"),
+    ("skipped.binary", "binary, content not searched"),
+    ("skipped.too_large", "over the archive size limit"),
+    ("skipped.unreadable", "unreadable archive"),
+    ("skipped.walk_error", "unreadable"),
+    ("skipped.symlink_cycle", "symbolic link to a directory already walked"),
+    ("skipped.symlink_depth", "too many symbolic links in a row"),
+    ("discovery.symlink_cycle", "Not following {path}: it leads to a directory already walked"),
+    ("discovery.symlink_depth", "Not following {path}: more than {max} symbolic links in a row"),
+    ("preview.title", "=== DIFF PREVIEW ==="),
+    ("preview.more_files", "... and {count} more files"),
+    ("preview.update", "\n⏺ Update({path})"),
+    ("preview.handler", "\n⏺ Update({path}) with handler {command}"),
+    ("preview.archive", "\n⏺ Update({path}) archive members:"),
+    ("preview.unreadable", "⚠️  Cannot preview {path}: unable to read file"),
+    ("preview.counts", "  ⎿  Updated {path} with {additions} additions and {removals} removals"),
+    ("imports.title", "=== IMPORTS OF RENAMED MODULES ==="),
+    ("imports.file", "  {path} ({count} reference(s))"),
+    ("execute.content", "Replacing content in files..."),
+    ("execute.modified", "Modified: {path}"),
+    ("execute.threads_tuned", "Replacing content with {threads} threads"),
+    ("execute.imports", "Updating imports of renamed modules..."),
+    ("execute.imports_file", "Updated {count} import reference(s) in {path}"),
+    ("execute.renames", "Renaming files and directories..."),
+    ("execute.renamed", "Renamed: {from} → {to}"),
+    ("execute.rename_failures", "{failed} rename operation(s) failed out of {total}"),
+    ("execute.rename_skipped", "Skipped {path}: it depends on the failed rename of {failed}"),
+    ("execute.rolled_back", "Rolled back {renames} rename(s) and {files} file content change(s)"),
+    ("execute.time_limit", "Time limit reached: {content} content change(s) and {renames} rename(s) left; checkpoint written to {path}"),
+    ("execute.left_content", "Left: content of {path}"),
+    ("execute.left_rename", "Left: {from} → {to}"),
+    ("execute.copy", "Writing the refactored tree to {path}..."),
+    ("execute.copied", "Written: {from} → {to}"),
+    ("validation.passed", "Validation passed: All operations can be performed safely."),
+    ("validation.title", "=== VALIDATION FAILED ==="),
+    ("validation.count", "Found {count} issue(s) that prevent safe execution:"),
+    ("validation.group", "📋 {group}: ({count} issue(s))"),
+    ("validation.location", "   📍 Location: {path}"),
+    ("validation.problem", "      Problem: {message}"),
+    ("validation.suggestion", "      Suggestion: {suggestion}"),
+    ("validation.cannot_proceed", "❌ Cannot proceed until these issues are resolved."),
+    ("validation.missing", "Missing Files/Directories"),
+    ("validation.permissions", "Permission Issues"),
+    ("validation.encoding", "Encoding/Binary File Issues"),
+    ("validation.file_type", "File Type Issues"),
+    ("validation.read_only", "Read-Only Files"),
+    ("validation.target_exists", "Target Conflicts"),
+    ("validation.parent_directory", "Directory Creation Issues"),
+    ("validation.content", "Content Issues"),
+    ("validation.empty_directory", "Directory Structure Issues"),
+    ("validation.locked", "Locked Files"),
+    ("validation.locked_retry", "{path} is locked by another process; retrying up to {count} time(s)"),
+    ("validation.other", "Other Issues"),
+    ("report.title", "=== OPERATION COMPLETE ==="),
+    ("report.success", "Operation completed successfully!"),
+    ("report.total", "Total changes applied: {count}"),
+    ("report.new_root", "Root directory is now {path}"),
+    ("report.output_dir", "Refactored tree written to {path}"),
+    ("report.errors", "{count} error(s) occurred:"),
+    ("report.archives", "Modified archive members:"),
+];
+
+const DE: &[(&str, &str)] = &[
+    ("status.error", "FEHLER:"),
+    ("status.warning", "WARNUNG:"),
+    ("status.info", "INFO:"),
+    ("status.success", "ERFOLG:"),
+    ("status.verbose", "DETAIL:"),
+    ("progress.scanning", "Dateien und Verzeichnisse werden durchsucht..."),
+    ("progress.scanned", "Durchsucht: {path}"),
+    ("progress.discovery_done", "Suche abgeschlossen"),
+    ("progress.content_label", "Inhalt:"),
+    ("progress.content", "Inhalte werden ersetzt"),
+    ("progress.content_file", "Verarbeite: {path}"),
+    ("progress.content_done", "Ersetzen abgeschlossen ({count} Dateien)"),
+    ("progress.rename_label", "Umbenennen:"),
+    ("progress.rename", "Dateien/Verzeichnisse werden umbenannt"),
+    ("progress.rename_item", "Benenne um: {path}"),
+    ("progress.rename_done", "Umbenennen abgeschlossen ({successful} erfolgreich, {failed} fehlgeschlagen)"),
+    ("header.title", "=== WORKSPACE REFAC-WERKZEUG ==="),
+    ("header.root", "Stammverzeichnis: {path}"),
+    ("header.pattern", "Muster: '{pattern}'"),
+    ("header.substitute", "Ersetzung: '{substitute}'"),
+    ("header.mode", "Modus: {mode}"),
+    ("header.backup", "Sicherungsmodus: Aktiviert"),
+    ("phase.discovery", "Phase 1: Dateien und Verzeichnisse werden gesucht..."),
+    ("phase.collisions", "Phase 2: Namenskollisionen werden geprüft..."),
+    ("phase.validation", "Phase 3: Alle Operationen werden geprüft..."),
+    ("run.no_changes", "Keine Änderungen nötig."),
+    ("run.cancelled", "Vorgang vom Benutzer abgebrochen."),
+    ("lock.waiting", "Warte, bis ein anderer Refactor-Lauf in {path} (Prozess {pid}) endet..."),
+    ("run.html_report", "Bericht über {changes} geplante Änderung(en) und {collisions} Kollision(en) nach {path} geschrieben; nichts wurde geändert"),
+    ("preview.serving", "Plan unter {url} prüfen; nichts wird geändert, bevor er dort angewendet wird"),
+    ("preview.applied", "{renames} Umbenennung(en) und {files} Inhaltsänderung(en) aus der Prüfung angewendet"),
+    ("preview.cancelled", "Prüfung abgebrochen; nichts wurde geändert"),
+    ("verify.passed", "Plan bestätigt: alle {count} Prüfung(en) bestanden"),
+    ("verify.failed", "Plan nicht vollständig angewendet: {failed} von {count} Prüfung(en) fehlgeschlagen"),
+    ("confirm.warning", "Dieser Vorgang ändert Ihre Dateien und Verzeichnisse."),
+    ("confirm.copy_warning", "Der umgestaltete Baum wird nach {path} geschrieben; die Originale bleiben unverändert."),
+    ("confirm.prompt", "Möchten Sie fortfahren?"),
+    ("summary.title", "=== GEPLANTE ÄNDERUNGEN ==="),
+    ("summary.affected", "Betroffene Dateien/Verzeichnisse: {count}"),
+    ("summary.content", "Inhaltsänderungen:     {count} Datei(en)"),
+    ("summary.file_renames", "Dateiumbenennungen:    {count} Datei(en)"),
+    ("summary.directory_renames", "Verzeichnisumbenennungen: {count} Verzeichnis(se)"),
+    ("summary.details_title", "=== ÄNDERUNGEN NACH ORT ==="),
+    ("summary.content_detail", "   Inhalt: {count} Vorkommen von '{pattern}' → '{substitute}'"),
+    ("summary.rename_detail", "   Umbenennen: {from} → {to}"),
+    ("summary.skipped", "Übersprungen:          {count} Pfad(e) ({reasons})"),
+    ("summary.skipped_hint", "   Mit --show-skipped werden sie aufgelistet"),
+    ("skipped.hidden", "versteckt"),
+    ("skipped.excluded", "durch --exclude ausgeschlossen"),
+    ("skipped.not_included", "von --include nicht erfasst"),
+    ("skipped.excluded_content", "durch --exclude-containing ausgeschlossen"),
+    ("skipped.binary", "binär, Inhalt nicht durchsucht"),
+    ("skipped.too_large", "über der Archivgrößengrenze"),
+    ("skipped.unreadable", "unlesbares Archiv"),
+    ("skipped.walk_error", "unlesbar"),
+    ("skipped.symlink_cycle", "symbolischer Link auf ein bereits durchlaufenes Verzeichnis"),
+    ("skipped.symlink_depth", "zu viele symbolische Links hintereinander"),
+    ("discovery.symlink_cycle", "{path} wird nicht gefolgt: führt zu einem bereits durchlaufenen Verzeichnis"),
+    ("discovery.symlink_depth", "{path} wird nicht gefolgt: mehr als {max} symbolische Links hintereinander"),
+    ("preview.title", "=== VORSCHAU DER ÄNDERUNGEN ==="),
+    ("preview.more_files", "... und {count} weitere Dateien"),
+    ("preview.update", "\n⏺ Änderung({path})"),
+    ("preview.handler", "\n⏺ Änderung({path}) mit Handler {command}"),
+    ("preview.archive", "\n⏺ Änderung({path}) Archivmitglieder:"),
+    ("preview.unreadable", "⚠️  Keine Vorschau für {path}: Datei nicht lesbar"),
+    ("preview.counts", "  ⎿  {path} geändert: {additions} Hinzufügungen und {removals} Entfernungen"),
+    ("imports.title", "=== IMPORTE UMBENANNTER MODULE ==="),
+    ("imports.file", "  {path} ({count} Verweis(e))"),
+    ("execute.content", "Inhalte in Dateien werden ersetzt..."),
+    ("execute.modified", "Geändert: {path}"),
+    ("execute.threads_tuned", "Inhalte werden mit {threads} Threads ersetzt"),
+    ("execute.imports", "Importe umbenannter Module werden aktualisiert..."),
+    ("execute.imports_file", "{count} Importverweis(e) in {path} aktualisiert"),
+    ("execute.renames", "Dateien und Verzeichnisse werden umbenannt..."),
+    ("execute.renamed", "Umbenannt: {from} → {to}"),
+    ("execute.rename_failures", "{failed} von {total} Umbenennung(en) fehlgeschlagen"),
+    ("execute.rename_skipped", "{path} übersprungen: hängt von der fehlgeschlagenen Umbenennung von {failed} ab"),
+    ("execute.rolled_back", "{renames} Umbenennung(en) und {files} Inhaltsänderung(en) rückgängig gemacht"),
+    ("execute.time_limit", "Zeitlimit erreicht: {content} Inhaltsänderung(en) und {renames} Umbenennung(en) offen; Checkpoint nach {path} geschrieben"),
+    ("execute.left_content", "Offen: Inhalt von {path}"),
+    ("execute.left_rename", "Offen: {from} → {to}"),
+    ("execute.copy", "Der umgestaltete Baum wird nach {path} geschrieben..."),
+    ("execute.copied", "Geschrieben: {from} → {to}"),
+    ("validation.passed", "Prüfung bestanden: Alle Operationen können sicher ausgeführt werden."),
+    ("validation.title", "=== PRÜFUNG FEHLGESCHLAGEN ==="),
+    ("validation.count", "{count} Problem(e) verhindern eine sichere Ausführung:"),
+    ("validation.group", "📋 {group}: ({count} Problem(e))"),
+    ("validation.location", "   📍 Ort: {path}"),
+    ("validation.problem", "      Problem: {message}"),
+    ("validation.suggestion", "      Vorschlag: {suggestion}"),
+    ("validation.cannot_proceed", "❌ Fortfahren erst möglich, wenn diese Probleme behoben sind."),
+    ("validation.missing", "Fehlende Dateien/Verzeichnisse"),
+    ("validation.permissions", "Berechtigungsprobleme"),
+    ("validation.encoding", "Kodierungs-/Binärdateiprobleme"),
+    ("validation.file_type", "Dateitypprobleme"),
+    ("validation.read_only", "Schreibgeschützte Dateien"),
+    ("validation.target_exists", "Zielkonflikte"),
+    ("validation.parent_directory", "Probleme beim Anlegen von Verzeichnissen"),
+    ("validation.content", "Inhaltsprobleme"),
+    ("validation.empty_directory", "Probleme der Verzeichnisstruktur"),
+    ("validation.locked", "Gesperrte Dateien"),
+    ("validation.locked_retry", "{path} ist von einem anderen Prozess gesperrt; bis zu {count} weitere(r) Versuch(e)"),
+    ("validation.other", "Sonstige Probleme"),
+    ("report.title", "=== VORGANG ABGESCHLOSSEN ==="),
+    ("report.success", "Vorgang erfolgreich abgeschlossen!"),
+    ("report.total", "Angewendete Änderungen: {count}"),
+    ("report.new_root", "Das Wurzelverzeichnis ist jetzt {path}"),
+    ("report.output_dir", "Umgestalteter Baum nach {path} geschrieben"),
+    ("report.errors", "{count} Fehler aufgetreten:"),
+    ("report.archives", "Geänderte Archivmitglieder:"),
+];
+
+/// The messages of one locale
+#[derive(Debug, Clone, PartialEq)]
+pub struct Catalog {
+    locale: String,
+    messages: HashMap<String, String>,
+}
+
+impl Catalog {
+    pub fn english() -> Self {
+        Self::from_pairs("en", EN)
+    }
+
+    /// A built-in catalog: `en` or `de`
+    pub fn builtin(locale: &str) -> Option<Self> {
+        match locale {
+            "en" => Some(Self::english()),
+            "de" => Some(Self::from_pairs("de", DE)),
+            _ => None,
+        }
+    }
+
+    /// A catalog read from a TOML file of message keys to texts
+    pub fn load(locale: &str, path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read message catalog: {}", path.display()))?;
+        let messages = toml::from_str(&text)
+            .with_context(|| format!("Invalid message catalog: {}", path.display()))?;
+        Ok(Self { locale: locale.to_string(), messages })
+    }
+
+    /// The catalog for a locale such as `de_DE.UTF-8`: built in, from
+    /// `dir`, or English when there is none
+    pub fn for_locale(locale: &str, dir: &Path) -> Self {
+        let name = locale.split(['.', '@']).next().unwrap_or_default();
+        let language = name.split(['_', '-']).next().unwrap_or_default().to_lowercase();
+        for candidate in [name, language.as_str()] {
+            if candidate.is_empty() {
+                continue;
+            }
+            if let Some(catalog) = Self::builtin(candidate) {
+                return catalog;
+            }
+            let path = dir.join(format!("{}.toml", candidate));
+            if path.is_file() {
+                match Self::load(candidate, &path) {
+                    Ok(catalog) => return catalog,
+                    Err(e) => log::warn!("{:#}", e),
+                }
+            }
+        }
+        Self::english()
+    }
+
+    fn from_pairs(locale: &str, pairs: &[(&str, &str)]) -> Self {
+        let messages = pairs.iter().map(|(key, text)| (key.to_string(), text.to_string())).collect();
+        Self { locale: locale.to_string(), messages }
+    }
+
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// The text of `key`, in English if this catalog lacks it
+    pub fn text<'a>(&'a self, key: &'a str) -> &'a str {
+        self.messages
+            .get(key)
+            .map(String::as_str)
+            .or_else(|| EN.iter().find(|(k, _)| *k == key).map(|(_, text)| *text))
+            .unwrap_or(key)
+    }
+
+    /// The text of `key` with its `{name}` placeholders filled from `args`
+    pub fn format(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        let template = self.text(key);
+        let mut message = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            message.push_str(&rest[..start]);
+            let placeholder = rest[start + 1..].find('}').map(|end| &rest[start + 1..start + 1 + end]);
+            match placeholder.and_then(|name| args.iter().find(|(arg, _)| *arg == name).map(|arg| (name, arg.1))) {
+                Some((name, value)) => {
+                    message.push_str(&value.to_string());
+                    rest = &rest[start + name.len() + 2..];
+                }
+                None => {
+                    message.push('{');
+                    rest = &rest[start + 1..];
+                }
+            }
+        }
+        message.push_str(rest);
+        message
+    }
+}
+
+/// The locale from `WS_LOCALE`, `LC_ALL`, `LC_MESSAGES` or `LANG`
+pub fn locale_from_env() -> String {
+    ["WS_LOCALE", "LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .filter(|value| value != "C" && value != "POSIX")
+        .unwrap_or_else(|| "en".to_string())
+}
+
+/// The catalog in use, chosen from the environment unless `install` was called first
+pub fn catalog() -> &'static Catalog {
+    CATALOG.get_or_init(|| {
+        let dir = std::env::var_os("WS_LOCALE_DIR").map(PathBuf::from).unwrap_or_else(|| PathBuf::from(DEFAULT_LOCALE_DIR));
+        Catalog::for_locale(&locale_from_env(), &dir)
+    })
+}
+
+/// Use `catalog` for all output, for programs embedding the refactor engine
+pub fn install(catalog: Catalog) -> Result<()> {
+    CATALOG
+        .set(catalog)
+        .map_err(|_| anyhow::anyhow!("A message catalog is already in use"))
+}
+
+/// A message from the catalog in use, e.g. `msg!("report.total", count = 3)`
+macro_rules! msg {
+    ($key:expr) => {
+        $crate::refac::messages::catalog().text($key)
+    };
+    ($key:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::refac::messages::catalog().format($key, &[$((stringify!($name), &$value as &dyn std::fmt::Display)),+])
+    };
+}
+pub(crate) use msg;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placeholders(text: &str) -> Vec<&str> {
+        let mut names: Vec<&str> = text.split('{').skip(1).filter_map(|part| part.split_once('}').map(|(name, _)| name)).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_builtin_catalogs_match() {
+        assert_eq!(DE.len(), EN.len());
+        for (key, text) in EN {
+            let translated = DE.iter().find(|(k, _)| k == key).map(|(_, text)| *text);
+            let translated = translated.unwrap_or_else(|| panic!("German catalog lacks {}", key));
+            assert_eq!(placeholders(translated), placeholders(text), "{}", key);
+        }
+    }
+
+    #[test]
+    fn test_format() {
+        let catalog = Catalog::english();
+        assert_eq!(catalog.format("report.total", &[("count", &3)]), "Total changes applied: 3");
+        assert_eq!(
+            catalog.format("execute.renamed", &[("from", &"{to}"), ("to", &"b")]),
+            "Renamed: {to} → b"
+        );
+        // Unknown keys and missing arguments are shown as they are
+        assert_eq!(catalog.text("no.such.key"), "no.such.key");
+        assert_eq!(catalog.format("header.root", &[]), "Root directory: {path}");
+    }
+
+    #[test]
+    fn test_locale_catalogs() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("fr.toml"), "\"report.total\" = \"Changements appliqués : {count}\"\n").unwrap();
+
+        assert_eq!(Catalog::for_locale("de_DE.UTF-8", temp_dir.path()).locale(), "de");
+        assert_eq!(Catalog::for_locale("pt_BR", temp_dir.path()).locale(), "en");
+
+        let french = Catalog::for_locale("fr_FR.UTF-8", temp_dir.path());
+        assert_eq!(french.locale(), "fr");
+        assert_eq!(french.format("report.total", &[("count", &2)]), "Changements appliqués : 2");
+        assert_eq!(french.text("report.title"), "=== OPERATION COMPLETE ===");
+    }
+}
@@ -1,9 +1,24 @@
 pub mod cli;
 pub mod file_ops;
+pub mod handlers;
+pub mod html_report;
+pub mod imports;
+pub mod lock;
+pub mod messages;
+pub mod output_schema;
+pub mod preview_server;
 pub mod rename_engine;
+pub mod server;
+pub mod stats;
 pub mod collision_detector;
+pub mod archives;
 pub mod binary_detector;
 pub mod progress;
+pub mod reporter;
+pub mod tokens;
+pub mod tuning;
+pub mod verify;
 
-pub use cli::{Args, Mode};
-pub use rename_engine::RenameEngine;
\ No newline at end of file
+pub use cli::{Args, Mode, RefacCommand};
+pub use rename_engine::RenameEngine;
+pub use reporter::{Phase, Reporter};
\ No newline at end of file
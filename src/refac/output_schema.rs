@@ -0,0 +1,62 @@
+//! Versioned JSON output of `--format json`, the `--serve` plan method,
+//! the `--time-limit` checkpoint and `ws refactor verify`.
+//! Every document carries `schema_version` and a `kind`; within a version
+//! fields are only added, never removed, renamed or given a new meaning, so
+//! parsers written against one release keep working with the next.
+
+use serde_json::{json, Value};
+
+use super::collision_detector::Collision;
+
+/// Raised only for changes that are not additive
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// JSON schema of the documents, printed by `ws refactor schema`
+pub const SCHEMA: &str = include_str!("../../schemas/refac-output.schema.json");
+
+/// `body` tagged with the schema version and `kind`
+pub fn document(kind: &str, mut body: Value) -> Value {
+    body["schema_version"] = json!(SCHEMA_VERSION);
+    body["kind"] = json!(kind);
+    body
+}
+
+/// A collision as the `collisions` and `plan` documents list it
+pub fn collision(collision: &Collision) -> Value {
+    json!({
+        "type": format!("{:?}", collision.collision_type),
+        "target": collision.target_path,
+        "sources": collision.source_paths,
+        "description": collision.description,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::refac::collision_detector::CollisionType;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_schema_matches_version() {
+        let schema: Value = serde_json::from_str(SCHEMA).unwrap();
+        assert_eq!(schema["properties"]["schema_version"]["const"], json!(SCHEMA_VERSION));
+        for kind in ["summary", "report", "collisions", "plan", "checkpoint", "verification"] {
+            assert!(schema["definitions"][kind].is_object(), "no definition of {}", kind);
+        }
+    }
+
+    #[test]
+    fn test_document_and_collision() {
+        let doc = document("collisions", json!({"collisions": [collision(&Collision {
+            collision_type: CollisionType::TargetAlreadyExists,
+            target_path: PathBuf::from("b.txt"),
+            source_paths: vec![PathBuf::from("a.txt")],
+            description: "b.txt already exists".to_string(),
+        })]}));
+        assert_eq!(doc["schema_version"], json!(1));
+        assert_eq!(doc["kind"], json!("collisions"));
+        assert_eq!(doc["collisions"][0]["type"], json!("TargetAlreadyExists"));
+        assert_eq!(doc["collisions"][0]["sources"], json!(["a.txt"]));
+    }
+}
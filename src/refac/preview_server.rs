@@ -0,0 +1,426 @@
+//! `ws refactor ROOT PATTERN SUBSTITUTE preview --port PORT`: the planned
+//! refactor as a local web page for reviewing refactors too large to read as
+//! terminal diffs. The page shows the changed paths as a tree with a toggle
+//! per rename and per content change, and the diff of each file; submitting
+//! it applies only the accepted changes.
+//!
+//! The server listens on 127.0.0.1 only and stops once the changes are
+//! applied or the review is cancelled from the page. Other pages open in the
+//! browser can still reach that port, so every request has to carry a token
+//! made up when the server starts, which only the printed URL and the page's
+//! form hold, and name the server's own address as `Host` and `Origin`.
+
+use anyhow::{Context, Result};
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::collision_detector::Collision;
+use super::html_report::{escape, write_diff, STYLE};
+use super::rename_engine::FileDiff;
+use crate::{ItemType, RenameConfig, RenameItem};
+
+/// Browsers open connections they never send on; don't let one stall the server
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Largest request body read; the form holds a few numbers per change
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+const PREVIEW_STYLE: &str = "
+.tree div { font-family: ui-monospace, Menlo, monospace; font-size: 0.9em; padding: 1px 0; }
+.tree .dir { color: #666; }
+.tree .to { color: #070; }
+.actions { position: sticky; top: 0; background: #fff; padding: 8px 0; border-bottom: 1px solid #ddd; }
+";
+
+const TOGGLE_SCRIPT: &str = "
+function setAll(checked) {
+  document.querySelectorAll('input[type=checkbox]').forEach(function (box) { box.checked = checked; });
+}
+";
+
+/// The changes accepted on the page, as indexes into the planned renames
+/// and content files
+#[derive(Debug, Default, PartialEq)]
+pub struct Selection {
+    pub renames: Vec<usize>,
+    pub contents: Vec<usize>,
+}
+
+impl Selection {
+    /// The checked `rename` and `content` boxes of a submitted form
+    pub fn from_form(body: &[u8]) -> Self {
+        let mut selection = Self::default();
+        for (key, value) in url::form_urlencoded::parse(body) {
+            let Ok(index) = value.parse() else { continue };
+            match key.as_ref() {
+                "rename" => selection.renames.push(index),
+                "content" => selection.contents.push(index),
+                _ => {}
+            }
+        }
+        selection
+    }
+
+    /// The accepted renames, in the planned order
+    pub fn renames_of(&self, rename_items: &[RenameItem]) -> Vec<RenameItem> {
+        pick(&self.renames, rename_items)
+    }
+
+    /// The accepted content files, in the planned order
+    pub fn contents_of(&self, content_files: &[PathBuf]) -> Vec<PathBuf> {
+        pick(&self.contents, content_files)
+    }
+}
+
+fn pick<T: Clone>(indexes: &[usize], items: &[T]) -> Vec<T> {
+    items.iter()
+        .enumerate()
+        .filter(|(index, _)| indexes.contains(index))
+        .map(|(_, item)| item.clone())
+        .collect()
+}
+
+/// The review page: collisions, the tree of changed paths with a checkbox
+/// per change (all accepted to begin with), and the diff of each file
+pub fn render(
+    config: &RenameConfig,
+    content_files: &[PathBuf],
+    rename_items: &[RenameItem],
+    diffs: &[(PathBuf, FileDiff)],
+    collisions: &[Collision],
+    token: &str,
+) -> String {
+    let relative = |path: &Path| path.strip_prefix(&config.root_dir).unwrap_or(path).to_path_buf();
+    let mut html = String::new();
+
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>Refactor preview: {pattern} → {substitute}</title>\n<style>{STYLE}{PREVIEW_STYLE}</style>\n</head>\n<body>\n\
+         <h1>Refactor preview: <code>{pattern}</code> → <code>{substitute}</code></h1>\n\
+         <p>Root <code>{root}</code>. Uncheck the changes to leave out, then apply the rest.</p>\n\
+         <form method=\"post\" action=\"/apply\">\n<input type=\"hidden\" name=\"token\" value=\"{token}\">\n<div class=\"actions\">\n\
+         <button type=\"submit\">Apply accepted changes</button>\n\
+         <button type=\"button\" onclick=\"setAll(true)\">Accept all</button>\n\
+         <button type=\"button\" onclick=\"setAll(false)\">Reject all</button>\n\
+         <button type=\"submit\" formaction=\"/cancel\">Cancel without changes</button>\n</div>\n",
+        pattern = escape(&config.pattern),
+        substitute = escape(&config.substitute),
+        root = escape(&config.root_dir.display().to_string()),
+        token = escape(token),
+    );
+
+    if !collisions.is_empty() {
+        html.push_str("<h2>Collisions</h2>\n<p>Nothing is applied while any of these remain among the accepted renames.</p>\n<ul>\n");
+        for collision in collisions {
+            let _ = writeln!(html, "<li class=\"collision\">{}</li>", escape(&collision.description));
+        }
+        html.push_str("</ul>\n");
+    }
+
+    // Every changed path with its ancestors, so the tree has its directories
+    let mut tree = BTreeSet::new();
+    for path in rename_items.iter().map(|item| &item.original_path).chain(content_files) {
+        let path = relative(path);
+        tree.extend(path.ancestors().filter(|ancestor| !ancestor.as_os_str().is_empty()).map(Path::to_path_buf));
+    }
+
+    html.push_str("<h2>Changes</h2>\n<div class=\"tree\">\n");
+    for path in &tree {
+        let depth = path.components().count() - 1;
+        let name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        let rename = rename_items.iter().position(|item| relative(&item.original_path) == *path);
+        let content = content_files.iter().position(|file| relative(file) == *path);
+        let is_dir = rename.is_some_and(|index| rename_items[index].item_type == ItemType::Directory)
+            || (rename.is_none() && content.is_none());
+
+        let _ = write!(html, "<div style=\"padding-left: {}em\"{}>", depth * 2, if is_dir { " class=\"dir\"" } else { "" });
+        let _ = write!(html, "{}{}", escape(&name), if is_dir { "/" } else { "" });
+        if let Some(index) = rename {
+            let new_name = rename_items[index].new_path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+            let _ = write!(
+                html,
+                " <label><input type=\"checkbox\" name=\"rename\" value=\"{index}\" checked> rename to <span class=\"to\">{}</span></label>",
+                escape(&new_name)
+            );
+        }
+        if let Some(index) = content {
+            let _ = write!(
+                html,
+                " <label><input type=\"checkbox\" name=\"content\" value=\"{index}\" checked> <a href=\"#diff-{index}\">replace content</a></label>"
+            );
+        }
+        html.push_str("</div>\n");
+    }
+    html.push_str("</div>\n");
+
+    if !diffs.is_empty() {
+        html.push_str("<h2>Diffs</h2>\n");
+        for (path, diff) in diffs {
+            let index = content_files.iter().position(|file| file == path).unwrap_or_default();
+            let _ = write!(
+                html,
+                "<details id=\"diff-{index}\" open>\n<summary><code>{}</code>",
+                escape(&relative(path).display().to_string())
+            );
+            write_diff(&mut html, diff);
+            html.push_str("</details>\n");
+        }
+    }
+
+    let _ = write!(html, "</form>\n<script>{TOGGLE_SCRIPT}</script>\n</body>\n</html>\n");
+    html
+}
+
+/// A short page reporting how the review ended, or why applying failed
+fn message_page(title: &str, message: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n<h1>{title}</h1>\n<p>{}</p>\n</body>\n</html>\n",
+        escape(message).replace('\n', "<br>")
+    )
+}
+
+/// The review page served on a local port
+pub struct PreviewServer {
+    listener: TcpListener,
+    /// Secret every request has to carry
+    token: String,
+}
+
+impl PreviewServer {
+    /// Listen on 127.0.0.1:`port`; port 0 picks a free one
+    pub fn bind(port: u16) -> Result<Self> {
+        use rand::Rng;
+
+        let listener = TcpListener::bind(("127.0.0.1", port))
+            .with_context(|| format!("Failed to listen on port {}", port))?;
+        let token = rand::thread_rng().gen::<[u8; 16]>().iter().map(|b| format!("{:02x}", b)).collect();
+        Ok(Self { listener, token })
+    }
+
+    /// The token `render` has to put in the page's form
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// Address of the page, token included
+    pub fn url(&self) -> Result<String> {
+        Ok(format!("http://{}/?token={}", self.listener.local_addr()?, self.token))
+    }
+
+    /// Serve `page` until the reviewer applies or cancels. `apply` gets the
+    /// accepted changes and returns what it did; when it fails, the error is
+    /// shown and the page stays up for another try. Returns what `apply`
+    /// returned, or `None` when the review was cancelled.
+    pub fn run(&self, page: &str, mut apply: impl FnMut(&Selection) -> Result<String>) -> Result<Option<String>> {
+        let address = self.listener.local_addr()?;
+        for stream in self.listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let Ok(request) = read_request(&stream) else { continue };
+
+            if !request.from_page(&address) {
+                respond(&mut stream, "403 Forbidden", &message_page("Forbidden", "The request did not come from the review page."));
+                continue;
+            }
+            if request.body.is_none() {
+                respond(&mut stream, "413 Payload Too Large", &message_page("Request too large", "The request body is over 1 MiB."));
+                continue;
+            }
+            if request.token().as_deref() != Some(self.token.as_str()) {
+                respond(&mut stream, "403 Forbidden", &message_page("Forbidden", "Open the review page through the URL printed in the terminal."));
+                continue;
+            }
+
+            let body = request.body.as_deref().unwrap_or_default();
+            match (request.method.as_str(), request.path.as_str()) {
+                ("GET", "/") => respond(&mut stream, "200 OK", page),
+                ("POST", "/apply") => match apply(&Selection::from_form(body)) {
+                    Ok(message) => {
+                        respond(&mut stream, "200 OK", &message_page("Changes applied", &message));
+                        return Ok(Some(message));
+                    }
+                    Err(error) => respond(&mut stream, "409 Conflict", &message_page("Nothing was applied", &format!("{:#}", error))),
+                },
+                ("POST", "/cancel") => {
+                    respond(&mut stream, "200 OK", &message_page("Review cancelled", "Nothing was changed."));
+                    return Ok(None);
+                }
+                _ => respond(&mut stream, "404 Not Found", &message_page("Not found", &request.path)),
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// One HTTP request
+#[derive(Debug, Default)]
+struct Request {
+    method: String,
+    /// The path without the query
+    path: String,
+    query: String,
+    host: Option<String>,
+    origin: Option<String>,
+    /// `None` when it is over `MAX_BODY_BYTES` and was not read
+    body: Option<Vec<u8>>,
+}
+
+impl Request {
+    /// Whether the request names the server's `address` as its `Host` and,
+    /// when it has one, `Origin`; a page of another site sends its own
+    /// origin, and one reaching the port through a rebound name another host
+    fn from_page(&self, address: &SocketAddr) -> bool {
+        let hosts = [address.to_string(), format!("localhost:{}", address.port())];
+        let host_ok = self.host.as_deref().is_some_and(|host| hosts.iter().any(|allowed| allowed == host));
+        let origin_ok = self.origin.as_deref().is_none_or(|origin| {
+            hosts.iter().any(|allowed| origin == format!("http://{}", allowed))
+        });
+        host_ok && origin_ok
+    }
+
+    /// The `token` field of the query (for the page) or the form (for its buttons)
+    fn token(&self) -> Option<String> {
+        let from_query = url::form_urlencoded::parse(self.query.as_bytes());
+        let from_form = url::form_urlencoded::parse(self.body.as_deref().unwrap_or_default());
+        from_query
+            .chain(from_form)
+            .find(|(key, _)| key == "token")
+            .map(|(_, value)| value.into_owned())
+    }
+}
+
+/// Read one HTTP request, leaving a body over `MAX_BODY_BYTES` unread
+fn read_request(stream: &TcpStream) -> Result<Request> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().context("Empty request")?.to_string();
+    let target = parts.next().context("Request without a path")?;
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let mut request = Request { method, path: path.to_string(), query: query.to_string(), ..Request::default() };
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            let value = value.trim().to_string();
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.parse().context("Invalid Content-Length")?;
+            } else if name.eq_ignore_ascii_case("host") {
+                request.host = Some(value);
+            } else if name.eq_ignore_ascii_case("origin") {
+                request.origin = Some(value);
+            }
+        }
+    }
+
+    if content_length <= MAX_BODY_BYTES {
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+        request.body = Some(body);
+    }
+    Ok(request)
+}
+
+fn respond(stream: &mut TcpStream, status: &str, html: &str) {
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{html}",
+        html.len()
+    );
+    let _ = stream.flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(from: &str, to: &str, item_type: ItemType) -> RenameItem {
+        RenameItem {
+            original_path: PathBuf::from(from),
+            new_path: PathBuf::from(to),
+            item_type,
+            depth: from.matches('/').count(),
+        }
+    }
+
+    #[test]
+    fn test_selection_from_form() {
+        let selection = Selection::from_form(b"rename=2&content=0&rename=0&other=1&content=x");
+        assert_eq!(selection, Selection { renames: vec![2, 0], contents: vec![0] });
+
+        let items = vec![
+            item("/r/old.txt", "/r/new.txt", ItemType::File),
+            item("/r/keep_old.txt", "/r/keep_new.txt", ItemType::File),
+            item("/r/old", "/r/new", ItemType::Directory),
+        ];
+        let accepted = selection.renames_of(&items);
+        assert_eq!(accepted.len(), 2);
+        assert_eq!(accepted[0].original_path, PathBuf::from("/r/old.txt"));
+        assert_eq!(accepted[1].original_path, PathBuf::from("/r/old"));
+        assert_eq!(selection.contents_of(&[PathBuf::from("/r/a"), PathBuf::from("/r/b")]), vec![PathBuf::from("/r/a")]);
+    }
+
+    #[test]
+    fn test_render_tree() {
+        let config = RenameConfig::new(Path::new("."), "old".to_string(), "new".to_string()).unwrap();
+        let root = config.root_dir.clone();
+        let items = vec![
+            item(root.join("old/old.txt").to_str().unwrap(), root.join("old/new.txt").to_str().unwrap(), ItemType::File),
+            item(root.join("old").to_str().unwrap(), root.join("new").to_str().unwrap(), ItemType::Directory),
+        ];
+        let content_files = vec![root.join("src/<lib>.rs")];
+        let page = render(&config, &content_files, &items, &[], &[], "secret");
+
+        assert!(page.contains("name=\"rename\" value=\"0\" checked"));
+        assert!(page.contains("name=\"rename\" value=\"1\" checked"));
+        assert!(page.contains("name=\"content\" value=\"0\" checked"));
+        // Directories come before their contents, the unchanged one included
+        let old_dir = page.find(">old/ <label>").unwrap();
+        let old_file = page.find(">old.txt <label>").unwrap();
+        assert!(old_dir < old_file);
+        assert!(page.contains("class=\"dir\">src/</div>"));
+        assert!(page.contains("&lt;lib&gt;.rs"));
+        assert!(page.contains("<input type=\"hidden\" name=\"token\" value=\"secret\">"));
+    }
+
+    /// Send `request` to the server at `address` and return the status line of the answer
+    fn send(address: SocketAddr, request: &str) -> String {
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        let _ = stream.read_to_string(&mut response);
+        response.lines().next().unwrap_or_default().to_string()
+    }
+
+    #[test]
+    fn test_requests_need_token_and_own_origin() {
+        let server = PreviewServer::bind(0).unwrap();
+        let address = server.listener.local_addr().unwrap();
+        let token = server.token().to_string();
+        let running = std::thread::spawn(move || server.run("page", |_| Ok("applied".to_string())).unwrap());
+
+        let post = |host: &str, origin: &str, body: &str| {
+            format!("POST /apply HTTP/1.1\r\nHost: {host}\r\nOrigin: {origin}\r\nContent-Length: {}\r\n\r\n{body}", body.len())
+        };
+        let own = format!("http://{}", address);
+        assert!(send(address, &post(&address.to_string(), &own, "rename=0")).contains("403"));
+        assert!(send(address, &post(&address.to_string(), "http://evil.example", &format!("token={token}"))).contains("403"));
+        assert!(send(address, &post("evil.example", &own, &format!("token={token}"))).contains("403"));
+        assert!(send(address, &format!("GET /?token=wrong HTTP/1.1\r\nHost: {address}\r\n\r\n")).contains("403"));
+        let oversized = format!("POST /apply HTTP/1.1\r\nHost: {address}\r\nContent-Length: {}\r\n\r\n", MAX_BODY_BYTES + 1);
+        assert!(send(address, &oversized).contains("413"));
+        assert!(send(address, &format!("GET /?token={token} HTTP/1.1\r\nHost: {address}\r\n\r\n")).contains("200"));
+
+        assert!(send(address, &post(&address.to_string(), &own, &format!("token={token}&rename=0"))).contains("200"));
+        assert_eq!(running.join().unwrap(), Some("applied".to_string()));
+    }
+}
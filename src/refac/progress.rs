@@ -4,6 +4,8 @@ use std::cell::RefCell;
 use std::time::Duration;
 use colored::*;
 
+use super::messages::msg;
+
 /// Progress tracking for the rename operation
 pub struct ProgressTracker {
     multi_progress: MultiProgress,
@@ -53,11 +55,11 @@ impl ProgressTracker {
         let pb = self.multi_progress.add(ProgressBar::new(total));
         pb.set_style(
             ProgressStyle::default_bar()
-                .template("  {spinner:.yellow} Content: [{bar:30.yellow/blue}] {pos}/{len} {msg}")
+                .template(&format!("  {{spinner:.yellow}} {} [{{bar:30.yellow/blue}}] {{pos}}/{{len}} {{msg}}", msg!("progress.content_label")))
                 .unwrap()
                 .progress_chars("#>-")
         );
-        pb.set_message("Replacing content".to_string());
+        pb.set_message(msg!("progress.content"));
         *self.content_bar.borrow_mut() = Some(pb);
     }
 
@@ -70,11 +72,11 @@ impl ProgressTracker {
         let pb = self.multi_progress.add(ProgressBar::new(total));
         pb.set_style(
             ProgressStyle::default_bar()
-                .template("  {spinner:.magenta} Rename: [{bar:30.magenta/blue}] {pos}/{len} {msg}")
+                .template(&format!("  {{spinner:.magenta}} {} [{{bar:30.magenta/blue}}] {{pos}}/{{len}} {{msg}}", msg!("progress.rename_label")))
                 .unwrap()
                 .progress_chars("#>-")
         );
-        pb.set_message("Renaming files/directories".to_string());
+        pb.set_message(msg!("progress.rename"));
         *self.rename_bar.borrow_mut() = Some(pb);
     }
 
@@ -93,7 +95,7 @@ impl ProgressTracker {
         if let Some(pb) = self.content_bar.borrow().as_ref() {
             pb.inc(1);
             if self.verbose {
-                pb.set_message(format!("Processing: {}", file_path));
+                pb.set_message(msg!("progress.content_file", path = file_path));
             }
         }
     }
@@ -103,7 +105,7 @@ impl ProgressTracker {
         if let Some(pb) = self.rename_bar.borrow().as_ref() {
             pb.inc(1);
             if self.verbose {
-                pb.set_message(format!("Renaming: {}", item_path));
+                pb.set_message(msg!("progress.rename_item", path = item_path));
             }
         }
     }
@@ -140,32 +142,32 @@ impl ProgressTracker {
 
     /// Print an error message
     pub fn print_error(&self, message: &str) {
-        let error_msg = format!("{} {}", "ERROR:".red().bold(), message);
+        let error_msg = format!("{} {}", msg!("status.error").red().bold(), message);
         self.println(&error_msg);
     }
 
     /// Print a warning message
     pub fn print_warning(&self, message: &str) {
-        let warning_msg = format!("{} {}", "WARNING:".yellow().bold(), message);
+        let warning_msg = format!("{} {}", msg!("status.warning").yellow().bold(), message);
         self.println(&warning_msg);
     }
 
     /// Print an info message
     pub fn print_info(&self, message: &str) {
-        let info_msg = format!("{} {}", "INFO:".blue().bold(), message);
+        let info_msg = format!("{} {}", msg!("status.info").blue().bold(), message);
         self.println(&info_msg);
     }
 
     /// Print a success message
     pub fn print_success(&self, message: &str) {
-        let success_msg = format!("{} {}", "SUCCESS:".green().bold(), message);
+        let success_msg = format!("{} {}", msg!("status.success").green().bold(), message);
         self.println(&success_msg);
     }
 
     /// Print verbose output
     pub fn print_verbose(&self, message: &str) {
         if self.verbose {
-            let verbose_msg = format!("{} {}", "VERBOSE:".cyan(), message);
+            let verbose_msg = format!("{} {}", msg!("status.verbose").cyan(), message);
             self.println(&verbose_msg);
         }
     }
@@ -205,47 +207,62 @@ impl Drop for ProgressTracker {
 /// Simple console output without progress bars
 pub struct SimpleOutput {
     verbose: bool,
+    /// Print messages on stderr, keeping stdout for `--format json` documents
+    stderr: bool,
 }
 
 impl SimpleOutput {
     pub fn new(verbose: bool) -> Self {
-        Self { verbose }
+        Self { verbose, stderr: false }
+    }
+
+    pub fn with_stderr(mut self, stderr: bool) -> Self {
+        self.stderr = stderr;
+        self
+    }
+
+    fn line(&self, text: std::fmt::Arguments) {
+        if self.stderr {
+            eprintln!("{}", text);
+        } else {
+            println!("{}", text);
+        }
     }
 
     pub fn println(&self, message: &str) {
-        println!("{}", message);
+        self.line(format_args!("{}", message));
     }
 
     pub fn print_error(&self, message: &str) {
         log::error!("Refac: {}", message);
-        eprintln!("{} {}", "ERROR:".red().bold(), message);
+        eprintln!("{} {}", msg!("status.error").red().bold(), message);
     }
 
     pub fn print_warning(&self, message: &str) {
         log::warn!("Refac: {}", message);
-        println!("{} {}", "WARNING:".yellow().bold(), message);
+        self.line(format_args!("{} {}", msg!("status.warning").yellow().bold(), message));
     }
 
     pub fn print_info(&self, message: &str) {
         log::info!("Refac: {}", message);
-        println!("{} {}", "INFO:".blue().bold(), message);
+        self.line(format_args!("{} {}", msg!("status.info").blue().bold(), message));
     }
 
     pub fn print_success(&self, message: &str) {
         log::info!("Refac success: {}", message);
-        println!("{} {}", "SUCCESS:".green().bold(), message);
+        self.line(format_args!("{} {}", msg!("status.success").green().bold(), message));
     }
 
     pub fn print_verbose(&self, message: &str) {
         if self.verbose {
             log::debug!("Refac verbose: {}", message);
-            println!("{} {}", "VERBOSE:".cyan(), message);
+            self.line(format_args!("{} {}", msg!("status.verbose").cyan(), message));
         }
     }
 
     pub fn print_step(&self, step: usize, total: usize, message: &str) {
         log::debug!("Refac step [{}/{}]: {}", step, total, message);
-        println!("[{}/{}] {}", step, total, message);
+        self.line(format_args!("[{}/{}] {}", step, total, message));
     }
 }
 
@@ -1,19 +1,38 @@
 use anyhow::{Context, Result};
 use rayon::prelude::*;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use walkdir::{DirEntry, WalkDir};
 
 use crate::{
     ItemType, RenameConfig, RenameItem, RenameStats, utils,
 };
 use super::{
-    cli::{Args, Mode, OutputFormat},
-    collision_detector::{CollisionDetector, CollisionType},
-    file_ops::FileOperations,
-    progress::{ProgressTracker, SimpleOutput},
+    cli::{Args, CaseCollisionCheck, Mode, OnError, OutputFormat},
+    collision_detector::{Collision, CollisionDetector, CollisionType, WalkedPaths},
+    archives::{ArchiveOptions, MemberChange},
+    file_ops::{FileOperations, CHUNKED_REPLACE_THRESHOLD},
+    handlers::ContentHandler,
+    messages::msg,
+    output_schema,
+    imports::{default_languages, reference_replacements, update_references, ImportLanguage, ModuleRename},
+    lock::RootLock,
+    tokens::{find_identifiers, TokenMode},
+    reporter::{JsonReporter, Phase, PlainReporter, Reporter, TerminalReporter},
+    stats::Counters,
+    tuning,
 };
 
+/// Files below this size are handed to parallel workers in batches
+const SMALL_FILE_BYTES: u64 = 64 * 1024;
+
+/// Number of small files in a batch
+const SMALL_FILE_BATCH: usize = 32;
+
 /// Detailed information about changes to a specific file/directory
 #[derive(Debug, Clone)]
 pub struct FileChangeReport {
@@ -23,6 +42,26 @@ pub struct FileChangeReport {
     pub item_type: ItemType,
 }
 
+/// A line changed by content replacement
+#[derive(Debug, Clone)]
+pub struct LineChange {
+    /// Line number, starting at 1
+    pub line: usize,
+    pub before: String,
+    pub after: String,
+}
+
+/// The planned change to a file's content, for reports
+#[derive(Debug, Clone)]
+pub enum FileDiff {
+    Lines(Vec<LineChange>),
+    /// Rewritten by the external handler with this command
+    Handler(String),
+    /// An archive, with its changed members
+    Archive(Vec<MemberChange>),
+    Unreadable,
+}
+
 /// Organized summary of all changes, grouped and sorted by location
 #[derive(Debug, Clone)]
 pub struct DetailedChangeReport {
@@ -30,6 +69,33 @@ pub struct DetailedChangeReport {
     pub total_stats: RenameStats,
 }
 
+/// A file whose references to renamed modules `--update-imports` updates
+#[derive(Debug, Clone)]
+pub struct ImportUpdate {
+    pub path: PathBuf,
+    /// Number of references updated
+    pub references: usize,
+    content: String,
+}
+
+/// Work the execution phases left undone at the `--time-limit` deadline,
+/// with paths as they are at that point
+#[derive(Debug, Default)]
+struct LeftOver {
+    content: Vec<PathBuf>,
+    renames: Vec<RenameItem>,
+}
+
+impl LeftOver {
+    fn len(&self) -> usize {
+        self.content.len() + self.renames.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
 /// Structured validation error with location and context information
 #[derive(Debug, Clone)]
 pub struct ValidationError {
@@ -51,6 +117,75 @@ pub enum ValidationErrorType {
     ParentDirectoryError,
     ContentNotFound,
     EmptyDirectoryIssue,
+    /// Held open by another process; a warning with `--retry-locked`
+    Locked,
+}
+
+/// Why discovery left a path alone, counted in the summary and listed by
+/// `--show-skipped`
+#[derive(Debug, Clone, PartialEq)]
+pub enum SkipReason {
+    /// Hidden, without `--hidden`
+    Hidden,
+    /// Matches an `--exclude` pattern
+    Excluded,
+    /// Matches none of the `--include` patterns
+    NotIncluded,
+    /// Content matches `--exclude-containing`
+    ExcludedContent,
+    /// Binary, so its content is not searched
+    Binary,
+    /// An archive over `--archive-size-limit`
+    TooLarge,
+    /// An archive that could not be read
+    Unreadable(String),
+    /// The directory walk could not read it
+    WalkError(String),
+    /// A followed symlink to a directory already walked, e.g. one of its own ancestors
+    SymlinkCycle,
+    /// A followed symlink deeper than `--max-symlink-depth` links
+    SymlinkDepth,
+}
+
+impl SkipReason {
+    /// Stable name, for `--format plain` and `--format json`
+    pub fn name(&self) -> &'static str {
+        match self {
+            SkipReason::Hidden => "hidden",
+            SkipReason::Excluded => "excluded",
+            SkipReason::NotIncluded => "not-included",
+            SkipReason::ExcludedContent => "excluded-content",
+            SkipReason::Binary => "binary",
+            SkipReason::TooLarge => "too-large",
+            SkipReason::Unreadable(_) => "unreadable",
+            SkipReason::WalkError(_) => "walk-error",
+            SkipReason::SymlinkCycle => "symlink-cycle",
+            SkipReason::SymlinkDepth => "symlink-depth",
+        }
+    }
+
+    /// Message key of the reason's description
+    fn message_key(&self) -> &'static str {
+        match self {
+            SkipReason::Hidden => "skipped.hidden",
+            SkipReason::Excluded => "skipped.excluded",
+            SkipReason::NotIncluded => "skipped.not_included",
+            SkipReason::ExcludedContent => "skipped.excluded_content",
+            SkipReason::Binary => "skipped.binary",
+            SkipReason::TooLarge => "skipped.too_large",
+            SkipReason::Unreadable(_) => "skipped.unreadable",
+            SkipReason::WalkError(_) => "skipped.walk_error",
+            SkipReason::SymlinkCycle => "skipped.symlink_cycle",
+            SkipReason::SymlinkDepth => "skipped.symlink_depth",
+        }
+    }
+
+    pub fn detail(&self) -> Option<&str> {
+        match self {
+            SkipReason::Unreadable(detail) | SkipReason::WalkError(detail) => Some(detail),
+            _ => None,
+        }
+    }
 }
 
 /// Main engine for executing rename operations
@@ -58,17 +193,52 @@ pub struct RenameEngine {
     config: RenameConfig,
     mode: Mode,
     file_ops: FileOperations,
-    progress: Option<ProgressTracker>,
-    simple_output: Option<SimpleOutput>,
+    reporter: Box<dyn Reporter>,
     thread_count: usize,
+    adaptive_threads: bool,
+    /// Thread count `--adaptive-threads` settled on for the content phase
+    tuned_threads: AtomicUsize,
     output_format: OutputFormat,
     max_depth: Option<usize>,
+    /// Rename `root_dir` too, after everything in it
+    include_root: bool,
     include_patterns: Vec<String>,
     exclude_patterns: Vec<String>,
+    /// Files whose content matches are left alone
+    exclude_containing: Option<regex::Regex>,
     ignore_case: bool,
     use_regex: bool,
     include_hidden: bool,
+    /// Symlinks followed in a row before discovery stops descending
+    max_symlink_depth: usize,
+    legacy_hidden_inference: bool,
     binary_names: bool,
+    token_mode: Option<TokenMode>,
+    report_html: Option<PathBuf>,
+    /// Port of the review page of `ws refactor ... preview`
+    preview_port: Option<u16>,
+    update_imports: bool,
+    import_languages: Vec<ImportLanguage>,
+    stats: bool,
+    counters: Arc<Counters>,
+    retry_locked: u32,
+    show_skipped: bool,
+    case_collision_check: CaseCollisionCheck,
+    /// Where `--output-dir` writes the refactored tree, leaving the root untouched
+    output_dir: Option<PathBuf>,
+    /// Take the root's lock for the run, and wait for it when another run holds it
+    lock: bool,
+    wait_for_lock: bool,
+    on_error: OnError,
+    time_limit: Option<Duration>,
+    /// When the execution phases stop taking new work, for `--time-limit`
+    deadline: Option<Instant>,
+    checkpoint: Option<PathBuf>,
+    left_over: Mutex<LeftOver>,
+    /// What the last discovery walked, for the collision check
+    walked: Mutex<WalkedPaths>,
+    /// What the last discovery left alone, and why
+    skipped: Mutex<BTreeMap<PathBuf, SkipReason>>,
 }
 
 impl RenameEngine {
@@ -90,105 +260,357 @@ impl RenameEngine {
             super::cli::ProgressMode::Auto => atty::is(atty::Stream::Stdout),
         };
 
-        let (progress, simple_output) = if show_progress && args.format == OutputFormat::Human {
-            (Some(ProgressTracker::new(true, args.verbose)), None)
-        } else {
-            (None, Some(SimpleOutput::new(args.verbose)))
+        let reporter: Box<dyn Reporter> = match args.format {
+            OutputFormat::Human if show_progress => Box::new(TerminalReporter::new(args.verbose)),
+            OutputFormat::Json => Box::new(JsonReporter::new(args.verbose)),
+            _ => Box::new(PlainReporter::new(args.verbose)),
+        };
+
+        let token_mode = args.token_mode.then_some(TokenMode { skip_literals: args.skip_literals });
+        let archives = args.into_archives.then(|| ArchiveOptions {
+            size_limit: args.archive_size_limit.saturating_mul(1024 * 1024),
+            rename_members: args.should_process_names(),
+            replace_content: args.should_process_content(),
+        });
+
+        let exclude_containing = args.exclude_containing.as_deref()
+            .map(|pattern| regex::Regex::new(pattern).with_context(|| format!("Invalid --exclude-containing pattern: {}", pattern)))
+            .transpose()?;
+
+        let counters = Arc::new(Counters::default());
+        let preview_port = match args.command {
+            Some(super::cli::RefacCommand::Preview { port }) => Some(port),
+            _ => None,
         };
 
         Ok(Self {
             config,
             mode: args.get_mode(),
-            file_ops: FileOperations::new().with_backup(args.backup),
-            progress,
-            simple_output,
+            file_ops: FileOperations::new()
+                .with_backup(args.backup)
+                .with_token_mode(token_mode)
+                .with_archives(archives)
+                .with_durability(args.durability)
+                .with_counters(Arc::clone(&counters))
+                .with_retry_locked(args.retry_locked),
+            reporter,
             thread_count: args.get_thread_count(),
+            adaptive_threads: args.adaptive_threads,
+            tuned_threads: AtomicUsize::new(0),
             output_format: args.format,
             max_depth: if args.max_depth > 0 { Some(args.max_depth) } else { None },
+            include_root: args.include_root,
             include_patterns: args.include_patterns,
             exclude_patterns: args.exclude_patterns,
+            exclude_containing,
             ignore_case: args.ignore_case,
             use_regex: args.use_regex,
             include_hidden: args.include_hidden,
+            max_symlink_depth: args.max_symlink_depth,
+            legacy_hidden_inference: false,
             binary_names: args.binary_names,
+            token_mode,
+            report_html: args.report_html,
+            preview_port,
+            update_imports: args.update_imports,
+            import_languages: default_languages().into_values().collect(),
+            stats: args.stats,
+            counters,
+            retry_locked: args.retry_locked,
+            show_skipped: args.show_skipped,
+            case_collision_check: args.case_collision_check,
+            output_dir: args.output_dir,
+            lock: !args.no_lock,
+            wait_for_lock: args.wait,
+            on_error: args.on_error,
+            time_limit: args.time_limit,
+            deadline: args.time_limit.map(|limit| Instant::now() + limit),
+            checkpoint: args.checkpoint,
+            left_over: Mutex::default(),
+            walked: Mutex::default(),
+            skipped: Mutex::default(),
         })
     }
 
+    /// Send messages, progress and documents to `reporter` in place of the terminal
+    pub fn with_reporter(mut self, reporter: impl Reporter + 'static) -> Self {
+        self.reporter = Box::new(reporter);
+        self
+    }
+
+    /// Replace content through external handlers in files matching their globs
+    pub fn with_handlers(mut self, handlers: Vec<ContentHandler>) -> Self {
+        self.file_ops = std::mem::take(&mut self.file_ops).with_handlers(handlers);
+        self
+    }
+
+    /// Module reference patterns for `--update-imports`, in place of the built-in ones
+    pub fn with_import_languages(mut self, languages: Vec<ImportLanguage>) -> Self {
+        self.import_languages = languages;
+        self
+    }
+
+    /// Also process hidden files when an include pattern contains `*`, the
+    /// behavior before `--hidden`
+    pub fn with_legacy_hidden_inference(mut self, enabled: bool) -> Self {
+        self.legacy_hidden_inference = enabled;
+        self
+    }
+
     /// Execute the rename operation
     pub fn execute(&self) -> Result<()> {
+        let _lock = self.lock_root()?;
+        let start = Instant::now();
+        let result = self.run();
+        if self.stats {
+            self.print_stats(start.elapsed())?;
+        }
+        result
+    }
+
+    /// Keep other runs off the root until the lock is dropped, unless `--no-lock`
+    pub(crate) fn lock_root(&self) -> Result<Option<RootLock>> {
+        if !self.lock {
+            return Ok(None);
+        }
+        let root = &self.config.root_dir;
+        let lock = RootLock::acquire(root, self.wait_for_lock, |pid| {
+            let _ = self.print_warning(&msg!("lock.waiting", path = root.display(), pid = pid));
+        })?;
+        Ok(Some(lock))
+    }
+
+    fn run(&self) -> Result<()> {
         self.print_header()?;
 
         // Phase 1: Discovery
-        self.print_info("Phase 1: Discovering files and directories...")?;
-        let (content_files, rename_items) = self.discover_items()?;
+        self.print_info(msg!("phase.discovery"))?;
+        let (content_files, rename_items) = self.timed("discovery", || self.discover_items())?;
+
+        if let Some(report_path) = &self.report_html {
+            return self.timed("report", || self.write_html_report(report_path, &content_files, &rename_items));
+        }
+
+        if let Some(port) = self.preview_port {
+            return self.serve_preview(port, &content_files, &rename_items);
+        }
 
         // Phase 2: Collision Detection
-        self.print_info("Phase 2: Checking for naming collisions...")?;
-        self.check_collisions(&rename_items)?;
+        self.print_info(msg!("phase.collisions"))?;
+        self.timed("collisions", || self.check_collisions(&rename_items))?;
 
-        // Phase 3: Mandatory Validation (Dry-Run)
-        self.print_info("Phase 3: Validating all operations...")?;
-        self.validate_all_operations(&content_files, &rename_items)?;
+        // Phase 3: Mandatory Validation (Dry-Run); a copy changes nothing in place
+        if self.output_dir.is_none() {
+            self.print_info(msg!("phase.validation"))?;
+            self.timed("validation", || self.validate_all_operations(&content_files, &rename_items))?;
+        }
 
         // Phase 4: Summary and Confirmation
-        let stats = self.show_summary(&content_files, &rename_items)?;
+        let stats = self.timed("summary", || {
+            let stats = self.show_summary(&content_files, &rename_items)?;
+            if stats.total_changes() == 0 {
+                return Ok(stats);
+            }
+
+            // Phase 4.5: Show diff preview for content changes
+            if !content_files.is_empty() && self.output_format == OutputFormat::Human {
+                self.show_diff_preview(&content_files)?;
+            }
+
+            self.show_import_updates(&rename_items)?;
+            Ok(stats)
+        })?;
         if stats.total_changes() == 0 {
-            self.print_success("No changes needed.")?;
+            self.print_success(msg!("run.no_changes"))?;
             return Ok(());
         }
 
-        // Phase 4.5: Show diff preview for content changes
-        if !content_files.is_empty() && self.output_format == OutputFormat::Human {
-            self.show_diff_preview(&content_files)?;
-        }
-
         if !self.confirm_changes()? {
-            self.print_info("Operation cancelled by user.")?;
+            self.print_info(msg!("run.cancelled"))?;
             return Ok(());
         }
 
+        if let Some(output_dir) = &self.output_dir {
+            self.timed("copy", || self.execute_copy(output_dir, &content_files, &rename_items))?;
+            return self.show_final_report(&stats, None);
+        }
+
         // Phase 5: Execute Changes
+        let archive_changes: Vec<(PathBuf, Vec<MemberChange>)> = content_files
+            .iter()
+            .filter(|path| self.file_ops.is_archive(path))
+            .filter_map(|path| Some((path.clone(), self.archive_changes(path).ok()?)))
+            .collect();
         self.execute_changes(&content_files, &rename_items)?;
 
+        let left_over = std::mem::take(&mut *self.left_over.lock().unwrap());
+        if !left_over.is_empty() {
+            return self.stop_at_time_limit(&left_over);
+        }
+
         // Phase 5: Final Report
-        self.show_final_report(&stats)?;
+        let new_root = self.planned_root(&rename_items).filter(|root| root.exists());
+        self.show_final_report(&stats, new_root)?;
+        self.show_archive_report(&archive_changes)?;
+
+        Ok(())
+    }
+
+    /// Write the planned changes to `report_path` without making them
+    #[tracing::instrument(skip_all)]
+    fn write_html_report(&self, report_path: &Path, content_files: &[PathBuf], rename_items: &[RenameItem]) -> Result<()> {
+        self.print_info(msg!("phase.collisions"))?;
+        let collisions = self.find_collisions(rename_items)?;
+        let report = self.generate_detailed_report(content_files, rename_items)?;
 
+        let diffs = self.file_diffs(content_files);
+
+        let html = super::html_report::render(&self.config, &report, &diffs, &collisions);
+        std::fs::write(report_path, html)
+            .with_context(|| format!("Failed to write report: {}", report_path.display()))?;
+        self.print_success(&msg!(
+            "run.html_report",
+            changes = report.total_stats.total_changes(),
+            collisions = collisions.len(),
+            path = report_path.display()
+        ))?;
         Ok(())
     }
 
+    /// Serve the plan as a review page on `port` and apply the changes
+    /// accepted there
+    fn serve_preview(&self, port: u16, content_files: &[PathBuf], rename_items: &[RenameItem]) -> Result<()> {
+        self.print_info(msg!("phase.collisions"))?;
+        let collisions = self.find_collisions(rename_items)?;
+        let diffs = self.file_diffs(content_files);
+        let server = super::preview_server::PreviewServer::bind(port)?;
+        let page = super::preview_server::render(&self.config, content_files, rename_items, &diffs, &collisions, server.token());
+        self.print_info(&msg!("preview.serving", url = server.url()?))?;
+        let applied = server.run(&page, |selection| {
+            let renames = selection.renames_of(rename_items);
+            let contents = selection.contents_of(content_files);
+            self.check_collisions(&renames)?;
+            self.validate_all_operations(&contents, &renames)?;
+            self.execute_changes(&contents, &renames)?;
+            Ok(msg!("preview.applied", renames = renames.len(), files = contents.len()))
+        })?;
+
+        match applied {
+            Some(message) => self.print_success(&message),
+            None => self.print_info(msg!("preview.cancelled")),
+        }
+    }
+
+    /// The planned change to each of `content_files`
+    pub(crate) fn file_diffs(&self, content_files: &[PathBuf]) -> Vec<(PathBuf, FileDiff)> {
+        content_files
+            .iter()
+            .map(|file_path| {
+                let diff = match self.file_ops.handler_for(file_path) {
+                    Some(handler) => FileDiff::Handler(handler.command.clone()),
+                    None if self.file_ops.is_archive(file_path) => match self.archive_changes(file_path) {
+                        Ok(changes) => FileDiff::Archive(changes),
+                        Err(_) => FileDiff::Unreadable,
+                    },
+                    None => match std::fs::read_to_string(file_path) {
+                        Ok(content) => FileDiff::Lines(self.line_changes(&content, file_path)),
+                        Err(_) => FileDiff::Unreadable,
+                    },
+                };
+                (file_path.clone(), diff)
+            })
+            .collect()
+    }
+
     /// Discover files for content replacement and items for renaming
-    fn discover_items(&self) -> Result<(Vec<PathBuf>, Vec<RenameItem>)> {
+    #[tracing::instrument(skip_all)]
+    pub(crate) fn discover_items(&self) -> Result<(Vec<PathBuf>, Vec<RenameItem>)> {
         let mut content_files = Vec::new();
         let mut rename_items = Vec::new();
 
         // Setup progress
-        if let Some(progress) = &self.progress {
-            progress.init_main_progress(0, "Scanning files and directories...");
-        }
-
-        // Walk the directory tree
+        self.reporter.start(Phase::Discovery, 0);
+
+        // Walk the directory tree, noting what it sees for the collision check
+        let max_depth = self.max_depth.unwrap_or(usize::MAX);
+        let mut walked = WalkedPaths::default();
+        let mut skipped_parents = Vec::new();
+        let mut symlinks = self.config.follow_symlinks.then(|| SymlinkGuard::new(&self.config.root_dir, self.max_symlink_depth));
+        self.skipped.lock().unwrap().clear();
         let walker = WalkDir::new(&self.config.root_dir)
             .follow_links(self.config.follow_symlinks)
-            .max_depth(self.max_depth.unwrap_or(usize::MAX))
+            .max_depth(max_depth)
             .into_iter()
-            .filter_entry(|e| self.should_process_entry(e));
+            .filter_entry(|e| {
+                if let Some(reason) = symlinks.as_mut().and_then(|guard| guard.check(e)) {
+                    self.skip_symlink(e.path(), reason);
+                    return false;
+                }
+                let keep = self.should_process_entry(e);
+                if !keep {
+                    skipped_parents.extend(e.path().parent().map(Path::to_path_buf));
+                    if is_hidden(e.path()) {
+                        self.record_skip(e.path(), SkipReason::Hidden);
+                    }
+                }
+                keep
+            });
 
         for entry in walker {
-            let entry = entry.with_context(|| "Failed to read directory entry")?;
+            // Unreadable entries are reported as skipped rather than ending the walk
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) if e.loop_ancestor().is_some() => {
+                    let path = e.path().unwrap_or(&self.config.root_dir).to_path_buf();
+                    self.skip_symlink(&path, SkipReason::SymlinkCycle);
+                    continue;
+                }
+                Err(e) => {
+                    let path = e.path().unwrap_or(&self.config.root_dir).to_path_buf();
+                    walked.add_partial_dir(&path);
+                    self.record_skip(&path, SkipReason::WalkError(e.to_string()));
+                    continue;
+                }
+            };
             let path = entry.path();
+            walked.add_path(path);
+            if entry.file_type().is_dir() && entry.depth() < max_depth {
+                walked.add_listed_dir(path);
+            }
 
             // Skip the root directory itself
             if path == self.config.root_dir {
                 continue;
             }
+            if entry.file_type().is_file() {
+                self.counters.add_file_walked();
+            }
 
             // Apply include/exclude patterns
-            if !self.matches_patterns(path)? {
+            if let Some(reason) = self.pattern_skip_reason(path) {
+                self.record_skip(path, reason);
                 continue;
             }
 
-            // Check for content replacement in files
-            if self.should_process_content() && 
+            // Check for content replacement in files. Archives go with them,
+            // also in names-only mode for their member names.
+            if self.should_process_files() && path.is_file() && self.file_ops.is_archive(path) {
+                if self.archive_needs_changes(path)? {
+                    content_files.push(path.to_path_buf());
+                }
+            } else if let (Some(exclude), true) = (&self.exclude_containing, path.is_file()) {
+                // The same read tells the content check and --exclude-containing
+                match self.scan_file(path, exclude)? {
+                    None => {
+                        self.record_skip(path, SkipReason::ExcludedContent);
+                        continue;
+                    }
+                    Some(true) if self.should_process_content() && self.should_process_files() => {
+                        content_files.push(path.to_path_buf());
+                    }
+                    Some(_) => {}
+                }
+            } else if self.should_process_content() && 
                self.should_process_files() && 
                path.is_file() {
                 if self.file_needs_content_replacement(path)? {
@@ -204,9 +626,7 @@ impl RenameEngine {
             }
 
             // Update progress
-            if let Some(progress) = &self.progress {
-                progress.update_main(&format!("Scanned: {}", path.display()));
-            }
+            self.reporter.advance(Phase::Discovery, &msg!("progress.scanned", path = path.display()));
         }
 
         // Sort rename items to prevent race conditions:
@@ -224,9 +644,19 @@ impl RenameEngine {
             }
         });
 
-        if let Some(progress) = &self.progress {
-            progress.finish_main("Discovery complete");
+        // The root goes last, once nothing is left to rename inside it
+        if self.include_root && self.should_process_names() {
+            if let Some(root_item) = self.create_rename_item(&self.config.root_dir)? {
+                rename_items.push(root_item);
+            }
+        }
+
+        self.reporter.finish(Phase::Discovery, msg!("progress.discovery_done"));
+
+        for parent in &skipped_parents {
+            walked.add_partial_dir(parent);
         }
+        *self.walked.lock().unwrap() = walked;
 
         Ok((content_files, rename_items))
     }
@@ -240,21 +670,12 @@ impl RenameEngine {
             return true;
         }
         
-        // Skip hidden files unless explicitly included
-        if let Some(name) = path.file_name() {
-            if let Some(name_str) = name.to_str() {
-                if name_str.starts_with('.') {
-                    // Check if include_hidden flag is set
-                    if self.include_hidden {
-                        // Include hidden files when flag is set
-                    } else {
-                        // Original logic: check include patterns
-                        let should_include = self.include_patterns.iter().any(|p| p == ".*" || p.contains("*"));
-                        if !should_include {
-                            return false;
-                        }
-                    }
-                }
+        // Skip hidden files unless --hidden is given
+        if is_hidden(path) && !self.include_hidden {
+            // Legacy: any include pattern with a wildcard let hidden files through
+            let inferred = self.legacy_hidden_inference && self.include_patterns.iter().any(|p| p.contains('*'));
+            if !inferred {
+                return false;
             }
         }
 
@@ -268,13 +689,18 @@ impl RenameEngine {
 
     /// Check if a path matches include/exclude patterns
     fn matches_patterns(&self, path: &Path) -> Result<bool> {
+        Ok(self.pattern_skip_reason(path).is_none())
+    }
+
+    /// Why the include/exclude patterns leave `path` out, if they do
+    fn pattern_skip_reason(&self, path: &Path) -> Option<SkipReason> {
         // If there are include patterns, the file must match at least one
         if !self.include_patterns.is_empty() {
             let matches = self.include_patterns.iter().any(|pattern| {
                 self.path_matches_pattern(path, pattern)
             });
             if !matches {
-                return Ok(false);
+                return Some(SkipReason::NotIncluded);
             }
         }
 
@@ -284,11 +710,33 @@ impl RenameEngine {
                 self.path_matches_pattern(path, pattern)
             });
             if excluded {
-                return Ok(false);
+                return Some(SkipReason::Excluded);
             }
         }
 
-        Ok(true)
+        None
+    }
+
+    /// Note that discovery left `path` alone; the first reason given stays
+    fn record_skip(&self, path: &Path, reason: SkipReason) {
+        log::debug!("Skipping {}: {}", path.display(), reason.name());
+        self.skipped.lock().unwrap().entry(path.to_path_buf()).or_insert(reason);
+    }
+
+    /// Record a symlink discovery does not follow, with a warning since
+    /// what lies behind it is left alone
+    fn skip_symlink(&self, path: &Path, reason: SkipReason) {
+        let message = match reason {
+            SkipReason::SymlinkDepth => msg!("discovery.symlink_depth", path = path.display(), max = self.max_symlink_depth),
+            _ => msg!("discovery.symlink_cycle", path = path.display()),
+        };
+        let _ = self.print_warning(&message);
+        self.record_skip(path, reason);
+    }
+
+    /// What the last discovery left alone, and why, by path
+    pub fn skipped(&self) -> Vec<(PathBuf, SkipReason)> {
+        self.skipped.lock().unwrap().iter().map(|(path, reason)| (path.clone(), reason.clone())).collect()
     }
 
     /// Check if a path matches a glob pattern
@@ -332,21 +780,81 @@ impl RenameEngine {
         compare_str.contains(&pattern_str)
     }
 
+    /// The member changes in the archive at `path`
+    fn archive_changes(&self, path: &Path) -> Result<Vec<MemberChange>> {
+        self.file_ops.archive_changes(path, &self.config.pattern, &self.config.substitute)
+    }
+
+    /// Check if an archive has members to rename or replace in. Archives that
+    /// cannot be processed, e.g. over the size limit, are skipped with a warning.
+    fn archive_needs_changes(&self, path: &Path) -> Result<bool> {
+        match self.archive_changes(path) {
+            Ok(changes) => Ok(!changes.is_empty()),
+            Err(e) => {
+                self.print_warning(&format!("Skipping archive {}: {:#}", path.display(), e))?;
+                let reason = if e.chain().any(|cause| cause.is::<super::archives::TooLarge>()) {
+                    SkipReason::TooLarge
+                } else {
+                    SkipReason::Unreadable(format!("{:#}", e))
+                };
+                self.record_skip(path, reason);
+                Ok(false)
+            }
+        }
+    }
+
     /// Check if a file needs content replacement
     fn file_needs_content_replacement(&self, path: &Path) -> Result<bool> {
+        // Handled files may be binary; they qualify if their bytes contain the pattern
+        if self.file_ops.handler_for(path).is_some() {
+            let bytes = self.file_ops.read_file(path)?;
+            return Ok(self.bytes_contain_pattern(&bytes));
+        }
+
         if !self.file_ops.is_text_file(path)? {
+            self.record_skip(path, SkipReason::Binary);
             return Ok(false);
         }
 
-        let search_string = if self.ignore_case {
+        self.file_ops.file_contains_string(path, &self.search_string())
+    }
+
+    /// `file_needs_content_replacement` and the `--exclude-containing` check
+    /// in one read: `None` when `exclude` matches the file, otherwise whether
+    /// it contains the pattern
+    fn scan_file(&self, path: &Path, exclude: &regex::Regex) -> Result<Option<bool>> {
+        if self.file_ops.handler_for(path).is_some() {
+            let bytes = self.file_ops.read_file(path)?;
+            if exclude.is_match(&String::from_utf8_lossy(&bytes)) {
+                return Ok(None);
+            }
+            return Ok(Some(self.bytes_contain_pattern(&bytes)));
+        }
+
+        if !self.file_ops.is_text_file(path)? {
+            if self.should_process_content() {
+                self.record_skip(path, SkipReason::Binary);
+            }
+            return Ok(Some(false));
+        }
+
+        let (contains, excluded) = self.file_ops.scan_content(path, &self.search_string(), exclude)?;
+        Ok((!excluded).then_some(contains))
+    }
+
+    fn bytes_contain_pattern(&self, bytes: &[u8]) -> bool {
+        let pattern = self.config.pattern.as_bytes();
+        !pattern.is_empty() && bytes.windows(pattern.len()).any(|window| window == pattern)
+    }
+
+    fn search_string(&self) -> String {
+        if self.ignore_case {
             // For case-insensitive search, we'd need to read the file content
             // This is simplified - a full implementation would use regex
-            &self.config.pattern.to_lowercase()
+            self.config.pattern.to_lowercase()
         } else {
-            &self.config.pattern
-        };
-
-        self.file_ops.file_contains_string(path, search_string)
+            self.config.pattern.clone()
+        }
     }
 
     /// Create a rename item if the path needs renaming
@@ -355,7 +863,9 @@ impl RenameEngine {
             .and_then(|n| n.to_str())
             .ok_or_else(|| anyhow::anyhow!("Invalid file name: {}", path.display()))?;
 
-        let contains_pattern = if self.ignore_case {
+        let contains_pattern = if self.token_mode.is_some() {
+            !find_identifiers(file_name, &self.config.pattern).is_empty()
+        } else if self.ignore_case {
             file_name.to_lowercase().contains(&self.config.pattern.to_lowercase())
         } else {
             file_name.contains(&self.config.pattern)
@@ -372,6 +882,7 @@ impl RenameEngine {
                 Ok(false) => {
                     // Binary file - only process if binary_names flag is set
                     if !self.binary_names {
+                        self.record_skip(path, SkipReason::Binary);
                         return Ok(None);
                     }
                 }
@@ -401,7 +912,9 @@ impl RenameEngine {
         };
 
         // Calculate new name
-        let new_name = if self.ignore_case {
+        let new_name = if self.token_mode.is_some() {
+            TokenMode::default().replace(file_name, &self.config.pattern, &self.config.substitute, path)
+        } else if self.ignore_case {
             // Case-insensitive replacement
             file_name.to_lowercase().replace(
                 &self.config.pattern.to_lowercase(),
@@ -422,52 +935,71 @@ impl RenameEngine {
         }))
     }
 
-    /// Check for collisions in the rename operations
-    fn check_collisions(&self, rename_items: &[RenameItem]) -> Result<()> {
-        if rename_items.is_empty() {
-            return Ok(());
-        }
+    /// What `ws refactor verify` needs to check a saved plan: the root and
+    /// what was replaced with what
+    pub(crate) fn record_plan_settings(&self, document: &mut serde_json::Value) {
+        document["root"] = serde_json::json!(self.config.root_dir);
+        document["pattern"] = serde_json::json!(self.config.pattern);
+        document["substitute"] = serde_json::json!(self.config.substitute);
+        document["token_mode"] = match self.token_mode {
+            Some(mode) => serde_json::json!({"skip_literals": mode.skip_literals}),
+            None => serde_json::Value::Null,
+        };
+    }
 
-        let mut detector = CollisionDetector::new();
-        
-        // Scan existing paths
-        detector.scan_existing_paths(&self.config.root_dir)?;
-        
-        // Add rename operations
-        detector.add_renames(rename_items);
-        
-        // Detect collisions
-        let collisions = detector.detect_collisions()?;
+    /// Where `--include-root` moves the root directory, if it does
+    fn planned_root<'a>(&self, rename_items: &'a [RenameItem]) -> Option<&'a Path> {
+        rename_items.iter()
+            .find(|item| item.original_path == self.config.root_dir && item.new_path != item.original_path)
+            .map(|item| item.new_path.as_path())
+    }
+
+    /// Check for collisions in the rename operations
+    #[tracing::instrument(skip_all, fields(items = rename_items.len()))]
+    pub(crate) fn check_collisions(&self, rename_items: &[RenameItem]) -> Result<()> {
+        let collisions = self.find_collisions(rename_items)?;
         
         if !collisions.is_empty() {
+            if self.output_format == OutputFormat::Json {
+                let report = output_schema::document("collisions", serde_json::json!({
+                    "collisions": collisions.iter().map(output_schema::collision).collect::<Vec<_>>()
+                }));
+                self.reporter.document(&report);
+            }
             self.print_error("Naming collisions detected!")?;
             
             for collision in &collisions {
-                match collision.collision_type {
-                    CollisionType::SourceEqualsTarget => {
-                        // Skip no-op renames
-                        continue;
-                    }
-                    _ => {
-                        self.print_error(&collision.description)?;
-                    }
-                }
+                self.print_error(&collision.description)?;
             }
             
-            let serious_collisions: Vec<_> = collisions.iter()
-                .filter(|c| c.collision_type != CollisionType::SourceEqualsTarget)
-                .collect();
-                
-            if !serious_collisions.is_empty() {
-                anyhow::bail!("Cannot proceed due to {} naming collision(s)", serious_collisions.len());
-            }
+            anyhow::bail!("Cannot proceed due to {} naming collision(s)", collisions.len());
         }
 
         Ok(())
     }
 
+    /// Collisions that prevent the renames, leaving out no-op renames
+    pub(crate) fn find_collisions(&self, rename_items: &[RenameItem]) -> Result<Vec<Collision>> {
+        if rename_items.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut detector = CollisionDetector::new().with_case_check(self.case_collision_check);
+
+        // Add rename operations, then find what exists where they land
+        detector.add_renames(rename_items);
+        detector.scan_target_directories(&self.walked.lock().unwrap())?;
+
+        // Detect collisions
+        let collisions = detector.detect_collisions()?;
+        
+        Ok(collisions.into_iter()
+            .filter(|c| c.collision_type != CollisionType::SourceEqualsTarget)
+            .collect())
+    }
+
     /// Generate detailed report of all changes organized by file/directory
-    fn generate_detailed_report(&self, content_files: &[PathBuf], rename_items: &[RenameItem]) -> Result<DetailedChangeReport> {
+    pub(crate) fn generate_detailed_report(&self, content_files: &[PathBuf], rename_items: &[RenameItem]) -> Result<DetailedChangeReport> {
         use std::collections::HashMap;
         
         let mut file_changes_map: HashMap<PathBuf, FileChangeReport> = HashMap::new();
@@ -476,9 +1008,16 @@ impl RenameEngine {
         // Process content changes
         for file_path in content_files {
             // Count occurrences of old string in this file
-            let content_count = match std::fs::read_to_string(file_path) {
-                Ok(content) => content.matches(&self.config.pattern).count(),
-                Err(_) => 0, // Already validated during validation phase
+            let content_count = if self.file_ops.is_archive(file_path) {
+                // Renamed members count as one change each
+                self.archive_changes(file_path)
+                    .map(|changes| changes.iter().map(|change| change.occurrences.max(1)).sum())
+                    .unwrap_or(0)
+            } else {
+                match std::fs::read_to_string(file_path) {
+                    Ok(content) => self.file_ops.count_matches(&content, &self.config.pattern, file_path),
+                    Err(_) => 0, // Already validated during validation phase
+                }
             };
             
             file_changes_map.insert(file_path.clone(), FileChangeReport {
@@ -521,18 +1060,22 @@ impl RenameEngine {
     }
 
     /// Show detailed summary of changes organized by file/directory
+    #[tracing::instrument(skip_all)]
     fn show_summary(&self, content_files: &[PathBuf], rename_items: &[RenameItem]) -> Result<RenameStats> {
         let report = self.generate_detailed_report(content_files, rename_items)?;
+        let skipped = self.skipped();
 
         match self.output_format {
             OutputFormat::Json => {
-                let json_report = serde_json::json!({
+                let mut json_report = output_schema::document("summary", serde_json::json!({
                     "summary": {
                         "content_changes": report.total_stats.files_with_content_changes,
                         "file_renames": report.total_stats.files_renamed,
                         "directory_renames": report.total_stats.directories_renamed,
-                        "total_changes": report.total_stats.total_changes()
+                        "total_changes": report.total_stats.total_changes(),
+                        "skipped": skipped.len()
                     },
+                    "new_root": self.planned_root(rename_items),
                     "file_changes": report.file_changes.iter().map(|fc| {
                         serde_json::json!({
                             "path": fc.path,
@@ -541,44 +1084,66 @@ impl RenameEngine {
                             "item_type": format!("{:?}", fc.item_type)
                         })
                     }).collect::<Vec<_>>()
-                });
-                println!("{}", serde_json::to_string_pretty(&json_report)?);
+                }));
+                self.record_plan_settings(&mut json_report);
+                if self.show_skipped {
+                    json_report["skipped"] = skipped.iter().map(|(path, reason)| {
+                        serde_json::json!({
+                            "path": path,
+                            "reason": reason.name(),
+                            "detail": reason.detail()
+                        })
+                    }).collect();
+                }
+                self.reporter.document(&json_report);
             }
             OutputFormat::Plain => {
-                println!("Content changes: {}", report.total_stats.files_with_content_changes);
-                println!("File renames: {}", report.total_stats.files_renamed);
-                println!("Directory renames: {}", report.total_stats.directories_renamed);
-                println!("Total changes: {}", report.total_stats.total_changes());
+                self.reporter.text(&format!("Content changes: {}", report.total_stats.files_with_content_changes));
+                self.reporter.text(&format!("File renames: {}", report.total_stats.files_renamed));
+                self.reporter.text(&format!("Directory renames: {}", report.total_stats.directories_renamed));
+                self.reporter.text(&format!("Total changes: {}", report.total_stats.total_changes()));
+                self.reporter.text(&format!("Skipped: {}", skipped.len()));
+                if self.show_skipped {
+                    for (path, reason) in &skipped {
+                        match reason.detail() {
+                            Some(detail) => self.reporter.text(&format!("Skipped {} ({}: {})", path.display(), reason.name(), detail)),
+                            None => self.reporter.text(&format!("Skipped {} ({})", path.display(), reason.name())),
+                        }
+                    }
+                }
             }
             OutputFormat::Human => {
-                self.print_info("=== PLANNED CHANGES ===")?;
-                self.print_info(&format!("Total files/directories affected: {}", report.file_changes.len()))?;
-                self.print_info(&format!("Content modifications: {} file(s)", report.total_stats.files_with_content_changes))?;
-                self.print_info(&format!("File renames:         {} file(s)", report.total_stats.files_renamed))?;
-                self.print_info(&format!("Directory renames:    {} directory(ies)", report.total_stats.directories_renamed))?;
+                self.print_info(msg!("summary.title"))?;
+                self.print_info(&msg!("summary.affected", count = report.file_changes.len()))?;
+                self.print_info(&msg!("summary.content", count = report.total_stats.files_with_content_changes))?;
+                self.print_info(&msg!("summary.file_renames", count = report.total_stats.files_renamed))?;
+                self.print_info(&msg!("summary.directory_renames", count = report.total_stats.directories_renamed))?;
+                self.show_skipped_summary(&skipped)?;
                 self.print_info("")?;
 
                 if !report.file_changes.is_empty() {
-                    self.print_info("=== DETAILED CHANGES BY LOCATION ===")?;
+                    self.print_info(msg!("summary.details_title"))?;
                     
                     for change in &report.file_changes {
-                        let relative_path = change.path.strip_prefix(&self.config.root_dir)
+                        // The root itself (with --include-root) is shown in full
+                        let relative_path = change.path.strip_prefix(&self.config.root_dir).ok()
+                            .filter(|relative| !relative.as_os_str().is_empty())
                             .unwrap_or(&change.path);
                         
                         self.print_info(&format!("📁 {}", relative_path.display()))?;
                         
                         // Show content changes
                         if let Some(count) = change.content_changes {
-                            self.print_verbose(&format!("   Content: {} occurrence(s) of '{}' → '{}'", 
-                                count, self.config.pattern, self.config.substitute))?;
+                            self.print_verbose(&msg!("summary.content_detail",
+                                count = count, pattern = self.config.pattern, substitute = self.config.substitute))?;
                         }
                         
                         // Show rename operation
                         if let Some(target) = &change.rename_target {
                             let relative_target = target.strip_prefix(&self.config.root_dir)
                                 .unwrap_or(target);
-                            self.print_verbose(&format!("   Rename:  {} → {}", 
-                                relative_path.display(), relative_target.display()))?;
+                            self.print_verbose(&msg!("summary.rename_detail",
+                                from = relative_path.display(), to = relative_target.display()))?;
                         }
                     }
                 }
@@ -588,37 +1153,80 @@ impl RenameEngine {
         Ok(report.total_stats)
     }
 
+    /// Skipped paths counted by reason, and listed with `--show-skipped`
+    fn show_skipped_summary(&self, skipped: &[(PathBuf, SkipReason)]) -> Result<()> {
+        if skipped.is_empty() {
+            return Ok(());
+        }
+
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for (_, reason) in skipped {
+            *counts.entry(reason.message_key()).or_default() += 1;
+        }
+        let reasons = counts.iter()
+            .map(|(key, count)| format!("{} {}", count, msg!(key)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        self.print_info(&msg!("summary.skipped", count = skipped.len(), reasons = reasons))?;
+
+        if !self.show_skipped {
+            return self.print_info(msg!("summary.skipped_hint"));
+        }
+        for (path, reason) in skipped {
+            let relative_path = path.strip_prefix(&self.config.root_dir).unwrap_or(path);
+            let reason_text = match reason.detail() {
+                Some(detail) => format!("{}: {}", msg!(reason.message_key()), detail),
+                None => msg!(reason.message_key()).to_string(),
+            };
+            self.print_info(&format!("   {} ({})", relative_path.display(), reason_text))?;
+        }
+        Ok(())
+    }
+
     /// Show diff preview for content changes with colored +/- lines
     fn show_diff_preview(&self, content_files: &[PathBuf]) -> Result<()> {
-        self.print_info("=== DIFF PREVIEW ===")?;
+        self.print_info(msg!("preview.title"))?;
         
         for (i, file_path) in content_files.iter().enumerate() {
             if i >= 5 {  // Limit preview to first 5 files to avoid overwhelming output
-                self.print_info(&format!("... and {} more files", content_files.len() - i))?;
+                self.print_info(&msg!("preview.more_files", count = content_files.len() - i))?;
                 break;
             }
             
             let relative_path = file_path.strip_prefix(&self.config.root_dir)
                 .unwrap_or(file_path);
+
+            if let Some(handler) = self.file_ops.handler_for(file_path) {
+                self.print_info(&msg!("preview.handler", path = relative_path.display(), command = handler.command))?;
+                continue;
+            }
+
+            if self.file_ops.is_archive(file_path) {
+                self.print_info(&msg!("preview.archive", path = relative_path.display()))?;
+                for change in self.archive_changes(file_path).unwrap_or_default() {
+                    self.print_info(&format!("  ⎿  {}", change))?;
+                }
+                continue;
+            }
             
             // Read file content
             let content = match std::fs::read_to_string(file_path) {
                 Ok(content) => content,
                 Err(_) => {
-                    self.print_warning(&format!("⚠️  Cannot preview {}: unable to read file", relative_path.display()))?;
+                    self.print_warning(&msg!("preview.unreadable", path = relative_path.display()))?;
                     continue;
                 }
             };
             
-            self.print_info(&format!("\n⏺ Update({})", relative_path.display()))?;
+            self.print_info(&msg!("preview.update", path = relative_path.display()))?;
             
             // Count replacements
-            let replacement_count = content.matches(&self.config.pattern).count();
+            let replacement_count = self.file_ops.count_matches(&content, &self.config.pattern, file_path);
             let pattern_removals = self.config.pattern.lines().count() * replacement_count;
             let substitute_additions = self.config.substitute.lines().count() * replacement_count;
             
-            self.print_verbose(&format!("  ⎿  Updated {} with {} additions and {} removals", 
-                relative_path.display(), substitute_additions, pattern_removals))?;
+            self.print_verbose(&msg!("preview.counts",
+                path = relative_path.display(), additions = substitute_additions, removals = pattern_removals))?;
             
             // Show context around changes
             self.show_diff_context(&content, file_path)?;
@@ -628,58 +1236,66 @@ impl RenameEngine {
         Ok(())
     }
     
+    /// Lines of `content`, the text of `file_path`, that the replacement changes
+    fn line_changes(&self, content: &str, file_path: &Path) -> Vec<LineChange> {
+        let lines: Vec<&str> = content.lines().collect();
+        // Compare line by line unless a multi-line pattern or substitute shifts the lines
+        let replaced = self.file_ops.replace_matches(content, &self.config.pattern, &self.config.substitute, file_path);
+        let new_lines: Vec<&str> = replaced.lines().collect();
+        let aligned = new_lines.len() == lines.len();
+
+        lines.iter().enumerate().filter_map(|(i, line)| {
+            let after = if aligned {
+                new_lines[i].to_string()
+            } else if line.contains(&self.config.pattern) {
+                line.replace(&self.config.pattern, &self.config.substitute)
+            } else {
+                return None;
+            };
+            (after != *line).then(|| LineChange { line: i + 1, before: line.to_string(), after })
+        }).collect()
+    }
+
     /// Show diff context with colored +/- lines for a specific file
-    fn show_diff_context(&self, content: &str, _file_path: &Path) -> Result<()> {
+    fn show_diff_context(&self, content: &str, file_path: &Path) -> Result<()> {
         use colored::*;
         
         let lines: Vec<&str> = content.lines().collect();
-        let mut changes_shown = 0;
         const MAX_CHANGES_TO_SHOW: usize = 3;  // Limit changes shown per file
         
-        for (i, line) in lines.iter().enumerate() {
-            if changes_shown >= MAX_CHANGES_TO_SHOW {
-                break;
+        for (shown, change) in self.line_changes(content, file_path).iter().take(MAX_CHANGES_TO_SHOW).enumerate() {
+            let i = change.line - 1;
+
+            // Show context: 2 lines before, the change, 2 lines after
+            let start_context = i.saturating_sub(2);
+            let end_context = std::cmp::min(i + 3, lines.len());
+            
+            // Show context lines before the change
+            for (j, context_line) in lines.iter().enumerate().take(i).skip(start_context) {
+                self.reporter.text(&format!("      {}        {}", (j + 1).to_string().dimmed(), context_line));
             }
             
-            if line.contains(&self.config.pattern) {
-                // Show context: 2 lines before, the change, 2 lines after
-                let start_context = i.saturating_sub(2);
-                let end_context = std::cmp::min(i + 3, lines.len());
-                
-                // Show context lines before the change
-                for j in start_context..i {
-                    let line_num = j + 1;  // Line numbers start at 1
-                    println!("      {}        {}", line_num.to_string().dimmed(), lines[j]);
-                }
-                
-                // Show the removed line (bright red background with white text)
-                let line_num = i + 1;
-                println!("      {} {}      {}", 
-                    line_num.to_string().dimmed(),
-                    "-".red().bold(),
-                    line.white().on_bright_red()
-                );
-                
-                // Show the added line (bright green background with white text)  
-                let new_line = line.replace(&self.config.pattern, &self.config.substitute);
-                
-                println!("      {} {}      {}", 
-                    line_num.to_string().dimmed(),
-                    "+".green().bold(),
-                    new_line.white().on_bright_green()
-                );
-                
-                // Show context lines after the change
-                for j in (i + 1)..end_context {
-                    let line_num = j + 1;
-                    println!("      {}        {}", line_num.to_string().dimmed(), lines[j]);
-                }
-                
-                changes_shown += 1;
-                
-                if changes_shown < MAX_CHANGES_TO_SHOW && i + 3 < lines.len() {
-                    println!("      ..."); // Separator between changes
-                }
+            // Show the removed line (bright red background with white text)
+            self.reporter.text(&format!("      {} {}      {}",
+                change.line.to_string().dimmed(),
+                "-".red().bold(),
+                change.before.white().on_bright_red()
+            ));
+            
+            // Show the added line (bright green background with white text)  
+            self.reporter.text(&format!("      {} {}      {}",
+                change.line.to_string().dimmed(),
+                "+".green().bold(),
+                change.after.white().on_bright_green()
+            ));
+            
+            // Show context lines after the change
+            for (j, context_line) in lines.iter().enumerate().take(end_context).skip(i + 1) {
+                self.reporter.text(&format!("      {}        {}", (j + 1).to_string().dimmed(), context_line));
+            }
+            
+            if shown + 1 < MAX_CHANGES_TO_SHOW && i + 3 < lines.len() {
+                self.reporter.text("      ..."); // Separator between changes
             }
         }
         
@@ -695,195 +1311,486 @@ impl RenameEngine {
         match self.output_format {
             OutputFormat::Json => Ok(true), // No confirmation in JSON mode
             OutputFormat::Plain | OutputFormat::Human => {
-                self.print_warning("This operation will modify your files and directories.")?;
+                match &self.output_dir {
+                    Some(output_dir) => self.print_warning(&msg!("confirm.copy_warning", path = output_dir.display()))?,
+                    None => self.print_warning(msg!("confirm.warning"))?,
+                }
                 
-                let confirmation = if let Some(progress) = &self.progress {
-                    progress.suspend(|| {
-                        dialoguer::Confirm::new()
-                            .with_prompt("Do you want to proceed?")
-                            .default(false)
-                            .interact()
-                    })
-                } else {
-                    dialoguer::Confirm::new()
-                        .with_prompt("Do you want to proceed?")
-                        .default(false)
-                        .interact()
-                };
-
-                confirmation.with_context(|| "Failed to get user confirmation")
+                let mut answer = Ok(false);
+                self.reporter.suspend(&mut || answer = crate::prompt::confirm(msg!("confirm.prompt"), false));
+                answer
             }
         }
     }
 
     /// Execute the actual changes
-    fn execute_changes(&self, content_files: &[PathBuf], rename_items: &[RenameItem]) -> Result<()> {
-        // Phase 1: Content replacement
-        if !content_files.is_empty() && self.should_process_content() {
-            self.execute_content_changes(content_files)?;
+    pub(crate) fn execute_changes(&self, content_files: &[PathBuf], rename_items: &[RenameItem]) -> Result<()> {
+        // What rollback restores: every file content replacement or import updates may write
+        let snapshot = match self.on_error {
+            OnError::Rollback => {
+                let import_files = self.find_import_updates(rename_items)?.into_iter().map(|update| update.path);
+                content_files.iter().cloned().chain(import_files)
+                    .filter_map(|path| Some((path.clone(), std::fs::read(&path).ok()?)))
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+
+        // Phase 1: Content replacement (only archives, for their member names, in names-only mode)
+        if !content_files.is_empty() {
+            let failed = self.timed("content", || self.execute_content_changes(content_files))?;
+            if failed > 0 && matches!(self.on_error, OnError::Abort | OnError::Rollback) {
+                return self.stop_after_failure(failed, &[], &snapshot);
+            }
         }
 
+        // Out of time: renames are left for the next run, and so are the imports
+        // they would update
+        if self.out_of_time() {
+            if self.should_process_names() {
+                self.left_over.lock().unwrap().renames = rename_items.iter()
+                    .filter(|item| item.original_path != item.new_path)
+                    .cloned()
+                    .collect();
+            }
+            return Ok(());
+        }
+
+        // Imports of renamed modules, after content replacement may have updated some
+        self.timed("imports", || self.execute_import_updates(rename_items))?;
+
         // Phase 2: Rename items (directories first, then files)
         if !rename_items.is_empty() && self.should_process_names() {
-            self.execute_renames(rename_items)?;
+            let (renamed, failed) = self.timed("renames", || self.execute_renames(rename_items))?;
+            if failed > 0 && matches!(self.on_error, OnError::Abort | OnError::Rollback) {
+                return self.stop_after_failure(failed, &renamed, &snapshot);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write the tree under the root to `output_dir` with the planned renames
+    /// and content changes made in the copy. Directories are written after
+    /// their entries so read-only ones can still be filled.
+    #[tracing::instrument(skip_all, fields(files = content_files.len()))]
+    fn execute_copy(&self, output_dir: &Path, content_files: &[PathBuf], rename_items: &[RenameItem]) -> Result<()> {
+        self.print_info(&msg!("execute.copy", path = output_dir.display()))?;
+
+        let root = &self.config.root_dir;
+        let content_files: HashSet<&Path> = content_files.iter().map(PathBuf::as_path).collect();
+        let new_names: HashMap<&Path, &OsStr> = rename_items
+            .iter()
+            .filter_map(|item| Some((item.original_path.as_path(), item.new_path.file_name()?)))
+            .collect();
+
+        self.file_ops.create_dir_all(output_dir)?;
+        let mut errors = Vec::new();
+        let walker = WalkDir::new(root)
+            .follow_links(self.config.follow_symlinks)
+            .min_depth(1)
+            .contents_first(true);
+        for entry in walker {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    errors.push(format!("Failed to read {}: {}", e.path().unwrap_or(root).display(), e));
+                    continue;
+                }
+            };
+            let source = entry.path();
+            let target = copy_target(root, output_dir, source, &new_names);
+            let result = if entry.file_type().is_dir() {
+                self.file_ops.copy_dir(source, &target)
+            } else if entry.file_type().is_symlink() {
+                self.file_ops.copy_symlink(source, &target)
+            } else if content_files.contains(source) {
+                let chunked = entry.metadata().is_ok_and(|metadata| metadata.len() >= CHUNKED_REPLACE_THRESHOLD);
+                self.file_ops
+                    .copy_with_rewrite(source, &target, &self.config.pattern, &self.config.substitute, chunked)
+                    .map(|_| ())
+            } else {
+                self.file_ops.copy_file(source, &target)
+            };
+
+            match result {
+                Ok(()) if self.config.verbose => {
+                    self.print_verbose(&msg!("execute.copied", from = source.display(), to = target.display()))?;
+                }
+                Ok(()) => {}
+                Err(e) => errors.push(format!("Failed to write {}: {}", target.display(), e)),
+            }
         }
 
+        for error in &errors {
+            self.print_error(error)?;
+        }
+        if !errors.is_empty() {
+            anyhow::bail!("{} item(s) could not be written to {}; the root was left untouched", errors.len(), output_dir.display());
+        }
         Ok(())
     }
 
-    /// Execute content changes
-    fn execute_content_changes(&self, content_files: &[PathBuf]) -> Result<()> {
-        self.print_info("Replacing content in files...")?;
+    /// Whether the `--time-limit` deadline has passed
+    fn out_of_time(&self) -> bool {
+        self.deadline.is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// End a run stopped at the `--time-limit` deadline: write the work left
+    /// to the checkpoint, a `checkpoint` document that `ws refactor verify`
+    /// reads like a plan, and report it
+    fn stop_at_time_limit(&self, left_over: &LeftOver) -> Result<()> {
+        let path = self.checkpoint.clone()
+            .unwrap_or_else(|| std::env::temp_dir().join(format!("refac-checkpoint-{}.json", std::process::id())));
+        let renames: Vec<serde_json::Value> = left_over.renames.iter().map(|item| {
+            let kind = match item.item_type {
+                ItemType::File => "file",
+                ItemType::Directory => "directory",
+            };
+            serde_json::json!({"type": kind, "from": item.original_path, "to": item.new_path})
+        }).collect();
+        let content: Vec<serde_json::Value> = left_over.content.iter().map(|path| serde_json::json!({"path": path})).collect();
+        let mut document = output_schema::document("checkpoint", serde_json::json!({
+            "time_limit_secs": self.time_limit.unwrap_or_default().as_secs(),
+            "renames": renames,
+            "content": content,
+        }));
+        self.record_plan_settings(&mut document);
+        std::fs::write(&path, serde_json::to_string_pretty(&document)?).with_context(|| format!("Failed to write checkpoint {}", path.display()))?;
+
+        if self.output_format == OutputFormat::Json {
+            self.reporter.document(&document);
+        }
+        self.print_warning(&msg!("execute.time_limit",
+            content = left_over.content.len(),
+            renames = left_over.renames.len(),
+            path = path.display()))?;
+        if self.config.verbose {
+            for file in &left_over.content {
+                self.print_verbose(&msg!("execute.left_content", path = file.display()))?;
+            }
+            for item in &left_over.renames {
+                self.print_verbose(&msg!("execute.left_rename", from = item.original_path.display(), to = item.new_path.display()))?;
+            }
+        }
+        anyhow::bail!("Stopped at the time limit with {} operation(s) left; run the same command again to continue", left_over.len())
+    }
+
+    /// End the run after `failed` operations failed with `--on-error abort`
+    /// or `rollback`; rollback first moves `renamed` back, latest first, and
+    /// restores the `snapshot` of file contents
+    fn stop_after_failure(&self, failed: usize, renamed: &[(PathBuf, PathBuf)], snapshot: &[(PathBuf, Vec<u8>)]) -> Result<()> {
+        if self.on_error != OnError::Rollback {
+            anyhow::bail!("Stopped after {} failed operation(s); changes made before the failure were kept", failed);
+        }
 
-        if let Some(progress) = &self.progress {
-            progress.init_content_progress(content_files.len() as u64);
+        let mut errors = Vec::new();
+        for (original, renamed_to) in renamed.iter().rev() {
+            if let Err(e) = self.file_ops.move_item(renamed_to, original) {
+                errors.push(format!("{} → {}: {}", renamed_to.display(), original.display(), e));
+            }
+        }
+        let mut restored = 0;
+        for (path, content) in snapshot {
+            if std::fs::read(path).is_ok_and(|current| &current == content) {
+                continue;
+            }
+            match self.file_ops.write_file(path, content) {
+                Ok(()) => restored += 1,
+                Err(e) => errors.push(format!("{}: {}", path.display(), e)),
+            }
+        }
+        if !errors.is_empty() {
+            anyhow::bail!("Rollback after {} failed operation(s) was incomplete: {}", failed, errors.join("; "));
         }
+        self.print_warning(&msg!("execute.rolled_back", renames = renamed.len(), files = restored))?;
+        anyhow::bail!("Rolled back after {} failed operation(s); nothing was changed", failed);
+    }
+
+    /// Execute content changes, returning how many files failed
+    #[tracing::instrument(skip_all, fields(files = content_files.len()))]
+    fn execute_content_changes(&self, content_files: &[PathBuf]) -> Result<usize> {
+        self.print_info(msg!("execute.content"))?;
+
+        self.reporter.start(Phase::Content, content_files.len() as u64);
 
         let errors = Arc::new(Mutex::new(Vec::new()));
-        let _progress_ref = &self.progress;
         let config_ref = &self.config;
         let file_ops_ref = &self.file_ops;
+        let counters_ref = &self.counters;
         let errors_ref = Arc::clone(&errors);
+        let deadline = self.deadline;
+        let left_over_ref = &self.left_over;
 
-        if self.thread_count > 1 {
+        if self.thread_count > 1 || self.adaptive_threads {
             // Parallel processing with improved error handling
-            content_files.par_iter().for_each(|file_path| {
+            let replace_file = |file_path: &PathBuf, chunked: bool| {
+                // Past the deadline, files not yet started are left for the next run
+                if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                    left_over_ref.lock().unwrap().content.push(file_path.clone());
+                    return;
+                }
+
                 // Validate file still exists before processing
                 if !file_path.exists() {
                     errors_ref.lock().unwrap().push(format!("File no longer exists: {}", file_path.display()));
                     return;
                 }
 
-                let result = file_ops_ref.replace_content(
-                    file_path,
-                    &config_ref.pattern,
-                    &config_ref.substitute,
-                );
+                let started = Instant::now();
+                let result = if chunked {
+                    file_ops_ref.replace_content_chunked(file_path, &config_ref.pattern, &config_ref.substitute)
+                } else {
+                    file_ops_ref.replace_content(file_path, &config_ref.pattern, &config_ref.substitute)
+                };
+                counters_ref.add_replace_time(started.elapsed());
 
-                match result {
-                    Ok(modified) => {
-                        if modified && config_ref.verbose {
-                            // Note: Can't use self.print_verbose in parallel context
-                            // Verbose output is handled in sequential mode
-                        }
-                    }
-                    Err(e) => {
-                        errors_ref.lock().unwrap().push(format!("Failed to modify {}: {}", file_path.display(), e));
-                    }
+                if let Err(e) = result {
+                    errors_ref.lock().unwrap().push(format!("Failed to modify {}: {}", file_path.display(), e));
                 }
-            });
+            };
+
+            // Small files cost mostly syscalls, so workers take them in batches.
+            // Files above the chunking threshold get all workers, one at a time.
+            let replace_files = |files: &[PathBuf]| {
+                let (small, medium, large) = tier_by_size(files);
+                small.par_chunks(SMALL_FILE_BATCH).for_each(|batch| {
+                    batch.iter().for_each(|file_path| replace_file(file_path, false));
+                });
+                medium.par_iter().for_each(|file_path| replace_file(file_path, false));
+                large.iter().for_each(|file_path| replace_file(file_path, true));
+            };
+
+            if self.adaptive_threads && content_files.len() >= tuning::MIN_FILES_TO_TUNE {
+                let (threads, tried) = tuning::tune(content_files, self.thread_count, tuning::SAMPLE_TIME, |threads, batch| {
+                    worker_pool(threads)?.install(|| replace_files(batch));
+                    Ok(batch.iter().map(|file_path| std::fs::metadata(file_path).map_or(0, |m| m.len())).sum())
+                })?;
+                self.tuned_threads.store(threads, Ordering::Relaxed);
+                self.print_verbose(&msg!("execute.threads_tuned", threads = threads))?;
+                worker_pool(threads)?.install(|| replace_files(&content_files[tried..]));
+            } else {
+                worker_pool(self.thread_count)?.install(|| replace_files(content_files));
+            }
         } else {
             // Sequential processing with enhanced error handling
             for file_path in content_files {
+                if self.out_of_time() {
+                    self.left_over.lock().unwrap().content.push(file_path.clone());
+                    continue;
+                }
+
                 // Validate file still exists before processing
                 if !file_path.exists() {
-                    self.print_error(&format!("File no longer exists: {}", file_path.display()))?;
-                    if let Some(progress) = &self.progress {
-                        progress.update_content(&file_path.display().to_string());
-                    }
+                    errors.lock().unwrap().push(format!("File no longer exists: {}", file_path.display()));
+                    self.reporter.advance(Phase::Content, &file_path.display().to_string());
                     continue;
                 }
 
+                let started = Instant::now();
                 let result = file_ops_ref.replace_content(
                     file_path,
                     &config_ref.pattern,
                     &config_ref.substitute,
                 );
+                counters_ref.add_replace_time(started.elapsed());
 
                 match result {
                     Ok(modified) => {
                         if modified && config_ref.verbose {
-                            self.print_verbose(&format!("Modified: {}", file_path.display()))?;
+                            self.print_verbose(&msg!("execute.modified", path = file_path.display()))?;
                         }
                     }
                     Err(e) => {
-                        self.print_error(&format!("Failed to modify {}: {}", file_path.display(), e))?;
+                        errors.lock().unwrap().push(format!("Failed to modify {}: {}", file_path.display(), e));
                     }
                 }
 
-                if let Some(progress) = &self.progress {
-                    progress.update_content(&file_path.display().to_string());
-                }
+                self.reporter.advance(Phase::Content, &file_path.display().to_string());
             }
         }
 
-        // Report any errors from parallel processing
         let errors = errors.lock().unwrap();
         for error in errors.iter() {
             self.print_error(error)?;
         }
 
-        if let Some(progress) = &self.progress {
-            progress.finish_content(&format!("Content replacement complete ({} files)", content_files.len()));
+        self.reporter.finish(Phase::Content, &msg!("progress.content_done", count = content_files.len()));
+
+        Ok(errors.len())
+    }
+
+    /// Files referring to modules that `rename_items` rename, for `--update-imports`
+    pub(crate) fn find_import_updates(&self, rename_items: &[RenameItem]) -> Result<Vec<ImportUpdate>> {
+        if !self.update_imports || !self.should_process_names() {
+            return Ok(Vec::new());
+        }
+        let renames: Vec<ModuleRename> = rename_items.iter().filter_map(ModuleRename::from_rename).collect();
+        if renames.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let walker = WalkDir::new(&self.config.root_dir)
+            .follow_links(self.config.follow_symlinks)
+            .max_depth(self.max_depth.unwrap_or(usize::MAX))
+            .into_iter()
+            .filter_entry(|e| self.should_process_entry(e));
+
+        let mut updates = Vec::new();
+        for entry in walker {
+            // Discovery already warned about symlink loops
+            let entry = match entry {
+                Err(e) if e.loop_ancestor().is_some() => continue,
+                entry => entry.with_context(|| "Failed to read directory entry")?,
+            };
+            let path = entry.path();
+            let Some(extension) = path.extension().and_then(|e| e.to_str()) else {
+                continue;
+            };
+            if !path.is_file() || !self.matches_patterns(path)? {
+                continue;
+            }
+            let replacements = reference_replacements(&self.import_languages, extension, &renames);
+            if replacements.is_empty() {
+                continue;
+            }
+            // Files that are not UTF-8 text have no imports to update
+            let Ok(content) = std::fs::read_to_string(path) else {
+                continue;
+            };
+            let (content, references) = update_references(&content, &replacements);
+            if references > 0 {
+                updates.push(ImportUpdate { path: path.to_path_buf(), references, content });
+            }
         }
+        Ok(updates)
+    }
 
+    fn show_import_updates(&self, rename_items: &[RenameItem]) -> Result<()> {
+        let updates = self.find_import_updates(rename_items)?;
+        if updates.is_empty() {
+            return Ok(());
+        }
+        self.print_info(msg!("imports.title"))?;
+        for update in &updates {
+            let relative_path = update.path.strip_prefix(&self.config.root_dir).unwrap_or(&update.path);
+            self.print_info(&msg!("imports.file", path = relative_path.display(), count = update.references))?;
+        }
         Ok(())
     }
 
-    /// Execute rename operations with proper ordering and error handling
-    fn execute_renames(&self, rename_items: &[RenameItem]) -> Result<()> {
-        self.print_info("Renaming files and directories...")?;
+    fn execute_import_updates(&self, rename_items: &[RenameItem]) -> Result<()> {
+        let updates = self.find_import_updates(rename_items)?;
+        if updates.is_empty() {
+            return Ok(());
+        }
+        self.print_info(msg!("execute.imports"))?;
+
+        for update in &updates {
+            if self.config.backup {
+                self.file_ops.create_backup(&update.path)?;
+            }
+            self.file_ops.write_file(&update.path, update.content.as_bytes())?;
+            if self.config.verbose {
+                self.print_verbose(&msg!("execute.imports_file", count = update.references, path = update.path.display()))?;
+            }
+        }
+        Ok(())
+    }
 
-        if let Some(progress) = &self.progress {
-            progress.init_rename_progress(rename_items.len() as u64);
+    /// List the archive members changed
+    fn show_archive_report(&self, archive_changes: &[(PathBuf, Vec<MemberChange>)]) -> Result<()> {
+        if archive_changes.is_empty() {
+            return Ok(());
+        }
+        self.print_info(msg!("report.archives"))?;
+        for (archive, changes) in archive_changes {
+            let relative_path = archive.strip_prefix(&self.config.root_dir).unwrap_or(archive);
+            self.print_info(&format!("  {}", relative_path.display()))?;
+            for change in changes {
+                self.print_info(&format!("    {}", change))?;
+            }
         }
+        Ok(())
+    }
+
+    /// Execute rename operations with proper ordering and error handling.
+    /// Returns the renames made, as they happened, and how many failed.
+    #[tracing::instrument(skip_all, fields(items = rename_items.len()))]
+    fn execute_renames(&self, rename_items: &[RenameItem]) -> Result<(Vec<(PathBuf, PathBuf)>, usize)> {
+        self.print_info(msg!("execute.renames"))?;
+
+        self.reporter.start(Phase::Rename, rename_items.len() as u64);
 
         let mut errors = Vec::new();
         let mut successful_renames = Vec::new();
+        let mut renamed_dirs: Vec<(PathBuf, PathBuf)> = Vec::new();
+        // Planned paths of failed renames, for --on-error skip-dependents
+        let mut failed: Vec<&Path> = Vec::new();
 
         // Process renames sequentially to maintain ordering (files before directories)
-        for item in rename_items {
-            // Skip no-op renames
-            if item.original_path == item.new_path {
-                if let Some(progress) = &self.progress {
-                    progress.update_rename(&item.original_path.display().to_string());
-                }
-                continue;
+        for (index, planned) in rename_items.iter().enumerate() {
+            if self.out_of_time() {
+                let left = rename_items[index..].iter()
+                    .filter(|planned| planned.original_path != planned.new_path)
+                    .map(|planned| RenameItem {
+                        original_path: rebase_path(&planned.original_path, &renamed_dirs),
+                        new_path: rebase_path(&planned.new_path, &renamed_dirs),
+                        ..planned.clone()
+                    });
+                self.left_over.lock().unwrap().renames.extend(left);
+                break;
             }
 
-            // Validate that source still exists (in case of race conditions, including broken symlinks)
-            let source_exists = item.original_path.exists() || item.original_path.symlink_metadata().is_ok();
-            if !source_exists {
-                errors.push(format!("Source path no longer exists: {}", item.original_path.display()));
-                if let Some(progress) = &self.progress {
-                    progress.update_rename(&item.original_path.display().to_string());
-                }
+
+            // Skip no-op renames
+            if planned.original_path == planned.new_path {
+                self.reporter.advance(Phase::Rename, &planned.original_path.display().to_string());
                 continue;
             }
 
-            // Ensure target directory exists
-            if let Some(parent) = item.new_path.parent() {
-                if let Err(e) = self.file_ops.create_dir_all(parent) {
-                    errors.push(format!("Failed to create parent directory for {}: {}", 
-                                      item.new_path.display(), e));
-                    if let Some(progress) = &self.progress {
-                        progress.update_rename(&item.original_path.display().to_string());
-                    }
+            // A directory whose contents were left half-renamed, or an entry
+            // of a directory that could not be renamed, stays as it is
+            if self.on_error == OnError::SkipDependents {
+                let cause = failed.iter().find(|path| planned.original_path.starts_with(path) || path.starts_with(&planned.original_path));
+                if let Some(cause) = cause {
+                    self.print_warning(&msg!("execute.rename_skipped",
+                        path = planned.original_path.display(),
+                        failed = cause.display()))?;
+                    self.reporter.advance(Phase::Rename, &planned.original_path.display().to_string());
                     continue;
                 }
             }
 
-            let result = self.file_ops.move_item(&item.original_path, &item.new_path);
+            // Paths were planned against the tree before any rename; follow
+            // directories already renamed in case a parent went first
+            let item = &RenameItem {
+                original_path: rebase_path(&planned.original_path, &renamed_dirs),
+                new_path: rebase_path(&planned.new_path, &renamed_dirs),
+                ..planned.clone()
+            };
 
-            match result {
+            match self.rename_item(item) {
                 Ok(()) => {
+                    if item.item_type == ItemType::Directory {
+                        renamed_dirs.push((item.original_path.clone(), item.new_path.clone()));
+                    }
                     successful_renames.push((item.original_path.clone(), item.new_path.clone()));
                     if self.config.verbose {
-                        self.print_verbose(&format!("Renamed: {} → {}", 
-                            item.original_path.display(), 
-                            item.new_path.display()))?;
+                        self.print_verbose(&msg!("execute.renamed",
+                            from = item.original_path.display(),
+                            to = item.new_path.display()))?;
                     }
                 }
-                Err(e) => {
-                    errors.push(format!("Failed to rename {} to {}: {}", 
-                        item.original_path.display(), 
-                        item.new_path.display(),
-                        e));
+                Err(error) => {
+                    errors.push(error);
+                    failed.push(&planned.original_path);
                 }
             }
 
-            if let Some(progress) = &self.progress {
-                progress.update_rename(&item.original_path.display().to_string());
+            self.reporter.advance(Phase::Rename, &item.original_path.display().to_string());
+            if !errors.is_empty() && matches!(self.on_error, OnError::Abort | OnError::Rollback) {
+                break;
             }
         }
 
@@ -893,21 +1800,38 @@ impl RenameEngine {
         }
 
         if !errors.is_empty() {
-            self.print_warning(&format!("{} rename operation(s) failed out of {}", 
-                                      errors.len(), rename_items.len()))?;
+            self.print_warning(&msg!("execute.rename_failures",
+                                      failed = errors.len(), total = rename_items.len()))?;
         }
 
-        if let Some(progress) = &self.progress {
-            progress.finish_rename(&format!("Rename complete ({} successful, {} failed)", 
-                                          successful_renames.len(), errors.len()));
+        self.reporter.finish(Phase::Rename, &msg!("progress.rename_done",
+                                                  successful = successful_renames.len(), failed = errors.len()));
+
+        Ok((successful_renames, errors.len()))
+    }
+
+    /// Move one item into place, checking first that it is still there
+    fn rename_item(&self, item: &RenameItem) -> std::result::Result<(), String> {
+        // Validate that source still exists (in case of race conditions, including broken symlinks)
+        let source_exists = item.original_path.exists() || item.original_path.symlink_metadata().is_ok();
+        if !source_exists {
+            return Err(format!("Source path no longer exists: {}", item.original_path.display()));
         }
 
-        Ok(())
+        // Ensure target directory exists
+        if let Some(parent) = item.new_path.parent() {
+            self.file_ops.create_dir_all(parent)
+                .map_err(|e| format!("Failed to create parent directory for {}: {}", item.new_path.display(), e))?;
+        }
+
+        self.file_ops.move_item(&item.original_path, &item.new_path)
+            .map_err(|e| format!("Failed to rename {} to {}: {}", item.original_path.display(), item.new_path.display(), e))
     }
 
     /// Validate all operations before execution (mandatory validation phase)
     /// This catches all potential issues before making any changes
-    fn validate_all_operations(&self, content_files: &[PathBuf], rename_items: &[RenameItem]) -> Result<()> {
+    #[tracing::instrument(skip_all, fields(files = content_files.len(), items = rename_items.len()))]
+    pub(crate) fn validate_all_operations(&self, content_files: &[PathBuf], rename_items: &[RenameItem]) -> Result<()> {
         let mut validation_errors = Vec::new();
 
         // Validate content replacement operations
@@ -923,6 +1847,17 @@ impl RenameEngine {
         // Validate that operation will not leave empty directories
         self.validate_no_empty_directories_remain(rename_items, &mut validation_errors)?;
 
+        // Locked files are waited for during execution with --retry-locked
+        if self.retry_locked > 0 {
+            let (locked, errors): (Vec<_>, Vec<_>) = validation_errors.into_iter()
+                .partition(|e| matches!(e.error_type, ValidationErrorType::Locked));
+            for error in &locked {
+                let relative_path = error.location.strip_prefix(&self.config.root_dir).unwrap_or(&error.location);
+                self.print_warning(&msg!("validation.locked_retry", path = relative_path.display(), count = self.retry_locked))?;
+            }
+            validation_errors = errors;
+        }
+
         // Report all validation errors with enhanced formatting
         if !validation_errors.is_empty() {
             self.report_validation_errors(&validation_errors)?;
@@ -938,6 +1873,7 @@ impl RenameEngine {
                     ValidationErrorType::ParentDirectoryError => "directory issues",
                     ValidationErrorType::ContentNotFound => "content issues",
                     ValidationErrorType::EmptyDirectoryIssue => "directory structure issues",
+                    ValidationErrorType::Locked => "locked files",
                     _ => "other issues",
                 }
             }).collect();
@@ -946,7 +1882,7 @@ impl RenameEngine {
             anyhow::bail!("Operation cannot proceed due to {} validation error(s): {}", validation_errors.len(), error_summary);
         }
 
-        self.print_info("Validation passed: All operations can be performed safely.")?;
+        self.print_info(msg!("validation.passed"))?;
         Ok(())
     }
 
@@ -975,6 +1911,11 @@ impl RenameEngine {
             return;
         }
 
+        if self.file_ops.is_locked(file_path) {
+            validation_errors.push(self.locked_error(file_path, relative_path));
+            return;
+        }
+
         // Check if file is readable and writable
         if let Err(e) = std::fs::OpenOptions::new().read(true).write(true).open(file_path) {
             let error_type = if e.kind() == std::io::ErrorKind::PermissionDenied {
@@ -992,13 +1933,16 @@ impl RenameEngine {
             return;
         }
 
+        // The handler decides what it can read; archives were read in discovery
+        if self.file_ops.handler_for(file_path).is_some() || self.file_ops.is_archive(file_path) {
+            return;
+        }
+
         // Check if file is binary before attempting to read as text
         match self.file_ops.is_text_file(file_path) {
             Ok(false) => {
                 // Binary file - skip validation (should not have been included in content_files)
-                if self.config.verbose {
-                    eprintln!("WARNING: Binary file {} was included in content validation - this may indicate a discovery phase issue", relative_path.display());
-                }
+                let _ = self.print_verbose(&format!("Binary file {} was included in content validation - this may indicate a discovery phase issue", relative_path.display()));
                 return; // Binary files are skipped during content processing
             }
             Err(e) => {
@@ -1041,6 +1985,15 @@ impl RenameEngine {
         }
     }
 
+    fn locked_error(&self, path: &Path, relative_path: &Path) -> ValidationError {
+        ValidationError {
+            location: path.to_path_buf(),
+            error_type: ValidationErrorType::Locked,
+            message: format!("File is locked by another process: {}", relative_path.display()),
+            suggestion: Some("Close the program using it, or pass --retry-locked N to wait for it".to_string()),
+        }
+    }
+
     /// Validate a single rename operation
     fn validate_rename_item(&self, item: &RenameItem, validation_errors: &mut Vec<ValidationError>) {
         let relative_source = item.original_path.strip_prefix(&self.config.root_dir)
@@ -1065,6 +2018,11 @@ impl RenameEngine {
             return;
         }
 
+        if self.file_ops.is_locked(&item.original_path) {
+            validation_errors.push(self.locked_error(&item.original_path, relative_source));
+            return;
+        }
+
         // Check target doesn't already exist (unless it's the same as source)
         if item.new_path.exists() && item.new_path != item.original_path {
             validation_errors.push(ValidationError {
@@ -1123,8 +2081,8 @@ impl RenameEngine {
 
     /// Report validation errors with enhanced formatting and organization
     fn report_validation_errors(&self, validation_errors: &[ValidationError]) -> Result<()> {
-        self.print_error("=== VALIDATION FAILED ===")?;
-        self.print_error(&format!("Found {} issue(s) that prevent safe execution:", validation_errors.len()))?;
+        self.print_error(msg!("validation.title"))?;
+        self.print_error(&msg!("validation.count", count = validation_errors.len()))?;
         self.print_error("")?;
 
         // Group errors by type for better organization
@@ -1133,16 +2091,17 @@ impl RenameEngine {
         
         for error in validation_errors {
             let type_name = match error.error_type {
-                ValidationErrorType::FileNotFound => "Missing Files/Directories",
-                ValidationErrorType::PermissionDenied => "Permission Issues", 
-                ValidationErrorType::EncodingError => "Encoding/Binary File Issues",
-                ValidationErrorType::NotAFile => "File Type Issues",
-                ValidationErrorType::ReadOnlyFile => "Read-Only Files",
-                ValidationErrorType::TargetExists => "Target Conflicts",
-                ValidationErrorType::ParentDirectoryError => "Directory Creation Issues",
-                ValidationErrorType::ContentNotFound => "Content Issues",
-                ValidationErrorType::EmptyDirectoryIssue => "Directory Structure Issues",
-                _ => "Other Issues",
+                ValidationErrorType::FileNotFound => msg!("validation.missing"),
+                ValidationErrorType::PermissionDenied => msg!("validation.permissions"),
+                ValidationErrorType::EncodingError => msg!("validation.encoding"),
+                ValidationErrorType::NotAFile => msg!("validation.file_type"),
+                ValidationErrorType::ReadOnlyFile => msg!("validation.read_only"),
+                ValidationErrorType::TargetExists => msg!("validation.target_exists"),
+                ValidationErrorType::ParentDirectoryError => msg!("validation.parent_directory"),
+                ValidationErrorType::ContentNotFound => msg!("validation.content"),
+                ValidationErrorType::EmptyDirectoryIssue => msg!("validation.empty_directory"),
+                ValidationErrorType::Locked => msg!("validation.locked"),
+                _ => msg!("validation.other"),
             };
             
             errors_by_type.entry(type_name.to_string())
@@ -1152,23 +2111,23 @@ impl RenameEngine {
 
         // Report errors grouped by type
         for (error_type, errors) in errors_by_type {
-            self.print_error(&format!("📋 {}: ({} issue(s))", error_type, errors.len()))?;
+            self.print_error(&msg!("validation.group", group = error_type, count = errors.len()))?;
             
             for error in errors {
                 let relative_path = error.location.strip_prefix(&self.config.root_dir)
                     .unwrap_or(&error.location);
                 
-                self.print_error(&format!("   📍 Location: {}", relative_path.display()))?;
-                self.print_error(&format!("      Problem: {}", error.message))?;
+                self.print_error(&msg!("validation.location", path = relative_path.display()))?;
+                self.print_error(&msg!("validation.problem", message = error.message))?;
                 
                 if let Some(suggestion) = &error.suggestion {
-                    self.print_error(&format!("      Suggestion: {}", suggestion))?;
+                    self.print_error(&msg!("validation.suggestion", suggestion = suggestion))?;
                 }
                 self.print_error("")?;
             }
         }
 
-        self.print_error("❌ Cannot proceed until these issues are resolved.")?;
+        self.print_error(msg!("validation.cannot_proceed"))?;
         Ok(())
     }
 
@@ -1249,10 +2208,10 @@ impl RenameEngine {
     }
 
     /// Show final report
-    fn show_final_report(&self, stats: &RenameStats) -> Result<()> {
+    fn show_final_report(&self, stats: &RenameStats, new_root: Option<&Path>) -> Result<()> {
         match self.output_format {
             OutputFormat::Json => {
-                let report = serde_json::json!({
+                let report = output_schema::document("report", serde_json::json!({
                     "result": "success",
                     "stats": {
                         "content_changes": stats.files_with_content_changes,
@@ -1261,20 +2220,34 @@ impl RenameEngine {
                         "total_changes": stats.total_changes(),
                         "errors": stats.errors.len()
                     },
-                });
-                println!("{}", serde_json::to_string_pretty(&report)?);
+                    "new_root": new_root,
+                    "output_dir": self.output_dir,
+                }));
+                self.reporter.document(&report);
             }
             OutputFormat::Plain => {
-                println!("Operation completed successfully.");
-                println!("Total changes: {}", stats.total_changes());
+                self.reporter.text("Operation completed successfully.");
+                self.reporter.text(&format!("Total changes: {}", stats.total_changes()));
+                if let Some(root) = new_root {
+                    self.reporter.text(&format!("New root: {}", root.display()));
+                }
+                if let Some(output_dir) = &self.output_dir {
+                    self.reporter.text(&format!("Output directory: {}", output_dir.display()));
+                }
             }
             OutputFormat::Human => {
-                self.print_success("=== OPERATION COMPLETE ===")?;
-                self.print_success("Operation completed successfully!")?;
-                self.print_info(&format!("Total changes applied: {}", stats.total_changes()))?;
+                self.print_success(msg!("report.title"))?;
+                self.print_success(msg!("report.success"))?;
+                self.print_info(&msg!("report.total", count = stats.total_changes()))?;
+                if let Some(root) = new_root {
+                    self.print_info(&msg!("report.new_root", path = root.display()))?;
+                }
+                if let Some(output_dir) = &self.output_dir {
+                    self.print_info(&msg!("report.output_dir", path = output_dir.display()))?;
+                }
 
                 if !stats.errors.is_empty() {
-                    self.print_warning(&format!("{} error(s) occurred:", stats.errors.len()))?;
+                    self.print_warning(&msg!("report.errors", count = stats.errors.len()))?;
                     for error in &stats.errors {
                         self.print_error(error)?;
                     }
@@ -1291,63 +2264,62 @@ impl RenameEngine {
             return Ok(());
         }
 
-        self.print_success("=== WORKSPACE REFAC TOOL ===")?;
-        self.print_info(&format!("Root directory: {}", self.config.root_dir.display()))?;
-        self.print_info(&format!("Pattern: '{}'", self.config.pattern))?;
-        self.print_info(&format!("Substitute: '{}'", self.config.substitute))?;
-        self.print_info(&format!("Mode: {:?}", self.mode))?;
+        self.print_success(msg!("header.title"))?;
+        self.print_info(&msg!("header.root", path = self.config.root_dir.display()))?;
+        self.print_info(&msg!("header.pattern", pattern = self.config.pattern))?;
+        self.print_info(&msg!("header.substitute", substitute = self.config.substitute))?;
+        self.print_info(&msg!("header.mode", mode = format!("{:?}", self.mode)))?;
         
         
         if self.config.backup {
-            self.print_info("Backup mode: Enabled")?;
+            self.print_info(msg!("header.backup"))?;
         }
 
         Ok(())
     }
 
+    /// Run one phase of the refactor, recording how long it took for `--stats`
+    fn timed<T>(&self, phase: &'static str, run: impl FnOnce() -> Result<T>) -> Result<T> {
+        let start = Instant::now();
+        let result = run();
+        self.counters.record_phase(phase, start.elapsed());
+        result
+    }
+
+    /// Print the `--stats` counters as JSON on stderr, apart from the regular output
+    fn print_stats(&self, total: std::time::Duration) -> Result<()> {
+        let threads = match self.tuned_threads.load(Ordering::Relaxed) {
+            0 => self.thread_count,
+            tuned => tuned,
+        };
+        let report = self.counters.report(self.file_ops.detector_stats(), threads, total);
+        eprintln!("{}", serde_json::to_string(&report)?);
+        Ok(())
+    }
+
     // Utility methods for printing
     fn print_info(&self, message: &str) -> Result<()> {
-        if let Some(progress) = &self.progress {
-            progress.print_info(message);
-        } else if let Some(output) = &self.simple_output {
-            output.print_info(message);
-        }
+        self.reporter.info(message);
         Ok(())
     }
 
     fn print_error(&self, message: &str) -> Result<()> {
-        if let Some(progress) = &self.progress {
-            progress.print_error(message);
-        } else if let Some(output) = &self.simple_output {
-            output.print_error(message);
-        }
+        self.reporter.error(message);
         Ok(())
     }
 
     fn print_warning(&self, message: &str) -> Result<()> {
-        if let Some(progress) = &self.progress {
-            progress.print_warning(message);
-        } else if let Some(output) = &self.simple_output {
-            output.print_warning(message);
-        }
+        self.reporter.warning(message);
         Ok(())
     }
 
     fn print_success(&self, message: &str) -> Result<()> {
-        if let Some(progress) = &self.progress {
-            progress.print_success(message);
-        } else if let Some(output) = &self.simple_output {
-            output.print_success(message);
-        }
+        self.reporter.success(message);
         Ok(())
     }
 
     fn print_verbose(&self, message: &str) -> Result<()> {
-        if let Some(progress) = &self.progress {
-            progress.print_verbose(message);
-        } else if let Some(output) = &self.simple_output {
-            output.print_verbose(message);
-        }
+        self.reporter.verbose(message);
         Ok(())
     }
 
@@ -1385,3 +2357,384 @@ impl ModeExt for Mode {
     }
 }
 
+/// A rayon pool of `threads` workers for the parallel phases
+fn worker_pool(threads: usize) -> Result<rayon::ThreadPool> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads.max(1))
+        .build()
+        .context("Failed to start worker threads")
+}
+
+/// `files` split into small, medium and large (chunk-parallel) by size.
+/// Files whose size is unknown count as small.
+fn tier_by_size(files: &[PathBuf]) -> (Vec<PathBuf>, Vec<PathBuf>, Vec<PathBuf>) {
+    let (mut small, mut medium, mut large) = (Vec::new(), Vec::new(), Vec::new());
+    for file_path in files {
+        match std::fs::metadata(file_path).map_or(0, |metadata| metadata.len()) {
+            size if size >= CHUNKED_REPLACE_THRESHOLD => large.push(file_path.clone()),
+            size if size >= SMALL_FILE_BYTES => medium.push(file_path.clone()),
+            _ => small.push(file_path.clone()),
+        }
+    }
+    (small, medium, large)
+}
+
+/// Keeps `--follow-symlinks` discovery finite: directories are walked once
+/// however many links lead to them, and a link is not followed when it
+/// would make more than `max_depth` links in a row under the root
+struct SymlinkGuard {
+    root: PathBuf,
+    max_depth: usize,
+    /// Directories walked so far, by (device, inode)
+    visited: HashSet<(u64, u64)>,
+}
+
+impl SymlinkGuard {
+    fn new(root: &Path, max_depth: usize) -> Self {
+        Self { root: root.to_path_buf(), max_depth, visited: HashSet::new() }
+    }
+
+    /// Why the walk should not descend into `entry`, if it should not
+    fn check(&mut self, entry: &DirEntry) -> Option<SkipReason> {
+        if !entry.file_type().is_dir() {
+            return None;
+        }
+        if entry.path_is_symlink() && self.links_in_path(entry.path()) > self.max_depth {
+            return Some(SkipReason::SymlinkDepth);
+        }
+        match file_id(entry.path()) {
+            Some(id) if !self.visited.insert(id) => Some(SkipReason::SymlinkCycle),
+            _ => None,
+        }
+    }
+
+    /// Symlinks among `path` and its ancestors below the root
+    fn links_in_path(&self, path: &Path) -> usize {
+        path.ancestors()
+            .take_while(|ancestor| *ancestor != self.root && ancestor.starts_with(&self.root))
+            .filter(|ancestor| ancestor.symlink_metadata().is_ok_and(|m| m.file_type().is_symlink()))
+            .count()
+    }
+}
+
+/// Device and inode of what `path` points to
+#[cfg(unix)]
+fn file_id(path: &Path) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata(path).ok().map(|metadata| (metadata.dev(), metadata.ino()))
+}
+
+/// Without inodes only walkdir's own check for links to ancestors applies
+#[cfg(not(unix))]
+fn file_id(_path: &Path) -> Option<(u64, u64)> {
+    None
+}
+
+/// Whether `path` names a hidden file or directory (one starting with '.')
+fn is_hidden(path: &Path) -> bool {
+    path.file_name().and_then(|name| name.to_str()).is_some_and(|name| name.starts_with('.'))
+}
+
+/// Where `path` is after `renamed_dirs` (from, to) were renamed in order
+fn rebase_path(path: &Path, renamed_dirs: &[(PathBuf, PathBuf)]) -> PathBuf {
+    renamed_dirs.iter().fold(path.to_path_buf(), |path, (from, to)| match path.strip_prefix(from) {
+        Ok(rest) if !rest.as_os_str().is_empty() => to.join(rest),
+        _ => path,
+    })
+}
+
+/// Where `path` under `root` goes in the copy at `output_dir`, with each of
+/// its components given its planned `new_names`
+fn copy_target(root: &Path, output_dir: &Path, path: &Path, new_names: &HashMap<&Path, &OsStr>) -> PathBuf {
+    let mut source = root.to_path_buf();
+    let mut target = output_dir.to_path_buf();
+    for component in path.strip_prefix(root).unwrap_or(path).components() {
+        source.push(component);
+        match new_names.get(source.as_path()) {
+            Some(new_name) => target.push(new_name),
+            None => target.push(component),
+        }
+    }
+    target
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use tempfile::TempDir;
+
+    /// Relative paths of everything under `root`, sorted
+    fn tree(root: &Path) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = WalkDir::new(root)
+            .min_depth(1)
+            .into_iter()
+            .map(|entry| entry.unwrap().path().strip_prefix(root).unwrap().to_path_buf())
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn test_copy_target() {
+        let (from_dir, from_file) = (PathBuf::from("/r/foo"), PathBuf::from("/r/foo/foo.txt"));
+        let new_names = HashMap::from([(from_dir.as_path(), OsStr::new("bar")), (from_file.as_path(), OsStr::new("bar.txt"))]);
+        let target = |path: &str| copy_target(Path::new("/r"), Path::new("/out"), Path::new(path), &new_names);
+        assert_eq!(target("/r/foo/foo.txt"), Path::new("/out/bar/bar.txt"));
+        assert_eq!(target("/r/foo/other.txt"), Path::new("/out/bar/other.txt"));
+        assert_eq!(target("/r/keep/foo.txt"), Path::new("/out/keep/foo.txt"));
+    }
+
+    #[test]
+    fn test_rebase_path() {
+        let renamed = [(PathBuf::from("/r/foo"), PathBuf::from("/r/bar")), (PathBuf::from("/r/bar/foo"), PathBuf::from("/r/bar/baz"))];
+        assert_eq!(rebase_path(Path::new("/r/foo/foo/a.txt"), &renamed), Path::new("/r/bar/baz/a.txt"));
+        assert_eq!(rebase_path(Path::new("/r/foo"), &renamed), Path::new("/r/foo"));
+        assert_eq!(rebase_path(Path::new("/r/food/a.txt"), &renamed), Path::new("/r/food/a.txt"));
+    }
+
+    #[test]
+    fn test_skipped_reasons() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        std::fs::write(root.join("foo.txt"), "foo")?;
+        std::fs::write(root.join(".foo_hidden"), "foo")?;
+        std::fs::write(root.join("foo.log"), "foo")?;
+        std::fs::write(root.join("foo.bin"), [0u8, 159, 146, 150, 0, 1, 2, 3])?;
+        std::fs::write(root.join("frozen.txt"), "foo\nDO NOT RENAME\n")?;
+
+        let root_arg = root.to_string_lossy().to_string();
+        let engine = RenameEngine::new(Args::parse_from([
+            "refac", root_arg.as_str(), "foo", "bar", "--assume-yes", "--format", "plain",
+            "--exclude", "*.log", "--exclude-containing", "DO NOT RENAME",
+        ]))?;
+        let (content_files, _) = engine.discover_items()?;
+        assert_eq!(content_files, vec![root.join("foo.txt")]);
+
+        let skipped: Vec<(PathBuf, &str)> = engine.skipped().iter().map(|(path, reason)| (path.clone(), reason.name())).collect();
+        assert_eq!(skipped, vec![
+            (root.join(".foo_hidden"), "hidden"),
+            (root.join("foo.bin"), "binary"),
+            (root.join("foo.log"), "excluded"),
+            (root.join("frozen.txt"), "excluded-content"),
+        ]);
+        Ok(())
+    }
+
+    /// Executing the plan in any order, parents before children included,
+    /// must end in the tree the plan describes
+    #[test]
+    fn test_rename_order_simulation() -> Result<()> {
+        let layout = ["foo/foo.txt", "foo/foo_foo/foo-foo.txt", "foo/foo_foo/foo/foofoo.txt", "foo/keep.txt", "other/foo/foo.md"];
+        let expected: Vec<PathBuf> = {
+            let source = TempDir::new()?;
+            for file in layout {
+                let path = source.path().join(file.replace("foo", "bar"));
+                std::fs::create_dir_all(path.parent().unwrap())?;
+                std::fs::write(path, "")?;
+            }
+            tree(source.path())
+        };
+
+        let orders: [fn(&mut Vec<RenameItem>); 3] = [|_| {}, |items| items.reverse(), |items| items.rotate_left(items.len() / 2)];
+        for reorder in orders {
+            let temp_dir = TempDir::new()?;
+            for file in layout {
+                let path = temp_dir.path().join(file);
+                std::fs::create_dir_all(path.parent().unwrap())?;
+                std::fs::write(path, "")?;
+            }
+
+            let root = temp_dir.path().to_string_lossy().to_string();
+            let engine = RenameEngine::new(Args::parse_from(["refac", root.as_str(), "foo", "bar", "--names-only", "--assume-yes", "--format", "plain"]))?;
+            let (_, mut rename_items) = engine.discover_items()?;
+            reorder(&mut rename_items);
+            engine.execute_renames(&rename_items)?;
+
+            assert_eq!(tree(temp_dir.path()), expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_on_error_policies() -> Result<()> {
+        // foo/foo.txt, then foo_top.txt, then the foo directory; foo_top.txt
+        // disappears after discovery so its rename fails
+        let run = |policy: &str| -> Result<(TempDir, bool)> {
+            let temp_dir = TempDir::new()?;
+            std::fs::create_dir_all(temp_dir.path().join("foo"))?;
+            for file in ["foo/foo.txt", "foo/keep.txt", "foo_top.txt"] {
+                std::fs::write(temp_dir.path().join(file), "")?;
+            }
+            let root = temp_dir.path().to_string_lossy().to_string();
+            let engine = RenameEngine::new(Args::parse_from([
+                "refac", root.as_str(), "foo", "bar", "--names-only", "--assume-yes", "--format", "plain", "--on-error", policy,
+            ]))?;
+            let (_, rename_items) = engine.discover_items()?;
+            std::fs::remove_file(temp_dir.path().join("foo_top.txt"))?;
+            let ok = engine.execute_changes(&[], &rename_items).is_ok();
+            Ok((temp_dir, ok))
+        };
+        let paths = |temp_dir: &TempDir| tree(temp_dir.path()).into_iter().map(|p| p.to_string_lossy().replace('\\', "/")).collect::<Vec<_>>();
+
+        let (temp_dir, ok) = run("continue")?;
+        assert!(ok);
+        assert_eq!(paths(&temp_dir), ["bar", "bar/bar.txt", "bar/keep.txt"]);
+
+        // A failure outside foo does not hold back its renames
+        let (temp_dir, ok) = run("skip-dependents")?;
+        assert!(ok);
+        assert_eq!(paths(&temp_dir), ["bar", "bar/bar.txt", "bar/keep.txt"]);
+
+        let (temp_dir, ok) = run("abort")?;
+        assert!(!ok);
+        assert_eq!(paths(&temp_dir), ["foo", "foo/bar.txt", "foo/keep.txt"]);
+
+        let (temp_dir, ok) = run("rollback")?;
+        assert!(!ok);
+        assert_eq!(paths(&temp_dir), ["foo", "foo/foo.txt", "foo/keep.txt"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_time_limit_writes_checkpoint() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let work = temp_dir.path().join("work");
+        std::fs::create_dir_all(work.join("foo"))?;
+        std::fs::write(work.join("foo/foo.txt"), "foo\n")?;
+        let checkpoint = temp_dir.path().join("checkpoint.json");
+        let args = |extra: &[&str]| {
+            let mut args = vec!["refac", work.to_str().unwrap(), "foo", "bar", "--assume-yes", "--format", "plain"];
+            args.extend_from_slice(extra);
+            Args::parse_from(args)
+        };
+
+        // A deadline already passed leaves every operation for the next run
+        let mut engine = RenameEngine::new(args(&["--time-limit", "1h", "--checkpoint", checkpoint.to_str().unwrap()]))?;
+        engine.deadline = Some(Instant::now());
+        assert!(engine.execute().is_err());
+        assert_eq!(tree(&work), [Path::new("foo"), Path::new("foo/foo.txt")]);
+
+        let plan = super::super::verify::Plan::load(&checkpoint)?;
+        assert_eq!(plan.content, [work.join("foo/foo.txt")]);
+        assert_eq!(plan.renames.len(), 2);
+        assert!(plan.verify().iter().all(|check| !check.passed()));
+
+        // Running again finishes the work the checkpoint lists
+        RenameEngine::new(args(&[]))?.execute()?;
+        assert!(plan.verify().iter().all(|check| check.passed()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_skip_dependents_of_failed_rename() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        std::fs::create_dir_all(temp_dir.path().join("foo/foo_sub"))?;
+        std::fs::write(temp_dir.path().join("foo/foo_sub/foo.txt"), "")?;
+        std::fs::write(temp_dir.path().join("foo/other_foo.txt"), "")?;
+        let root = temp_dir.path().to_string_lossy().to_string();
+        let engine = RenameEngine::new(Args::parse_from([
+            "refac", root.as_str(), "foo", "bar", "--names-only", "--assume-yes", "--format", "plain", "--on-error", "skip-dependents",
+        ]))?;
+        let (_, rename_items) = engine.discover_items()?;
+        std::fs::remove_file(temp_dir.path().join("foo/foo_sub/foo.txt"))?;
+
+        // Its directories stay put; the unrelated sibling is still renamed
+        let (renamed, failed) = engine.execute_renames(&rename_items)?;
+        assert_eq!(failed, 1);
+        assert_eq!(renamed, vec![(temp_dir.path().join("foo/other_foo.txt"), temp_dir.path().join("foo/other_bar.txt"))]);
+        assert!(temp_dir.path().join("foo/foo_sub").is_dir());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_follow_symlinks_breaks_cycles() -> Result<()> {
+        use std::os::unix::fs::symlink;
+        let temp_dir = TempDir::new()?;
+        let base = temp_dir.path().canonicalize()?;
+        let root_path = base.join("root");
+        std::fs::create_dir_all(root_path.join("foo_dir/inner"))?;
+        std::fs::write(root_path.join("foo_dir/inner/foo.txt"), "foo")?;
+        // A link to its own ancestor, and a second way into the same directory
+        symlink(&root_path, root_path.join("foo_dir/inner/back"))?;
+        symlink(root_path.join("foo_dir"), root_path.join("alias"))?;
+        let root = root_path.to_string_lossy().to_string();
+        let engine = RenameEngine::new(Args::parse_from([
+            "refac", root.as_str(), "foo", "bar", "--follow-symlinks", "--assume-yes", "--format", "plain",
+        ]))?;
+        let (content_files, _) = engine.discover_items()?;
+        assert_eq!(content_files.iter().filter(|path| path.ends_with("foo.txt")).count(), 1);
+        let cycles: Vec<PathBuf> = engine.skipped()
+            .into_iter()
+            .filter(|(_, reason)| *reason == SkipReason::SymlinkCycle)
+            .map(|(path, _)| path)
+            .collect();
+        assert_eq!(cycles.len(), 2, "{:?}", cycles);
+        assert!(cycles.iter().any(|path| path.ends_with("inner/back")));
+
+        // Two links in a row, outside the root: followed by default, not with a limit of one
+        std::fs::create_dir_all(base.join("outside/first"))?;
+        std::fs::create_dir_all(base.join("outside/second"))?;
+        std::fs::write(base.join("outside/second/foo_deep.txt"), "foo")?;
+        symlink(base.join("outside/second"), base.join("outside/first/next"))?;
+        symlink(base.join("outside/first"), root_path.join("chain"))?;
+        let discover = |extra: &[&str]| -> Result<RenameEngine> {
+            let mut args = vec!["refac", root.as_str(), "foo", "bar", "--follow-symlinks", "--assume-yes", "--format", "plain"];
+            args.extend_from_slice(extra);
+            let engine = RenameEngine::new(Args::parse_from(args))?;
+            let (content_files, _) = engine.discover_items()?;
+            assert_eq!(
+                content_files.iter().any(|path| path.ends_with("foo_deep.txt")),
+                extra.is_empty()
+            );
+            Ok(engine)
+        };
+        discover(&[])?;
+        let engine = discover(&["--max-symlink-depth", "1"])?;
+        assert!(engine.skipped().contains(&(root_path.join("chain/next"), SkipReason::SymlinkDepth)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_injected_reporter_receives_output() -> Result<()> {
+        #[derive(Default)]
+        struct Recorded {
+            documents: Vec<serde_json::Value>,
+            phases: Vec<Phase>,
+            errors: usize,
+        }
+        struct Recorder(Arc<Mutex<Recorded>>);
+        impl Reporter for Recorder {
+            fn info(&self, _message: &str) {}
+            fn warning(&self, _message: &str) {}
+            fn error(&self, _message: &str) {
+                self.0.lock().unwrap().errors += 1;
+            }
+            fn success(&self, _message: &str) {}
+            fn verbose(&self, _message: &str) {}
+            fn text(&self, _text: &str) {}
+            fn document(&self, document: &serde_json::Value) {
+                self.0.lock().unwrap().documents.push(document.clone());
+            }
+            fn start(&self, phase: Phase, _total: u64) {
+                self.0.lock().unwrap().phases.push(phase);
+            }
+        }
+
+        let temp_dir = TempDir::new()?;
+        std::fs::write(temp_dir.path().join("foo.txt"), "foo\n")?;
+        let root = temp_dir.path().to_string_lossy().to_string();
+        let recorded = Arc::new(Mutex::new(Recorded::default()));
+        RenameEngine::new(Args::parse_from(["refac", root.as_str(), "foo", "bar", "--assume-yes", "--format", "json"]))?
+            .with_reporter(Recorder(Arc::clone(&recorded)))
+            .execute()?;
+
+        let recorded = recorded.lock().unwrap();
+        assert_eq!(recorded.phases, [Phase::Discovery, Phase::Content, Phase::Rename]);
+        assert_eq!(recorded.documents.last().unwrap()["kind"], "report");
+        assert_eq!(recorded.errors, 0);
+        assert_eq!(std::fs::read_to_string(temp_dir.path().join("bar.txt"))?, "bar\n");
+        Ok(())
+    }
+}
@@ -0,0 +1,208 @@
+//! Where the refactor engine sends its messages, progress and result documents
+//!
+//! The engine never prints on its own; it goes through a [`Reporter`]. `ws refactor`
+//! picks one from `--format` and `--progress`, and library callers can hand the
+//! engine their own through [`RenameEngine::with_reporter`](super::RenameEngine::with_reporter).
+
+use super::messages::msg;
+use super::progress::{ProgressTracker, SimpleOutput};
+
+/// Phases of a run that report progress
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Discovery,
+    Content,
+    Rename,
+}
+
+/// Receives everything the engine has to say during a run
+pub trait Reporter: Send {
+    fn info(&self, message: &str);
+    fn warning(&self, message: &str);
+    fn error(&self, message: &str);
+    fn success(&self, message: &str);
+    /// Detail only shown with `--verbose`
+    fn verbose(&self, message: &str);
+    /// Text printed as is: plain summaries and diff context
+    fn text(&self, text: &str);
+    /// A result document of `--format json`
+    fn document(&self, document: &serde_json::Value);
+
+    /// A phase starts with `total` items, 0 when not known up front
+    fn start(&self, _phase: Phase, _total: u64) {}
+    /// One item of the phase is done
+    fn advance(&self, _phase: Phase, _item: &str) {}
+    fn finish(&self, _phase: Phase, _message: &str) {}
+    /// Run a prompt with any progress display out of the way
+    fn suspend(&self, prompt: &mut dyn FnMut()) {
+        prompt()
+    }
+}
+
+fn pretty(document: &serde_json::Value) -> String {
+    serde_json::to_string_pretty(document).unwrap_or_default()
+}
+
+/// Colored messages under progress bars, for an interactive terminal
+pub struct TerminalReporter {
+    progress: ProgressTracker,
+}
+
+impl TerminalReporter {
+    pub fn new(verbose: bool) -> Self {
+        Self { progress: ProgressTracker::new(true, verbose) }
+    }
+}
+
+impl Reporter for TerminalReporter {
+    fn info(&self, message: &str) {
+        self.progress.print_info(message);
+    }
+
+    fn warning(&self, message: &str) {
+        self.progress.print_warning(message);
+    }
+
+    fn error(&self, message: &str) {
+        self.progress.print_error(message);
+    }
+
+    fn success(&self, message: &str) {
+        self.progress.print_success(message);
+    }
+
+    fn verbose(&self, message: &str) {
+        self.progress.print_verbose(message);
+    }
+
+    fn text(&self, text: &str) {
+        self.progress.println(text);
+    }
+
+    fn document(&self, document: &serde_json::Value) {
+        self.progress.println(&pretty(document));
+    }
+
+    fn start(&self, phase: Phase, total: u64) {
+        match phase {
+            Phase::Discovery => self.progress.init_main_progress(total, msg!("progress.scanning")),
+            Phase::Content => self.progress.init_content_progress(total),
+            Phase::Rename => self.progress.init_rename_progress(total),
+        }
+    }
+
+    fn advance(&self, phase: Phase, item: &str) {
+        match phase {
+            Phase::Discovery => self.progress.update_main(item),
+            Phase::Content => self.progress.update_content(item),
+            Phase::Rename => self.progress.update_rename(item),
+        }
+    }
+
+    fn finish(&self, phase: Phase, message: &str) {
+        match phase {
+            Phase::Discovery => self.progress.finish_main(message),
+            Phase::Content => self.progress.finish_content(message),
+            Phase::Rename => self.progress.finish_rename(message),
+        }
+    }
+
+    fn suspend(&self, prompt: &mut dyn FnMut()) {
+        self.progress.suspend(prompt)
+    }
+}
+
+/// Line by line messages on stdout, without progress bars
+pub struct PlainReporter {
+    output: SimpleOutput,
+}
+
+impl PlainReporter {
+    pub fn new(verbose: bool) -> Self {
+        Self { output: SimpleOutput::new(verbose) }
+    }
+}
+
+impl Reporter for PlainReporter {
+    fn info(&self, message: &str) {
+        self.output.print_info(message);
+    }
+
+    fn warning(&self, message: &str) {
+        self.output.print_warning(message);
+    }
+
+    fn error(&self, message: &str) {
+        self.output.print_error(message);
+    }
+
+    fn success(&self, message: &str) {
+        self.output.print_success(message);
+    }
+
+    fn verbose(&self, message: &str) {
+        self.output.print_verbose(message);
+    }
+
+    fn text(&self, text: &str) {
+        println!("{}", text);
+    }
+
+    fn document(&self, document: &serde_json::Value) {
+        println!("{}", pretty(document));
+    }
+}
+
+/// Documents on stdout and messages on stderr, so stdout stays parseable
+pub struct JsonReporter {
+    output: SimpleOutput,
+}
+
+impl JsonReporter {
+    pub fn new(verbose: bool) -> Self {
+        Self { output: SimpleOutput::new(verbose).with_stderr(true) }
+    }
+}
+
+impl Reporter for JsonReporter {
+    fn info(&self, message: &str) {
+        self.output.print_info(message);
+    }
+
+    fn warning(&self, message: &str) {
+        self.output.print_warning(message);
+    }
+
+    fn error(&self, message: &str) {
+        self.output.print_error(message);
+    }
+
+    fn success(&self, message: &str) {
+        self.output.print_success(message);
+    }
+
+    fn verbose(&self, message: &str) {
+        self.output.print_verbose(message);
+    }
+
+    fn text(&self, text: &str) {
+        self.output.println(text);
+    }
+
+    fn document(&self, document: &serde_json::Value) {
+        println!("{}", pretty(document));
+    }
+}
+
+/// Drops everything, for callers that only want the result
+pub struct SilentReporter;
+
+impl Reporter for SilentReporter {
+    fn info(&self, _message: &str) {}
+    fn warning(&self, _message: &str) {}
+    fn error(&self, _message: &str) {}
+    fn success(&self, _message: &str) {}
+    fn verbose(&self, _message: &str) {}
+    fn text(&self, _text: &str) {}
+    fn document(&self, _document: &serde_json::Value) {}
+}
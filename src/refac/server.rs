@@ -0,0 +1,362 @@
+//! `ws refactor --serve SOCKET`: a long-running refactor daemon for editor
+//! plugins, so a preview on every keystroke does not pay for process startup
+//! and discovery.
+//!
+//! Clients send JSON-RPC 2.0 requests, one per line, on the Unix socket and
+//! get one response line each. Every refactor method takes `root`, `pattern`
+//! and `substitute`, plus optional `token_mode`, `skip_literals`,
+//! `names_only`, `content_only` and `exclude` overriding the options the
+//! daemon was started with:
+//!
+//! - `plan`: the renames, content changes and collisions
+//! - `preview`: the changed lines of each file (or only `path`)
+//! - `execute`: run the refactor
+//! - `undo`: revert the last executed refactor
+//! - `shutdown`: stop the daemon
+//!
+//! Discovery results are cached per set of parameters until the next
+//! `execute` or `undo`; pass `"refresh": true` to rediscover after editing.
+
+use anyhow::Result;
+use std::path::Path;
+
+use super::cli::Args;
+use super::handlers::ContentHandler;
+
+/// Serve refactor requests on `socket` until a `shutdown` request. `template`
+/// holds the options for requests that do not set them.
+#[cfg(unix)]
+pub fn serve(socket: &Path, template: Args, handlers: Vec<ContentHandler>) -> Result<()> {
+    unix::serve(socket, template, handlers)
+}
+
+#[cfg(not(unix))]
+pub fn serve(_socket: &Path, _template: Args, _handlers: Vec<ContentHandler>) -> Result<()> {
+    anyhow::bail!("--serve needs Unix domain sockets, which this platform does not have")
+}
+
+#[cfg(unix)]
+mod unix {
+    use anyhow::{Context, Result};
+    use serde::Deserialize;
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+    use std::fs;
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::fs::FileTypeExt;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use super::super::cli::{Args, OutputFormat, ProgressMode};
+    use super::super::handlers::ContentHandler;
+    use super::super::output_schema;
+    use super::super::rename_engine::{FileDiff, RenameEngine};
+    use crate::{ItemType, RenameItem};
+
+    const PARSE_ERROR: i32 = -32700;
+    const METHOD_NOT_FOUND: i32 = -32601;
+    const INVALID_PARAMS: i32 = -32602;
+    /// The refactor itself failed, e.g. on collisions
+    const REFACTOR_FAILED: i32 = -32000;
+
+    /// Parameters shared by the refactor methods, also the discovery cache key
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+    struct RefactorParams {
+        root: PathBuf,
+        pattern: String,
+        substitute: String,
+        token_mode: Option<bool>,
+        skip_literals: Option<bool>,
+        names_only: Option<bool>,
+        content_only: Option<bool>,
+        #[serde(default)]
+        exclude: Vec<String>,
+    }
+
+    type Discovery = (Vec<PathBuf>, Vec<RenameItem>);
+
+    /// What an executed refactor changed, to undo it
+    struct Journal {
+        /// Renames that happened, in execution order
+        renames: Vec<RenameItem>,
+        /// Files whose content was replaced, with their content before
+        contents: Vec<(PathBuf, Vec<u8>)>,
+    }
+
+    struct Server {
+        template: Args,
+        handlers: Vec<ContentHandler>,
+        discoveries: HashMap<RefactorParams, Discovery>,
+        journals: Vec<Journal>,
+    }
+
+    enum Failure {
+        UnknownMethod(String),
+        InvalidParams(String),
+        Refactor(anyhow::Error),
+    }
+
+    impl From<anyhow::Error> for Failure {
+        fn from(error: anyhow::Error) -> Self {
+            Failure::Refactor(error)
+        }
+    }
+
+    pub fn serve(socket: &Path, template: Args, handlers: Vec<ContentHandler>) -> Result<()> {
+        if let Ok(metadata) = fs::symlink_metadata(socket) {
+            if !metadata.file_type().is_socket() {
+                anyhow::bail!("{} exists and is not a socket", socket.display());
+            }
+            if UnixStream::connect(socket).is_ok() {
+                anyhow::bail!("Another refactor server is listening on {}", socket.display());
+            }
+            // Left behind by a server that did not shut down
+            fs::remove_file(socket).with_context(|| format!("Failed to remove stale socket {}", socket.display()))?;
+        }
+        let listener = UnixListener::bind(socket).with_context(|| format!("Failed to listen on {}", socket.display()))?;
+        println!("Serving refactor requests on {}", socket.display());
+
+        let server = Arc::new(Mutex::new(Server { template, handlers, discoveries: HashMap::new(), journals: Vec::new() }));
+        let stopping = Arc::new(AtomicBool::new(false));
+        for stream in listener.incoming() {
+            if stopping.load(Ordering::SeqCst) {
+                break;
+            }
+            let Ok(stream) = stream else { continue };
+            let server = Arc::clone(&server);
+            let stopping = Arc::clone(&stopping);
+            let socket = socket.to_path_buf();
+            std::thread::spawn(move || {
+                if handle_connection(stream, &server) {
+                    stopping.store(true, Ordering::SeqCst);
+                    // Wake up the accept loop so it sees the flag
+                    let _ = UnixStream::connect(&socket);
+                }
+            });
+        }
+
+        let _ = fs::remove_file(socket);
+        Ok(())
+    }
+
+    /// Answer the requests of one client, returning whether it asked for a shutdown
+    fn handle_connection(stream: UnixStream, server: &Mutex<Server>) -> bool {
+        let Ok(mut writer) = stream.try_clone() else { return false };
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else { break };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (response, shutdown) = match serde_json::from_str::<Value>(&line) {
+                Ok(request) => {
+                    let id = request.get("id").cloned().unwrap_or(Value::Null);
+                    let method = request.get("method").and_then(Value::as_str).unwrap_or_default().to_string();
+                    let params = request.get("params").cloned().unwrap_or(Value::Null);
+                    let mut server = server.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                    let response = match server.dispatch(&method, &params) {
+                        Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+                        Err(error) => error_response(id, error),
+                    };
+                    (response, method == "shutdown")
+                }
+                Err(error) => (json!({"jsonrpc": "2.0", "id": null, "error": {"code": PARSE_ERROR, "message": error.to_string()}}), false),
+            };
+            if writeln!(writer, "{}", response).is_err() {
+                break;
+            }
+            if shutdown {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn error_response(id: Value, error: Failure) -> Value {
+        let (code, message) = match error {
+            Failure::UnknownMethod(method) => (METHOD_NOT_FOUND, format!("Unknown method: {}", method)),
+            Failure::InvalidParams(message) => (INVALID_PARAMS, message),
+            Failure::Refactor(error) => (REFACTOR_FAILED, format!("{:#}", error)),
+        };
+        json!({"jsonrpc": "2.0", "id": id, "error": {"code": code, "message": message}})
+    }
+
+    impl Server {
+        fn dispatch(&mut self, method: &str, params: &Value) -> Result<Value, Failure> {
+            match method {
+                "plan" => self.plan(params),
+                "preview" => self.preview(params),
+                "execute" => self.execute(params),
+                "undo" => self.undo(),
+                "shutdown" => Ok(Value::Null),
+                _ => Err(Failure::UnknownMethod(method.to_string())),
+            }
+        }
+
+        fn engine(&self, params: &RefactorParams) -> Result<RenameEngine> {
+            let template = &self.template;
+            let mut exclude_patterns = template.exclude_patterns.clone();
+            exclude_patterns.extend(params.exclude.iter().cloned());
+            let args = Args {
+                root_dir: params.root.clone(),
+                pattern: params.pattern.clone(),
+                substitute: params.substitute.clone(),
+                assume_yes: true,
+                names_only: params.names_only.unwrap_or(template.names_only),
+                content_only: params.content_only.unwrap_or(template.content_only),
+                exclude_patterns,
+                format: OutputFormat::Plain,
+                progress: ProgressMode::Never,
+                token_mode: params.token_mode.unwrap_or(template.token_mode),
+                skip_literals: params.skip_literals.unwrap_or(template.skip_literals),
+                report_html: None,
+                serve: None,
+                ..template.clone()
+            };
+            Ok(RenameEngine::new(args)?.with_handlers(self.handlers.clone()))
+        }
+
+        /// The engine and discovery for `params`, from the cache unless `refresh` is set
+        fn discover(&mut self, params: &Value) -> Result<(RenameEngine, Discovery, bool), Failure> {
+            let key: RefactorParams =
+                serde_json::from_value(params.clone()).map_err(|error| Failure::InvalidParams(format!("Invalid params: {}", error)))?;
+            let engine = self.engine(&key)?;
+            let refresh = params.get("refresh").and_then(Value::as_bool).unwrap_or(false);
+            if let Some(discovery) = self.discoveries.get(&key).filter(|_| !refresh) {
+                return Ok((engine, discovery.clone(), true));
+            }
+            let discovery = engine.discover_items()?;
+            self.discoveries.insert(key, discovery.clone());
+            Ok((engine, discovery, false))
+        }
+
+        fn plan(&mut self, params: &Value) -> Result<Value, Failure> {
+            let (engine, (content_files, rename_items), cached) = self.discover(params)?;
+            let collisions = engine.find_collisions(&rename_items)?;
+            let report = engine.generate_detailed_report(&content_files, &rename_items)?;
+
+            let renames: Vec<Value> = report
+                .file_changes
+                .iter()
+                .filter_map(|change| {
+                    let target = change.rename_target.as_ref()?;
+                    let kind = match change.item_type {
+                        ItemType::File => "file",
+                        ItemType::Directory => "directory",
+                    };
+                    Some(json!({"type": kind, "from": change.path, "to": target}))
+                })
+                .collect();
+            let content: Vec<Value> = report
+                .file_changes
+                .iter()
+                .filter_map(|change| Some(json!({"path": change.path, "occurrences": change.content_changes?})))
+                .collect();
+            let collisions: Vec<Value> = collisions.iter().map(output_schema::collision).collect();
+            let mut document = output_schema::document(
+                "plan",
+                json!({"renames": renames, "content": content, "collisions": collisions, "cached": cached}),
+            );
+            engine.record_plan_settings(&mut document);
+            Ok(document)
+        }
+
+        fn preview(&mut self, params: &Value) -> Result<Value, Failure> {
+            let (engine, (mut content_files, _), cached) = self.discover(params)?;
+            if let Some(path) = params.get("path").and_then(Value::as_str) {
+                content_files.retain(|file_path| file_path == Path::new(path));
+            }
+            let files: Vec<Value> = engine
+                .file_diffs(&content_files)
+                .into_iter()
+                .map(|(path, diff)| match diff {
+                    FileDiff::Lines(lines) => {
+                        let lines: Vec<Value> = lines
+                            .iter()
+                            .map(|change| json!({"line": change.line, "before": change.before, "after": change.after}))
+                            .collect();
+                        json!({"path": path, "lines": lines})
+                    }
+                    FileDiff::Handler(command) => json!({"path": path, "handler": command}),
+                    FileDiff::Archive(changes) => {
+                        let members: Vec<Value> = changes
+                            .iter()
+                            .map(|change| json!({"name": change.name, "new_name": change.new_name, "occurrences": change.occurrences}))
+                            .collect();
+                        json!({"path": path, "members": members})
+                    }
+                    FileDiff::Unreadable => json!({"path": path, "unreadable": true}),
+                })
+                .collect();
+            Ok(json!({"files": files, "cached": cached}))
+        }
+
+        fn execute(&mut self, params: &Value) -> Result<Value, Failure> {
+            // Never run on a stale plan
+            let mut params = params.clone();
+            if let Some(params) = params.as_object_mut() {
+                params.insert("refresh".to_string(), Value::Bool(true));
+            }
+            let (engine, (content_files, rename_items), _) = self.discover(&params)?;
+            let _lock = engine.lock_root()?;
+            engine.check_collisions(&rename_items)?;
+            engine.validate_all_operations(&content_files, &rename_items)?;
+
+            let contents: Vec<(PathBuf, Vec<u8>)> = content_files
+                .iter()
+                .filter_map(|path| Some((path.clone(), fs::read(path).ok()?)))
+                .collect();
+            engine.execute_changes(&content_files, &rename_items)?;
+            self.discoveries.clear();
+
+            // A rename happened if its target exists, wherever later directory renames took it
+            let renames: Vec<RenameItem> = rename_items
+                .iter()
+                .enumerate()
+                .filter(|(index, item)| {
+                    item.original_path != item.new_path && current_path(&item.new_path, &rename_items[index + 1..]).exists()
+                })
+                .map(|(_, item)| item.clone())
+                .collect();
+            let contents: Vec<(PathBuf, Vec<u8>)> = contents
+                .into_iter()
+                .filter(|(path, before)| fs::read(current_path(path, &renames)).is_ok_and(|after| &after != before))
+                .collect();
+            let result = json!({"files_changed": contents.len(), "renamed": renames.len()});
+            self.journals.push(Journal { renames, contents });
+            Ok(result)
+        }
+
+        fn undo(&mut self) -> Result<Value, Failure> {
+            let journal = self.journals.pop().ok_or_else(|| Failure::Refactor(anyhow::anyhow!("Nothing to undo")))?;
+            self.discoveries.clear();
+
+            // Later renames may have moved the parents of earlier ones, so go backwards
+            let mut errors = Vec::new();
+            for item in journal.renames.iter().rev() {
+                if let Err(error) = fs::rename(&item.new_path, &item.original_path) {
+                    errors.push(format!("{} → {}: {}", item.new_path.display(), item.original_path.display(), error));
+                }
+            }
+            for (path, content) in &journal.contents {
+                if let Err(error) = fs::write(path, content) {
+                    errors.push(format!("{}: {}", path.display(), error));
+                }
+            }
+            if !errors.is_empty() {
+                return Err(Failure::Refactor(anyhow::anyhow!("Undo was incomplete: {}", errors.join("; "))));
+            }
+            Ok(json!({"files_restored": journal.contents.len(), "renames_reverted": journal.renames.len()}))
+        }
+    }
+
+    /// Where `path` is after `renames`, which rename files before their directories
+    fn current_path(path: &Path, renames: &[RenameItem]) -> PathBuf {
+        renames.iter().fold(path.to_path_buf(), |path, item| match path.strip_prefix(&item.original_path) {
+            Ok(rest) if rest.as_os_str().is_empty() => item.new_path.clone(),
+            Ok(rest) => item.new_path.join(rest),
+            Err(_) => path,
+        })
+    }
+}
@@ -0,0 +1,107 @@
+//! Performance counters for `--stats`, printed as one JSON object on stderr
+//! when the run ends, so tuning on large trees (`--threads`, `--durability`,
+//! excludes) can be measured instead of guessed.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::binary_detector::DetectorStats;
+
+/// Counters shared by the engine and its file operations; cheap enough to always collect
+#[derive(Debug, Default)]
+pub struct Counters {
+    files_walked: AtomicU64,
+    bytes_scanned: AtomicU64,
+    bytes_rewritten: AtomicU64,
+    /// Time spent replacing content, summed over all workers
+    replace_nanos: AtomicU64,
+    phases: Mutex<Vec<PhaseTime>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PhaseTime {
+    pub phase: &'static str,
+    pub seconds: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StatsReport {
+    pub files_walked: u64,
+    pub bytes_scanned: u64,
+    pub bytes_rewritten: u64,
+    pub detector: DetectorStats,
+    pub phases: Vec<PhaseTime>,
+    pub total_seconds: f64,
+    pub threads: usize,
+    /// Share of the content phase the worker threads spent replacing, from 0
+    /// to 1; none when no content was replaced
+    pub thread_utilization: Option<f64>,
+}
+
+impl Counters {
+    pub fn add_file_walked(&self) {
+        self.files_walked.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn add_bytes_scanned(&self, bytes: u64) {
+        self.bytes_scanned.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub fn add_bytes_rewritten(&self, bytes: u64) {
+        self.bytes_rewritten.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub fn add_replace_time(&self, time: Duration) {
+        self.replace_nanos.fetch_add(time.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_phase(&self, phase: &'static str, time: Duration) {
+        self.phases.lock().unwrap().push(PhaseTime { phase, seconds: time.as_secs_f64() });
+    }
+
+    pub fn report(&self, detector: DetectorStats, threads: usize, total: Duration) -> StatsReport {
+        let phases = self.phases.lock().unwrap().clone();
+        let content_seconds: f64 = phases.iter().filter(|phase| phase.phase == "content").map(|phase| phase.seconds).sum();
+        let replace_seconds = Duration::from_nanos(self.replace_nanos.load(Ordering::Relaxed)).as_secs_f64();
+        let thread_utilization = (content_seconds > 0.0 && threads > 0)
+            .then(|| (replace_seconds / (content_seconds * threads as f64)).min(1.0));
+
+        StatsReport {
+            files_walked: self.files_walked.load(Ordering::Relaxed),
+            bytes_scanned: self.bytes_scanned.load(Ordering::Relaxed),
+            bytes_rewritten: self.bytes_rewritten.load(Ordering::Relaxed),
+            detector,
+            phases,
+            total_seconds: total.as_secs_f64(),
+            threads,
+            thread_utilization,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let counters = Counters::default();
+        counters.add_file_walked();
+        counters.add_bytes_scanned(100);
+        counters.add_bytes_rewritten(40);
+        counters.record_phase("discovery", Duration::from_millis(10));
+        assert_eq!(counters.report(DetectorStats::default(), 4, Duration::from_millis(20)).thread_utilization, None);
+
+        // Four workers busy for half of a one second phase
+        counters.record_phase("content", Duration::from_secs(1));
+        counters.add_replace_time(Duration::from_secs(2));
+        let report = counters.report(DetectorStats { lookups: 3, cache_hits: 2 }, 4, Duration::from_secs(2));
+        assert_eq!(report.files_walked, 1);
+        assert_eq!(report.bytes_scanned, 100);
+        assert_eq!(report.bytes_rewritten, 40);
+        assert_eq!(report.phases.len(), 2);
+        assert_eq!(report.thread_utilization, Some(0.5));
+    }
+}
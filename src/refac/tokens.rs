@@ -0,0 +1,221 @@
+//! Identifier matching for `--token-mode`: the pattern only matches where it
+//! stands as a whole identifier, so renaming `user` leaves `username` and
+//! `get_user` alone. With `--skip-literals`, matches inside string literals
+//! and comments are left alone too, for Rust, Python and JavaScript/TypeScript
+//! files (by extension). Other files get the identifier boundaries only.
+
+use std::ops::Range;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Language {
+    Rust,
+    Python,
+    JavaScript,
+}
+
+impl Language {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "rs" => Some(Language::Rust),
+            "py" | "pyi" => Some(Language::Python),
+            "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" => Some(Language::JavaScript),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TokenMode {
+    /// Skip matches inside string literals and comments
+    pub skip_literals: bool,
+}
+
+impl TokenMode {
+    /// Byte offsets of the matches of `pattern` in `content`, the text of `path`
+    pub fn find(&self, content: &str, pattern: &str, path: &Path) -> Vec<usize> {
+        let literals = match Language::from_path(path) {
+            Some(language) if self.skip_literals => literal_ranges(content, language),
+            _ => Vec::new(),
+        };
+        find_identifiers(content, pattern)
+            .into_iter()
+            .filter(|start| !literals.iter().any(|range| range.contains(start)))
+            .collect()
+    }
+
+    pub fn replace(&self, content: &str, pattern: &str, substitute: &str, path: &Path) -> String {
+        let mut replaced = String::with_capacity(content.len());
+        let mut last = 0;
+        for start in self.find(content, pattern, path) {
+            replaced.push_str(&content[last..start]);
+            replaced.push_str(substitute);
+            last = start + pattern.len();
+        }
+        replaced.push_str(&content[last..]);
+        replaced
+    }
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Offsets where `pattern` occurs without identifier characters right before
+/// or after it (where its own first or last character is one)
+pub fn find_identifiers(text: &str, pattern: &str) -> Vec<usize> {
+    let (Some(first), Some(last)) = (pattern.chars().next(), pattern.chars().next_back()) else {
+        return Vec::new();
+    };
+    text.match_indices(pattern)
+        .map(|(start, _)| start)
+        .filter(|&start| {
+            let before = text[..start].chars().next_back();
+            let after = text[start + pattern.len()..].chars().next();
+            !(is_identifier_char(first) && before.is_some_and(is_identifier_char))
+                && !(is_identifier_char(last) && after.is_some_and(is_identifier_char))
+        })
+        .collect()
+}
+
+/// Byte ranges of the string literals and comments in `text`. Delimiters are
+/// ASCII, so scanning bytes never splits a character.
+fn literal_ranges(text: &str, language: Language) -> Vec<Range<usize>> {
+    let bytes = text.as_bytes();
+    let mut ranges = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let rest = &bytes[i..];
+        let after_identifier = i > 0 && (bytes[i - 1].is_ascii_alphanumeric() || bytes[i - 1] == b'_');
+        let end = match (language, bytes[i]) {
+            (Language::Python, b'#') => line_end(bytes, i),
+            (Language::Rust | Language::JavaScript, b'/') if rest.starts_with(b"//") => line_end(bytes, i),
+            (Language::Rust | Language::JavaScript, b'/') if rest.starts_with(b"/*") => {
+                block_comment_end(bytes, i, language == Language::Rust)
+            }
+            (Language::Python, b'"' | b'\'') if rest.starts_with(b"\"\"\"") || rest.starts_with(b"'''") => {
+                find(bytes, i + 3, &rest[..3]).map_or(bytes.len(), |end| end + 3)
+            }
+            (Language::Rust, b'r') if !after_identifier && raw_string_hashes(rest).is_some() => {
+                let hashes = raw_string_hashes(rest).unwrap_or(0);
+                let mut closing = vec![b'"'];
+                closing.resize(hashes + 1, b'#');
+                find(bytes, i + hashes + 2, &closing).map_or(bytes.len(), |end| end + closing.len())
+            }
+            (Language::Rust, b'\'') => match char_literal_end(bytes, i) {
+                Some(end) => end,
+                // A lifetime
+                None => {
+                    i += 1;
+                    continue;
+                }
+            },
+            (_, b'"') => quoted_end(bytes, i, b'"'),
+            (Language::Python | Language::JavaScript, b'\'') => quoted_end(bytes, i, b'\''),
+            (Language::JavaScript, b'`') => quoted_end(bytes, i, b'`'),
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        ranges.push(i..end);
+        i = end;
+    }
+    ranges
+}
+
+fn find(bytes: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+    bytes.get(from..)?.windows(needle.len()).position(|window| window == needle).map(|pos| from + pos)
+}
+
+fn line_end(bytes: &[u8], from: usize) -> usize {
+    find(bytes, from, b"\n").unwrap_or(bytes.len())
+}
+
+fn quoted_end(bytes: &[u8], start: usize, quote: u8) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b if b == quote => return i + 1,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+fn block_comment_end(bytes: &[u8], start: usize, nested: bool) -> usize {
+    let mut depth = 0;
+    let mut i = start;
+    while i + 1 < bytes.len() {
+        if bytes[i] == b'/' && bytes[i + 1] == b'*' && (nested || depth == 0) {
+            depth += 1;
+            i += 2;
+        } else if bytes[i] == b'*' && bytes[i + 1] == b'/' {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return i;
+            }
+        } else {
+            i += 1;
+        }
+    }
+    bytes.len()
+}
+
+/// The number of `#` of a raw string starting at `r` (`r"…"`, `r#"…"#`)
+fn raw_string_hashes(rest: &[u8]) -> Option<usize> {
+    let hashes = rest[1..].iter().take_while(|&&b| b == b'#').count();
+    (rest.get(1 + hashes) == Some(&b'"')).then_some(hashes)
+}
+
+/// The end of a char literal at `start`, or None for a lifetime
+fn char_literal_end(bytes: &[u8], start: usize) -> Option<usize> {
+    if bytes.get(start + 1) == Some(&b'\\') {
+        return find(bytes, start + 2, b"'").map(|end| end + 1);
+    }
+    // One character, which may take up to four bytes
+    (2..=5).find(|&len| bytes.get(start + len) == Some(&b'\'')).filter(|&len| {
+        std::str::from_utf8(&bytes[start + 1..start + len]).is_ok_and(|c| c.chars().count() == 1)
+    }).map(|len| start + len + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identifier_boundaries() {
+        let text = "let user = get_user(username); user.name";
+        assert_eq!(find_identifiers(text, "user"), vec![4, 31]);
+        assert_eq!(find_identifiers("a::user::b", "::user"), vec![1]);
+        assert_eq!(find_identifiers("nothing here", ""), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_skip_literals() {
+        let mode = TokenMode { skip_literals: true };
+        let rust = "// user\nfn user<'a>(c: char) -> &'a str { let _ = 'u'; r#\"user\"#; \"user\" } /* user /* user */ */ user";
+        assert_eq!(
+            mode.replace(rust, "user", "account", Path::new("lib.rs")),
+            "// user\nfn account<'a>(c: char) -> &'a str { let _ = 'u'; r#\"user\"#; \"user\" } /* user /* user */ */ account"
+        );
+
+        let python = "# user\nuser = 'user' + \"\"\"\nuser\n\"\"\"\nprint(user)";
+        assert_eq!(
+            mode.replace(python, "user", "account", Path::new("app.py")),
+            "# user\naccount = 'user' + \"\"\"\nuser\n\"\"\"\nprint(account)"
+        );
+
+        let js = "const user = `user ${x}`; // user\nexport { user };";
+        assert_eq!(
+            mode.replace(js, "user", "account", Path::new("app.ts")),
+            "const account = `user ${x}`; // user\nexport { account };"
+        );
+
+        // Unknown languages and the default mode only respect boundaries
+        assert_eq!(mode.find("\"user\" users", "user", Path::new("notes.txt")), vec![1]);
+        assert_eq!(TokenMode::default().find("# user", "user", Path::new("app.py")), vec![2]);
+    }
+}
@@ -0,0 +1,92 @@
+//! Worker count for the content phase with `--adaptive-threads`. The first
+//! files are replaced with a few candidate thread counts in turn, and the
+//! count that rewrote the most bytes per second replaces the rest. Network
+//! filesystems tend to reward more threads than there are CPUs, small trees
+//! and spinning disks fewer.
+
+use anyhow::Result;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// How long each candidate thread count is tried at most
+pub const SAMPLE_TIME: Duration = Duration::from_millis(500);
+
+/// Below this many files tuning costs more than it can save
+pub const MIN_FILES_TO_TUNE: usize = 256;
+
+/// Files handed to a candidate at a time
+const SAMPLE_BATCH: usize = 32;
+
+/// Thread counts tried around `base`: half, the same and double
+pub fn candidates(base: usize) -> Vec<usize> {
+    let mut candidates = vec![(base / 2).max(1), base.max(1), base.max(1) * 2];
+    candidates.dedup();
+    candidates
+}
+
+/// Try each candidate on successive batches of `files` for up to `sample`
+/// each, using at most half of the files. `replace(threads, batch)` replaces
+/// a batch with that many threads and returns the bytes it processed.
+/// Returns the fastest thread count and how many files the trials replaced.
+pub fn tune(
+    files: &[PathBuf],
+    base: usize,
+    sample: Duration,
+    mut replace: impl FnMut(usize, &[PathBuf]) -> Result<u64>,
+) -> Result<(usize, usize)> {
+    let budget = files.len() / 2;
+    let mut offset = 0;
+    let mut best: Option<(usize, f64)> = None;
+
+    for threads in candidates(base) {
+        let started = Instant::now();
+        let mut bytes = 0;
+        let first = offset;
+        while offset < budget && started.elapsed() < sample {
+            let end = (offset + SAMPLE_BATCH.max(threads * 4)).min(budget);
+            bytes += replace(threads, &files[offset..end])?;
+            offset = end;
+        }
+        if offset == first {
+            break;
+        }
+
+        let rate = bytes as f64 / started.elapsed().as_secs_f64().max(f64::EPSILON);
+        log::debug!("{} threads: {:.0} bytes/s over {} files", threads, rate, offset - first);
+        if best.is_none_or(|(_, best_rate)| rate > best_rate) {
+            best = Some((threads, rate));
+        }
+    }
+
+    Ok((best.map_or(base, |(threads, _)| threads), offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidates() {
+        assert_eq!(candidates(8), [4, 8, 16]);
+        assert_eq!(candidates(1), [1, 2]);
+        assert_eq!(candidates(0), [1, 2]);
+    }
+
+    #[test]
+    fn test_tune_picks_fastest() -> Result<()> {
+        let files: Vec<PathBuf> = (0..4000).map(|i| PathBuf::from(format!("{}.txt", i))).collect();
+
+        // Up to 8 threads speed things up, more contend with each other
+        let (threads, tried) = tune(&files, 8, Duration::from_millis(20), |threads, batch| {
+            let speedup = if threads > 8 { 2 } else { threads as u32 };
+            std::thread::sleep(Duration::from_micros(400) * batch.len() as u32 / speedup);
+            Ok(batch.len() as u64)
+        })?;
+        assert_eq!(threads, 8);
+        assert!(tried > 0 && tried <= files.len() / 2);
+
+        // Too few files to sample keeps the base count
+        assert_eq!(tune(&files[..1], 8, Duration::from_millis(20), |_, _| Ok(1))?, (8, 0));
+        Ok(())
+    }
+}
@@ -0,0 +1,259 @@
+//! `ws refactor verify PLAN`: checks that a plan saved from `--format json`
+//! (the `summary` document) or from the `plan` method of `--serve` was
+//! applied, after an interrupted run or by automation that should be audited.
+//! The `--time-limit` checkpoint works the same way for the work it lists.
+
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+use super::cli::OutputFormat;
+use super::messages::msg;
+use super::output_schema;
+use super::tokens::TokenMode;
+
+/// What a plan document says should have happened
+#[derive(Debug, Clone, Default)]
+pub struct Plan {
+    pub pattern: String,
+    pub substitute: String,
+    pub token_mode: Option<TokenMode>,
+    /// (from, to, is_directory), paths as they were before the run
+    pub renames: Vec<(PathBuf, PathBuf, bool)>,
+    /// Files whose content was to be replaced, by their paths before the run
+    pub content: Vec<PathBuf>,
+}
+
+impl Plan {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read plan {}", path.display()))?;
+        let document: Value = serde_json::from_str(&text)
+            .with_context(|| format!("Plan {} is not JSON", path.display()))?;
+        Self::from_document(&document).with_context(|| format!("Invalid plan {}", path.display()))
+    }
+
+    /// A `summary`, `plan` or `checkpoint` document; relative paths are taken from its `root`
+    pub fn from_document(document: &Value) -> Result<Self> {
+        let string = |key: &str| document.get(key).and_then(Value::as_str).map(str::to_string);
+        let root = string("root").map(PathBuf::from).unwrap_or_default();
+        let path = |value: &Value| value.as_str().map(|p| root.join(p));
+
+        let mut plan = Plan {
+            pattern: string("pattern").context("No pattern recorded; save the plan with this version of ws")?,
+            substitute: string("substitute").unwrap_or_default(),
+            token_mode: document.get("token_mode").filter(|mode| mode.is_object()).map(|mode| TokenMode {
+                skip_literals: mode["skip_literals"].as_bool().unwrap_or(false),
+            }),
+            ..Plan::default()
+        };
+        match document.get("kind").and_then(Value::as_str) {
+            Some("summary") => {
+                for change in document["file_changes"].as_array().into_iter().flatten() {
+                    let from = path(&change["path"]).context("Change without a path")?;
+                    if let Some(to) = path(&change["rename_target"]) {
+                        plan.renames.push((from.clone(), to, change["item_type"] == "Directory"));
+                    }
+                    if !change["content_changes"].is_null() {
+                        plan.content.push(from);
+                    }
+                }
+            }
+            Some("plan") | Some("checkpoint") => {
+                for rename in document["renames"].as_array().into_iter().flatten() {
+                    let from = path(&rename["from"]).context("Rename without a source")?;
+                    let to = path(&rename["to"]).context("Rename without a target")?;
+                    plan.renames.push((from, to, rename["type"] == "directory"));
+                }
+                for change in document["content"].as_array().into_iter().flatten() {
+                    plan.content.push(path(&change["path"]).context("Content change without a path")?);
+                }
+            }
+            other => anyhow::bail!("Expected a summary, plan or checkpoint document, got {}", other.unwrap_or("no kind")),
+        }
+        Ok(plan)
+    }
+
+    /// Check every rename and content change against the tree as it is now
+    pub fn verify(&self) -> Vec<Check> {
+        let mut checks = Vec::new();
+
+        // Parents first, so children are looked for where their renamed parents are
+        let mut renames: Vec<&(PathBuf, PathBuf, bool)> = self.renames.iter().collect();
+        renames.sort_by_key(|(from, _, is_dir)| (!is_dir, from.components().count()));
+        let mut moved_dirs: Vec<(PathBuf, PathBuf)> = Vec::new();
+        let mut moved_files: Vec<(PathBuf, PathBuf)> = Vec::new();
+        for (from, to, is_dir) in renames {
+            let source = rebase(from, &moved_dirs);
+            let target = rebase(to, &moved_dirs);
+            let problem = if !exists(&target) {
+                Some(format!("target {} is missing", target.display()))
+            } else if exists(&source) && source != target {
+                Some(format!("source {} still exists", source.display()))
+            } else {
+                None
+            };
+            if exists(&target) {
+                let moved = if *is_dir { &mut moved_dirs } else { &mut moved_files };
+                moved.push((source, target.clone()));
+            }
+            checks.push(Check { kind: CheckKind::Rename, path: from.clone(), target, problem });
+        }
+
+        for file in &self.content {
+            let location = moved_files.iter()
+                .find(|(source, _)| *source == rebase(file, &moved_dirs))
+                .map(|(_, target)| target.clone())
+                .unwrap_or_else(|| rebase(file, &moved_dirs));
+            let problem = match std::fs::read(&location) {
+                Err(e) => Some(format!("cannot read {}: {}", location.display(), e)),
+                Ok(bytes) => match self.remaining(&String::from_utf8_lossy(&bytes), &location) {
+                    0 => None,
+                    count => Some(format!("{} occurrence(s) of {} remain", count, self.pattern)),
+                },
+            };
+            checks.push(Check { kind: CheckKind::Content, path: file.clone(), target: location, problem });
+        }
+        checks
+    }
+
+    /// Occurrences of the pattern in `content` that the replacement should
+    /// have removed; those inside the substitute (`old` to `old_v2`) are fine
+    fn remaining(&self, content: &str, path: &Path) -> usize {
+        match &self.token_mode {
+            Some(mode) => mode.find(content, &self.pattern, path).len(),
+            None if !self.substitute.is_empty() && self.substitute.contains(&self.pattern) => {
+                content.split(self.substitute.as_str()).map(|part| part.matches(&self.pattern).count()).sum()
+            }
+            None => content.matches(&self.pattern).count(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CheckKind {
+    Rename,
+    Content,
+}
+
+impl CheckKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CheckKind::Rename => "rename",
+            CheckKind::Content => "content",
+        }
+    }
+}
+
+/// One verified change; `problem` says what is wrong when it was not applied
+#[derive(Debug, Clone, PartialEq)]
+pub struct Check {
+    pub kind: CheckKind,
+    /// The path in the plan
+    pub path: PathBuf,
+    /// Where the change was looked for
+    pub target: PathBuf,
+    pub problem: Option<String>,
+}
+
+impl Check {
+    pub fn passed(&self) -> bool {
+        self.problem.is_none()
+    }
+}
+
+/// Verify the plan at `plan_path` and print the report; whether every check passed
+pub fn run(plan_path: &Path, format: &OutputFormat) -> Result<bool> {
+    let checks = Plan::load(plan_path)?.verify();
+    let failed = checks.iter().filter(|check| !check.passed()).count();
+
+    match format {
+        OutputFormat::Json => {
+            let document = output_schema::document("verification", json!({
+                "result": if failed == 0 { "pass" } else { "fail" },
+                "passed": checks.len() - failed,
+                "failed": failed,
+                "checks": checks.iter().map(|check| json!({
+                    "check": check.kind.name(),
+                    "path": check.path,
+                    "target": check.target,
+                    "passed": check.passed(),
+                    "problem": check.problem,
+                })).collect::<Vec<_>>(),
+            }));
+            println!("{}", serde_json::to_string_pretty(&document)?);
+        }
+        OutputFormat::Plain | OutputFormat::Human => {
+            for check in &checks {
+                match &check.problem {
+                    Some(problem) => println!("FAIL {} {}: {}", check.kind.name(), check.path.display(), problem),
+                    None => println!("ok   {} {}", check.kind.name(), check.path.display()),
+                }
+            }
+            if failed == 0 {
+                println!("{}", msg!("verify.passed", count = checks.len()));
+            } else {
+                println!("{}", msg!("verify.failed", failed = failed, count = checks.len()));
+            }
+        }
+    }
+    Ok(failed == 0)
+}
+
+/// Whether anything, a broken symlink included, is at `path`
+fn exists(path: &Path) -> bool {
+    path.symlink_metadata().is_ok()
+}
+
+/// Where `path` is once `moved` (from, to) directories were renamed, in order
+fn rebase(path: &Path, moved: &[(PathBuf, PathBuf)]) -> PathBuf {
+    moved.iter().fold(path.to_path_buf(), |path, (from, to)| match path.strip_prefix(from) {
+        Ok(rest) if !rest.as_os_str().is_empty() => to.join(rest),
+        _ => path,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_verify_finds_unapplied_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let plan = Plan::from_document(&json!({
+            "kind": "plan",
+            "root": root,
+            "pattern": "old",
+            "substitute": "old_v2",
+            "renames": [
+                {"type": "directory", "from": "old_dir", "to": "old_v2_dir"},
+                {"type": "file", "from": "old_dir/old.txt", "to": "old_dir/old_v2.txt"},
+                {"type": "file", "from": "old_b.txt", "to": "old_v2_b.txt"}
+            ],
+            "content": [{"path": "old_dir/old.txt", "occurrences": 1}, {"path": "notes.txt", "occurrences": 2}]
+        })).unwrap();
+
+        // The directory and the file in it were renamed; old_b.txt and notes.txt were not done
+        fs::create_dir(root.join("old_v2_dir")).unwrap();
+        fs::write(root.join("old_v2_dir/old_v2.txt"), "old_v2\n").unwrap();
+        fs::write(root.join("old_b.txt"), "").unwrap();
+        fs::write(root.join("notes.txt"), "old and old_v2\n").unwrap();
+
+        let checks = plan.verify();
+        let failed: Vec<_> = checks.iter().filter(|check| !check.passed()).map(|check| check.path.strip_prefix(root).unwrap()).collect();
+        assert_eq!(failed, [Path::new("old_b.txt"), Path::new("notes.txt")]);
+        let notes = checks.iter().find(|check| check.path.ends_with("notes.txt")).unwrap();
+        assert_eq!(notes.problem.as_deref(), Some("1 occurrence(s) of old remain"));
+        let renamed = checks.iter().find(|check| check.path.ends_with("old_dir/old.txt")).unwrap();
+        assert_eq!(renamed.target, root.join("old_v2_dir/old_v2.txt"));
+    }
+
+    #[test]
+    fn test_plan_needs_pattern() {
+        assert!(Plan::from_document(&json!({"kind": "summary", "file_changes": []})).is_err());
+        assert!(Plan::from_document(&json!({"kind": "report", "pattern": "old"})).is_err());
+    }
+}
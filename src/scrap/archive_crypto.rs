@@ -0,0 +1,227 @@
+use anyhow::{anyhow, Context, Result};
+use argon2::Argon2;
+use chacha20poly1305::aead::stream::{DecryptorBE32, EncryptorBE32};
+use chacha20poly1305::aead::KeyInit;
+use chacha20poly1305::{Key, XChaCha20Poly1305};
+use rand::RngCore;
+use std::io::{self, Read, Write};
+
+/// Header identifying an encrypted scrap archive
+const MAGIC: &[u8; 8] = b"WSSCRAP1";
+const SALT_LEN: usize = 16;
+/// XChaCha20 nonce less the 5 bytes of STREAM counter and last-chunk flag
+const NONCE_LEN: usize = 19;
+/// Plaintext per chunk; only the last chunk is shorter, possibly empty
+const CHUNK_LEN: usize = 64 * 1024;
+const TAG_LEN: usize = 16;
+
+/// Environment variable consulted before prompting for a passphrase
+pub const PASSPHRASE_ENV: &str = "WS_SCRAP_PASSPHRASE";
+
+const DECRYPT_FAILED: &str = "Failed to decrypt archive (wrong passphrase or corrupted file)";
+
+/// Encrypts everything written to it with a key derived from the passphrase
+/// (Argon2id + XChaCha20-Poly1305 in the STREAM construction), one chunk at
+/// a time. Output layout: magic | salt | nonce | chunks. Call
+/// [`EncryptWriter::finish`] to seal the last chunk; a stream that was not
+/// finished does not decrypt.
+pub struct EncryptWriter<W: Write> {
+    inner: W,
+    encryptor: EncryptorBE32<XChaCha20Poly1305>,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> EncryptWriter<W> {
+    pub fn new(mut inner: W, passphrase: &str) -> Result<Self> {
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut salt);
+        rand::thread_rng().fill_bytes(&mut nonce);
+
+        let encryptor = EncryptorBE32::from_aead(cipher_for(passphrase, &salt)?, nonce.as_slice().into());
+        inner.write_all(MAGIC)?;
+        inner.write_all(&salt)?;
+        inner.write_all(&nonce)?;
+        Ok(Self { inner, encryptor, buffer: Vec::with_capacity(CHUNK_LEN) })
+    }
+
+    /// Encrypt what is left as the last chunk and return the inner writer
+    pub fn finish(mut self) -> Result<W> {
+        let chunk = self.encryptor
+            .encrypt_last(self.buffer.as_slice())
+            .map_err(|_| anyhow!("Failed to encrypt archive"))?;
+        self.inner.write_all(&chunk)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for EncryptWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let taken = data.len().min(CHUNK_LEN - self.buffer.len());
+        self.buffer.extend_from_slice(&data[..taken]);
+        if self.buffer.len() == CHUNK_LEN {
+            let chunk = self.encryptor
+                .encrypt_next(self.buffer.as_slice())
+                .map_err(|_| io::Error::other("Failed to encrypt archive"))?;
+            self.inner.write_all(&chunk)?;
+            self.buffer.clear();
+        }
+        Ok(taken)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Reverse of [`EncryptWriter`]: decrypts an archive as it is read, failing
+/// on a wrong passphrase, tampered data or a truncated file
+pub struct DecryptReader<R: Read> {
+    inner: R,
+    decryptor: Option<DecryptorBE32<XChaCha20Poly1305>>,
+    plaintext: Vec<u8>,
+    position: usize,
+}
+
+impl<R: Read> DecryptReader<R> {
+    /// Read the header from `inner` and decrypt the first chunk, so a wrong
+    /// passphrase shows here rather than halfway through unpacking
+    pub fn new(mut inner: R, passphrase: &str) -> Result<Self> {
+        let mut header = [0u8; MAGIC.len() + SALT_LEN + NONCE_LEN];
+        inner.read_exact(&mut header).context("Not an encrypted scrap archive")?;
+        if !is_encrypted(&header) {
+            anyhow::bail!("Not an encrypted scrap archive");
+        }
+
+        let (salt, nonce) = header[MAGIC.len()..].split_at(SALT_LEN);
+        let decryptor = DecryptorBE32::from_aead(cipher_for(passphrase, salt)?, nonce.into());
+        let mut reader = Self { inner, decryptor: Some(decryptor), plaintext: Vec::new(), position: 0 };
+        reader.next_chunk()?;
+        Ok(reader)
+    }
+
+    fn next_chunk(&mut self) -> io::Result<()> {
+        let Some(decryptor) = self.decryptor.as_mut() else {
+            self.plaintext.clear();
+            self.position = 0;
+            return Ok(());
+        };
+
+        let mut chunk = vec![0u8; CHUNK_LEN + TAG_LEN];
+        let mut filled = 0;
+        while filled < chunk.len() {
+            match self.inner.read(&mut chunk[filled..])? {
+                0 => break,
+                n => filled += n,
+            }
+        }
+        chunk.truncate(filled);
+
+        let decrypted = if filled == CHUNK_LEN + TAG_LEN {
+            decryptor.decrypt_next(chunk.as_slice())
+        } else {
+            self.decryptor.take().expect("checked above").decrypt_last(chunk.as_slice())
+        };
+        self.plaintext = decrypted.map_err(|_| io::Error::new(io::ErrorKind::InvalidData, DECRYPT_FAILED))?;
+        self.position = 0;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for DecryptReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.plaintext.len() {
+            if self.decryptor.is_none() {
+                return Ok(0);
+            }
+            self.next_chunk()?;
+        }
+        let n = buf.len().min(self.plaintext.len() - self.position);
+        buf[..n].copy_from_slice(&self.plaintext[self.position..self.position + n]);
+        self.position += n;
+        Ok(n)
+    }
+}
+
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// Read the passphrase from `WS_SCRAP_PASSPHRASE`, or prompt for it.
+/// `confirm` asks twice, which is what you want when encrypting.
+pub fn read_passphrase(confirm: bool) -> Result<String> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        if !passphrase.is_empty() {
+            return Ok(passphrase);
+        }
+    }
+
+    if !atty::is(atty::Stream::Stdin) {
+        anyhow::bail!("No terminal to ask for the archive passphrase; set {}", PASSPHRASE_ENV);
+    }
+    let mut prompt = dialoguer::Password::new().with_prompt("Archive passphrase");
+    if confirm {
+        prompt = prompt.with_confirmation("Confirm passphrase", "Passphrases do not match");
+    }
+    prompt.interact().context("Failed to read passphrase")
+}
+
+fn cipher_for(passphrase: &str, salt: &[u8]) -> Result<XChaCha20Poly1305> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow!("Failed to derive archive key: {}", e))?;
+    Ok(XChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encrypt(plaintext: &[u8], passphrase: &str) -> Vec<u8> {
+        let mut writer = EncryptWriter::new(Vec::new(), passphrase).unwrap();
+        writer.write_all(plaintext).unwrap();
+        writer.finish().unwrap()
+    }
+
+    fn decrypt(data: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+        let mut plaintext = Vec::new();
+        DecryptReader::new(data, passphrase)?.read_to_end(&mut plaintext)?;
+        Ok(plaintext)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let encrypted = encrypt(b"secret config", "hunter2");
+        assert!(is_encrypted(&encrypted));
+        assert_eq!(decrypt(&encrypted, "hunter2").unwrap(), b"secret config");
+    }
+
+    #[test]
+    fn test_round_trip_across_chunks() {
+        for len in [0, CHUNK_LEN - 1, CHUNK_LEN, 2 * CHUNK_LEN + 5] {
+            let plaintext: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+            assert_eq!(decrypt(&encrypt(&plaintext, "hunter2"), "hunter2").unwrap(), plaintext, "{} bytes", len);
+        }
+    }
+
+    #[test]
+    fn test_wrong_passphrase_fails() {
+        let encrypted = encrypt(b"secret config", "hunter2");
+        assert!(decrypt(&encrypted, "wrong").is_err());
+    }
+
+    #[test]
+    fn test_truncated_archive_fails() {
+        let encrypted = encrypt(&vec![7u8; 2 * CHUNK_LEN], "hunter2");
+        let cut = MAGIC.len() + SALT_LEN + NONCE_LEN + CHUNK_LEN + TAG_LEN;
+        assert!(decrypt(&encrypted[..cut], "hunter2").is_err());
+    }
+
+    #[test]
+    fn test_plain_data_is_not_encrypted() {
+        assert!(!is_encrypted(b"\x1f\x8b plain gzip"));
+        assert!(decrypt(b"\x1f\x8b plain gzip", "hunter2").is_err());
+    }
+}
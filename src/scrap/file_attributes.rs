@@ -0,0 +1,236 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Permissions and extended attributes of a scrapped item, recorded so
+/// `unscrap` can put them back even when the data had to be copied. Symlinks
+/// are moved and copied as links, so their target needs no record.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FileAttributes {
+    /// Unix permission bits
+    #[serde(default)]
+    pub mode: Option<u32>,
+    /// Extended attribute values, hex encoded
+    #[serde(default)]
+    pub xattrs: BTreeMap<String, String>,
+}
+
+impl FileAttributes {
+    /// Read the attributes of `path` without following a symlink
+    pub fn capture(path: &Path) -> Result<Self> {
+        let metadata = fs::symlink_metadata(path)
+            .with_context(|| format!("Failed to read metadata of {}", path.display()))?;
+
+        Ok(Self {
+            mode: unix_mode(&metadata),
+            xattrs: read_xattrs(path),
+        })
+    }
+
+    /// Restore the recorded mode and extended attributes onto `path`.
+    /// Extended attributes are best effort since not every filesystem supports them.
+    pub fn apply(&self, path: &Path) -> Result<()> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            // Symlink permissions are meaningless and chmod would follow the link
+            let is_symlink = fs::symlink_metadata(path)?.file_type().is_symlink();
+            if let (Some(mode), false) = (self.mode, is_symlink) {
+                fs::set_permissions(path, fs::Permissions::from_mode(mode))
+                    .with_context(|| format!("Failed to restore permissions of {}", path.display()))?;
+            }
+
+            for (name, value) in &self.xattrs {
+                let Some(value) = decode_hex(value) else { continue };
+                if let Err(e) = xattr::set(path, name, &value) {
+                    log::warn!("Failed to restore extended attribute {} on {}: {}", name, path.display(), e);
+                }
+            }
+        }
+
+        #[cfg(not(unix))]
+        let _ = path;
+
+        Ok(())
+    }
+}
+
+/// Move `source` to `dest`, copying (with attributes) and deleting the
+/// original when they are on different filesystems
+pub fn move_path(source: &Path, dest: &Path) -> Result<()> {
+    match fs::rename(source, dest) {
+        Ok(()) => Ok(()),
+        Err(e) if is_cross_device(&e) => {
+            copy_path(source, dest)?;
+            if fs::symlink_metadata(source)?.is_dir() {
+                fs::remove_dir_all(source)?;
+            } else {
+                fs::remove_file(source)?;
+            }
+            Ok(())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Recursively copy `source` to `dest`, recreating symlinks rather than
+/// following them and preserving permissions, extended attributes and file
+/// modification times
+pub fn copy_path(source: &Path, dest: &Path) -> Result<()> {
+    let metadata = fs::symlink_metadata(source)
+        .with_context(|| format!("Failed to read metadata of {}", source.display()))?;
+    let attributes = FileAttributes::capture(source)?;
+
+    if metadata.file_type().is_symlink() {
+        create_symlink(&fs::read_link(source)?, dest, source)
+            .with_context(|| format!("Failed to recreate symlink {}", dest.display()))?;
+    } else if metadata.is_dir() {
+        fs::create_dir_all(dest)?;
+        for entry in fs::read_dir(source)? {
+            let entry = entry?;
+            copy_path(&entry.path(), &dest.join(entry.file_name()))?;
+        }
+    } else {
+        fs::copy(source, dest)
+            .with_context(|| format!("Failed to copy {} to {}", source.display(), dest.display()))?;
+        if let Ok(modified) = metadata.modified() {
+            set_modified(dest, modified)
+                .with_context(|| format!("Failed to set the modification time of {}", dest.display()))?;
+        }
+    }
+
+    // Applied last so a read-only directory can still be filled first
+    attributes.apply(dest)
+}
+
+/// Set the modification time of `path` through a handle without write
+/// access to its data, which a read-only copy would refuse
+fn set_modified(path: &Path, modified: std::time::SystemTime) -> std::io::Result<()> {
+    let mut options = fs::File::options();
+    options.read(true);
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::OpenOptionsExt;
+        // FILE_WRITE_ATTRIBUTES, granted even on read-only files
+        options.access_mode(0x100);
+    }
+    options.open(path)?.set_modified(modified)
+}
+
+fn is_cross_device(e: &std::io::Error) -> bool {
+    // EXDEV on Unix, ERROR_NOT_SAME_DEVICE on Windows
+    const CROSS_DEVICE: i32 = if cfg!(windows) { 17 } else { 18 };
+    e.raw_os_error() == Some(CROSS_DEVICE)
+}
+
+#[cfg(unix)]
+fn create_symlink(target: &Path, link: &Path, _original: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn create_symlink(target: &Path, link: &Path, original: &Path) -> std::io::Result<()> {
+    if fs::metadata(original).map(|m| m.is_dir()).unwrap_or(false) {
+        std::os::windows::fs::symlink_dir(target, link)
+    } else {
+        std::os::windows::fs::symlink_file(target, link)
+    }
+}
+
+#[cfg(unix)]
+fn unix_mode(metadata: &fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(metadata.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+fn unix_mode(_metadata: &fs::Metadata) -> Option<u32> {
+    None
+}
+
+#[cfg(unix)]
+fn read_xattrs(path: &Path) -> BTreeMap<String, String> {
+    let mut xattrs = BTreeMap::new();
+    let Ok(names) = xattr::list(path) else { return xattrs };
+    for name in names {
+        if let Ok(Some(value)) = xattr::get(path, &name) {
+            xattrs.insert(name.to_string_lossy().to_string(), encode_hex(&value));
+        }
+    }
+    xattrs
+}
+
+#[cfg(not(unix))]
+fn read_xattrs(_path: &Path) -> BTreeMap<String, String> {
+    BTreeMap::new()
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_round_trip() {
+        let bytes = vec![0u8, 1, 127, 255];
+        assert_eq!(decode_hex(&encode_hex(&bytes)), Some(bytes));
+        assert_eq!(decode_hex("abc"), None);
+        assert_eq!(decode_hex("zz"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_copy_path_preserves_mode_and_symlinks() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        fs::create_dir(&source).unwrap();
+        fs::write(source.join("run.sh"), "#!/bin/sh\n").unwrap();
+        fs::set_permissions(source.join("run.sh"), fs::Permissions::from_mode(0o755)).unwrap();
+        std::os::unix::fs::symlink("run.sh", source.join("link")).unwrap();
+
+        let dest = temp_dir.path().join("dest");
+        copy_path(&source, &dest).unwrap();
+
+        let mode = fs::metadata(dest.join("run.sh")).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+        assert_eq!(fs::read_link(dest.join("link")).unwrap(), Path::new("run.sh"));
+    }
+
+    #[test]
+    fn test_copy_path_keeps_times_of_read_only_files() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let source = temp_dir.path().join("readonly.txt");
+        fs::write(&source, "frozen").unwrap();
+        let modified = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_600_000_000);
+        fs::File::options().write(true).open(&source).unwrap().set_modified(modified).unwrap();
+        let mut permissions = fs::metadata(&source).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&source, permissions).unwrap();
+
+        let dest = temp_dir.path().join("copy.txt");
+        copy_path(&source, &dest).unwrap();
+
+        let copied = fs::metadata(&dest).unwrap();
+        assert!(copied.permissions().readonly());
+        assert_eq!(copied.modified().unwrap(), modified);
+        assert_eq!(fs::read_to_string(&dest).unwrap(), "frozen");
+    }
+}
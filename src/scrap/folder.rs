@@ -0,0 +1,335 @@
+use anyhow::Result;
+use chrono::Utc;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use super::scrap_common::{absolute_path, ARCHIVES_DIR, HistoryItem, HistoryOperation, MetadataLock, ScrapEntry, ScrapMetadata};
+use super::{ItemFilter, RestoreOptions};
+
+/// Handle on a `.scrap` folder, for tools that embed scrap behaviour instead
+/// of shelling out to `ws scrap`. Each operation takes the metadata lock it
+/// needs, records history like the CLI does, and returns what it changed.
+#[derive(Debug, Clone)]
+pub struct ScrapFolder {
+    dir: PathBuf,
+    compress_over: Option<u64>,
+    require_archived: bool,
+}
+
+impl ScrapFolder {
+    /// The scrap folder of `working_dir`, i.e. `working_dir/.scrap`
+    pub fn in_directory(working_dir: &Path) -> Self {
+        Self { dir: working_dir.join(".scrap"), compress_over: None, require_archived: false }
+    }
+
+    /// Store items larger than `bytes` compressed, as `ws scrap` does with
+    /// the `compress_over_mb` setting
+    pub fn with_compress_over(mut self, bytes: u64) -> Self {
+        self.compress_over = Some(bytes);
+        self
+    }
+
+    /// Make `clean` and `purge` keep items that were never written to an
+    /// archive, as `ws scrap purge --require-archived` does
+    pub fn with_require_archived(mut self) -> Self {
+        self.require_archived = true;
+        self
+    }
+
+    /// The scrap folder `ws scrap` would use here: the one `SCRAP_DIR`
+    /// names, or that of the current directory
+    pub fn current() -> Result<Self> {
+        Ok(Self { dir: super::get_scrap_directory()?, compress_over: None, require_archived: false })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn exists(&self) -> bool {
+        self.dir.exists()
+    }
+
+    /// Create the folder (and its ignore entry) if it does not exist yet
+    pub fn ensure(&self) -> Result<()> {
+        if !self.dir.exists() {
+            super::create_scrap_directory(&self.dir)?;
+        }
+        Ok(())
+    }
+
+    pub fn metadata(&self) -> Result<ScrapMetadata> {
+        ScrapMetadata::load(&self.dir)
+    }
+
+    /// All entries, oldest first
+    pub fn entries(&self) -> Result<Vec<ScrapEntry>> {
+        let mut entries: Vec<_> = self.metadata()?.entries.into_values().collect();
+        entries.sort_by_key(|e| e.scrapped_at);
+        Ok(entries)
+    }
+
+    /// Items in the folder that pass `filter`, as (scrapped name, path) sorted by name
+    pub fn items(&self, filter: &ItemFilter) -> Result<Vec<(String, PathBuf)>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+        let metadata = self.metadata().unwrap_or_else(|_| ScrapMetadata::new());
+        super::select_items(&self.dir, &metadata, filter)
+    }
+
+    /// Names of the items passing `filter` that were never written to an
+    /// archive; items missing from the metadata count as never archived
+    pub fn unarchived(&self, filter: &ItemFilter) -> Result<Vec<String>> {
+        let metadata = self.metadata().unwrap_or_else(|_| ScrapMetadata::new());
+        Ok(self.items(filter)?
+            .into_iter()
+            .map(|(name, _)| name)
+            .filter(|name| !metadata.get_entry(name).is_some_and(ScrapEntry::is_archived))
+            .collect())
+    }
+
+    /// Move `path` into the folder and return its new entry
+    pub fn scrap(&self, path: &Path) -> Result<ScrapEntry> {
+        if !path.exists() && !path.is_symlink() {
+            anyhow::bail!("Path does not exist: {}", path.display());
+        }
+
+        self.ensure()?;
+        let _lock = MetadataLock::acquire(&self.dir)?;
+        let mut metadata = self.metadata()?;
+
+        let (scrapped_name, _) = super::move_into_scrap(&mut metadata, &self.dir, path, self.compress_over)?;
+        metadata.save(&self.dir)?;
+        super::record_history(&self.dir, HistoryOperation::Scrap, vec![HistoryItem {
+            scrapped_name: scrapped_name.clone(),
+            path: path.to_path_buf(),
+        }]);
+
+        metadata.get_entry(&scrapped_name)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Missing metadata entry for {}", scrapped_name))
+    }
+
+    /// Restore `name` to `dest` (default: where it was scrapped from) and
+    /// return where it ended up. Prints the same line as `ws unscrap`.
+    pub fn restore(&self, name: &str, dest: Option<&Path>, options: RestoreOptions) -> Result<PathBuf> {
+        let _lock = MetadataLock::acquire(&self.dir)?;
+        let mut metadata = self.metadata()?;
+        super::restore_item(&mut metadata, &self.dir, name, dest.map(Path::to_path_buf), options)
+    }
+
+    /// Remove items scrapped more than `days` days ago; returns their entries.
+    /// With `with_require_archived`, items never archived are kept.
+    pub fn clean(&self, days: u32) -> Result<Vec<ScrapEntry>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let _lock = MetadataLock::acquire(&self.dir)?;
+        let mut metadata = self.metadata()?;
+        let cutoff_date = Utc::now() - chrono::Duration::days(days as i64);
+
+        let mut names: Vec<_> = metadata.entries.iter()
+            .filter(|(_, entry)| entry.scrapped_at < cutoff_date)
+            .filter(|(_, entry)| !self.require_archived || entry.is_archived())
+            .map(|(name, _)| name.clone())
+            .collect();
+        names.sort();
+
+        let mut removed = Vec::new();
+        for name in names {
+            remove_item(&self.dir.join(&name))?;
+            if let Some(entry) = metadata.remove_entry(&name) {
+                removed.push(entry);
+            }
+        }
+
+        if !removed.is_empty() {
+            metadata.save(&self.dir)?;
+            super::record_history(&self.dir, HistoryOperation::Clean, removed.iter().map(|entry| HistoryItem {
+                scrapped_name: entry.scrapped_name.clone(),
+                path: entry.original_path.clone(),
+            }).collect());
+        }
+
+        Ok(removed)
+    }
+
+    /// Move items scrapped more than `days` days ago into monthly archives
+    /// under `.scrap/archives` (`2024-05.tar.zst` for items scrapped in May
+    /// 2024) and return their entries. Items brought back from a tier with
+    /// `scrap restore-archive` are not tiered again.
+    pub fn tier(&self, days: u32) -> Result<Vec<ScrapEntry>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let _lock = MetadataLock::acquire(&self.dir)?;
+        let mut metadata = self.metadata()?;
+        let cutoff_date = Utc::now() - chrono::Duration::days(days as i64);
+        let tiers_dir = absolute_path(&self.dir.join(ARCHIVES_DIR));
+
+        let mut tiers: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (name, entry) in &metadata.entries {
+            let path = self.dir.join(name);
+            if entry.scrapped_at < cutoff_date
+                && (path.exists() || path.is_symlink())
+                && !entry.archived_in.iter().any(|archive| archive.starts_with(&tiers_dir))
+            {
+                tiers.entry(entry.scrapped_at.format("%Y-%m").to_string()).or_default().push(name.clone());
+            }
+        }
+
+        let mut tiered = Vec::new();
+        for (tier, mut names) in tiers {
+            names.sort();
+            let archive = tiers_dir.join(format!("{}.tar.zst", tier));
+            for name in &names {
+                if let Some(entry) = metadata.entries.get_mut(name) {
+                    entry.archived_in.push(archive.clone());
+                }
+            }
+            let items: Vec<_> = names.into_iter().map(|name| {
+                let path = self.dir.join(&name);
+                (name, path)
+            }).collect();
+            super::append_tier(&archive, &items, &metadata)?;
+
+            for (name, path) in &items {
+                remove_item(path)?;
+                if let Some(entry) = metadata.remove_entry(name) {
+                    tiered.push(entry);
+                }
+            }
+            // Saved per tier, so a failure later on leaves no entry without its item
+            metadata.save(&self.dir)?;
+        }
+
+        if !tiered.is_empty() {
+            super::record_history(&self.dir, HistoryOperation::Tier, tiered.iter().map(|entry| HistoryItem {
+                scrapped_name: entry.scrapped_name.clone(),
+                path: entry.original_path.clone(),
+            }).collect());
+        }
+
+        Ok(tiered)
+    }
+
+    /// Delete every item passing `filter` (everything when it is empty) and
+    /// return their names. A full purge also works when the metadata is
+    /// unreadable. With `with_require_archived`, items never archived are kept.
+    pub fn purge(&self, filter: &ItemFilter) -> Result<Vec<String>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let _lock = MetadataLock::acquire(&self.dir)?;
+
+        // A filtered purge keeps the remaining entries, so it needs the metadata intact
+        let mut metadata = if filter.is_empty() && !self.require_archived {
+            self.metadata().unwrap_or_else(|_| ScrapMetadata::new())
+        } else {
+            self.metadata()?
+        };
+
+        // Remove the selected files and subdirectories, never the metadata and history files
+        let mut items = super::select_items(&self.dir, &metadata, filter)?;
+        let selected = items.len();
+        if self.require_archived {
+            items.retain(|(name, _)| metadata.get_entry(name).is_some_and(ScrapEntry::is_archived));
+        }
+        let mut history_items = Vec::new();
+        for (name, path) in &items {
+            history_items.push(HistoryItem {
+                path: metadata.get_entry(name)
+                    .map(|e| e.original_path.clone())
+                    .unwrap_or_else(|| PathBuf::from(name)),
+                scrapped_name: name.clone(),
+            });
+            remove_item(path)?;
+            metadata.remove_entry(name);
+        }
+
+        if filter.is_empty() && items.len() == selected {
+            // Clear metadata
+            metadata = ScrapMetadata::new();
+        }
+        metadata.save(&self.dir)?;
+        super::record_history(&self.dir, HistoryOperation::Purge, history_items);
+
+        Ok(items.into_iter().map(|(name, _)| name).collect())
+    }
+
+    /// Entries whose scrapped name or original path contains `pattern`
+    pub fn find(&self, pattern: &str) -> Result<Vec<ScrapEntry>> {
+        Ok(self.entries()?
+            .into_iter()
+            .filter(|entry| {
+                entry.scrapped_name.contains(pattern)
+                    || entry.original_path.to_string_lossy().contains(pattern)
+            })
+            .collect())
+    }
+
+    /// Write a tar.gz of the folder (or only the items passing `filter`, with
+    /// a metadata file covering them) to `writer`, hand it to `finish` once
+    /// complete and record that the items are in `archive`. With `remove`,
+    /// exactly those items are then deleted. All of it happens under one
+    /// metadata lock, so items scrapped meanwhile are neither archived nor
+    /// removed. Returns the names of the archived items.
+    pub fn archive<W: Write>(
+        &self,
+        writer: W,
+        finish: impl FnOnce(W) -> Result<()>,
+        filter: &ItemFilter,
+        archive: &Path,
+        remove: bool,
+    ) -> Result<Vec<String>> {
+        let _lock = MetadataLock::acquire(&self.dir)?;
+        let mut metadata = self.metadata()?;
+        let items = super::select_items(&self.dir, &metadata, filter)?;
+        let selection = (!filter.is_empty()).then_some((items.as_slice(), &metadata));
+        finish(super::write_archive(writer, &self.dir, selection)?)?;
+
+        // Lets purge and clean tell these items apart from ones that exist nowhere else
+        let archive = absolute_path(archive);
+        for (name, _) in &items {
+            if let Some(entry) = metadata.entries.get_mut(name) {
+                if !entry.archived_in.contains(&archive) {
+                    entry.archived_in.push(archive.clone());
+                }
+            }
+        }
+
+        let mut removed = Vec::new();
+        if remove {
+            for (name, path) in &items {
+                remove_item(path)?;
+                removed.push(HistoryItem {
+                    path: metadata.remove_entry(name)
+                        .map(|entry| entry.original_path)
+                        .unwrap_or_else(|| PathBuf::from(name)),
+                    scrapped_name: name.clone(),
+                });
+            }
+        }
+        metadata.save(&self.dir)?;
+        if !removed.is_empty() {
+            super::record_history(&self.dir, HistoryOperation::Purge, removed);
+        }
+
+        Ok(items.into_iter().map(|(name, _)| name).collect())
+    }
+}
+
+fn remove_item(path: &Path) -> Result<()> {
+    if path.is_dir() && !path.is_symlink() {
+        fs::remove_dir_all(path)?;
+    } else if path.exists() || path.is_symlink() {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
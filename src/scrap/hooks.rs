@@ -0,0 +1,76 @@
+//! Commands run after scrap operations, configured in the `[scrap]` section
+//! of `ws.toml`:
+//!
+//! ```toml
+//! [scrap]
+//! on_scrap = "logger -t ws-scrap"
+//! on_clean = "notify-send 'Scrap clean' \"$WS_SCRAP_COUNT item(s) removed\""
+//! on_purge = "curl -s -X POST --data-binary @- https://audit.example.com/scrap"
+//! ```
+//!
+//! The command runs through the shell in the directory holding `.scrap`,
+//! with the history record (operation, timestamp and items) as JSON on
+//! stdin and the operation, scrap folder and item count in `WS_SCRAP_EVENT`,
+//! `WS_SCRAP_DIR` and `WS_SCRAP_COUNT`. A failing hook is reported but never
+//! fails the operation itself.
+
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use super::HistoryRecord;
+
+/// Environment variable holding the operation (`scrap`, `clean` or `purge`)
+pub const EVENT_ENV: &str = "WS_SCRAP_EVENT";
+/// Environment variable holding the path of the scrap folder
+pub const DIR_ENV: &str = "WS_SCRAP_DIR";
+/// Environment variable holding the number of affected items
+pub const COUNT_ENV: &str = "WS_SCRAP_COUNT";
+
+/// Run `command` for `record`, which has already been written to the history
+pub fn run_hook(command: &str, scrap_dir: &Path, record: &HistoryRecord) -> Result<()> {
+    let payload = serde_json::to_vec(record)?;
+    let workdir = scrap_dir.parent().unwrap_or(scrap_dir);
+
+    let mut child = shell(command)
+        .current_dir(workdir)
+        .env(EVENT_ENV, record.operation.to_string())
+        .env(DIR_ENV, scrap_dir)
+        .env(COUNT_ENV, record.items.len().to_string())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run scrap hook: {}", command))?;
+
+    // Hooks that ignore stdin may exit before reading it; only the exit status counts
+    let mut stdin = child.stdin.take().context("Hook stdin is not piped")?;
+    let writer = std::thread::spawn(move || stdin.write_all(&payload));
+    let output = child.wait_with_output().with_context(|| format!("Scrap hook failed: {}", command))?;
+    let _ = writer.join();
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Scrap hook `{}` exited with {}: {}",
+            command,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(command);
+    cmd
+}
+
+#[cfg(not(unix))]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.arg("/C").arg(command);
+    cmd
+}
@@ -1,44 +1,222 @@
+pub mod archive_crypto;
+pub mod file_attributes;
+pub mod folder;
+pub mod hooks;
 pub mod scrap_common;
+pub mod scrap_config;
+pub mod schedule;
 
-pub use scrap_common::{ScrapMetadata, ScrapEntry};
+pub use file_attributes::FileAttributes;
+pub use folder::ScrapFolder;
+pub use schedule::{CleanSchedule, ScheduleFrequency};
+pub use scrap_config::{GitignoreMode, ScrapConfig};
+pub use scrap_common::{ScrapMetadata, ScrapEntry, ScrapManifest, Compression, HistoryOperation, HistoryItem, HistoryRecord};
+
+use file_attributes::{copy_path, move_path};
+use scrap_common::{MetadataLock, ARCHIVES_DIR, SCRAP_DIR_ENV, global_scrap_dir, COMPRESSION_LEVEL, MANIFEST_FILE, METADATA_FILE, METADATA_LOCK_FILE, absolute_path, compress_item, decompress_item, is_reserved_name, parse_age, parse_size, path_size, load_protected_patterns, protection_reason, load_history, hash_path, hash_stored, is_hard_linked, is_regular_file, is_same_file, link_duplicate};
 
 use anyhow::{Context, Result};
 use chrono::Utc;
 use log;
+use std::collections::HashSet;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Component, Path, PathBuf};
+
+/// What `unscrap` does when the restore destination already exists
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConflictPolicy {
+    /// Refuse to restore
+    Fail,
+    /// Replace the existing file or directory
+    Overwrite,
+    /// Restore next to it as `name.restored-N`
+    Rename,
+    /// Move the existing item into .scrap first, then restore
+    Backup,
+}
+
+impl std::str::FromStr for ConflictPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "fail" => Ok(ConflictPolicy::Fail),
+            "overwrite" => Ok(ConflictPolicy::Overwrite),
+            "rename" => Ok(ConflictPolicy::Rename),
+            "backup" => Ok(ConflictPolicy::Backup),
+            _ => anyhow::bail!("Unknown conflict policy: {} (expected fail, overwrite, rename or backup)", s),
+        }
+    }
+}
+
+impl std::fmt::Display for ConflictPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ConflictPolicy::Fail => "fail",
+            ConflictPolicy::Overwrite => "overwrite",
+            ConflictPolicy::Rename => "rename",
+            ConflictPolicy::Backup => "backup",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Options shared by every unscrap restore path
+#[derive(Debug, Clone, Copy)]
+pub struct RestoreOptions {
+    pub on_conflict: ConflictPolicy,
+    /// Merge a scrapped directory into an existing directory file-by-file
+    pub merge: bool,
+    pub dry_run: bool,
+    /// Compare items with the checksum recorded when they were scrapped
+    pub verify: bool,
+}
+
+impl Default for RestoreOptions {
+    fn default() -> Self {
+        Self {
+            on_conflict: ConflictPolicy::Fail,
+            merge: false,
+            dry_run: false,
+            verify: true,
+        }
+    }
+}
+
+/// Whether an item was a file or a directory when it was scrapped
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ItemKind {
+    File,
+    Directory,
+}
+
+/// Criteria narrowing `list`, `purge` and `archive` to part of the scrap
+/// folder. An item must satisfy every criterion that is set.
+#[derive(Debug, Default)]
+pub struct ItemFilter {
+    pub older_than: Option<chrono::Duration>,
+    /// Size in bytes
+    pub larger_than: Option<u64>,
+    /// Matched against the scrapped name and the original path
+    pub matching: Option<regex::Regex>,
+    pub kind: Option<ItemKind>,
+}
+
+impl ItemFilter {
+    /// Read `--older-than AGE`, `--larger-than SIZE`, `--matching PATTERN`,
+    /// `--files-only` and `--dirs-only`
+    pub fn from_args(args: &[String]) -> Result<Self> {
+        let value_of = |flag: &str| {
+            args.iter()
+                .position(|a| a == flag)
+                .map(|i| args.get(i + 1).with_context(|| format!("{} requires a value", flag)))
+                .transpose()
+        };
+
+        let kind = match (args.iter().any(|a| a == "--files-only"), args.iter().any(|a| a == "--dirs-only")) {
+            (true, true) => anyhow::bail!("--files-only and --dirs-only cannot be combined"),
+            (true, false) => Some(ItemKind::File),
+            (false, true) => Some(ItemKind::Directory),
+            (false, false) => None,
+        };
+
+        Ok(Self {
+            older_than: value_of("--older-than")?.map(|v| parse_age(v)).transpose()?,
+            larger_than: value_of("--larger-than")?.map(|v| parse_size(v)).transpose()?,
+            matching: value_of("--matching")?
+                .map(|p| regex::Regex::new(p).with_context(|| format!("Invalid pattern: {}", p)))
+                .transpose()?,
+            kind,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.older_than.is_none() && self.larger_than.is_none() && self.matching.is_none() && self.kind.is_none()
+    }
+
+    fn matches(&self, name: &str, path: &Path, entry: Option<&ScrapEntry>) -> bool {
+        if let Some(kind) = self.kind {
+            // A compressed directory is stored as a single file
+            let is_dir = match entry.and_then(|e| e.compression) {
+                Some(compression) => compression == Compression::TarZstd,
+                None => fs::symlink_metadata(path).is_ok_and(|m| m.is_dir()),
+            };
+            if is_dir != (kind == ItemKind::Directory) {
+                return false;
+            }
+        }
+
+        if let Some(age) = self.older_than {
+            // Items missing from the metadata fall back to their modification time
+            let scrapped_at = match entry {
+                Some(entry) => Some(entry.scrapped_at),
+                None => fs::symlink_metadata(path)
+                    .and_then(|m| m.modified())
+                    .ok()
+                    .map(chrono::DateTime::<Utc>::from),
+            };
+            let cutoff = Utc::now() - age;
+            match scrapped_at {
+                Some(scrapped_at) if scrapped_at < cutoff => {}
+                _ => return false,
+            }
+        }
+
+        if let Some(min_size) = self.larger_than {
+            if path_size(path) <= min_size {
+                return false;
+            }
+        }
+
+        if let Some(pattern) = &self.matching {
+            let original_matches = entry
+                .map(|e| pattern.is_match(&e.original_path.to_string_lossy()))
+                .unwrap_or(false);
+            if !pattern.is_match(name) && !original_matches {
+                return false;
+            }
+        }
+
+        true
+    }
+}
 
 /// Run scrap command with the given arguments
 pub fn run_scrap(args: Vec<String>) -> Result<()> {
+    let args = take_global_flag(args);
+
+    // Items past the configured age move into monthly archives first
+    if let Some(days) = crate::config::WsConfig::current().scrap_tier_after_days() {
+        tier_scrap_folder(days);
+    }
+
     if args.is_empty() {
         // Default action: list contents
-        return list_scrap_contents(None);
+        return list_scrap_contents(&ListOptions::default());
     }
 
     let mut args_iter = args.iter();
     let first_arg = args_iter.next().unwrap();
 
     match first_arg.as_str() {
-        "list" => {
-            let sort_option = if args.len() > 2 && args[1] == "--sort" {
-                Some(&args[2])
-            } else {
-                None
-            };
-            list_scrap_contents(sort_option.map(|s| s.as_str()))
-        }
+        "list" => list_scrap_contents(&ListOptions::from_args(&args)?),
         "clean" => {
             let days = if args.len() > 2 && args[1] == "--days" {
-                args[2].parse().unwrap_or(30)
+                args[2].parse().with_context(|| format!("Invalid number of days: {}", args[2]))?
             } else {
-                30
+                crate::config::WsConfig::current().retention_days()
             };
             let dry_run = args.contains(&"--dry-run".to_string());
-            clean_scrap_folder(days, dry_run)
+            let require_archived = args.contains(&"--require-archived".to_string());
+            clean_scrap_folder(days, dry_run, require_archived)
         }
         "purge" => {
             let force = args.contains(&"--force".to_string());
-            purge_scrap_folder(force)
+            let dry_run = args.contains(&"--dry-run".to_string());
+            let require_archived = args.contains(&"--require-archived".to_string());
+            let filter = ItemFilter::from_args(&args)?;
+            purge_scrap_folder(force, dry_run, require_archived, &filter)
         }
         "find" => {
             if args.len() < 2 {
@@ -48,40 +226,131 @@ pub fn run_scrap(args: Vec<String>) -> Result<()> {
             let content_search = args.contains(&"--content".to_string());
             find_in_scrap(pattern, content_search)
         }
+        "archive" if args.get(1).is_some_and(|a| a == "list") => {
+            list_tiers(args.get(2).map(|s| s.as_str()))
+        }
         "archive" => {
-            let output = if args.len() > 2 && args[1] == "--output" {
-                Some(&args[2])
+            let output = args.iter()
+                .position(|a| a == "--output")
+                .and_then(|i| args.get(i + 1));
+            let remove = args.contains(&"--remove".to_string());
+            let encrypt = args.contains(&"--encrypt".to_string());
+            let filter = ItemFilter::from_args(&args)?;
+            archive_scrap_folder(output.map(|s| s.as_str()), remove, encrypt, &filter)
+        }
+        "restore-archive" => {
+            if args.len() < 2 {
+                anyhow::bail!("restore-archive requires an archive path");
+            }
+            restore_archive(Path::new(&args[1]))
+        }
+        "export" => {
+            let manifest = match args.get(1) {
+                Some(path) => PathBuf::from(path),
+                None => get_scrap_directory()?.join(MANIFEST_FILE),
+            };
+            export_manifest(&manifest)
+        }
+        "import" => {
+            let manifest = args.get(1).context("import requires a manifest or scrap folder path")?;
+            let from = args.iter()
+                .position(|a| a == "--from")
+                .map(|i| args.get(i + 1).context("--from requires a directory"))
+                .transpose()?;
+            let dry_run = args.contains(&"--dry-run".to_string());
+            import_manifest(Path::new(manifest), from.map(Path::new), dry_run)
+        }
+        "doctor" => {
+            let fix = args.contains(&"--fix".to_string());
+            let verify = !args.contains(&"--no-verify".to_string());
+            doctor_scrap_folder(fix, verify)
+        }
+        "dedup" => {
+            let dry_run = args.contains(&"--dry-run".to_string());
+            dedup_scrap_folder(dry_run)
+        }
+        "schedule-clean" => {
+            let days = match args.iter().position(|a| a == "--days").and_then(|i| args.get(i + 1)) {
+                Some(days) => days.parse().with_context(|| format!("Invalid number of days: {}", days))?,
+                None => 30,
+            };
+            let frequency = ["daily", "weekly", "monthly"]
+                .into_iter()
+                .find(|f| args.contains(&format!("--{}", f)))
+                .unwrap_or("weekly")
+                .parse()?;
+            let schedule = CleanSchedule {
+                working_dir: std::env::current_dir()?,
+                days,
+                frequency,
+                executable: std::env::current_exe()?,
+            };
+            schedule_clean_command(&schedule, args.contains(&"--remove".to_string()), args.contains(&"--print".to_string()))
+        }
+        "config" => {
+            scrap_config_command(args.get(1).map(|s| s.as_str()), args.get(2).map(|s| s.as_str()))
+        }
+        "versions" => {
+            let path = args.get(1).context("versions requires a path")?;
+            list_versions(path)
+        }
+        "history" => {
+            let limit = if args.len() > 2 && args[1] == "--limit" {
+                Some(args[2].parse().context("--limit requires a number")?)
             } else {
                 None
             };
-            let remove = args.contains(&"--remove".to_string());
-            archive_scrap_folder(output.map(|s| s.as_str()), remove)
+            show_history(limit)
         }
-        path => {
-            // Treat as file path to scrap
-            let path_buf = PathBuf::from(path);
-            scrap_file_or_directory(&path_buf)
+        _ => {
+            // Treat the remaining arguments (and with --stdin, each line of input) as paths to scrap
+            let force_protected = args.contains(&"--force-protected".to_string());
+            let mut paths: Vec<PathBuf> = args.iter()
+                .filter(|a| !a.starts_with("--"))
+                .map(PathBuf::from)
+                .collect();
+            if args.contains(&"--stdin".to_string()) {
+                paths.extend(read_stdin_paths()?);
+            }
+            if paths.is_empty() {
+                println!("No paths to scrap");
+                return Ok(());
+            }
+            scrap_paths(&paths, force_protected)
         }
     }
 }
 
 /// Run unscrap command with the given arguments
 pub fn run_unscrap(args: Vec<String>) -> Result<()> {
+    let args = take_global_flag(args);
     let scrap_dir = get_scrap_directory()?;
-    let mut metadata = ScrapMetadata::load(&scrap_dir)?;
 
-    if args.is_empty() {
-        // Restore last scrapped item
-        return restore_last_item(&mut metadata, &scrap_dir);
+    // Used by the shell completion scripts; must stay fast and quiet
+    if args.iter().any(|a| a == "--complete-items") {
+        if let Ok(metadata) = ScrapMetadata::load(&scrap_dir) {
+            let mut names: Vec<_> = metadata.entries.keys().collect();
+            names.sort();
+            for name in names {
+                println!("{}", name);
+            }
+        }
+        return Ok(());
     }
 
-    let mut args_iter = args.iter();
-    let name = args_iter.next().unwrap();
+    let _lock = if scrap_dir.exists() { Some(MetadataLock::acquire(&scrap_dir)?) } else { None };
+    let mut metadata = ScrapMetadata::load(&scrap_dir)?;
+
+    let mut name = None;
     let mut to_path = None;
-    let mut force = false;
+    let mut options = RestoreOptions::default();
+    let mut all = false;
+    let mut matching = None;
+    let mut from_origin = None;
+    let mut version = None;
+    let mut rebase = None;
 
-    // Parse remaining arguments
-    let mut i = 1;
+    let mut i = 0;
     while i < args.len() {
         match args[i].as_str() {
             "--to" => {
@@ -92,18 +361,138 @@ pub fn run_unscrap(args: Vec<String>) -> Result<()> {
                     anyhow::bail!("--to requires a path argument");
                 }
             }
+            "--matching" => {
+                if i + 1 < args.len() {
+                    matching = Some(args[i + 1].clone());
+                    i += 2;
+                } else {
+                    anyhow::bail!("--matching requires a pattern argument");
+                }
+            }
+            "--from-origin" => {
+                if i + 1 < args.len() {
+                    from_origin = Some(args[i + 1].clone());
+                    i += 2;
+                } else {
+                    anyhow::bail!("--from-origin requires a path or glob argument");
+                }
+            }
+            "--version" => {
+                let value = args.get(i + 1).context("--version requires a version number")?;
+                version = Some(value.parse::<u32>().with_context(|| format!("Invalid version: {}", value))?);
+                i += 2;
+            }
+            "--rebase" => {
+                match (args.get(i + 1), args.get(i + 2)) {
+                    (Some(old_root), Some(new_root)) => {
+                        rebase = Some((absolute_path(Path::new(old_root)), absolute_path(Path::new(new_root))));
+                        i += 3;
+                    }
+                    _ => anyhow::bail!("--rebase requires OLD_ROOT and NEW_ROOT arguments"),
+                }
+            }
             "--force" => {
-                force = true;
+                options.on_conflict = ConflictPolicy::Overwrite;
+                i += 1;
+            }
+            "--on-conflict" => {
+                if i + 1 < args.len() {
+                    options.on_conflict = args[i + 1].parse()?;
+                    i += 2;
+                } else {
+                    anyhow::bail!("--on-conflict requires a policy argument");
+                }
+            }
+            "--all" => {
+                all = true;
+                i += 1;
+            }
+            "--dry-run" => {
+                options.dry_run = true;
+                i += 1;
+            }
+            "--merge" => {
+                options.merge = true;
+                i += 1;
+            }
+            "--no-verify" => {
+                options.verify = false;
+                i += 1;
+            }
+            arg => {
+                if name.is_none() && !arg.starts_with("--") {
+                    name = Some(arg.to_string());
+                }
                 i += 1;
             }
-            _ => i += 1,
         }
     }
 
-    restore_item(&mut metadata, &scrap_dir, name, to_path, force)
+    // Without an explicit --rebase, original paths follow the project when
+    // the scrap folder is no longer where it was first used
+    let rebase = rebase.or_else(|| {
+        let current_root = scrap_dir.parent().map(absolute_path)?;
+        metadata.project_root.clone()
+            .filter(|root| *root != current_root)
+            .map(|root| (root, current_root))
+    });
+    if let Some((old_root, new_root)) = rebase {
+        let rebased = metadata.rebase(&old_root, &new_root);
+        if rebased > 0 {
+            println!("Rebased {} original paths from {} to {}", rebased, old_root.display(), new_root.display());
+        }
+    }
+
+    if all || matching.is_some() {
+        if name.is_some() || to_path.is_some() {
+            anyhow::bail!("--all and --matching cannot be combined with an item name or --to");
+        }
+        let pattern = matching
+            .map(|p| regex::Regex::new(&p).with_context(|| format!("Invalid pattern: {}", p)))
+            .transpose()?;
+        return restore_bulk(&mut metadata, &scrap_dir, pattern.as_ref(), options);
+    }
+
+    if let Some(origin) = from_origin {
+        if name.is_some() {
+            anyhow::bail!("--from-origin cannot be combined with an item name");
+        }
+        return restore_from_origin(&mut metadata, &scrap_dir, &origin, version, to_path, options);
+    }
+
+    if let Some(version) = version {
+        let name = name.context("--version needs an item name or --from-origin")?;
+        let origin = match metadata.get_entry(&name) {
+            Some(entry) => entry.origin().to_path_buf(),
+            None => absolute_path(Path::new(&name)),
+        };
+        let chosen = select_version(&metadata, &scrap_dir, &origin, Some(version))?;
+        return restore_item(&mut metadata, &scrap_dir, &chosen, to_path, options).map(|_| ());
+    }
+
+    match name {
+        Some(name) => restore_item(&mut metadata, &scrap_dir, &name, to_path, options).map(|_| ()),
+        // Restore last scrapped item
+        None => restore_last_item(&mut metadata, &scrap_dir, options),
+    }
+}
+
+/// Point this run at the global scrap folder when `--global` is among
+/// `args`, returning the other arguments
+fn take_global_flag(args: Vec<String>) -> Vec<String> {
+    let (global, args): (Vec<_>, Vec<_>) = args.into_iter().partition(|a| a == "--global");
+    if !global.is_empty() {
+        // Through the environment, so hooks and nested runs see the same folder
+        std::env::set_var(SCRAP_DIR_ENV, global_scrap_dir());
+    }
+    args
 }
 
+/// The scrap folder: `SCRAP_DIR` when set, otherwise `.scrap` in the current directory
 fn get_scrap_directory() -> Result<PathBuf> {
+    if let Some(dir) = std::env::var_os(SCRAP_DIR_ENV).filter(|dir| !dir.is_empty()) {
+        return Ok(absolute_path(Path::new(&dir)));
+    }
     let current_dir = std::env::current_dir()?;
     Ok(current_dir.join(".scrap"))
 }
@@ -111,18 +500,79 @@ fn get_scrap_directory() -> Result<PathBuf> {
 fn ensure_scrap_directory() -> Result<PathBuf> {
     let scrap_dir = get_scrap_directory()?;
     if !scrap_dir.exists() {
-        fs::create_dir_all(&scrap_dir)?;
-        update_gitignore(&scrap_dir)?;
+        create_scrap_directory(&scrap_dir)?;
     }
     Ok(scrap_dir)
 }
 
+/// Create `scrap_dir` and, for a `.scrap` folder, its ignore entry. A
+/// folder that can't be created because of where it lives gets an
+/// explanation and the ways around it rather than the bare OS error.
+fn create_scrap_directory(scrap_dir: &Path) -> Result<()> {
+    if let Err(e) = fs::create_dir_all(scrap_dir) {
+        let cause = if is_read_only_filesystem(&e) {
+            "The filesystem is mounted read-only, as in a read-only checkout or container."
+        } else if e.kind() == std::io::ErrorKind::PermissionDenied {
+            "You are not allowed to create directories there."
+        } else {
+            return Err(anyhow::Error::new(e).context(format!("Failed to create scrap folder {}", scrap_dir.display())));
+        };
+
+        let mut message = format!("Cannot create scrap folder {}: {}\n  {}\n  Alternatives:", scrap_dir.display(), e, cause);
+        let global_dir = global_scrap_dir();
+        if scrap_dir != global_dir {
+            message.push_str(&format!("\n    ws scrap --global ...           keep items in {}", global_dir.display()));
+        }
+        message.push_str(&format!("\n    {}=/writable/dir ws scrap ...  keep items in a directory of your choice", SCRAP_DIR_ENV));
+        anyhow::bail!(message);
+    }
+
+    if scrap_dir.file_name().is_some_and(|name| name == ".scrap") {
+        update_gitignore(scrap_dir)?;
+    }
+    Ok(())
+}
+
+/// EROFS
+#[cfg(unix)]
+fn is_read_only_filesystem(e: &std::io::Error) -> bool {
+    e.raw_os_error() == Some(30)
+}
+
+/// ERROR_WRITE_PROTECT
+#[cfg(not(unix))]
+fn is_read_only_filesystem(e: &std::io::Error) -> bool {
+    e.raw_os_error() == Some(19)
+}
+
+/// Make sure git ignores the new scrap folder, in the place chosen by the
+/// `gitignore` scrap config setting
 fn update_gitignore(scrap_dir: &Path) -> Result<()> {
-    let gitignore_path = scrap_dir.parent().unwrap().join(".gitignore");
+    let working_dir = scrap_dir.parent().unwrap();
+    let mode = match crate::config::WsConfig::current().scrap.gitignore {
+        Some(mode) => mode,
+        None => ScrapConfig::load()
+            .map(|config| config.gitignore)
+            .unwrap_or_else(|e| {
+                log::warn!("Ignoring unreadable scrap config: {:#}", e);
+                GitignoreMode::default()
+            }),
+    };
+
+    match (mode, find_git_repository(working_dir)) {
+        (GitignoreMode::Off, _) => Ok(()),
+        (GitignoreMode::Local, _) | (_, None) => add_ignore_entry(&working_dir.join(".gitignore")),
+        (GitignoreMode::Auto | GitignoreMode::Repo, Some(repo)) => add_ignore_entry(&repo.root.join(".gitignore")),
+        (GitignoreMode::Exclude, Some(repo)) => add_ignore_entry(&repo.exclude_file),
+    }
+}
+
+fn add_ignore_entry(ignore_path: &Path) -> Result<()> {
+    // No leading slash, so one entry covers .scrap folders at any depth
     let entry = ".scrap/";
     
-    if gitignore_path.exists() {
-        let content = fs::read_to_string(&gitignore_path)?;
+    if ignore_path.exists() {
+        let content = fs::read_to_string(ignore_path)?;
         if !content.contains(entry) {
             let mut new_content = content;
             if !new_content.is_empty() && !new_content.ends_with('\n') {
@@ -130,49 +580,291 @@ fn update_gitignore(scrap_dir: &Path) -> Result<()> {
             }
             new_content.push_str(entry);
             new_content.push('\n');
-            fs::write(&gitignore_path, new_content)?;
+            fs::write(ignore_path, new_content)?;
         }
     } else {
-        fs::write(&gitignore_path, format!("{}\n", entry))?;
+        if let Some(parent) = ignore_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(ignore_path, format!("{}\n", entry))?;
     }
     
     Ok(())
 }
 
-fn scrap_file_or_directory(path: &Path) -> Result<()> {
-    if !path.exists() {
+struct GitRepository {
+    root: PathBuf,
+    exclude_file: PathBuf,
+}
+
+/// The git repository enclosing `dir`, if any (and if git is installed)
+fn find_git_repository(dir: &Path) -> Option<GitRepository> {
+    let git = |args: &[&str]| -> Option<String> {
+        let output = std::process::Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+
+    let root = PathBuf::from(git(&["rev-parse", "--show-toplevel"])?);
+    // Relative to `dir`; join keeps it as-is when git prints an absolute path
+    let exclude_file = dir.join(git(&["rev-parse", "--git-path", "info/exclude"])?);
+    Some(GitRepository { root, exclude_file })
+}
+
+fn schedule_clean_command(schedule: &CleanSchedule, remove: bool, print: bool) -> Result<()> {
+    if print {
+        if cfg!(target_os = "macos") {
+            print!("{}", schedule.launchd_plist());
+        } else {
+            let (service, timer) = schedule.systemd_units();
+            println!("# {}.service\n{}", schedule.name(), service);
+            print!("# {}.timer\n{}", schedule.name(), timer);
+        }
+        return Ok(());
+    }
+
+    if remove {
+        let removed = schedule.remove()?;
+        if removed.is_empty() {
+            println!("No scheduled clean found for {}", schedule.working_dir.display());
+        }
+        for path in removed {
+            println!("Removed {}", path.display());
+        }
+        return Ok(());
+    }
+
+    for path in schedule.install()? {
+        println!("Installed {}", path.display());
+    }
+    println!(
+        "Scrap folder in {} will be cleaned {} (items older than {} days)",
+        schedule.working_dir.display(),
+        schedule.frequency,
+        schedule.days
+    );
+    Ok(())
+}
+
+/// Show or change a user-level scrap setting
+fn scrap_config_command(key: Option<&str>, value: Option<&str>) -> Result<()> {
+    let mut config = ScrapConfig::load()?;
+    match (key, value) {
+        (Some(key), Some(value)) => {
+            config.set(key, value)?;
+            config.save()?;
+            println!("Set {} = {} in {}", key, value, ScrapConfig::path().display());
+            Ok(())
+        }
+        (key, _) => config.show(key),
+    }
+}
+
+/// Scrap every path in one metadata transaction: one lock, one save and one
+/// history record. Paths that cannot be scrapped are reported and skipped.
+fn scrap_paths(paths: &[PathBuf], force_protected: bool) -> Result<()> {
+    let patterns = if force_protected {
+        None
+    } else {
+        Some(load_protected_patterns(&get_scrap_directory()?)?)
+    };
+
+    let mut failures = Vec::new();
+    let mut accepted = Vec::new();
+    for path in paths {
+        match check_scrappable(path, patterns.as_deref()) {
+            Ok(()) => accepted.push(path),
+            Err(e) => failures.push(e),
+        }
+    }
+
+    if !accepted.is_empty() {
+        let scrap_dir = ensure_scrap_directory()?;
+        let _lock = MetadataLock::acquire(&scrap_dir)?;
+        let mut metadata = ScrapMetadata::load(&scrap_dir)?;
+        let mut history_items = Vec::new();
+        let compress_over = crate::config::WsConfig::current().scrap_compress_over();
+
+        for path in accepted {
+            match move_into_scrap(&mut metadata, &scrap_dir, path, compress_over) {
+                Ok((scrapped_name, deduplicated_with)) => {
+                    log::info!("Scrapped file: {} -> .scrap/{}", path.display(), scrapped_name);
+                    println!("Moved {} to .scrap/{}", path.display(), scrapped_name);
+                    if let Some(existing) = deduplicated_with {
+                        println!("  Identical to .scrap/{}, stored as a hard link", existing);
+                    }
+                    history_items.push(HistoryItem {
+                        scrapped_name,
+                        path: path.clone(),
+                    });
+                }
+                Err(e) => failures.push(e),
+            }
+        }
+
+        metadata.save(&scrap_dir)?;
+        if !history_items.is_empty() {
+            record_history(&scrap_dir, HistoryOperation::Scrap, history_items);
+        }
+    }
+
+    // A single path keeps its original error
+    if paths.len() == 1 {
+        if let Some(e) = failures.pop() {
+            return Err(e);
+        }
+    }
+
+    if !failures.is_empty() {
+        for e in &failures {
+            eprintln!("Error: {:#}", e);
+        }
+        anyhow::bail!("Failed to scrap {} of {} paths", failures.len(), paths.len());
+    }
+    Ok(())
+}
+
+fn check_scrappable(path: &Path, protected_patterns: Option<&[String]>) -> Result<()> {
+    if !path.exists() && !path.is_symlink() {
         anyhow::bail!("Path does not exist: {}", path.display());
     }
 
-    let scrap_dir = ensure_scrap_directory()?;
-    let mut metadata = ScrapMetadata::load(&scrap_dir)?;
+    if let Some(patterns) = protected_patterns {
+        if let Some(reason) = protection_reason(path, patterns)? {
+            anyhow::bail!(
+                "Refusing to scrap {}: {}. Use --force-protected to scrap it anyway",
+                path.display(),
+                reason
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Read paths from stdin, NUL-delimited if the input contains a NUL byte
+/// (`find -print0`, `fd -0`) and newline-delimited otherwise
+fn read_stdin_paths() -> Result<Vec<PathBuf>> {
+    let mut input = Vec::new();
+    std::io::stdin().read_to_end(&mut input)
+        .context("Failed to read paths from stdin")?;
+
+    let separator = if input.contains(&0) { b'\0' } else { b'\n' };
+    Ok(input
+        .split(|&b| b == separator)
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .filter(|line| !line.is_empty())
+        .map(path_from_bytes)
+        .collect())
+}
+
+/// Unix paths are arbitrary bytes, so names that aren't UTF-8 are kept as is
+#[cfg(unix)]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+    PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+}
 
+/// Move `path` into the scrap folder and add its metadata entry (without
+/// saving). Items larger than `compress_over` bytes are stored compressed.
+/// Returns the scrapped name and, if the item was stored as a hard link to an
+/// identical scrapped file, that file's name.
+#[tracing::instrument(level = "debug", skip(metadata, scrap_dir))]
+fn move_into_scrap(metadata: &mut ScrapMetadata, scrap_dir: &Path, path: &Path, compress_over: Option<u64>) -> Result<(String, Option<String>)> {
     let file_name = path.file_name()
         .ok_or_else(|| anyhow::anyhow!("Invalid file name"))?
         .to_string_lossy();
 
     // Generate unique name if file already exists in scrap
-    let scrapped_name = generate_unique_name(&scrap_dir, &file_name);
-    let dest_path = scrap_dir.join(&scrapped_name);
+    let mut scrapped_name = generate_unique_name(scrap_dir, &file_name);
+    let mut dest_path = scrap_dir.join(&scrapped_name);
+
+    // Capture before moving, since a cross-device move goes through a copy
+    let attributes = match FileAttributes::capture(path) {
+        Ok(attributes) => Some(attributes),
+        Err(e) => {
+            log::warn!("Failed to record attributes of {}: {}", path.display(), e);
+            None
+        }
+    };
 
     // Move file/directory to scrap
-    fs::rename(path, &dest_path)
+    move_path(path, &dest_path)
         .with_context(|| format!("Failed to move {} to scrap", path.display()))?;
 
-    // Update metadata
-    metadata.add_entry(&scrapped_name, path.to_path_buf());
-    metadata.save(&scrap_dir)?;
+    let content_hash = match hash_path(&dest_path) {
+        Ok(hash) => Some(hash),
+        Err(e) => {
+            log::warn!("Failed to hash {}: {}", dest_path.display(), e);
+            None
+        }
+    };
 
-    log::info!("Scrapped file: {} -> .scrap/{}", path.display(), scrapped_name);
-    println!("Moved {} to .scrap/{}", path.display(), scrapped_name);
-    Ok(())
+    // Large items are compressed once in scrap; the hash above stays that of
+    // the original content
+    let mut compression = None;
+    let large = compress_over.is_some_and(|limit| !dest_path.is_symlink() && path_size(&dest_path) > limit);
+    if large {
+        let kind = if dest_path.is_dir() { Compression::TarZstd } else { Compression::Zstd };
+        let compressed_name = generate_unique_name(scrap_dir, &format!("{}.{}", scrapped_name, kind.extension()));
+        let compressed_path = scrap_dir.join(&compressed_name);
+        // The item is in scrap already, so it must get its entry either way
+        match compress_item(&dest_path, &compressed_path) {
+            Ok(kind) => {
+                compression = Some(kind);
+                scrapped_name = compressed_name;
+                dest_path = compressed_path;
+            }
+            Err(e) => log::warn!("Keeping {} uncompressed: {:#}", scrapped_name, e),
+        }
+    }
+
+    // Share storage with an identical file that is already scrapped
+    let mut deduplicated_with = None;
+    if let Some(hash) = &content_hash {
+        if is_regular_file(&dest_path) && compression != Some(Compression::TarZstd) {
+            if let Some(existing) = metadata.find_by_hash(hash, &scrapped_name).filter(|e| e.compression == compression) {
+                let existing_path = scrap_dir.join(&existing.scrapped_name);
+                if is_regular_file(&existing_path) {
+                    match link_duplicate(&existing_path, &dest_path) {
+                        Ok(true) => deduplicated_with = Some(existing.scrapped_name.clone()),
+                        Ok(false) => {}
+                        Err(e) => log::warn!("Keeping {} as a separate copy: {:#}", scrapped_name, e),
+                    }
+                }
+            }
+        }
+    }
+
+    let entry = metadata.add_entry(&scrapped_name, path.to_path_buf());
+    entry.content_hash = content_hash;
+    entry.attributes = attributes;
+    entry.compression = compression;
+    Ok((scrapped_name, deduplicated_with))
 }
 
 fn generate_unique_name(scrap_dir: &Path, base_name: &str) -> String {
+    // Names of bookkeeping files are taken too. One starting like the
+    // metadata file stays reserved whatever the suffix, so it loses the dot.
+    let base_name = match base_name.strip_prefix('.') {
+        Some(rest) if base_name.starts_with(METADATA_FILE) => rest,
+        _ => base_name,
+    };
     let mut name = base_name.to_string();
     let mut counter = 1;
     
-    while scrap_dir.join(&name).exists() {
+    while scrap_dir.join(&name).exists() || is_reserved_name(&name) {
         if let Some(dot_pos) = base_name.rfind('.') {
             let (stem, ext) = base_name.split_at(dot_pos);
             name = format!("{}_{}{}", stem, counter, ext);
@@ -185,12 +877,86 @@ fn generate_unique_name(scrap_dir: &Path, base_name: &str) -> String {
     name
 }
 
-fn list_scrap_contents(sort_option: Option<&str>) -> Result<()> {
+/// Column shown by `scrap list --columns`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ListColumn {
+    Name,
+    Size,
+    Age,
+    Date,
+    Origin,
+}
+
+impl std::str::FromStr for ListColumn {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "name" => Ok(ListColumn::Name),
+            "size" => Ok(ListColumn::Size),
+            "age" => Ok(ListColumn::Age),
+            "date" => Ok(ListColumn::Date),
+            "origin" => Ok(ListColumn::Origin),
+            other => anyhow::bail!("Unknown column: {} (expected name, size, age, date or origin)", other),
+        }
+    }
+}
+
+impl ListColumn {
+    fn header(self) -> &'static str {
+        match self {
+            ListColumn::Name => "NAME",
+            ListColumn::Size => "SIZE",
+            ListColumn::Age => "AGE",
+            ListColumn::Date => "DATE",
+            ListColumn::Origin => "ORIGIN",
+        }
+    }
+}
+
+/// Filtering, sorting, paging and layout for `scrap list`
+#[derive(Debug, Default)]
+pub struct ListOptions {
+    pub filter: ItemFilter,
+    /// name, date or size; date when unset
+    pub sort: Option<String>,
+    pub reverse: bool,
+    pub limit: Option<usize>,
+    pub offset: usize,
+    /// Table columns; the classic one-line-per-item format when empty
+    pub columns: Vec<ListColumn>,
+    /// Send the output through `$PAGER`
+    pub pager: bool,
+}
+
+impl ListOptions {
+    pub fn from_args(args: &[String]) -> Result<Self> {
+        let value_of = |flag: &str| args.iter().position(|a| a == flag).and_then(|i| args.get(i + 1));
+
+        Ok(Self {
+            filter: ItemFilter::from_args(args)?,
+            sort: value_of("--sort").cloned(),
+            reverse: args.contains(&"--reverse".to_string()),
+            limit: value_of("--limit")
+                .map(|v| v.parse().context("--limit requires a number"))
+                .transpose()?,
+            offset: value_of("--offset")
+                .map(|v| v.parse().context("--offset requires a number"))
+                .transpose()?
+                .unwrap_or(0),
+            columns: value_of("--columns")
+                .map(|v| v.split(',').map(str::parse).collect::<Result<Vec<_>>>())
+                .transpose()?
+                .unwrap_or_default(),
+            pager: args.contains(&"--pager".to_string()),
+        })
+    }
+}
+
+fn list_scrap_contents(options: &ListOptions) -> Result<()> {
     let scrap_dir = get_scrap_directory()?;
     if !scrap_dir.exists() {
-        fs::create_dir_all(&scrap_dir)
-            .with_context(|| format!("Failed to create scrap directory: {}", scrap_dir.display()))?;
-        update_gitignore(&scrap_dir)?;
+        create_scrap_directory(&scrap_dir)?;
         log::info!("Scrap folder is empty (new)");
         println!("Scrap folder is empty");
         return Ok(());
@@ -203,187 +969,926 @@ fn list_scrap_contents(sort_option: Option<&str>) -> Result<()> {
         return Ok(());
     }
 
-    let mut entries: Vec<_> = metadata.entries.values().collect();
+    let needs_size = options.sort.as_deref() == Some("size") || options.columns.contains(&ListColumn::Size);
+    let mut entries: Vec<_> = metadata.entries.values()
+        .filter(|e| options.filter.matches(&e.scrapped_name, &scrap_dir.join(&e.scrapped_name), Some(e)))
+        .map(|e| {
+            let size = if needs_size { path_size(&scrap_dir.join(&e.scrapped_name)) } else { 0 };
+            (e, size)
+        })
+        .collect();
     
-    match sort_option {
-        Some("date") => entries.sort_by_key(|e| e.scrapped_at),
-        Some("name") => entries.sort_by_key(|e| &e.scrapped_name),
-        Some("size") => {
-            // For size sorting, we'd need to get actual file sizes
-            entries.sort_by_key(|e| &e.scrapped_name); // Fallback to name
-        }
-        _ => entries.sort_by_key(|e| e.scrapped_at),
+    match options.sort.as_deref() {
+        Some("date") | None => entries.sort_by_key(|(e, _)| e.scrapped_at),
+        Some("name") => entries.sort_by(|(a, _), (b, _)| a.scrapped_name.cmp(&b.scrapped_name)),
+        // Largest first
+        Some("size") => entries.sort_by(|(_, a), (_, b)| b.cmp(a)),
+        Some(other) => anyhow::bail!("Unknown sort order: {} (expected name, date or size)", other),
     }
-
-    println!("Scrapped files:");
-    for entry in entries {
-        println!("  {} (from {}) - {}", 
-                 entry.scrapped_name, 
-                 entry.original_path.display(),
-                 entry.scrapped_at.format("%Y-%m-%d %H:%M:%S"));
+    if options.reverse {
+        entries.reverse();
     }
 
-    Ok(())
-}
-
-fn clean_scrap_folder(days: u32, dry_run: bool) -> Result<()> {
-    let scrap_dir = get_scrap_directory()?;
-    if !scrap_dir.exists() {
-        println!("No .scrap directory found");
+    if entries.is_empty() {
+        println!("No scrapped items match the filter");
+        return Ok(());
+    }
+
+    let total = entries.len();
+    let page: Vec<_> = entries.into_iter()
+        .skip(options.offset)
+        .take(options.limit.unwrap_or(usize::MAX))
+        .collect();
+
+    let mut lines = Vec::new();
+    if options.columns.is_empty() {
+        lines.push("Scrapped files:".to_string());
+        for (entry, _) in &page {
+            lines.push(format!("  {} (from {}) - {}", 
+                     entry.scrapped_name, 
+                     entry.original_path.display(),
+                     entry.scrapped_at.format("%Y-%m-%d %H:%M:%S")));
+        }
+    } else {
+        let rows: Vec<Vec<String>> = page.iter()
+            .map(|(entry, size)| options.columns.iter().map(|column| match column {
+                ListColumn::Name => entry.scrapped_name.clone(),
+                ListColumn::Size => format_size(*size),
+                ListColumn::Age => format_age(entry.scrapped_at),
+                ListColumn::Date => entry.scrapped_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+                ListColumn::Origin => entry.original_path.display().to_string(),
+            }).collect())
+            .collect();
+        lines.extend(format_table(&options.columns, &rows));
+    }
+
+    if options.offset > 0 || page.len() < total {
+        let first = if page.is_empty() { 0 } else { options.offset + 1 };
+        lines.push(format!("Showing {}-{} of {} items", first, options.offset + page.len(), total));
+    }
+
+    if options.pager {
+        page_output(&lines)
+    } else {
+        for line in &lines {
+            println!("{}", line);
+        }
+        Ok(())
+    }
+}
+
+/// Left-aligned columns separated by two spaces; the last column is not padded
+fn format_table(columns: &[ListColumn], rows: &[Vec<String>]) -> Vec<String> {
+    let mut widths: Vec<usize> = columns.iter().map(|c| c.header().len()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let headers: Vec<String> = columns.iter().map(|c| c.header().to_string()).collect();
+    std::iter::once(&headers)
+        .chain(rows)
+        .map(|row| {
+            let cells: Vec<String> = row.iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect();
+            cells.join("  ").trim_end().to_string()
+        })
+        .collect()
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+fn format_age(scrapped_at: chrono::DateTime<Utc>) -> String {
+    let age = Utc::now() - scrapped_at;
+    if age.num_days() > 0 {
+        format!("{}d ago", age.num_days())
+    } else if age.num_hours() > 0 {
+        format!("{}h ago", age.num_hours())
+    } else if age.num_minutes() > 0 {
+        format!("{}m ago", age.num_minutes())
+    } else {
+        "just now".to_string()
+    }
+}
+
+/// Write `lines` through `$PAGER` (default `less`), falling back to stdout
+/// when the pager cannot be started
+fn page_output(lines: &[String]) -> Result<()> {
+    let pager = std::env::var("PAGER").unwrap_or_else(|_| "less".to_string());
+    let mut parts = pager.split_whitespace();
+    let spawned = parts.next().and_then(|program| {
+        std::process::Command::new(program)
+            .args(parts)
+            .stdin(std::process::Stdio::piped())
+            .spawn()
+            .ok()
+    });
+
+    let Some(mut child) = spawned else {
+        for line in lines {
+            println!("{}", line);
+        }
+        return Ok(());
+    };
+
+    if let Some(mut stdin) = child.stdin.take() {
+        use std::io::Write;
+        for line in lines {
+            // The user may quit the pager early
+            if writeln!(stdin, "{}", line).is_err() {
+                break;
+            }
+        }
+    }
+    child.wait().context("Failed to wait for pager")?;
+    Ok(())
+}
+
+#[tracing::instrument]
+fn clean_scrap_folder(days: u32, dry_run: bool, require_archived: bool) -> Result<()> {
+    let mut folder = ScrapFolder::current()?;
+    if !folder.exists() {
+        println!("No .scrap directory found");
         return Ok(());
     }
 
-    let mut metadata = ScrapMetadata::load(&scrap_dir)?;
     let cutoff_date = Utc::now() - chrono::Duration::days(days as i64);
-    let mut removed_count = 0;
+    let expired: Vec<_> = folder.entries()?
+        .into_iter()
+        .filter(|entry| entry.scrapped_at < cutoff_date)
+        .collect();
+    let mut unarchived: Vec<_> = expired.iter()
+        .filter(|entry| !entry.is_archived())
+        .map(|entry| entry.scrapped_name.clone())
+        .collect();
+    unarchived.sort();
+    report_unarchived(&unarchived, require_archived);
+    if require_archived {
+        folder = folder.with_require_archived();
+    }
 
-    let entries_to_remove: Vec<_> = metadata.entries.iter()
-        .filter(|(_, entry)| entry.scrapped_at < cutoff_date)
-        .map(|(name, _)| name.clone())
+    if dry_run {
+        let mut expired: Vec<_> = expired.into_iter()
+            .filter(|entry| !require_archived || entry.is_archived())
+            .map(|entry| entry.scrapped_name)
+            .collect();
+        expired.sort();
+        for name in &expired {
+            println!("Would remove: {}", name);
+        }
+        println!("Would remove {} items older than {} days", expired.len(), days);
+        return Ok(());
+    }
+
+    let removed = folder.clean(days)?;
+    for entry in &removed {
+        println!("Removed: {}", entry.scrapped_name);
+    }
+    println!("Removed {} items older than {} days", removed.len(), days);
+    Ok(())
+}
+
+/// Point out items about to be deleted that exist in no archive, or with
+/// `--require-archived`, that they are kept
+fn report_unarchived(names: &[String], require_archived: bool) {
+    if require_archived {
+        for name in names {
+            println!("Keeping (never archived): {}", name);
+        }
+    } else if !names.is_empty() {
+        eprintln!("Warning: {} items have never been archived: {}", names.len(), names.join(", "));
+    }
+}
+
+fn purge_scrap_folder(force: bool, dry_run: bool, require_archived: bool, filter: &ItemFilter) -> Result<()> {
+    let mut folder = ScrapFolder::current()?;
+    if !folder.exists() {
+        println!("No .scrap directory found");
+        return Ok(());
+    }
+
+    let unarchived = folder.unarchived(filter)?;
+    report_unarchived(&unarchived, require_archived);
+    if require_archived {
+        folder = folder.with_require_archived();
+    }
+    let items: Vec<_> = folder.items(filter)?
+        .into_iter()
+        .filter(|(name, _)| !require_archived || !unarchived.contains(name))
         .collect();
 
-    for name in entries_to_remove {
-        let file_path = scrap_dir.join(&name);
-        if dry_run {
+    if dry_run {
+        for (name, _) in &items {
             println!("Would remove: {}", name);
+        }
+        println!("Would purge {} items from scrap folder", items.len());
+        return Ok(());
+    }
+
+    if !force {
+        let count = items.len();
+        if !crate::prompt::confirm(&format!("Permanently delete {} items from the scrap folder?", count), false)? {
+            anyhow::bail!("Use --force or --assume-yes to confirm purging scrapped files");
+        }
+    }
+
+    let purged = folder.purge(filter)?;
+    println!("Purged {} items from scrap folder", purged.len());
+    Ok(())
+}
+
+/// Items in the scrap folder (excluding bookkeeping files) that pass `filter`, sorted by name
+fn select_items(scrap_dir: &Path, metadata: &ScrapMetadata, filter: &ItemFilter) -> Result<Vec<(String, PathBuf)>> {
+    let mut items = Vec::new();
+    for entry in fs::read_dir(scrap_dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let path = entry.path();
+        if !is_reserved_name(&name) && filter.matches(&name, &path, metadata.get_entry(&name)) {
+            items.push((name, path));
+        }
+    }
+    items.sort();
+    Ok(items)
+}
+
+fn find_in_scrap(pattern: &str, _content_search: bool) -> Result<()> {
+    let folder = ScrapFolder::current()?;
+    if !folder.exists() {
+        println!("No .scrap directory found");
+        return Ok(());
+    }
+
+    // Content search is not implemented yet; --content matches names and paths only
+    let found = folder.find(pattern)?;
+    for entry in &found {
+        println!("{} (from {}) - {}", 
+                 entry.scrapped_name, 
+                 entry.original_path.display(),
+                 entry.scrapped_at.format("%Y-%m-%d %H:%M:%S"));
+    }
+
+    if found.is_empty() {
+        println!("No matching files found");
+    } else {
+        println!("Found {} matching files", found.len());
+    }
+
+    Ok(())
+}
+
+#[tracing::instrument(skip(filter))]
+fn archive_scrap_folder(output: Option<&str>, remove: bool, encrypt: bool, filter: &ItemFilter) -> Result<()> {
+    let folder = ScrapFolder::current()?;
+    if !folder.exists() {
+        println!("No .scrap directory found");
+        return Ok(());
+    }
+
+    // With filters, archive only the selected items plus their metadata entries
+    if !filter.is_empty() && folder.items(filter)?.is_empty() {
+        println!("No items match the given filters");
+        return Ok(());
+    }
+
+    let default_name = if encrypt { "scrap-archive.tar.gz.enc" } else { "scrap-archive.tar.gz" };
+    let archive_name = output.unwrap_or(default_name);
+    let archive_path = Path::new(archive_name);
+
+    // Items are only removed once the archive is complete on disk
+    if encrypt {
+        // Ask before doing any work so a mistyped confirmation costs nothing
+        let passphrase = archive_crypto::read_passphrase(true)?;
+        let file = fs::File::create(archive_name)
+            .with_context(|| format!("Failed to write archive {}", archive_name))?;
+        let writer = archive_crypto::EncryptWriter::new(file, &passphrase)?;
+        folder.archive(writer, |writer| Ok(writer.finish()?.sync_all()?), filter, archive_path, remove)?;
+    } else {
+        let file = fs::File::create(archive_name)
+            .with_context(|| format!("Failed to write archive {}", archive_name))?;
+        folder.archive(file, |file| Ok(file.sync_all()?), filter, archive_path, remove)?;
+    }
+
+    println!("Created archive: {}", archive_name);
+    if remove {
+        if filter.is_empty() {
+            println!("Removed all files from scrap folder");
         } else {
-            if file_path.exists() {
-                if file_path.is_dir() {
-                    fs::remove_dir_all(&file_path)?;
+            println!("Removed archived items from scrap folder");
+        }
+    }
+
+    Ok(())
+}
+
+/// Write a tar.gz of the whole scrap folder, or of just the selected items
+/// together with a metadata file covering them
+fn write_archive<W: std::io::Write>(
+    writer: W,
+    scrap_dir: &Path,
+    selection: Option<(&[(String, PathBuf)], &ScrapMetadata)>,
+) -> Result<W> {
+    let enc = flate2::write::GzEncoder::new(writer, flate2::Compression::default());
+    let mut tar = tar::Builder::new(enc);
+
+    match selection {
+        // Add all files from scrap directory, but for the metadata lock held
+        // while the archive is written, which Windows would not let us read
+        None => {
+            tar.append_dir("scrap", scrap_dir)?;
+            let mut entries = fs::read_dir(scrap_dir)?.collect::<std::io::Result<Vec<_>>>()?;
+            entries.sort_by_key(|entry| entry.file_name());
+            for entry in entries {
+                if entry.file_name() == METADATA_LOCK_FILE {
+                    continue;
+                }
+                let archived_path = Path::new("scrap").join(entry.file_name());
+                if entry.file_type()?.is_dir() {
+                    tar.append_dir_all(&archived_path, entry.path())?;
                 } else {
-                    fs::remove_file(&file_path)?;
+                    tar.append_path_with_name(entry.path(), &archived_path)?;
                 }
             }
-            metadata.remove_entry(&name);
-            println!("Removed: {}", name);
         }
-        removed_count += 1;
+        Some((items, metadata)) => append_items(&mut tar, items, metadata)?,
     }
 
-    if !dry_run && removed_count > 0 {
-        metadata.save(&scrap_dir)?;
+    Ok(tar.into_inner()?.finish()?)
+}
+
+/// Add `items` under `scrap/`, followed by a metadata file holding their entries
+fn append_items<W: std::io::Write>(tar: &mut tar::Builder<W>, items: &[(String, PathBuf)], metadata: &ScrapMetadata) -> Result<()> {
+    let mut archived = ScrapMetadata::new();
+    for (name, path) in items {
+        let archived_path = Path::new("scrap").join(name);
+        if path.is_dir() {
+            tar.append_dir_all(&archived_path, path)?;
+        } else {
+            tar.append_path_with_name(path, &archived_path)?;
+        }
+        if let Some(entry) = metadata.get_entry(name) {
+            archived.entries.insert(name.clone(), entry.clone());
+        }
     }
 
-    if dry_run {
-        println!("Would remove {} items older than {} days", removed_count, days);
+    let content = serde_json::to_vec_pretty(&archived)
+        .context("Failed to serialize metadata")?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(Utc::now().timestamp() as u64);
+    tar.append_data(&mut header, Path::new("scrap").join(METADATA_FILE), content.as_slice())?;
+    Ok(())
+}
+
+/// Add `items` to the tier `archive` as one more zstd frame holding a tar of
+/// them and their entries; the file is only ever appended to
+fn append_tier(archive: &Path, items: &[(String, PathBuf)], metadata: &ScrapMetadata) -> Result<()> {
+    if let Some(parent) = archive.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(archive)
+        .with_context(|| format!("Failed to open archive {}", archive.display()))?;
+    let mut tar = tar::Builder::new(zstd::Encoder::new(file, COMPRESSION_LEVEL)?);
+    append_items(&mut tar, items, metadata)?;
+    tar.into_inner()?.finish()?.sync_all()?;
+    Ok(())
+}
+
+/// A tar reader over a `scrap archive` (tar.gz) or a tier (zstd frames, one
+/// tar each, read back to back)
+fn archive_reader<'a>(reader: impl Read + 'a) -> Result<tar::Archive<Box<dyn Read + 'a>>> {
+    const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+    let mut reader = BufReader::new(reader);
+    let reader: Box<dyn Read + 'a> = if reader.fill_buf()?.starts_with(&ZSTD_MAGIC) {
+        Box::new(zstd::Decoder::with_buffer(reader)?)
     } else {
-        println!("Removed {} items older than {} days", removed_count, days);
+        Box::new(flate2::read::GzDecoder::new(reader))
+    };
+    let mut archive = tar::Archive::new(reader);
+    archive.set_ignore_zeros(true);
+    Ok(archive)
+}
+
+/// The archive at `path`, decrypted as it is read when it is encrypted
+fn open_archive(path: &Path) -> Result<Box<dyn Read>> {
+    let file = fs::File::open(path).with_context(|| format!("Failed to read archive {}", path.display()))?;
+    let mut reader = BufReader::new(file);
+    if archive_crypto::is_encrypted(reader.fill_buf()?) {
+        let passphrase = archive_crypto::read_passphrase(false)?;
+        return Ok(Box::new(archive_crypto::DecryptReader::new(reader, &passphrase)?));
+    }
+    Ok(Box::new(reader))
+}
+
+/// The entries of every metadata file in an archive
+fn archived_metadata(reader: impl Read) -> Result<ScrapMetadata> {
+    let archived_metadata_path = Path::new("scrap").join(METADATA_FILE);
+    let mut archived = ScrapMetadata::new();
+    for entry in archive_reader(reader)?.entries()? {
+        let mut entry = entry?;
+        if entry.path()? == archived_metadata_path {
+            read_archived_metadata(&mut entry, &mut archived)?;
+        }
     }
+    Ok(archived)
+}
 
+/// Add the entries of the archived metadata file `entry` to `archived`
+fn read_archived_metadata(entry: &mut impl Read, archived: &mut ScrapMetadata) -> Result<()> {
+    let mut content = String::new();
+    entry.read_to_string(&mut content)?;
+    let part: ScrapMetadata = serde_json::from_str(&content).context("Failed to parse archived metadata")?;
+    archived.entries.extend(part.entries);
     Ok(())
 }
 
-fn purge_scrap_folder(force: bool) -> Result<()> {
+/// Tier items older than `days`, reporting on stderr so listings stay clean.
+/// Failing to tier never fails the command that triggered it.
+fn tier_scrap_folder(days: u32) {
+    let folder = match ScrapFolder::current() {
+        Ok(folder) => folder,
+        Err(_) => return,
+    };
+    match folder.tier(days) {
+        Ok(tiered) if !tiered.is_empty() => {
+            eprintln!("Archived {} items older than {} days into {}", tiered.len(), days, folder.path().join(ARCHIVES_DIR).display());
+        }
+        Ok(_) => {}
+        Err(e) => eprintln!("Warning: could not archive items older than {} days: {:#}", days, e),
+    }
+}
+
+/// List the tiers in `.scrap/archives`, or the items in one of them
+fn list_tiers(tier: Option<&str>) -> Result<()> {
+    let tiers_dir = get_scrap_directory()?.join(ARCHIVES_DIR);
+    let mut tiers: Vec<PathBuf> = match fs::read_dir(&tiers_dir) {
+        Ok(dir) => dir.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect(),
+        Err(_) => Vec::new(),
+    };
+    tiers.retain(|path| path.to_string_lossy().ends_with(".tar.zst"));
+    tiers.sort();
+
+    let Some(tier) = tier else {
+        if tiers.is_empty() {
+            println!("No archive tiers in {}", tiers_dir.display());
+            return Ok(());
+        }
+        for path in &tiers {
+            let file = fs::File::open(path).with_context(|| format!("Failed to read archive {}", path.display()))?;
+            let size = file.metadata()?.len();
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            println!("{}  {} items  {}", name.trim_end_matches(".tar.zst"), archived_metadata(file)?.entries.len(), format_size(size));
+        }
+        return Ok(());
+    };
+
+    let path = tiers_dir.join(format!("{}.tar.zst", tier.trim_end_matches(".tar.zst")));
+    let file = fs::File::open(&path).with_context(|| format!("No archive tier {} in {}", tier, tiers_dir.display()))?;
+    let mut entries: Vec<_> = archived_metadata(file)?.entries.into_values().collect();
+    entries.sort_by_key(|entry| entry.scrapped_at);
+    for entry in &entries {
+        println!("{} (from {}) - {}",
+                 entry.scrapped_name,
+                 entry.original_path.display(),
+                 entry.scrapped_at.format("%Y-%m-%d %H:%M:%S"));
+    }
+    println!("{} items; restore them with: ws scrap restore-archive {}", entries.len(), path.display());
+    Ok(())
+}
+
+/// Write every entry of the scrap folder, with its original path, hash,
+/// attributes and timestamps, to a JSON manifest
+fn export_manifest(manifest_path: &Path) -> Result<()> {
     let scrap_dir = get_scrap_directory()?;
-    if !scrap_dir.exists() {
-        println!("No .scrap directory found");
+    let metadata = ScrapMetadata::load(&scrap_dir)?;
+    let manifest = ScrapManifest::from_metadata(&metadata);
+
+    let content = serde_json::to_string_pretty(&manifest)
+        .context("Failed to serialize manifest")?;
+    fs::write(manifest_path, content)
+        .with_context(|| format!("Failed to write {}", manifest_path.display()))?;
+
+    println!("Exported {} entries to {}", manifest.entries.len(), manifest_path.display());
+    Ok(())
+}
+
+/// Add the entries of a manifest written by `scrap export` (or of the
+/// manifest inside a scrap folder) to this scrap folder. Items are copied in
+/// from `from` (by default the directory holding the manifest) unless they
+/// are already here, e.g. after copying a whole `.scrap` folder; a taken
+/// name gets a numbered one. Entries already present and entries whose item
+/// can't be found are skipped.
+fn import_manifest(manifest_path: &Path, from: Option<&Path>, dry_run: bool) -> Result<()> {
+    let manifest_path = if manifest_path.is_dir() {
+        manifest_path.join(MANIFEST_FILE)
+    } else {
+        manifest_path.to_path_buf()
+    };
+    let manifest_path = manifest_path.as_path();
+    let content = fs::read_to_string(manifest_path)
+        .with_context(|| format!("Failed to read {}", manifest_path.display()))?;
+    let manifest: ScrapManifest = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse manifest {}", manifest_path.display()))?;
+    let from = match from {
+        Some(dir) => dir.to_path_buf(),
+        None => manifest_path.parent().map(absolute_path).unwrap_or_default(),
+    };
+
+    let scrap_dir = ensure_scrap_directory()?;
+    let _lock = MetadataLock::acquire(&scrap_dir)?;
+    let mut metadata = ScrapMetadata::load(&scrap_dir)?;
+    let in_place = from.canonicalize().ok() == scrap_dir.canonicalize().ok();
+
+    let mut imported = 0;
+    let mut skipped = 0;
+    for mut entry in manifest.entries {
+        let already_present = metadata.entries.values().any(|e| {
+            e.origin() == entry.origin() && e.scrapped_at == entry.scrapped_at && e.content_hash == entry.content_hash
+        });
+        if already_present {
+            println!("Skipping {}: already in scrap", entry.scrapped_name);
+            skipped += 1;
+            continue;
+        }
+
+        let source = from.join(&entry.scrapped_name);
+        let single_component = matches!(
+            Path::new(&entry.scrapped_name).components().collect::<Vec<_>>().as_slice(),
+            [Component::Normal(_)]
+        );
+        if is_reserved_name(&entry.scrapped_name) || !single_component || !(source.exists() || source.is_symlink()) {
+            println!("Skipping {}: item not found in {}", entry.scrapped_name, from.display());
+            skipped += 1;
+            continue;
+        }
+
+        let name = if in_place && !metadata.entries.contains_key(&entry.scrapped_name) {
+            entry.scrapped_name.clone()
+        } else {
+            generate_unique_name(&scrap_dir, &entry.scrapped_name)
+        };
+        if dry_run {
+            println!("Would import {} as {} (from {})", entry.scrapped_name, name, entry.original_path.display());
+            imported += 1;
+            continue;
+        }
+
+        let dest = scrap_dir.join(&name);
+        if dest != source {
+            copy_path(&source, &dest)
+                .with_context(|| format!("Failed to copy {} into scrap", source.display()))?;
+        }
+        entry.scrapped_name = name;
+        metadata.add_imported_entry(entry);
+        imported += 1;
+    }
+
+    if dry_run {
+        println!("Would import {} entries, {} skipped", imported, skipped);
         return Ok(());
     }
+    metadata.save(&scrap_dir)?;
+    println!("Imported {} entries from {}, {} skipped", imported, manifest_path.display(), skipped);
 
-    if !force {
-        anyhow::bail!("Use --force to confirm purging all scrapped files");
+    if let (Some(old_root), Some(new_root)) = (&manifest.project_root, &metadata.project_root) {
+        if old_root != new_root && imported > 0 {
+            println!("Original paths point into {}; restore them here with `unscrap --rebase {} {}`",
+                old_root.display(), old_root.display(), new_root.display());
+        }
     }
+    Ok(())
+}
 
-    // Remove all files and subdirectories in .scrap except .metadata.json
-    let entries = fs::read_dir(&scrap_dir)?;
-    let mut removed_count = 0;
+/// Unpack a `scrap archive` (encrypted or not) back into the scrap folder,
+/// streaming it from disk. Items whose name is already taken in `.scrap`
+/// are skipped, as are links that would point outside it.
+fn restore_archive(archive_path: &Path) -> Result<()> {
+    let reader = open_archive(archive_path)?;
 
-    for entry in entries {
-        let entry = entry?;
-        let path = entry.path();
-        let file_name = entry.file_name();
-        
-        if file_name != ".metadata.json" {
-            if path.is_dir() {
-                fs::remove_dir_all(&path)?;
-            } else {
-                fs::remove_file(&path)?;
-            }
-            removed_count += 1;
+    let scrap_dir = ensure_scrap_directory()?;
+    let _lock = MetadataLock::acquire(&scrap_dir)?;
+    let mut metadata = ScrapMetadata::load(&scrap_dir)?;
+
+    // Entries unpack under `scrap/` in a staging folder, where `unpack_in`
+    // keeps them from writing outside it, and move into place once complete
+    let staging = StagingDir::create(&scrap_dir)?;
+    let archived_metadata_path = Path::new("scrap").join(METADATA_FILE);
+    let mut archived = ScrapMetadata::new();
+    let mut restored = Vec::new();
+    let mut skipped = HashSet::new();
+    for entry in archive_reader(reader)?.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        if path == archived_metadata_path {
+            // Read along the way so restored items keep their original paths
+            read_archived_metadata(&mut entry, &mut archived)?;
+            continue;
         }
+        let Ok(relative) = path.strip_prefix("scrap") else { continue };
+        if !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+            continue;
+        }
+        let Some(top) = relative.components().next() else { continue };
+        let top_name = top.as_os_str().to_string_lossy().to_string();
+
+        if is_reserved_name(&top_name) || skipped.contains(&top_name) {
+            continue;
+        }
+        if !restored.contains(&top_name) && scrap_dir.join(&top_name).exists() {
+            println!("Skipping {}: already exists in scrap folder", top_name);
+            skipped.insert(top_name);
+            continue;
+        }
+        if !link_stays_inside(relative, &entry)? {
+            println!("Skipping {}: link {} points outside the scrap folder", top_name, relative.display());
+            restored.retain(|name| *name != top_name);
+            skipped.insert(top_name);
+            continue;
+        }
+        if !restored.contains(&top_name) {
+            restored.push(top_name);
+        }
+
+        entry.unpack_in(&staging.0)
+            .with_context(|| format!("Failed to extract {}", relative.display()))?;
     }
 
-    // Clear metadata
-    let empty_metadata = ScrapMetadata::new();
-    empty_metadata.save(&scrap_dir)?;
+    restored.retain(|name| {
+        let unpacked = staging.0.join("scrap").join(name);
+        unpacked.exists() || unpacked.is_symlink()
+    });
+    for name in &restored {
+        let unpacked = staging.0.join("scrap").join(name);
+        fs::rename(&unpacked, scrap_dir.join(name))
+            .with_context(|| format!("Failed to move {} into the scrap folder", name))?;
+        match archived.entries.get(name) {
+            Some(entry) => {
+                metadata.entries.insert(name.clone(), entry.clone());
+            }
+            None => {
+                metadata.add_entry(name, PathBuf::from(name));
+            }
+        }
+    }
+    metadata.save(&scrap_dir)?;
 
-    println!("Purged {} items from scrap folder", removed_count);
+    println!("Restored {} items from {}", restored.len(), archive_path.display());
     Ok(())
 }
 
-fn find_in_scrap(pattern: &str, content_search: bool) -> Result<()> {
+/// Folder inside the scrap folder that an archive unpacks into first,
+/// removed with whatever is left in it on drop
+struct StagingDir(PathBuf);
+
+impl StagingDir {
+    fn create(scrap_dir: &Path) -> Result<Self> {
+        // A reserved name, so it is never taken for an item
+        let path = scrap_dir.join(format!("{}.restore-{}", METADATA_FILE, std::process::id()));
+        if path.exists() {
+            fs::remove_dir_all(&path)?;
+        }
+        fs::create_dir(&path).with_context(|| format!("Failed to create {}", path.display()))?;
+        Ok(Self(path))
+    }
+}
+
+impl Drop for StagingDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Whether `entry`, unpacked to `relative` inside the scrap folder, is not
+/// a link or is one that stays inside the scrap folder. Hard link targets
+/// are named from the root of the archive, symbolic ones from the link.
+fn link_stays_inside(relative: &Path, entry: &tar::Entry<impl Read>) -> Result<bool> {
+    let Some(target) = entry.link_name()? else { return Ok(true) };
+    let resolved = if entry.header().entry_type().is_hard_link() {
+        match target.strip_prefix("scrap") {
+            Ok(target) => target.to_path_buf(),
+            Err(_) => return Ok(false),
+        }
+    } else {
+        relative.parent().unwrap_or(Path::new("")).join(&target)
+    };
+
+    let mut depth = 0usize;
+    for component in resolved.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir if depth > 0 => depth -= 1,
+            // Absolute, or climbing out of the scrap folder
+            _ => return Ok(false),
+        }
+    }
+    Ok(true)
+}
+
+/// Compare `.metadata.json` with the contents of the scrap folder and report
+/// (or with `fix`, repair) entries that no longer match reality. With
+/// `verify`, items are also checked against their recorded checksums; those
+/// cannot be repaired, only reported.
+fn doctor_scrap_folder(fix: bool, verify: bool) -> Result<()> {
     let scrap_dir = get_scrap_directory()?;
     if !scrap_dir.exists() {
         println!("No .scrap directory found");
         return Ok(());
     }
 
-    let metadata = ScrapMetadata::load(&scrap_dir)?;
-    let mut found_count = 0;
+    let _lock = MetadataLock::acquire(&scrap_dir)?;
+    let metadata_path = scrap_dir.join(METADATA_FILE);
+    let (mut metadata, corrupt) = match ScrapMetadata::load(&scrap_dir) {
+        Ok(metadata) => (metadata, false),
+        Err(e) => {
+            println!("Corrupt metadata: {:#}", e);
+            (ScrapMetadata::new(), true)
+        }
+    };
 
-    for (name, entry) in &metadata.entries {
-        let matches = if content_search {
-            // For content search, we'd need to read file contents
-            // For now, just match filename
-            name.contains(pattern) || entry.original_path.to_string_lossy().contains(pattern)
-        } else {
-            name.contains(pattern) || entry.original_path.to_string_lossy().contains(pattern)
-        };
+    let mut orphaned: Vec<String> = metadata.entries.keys()
+        .filter(|name| {
+            let path = scrap_dir.join(name);
+            !path.exists() && !path.is_symlink()
+        })
+        .cloned()
+        .collect();
+    orphaned.sort();
 
-        if matches {
-            println!("{} (from {}) - {}", 
-                     name, 
-                     entry.original_path.display(),
-                     entry.scrapped_at.format("%Y-%m-%d %H:%M:%S"));
-            found_count += 1;
+    let mut untracked = Vec::new();
+    for entry in fs::read_dir(&scrap_dir)? {
+        let name = entry?.file_name().to_string_lossy().to_string();
+        if !is_reserved_name(&name) && !metadata.entries.contains_key(&name) {
+            untracked.push(name);
         }
     }
+    untracked.sort();
 
-    if found_count == 0 {
-        println!("No matching files found");
-    } else {
-        println!("Found {} matching files", found_count);
+    let mut modified = Vec::new();
+    if verify {
+        let mut entries: Vec<&ScrapEntry> = metadata.entries.values()
+            .filter(|e| !orphaned.contains(&e.scrapped_name))
+            .collect();
+        entries.sort_by(|a, b| a.scrapped_name.cmp(&b.scrapped_name));
+        for entry in entries {
+            if content_modified(&scrap_dir, entry) {
+                modified.push(entry.scrapped_name.clone());
+            }
+        }
+    }
+
+    for name in &modified {
+        println!("Modified item: {} (content no longer matches its checksum)", name);
+    }
+    for name in &orphaned {
+        println!("Orphaned entry: {} (missing from .scrap)", name);
+    }
+    for name in &untracked {
+        println!("Untracked item: {} (no metadata entry)", name);
     }
 
+    let issues = orphaned.len() + untracked.len() + usize::from(corrupt);
+    if issues == 0 && modified.is_empty() {
+        println!("Scrap folder is healthy ({} entries)", metadata.entries.len());
+        return Ok(());
+    }
+    if !modified.is_empty() {
+        println!("Found {} modified items; they restore as they are now", modified.len());
+    }
+    if issues == 0 {
+        return Ok(());
+    }
+
+    if !fix {
+        println!("Found {} issues (run 'scrap doctor --fix' to repair)", issues);
+        return Ok(());
+    }
+
+    if corrupt {
+        let backup_path = scrap_dir.join(format!("{}.corrupt", METADATA_FILE));
+        fs::copy(&metadata_path, &backup_path)
+            .context("Failed to back up corrupt metadata file")?;
+        println!("Backed up corrupt metadata to {}", backup_path.display());
+    }
+
+    for name in &orphaned {
+        metadata.remove_entry(name);
+        println!("Pruned: {}", name);
+    }
+
+    // The original location of untracked items is unknown, so they restore
+    // into the current directory under their scrapped name.
+    for name in &untracked {
+        let content_hash = hash_path(&scrap_dir.join(name)).ok();
+        metadata.add_entry(name, PathBuf::from(name)).content_hash = content_hash;
+        println!("Registered: {}", name);
+    }
+
+    metadata.save(&scrap_dir)?;
+    println!("Repaired {} issues", issues);
     Ok(())
 }
 
-fn archive_scrap_folder(output: Option<&str>, remove: bool) -> Result<()> {
+#[tracing::instrument]
+fn dedup_scrap_folder(dry_run: bool) -> Result<()> {
     let scrap_dir = get_scrap_directory()?;
     if !scrap_dir.exists() {
         println!("No .scrap directory found");
         return Ok(());
     }
 
-    let archive_name = output.unwrap_or("scrap-archive.tar.gz");
-    
-    // Create tar.gz archive
-    let tar_gz = fs::File::create(archive_name)?;
-    let enc = flate2::write::GzEncoder::new(tar_gz, flate2::Compression::default());
-    let mut tar = tar::Builder::new(enc);
+    let _lock = MetadataLock::acquire(&scrap_dir)?;
+    let mut metadata = ScrapMetadata::load(&scrap_dir)?;
 
-    // Add all files from scrap directory
-    tar.append_dir_all("scrap", &scrap_dir)?;
-    tar.finish()?;
+    // Backfill hashes for entries scrapped before hashes were recorded. A
+    // compressed entry's hash is that of its original content, which is no
+    // longer at hand.
+    let mut metadata_changed = false;
+    for entry in metadata.entries.values_mut() {
+        if entry.content_hash.is_none() && entry.compression.is_none() {
+            let path = scrap_dir.join(&entry.scrapped_name);
+            if path.exists() {
+                entry.content_hash = hash_path(&path).ok();
+                metadata_changed |= entry.content_hash.is_some();
+            }
+        }
+    }
 
-    println!("Created archive: {}", archive_name);
+    // Group regular files by hash, oldest entry first so it becomes the link target
+    let mut groups: std::collections::HashMap<&str, Vec<&ScrapEntry>> = std::collections::HashMap::new();
+    for entry in metadata.entries.values() {
+        if let Some(hash) = &entry.content_hash {
+            if is_regular_file(&scrap_dir.join(&entry.scrapped_name)) {
+                groups.entry(hash.as_str()).or_default().push(entry);
+            }
+        }
+    }
 
-    if remove {
-        purge_scrap_folder(true)?;
-        println!("Removed all files from scrap folder");
+    let mut linked_count = 0;
+    let mut saved_bytes = 0u64;
+    for group in groups.values_mut().filter(|g| g.len() > 1) {
+        group.sort_by_key(|e| e.scrapped_at);
+        let original = scrap_dir.join(&group[0].scrapped_name);
+        for duplicate in &group[1..] {
+            let duplicate_path = scrap_dir.join(&duplicate.scrapped_name);
+            if is_same_file(&original, &duplicate_path) {
+                continue;
+            }
+            let size = fs::metadata(&duplicate_path).map(|m| m.len()).unwrap_or(0);
+            if dry_run {
+                println!("Would link: {} -> {}", duplicate.scrapped_name, group[0].scrapped_name);
+            } else if link_duplicate(&original, &duplicate_path)? {
+                println!("Linked: {} -> {}", duplicate.scrapped_name, group[0].scrapped_name);
+            } else {
+                println!("Skipped: {} (hard links not supported)", duplicate.scrapped_name);
+                continue;
+            }
+            linked_count += 1;
+            saved_bytes += size;
+        }
+    }
+
+    if metadata_changed && !dry_run {
+        metadata.save(&scrap_dir)?;
+    }
+
+    if dry_run {
+        println!("Would deduplicate {} items, saving {} bytes", linked_count, saved_bytes);
+    } else {
+        println!("Deduplicated {} items, saved {} bytes", linked_count, saved_bytes);
     }
 
     Ok(())
 }
 
-fn restore_last_item(metadata: &mut ScrapMetadata, scrap_dir: &Path) -> Result<()> {
+fn restore_last_item(metadata: &mut ScrapMetadata, scrap_dir: &Path, options: RestoreOptions) -> Result<()> {
     let last_entry = metadata.entries.values()
         .max_by_key(|entry| entry.scrapped_at);
 
     match last_entry {
         Some(entry) => {
             let name = entry.scrapped_name.clone();
-            restore_item(metadata, scrap_dir, &name, None, false)
+            restore_item(metadata, scrap_dir, &name, None, options).map(|_| ())
         }
         None => {
             println!("No items in scrap folder to restore");
@@ -392,15 +1897,421 @@ fn restore_last_item(metadata: &mut ScrapMetadata, scrap_dir: &Path) -> Result<(
     }
 }
 
-fn restore_item(metadata: &mut ScrapMetadata, scrap_dir: &Path, name: &str, to_path: Option<PathBuf>, force: bool) -> Result<()> {
+/// Restore every entry (or every entry whose name or original path matches
+/// `pattern`) to its original location. Entries whose destination is
+/// occupied are skipped under `ConflictPolicy::Fail`, otherwise the policy
+/// is applied per item.
+fn restore_bulk(
+    metadata: &mut ScrapMetadata,
+    scrap_dir: &Path,
+    pattern: Option<&regex::Regex>,
+    options: RestoreOptions,
+) -> Result<()> {
+    let mut selected: Vec<ScrapEntry> = metadata.entries.values()
+        .filter(|e| match pattern {
+            Some(p) => p.is_match(&e.scrapped_name) || p.is_match(&e.original_path.to_string_lossy()),
+            None => true,
+        })
+        .cloned()
+        .collect();
+    selected.sort_by_key(|e| e.scrapped_at);
+
+    if selected.is_empty() {
+        println!("No matching items in scrap folder to restore");
+        return Ok(());
+    }
+
+    let mut restored = 0;
+    let mut conflicts = Vec::new();
+    let mut failures = Vec::new();
+    let mut planned_destinations = Vec::new();
+
+    for entry in &selected {
+        let dest_path = default_restore_path(entry);
+        let mergeable = options.merge && dest_path.is_dir() && scrap_dir.join(&entry.scrapped_name).is_dir();
+        if options.on_conflict == ConflictPolicy::Fail && !mergeable
+            && (dest_path.exists() || planned_destinations.contains(&dest_path)) {
+            println!("Conflict: {} -> {} (destination exists, skipped)", entry.scrapped_name, dest_path.display());
+            conflicts.push(entry.scrapped_name.clone());
+            continue;
+        }
+
+        if options.dry_run {
+            restore_item(metadata, scrap_dir, &entry.scrapped_name, Some(dest_path.clone()), options)?;
+            planned_destinations.push(dest_path);
+            restored += 1;
+            continue;
+        }
+
+        match restore_item(metadata, scrap_dir, &entry.scrapped_name, Some(dest_path), options) {
+            Ok(_) => restored += 1,
+            Err(e) => {
+                eprintln!("Failed to restore {}: {:#}", entry.scrapped_name, e);
+                failures.push(entry.scrapped_name.clone());
+            }
+        }
+    }
+
+    println!();
+    if options.dry_run {
+        println!("Would restore {} items, {} conflicts skipped", restored, conflicts.len());
+    } else {
+        println!("Restored {} items, {} conflicts skipped, {} failed", restored, conflicts.len(), failures.len());
+    }
+
+    if !failures.is_empty() {
+        anyhow::bail!("Failed to restore {} items: {}", failures.len(), failures.join(", "));
+    }
+
+    Ok(())
+}
+
+/// Restore the entries whose original path matches `origin`, a path or a
+/// glob (`*` and `?` within one path component, `**` across them). When one
+/// original path was scrapped several times, `version` or the user picks the
+/// version.
+fn restore_from_origin(
+    metadata: &mut ScrapMetadata,
+    scrap_dir: &Path,
+    origin: &str,
+    version: Option<u32>,
+    to_path: Option<PathBuf>,
+    options: RestoreOptions,
+) -> Result<()> {
+    let pattern = origin_pattern(&absolute_path(Path::new(origin)))?;
+    let mut origins: Vec<PathBuf> = metadata.entries.values()
+        .map(|e| e.origin().to_path_buf())
+        .filter(|o| pattern.is_match(&o.to_string_lossy()))
+        .collect();
+    origins.sort();
+    origins.dedup();
+
+    if origins.is_empty() {
+        anyhow::bail!("No item in scrap was originally at {}", origin);
+    }
+    if (to_path.is_some() || version.is_some()) && origins.len() > 1 {
+        anyhow::bail!("{} matches {} original paths; --to and --version need exactly one", origin, origins.len());
+    }
+
+    let selected = origins.iter()
+        .map(|o| select_version(metadata, scrap_dir, o, version))
+        .collect::<Result<Vec<_>>>()?;
+    for name in selected {
+        restore_item(metadata, scrap_dir, &name, to_path.clone(), options)?;
+    }
+    Ok(())
+}
+
+/// Scrapped name of the given version of the item at `origin`; without a
+/// version the user picks one, newest first
+fn select_version(metadata: &ScrapMetadata, scrap_dir: &Path, origin: &Path, version: Option<u32>) -> Result<String> {
+    let mut versions = metadata.versions(origin);
+    if let Some(version) = version {
+        return versions.iter()
+            .find(|e| e.version == Some(version))
+            .map(|e| e.scrapped_name.clone())
+            .with_context(|| format!("{} has no version {} in scrap (see `ws scrap versions`)", origin.display(), version));
+    }
+
+    versions.reverse();
+    let choice = if versions.len() <= 1 {
+        0
+    } else {
+        let items: Vec<String> = versions.iter()
+            .map(|e| format!("v{} {} (scrapped {}, {})", e.version.unwrap_or_default(), e.scrapped_name, format_age(e.scrapped_at), format_size(path_size(&scrap_dir.join(&e.scrapped_name)))))
+            .collect();
+        crate::prompt::select(&format!("{} was scrapped {} times; restore which version?", origin.display(), versions.len()), &items, 0)?
+    };
+    versions.get(choice)
+        .map(|e| e.scrapped_name.clone())
+        .with_context(|| format!("No item in scrap was originally at {}", origin.display()))
+}
+
+/// Print every version of the item originally at `path`, or of the item a
+/// scrapped name belongs to
+fn list_versions(path: &str) -> Result<()> {
+    let scrap_dir = get_scrap_directory()?;
+    let metadata = ScrapMetadata::load(&scrap_dir)?;
+    let origin = match metadata.get_entry(path) {
+        Some(entry) => entry.origin().to_path_buf(),
+        None => absolute_path(Path::new(path)),
+    };
+
+    let versions = metadata.versions(&origin);
+    if versions.is_empty() {
+        println!("No versions of {} in scrap", origin.display());
+        return Ok(());
+    }
+
+    println!("Versions of {}:", origin.display());
+    for entry in versions {
+        println!("  v{}  {} - {} ({}, {})",
+                 entry.version.unwrap_or_default(),
+                 entry.scrapped_name,
+                 entry.scrapped_at.format("%Y-%m-%d %H:%M:%S"),
+                 format_age(entry.scrapped_at),
+                 format_size(path_size(&scrap_dir.join(&entry.scrapped_name))));
+    }
+    Ok(())
+}
+
+/// Regex matching the original paths selected by a path or glob
+fn origin_pattern(glob: &Path) -> Result<regex::Regex> {
+    let glob = glob.to_string_lossy();
+    let glob = glob.trim_end_matches('/');
+    let mut pattern = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                pattern.push_str(".*");
+            }
+            '*' => pattern.push_str("[^/]*"),
+            '?' => pattern.push_str("[^/]"),
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+    regex::Regex::new(&pattern).with_context(|| format!("Invalid origin pattern: {}", glob))
+}
+
+/// Apply the conflict policy to an occupied destination and return the path
+/// the item should actually be restored to.
+fn resolve_conflict(metadata: &mut ScrapMetadata, scrap_dir: &Path, dest_path: &Path, policy: ConflictPolicy) -> Result<PathBuf> {
+    match policy {
+        ConflictPolicy::Fail => {
+            anyhow::bail!(
+                "Destination already exists: {} (use --force or --on-conflict to resolve)",
+                dest_path.display()
+            );
+        }
+        ConflictPolicy::Overwrite => {
+            if dest_path.is_dir() && !dest_path.is_symlink() {
+                fs::remove_dir_all(dest_path)?;
+            } else {
+                fs::remove_file(dest_path)?;
+            }
+            Ok(dest_path.to_path_buf())
+        }
+        ConflictPolicy::Rename => renamed_destination(dest_path),
+        ConflictPolicy::Backup => {
+            let (backup_name, _) = move_into_scrap(metadata, scrap_dir, dest_path, None)?;
+            metadata.save(scrap_dir)?;
+            record_history(scrap_dir, HistoryOperation::Scrap, vec![HistoryItem {
+                scrapped_name: backup_name.clone(),
+                path: dest_path.to_path_buf(),
+            }]);
+            println!("Backed up existing {} to .scrap/{}", dest_path.display(), backup_name);
+            Ok(dest_path.to_path_buf())
+        }
+    }
+}
+
+/// Counts gathered while merging a scrapped directory into an existing one
+#[derive(Debug, Default)]
+struct MergeStats {
+    merged: usize,
+    resolved: usize,
+    skipped: usize,
+}
+
+/// Restore a scrapped directory into an existing directory, moving entries
+/// one by one and applying the conflict policy to each clashing file. Files
+/// skipped under `ConflictPolicy::Fail` stay in .scrap with the entry intact.
+fn merge_restore(
+    metadata: &mut ScrapMetadata,
+    scrap_dir: &Path,
+    name: &str,
+    dest_path: &Path,
+    options: RestoreOptions,
+) -> Result<()> {
+    let source_path = scrap_dir.join(name);
+    let mut stats = MergeStats::default();
+    merge_directory(metadata, scrap_dir, &source_path, dest_path, options, &mut stats)?;
+
+    if options.dry_run {
+        println!("Would merge {} into {}: {} moved, {} resolved by {}, {} conflicts skipped",
+                 name, dest_path.display(), stats.merged, stats.resolved, options.on_conflict, stats.skipped);
+        return Ok(());
+    }
+
+    if stats.skipped == 0 {
+        fs::remove_dir(&source_path)
+            .with_context(|| format!("Failed to remove merged directory {}", source_path.display()))?;
+        metadata.remove_entry(name);
+    } else if stats.merged + stats.resolved > 0 {
+        // What remains is no longer the content that was scrapped, so verify
+        // would report it as modified against the old hash
+        let content_hash = hash_path(&source_path).ok();
+        if let Some(entry) = metadata.entries.get_mut(name) {
+            entry.content_hash = content_hash;
+        }
+    }
+    metadata.save(scrap_dir)?;
+    record_history(scrap_dir, HistoryOperation::Unscrap, vec![HistoryItem {
+        scrapped_name: name.to_string(),
+        path: dest_path.to_path_buf(),
+    }]);
+
+    println!("Merged {} into {}: {} moved, {} conflicts resolved, {} conflicts skipped",
+             name, dest_path.display(), stats.merged, stats.resolved, stats.skipped);
+    if stats.skipped > 0 {
+        println!("Skipped files remain in .scrap/{}", name);
+    }
+    Ok(())
+}
+
+fn merge_directory(
+    metadata: &mut ScrapMetadata,
+    scrap_dir: &Path,
+    source: &Path,
+    dest: &Path,
+    options: RestoreOptions,
+    stats: &mut MergeStats,
+) -> Result<()> {
+    let mut children: Vec<_> = fs::read_dir(source)?
+        .collect::<std::result::Result<_, _>>()?;
+    children.sort_by_key(|e| e.file_name());
+
+    for child in children {
+        let source_child = child.path();
+        let dest_child = dest.join(child.file_name());
+        let source_is_dir = source_child.is_dir() && !source_child.is_symlink();
+        let occupied = dest_child.exists() || dest_child.is_symlink();
+
+        if source_is_dir && dest_child.is_dir() && !dest_child.is_symlink() {
+            merge_directory(metadata, scrap_dir, &source_child, &dest_child, options, stats)?;
+            if !options.dry_run && fs::read_dir(&source_child)?.next().is_none() {
+                fs::remove_dir(&source_child)?;
+            }
+            continue;
+        }
+
+        if !occupied {
+            if options.dry_run {
+                println!("Would move {} -> {}", source_child.display(), dest_child.display());
+            } else {
+                move_path(&source_child, &dest_child)
+                    .with_context(|| format!("Failed to move {} to {}", source_child.display(), dest_child.display()))?;
+            }
+            stats.merged += 1;
+            continue;
+        }
+
+        if options.on_conflict == ConflictPolicy::Fail {
+            println!("Conflict: {} already exists, skipped", dest_child.display());
+            stats.skipped += 1;
+            continue;
+        }
+
+        if options.dry_run {
+            println!("Would resolve conflict at {} ({})", dest_child.display(), options.on_conflict);
+        } else {
+            let target = resolve_conflict(metadata, scrap_dir, &dest_child, options.on_conflict)?;
+            move_path(&source_child, &target)
+                .with_context(|| format!("Failed to move {} to {}", source_child.display(), target.display()))?;
+        }
+        stats.resolved += 1;
+    }
+
+    Ok(())
+}
+
+/// First free `name.restored-N` sibling of an occupied destination
+fn renamed_destination(dest_path: &Path) -> Result<PathBuf> {
+    let file_name = dest_path.file_name()
+        .ok_or_else(|| anyhow::anyhow!("Invalid destination: {}", dest_path.display()))?
+        .to_string_lossy()
+        .to_string();
+    let mut counter = 1;
+    loop {
+        let candidate = dest_path.with_file_name(format!("{}.restored-{}", file_name, counter));
+        if !candidate.exists() && !candidate.is_symlink() {
+            return Ok(candidate);
+        }
+        counter += 1;
+    }
+}
+
+/// Describe what restoring `name` to `dest_path` would do, without touching anything
+fn preview_restore(name: &str, dest_path: &Path, on_conflict: ConflictPolicy) -> Result<()> {
+    if !dest_path.exists() && !dest_path.is_symlink() {
+        println!("Would restore {} to {}", name, dest_path.display());
+        return Ok(());
+    }
+
+    match on_conflict {
+        ConflictPolicy::Fail => {
+            println!("Conflict: {} already exists, {} would not be restored", dest_path.display(), name);
+        }
+        ConflictPolicy::Overwrite => {
+            println!("Would restore {} to {} (overwriting existing)", name, dest_path.display());
+        }
+        ConflictPolicy::Rename => {
+            println!("Would restore {} to {} ({} exists)", name, renamed_destination(dest_path)?.display(), dest_path.display());
+        }
+        ConflictPolicy::Backup => {
+            println!("Would back up existing {} to .scrap and restore {} there", dest_path.display(), name);
+        }
+    }
+    Ok(())
+}
+
+/// Where an entry is restored when no explicit destination is given: its
+/// recorded original path, or the current directory if none was recorded.
+fn default_restore_path(entry: &ScrapEntry) -> PathBuf {
+    if entry.original_path.as_os_str().is_empty() {
+        PathBuf::from(&entry.scrapped_name)
+    } else {
+        entry.original_path.clone()
+    }
+}
+
+fn restore_item(
+    metadata: &mut ScrapMetadata,
+    scrap_dir: &Path,
+    name: &str,
+    to_path: Option<PathBuf>,
+    options: RestoreOptions,
+) -> Result<PathBuf> {
+    let name = stored_name(metadata, name);
+    let name = name.as_str();
+
+    // Compressed items are expanded inside the scrap folder first, so they
+    // are restored like any other item
+    let expanded;
+    let name = match metadata.get_entry(name).and_then(|e| e.compression) {
+        Some(compression) if !options.dry_run => {
+            expanded = expand_item(metadata, scrap_dir, name, compression)?;
+            expanded.as_str()
+        }
+        _ => name,
+    };
+
     let entry = metadata.get_entry(name)
         .ok_or_else(|| anyhow::anyhow!("Item not found in scrap: {}", name))?;
+    let attributes = entry.attributes.clone();
+
+    if options.verify && content_modified(scrap_dir, entry) {
+        eprintln!("Warning: {} was modified or corrupted in .scrap since it was scrapped", name);
+    }
 
     let source_path = scrap_dir.join(name);
-    let dest_path = to_path.unwrap_or_else(|| entry.original_path.clone());
+    let mut dest_path = to_path.unwrap_or_else(|| default_restore_path(entry));
 
-    if dest_path.exists() && !force {
-        anyhow::bail!("Destination already exists: {} (use --force to overwrite)", dest_path.display());
+    if options.merge && source_path.is_dir() && dest_path.is_dir() && !dest_path.is_symlink() {
+        merge_restore(metadata, scrap_dir, name, &dest_path, options)?;
+        return Ok(dest_path);
+    }
+
+    if options.dry_run {
+        preview_restore(name, &dest_path, options.on_conflict)?;
+        return Ok(dest_path);
+    }
+
+    if dest_path.exists() || dest_path.is_symlink() {
+        dest_path = resolve_conflict(metadata, scrap_dir, &dest_path, options.on_conflict)?;
     }
 
     // Ensure parent directory exists
@@ -410,14 +2321,130 @@ fn restore_item(metadata: &mut ScrapMetadata, scrap_dir: &Path, name: &str, to_p
         }
     }
 
-    // Move file back
-    fs::rename(&source_path, &dest_path)
-        .with_context(|| format!("Failed to restore {} to {}", name, dest_path.display()))?;
+    // Move file back. Deduplicated files are copied out so the restored file
+    // no longer shares data with the copies that stay in scrap.
+    if is_hard_linked(&source_path) {
+        copy_path(&source_path, &dest_path)
+            .with_context(|| format!("Failed to restore {} to {}", name, dest_path.display()))?;
+        fs::remove_file(&source_path)?;
+    } else {
+        move_path(&source_path, &dest_path)
+            .with_context(|| format!("Failed to restore {} to {}", name, dest_path.display()))?;
+    }
+
+    if let Some(attributes) = attributes {
+        if let Err(e) = attributes.apply(&dest_path) {
+            log::warn!("Failed to restore attributes of {}: {}", dest_path.display(), e);
+        }
+    }
 
     // Remove from metadata
     metadata.remove_entry(name);
     metadata.save(scrap_dir)?;
+    record_history(scrap_dir, HistoryOperation::Unscrap, vec![HistoryItem {
+        scrapped_name: name.to_string(),
+        path: dest_path.clone(),
+    }]);
 
     println!("Restored {} to {}", name, dest_path.display());
+    Ok(dest_path)
+}
+
+/// Whether the stored item no longer has the content it was scrapped with.
+/// Entries without a recorded checksum, and items that cannot be read, are
+/// not reported as modified.
+fn content_modified(scrap_dir: &Path, entry: &ScrapEntry) -> bool {
+    let Some(expected) = &entry.content_hash else {
+        return false;
+    };
+    let path = scrap_dir.join(&entry.scrapped_name);
+    match hash_stored(&path, entry.compression) {
+        Ok(actual) => actual != *expected,
+        Err(e) => {
+            log::warn!("Failed to verify {}: {:#}", path.display(), e);
+            false
+        }
+    }
+}
+
+/// `name` as it is stored: a compressed item may be given without its
+/// `.zst` or `.tar.zst` suffix
+fn stored_name(metadata: &ScrapMetadata, name: &str) -> String {
+    if metadata.get_entry(name).is_some() {
+        return name.to_string();
+    }
+    [Compression::Zstd, Compression::TarZstd]
+        .into_iter()
+        .find_map(|compression| {
+            metadata.get_entry(&format!("{}.{}", name, compression.extension()))
+                .filter(|e| e.compression == Some(compression))
+        })
+        .map_or_else(|| name.to_string(), |e| e.scrapped_name.clone())
+}
+
+/// Decompress the item stored as `name` next to it and move its metadata
+/// entry to the expanded name, which is returned
+fn expand_item(metadata: &mut ScrapMetadata, scrap_dir: &Path, name: &str, compression: Compression) -> Result<String> {
+    let base_name = name.strip_suffix(&format!(".{}", compression.extension())).unwrap_or(name);
+    let expanded_name = generate_unique_name(scrap_dir, base_name);
+    decompress_item(&scrap_dir.join(name), compression, &scrap_dir.join(&expanded_name))?;
+
+    let mut entry = metadata.remove_entry(name)
+        .ok_or_else(|| anyhow::anyhow!("Item not found in scrap: {}", name))?;
+    entry.scrapped_name = expanded_name.clone();
+    entry.compression = None;
+    metadata.entries.insert(expanded_name.clone(), entry);
+    metadata.save(scrap_dir)?;
+    Ok(expanded_name)
+}
+
+/// Append an operation to the history log. Failures are logged rather than
+/// returned because the operation itself has already completed.
+fn record_history(scrap_dir: &Path, operation: HistoryOperation, items: Vec<HistoryItem>) {
+    let record = HistoryRecord::new(operation, items);
+    if let Err(e) = record.append(scrap_dir) {
+        log::warn!("Failed to record {} in scrap history: {}", operation, e);
+    }
+    if !record.items.is_empty() {
+        run_scrap_hook(scrap_dir, &record);
+    }
+}
+
+/// Run the `on_<operation>` hook from the project's ws config, if any
+fn run_scrap_hook(scrap_dir: &Path, record: &HistoryRecord) {
+    let project_dir = scrap_dir.parent().unwrap_or(scrap_dir);
+    let config = crate::config::WsConfig::load(project_dir).unwrap_or_else(|e| {
+        log::warn!("Ignoring unreadable ws config: {:#}", e);
+        crate::config::WsConfig::default()
+    });
+    if let Some(command) = config.scrap_hook(record.operation) {
+        if let Err(e) = hooks::run_hook(command, scrap_dir, record) {
+            log::warn!("{:#}", e);
+            eprintln!("Warning: {:#}", e);
+        }
+    }
+}
+
+fn show_history(limit: Option<usize>) -> Result<()> {
+    let scrap_dir = get_scrap_directory()?;
+    let records = load_history(&scrap_dir)?;
+    if records.is_empty() {
+        println!("No scrap history recorded");
+        return Ok(());
+    }
+
+    let skip = limit.map(|l| records.len().saturating_sub(l)).unwrap_or(0);
+
+    println!("Scrap history:");
+    for record in &records[skip..] {
+        println!("  {} {:<8} {} item(s)",
+                 record.timestamp.format("%Y-%m-%d %H:%M:%S"),
+                 record.operation.to_string(),
+                 record.items.len());
+        for item in &record.items {
+            println!("      {} ({})", item.scrapped_name, item.path.display());
+        }
+    }
+
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,243 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// How often a scheduled `scrap clean` runs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScheduleFrequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl std::str::FromStr for ScheduleFrequency {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "daily" => Ok(ScheduleFrequency::Daily),
+            "weekly" => Ok(ScheduleFrequency::Weekly),
+            "monthly" => Ok(ScheduleFrequency::Monthly),
+            _ => anyhow::bail!("Unknown frequency: {} (expected daily, weekly or monthly)", s),
+        }
+    }
+}
+
+impl std::fmt::Display for ScheduleFrequency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ScheduleFrequency::Daily => "daily",
+            ScheduleFrequency::Weekly => "weekly",
+            ScheduleFrequency::Monthly => "monthly",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A periodic `ws scrap clean --days N` for one working directory, installed
+/// as a user-level systemd timer (Linux) or launchd agent (macOS)
+#[derive(Debug, Clone)]
+pub struct CleanSchedule {
+    pub working_dir: PathBuf,
+    pub days: u32,
+    pub frequency: ScheduleFrequency,
+    /// The `ws` binary to run
+    pub executable: PathBuf,
+}
+
+impl CleanSchedule {
+    /// Unit/agent name, unique per working directory so several folders can be scheduled
+    pub fn name(&self) -> String {
+        use sha2::{Digest, Sha256};
+        let digest = Sha256::digest(self.working_dir.to_string_lossy().as_bytes());
+        let id: String = digest.iter().take(4).map(|b| format!("{:02x}", b)).collect();
+        format!("ws-scrap-clean-{}", id)
+    }
+
+    /// The systemd `.service` and `.timer` unit files
+    pub fn systemd_units(&self) -> (String, String) {
+        let service = format!(
+            "[Unit]\n\
+             Description=Clean scrap folder in {dir}\n\
+             \n\
+             [Service]\n\
+             Type=oneshot\n\
+             WorkingDirectory={dir}\n\
+             Environment=WS_COMPLETIONS_LOADED=1\n\
+             ExecStart=\"{exe}\" scrap clean --days {days}\n",
+            dir = self.working_dir.display(),
+            exe = self.executable.display(),
+            days = self.days,
+        );
+        let timer = format!(
+            "[Unit]\n\
+             Description=Clean scrap folder in {dir} {frequency}\n\
+             \n\
+             [Timer]\n\
+             OnCalendar={frequency}\n\
+             Persistent=true\n\
+             \n\
+             [Install]\n\
+             WantedBy=timers.target\n",
+            dir = self.working_dir.display(),
+            frequency = self.frequency,
+        );
+        (service, timer)
+    }
+
+    /// The launchd agent property list
+    pub fn launchd_plist(&self) -> String {
+        let interval = match self.frequency {
+            ScheduleFrequency::Daily => "",
+            ScheduleFrequency::Weekly => "        <key>Weekday</key>\n        <integer>0</integer>\n",
+            ScheduleFrequency::Monthly => "        <key>Day</key>\n        <integer>1</integer>\n",
+        };
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{exe}</string>
+        <string>scrap</string>
+        <string>clean</string>
+        <string>--days</string>
+        <string>{days}</string>
+    </array>
+    <key>WorkingDirectory</key>
+    <string>{dir}</string>
+    <key>EnvironmentVariables</key>
+    <dict>
+        <key>WS_COMPLETIONS_LOADED</key>
+        <string>1</string>
+    </dict>
+    <key>StartCalendarInterval</key>
+    <dict>
+{interval}        <key>Hour</key>
+        <integer>3</integer>
+        <key>Minute</key>
+        <integer>0</integer>
+    </dict>
+</dict>
+</plist>
+"#,
+            label = self.launchd_label(),
+            exe = xml_escape(&self.executable.to_string_lossy()),
+            days = self.days,
+            dir = xml_escape(&self.working_dir.to_string_lossy()),
+            interval = interval,
+        )
+    }
+
+    fn launchd_label(&self) -> String {
+        format!("com.workspace.{}", self.name())
+    }
+
+    /// Write the unit files for this platform and activate them
+    pub fn install(&self) -> Result<Vec<PathBuf>> {
+        if cfg!(target_os = "macos") {
+            let plist_path = launch_agents_dir()?.join(format!("{}.plist", self.launchd_label()));
+            write_file(&plist_path, &self.launchd_plist())?;
+            run("launchctl", &["load", "-w", &plist_path.to_string_lossy()])?;
+            Ok(vec![plist_path])
+        } else if cfg!(target_os = "linux") {
+            let unit_dir = systemd_user_dir()?;
+            let (service, timer) = self.systemd_units();
+            let service_path = unit_dir.join(format!("{}.service", self.name()));
+            let timer_path = unit_dir.join(format!("{}.timer", self.name()));
+            write_file(&service_path, &service)?;
+            write_file(&timer_path, &timer)?;
+            run("systemctl", &["--user", "daemon-reload"])?;
+            run("systemctl", &["--user", "enable", "--now", &format!("{}.timer", self.name())])?;
+            Ok(vec![service_path, timer_path])
+        } else {
+            anyhow::bail!("Scheduled cleaning is only supported with systemd (Linux) or launchd (macOS)");
+        }
+    }
+
+    /// Deactivate and delete the unit files for this working directory
+    pub fn remove(&self) -> Result<Vec<PathBuf>> {
+        let mut removed = Vec::new();
+        if cfg!(target_os = "macos") {
+            let plist_path = launch_agents_dir()?.join(format!("{}.plist", self.launchd_label()));
+            if plist_path.exists() {
+                // Unloading fails if the agent is not loaded, which is fine
+                let _ = run("launchctl", &["unload", "-w", &plist_path.to_string_lossy()]);
+                fs::remove_file(&plist_path)?;
+                removed.push(plist_path);
+            }
+        } else if cfg!(target_os = "linux") {
+            let unit_dir = systemd_user_dir()?;
+            let timer_path = unit_dir.join(format!("{}.timer", self.name()));
+            if timer_path.exists() {
+                let _ = run("systemctl", &["--user", "disable", "--now", &format!("{}.timer", self.name())]);
+            }
+            for path in [timer_path, unit_dir.join(format!("{}.service", self.name()))] {
+                if path.exists() {
+                    fs::remove_file(&path)?;
+                    removed.push(path);
+                }
+            }
+            if !removed.is_empty() {
+                let _ = run("systemctl", &["--user", "daemon-reload"]);
+            }
+        } else {
+            anyhow::bail!("Scheduled cleaning is only supported with systemd (Linux) or launchd (macOS)");
+        }
+        Ok(removed)
+    }
+}
+
+fn home_dir() -> Result<PathBuf> {
+    std::env::var("HOME")
+        .map(PathBuf::from)
+        .context("HOME is not set")
+}
+
+fn systemd_user_dir() -> Result<PathBuf> {
+    // Check XDG_CONFIG_HOME first, fallback to ~/.config
+    let config_home = match std::env::var("XDG_CONFIG_HOME") {
+        Ok(dir) => PathBuf::from(dir),
+        Err(_) => home_dir()?.join(".config"),
+    };
+    Ok(config_home.join("systemd").join("user"))
+}
+
+fn launch_agents_dir() -> Result<PathBuf> {
+    Ok(home_dir()?.join("Library").join("LaunchAgents"))
+}
+
+fn write_file(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn run(program: &str, args: &[&str]) -> Result<()> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "{} {} failed: {}",
+            program,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -1,14 +1,149 @@
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use super::file_attributes::FileAttributes;
+use crate::file_lock::FileLock;
 use std::collections::HashMap;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+
+/// Name of the metadata file kept inside the scrap folder
+pub const METADATA_FILE: &str = ".metadata.json";
+
+/// Lock file guarding the metadata file
+pub const METADATA_LOCK_FILE: &str = ".metadata.json.lock";
+
+/// Name of the append-only operation log kept inside the scrap folder
+pub const HISTORY_FILE: &str = ".history.jsonl";
+
+/// Optional JSON array of extra protected patterns kept inside the scrap folder
+pub const PROTECTED_FILE: &str = ".protected.json";
+
+/// Where `scrap export` writes the manifest when no path is given
+pub const MANIFEST_FILE: &str = ".manifest.json";
+
+/// Directory in the scrap folder holding the monthly archives old items are tiered into
+pub const ARCHIVES_DIR: &str = "archives";
+
+/// Environment variable naming the scrap folder to use instead of `.scrap`
+/// in the current directory
+pub const SCRAP_DIR_ENV: &str = "SCRAP_DIR";
+
+/// The scrap folder `--global` uses, shared by every directory:
+/// `$XDG_DATA_HOME/ws/scrap`
+pub fn global_scrap_dir() -> PathBuf {
+    // Check XDG_DATA_HOME first, fallback to ~/.local/share
+    let data_home = std::env::var("XDG_DATA_HOME").unwrap_or_else(|_| {
+        let home = std::env::var("HOME").unwrap_or_default();
+        format!("{}/.local/share", home)
+    });
+    PathBuf::from(data_home).join("ws").join("scrap")
+}
+
+/// Patterns (gitignore syntax) that need `--force-protected` to be scrapped
+pub const DEFAULT_PROTECTED_PATTERNS: &[&str] = &[
+    ".git",
+    ".ws",
+    ".scrap",
+    "Cargo.toml",
+    "Cargo.lock",
+    "package.json",
+    "pyproject.toml",
+    "go.mod",
+];
+
+/// Whether a name in the scrap folder is bookkeeping rather than a scrapped item
+pub fn is_reserved_name(name: &str) -> bool {
+    name.starts_with(METADATA_FILE) || name == HISTORY_FILE || name == PROTECTED_FILE || name == MANIFEST_FILE || name == ARCHIVES_DIR
+}
+
+/// Built-in protected patterns plus any listed in `.scrap/.protected.json`
+pub fn load_protected_patterns(scrap_dir: &Path) -> Result<Vec<String>> {
+    let mut patterns: Vec<String> = DEFAULT_PROTECTED_PATTERNS.iter().map(|p| p.to_string()).collect();
+
+    let protected_path = scrap_dir.join(PROTECTED_FILE);
+    if protected_path.exists() {
+        let content = fs::read_to_string(&protected_path)
+            .with_context(|| format!("Failed to read {}", protected_path.display()))?;
+        let extra: Vec<String> = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {} (expected a JSON array of patterns)", protected_path.display()))?;
+        patterns.extend(extra);
+    }
+
+    Ok(patterns)
+}
+
+/// Explain why `path` must not be scrapped without `--force-protected`, if it is protected
+pub fn protection_reason(path: &Path, patterns: &[String]) -> Result<Option<String>> {
+    let cwd = fs::canonicalize(std::env::current_dir()?)?;
+
+    // Resolve the parent only, so a symlink is judged by its own name
+    let file_name = path.file_name()
+        .ok_or_else(|| anyhow::anyhow!("Invalid file name"))?;
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => fs::canonicalize(parent)?,
+        _ => cwd.clone(),
+    };
+    let absolute = parent.join(file_name);
+    let is_dir = absolute.is_dir();
+
+    if cwd.starts_with(&absolute) {
+        return Ok(Some("it contains the current directory".to_string()));
+    }
+    if is_dir && absolute.join(".git").exists() {
+        return Ok(Some("it is the root of a git repository".to_string()));
+    }
+
+    let mut builder = ignore::gitignore::GitignoreBuilder::new(&cwd);
+    for pattern in patterns {
+        builder.add_line(None, pattern)
+            .with_context(|| format!("Invalid protected pattern '{}'", pattern))?;
+    }
+    let matcher = builder.build()?;
+
+    let matched = match absolute.strip_prefix(&cwd) {
+        Ok(relative) => matcher.matched_path_or_any_parents(relative, is_dir),
+        Err(_) => matcher.matched(Path::new(file_name), is_dir),
+    };
+
+    Ok(match matched {
+        ignore::Match::Ignore(glob) => Some(format!("it matches protected pattern '{}'", glob.original())),
+        _ => None,
+    })
+}
+
+/// zstd level for items compressed on their way into the scrap folder
+pub const COMPRESSION_LEVEL: i32 = 3;
+
+/// How a large item is stored compressed in the scrap folder
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Compression {
+    /// A file as a single zstd stream, stored as `name.zst`
+    Zstd,
+    /// A directory as a zstd-compressed tar archive, stored as `name.tar.zst`
+    TarZstd,
+}
+
+impl Compression {
+    /// Suffix appended to the scrapped name
+    pub fn extension(self) -> &'static str {
+        match self {
+            Compression::Zstd => "zst",
+            Compression::TarZstd => "tar.zst",
+        }
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ScrapMetadata {
     pub version: u32,
     pub entries: HashMap<String, ScrapEntry>,
+    /// Directory holding the scrap folder when the metadata was first
+    /// written; lets `unscrap` notice that the project has moved
+    #[serde(default)]
+    pub project_root: Option<PathBuf>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -16,6 +151,36 @@ pub struct ScrapEntry {
     pub original_path: PathBuf,
     pub scrapped_at: DateTime<Utc>,
     pub scrapped_name: String,
+    /// SHA-256 of the item's content (files) or of its relative paths and contents (directories)
+    #[serde(default)]
+    pub content_hash: Option<String>,
+    /// Permissions, symlink target and xattrs recorded when the item was scrapped
+    #[serde(default)]
+    pub attributes: Option<FileAttributes>,
+    /// Absolute original path; entries sharing it are versions of one item
+    #[serde(default)]
+    pub origin: Option<PathBuf>,
+    /// 1 for the first time `origin` was scrapped, counting up
+    #[serde(default)]
+    pub version: Option<u32>,
+    /// Set when the item is stored compressed; `unscrap` expands it again
+    #[serde(default)]
+    pub compression: Option<Compression>,
+    /// Archives written by `scrap archive` that contain this item, as absolute paths
+    #[serde(default)]
+    pub archived_in: Vec<PathBuf>,
+}
+
+impl ScrapEntry {
+    /// The absolute original path identifying this item
+    pub fn origin(&self) -> &Path {
+        self.origin.as_deref().unwrap_or(&self.original_path)
+    }
+
+    /// Whether the item has been written to at least one archive
+    pub fn is_archived(&self) -> bool {
+        !self.archived_in.is_empty()
+    }
 }
 
 impl ScrapMetadata {
@@ -23,42 +188,132 @@ impl ScrapMetadata {
         Self {
             version: 1,
             entries: HashMap::new(),
+            project_root: None,
         }
     }
 
     pub fn load(scrap_dir: &Path) -> Result<Self> {
-        let metadata_path = scrap_dir.join(".metadata.json");
-        if !metadata_path.exists() {
-            return Ok(Self::new());
+        let metadata_path = scrap_dir.join(METADATA_FILE);
+        let mut metadata = if metadata_path.exists() {
+            let content = fs::read_to_string(&metadata_path)
+                .context("Failed to read metadata file")?;
+            serde_json::from_str(&content)
+                .context("Failed to parse metadata file")?
+        } else {
+            Self::new()
+        };
+        metadata.assign_versions();
+        if metadata.project_root.is_none() {
+            metadata.project_root = scrap_dir.parent().map(absolute_path);
         }
+        Ok(metadata)
+    }
 
-        let content = fs::read_to_string(&metadata_path)
-            .context("Failed to read metadata file")?;
-        
-        serde_json::from_str(&content)
-            .context("Failed to parse metadata file")
+    /// Translate original paths under `old_root` to the same paths under
+    /// `new_root`, returning how many entries changed
+    pub fn rebase(&mut self, old_root: &Path, new_root: &Path) -> usize {
+        let rebase = |path: &Path| path.strip_prefix(old_root).ok().map(|rest| new_root.join(rest));
+        let mut changed = 0;
+        for entry in self.entries.values_mut() {
+            let mut moved = false;
+            if let Some(path) = rebase(&entry.original_path) {
+                entry.original_path = path;
+                moved = true;
+            }
+            if let Some(path) = entry.origin.as_deref().and_then(rebase) {
+                entry.origin = Some(path);
+                moved = true;
+            }
+            if moved {
+                changed += 1;
+            }
+        }
+        if self.project_root.as_deref() == Some(old_root) {
+            self.project_root = Some(new_root.to_path_buf());
+        }
+        changed
+    }
+
+    /// Give entries recorded before versions were tracked an origin and a
+    /// version, numbering them in the order they were scrapped
+    fn assign_versions(&mut self) {
+        let mut unversioned: Vec<String> = Vec::new();
+        for entry in self.entries.values_mut() {
+            if entry.origin.is_none() {
+                entry.origin = Some(origin_of(&entry.original_path, &entry.scrapped_name));
+            }
+            if entry.version.is_none() {
+                unversioned.push(entry.scrapped_name.clone());
+            }
+        }
+        unversioned.sort_by_key(|name| self.entries[name].scrapped_at);
+        for name in unversioned {
+            let version = self.next_version(self.entries[&name].origin());
+            if let Some(entry) = self.entries.get_mut(&name) {
+                entry.version = Some(version);
+            }
+        }
     }
 
+    fn next_version(&self, origin: &Path) -> u32 {
+        self.entries.values()
+            .filter(|e| e.origin() == origin)
+            .filter_map(|e| e.version)
+            .max()
+            .unwrap_or(0) + 1
+    }
+
+    /// The versions of the item originally at `origin` (absolute), oldest first
+    pub fn versions(&self, origin: &Path) -> Vec<&ScrapEntry> {
+        let mut versions: Vec<_> = self.entries.values().filter(|e| e.origin() == origin).collect();
+        versions.sort_by_key(|e| (e.version, e.scrapped_at));
+        versions
+    }
+
+    /// Write the metadata atomically (temp file + rename) so a concurrent
+    /// reader never sees a half-written file
     pub fn save(&self, scrap_dir: &Path) -> Result<()> {
-        let metadata_path = scrap_dir.join(".metadata.json");
+        let metadata_path = scrap_dir.join(METADATA_FILE);
+        let tmp_path = scrap_dir.join(format!("{}.tmp", METADATA_FILE));
         let content = serde_json::to_string_pretty(self)
             .context("Failed to serialize metadata")?;
         
-        fs::write(&metadata_path, content)
+        fs::write(&tmp_path, content)
+            .context("Failed to write metadata file")?;
+        fs::rename(&tmp_path, &metadata_path)
             .context("Failed to write metadata file")?;
         
         Ok(())
     }
 
-    pub fn add_entry(&mut self, scrapped_name: &str, original_path: PathBuf) {
+    pub fn add_entry(&mut self, scrapped_name: &str, original_path: PathBuf) -> &mut ScrapEntry {
+        let origin = origin_of(&original_path, scrapped_name);
+        let version = self.next_version(&origin);
         self.entries.insert(
             scrapped_name.to_string(),
             ScrapEntry {
                 original_path,
                 scrapped_at: Utc::now(),
                 scrapped_name: scrapped_name.to_string(),
+                content_hash: None,
+                attributes: None,
+                origin: Some(origin),
+                version: Some(version),
+                compression: None,
+                archived_in: Vec::new(),
             },
         );
+        self.entries.get_mut(scrapped_name).unwrap()
+    }
+
+    /// Add an entry brought in from another scrap folder, numbering it after
+    /// the versions of its origin already here
+    pub fn add_imported_entry(&mut self, mut entry: ScrapEntry) {
+        let origin = entry.origin.clone()
+            .unwrap_or_else(|| origin_of(&entry.original_path, &entry.scrapped_name));
+        entry.version = Some(self.next_version(&origin));
+        entry.origin = Some(origin);
+        self.entries.insert(entry.scrapped_name.clone(), entry);
     }
 
     pub fn remove_entry(&mut self, scrapped_name: &str) -> Option<ScrapEntry> {
@@ -68,4 +323,412 @@ impl ScrapMetadata {
     pub fn get_entry(&self, scrapped_name: &str) -> Option<&ScrapEntry> {
         self.entries.get(scrapped_name)
     }
-}
\ No newline at end of file
+
+    /// Find another entry with the given content hash
+    pub fn find_by_hash(&self, content_hash: &str, exclude: &str) -> Option<&ScrapEntry> {
+        self.entries.values()
+            .filter(|e| e.scrapped_name != exclude)
+            .find(|e| e.content_hash.as_deref() == Some(content_hash))
+    }
+}
+
+/// The entries of a scrap folder as written by `scrap export`, for moving
+/// them to another machine or merging them into another project's scrap
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScrapManifest {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    /// Project the entries were scrapped in
+    #[serde(default)]
+    pub project_root: Option<PathBuf>,
+    /// Oldest first
+    pub entries: Vec<ScrapEntry>,
+}
+
+impl ScrapManifest {
+    pub fn from_metadata(metadata: &ScrapMetadata) -> Self {
+        let mut entries: Vec<ScrapEntry> = metadata.entries.values().cloned().collect();
+        entries.sort_by_key(|e| e.scrapped_at);
+        Self {
+            version: 1,
+            exported_at: Utc::now(),
+            project_root: metadata.project_root.clone(),
+            entries,
+        }
+    }
+}
+
+/// `path` made absolute against the current directory, with `.` and `..`
+/// resolved lexically (the path usually no longer exists, so it can't be
+/// canonicalized)
+pub fn absolute_path(path: &Path) -> PathBuf {
+    let joined = match std::env::current_dir() {
+        Ok(cwd) => cwd.join(path),
+        Err(_) => path.to_path_buf(),
+    };
+    let mut absolute = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                absolute.pop();
+            }
+            component => absolute.push(component),
+        }
+    }
+    absolute
+}
+
+/// The origin of an item scrapped from `original_path`; items without a
+/// recorded path restore into the current directory under their scrapped name
+fn origin_of(original_path: &Path, scrapped_name: &str) -> PathBuf {
+    if original_path.as_os_str().is_empty() {
+        absolute_path(Path::new(scrapped_name))
+    } else {
+        absolute_path(original_path)
+    }
+}
+
+/// Compute the content hash of a file or directory tree. File contents are
+/// streamed through the hasher; symlinks, whether the item itself or inside
+/// it, are hashed by the path they point to rather than followed.
+pub fn hash_path(path: &Path) -> Result<String> {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    let file_type = fs::symlink_metadata(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?
+        .file_type();
+    if file_type.is_dir() {
+        let mut entries: Vec<_> = walkdir::WalkDir::new(path)
+            .min_depth(1)
+            .into_iter()
+            .collect::<std::result::Result<_, _>>()
+            .with_context(|| format!("Failed to walk {}", path.display()))?;
+        entries.sort_by(|a, b| a.path().cmp(b.path()));
+
+        for entry in entries {
+            let relative = entry.path().strip_prefix(path).unwrap_or(entry.path());
+            hasher.update(relative.to_string_lossy().as_bytes());
+            hasher.update([0u8]);
+            if entry.file_type().is_file() {
+                hash_file(entry.path(), &mut hasher)?;
+            } else if entry.file_type().is_symlink() {
+                hash_link(entry.path(), &mut hasher)?;
+            }
+        }
+    } else if file_type.is_symlink() {
+        hash_link(path, &mut hasher)?;
+    } else {
+        hash_file(path, &mut hasher)?;
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+fn hash_file(path: &Path, hasher: &mut sha2::Sha256) -> Result<()> {
+    let mut file = fs::File::open(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    std::io::copy(&mut file, hasher)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(())
+}
+
+fn hash_link(path: &Path, hasher: &mut sha2::Sha256) -> Result<()> {
+    use sha2::Digest;
+
+    let target = fs::read_link(path)
+        .with_context(|| format!("Failed to read link {}", path.display()))?;
+    hasher.update(b"symlink\0");
+    hasher.update(target.to_string_lossy().as_bytes());
+    Ok(())
+}
+
+/// Whether `path` is a regular file, not following a symlink to one; only
+/// those are deduplicated
+pub fn is_regular_file(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_file())
+}
+
+/// Whether a file shares its data with other hard links
+pub fn is_hard_linked(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        fs::symlink_metadata(path).map(|m| m.is_file() && m.nlink() > 1).unwrap_or(false)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        false
+    }
+}
+
+/// Whether two paths refer to the same underlying file
+pub fn is_same_file(a: &Path, b: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        match (fs::metadata(a), fs::metadata(b)) {
+            (Ok(ma), Ok(mb)) => ma.dev() == mb.dev() && ma.ino() == mb.ino(),
+            _ => false,
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (a, b);
+        false
+    }
+}
+
+/// Replace `duplicate` with a hard link to `original`. Returns false if the
+/// filesystem does not support hard links, leaving `duplicate` untouched.
+pub fn link_duplicate(original: &Path, duplicate: &Path) -> Result<bool> {
+    let mut tmp_name = duplicate.as_os_str().to_owned();
+    tmp_name.push(".dedup-tmp");
+    let tmp_path = PathBuf::from(tmp_name);
+
+    if let Err(e) = fs::hard_link(original, &tmp_path) {
+        log::debug!("Hard link {} -> {} failed: {}", original.display(), tmp_path.display(), e);
+        return Ok(false);
+    }
+
+    if let Err(e) = fs::rename(&tmp_path, duplicate) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e).with_context(|| format!("Failed to replace {} with hard link", duplicate.display()));
+    }
+    Ok(true)
+}
+
+/// Compress the file or directory at `path` into `dest` and remove `path`.
+/// Symlinks inside a directory are stored as links, not followed.
+pub fn compress_item(path: &Path, dest: &Path) -> Result<Compression> {
+    let compression = if path.is_dir() { Compression::TarZstd } else { Compression::Zstd };
+    let written = (|| -> Result<()> {
+        let output = fs::File::create(dest)?;
+        match compression {
+            Compression::Zstd => zstd::stream::copy_encode(fs::File::open(path)?, output, COMPRESSION_LEVEL)?,
+            Compression::TarZstd => {
+                let mut builder = tar::Builder::new(zstd::Encoder::new(output, COMPRESSION_LEVEL)?);
+                builder.follow_symlinks(false);
+                builder.append_dir_all(".", path)?;
+                builder.into_inner()?.finish()?;
+            }
+        }
+        Ok(())
+    })();
+    if let Err(e) = written {
+        let _ = fs::remove_file(dest);
+        return Err(e.context(format!("Failed to compress {}", path.display())));
+    }
+
+    // A file that cannot be removed is still whole, so the compressed copy
+    // goes instead. A directory may be partly gone by then, which leaves the
+    // compressed copy as the only complete one.
+    if compression == Compression::TarZstd {
+        if let Err(e) = fs::remove_dir_all(path) {
+            log::warn!("Left part of {} behind after compressing it: {}", path.display(), e);
+        }
+    } else if let Err(e) = fs::remove_file(path) {
+        let _ = fs::remove_file(dest);
+        return Err(e).with_context(|| format!("Failed to remove {} after compressing it", path.display()));
+    }
+    Ok(compression)
+}
+
+/// Expand an item stored by `compress_item` at `path` into `dest` and remove `path`
+pub fn decompress_item(path: &Path, compression: Compression, dest: &Path) -> Result<()> {
+    unpack_item(path, compression, dest)?;
+    fs::remove_file(path)?;
+    Ok(())
+}
+
+/// Expand a compressed item to `dest`, leaving the compressed file in place
+fn unpack_item(path: &Path, compression: Compression, dest: &Path) -> Result<()> {
+    let expanded = (|| -> Result<()> {
+        let input = fs::File::open(path)?;
+        match compression {
+            Compression::Zstd => zstd::stream::copy_decode(input, fs::File::create(dest)?)?,
+            Compression::TarZstd => {
+                let mut archive = tar::Archive::new(zstd::Decoder::new(input)?);
+                archive.set_preserve_permissions(true);
+                archive.unpack(dest)?;
+            }
+        }
+        Ok(())
+    })();
+    if let Err(e) = expanded {
+        let _ = if dest.is_dir() { fs::remove_dir_all(dest) } else { fs::remove_file(dest) };
+        return Err(e.context(format!("Failed to decompress {}", path.display())));
+    }
+    Ok(())
+}
+
+/// Content hash of an item as stored in scrap, comparable with the hash
+/// recorded when it was scrapped: compressed items are expanded to a
+/// temporary location first
+pub fn hash_stored(path: &Path, compression: Option<Compression>) -> Result<String> {
+    let Some(compression) = compression else {
+        return hash_path(path);
+    };
+    let expanded = std::env::temp_dir().join(format!("ws-scrap-verify-{}", std::process::id()));
+    let hash = unpack_item(path, compression, &expanded).and_then(|()| hash_path(&expanded));
+    let _ = if expanded.is_dir() { fs::remove_dir_all(&expanded) } else { fs::remove_file(&expanded) };
+    hash
+}
+
+/// Total size in bytes of a file, or of every file under a directory
+pub fn path_size(path: &Path) -> u64 {
+    walkdir::WalkDir::new(path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.metadata().ok())
+        .filter(|m| m.is_file())
+        .map(|m| m.len())
+        .sum()
+}
+
+/// Parse a human-readable size such as `500`, `10K`, `1.5M` or `2GB` (binary units)
+pub fn parse_size(input: &str) -> Result<u64> {
+    let input = input.trim();
+    let split = input.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+    let number: f64 = number.parse()
+        .with_context(|| format!("Invalid size: {}", input))?;
+
+    let unit = unit.trim().to_ascii_uppercase();
+    let multiplier: u64 = match unit.trim_end_matches("IB").trim_end_matches('B') {
+        "" => 1,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        "T" => 1 << 40,
+        _ => anyhow::bail!("Invalid size unit in '{}' (expected K, M, G or T)", input),
+    };
+
+    Ok((number * multiplier as f64) as u64)
+}
+
+/// Parse an age such as `7` (days), `7d`, `12h` or `2w`
+pub fn parse_age(input: &str) -> Result<chrono::Duration> {
+    let input = input.trim();
+    let split = input.find(|c: char| !c.is_ascii_digit()).unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+    let number: i64 = number.parse()
+        .with_context(|| format!("Invalid age: {}", input))?;
+
+    Ok(match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "d" => chrono::Duration::days(number),
+        "h" => chrono::Duration::hours(number),
+        "w" => chrono::Duration::weeks(number),
+        _ => anyhow::bail!("Invalid age unit in '{}' (expected h, d or w)", input),
+    })
+}
+
+/// Advisory lock serializing read-modify-write cycles on `.metadata.json`
+/// across processes. Held for the duration of an operation and released on
+/// drop, or by the operating system when the holder dies; a scrap waits for
+/// as long as another one runs, however long its hashing takes.
+#[derive(Debug)]
+pub struct MetadataLock {
+    _lock: FileLock,
+}
+
+impl MetadataLock {
+    pub fn acquire(scrap_dir: &Path) -> Result<Self> {
+        let path = scrap_dir.join(METADATA_LOCK_FILE);
+        let lock = FileLock::acquire(&path, |holder| {
+            log::info!("Waiting for scrap metadata lock {} (held by process {})", path.display(), holder);
+        })?;
+        Ok(Self { _lock: lock })
+    }
+}
+
+/// Kind of operation recorded in the scrap history log
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryOperation {
+    Scrap,
+    Unscrap,
+    Clean,
+    Purge,
+    /// Items moved into a monthly archive under `.scrap/archives`
+    Tier,
+}
+
+impl std::fmt::Display for HistoryOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            HistoryOperation::Scrap => "scrap",
+            HistoryOperation::Unscrap => "unscrap",
+            HistoryOperation::Clean => "clean",
+            HistoryOperation::Purge => "purge",
+            HistoryOperation::Tier => "tier",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// An item affected by a recorded operation
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HistoryItem {
+    pub scrapped_name: String,
+    pub path: PathBuf,
+}
+
+/// A single line of `.scrap/.history.jsonl`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HistoryRecord {
+    pub timestamp: DateTime<Utc>,
+    pub operation: HistoryOperation,
+    pub items: Vec<HistoryItem>,
+}
+
+impl HistoryRecord {
+    pub fn new(operation: HistoryOperation, items: Vec<HistoryItem>) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            operation,
+            items,
+        }
+    }
+
+    /// Append this record as one JSON line to the scrap history log
+    pub fn append(&self, scrap_dir: &Path) -> Result<()> {
+        let history_path = scrap_dir.join(HISTORY_FILE);
+        let line = serde_json::to_string(self)
+            .context("Failed to serialize history record")?;
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&history_path)
+            .context("Failed to open history file")?;
+        writeln!(file, "{}", line)
+            .context("Failed to write history file")?;
+
+        Ok(())
+    }
+}
+
+/// Load all records from the scrap history log, oldest first.
+/// Malformed lines are skipped so a partially written entry never hides the rest.
+pub fn load_history(scrap_dir: &Path) -> Result<Vec<HistoryRecord>> {
+    let history_path = scrap_dir.join(HISTORY_FILE);
+    if !history_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&history_path)
+        .context("Failed to read history file")?;
+
+    let mut records = Vec::new();
+    for line in content.lines().filter(|l| !l.trim().is_empty()) {
+        match serde_json::from_str::<HistoryRecord>(line) {
+            Ok(record) => records.push(record),
+            Err(e) => log::warn!("Skipping malformed history entry: {}", e),
+        }
+    }
+
+    Ok(records)
+}
@@ -0,0 +1,109 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// Where `.scrap/` gets ignored when a scrap folder is created
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GitignoreMode {
+    /// Repository root `.gitignore` inside a git repository, otherwise the local one
+    #[default]
+    Auto,
+    /// `.gitignore` in the directory holding `.scrap`
+    Local,
+    /// `.gitignore` at the repository root
+    Repo,
+    /// `.git/info/exclude`, which is never committed
+    Exclude,
+    /// Leave ignore files alone
+    Off,
+}
+
+impl std::str::FromStr for GitignoreMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "auto" => Ok(GitignoreMode::Auto),
+            "local" => Ok(GitignoreMode::Local),
+            "repo" => Ok(GitignoreMode::Repo),
+            "exclude" => Ok(GitignoreMode::Exclude),
+            "off" => Ok(GitignoreMode::Off),
+            _ => anyhow::bail!("Unknown gitignore mode: {} (expected auto, local, repo, exclude or off)", s),
+        }
+    }
+}
+
+impl std::fmt::Display for GitignoreMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            GitignoreMode::Auto => "auto",
+            GitignoreMode::Local => "local",
+            GitignoreMode::Repo => "repo",
+            GitignoreMode::Exclude => "exclude",
+            GitignoreMode::Off => "off",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// User-level scrap settings, stored in `$XDG_CONFIG_HOME/ws/scrap.json`
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ScrapConfig {
+    #[serde(default)]
+    pub gitignore: GitignoreMode,
+}
+
+impl ScrapConfig {
+    pub fn path() -> PathBuf {
+        // Check XDG_CONFIG_HOME first, fallback to ~/.config
+        let config_home = std::env::var("XDG_CONFIG_HOME").unwrap_or_else(|_| {
+            let home = std::env::var("HOME").unwrap_or_default();
+            format!("{}/.config", home)
+        });
+        PathBuf::from(config_home).join("ws").join("scrap.json")
+    }
+
+    pub fn load() -> Result<Self> {
+        let path = Self::path();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let content = serde_json::to_string_pretty(self)
+            .context("Failed to serialize scrap config")?;
+        fs::write(&path, content)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Print one setting, or all of them when `key` is `None`
+    pub fn show(&self, key: Option<&str>) -> Result<()> {
+        match key {
+            None => println!("gitignore = {}", self.gitignore),
+            Some("gitignore") => println!("{}", self.gitignore),
+            Some(other) => anyhow::bail!("Unknown scrap config key: {}", other),
+        }
+        Ok(())
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        match key {
+            "gitignore" => self.gitignore = value.parse()?,
+            other => anyhow::bail!("Unknown scrap config key: {}", other),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,220 @@
+//! Versions for build scripts and other tools, computed the way `ws`
+//! computes them:
+//!
+//! ```no_run
+//! use workspace::st8::api::{Level, Repo};
+//!
+//! let repo = Repo::open(".")?;
+//! println!("{}", repo.version()?.full_version);
+//! repo.bump(Level::Minor)?;
+//! repo.write_project_files()?;
+//! # Ok::<(), workspace::st8::api::VersionError>(())
+//! ```
+
+use sqlx::Row;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub use super::conventional::{BumpLevel as Level, SemVer};
+pub use super::st8_common::VersionInfo;
+
+use super::git::{with_root, GitError, GitRepo};
+use super::history::{apply_version, with_pool, ChangeSource};
+use super::preview::preview_version_update;
+use super::st8_common::{canonical_version, current_version, St8Config};
+
+/// Why a version could not be read or written
+#[derive(Debug)]
+pub enum VersionError {
+    NotARepository,
+    /// The repository exists but HEAD does not point at a commit yet
+    NoCommits,
+    /// The settings in `.ws/project.db` or `.ws/version.*` could not be loaded
+    Config(String),
+    /// There is no version file and no release tag to read a version from
+    NoVersion,
+    Io(std::io::Error),
+    Other(String),
+}
+
+impl fmt::Display for VersionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VersionError::NotARepository => write!(f, "Not in a git repository"),
+            VersionError::NoCommits => write!(f, "The repository has no commits yet"),
+            VersionError::Config(message) => write!(f, "Invalid version settings: {}", message),
+            VersionError::NoVersion => write!(f, "No version yet: the version file is missing and there are no release tags"),
+            VersionError::Io(e) => write!(f, "{}", e),
+            VersionError::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for VersionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            VersionError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<GitError> for VersionError {
+    fn from(e: GitError) -> Self {
+        match e {
+            GitError::NotARepository => VersionError::NotARepository,
+            GitError::NoCommits => VersionError::NoCommits,
+            other => VersionError::Other(other.to_string()),
+        }
+    }
+}
+
+impl From<std::io::Error> for VersionError {
+    fn from(e: std::io::Error) -> Self {
+        VersionError::Io(e)
+    }
+}
+
+impl From<anyhow::Error> for VersionError {
+    fn from(e: anyhow::Error) -> Self {
+        match e.downcast::<GitError>() {
+            Ok(git_error) => git_error.into(),
+            Err(e) => match e.downcast::<std::io::Error>() {
+                Ok(io_error) => io_error.into(),
+                Err(e) => VersionError::Other(format!("{:#}", e)),
+            },
+        }
+    }
+}
+
+pub type VersionResult<T> = std::result::Result<T, VersionError>;
+
+/// A repository versioned with its `ws` settings
+#[derive(Debug, Clone)]
+pub struct Repo {
+    root: PathBuf,
+    config: St8Config,
+}
+
+impl Repo {
+    /// The repository containing `path`, with its settings loaded (creating
+    /// `.ws/project.db` with the defaults when there is none). Must not be
+    /// called from within an async runtime.
+    pub fn open(path: impl AsRef<Path>) -> VersionResult<Self> {
+        let root = GitRepo::discover_from(path.as_ref())?.root().to_path_buf();
+        fs::create_dir_all(root.join(".ws"))?;
+        let mut config = St8Config::load(&root).map_err(|e| VersionError::Config(format!("{:#}", e)))?;
+        config.version_file = root.join(&config.version_file).to_string_lossy().to_string();
+        Ok(Self { root, config })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn config(&self) -> &St8Config {
+        &self.config
+    }
+
+    /// The version `ws update` would write now, by the configured strategy
+    /// and format
+    pub fn version(&self) -> VersionResult<VersionInfo> {
+        let major = self.major_version()?;
+        self.in_repo(|| VersionInfo::calculate_for(&self.config, major))
+    }
+
+    /// Bump the version last written (or the last release tag) by `level`
+    /// and write it to the version and project files, like `ws version bump`
+    pub fn bump(&self, level: Level) -> VersionResult<SemVer> {
+        self.in_repo(|| {
+            let next = current_version(&self.config)?.bump(level);
+            apply_version(&self.root, &VersionInfo::from_semver(&next), &self.config, ChangeSource::Bump)?;
+            Ok(next)
+        })
+    }
+
+    /// Write the version in the version file (or the last release tag) to
+    /// every project file that differs, like `ws version sync` but without
+    /// staging; returns the files written
+    pub fn write_project_files(&self) -> VersionResult<Vec<PathBuf>> {
+        self.in_repo(|| {
+            let Some(version) = canonical_version(&self.config)? else {
+                return Err(VersionError::NoVersion.into());
+            };
+            let mut written = Vec::new();
+            for change in preview_version_update(&VersionInfo::from_version(&version), &self.config)? {
+                fs::write(&change.path, &change.after)?;
+                written.push(change.path);
+            }
+            Ok(written)
+        })
+    }
+
+    fn major_version(&self) -> VersionResult<u32> {
+        let major = with_pool(&self.root, |pool| async move {
+            let row = sqlx::query("SELECT major_version FROM projects LIMIT 1").fetch_optional(&pool).await?;
+            Ok(row.map_or(0, |row| row.get::<i64, _>("major_version") as u32))
+        })?;
+        Ok(major)
+    }
+
+    /// Run `run` against this repository; the version file is already
+    /// absolute, and the engine finds the repository and its project files
+    /// through `with_root`, leaving the working directory alone
+    fn in_repo<T>(&self, run: impl FnOnce() -> anyhow::Result<T>) -> VersionResult<T> {
+        with_root(&self.root, run).map_err(|e| match e.downcast::<VersionError>() {
+            Ok(version_error) => version_error,
+            Err(e) => e.into(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::st8::git::test_repo::{git, init_repo};
+
+    #[test]
+    fn test_repo_api() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        assert!(matches!(Repo::open(dir), Err(VersionError::NotARepository)));
+
+        init_repo(dir);
+        fs::write(dir.join("Cargo.toml"), "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n").unwrap();
+        git(dir, &["add", "Cargo.toml"]);
+        git(dir, &["commit", "-q", "-m", "first"]);
+        git(dir, &["tag", "v1.2.0"]);
+
+        let repo = Repo::open(dir).unwrap();
+        assert!(repo.version().is_ok());
+        assert_eq!(repo.bump(Level::Minor).unwrap(), SemVer::new(1, 3, 0));
+        assert_eq!(fs::read_to_string(dir.join("version.txt")).unwrap().trim(), "1.3.0");
+        assert!(fs::read_to_string(dir.join("Cargo.toml")).unwrap().contains("version = \"1.3.0\""));
+
+        // A merge that left a project file behind
+        fs::write(dir.join("Cargo.toml"), "[package]\nname = \"demo\"\nversion = \"1.1.0\"\n").unwrap();
+        let written = repo.write_project_files().unwrap();
+        assert_eq!(written.len(), 1);
+        assert!(written[0].ends_with("Cargo.toml"));
+        assert!(repo.write_project_files().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_repo_api_keeps_working_directory() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        init_repo(dir);
+        fs::write(dir.join("README.md"), "demo\n").unwrap();
+        git(dir, &["add", "README.md"]);
+        git(dir, &["commit", "-q", "-m", "first"]);
+        git(dir, &["tag", "v2.0.0"]);
+
+        let cwd = std::env::current_dir().unwrap();
+        let repo = Repo::open(dir).unwrap();
+        assert_eq!(repo.bump(Level::Patch).unwrap(), SemVer::new(2, 0, 1));
+        assert_eq!(std::env::current_dir().unwrap(), cwd);
+        assert_eq!(fs::read_to_string(dir.join("version.txt")).unwrap().trim(), "2.0.1");
+    }
+}
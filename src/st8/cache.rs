@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use super::git::{GitRepo, GitResult};
+
+/// Cache file kept in the git directory, so it is never committed
+pub const CACHE_FILE: &str = "ws-version-cache";
+
+/// Key of the count over the whole history
+const ALL_HISTORY: &str = "all";
+
+/// Cumulative line changes up to a commit, per starting point
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ChangeCache {
+    /// Keyed by the commit id counting starts after, or `all`
+    entries: HashMap<String, CachedCount>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedCount {
+    /// Last commit counted
+    head: String,
+    total: u32,
+}
+
+/// `GitRepo::line_changes`, summing only the commits added since the last
+/// call instead of walking the whole history on every commit. Falls back to
+/// a full count when history was rewritten (rebase, reset) or the starting
+/// tag moved.
+pub fn line_changes(repo: &GitRepo, since: Option<&str>) -> GitResult<u32> {
+    let head = repo.head_commit()?;
+    let key = match since {
+        Some(since) => repo.resolve_commit(since)?,
+        None => ALL_HISTORY.to_string(),
+    };
+    let Ok(path) = cache_path(repo) else { return repo.line_changes(since) };
+    let mut cache = load(&path);
+
+    let total = match cache.entries.get(&key) {
+        Some(cached) if cached.head == head => return Ok(cached.total),
+        Some(cached) if repo.is_ancestor(&cached.head, &head).unwrap_or(false) => {
+            log::debug!("Counting line changes since cached commit {}", cached.head);
+            cached.total.saturating_add(repo.line_changes(Some(&cached.head))?)
+        }
+        _ => repo.line_changes(since)?,
+    };
+
+    cache.entries.insert(key, CachedCount { head, total });
+    save(&path, &cache);
+    Ok(total)
+}
+
+fn cache_path(repo: &GitRepo) -> GitResult<PathBuf> {
+    Ok(repo.git_dir()?.join(CACHE_FILE))
+}
+
+/// A missing or unreadable cache is an empty one
+fn load(path: &std::path::Path) -> ChangeCache {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Write through a temporary file so concurrent readers never see half a
+/// cache; failing to cache only costs time
+fn save(path: &std::path::Path, cache: &ChangeCache) {
+    let Ok(content) = serde_json::to_string(cache) else { return };
+    let temporary = path.with_extension(format!("tmp{}", std::process::id()));
+    if fs::write(&temporary, content).and_then(|_| fs::rename(&temporary, path)).is_err() {
+        log::debug!("Failed to write line change cache {}", path.display());
+        let _ = fs::remove_file(&temporary);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::st8::git::test_repo::{git, init_repo};
+
+    #[test]
+    fn test_cached_counts_match_full_counts() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        init_repo(dir);
+        fs::write(dir.join("a.txt"), "one\ntwo\n").unwrap();
+        git(dir, &["add", "."]);
+        git(dir, &["commit", "-q", "-m", "initial"]);
+        git(dir, &["tag", "v1.0.0"]);
+
+        let repo = GitRepo::discover_from(dir).unwrap();
+        assert_eq!(line_changes(&repo, None).unwrap(), repo.line_changes(None).unwrap());
+        assert!(repo.git_dir().unwrap().join(CACHE_FILE).exists());
+
+        // New commits are added to the cached totals
+        fs::write(dir.join("a.txt"), "one\nthree\nfour\n").unwrap();
+        git(dir, &["commit", "-q", "-am", "more"]);
+        assert_eq!(line_changes(&repo, None).unwrap(), repo.line_changes(None).unwrap());
+        assert_eq!(line_changes(&repo, Some("v1.0.0")).unwrap(), repo.line_changes(Some("v1.0.0")).unwrap());
+
+        // Rewritten history is counted again from scratch
+        git(dir, &["reset", "-q", "--hard", "v1.0.0"]);
+        assert_eq!(line_changes(&repo, None).unwrap(), 2);
+    }
+}
@@ -0,0 +1,299 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tera::{Context as TeraContext, Tera};
+
+use super::conventional::{commit_messages_since, find_last_semver_tag, ConventionalCommit};
+use super::tags::TagScheme;
+
+/// Heading used for changes that are not part of a release yet
+pub const UNRELEASED: &str = "Unreleased";
+
+const CHANGELOG_HEADER: &str = "# Changelog\n\n\
+All notable changes to this project will be documented in this file.\n\n\
+The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.1.0/),\n\
+and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).\n";
+
+const DEFAULT_TEMPLATE: &str = "## [{{ version }}]{% if date %} - {{ date }}{% endif %}\n\
+{% for group in groups %}\n### {{ group.title }}\n\n\
+{% for entry in group.entries %}- {% if entry.breaking %}**BREAKING:** {% endif %}\
+{% if entry.scope %}**{{ entry.scope }}:** {% endif %}{{ entry.description }} ({{ entry.hash }})\n\
+{% endfor %}{% endfor %}";
+
+/// Changelog settings, stored with the rest of the st8 configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChangelogConfig {
+    /// Regenerate the changelog on `ws update` and `ws version bump`
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_changelog_path")]
+    pub path: String,
+    /// Tera template for one release section, instead of the built-in one
+    #[serde(default)]
+    pub template: Option<String>,
+    /// Regexes matched against commit headers; matching commits are left out
+    #[serde(default = "default_exclude_patterns")]
+    pub exclude: Vec<String>,
+}
+
+fn default_changelog_path() -> String {
+    "CHANGELOG.md".to_string()
+}
+
+fn default_exclude_patterns() -> Vec<String> {
+    vec![r"^chore\(release\)".to_string()]
+}
+
+impl Default for ChangelogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: default_changelog_path(),
+            template: None,
+            exclude: default_exclude_patterns(),
+        }
+    }
+}
+
+impl ChangelogConfig {
+    pub const KEYS: [&'static str; 4] = ["enabled", "path", "template", "exclude"];
+
+    /// The value of one setting, as `show` prints it
+    pub fn get(&self, key: &str) -> Result<String> {
+        let value = match key {
+            "enabled" => self.enabled.to_string(),
+            "path" => self.path.clone(),
+            "template" => self.template.as_deref().unwrap_or("(built-in)").to_string(),
+            "exclude" => self.exclude.join(","),
+            other => anyhow::bail!("Unknown changelog config key: {}", other),
+        };
+        Ok(value)
+    }
+
+    /// Print one setting, or all of them when `key` is `None`
+    pub fn show(&self, key: Option<&str>) -> Result<()> {
+        match key {
+            None => {
+                for key in Self::KEYS {
+                    println!("{} = {}", key, self.get(key)?);
+                }
+            }
+            Some(key) => println!("{}", self.get(key)?),
+        }
+        Ok(())
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        match key {
+            "enabled" => self.enabled = value.parse()
+                .with_context(|| format!("Expected true or false, got {}", value))?,
+            "path" => self.path = value.to_string(),
+            "template" => self.template = Some(value.to_string()).filter(|v| !v.is_empty()),
+            "exclude" => {
+                let patterns: Vec<String> = value.split(',')
+                    .map(|p| p.trim().to_string())
+                    .filter(|p| !p.is_empty())
+                    .collect();
+                for pattern in &patterns {
+                    Regex::new(pattern).with_context(|| format!("Invalid exclude pattern: {}", pattern))?;
+                }
+                self.exclude = patterns;
+            }
+            other => anyhow::bail!("Unknown changelog config key: {}", other),
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ChangelogEntry {
+    scope: Option<String>,
+    description: String,
+    hash: String,
+    breaking: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct ChangelogGroup {
+    title: &'static str,
+    entries: Vec<ChangelogEntry>,
+}
+
+/// Keep-a-Changelog section for a commit type; `None` for commits that do
+/// not belong in a changelog (docs, chore, ci, ...)
+fn group_title(commit: &ConventionalCommit) -> Option<&'static str> {
+    match commit.kind.as_str() {
+        "feat" => Some("Added"),
+        "fix" => Some("Fixed"),
+        "revert" => Some("Removed"),
+        "security" => Some("Security"),
+        "deprecate" => Some("Deprecated"),
+        "perf" | "refactor" => Some("Changed"),
+        _ if commit.breaking => Some("Changed"),
+        _ => None,
+    }
+}
+
+const GROUP_ORDER: [&str; 6] = ["Added", "Changed", "Deprecated", "Removed", "Fixed", "Security"];
+
+/// Render the section for `version` from (hash, message) pairs, newest first
+pub fn render_section(config: &ChangelogConfig, version: &str, date: Option<&str>, commits: &[(String, String)]) -> Result<String> {
+    let exclude = config.exclude.iter()
+        .map(|p| Regex::new(p).with_context(|| format!("Invalid exclude pattern: {}", p)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut groups: Vec<ChangelogGroup> = GROUP_ORDER.iter()
+        .map(|title| ChangelogGroup { title, entries: Vec::new() })
+        .collect();
+
+    for (hash, message) in commits {
+        let header = message.lines().next().unwrap_or_default();
+        if exclude.iter().any(|re| re.is_match(header)) {
+            continue;
+        }
+        let Some(commit) = ConventionalCommit::parse(hash, message) else { continue };
+        let Some(title) = group_title(&commit) else { continue };
+        if let Some(group) = groups.iter_mut().find(|g| g.title == title) {
+            group.entries.push(ChangelogEntry {
+                scope: commit.scope.clone(),
+                description: commit.description.clone(),
+                hash: hash[..hash.len().min(7)].to_string(),
+                breaking: commit.breaking,
+            });
+        }
+    }
+
+    groups.retain(|g| !g.entries.is_empty());
+    for group in &mut groups {
+        // Breaking changes first, then grouped by scope; stable so history order is kept
+        group.entries.sort_by(|a, b| b.breaking.cmp(&a.breaking).then_with(|| a.scope.cmp(&b.scope)));
+    }
+
+    let template = match &config.template {
+        Some(path) => fs::read_to_string(path)
+            .with_context(|| format!("Failed to read changelog template {}", path))?,
+        None => DEFAULT_TEMPLATE.to_string(),
+    };
+
+    let mut context = TeraContext::new();
+    context.insert("version", version);
+    context.insert("date", &date);
+    context.insert("groups", &groups);
+    Tera::one_off(&template, &context, false).context("Failed to render changelog template")
+}
+
+/// Replace the `version` section (and any Unreleased section) of `content`
+/// with `section`, keeping it above older releases
+pub fn insert_section(content: &str, version: &str, section: &str) -> String {
+    let content = if content.trim().is_empty() { CHANGELOG_HEADER } else { content };
+    let replaced = [format!("## [{}]", version), format!("## [{}]", UNRELEASED)];
+
+    let mut preamble = String::new();
+    let mut releases = String::new();
+    let mut skipping = false;
+    let mut in_releases = false;
+    for line in content.lines() {
+        if line.starts_with("## ") {
+            in_releases = true;
+            skipping = replaced.iter().any(|heading| line.starts_with(heading.as_str()));
+        }
+        if skipping {
+            continue;
+        }
+        let target = if in_releases { &mut releases } else { &mut preamble };
+        target.push_str(line);
+        target.push('\n');
+    }
+
+    let mut result = format!("{}\n\n{}\n", preamble.trim_end(), section.trim_end());
+    if !releases.is_empty() {
+        result.push('\n');
+        result.push_str(releases.trim_end());
+        result.push('\n');
+    }
+    result
+}
+
+/// The section for `version` (dated) or Unreleased, covering the commits
+/// since the last release tag named by `tags`
+pub fn section_since_last_release(config: &ChangelogConfig, tags: &TagScheme, version: Option<&str>) -> Result<String> {
+    let commits = commit_messages_since(find_last_semver_tag(tags)?.as_deref())?;
+    let date = version.map(|_| chrono::Local::now().format("%Y-%m-%d").to_string());
+    render_section(config, version.unwrap_or(UNRELEASED), date.as_deref(), &commits)
+}
+
+/// Write the section for `version` (or Unreleased) into the changelog,
+/// returning its path
+pub fn update_changelog(config: &ChangelogConfig, tags: &TagScheme, repo_root: &Path, version: Option<&str>) -> Result<PathBuf> {
+    let (path, content) = changelog_content(config, tags, repo_root, version)?;
+    fs::write(&path, content)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// The changelog path and what `update_changelog` would write to it
+pub fn changelog_content(config: &ChangelogConfig, tags: &TagScheme, repo_root: &Path, version: Option<&str>) -> Result<(PathBuf, String)> {
+    let section = section_since_last_release(config, tags, version)?;
+
+    let path = repo_root.join(&config.path);
+    let existing = fs::read_to_string(&path).unwrap_or_default();
+    let content = insert_section(&existing, version.unwrap_or(UNRELEASED), &section);
+    Ok((path, content))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commits() -> Vec<(String, String)> {
+        [
+            ("aaaaaaa1", "fix(parser): handle empty input"),
+            ("bbbbbbb2", "docs: explain flags"),
+            ("ccccccc3", "feat(cli): add --json"),
+            ("ddddddd4", "chore(release): 1.2.0"),
+            ("eeeeeee5", "feat!: drop legacy config"),
+        ]
+        .iter()
+        .map(|(h, m)| (h.to_string(), m.to_string()))
+        .collect()
+    }
+
+    #[test]
+    fn test_render_section_groups_by_type() {
+        let section = render_section(&ChangelogConfig::default(), "1.3.0", Some("2024-05-01"), &commits()).unwrap();
+        assert!(section.starts_with("## [1.3.0] - 2024-05-01"));
+        let added = section.find("### Added").unwrap();
+        let fixed = section.find("### Fixed").unwrap();
+        assert!(added < fixed);
+        assert!(section.contains("- **BREAKING:** drop legacy config (eeeeeee)"));
+        assert!(section.contains("- **cli:** add --json (ccccccc)"));
+        assert!(section.contains("- **parser:** handle empty input (aaaaaaa)"));
+        assert!(!section.contains("explain flags"));
+        assert!(!section.contains("chore(release)"));
+    }
+
+    #[test]
+    fn test_exclude_patterns() {
+        let config = ChangelogConfig { exclude: vec!["--json".to_string()], ..Default::default() };
+        let section = render_section(&config, UNRELEASED, None, &commits()).unwrap();
+        assert!(section.starts_with("## [Unreleased]\n"));
+        assert!(!section.contains("--json"));
+    }
+
+    #[test]
+    fn test_insert_section_replaces_unreleased() {
+        let initial = insert_section("", UNRELEASED, "## [Unreleased]\n\n### Added\n\n- one (a)\n");
+        assert!(initial.starts_with("# Changelog"));
+
+        let released = insert_section(&initial, "1.0.0", "## [1.0.0] - 2024-05-01\n\n### Added\n\n- one (a)\n");
+        assert!(!released.contains("[Unreleased]"));
+
+        let next = insert_section(&released, UNRELEASED, "## [Unreleased]\n\n### Fixed\n\n- two (b)\n");
+        let unreleased = next.find("## [Unreleased]").unwrap();
+        let previous = next.find("## [1.0.0]").unwrap();
+        assert!(unreleased < previous);
+        assert_eq!(next.matches("# Changelog").count(), 1);
+    }
+}
@@ -0,0 +1,121 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::path::Path;
+
+use super::conventional::{find_last_semver_tag, BumpLevel, SemVer};
+use super::git::read_history;
+use super::history::{apply_version, ChangeSource};
+use super::st8_common::{St8Config, VersionInfo};
+use super::tags::TagScheme;
+
+/// A prerelease train released from the same repository as the stable
+/// versions, e.g. `beta` tagged `v1.3.0-beta.2`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChannelConfig {
+    pub name: String,
+    /// Prerelease label of the channel's versions; the name when unset
+    #[serde(default)]
+    pub label: Option<String>,
+    /// File holding the channel's version; `version-<name>.txt` when unset
+    #[serde(default)]
+    pub version_file: Option<String>,
+}
+
+impl ChannelConfig {
+    pub fn new(name: &str) -> Self {
+        Self { name: name.to_string(), label: None, version_file: None }
+    }
+
+    pub fn label(&self) -> &str {
+        self.label.as_deref().unwrap_or(&self.name)
+    }
+
+    pub fn version_file(&self) -> String {
+        self.version_file.clone().unwrap_or_else(|| format!("version-{}.txt", self.name))
+    }
+
+    /// Glob of the channel's tags, e.g. `v*-beta*`
+    pub fn tag_pattern(&self, tag_prefix: &str) -> String {
+        format!("{}*-{}*", tag_prefix, self.label())
+    }
+
+    pub fn tag_scheme(&self, config: &St8Config) -> TagScheme {
+        TagScheme::new(&config.tag_prefix, Some(&self.tag_pattern(&config.tag_prefix)))
+    }
+}
+
+/// The configured channel called `name`
+pub fn find_channel<'a>(config: &'a St8Config, name: &str) -> Result<&'a ChannelConfig> {
+    config.channels.iter().find(|channel| channel.name == name).ok_or_else(|| {
+        let names: Vec<&str> = config.channels.iter().map(|channel| channel.name.as_str()).collect();
+        anyhow::anyhow!("No channel named {} (configured: {})", name, if names.is_empty() { "none".to_string() } else { names.join(", ") })
+    })
+}
+
+/// A channel's next version and the tag it continues from
+#[derive(Debug, Clone)]
+pub struct ChannelVersion {
+    pub version: SemVer,
+    pub last_tag: Option<String>,
+}
+
+/// The channel's next version, relative to its latest tag: the next
+/// prerelease number of that tag's version while the stable train has not
+/// moved past it, otherwise the first prerelease of the next stable version
+/// (`stable`), e.g. `1.4.0-beta.1`
+pub fn channel_version(config: &St8Config, channel: &ChannelConfig, stable: &SemVer) -> Result<ChannelVersion> {
+    let tags = channel.tag_scheme(config);
+    let last_tag = read_history(None, |repo| {
+        Ok(repo.tags_reachable_from_head()?
+            .into_iter()
+            .filter_map(|tag| tags.version(&tag).map(|version| (tag, version)))
+            .max_by(|a, b| a.1.cmp_precedence(&b.1)))
+    })?;
+
+    // A stable version that is already released cannot get prereleases
+    let mut base = SemVer::new(stable.major, stable.minor, stable.patch);
+    let stable_tags = config.tag_scheme();
+    if let Some(released) = find_last_semver_tag(&stable_tags)?.and_then(|tag| stable_tags.version(&tag)) {
+        if released.cmp_precedence(&base) != Ordering::Less {
+            base = released.bump(BumpLevel::Patch);
+        }
+    }
+
+    let version = match &last_tag {
+        Some((tag, last)) if SemVer::new(last.major, last.minor, last.patch).cmp_precedence(&base) != Ordering::Less => {
+            let commits = read_history(0, |repo| repo.commit_count(Some(tag)))?;
+            if commits == 0 { last.clone() } else { last.prerelease(channel.label()) }
+        }
+        _ => SemVer { pre: Some(format!("{}.1", channel.label())), ..base },
+    };
+    Ok(ChannelVersion { version, last_tag: last_tag.map(|(tag, _)| tag) })
+}
+
+/// Write `version` to the channel's version file only; project files carry
+/// the stable version
+pub fn write_channel_version(repo_root: &Path, config: &St8Config, channel: &ChannelConfig, version: &SemVer) -> Result<bool> {
+    let mut channel_config = config.clone();
+    channel_config.version_file = channel.version_file();
+    channel_config.auto_detect_project_files = false;
+    channel_config.project_files.clear();
+    apply_version(repo_root, &VersionInfo::from_semver(version), &channel_config, ChangeSource::Update)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_channel_defaults() {
+        let beta = ChannelConfig::new("beta");
+        assert_eq!(beta.label(), "beta");
+        assert_eq!(beta.version_file(), "version-beta.txt");
+        assert_eq!(beta.tag_pattern("v"), "v*-beta*");
+
+        let config = St8Config::default();
+        let scheme = beta.tag_scheme(&config);
+        assert_eq!(scheme.version("v1.3.0-beta.2"), SemVer::parse("1.3.0-beta.2"));
+        assert_eq!(scheme.version("v1.3.0"), None);
+    }
+}
@@ -0,0 +1,153 @@
+use anyhow::{Context, Result};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use super::conventional::SemVer;
+
+/// Dotenv file written on GitLab, to be declared as an `artifacts:reports:dotenv`
+pub const GITLAB_DOTENV: &str = "version.env";
+
+/// The CI service running the current job
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CiProvider {
+    GitHub,
+    GitLab,
+    /// Not running in a known CI, only an explicit env file is written
+    None,
+}
+
+impl CiProvider {
+    /// Tell the provider from the variables it sets in every job
+    pub fn detect() -> Self {
+        let is_set = |name: &str| std::env::var(name).is_ok_and(|v| v == "true");
+        if is_set("GITHUB_ACTIONS") {
+            CiProvider::GitHub
+        } else if is_set("GITLAB_CI") {
+            CiProvider::GitLab
+        } else {
+            CiProvider::None
+        }
+    }
+}
+
+impl std::str::FromStr for CiProvider {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "github" => Ok(CiProvider::GitHub),
+            "gitlab" => Ok(CiProvider::GitLab),
+            "none" => Ok(CiProvider::None),
+            _ => anyhow::bail!("Unknown CI provider: {} (expected github, gitlab or none)", s),
+        }
+    }
+}
+
+impl std::fmt::Display for CiProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            CiProvider::GitHub => "github",
+            CiProvider::GitLab => "gitlab",
+            CiProvider::None => "none",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// `VERSION` and, when it reads as a semantic version, its components
+pub fn version_variables(version: &str) -> Vec<(String, String)> {
+    let mut variables = vec![("VERSION".to_string(), version.to_string())];
+    if let Some(semver) = SemVer::parse(version) {
+        variables.push(("VERSION_MAJOR".to_string(), semver.major.to_string()));
+        variables.push(("VERSION_MINOR".to_string(), semver.minor.to_string()));
+        variables.push(("VERSION_PATCH".to_string(), semver.patch.to_string()));
+        variables.push(("VERSION_PRERELEASE".to_string(), semver.pre.unwrap_or_default()));
+        variables.push(("VERSION_BUILD".to_string(), semver.build.unwrap_or_default()));
+    }
+    variables
+}
+
+/// Hand `variables` to the CI: step outputs (lowercase names) and job
+/// environment on GitHub, a dotenv artifact on GitLab, plus `env_file`
+/// when given. Returns the files written.
+pub fn write_ci_outputs(provider: CiProvider, variables: &[(String, String)], env_file: Option<&Path>) -> Result<Vec<PathBuf>> {
+    let mut written = Vec::new();
+    match provider {
+        CiProvider::GitHub => {
+            let output = github_file("GITHUB_OUTPUT")?;
+            let lowercase: Vec<(String, String)> = variables.iter()
+                .map(|(name, value)| (name.to_lowercase(), value.clone()))
+                .collect();
+            append_dotenv(&output, &lowercase)?;
+            written.push(output);
+
+            let env = github_file("GITHUB_ENV")?;
+            append_dotenv(&env, variables)?;
+            written.push(env);
+        }
+        CiProvider::GitLab => {
+            let dotenv = PathBuf::from(GITLAB_DOTENV);
+            write_dotenv(&dotenv, variables)?;
+            written.push(dotenv);
+        }
+        CiProvider::None => {}
+    }
+
+    if let Some(env_file) = env_file {
+        write_dotenv(env_file, variables)?;
+        written.push(env_file.to_path_buf());
+    }
+    Ok(written)
+}
+
+fn github_file(variable: &str) -> Result<PathBuf> {
+    std::env::var(variable)
+        .map(PathBuf::from)
+        .with_context(|| format!("{} is not set; is this a GitHub Actions step?", variable))
+}
+
+fn dotenv(variables: &[(String, String)]) -> String {
+    variables.iter().map(|(name, value)| format!("{}={}\n", name, value)).collect()
+}
+
+/// GitHub reads these files after the step, so add to what other steps wrote
+fn append_dotenv(path: &Path, variables: &[(String, String)]) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    file.write_all(dotenv(variables).as_bytes())
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn write_dotenv(path: &Path, variables: &[(String, String)]) -> Result<()> {
+    std::fs::write(path, dotenv(variables)).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_variables() {
+        let variables = version_variables("1.4.0-rc.2+abc1234");
+        assert_eq!(variables[0], ("VERSION".to_string(), "1.4.0-rc.2+abc1234".to_string()));
+        assert!(variables.contains(&("VERSION_MINOR".to_string(), "4".to_string())));
+        assert!(variables.contains(&("VERSION_PRERELEASE".to_string(), "rc.2".to_string())));
+
+        // Custom formats that are not semantic versions only get VERSION
+        assert_eq!(version_variables("build-42").len(), 1);
+    }
+
+    #[test]
+    fn test_env_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let env_file = temp_dir.path().join("version.env");
+        let written = write_ci_outputs(CiProvider::None, &version_variables("2.0.1"), Some(&env_file)).unwrap();
+        assert_eq!(written, vec![env_file.clone()]);
+        let content = std::fs::read_to_string(&env_file).unwrap();
+        assert!(content.starts_with("VERSION=2.0.1\nVERSION_MAJOR=2\n"));
+    }
+}
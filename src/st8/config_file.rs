@@ -0,0 +1,169 @@
+use anyhow::{Context, Result};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::st8_common::St8Config;
+
+/// Config file names, in the order they are looked for
+pub const CONFIG_FILE_NAMES: [&str; 4] = ["version.toml", "version.yaml", "version.yml", "version.json"];
+
+/// JSON schema of the config files, for editor validation
+pub const SCHEMA: &str = include_str!("../../schemas/version-config.schema.json");
+
+/// The repository's config file in `.ws/`, checked in with the project; the
+/// settings it sets override the project database
+pub fn repo_config_file(repo_root: &Path) -> Option<PathBuf> {
+    find_config_file(&repo_root.join(".ws"))
+}
+
+/// The user's config file in `$XDG_CONFIG_HOME/ws/`, whose settings are the
+/// defaults for projects whose settings are created afterwards
+pub fn user_config_file() -> Option<PathBuf> {
+    find_config_file(&user_config_dir())
+}
+
+pub fn user_config_dir() -> PathBuf {
+    // Check XDG_CONFIG_HOME first, fallback to ~/.config
+    let config_home = std::env::var("XDG_CONFIG_HOME").unwrap_or_else(|_| {
+        let home = std::env::var("HOME").unwrap_or_default();
+        format!("{}/.config", home)
+    });
+    PathBuf::from(config_home).join("ws")
+}
+
+fn find_config_file(dir: &Path) -> Option<PathBuf> {
+    CONFIG_FILE_NAMES.iter().map(|name| dir.join(name)).find(|path| path.is_file())
+}
+
+/// The settings a config file sets, as JSON whatever its format
+pub fn read_layer(path: &Path) -> Result<Map<String, Value>> {
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let value: Value = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))?,
+        Some("yaml" | "yml") => serde_yaml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))?,
+        _ => serde_json::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))?,
+    };
+    let Value::Object(mut layer) = value else {
+        anyhow::bail!("{} must hold a table of settings", path.display());
+    };
+    // Editors' pointer to the schema, not a setting
+    layer.remove("$schema");
+    check_settings(&layer, path)?;
+    Ok(layer)
+}
+
+/// Fail on settings `St8Config` does not have, e.g. misspelled ones
+pub fn check_settings(layer: &Map<String, Value>, source: &Path) -> Result<()> {
+    let known = serde_json::to_value(St8Config::default())?;
+    for key in layer.keys() {
+        if known.get(key).is_none() {
+            anyhow::bail!("Unknown setting {} in {}", key, source.display());
+        }
+    }
+    Ok(())
+}
+
+/// `config` with the settings of `layer` on top; nested tables such as
+/// `changelog` are merged key by key
+pub fn apply_layer(config: &St8Config, layer: &Map<String, Value>, source: &Path) -> Result<St8Config> {
+    let mut merged = serde_json::to_value(config)?;
+    merge(&mut merged, layer);
+    let config: St8Config = serde_json::from_value(merged)
+        .with_context(|| format!("Invalid settings in {}", source.display()))?;
+    super::format::validate_format(&config.format)
+        .and_then(|_| super::policy::path_matcher(Path::new("."), &config.ignore_paths).map(|_| ()))
+        .with_context(|| format!("Invalid settings in {}", source.display()))?;
+    Ok(config)
+}
+
+/// `config` with the settings of the config file at `path`, if there is one
+pub fn apply_file(config: St8Config, path: Option<PathBuf>) -> Result<St8Config> {
+    match path {
+        Some(path) => {
+            let layer = read_layer(&path)?;
+            log::debug!("Applying version settings from {}", path.display());
+            apply_layer(&config, &layer, &path)
+        }
+        None => Ok(config),
+    }
+}
+
+/// The repository config file, when it sets the top-level setting of `key`
+/// (`changelog.path` is set by a `changelog` table)
+pub fn overriding_file(repo_root: &Path, key: &str) -> Result<Option<PathBuf>> {
+    let Some(path) = repo_config_file(repo_root) else { return Ok(None) };
+    let (top, nested) = match key.split_once('.') {
+        Some((top, nested)) => (top, Some(nested)),
+        None => (key, None),
+    };
+    let layer = read_layer(&path)?;
+    let overridden = match (layer.get(top), nested) {
+        (Some(Value::Object(table)), Some(nested)) => table.contains_key(nested),
+        (Some(_), _) => true,
+        (None, _) => false,
+    };
+    Ok(overridden.then_some(path))
+}
+
+fn merge(base: &mut Value, layer: &Map<String, Value>) {
+    for (key, value) in layer {
+        match (base.get_mut(key), value) {
+            (Some(Value::Object(base_table)), Value::Object(layer_table)) => {
+                for (nested_key, nested_value) in layer_table {
+                    base_table.insert(nested_key.clone(), nested_value.clone());
+                }
+            }
+            _ => {
+                base[key] = value.clone();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::conventional::VersionStrategy;
+
+    #[test]
+    fn test_layers_in_every_format() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let files = [
+            ("version.toml", "strategy = \"conventional\"\n\n[changelog]\nenabled = true\n"),
+            ("version.yaml", "strategy: conventional\nchangelog:\n  enabled: true\n"),
+            ("version.json", r#"{"$schema": "version-config.schema.json", "strategy": "conventional", "changelog": {"enabled": true}}"#),
+        ];
+        for (name, content) in files {
+            let path = temp_dir.path().join(name);
+            fs::write(&path, content).unwrap();
+            let config = apply_file(St8Config::default(), Some(path)).unwrap();
+            assert_eq!(config.strategy, VersionStrategy::Conventional, "{}", name);
+            assert!(config.changelog.enabled, "{}", name);
+            // Untouched nested settings keep their values
+            assert_eq!(config.changelog.path, "CHANGELOG.md", "{}", name);
+            assert_eq!(config.version_file, "version.txt", "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_invalid_layers_are_rejected() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let unknown = temp_dir.path().join("version.toml");
+        fs::write(&unknown, "colour = \"blue\"\n").unwrap();
+        assert!(read_layer(&unknown).unwrap_err().to_string().contains("Unknown setting colour"));
+
+        let invalid = temp_dir.path().join("version.json");
+        fs::write(&invalid, r#"{"strategy": "semver"}"#).unwrap();
+        assert!(apply_file(St8Config::default(), Some(invalid)).is_err());
+    }
+
+    #[test]
+    fn test_schema_covers_every_setting() {
+        let schema: Value = serde_json::from_str(SCHEMA).unwrap();
+        let properties = schema["properties"].as_object().unwrap();
+        for key in serde_json::to_value(St8Config::default()).unwrap().as_object().unwrap().keys() {
+            assert!(properties.contains_key(key), "schema is missing {}", key);
+        }
+    }
+}
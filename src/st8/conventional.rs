@@ -0,0 +1,426 @@
+use anyhow::Result;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::sync::OnceLock;
+
+use super::git::read_history;
+use super::tags::TagScheme;
+
+/// How the minor and patch components of the version are derived
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VersionStrategy {
+    /// Database major, total commit count, changes since the last release tag
+    #[default]
+    Count,
+    /// Last release tag bumped according to the Conventional Commits since it
+    Conventional,
+}
+
+impl std::str::FromStr for VersionStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "count" => Ok(VersionStrategy::Count),
+            "conventional" => Ok(VersionStrategy::Conventional),
+            _ => anyhow::bail!("Unknown version strategy: {} (expected count or conventional)", s),
+        }
+    }
+}
+
+impl std::fmt::Display for VersionStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            VersionStrategy::Count => "count",
+            VersionStrategy::Conventional => "conventional",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Semantic version level a commit asks for, ordered by significance
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BumpLevel {
+    None,
+    Patch,
+    Minor,
+    Major,
+}
+
+impl std::fmt::Display for BumpLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            BumpLevel::None => "none",
+            BumpLevel::Patch => "patch",
+            BumpLevel::Minor => "minor",
+            BumpLevel::Major => "major",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A `MAJOR.MINOR.PATCH[-PRE][+BUILD]` version
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SemVer {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+    pub pre: Option<String>,
+    /// Build metadata; ignored when ordering versions
+    pub build: Option<String>,
+}
+
+impl SemVer {
+    pub fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self { major, minor, patch, pre: None, build: None }
+    }
+
+    /// Parse a version or release tag; the `v` prefix and missing trailing
+    /// components are accepted, so `v1.2` reads as 1.2.0
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let text = text.strip_prefix('v').unwrap_or(text);
+        let (text, build) = match text.split_once('+') {
+            Some((text, build)) if !build.is_empty() => (text, Some(build.to_string())),
+            Some(_) => return None,
+            None => (text, None),
+        };
+        let (core, pre) = match text.split_once('-') {
+            Some((core, pre)) if !pre.is_empty() => (core, Some(pre.to_string())),
+            Some(_) => return None,
+            None => (text, None),
+        };
+
+        let parts: Vec<&str> = core.split('.').collect();
+        if parts.is_empty() || parts.len() > 3 {
+            return None;
+        }
+        let mut numbers = [0u32; 3];
+        for (slot, part) in numbers.iter_mut().zip(&parts) {
+            *slot = part.parse().ok()?;
+        }
+
+        Some(Self { major: numbers[0], minor: numbers[1], patch: numbers[2], pre, build })
+    }
+
+    /// Order by semver precedence: a prerelease comes before its release,
+    /// numeric prerelease identifiers compare as numbers (`rc.10` > `rc.9`)
+    /// and build metadata is ignored
+    pub fn cmp_precedence(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (&self.pre, &other.pre) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(a), Some(b)) => cmp_prerelease(a, b),
+            })
+    }
+
+    /// Label of the prerelease, `rc` for `1.2.0-rc.3`
+    pub fn pre_label(&self) -> Option<&str> {
+        let pre = self.pre.as_deref()?;
+        Some(pre.split('.').next().unwrap_or(pre))
+    }
+
+    pub fn with_build(self, build: Option<String>) -> Self {
+        Self { build, ..self }
+    }
+
+    /// The next version at `level`; `BumpLevel::None` only drops a prerelease.
+    /// A prerelease of the version `level` leads to is released as is, so
+    /// `2.0.0-rc.1` bumps to `2.0.0` at any level.
+    pub fn bump(&self, level: BumpLevel) -> Self {
+        let pre = self.pre.is_some();
+        match level {
+            BumpLevel::Major if pre && self.minor == 0 && self.patch == 0 => self.release(),
+            BumpLevel::Major => Self::new(self.major + 1, 0, 0),
+            BumpLevel::Minor if pre && self.patch == 0 => self.release(),
+            BumpLevel::Minor => Self::new(self.major, self.minor + 1, 0),
+            BumpLevel::Patch if pre => self.release(),
+            BumpLevel::Patch => Self::new(self.major, self.minor, self.patch + 1),
+            BumpLevel::None => self.release(),
+        }
+    }
+
+    /// The next `label` prerelease: `1.2.3` → `1.2.4-label.1`,
+    /// `1.2.4-label.1` → `1.2.4-label.2`, `1.2.4-beta.3` → `1.2.4-label.1`
+    pub fn prerelease(&self, label: &str) -> Self {
+        let (core, number) = match &self.pre {
+            None => (self.bump(BumpLevel::Patch), 1),
+            Some(pre) => {
+                let current = pre.strip_prefix(label)
+                    .and_then(|rest| rest.strip_prefix('.'))
+                    .and_then(|n| n.parse::<u32>().ok());
+                (self.release(), current.map_or(1, |n| n + 1))
+            }
+        };
+        Self { pre: Some(format!("{}.{}", label, number)), ..core }
+    }
+
+    fn release(&self) -> Self {
+        Self::new(self.major, self.minor, self.patch)
+    }
+}
+
+fn cmp_prerelease(a: &str, b: &str) -> Ordering {
+    let mut left = a.split('.');
+    let mut right = b.split('.');
+    loop {
+        match (left.next(), right.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => {
+                let ordering = match (x.parse::<u64>(), y.parse::<u64>()) {
+                    (Ok(x), Ok(y)) => x.cmp(&y),
+                    // Numeric identifiers sort before alphanumeric ones
+                    (Ok(_), Err(_)) => Ordering::Less,
+                    (Err(_), Ok(_)) => Ordering::Greater,
+                    (Err(_), Err(_)) => x.cmp(y),
+                };
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for SemVer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if let Some(pre) = &self.pre {
+            write!(f, "-{}", pre)?;
+        }
+        if let Some(build) = &self.build {
+            write!(f, "+{}", build)?;
+        }
+        Ok(())
+    }
+}
+
+/// A commit whose header follows `type(scope)!: description`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConventionalCommit {
+    pub hash: String,
+    pub kind: String,
+    pub scope: Option<String>,
+    pub breaking: bool,
+    pub description: String,
+}
+
+impl ConventionalCommit {
+    /// Parse a full commit message; `None` when the header is not conventional
+    pub fn parse(hash: &str, message: &str) -> Option<Self> {
+        static HEADER: OnceLock<Regex> = OnceLock::new();
+        let header_regex = HEADER.get_or_init(|| {
+            Regex::new(r"^(?P<kind>[A-Za-z]+)(?:\((?P<scope>[^)]*)\))?(?P<bang>!)?:\s+(?P<description>.+)$").unwrap()
+        });
+
+        let header = message.lines().next()?.trim();
+        let caps = header_regex.captures(header)?;
+        let breaking_footer = message.lines().skip(1).any(|line| {
+            line.starts_with("BREAKING CHANGE:") || line.starts_with("BREAKING-CHANGE:")
+        });
+
+        Some(Self {
+            hash: hash.to_string(),
+            kind: caps["kind"].to_lowercase(),
+            scope: caps.name("scope").map(|s| s.as_str().to_string()).filter(|s| !s.is_empty()),
+            breaking: caps.name("bang").is_some() || breaking_footer,
+            description: caps["description"].trim().to_string(),
+        })
+    }
+
+    pub fn bump_level(&self) -> BumpLevel {
+        if self.breaking {
+            return BumpLevel::Major;
+        }
+        match self.kind.as_str() {
+            "feat" => BumpLevel::Minor,
+            "fix" | "perf" => BumpLevel::Patch,
+            _ => BumpLevel::None,
+        }
+    }
+
+    /// The header as it was written, for display
+    pub fn header(&self) -> String {
+        let scope = self.scope.as_ref().map(|s| format!("({})", s)).unwrap_or_default();
+        let bang = if self.breaking { "!" } else { "" };
+        format!("{}{}{}: {}", self.kind, scope, bang, self.description)
+    }
+}
+
+/// Outcome of reading the commits since the last release tag
+#[derive(Debug, Clone)]
+pub struct BumpDecision {
+    pub last_tag: Option<String>,
+    pub base_version: SemVer,
+    pub commits_analyzed: usize,
+    pub level: BumpLevel,
+    /// The newest commit asking for `level`
+    pub deciding_commit: Option<ConventionalCommit>,
+}
+
+impl BumpDecision {
+    pub fn from_commits(last_tag: Option<String>, base_version: SemVer, commits: &[(String, String)]) -> Self {
+        let mut level = BumpLevel::None;
+        let mut deciding_commit = None;
+        for (hash, message) in commits {
+            let Some(commit) = ConventionalCommit::parse(hash, message) else { continue };
+            if commit.bump_level() > level {
+                level = commit.bump_level();
+                deciding_commit = Some(commit);
+            }
+        }
+
+        Self {
+            last_tag,
+            base_version,
+            commits_analyzed: commits.len(),
+            level,
+            deciding_commit,
+        }
+    }
+
+    /// Read the git history of the current repository, since the last
+    /// release tag named by `tags`
+    pub fn analyze(tags: &TagScheme) -> Result<Self> {
+        let last_tag = find_last_semver_tag(tags)?;
+        let base_version = last_tag.as_deref().and_then(|tag| tags.version(tag)).unwrap_or_default();
+        let commits = commit_messages_since(last_tag.as_deref())?;
+        Ok(Self::from_commits(last_tag, base_version, &commits))
+    }
+
+    pub fn next_version(&self) -> SemVer {
+        self.base_version.bump(self.level)
+    }
+
+    /// Whether the commits call for a release; without a feat, fix or
+    /// breaking commit the next version is the one already tagged
+    pub fn release_due(&self) -> bool {
+        self.last_tag.is_none() || self.level != BumpLevel::None
+    }
+
+    /// One-line explanation of the decision
+    pub fn describe(&self) -> String {
+        let since = self.last_tag.as_deref().unwrap_or("the first commit");
+        match &self.deciding_commit {
+            Some(commit) => format!(
+                "{} bump from {} commits since {} ({} \"{}\")",
+                self.level,
+                self.commits_analyzed,
+                since,
+                &commit.hash[..commit.hash.len().min(7)],
+                commit.header()
+            ),
+            None => format!(
+                "no feat/fix/breaking commits among {} since {}",
+                self.commits_analyzed, since
+            ),
+        }
+    }
+}
+
+/// The most recent release tag reachable from HEAD that names a version
+/// other than a prerelease
+pub(crate) fn find_last_semver_tag(tags: &TagScheme) -> Result<Option<String>> {
+    let reachable = read_history(Vec::new(), |repo| repo.tags_reachable_from_head())?;
+    Ok(reachable.into_iter().find(|tag| tags.version(tag).is_some_and(|v| v.pre.is_none())))
+}
+
+/// (hash, full message) of every commit after `tag`, newest first
+pub fn commit_messages_since(tag: Option<&str>) -> Result<Vec<(String, String)>> {
+    read_history(Vec::new(), |repo| repo.commits_since(tag))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_conventional_commit() {
+        let commit = ConventionalCommit::parse("abc", "feat(parser): support arrays").unwrap();
+        assert_eq!(commit.kind, "feat");
+        assert_eq!(commit.scope.as_deref(), Some("parser"));
+        assert!(!commit.breaking);
+        assert_eq!(commit.bump_level(), BumpLevel::Minor);
+
+        assert_eq!(ConventionalCommit::parse("abc", "fix: typo").unwrap().bump_level(), BumpLevel::Patch);
+        assert_eq!(ConventionalCommit::parse("abc", "chore: deps").unwrap().bump_level(), BumpLevel::None);
+        assert!(ConventionalCommit::parse("abc", "Update README").is_none());
+    }
+
+    #[test]
+    fn test_breaking_changes() {
+        let bang = ConventionalCommit::parse("abc", "refactor!: drop old API").unwrap();
+        assert_eq!(bang.bump_level(), BumpLevel::Major);
+
+        let footer = ConventionalCommit::parse("abc", "fix: rename flag\n\nBREAKING CHANGE: --old is gone").unwrap();
+        assert_eq!(footer.bump_level(), BumpLevel::Major);
+    }
+
+    #[test]
+    fn test_semver_parse_and_bump() {
+        assert_eq!(SemVer::parse("v1.2"), Some(SemVer::new(1, 2, 0)));
+        assert_eq!(SemVer::parse("1.2.3-rc.1").unwrap().pre.as_deref(), Some("rc.1"));
+        assert_eq!(SemVer::parse("release"), None);
+
+        let version = SemVer::new(1, 2, 3);
+        assert_eq!(version.bump(BumpLevel::Major).to_string(), "2.0.0");
+        assert_eq!(version.bump(BumpLevel::Minor).to_string(), "1.3.0");
+        assert_eq!(version.bump(BumpLevel::Patch).to_string(), "1.2.4");
+    }
+
+    #[test]
+    fn test_semver_prerelease() {
+        let release = SemVer::new(1, 2, 3);
+        let rc1 = release.prerelease("rc");
+        assert_eq!(rc1.to_string(), "1.2.4-rc.1");
+        assert_eq!(rc1.prerelease("rc").to_string(), "1.2.4-rc.2");
+        assert_eq!(rc1.prerelease("beta").to_string(), "1.2.4-beta.1");
+
+        // Bumping a prerelease releases it when it already is that level
+        assert_eq!(rc1.bump(BumpLevel::Patch).to_string(), "1.2.4");
+        assert_eq!(rc1.bump(BumpLevel::Minor).to_string(), "1.3.0");
+        assert_eq!(SemVer::parse("2.0.0-rc.1").unwrap().bump(BumpLevel::Major).to_string(), "2.0.0");
+    }
+
+    #[test]
+    fn test_semver_precedence_and_build() {
+        let parse = |v: &str| SemVer::parse(v).unwrap();
+        assert_eq!(parse("1.2.0-rc.1+abc123").build.as_deref(), Some("abc123"));
+        assert_eq!(parse("1.2.0-rc.1+abc123").to_string(), "1.2.0-rc.1+abc123");
+        assert_eq!(parse("1.2.0-rc.3").cmp_precedence(&parse("1.2.0-rc.1")), Ordering::Greater);
+        assert_eq!(parse("1.2.0-rc.10").cmp_precedence(&parse("1.2.0-rc.9")), Ordering::Greater);
+        assert_eq!(parse("1.2.0-alpha.4").cmp_precedence(&parse("1.2.0-rc.1")), Ordering::Less);
+        assert_eq!(parse("1.2.0-rc.3").cmp_precedence(&parse("1.2.0")), Ordering::Less);
+        assert_eq!(parse("1.2.0+a").cmp_precedence(&parse("1.2.0+b")), Ordering::Equal);
+        assert_eq!(parse("1.2.0-rc.3").pre_label(), Some("rc"));
+    }
+
+    #[test]
+    fn test_bump_decision_takes_highest_level() {
+        let commits = vec![
+            ("c3".to_string(), "fix: off by one".to_string()),
+            ("c2".to_string(), "feat: add export".to_string()),
+            ("c1".to_string(), "docs: usage".to_string()),
+        ];
+        let decision = BumpDecision::from_commits(Some("v1.4.2".to_string()), SemVer::new(1, 4, 2), &commits);
+        assert_eq!(decision.level, BumpLevel::Minor);
+        assert_eq!(decision.next_version().to_string(), "1.5.0");
+        assert_eq!(decision.deciding_commit.unwrap().hash, "c2");
+    }
+
+    #[test]
+    fn test_bump_decision_without_releasable_commits() {
+        let commits = vec![("c1".to_string(), "docs: usage".to_string())];
+        let decision = BumpDecision::from_commits(Some("v1.4.2".to_string()), SemVer::new(1, 4, 2), &commits);
+        assert_eq!(decision.next_version().to_string(), "1.4.2");
+        assert!(!decision.release_due());
+        assert!(BumpDecision::from_commits(None, SemVer::default(), &commits).release_due());
+    }
+}
@@ -0,0 +1,342 @@
+//! Version information compiled into applications. `ws version embed`
+//! renders a source file (`version.rs`, `version.h` or `version.ts`) holding
+//! the version, commit id and build date, and build scripts can do the same
+//! through [`embed_for_build`]:
+//!
+//! ```no_run
+//! // build.rs
+//! fn main() {
+//!     workspace::st8::embed::embed_for_build("version.rs").unwrap();
+//! }
+//!
+//! // src/main.rs
+//! // include!(concat!(env!("OUT_DIR"), "/version.rs"));
+//! ```
+//!
+//! Templates use Tera syntax with the variables `version`, `major`, `minor`,
+//! `patch`, `pre`, `build`, `commit`, `short_commit` and `build_date`.
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tera::{Context as TeraContext, Tera};
+
+use super::conventional::SemVer;
+use super::git::GitRepo;
+use super::st8_common::{current_version, St8Config};
+
+const RUST_TEMPLATE: &str = r#"// Generated by ws version embed; do not edit
+pub const VERSION: &str = "{{ version }}";
+pub const VERSION_MAJOR: u32 = {{ major }};
+pub const VERSION_MINOR: u32 = {{ minor }};
+pub const VERSION_PATCH: u32 = {{ patch }};
+pub const VERSION_PRERELEASE: &str = "{{ pre }}";
+pub const COMMIT: &str = "{{ commit }}";
+pub const SHORT_COMMIT: &str = "{{ short_commit }}";
+pub const BUILD_DATE: &str = "{{ build_date }}";
+"#;
+
+const C_TEMPLATE: &str = r#"/* Generated by ws version embed; do not edit */
+#ifndef WS_VERSION_H
+#define WS_VERSION_H
+
+#define VERSION "{{ version }}"
+#define VERSION_MAJOR {{ major }}
+#define VERSION_MINOR {{ minor }}
+#define VERSION_PATCH {{ patch }}
+#define VERSION_PRERELEASE "{{ pre }}"
+#define VERSION_COMMIT "{{ commit }}"
+#define VERSION_SHORT_COMMIT "{{ short_commit }}"
+#define VERSION_BUILD_DATE "{{ build_date }}"
+
+#endif /* WS_VERSION_H */
+"#;
+
+const TYPESCRIPT_TEMPLATE: &str = r#"// Generated by ws version embed; do not edit
+export const VERSION = "{{ version }}";
+export const VERSION_MAJOR = {{ major }};
+export const VERSION_MINOR = {{ minor }};
+export const VERSION_PATCH = {{ patch }};
+export const VERSION_PRERELEASE = "{{ pre }}";
+export const COMMIT = "{{ commit }}";
+export const SHORT_COMMIT = "{{ short_commit }}";
+export const BUILD_DATE = "{{ build_date }}";
+"#;
+
+/// Language of a generated version file, which picks the built-in template
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmbedLanguage {
+    Rust,
+    /// A C or C++ header
+    C,
+    TypeScript,
+}
+
+impl EmbedLanguage {
+    /// Tell the language from the extension of the file to write
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "rs" => Some(EmbedLanguage::Rust),
+            "h" | "hpp" | "hh" => Some(EmbedLanguage::C),
+            "ts" | "mts" => Some(EmbedLanguage::TypeScript),
+            _ => None,
+        }
+    }
+
+    pub fn template(self) -> &'static str {
+        match self {
+            EmbedLanguage::Rust => RUST_TEMPLATE,
+            EmbedLanguage::C => C_TEMPLATE,
+            EmbedLanguage::TypeScript => TYPESCRIPT_TEMPLATE,
+        }
+    }
+}
+
+impl std::str::FromStr for EmbedLanguage {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "rust" => Ok(EmbedLanguage::Rust),
+            "c" => Ok(EmbedLanguage::C),
+            "typescript" => Ok(EmbedLanguage::TypeScript),
+            _ => anyhow::bail!("Unknown embed language: {} (expected rust, c or typescript)", s),
+        }
+    }
+}
+
+impl std::fmt::Display for EmbedLanguage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            EmbedLanguage::Rust => "rust",
+            EmbedLanguage::C => "c",
+            EmbedLanguage::TypeScript => "typescript",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// What a generated version file states
+#[derive(Debug, Clone, PartialEq)]
+pub struct BuildInfo {
+    pub version: String,
+    /// Commit id of HEAD; empty outside a repository
+    pub commit: String,
+    /// RFC 3339 UTC time of the build, taken from `SOURCE_DATE_EPOCH` when
+    /// set so reproducible builds get the same file
+    pub build_date: String,
+}
+
+impl BuildInfo {
+    /// `version` built now from the HEAD of the repository enclosing `dir`
+    pub fn new(version: impl Into<String>, dir: &Path) -> Self {
+        let commit = GitRepo::discover_from(dir)
+            .and_then(|repo| repo.head_commit())
+            .unwrap_or_default();
+        let build_date = std::env::var("SOURCE_DATE_EPOCH")
+            .ok()
+            .and_then(|epoch| epoch.parse().ok())
+            .and_then(|epoch| chrono::DateTime::from_timestamp(epoch, 0))
+            .unwrap_or_else(chrono::Utc::now);
+        Self {
+            version: version.into(),
+            commit,
+            build_date: build_date.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        }
+    }
+
+    fn context(&self) -> TeraContext {
+        let semver = SemVer::parse(&self.version).unwrap_or_default();
+        let mut context = TeraContext::new();
+        context.insert("version", &self.version);
+        context.insert("major", &semver.major);
+        context.insert("minor", &semver.minor);
+        context.insert("patch", &semver.patch);
+        context.insert("pre", &semver.pre.unwrap_or_default());
+        context.insert("build", &semver.build.unwrap_or_default());
+        context.insert("commit", &self.commit);
+        context.insert("short_commit", &self.commit[..self.commit.len().min(7)]);
+        context.insert("build_date", &self.build_date);
+        context
+    }
+}
+
+/// Render `template` with `info`
+pub fn render(template: &str, info: &BuildInfo) -> Result<String> {
+    Tera::one_off(template, &info.context(), false).context("Failed to render version template")
+}
+
+/// Render `template` into `path`, leaving the file alone when it already
+/// holds the result so builds depending on it are not redone. Without
+/// `SOURCE_DATE_EPOCH` the build date is always new, so it is left out of
+/// that comparison and the file keeps the date of the build that last
+/// changed it. Returns whether the file was written.
+pub fn write_embed(path: &Path, template: &str, info: &BuildInfo) -> Result<bool> {
+    let content = render(template, info)?;
+    let dated = std::env::var_os("SOURCE_DATE_EPOCH").is_some();
+    let up_to_date = |current: String| current == content || (!dated && same_but_build_date(&current, template, info));
+    if fs::read_to_string(path).is_ok_and(up_to_date) {
+        return Ok(false);
+    }
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(true)
+}
+
+/// Whether `current` is `template` rendered with `info` apart from the
+/// build date, which may be any RFC 3339 time. Templates that format the
+/// date with a filter never match, so they are rewritten as before.
+fn same_but_build_date(current: &str, template: &str, info: &BuildInfo) -> bool {
+    const MARKER: &str = "\u{0}build_date\u{0}";
+    let marked = BuildInfo { build_date: MARKER.to_string(), ..info.clone() };
+    let Ok(rendered) = render(template, &marked) else { return false };
+    let pattern = rendered.split(MARKER).map(regex::escape).collect::<Vec<_>>().join(r"[0-9]{4}-[0-9]{2}-[0-9]{2}T[0-9:.]+(?:Z|[+-][0-9:]+)");
+    Regex::new(&format!("^{}$", pattern)).is_ok_and(|regex| regex.is_match(current))
+}
+
+/// Write `file_name` into the build script's `OUT_DIR` with the built-in
+/// template for its extension, stating the current version of the project
+/// around the package: that of the version file, or the last release tag.
+/// Asks Cargo to run the script again when the version file, HEAD or the
+/// branch it points to moves.
+pub fn embed_for_build(file_name: &str) -> Result<PathBuf> {
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").context("OUT_DIR is not set; is this a build script?")?);
+    let manifest_dir = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").context("CARGO_MANIFEST_DIR is not set")?);
+    let path = out_dir.join(file_name);
+    let language = EmbedLanguage::from_path(&path)
+        .with_context(|| format!("Can't tell the language of {} (use .rs, .h or .ts)", file_name))?;
+
+    // Settings live in the .ws folder of the project, which may enclose the package
+    let root = manifest_dir.ancestors().find(|dir| dir.join(".ws/project.db").exists());
+    let mut config = match root {
+        Some(root) => St8Config::load(root)?,
+        None => St8Config::default(),
+    };
+    let root = root.unwrap_or(&manifest_dir);
+    config.version_file = root.join(&config.version_file).to_string_lossy().to_string();
+
+    let info = BuildInfo::new(current_version(&config)?.to_string(), &manifest_dir);
+    write_embed(&path, language.template(), &info)?;
+
+    println!("cargo:rerun-if-changed={}", config.version_file);
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    if let Ok(git_dir) = GitRepo::discover_from(&manifest_dir).and_then(|repo| repo.git_dir()) {
+        for watched in head_files(&git_dir) {
+            println!("cargo:rerun-if-changed={}", watched.display());
+        }
+    }
+    Ok(path)
+}
+
+/// The files a commit changes when it moves HEAD: HEAD itself, the branch
+/// ref it points to and `packed-refs`, where that ref may live instead.
+/// Only existing paths are listed, since Cargo reruns the script on every
+/// build for a missing one; while the branch is only packed (or has no
+/// commits yet) its closest existing directory stands in for the loose ref.
+fn head_files(git_dir: &Path) -> Vec<PathBuf> {
+    let head = git_dir.join("HEAD");
+    // Refs of a linked worktree live in the main repository's git directory
+    let common_dir = fs::read_to_string(git_dir.join("commondir"))
+        .map(|dir| git_dir.join(dir.trim()))
+        .unwrap_or_else(|_| git_dir.to_path_buf());
+
+    let mut files = vec![head.clone()];
+    let reference = fs::read_to_string(&head).ok()
+        .and_then(|content| content.strip_prefix("ref:").map(|reference| reference.trim().to_string()));
+    if let Some(reference) = reference {
+        let loose = common_dir.join(&reference);
+        let watched = loose.ancestors()
+            .take_while(|path| path.starts_with(common_dir.join("refs")))
+            .find(|path| path.exists())
+            .map(Path::to_path_buf);
+        files.extend(watched);
+    }
+    files.push(common_dir.join("packed-refs"));
+    files.retain(|path| path.exists());
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(version: &str) -> BuildInfo {
+        BuildInfo {
+            version: version.to_string(),
+            commit: "0123456789abcdef".to_string(),
+            build_date: "2024-05-01T12:00:00Z".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_language_from_path() {
+        assert_eq!(EmbedLanguage::from_path(Path::new("src/version.rs")), Some(EmbedLanguage::Rust));
+        assert_eq!(EmbedLanguage::from_path(Path::new("include/version.h")), Some(EmbedLanguage::C));
+        assert_eq!(EmbedLanguage::from_path(Path::new("version.ts")), Some(EmbedLanguage::TypeScript));
+        assert_eq!(EmbedLanguage::from_path(Path::new("version.txt")), None);
+        assert!("go".parse::<EmbedLanguage>().is_err());
+    }
+
+    #[test]
+    fn test_render_templates() {
+        let rust = render(EmbedLanguage::Rust.template(), &info("1.4.0-rc.2")).unwrap();
+        assert!(rust.contains("pub const VERSION: &str = \"1.4.0-rc.2\";"));
+        assert!(rust.contains("pub const VERSION_MINOR: u32 = 4;"));
+        assert!(rust.contains("pub const VERSION_PRERELEASE: &str = \"rc.2\";"));
+        assert!(rust.contains("pub const SHORT_COMMIT: &str = \"0123456\";"));
+
+        let header = render(EmbedLanguage::C.template(), &info("2.0.1")).unwrap();
+        assert!(header.contains("#define VERSION_MAJOR 2\n"));
+        assert!(header.contains("#define VERSION_BUILD_DATE \"2024-05-01T12:00:00Z\""));
+
+        // Versions that are not semantic get zero components
+        let typescript = render(EmbedLanguage::TypeScript.template(), &info("build-42")).unwrap();
+        assert!(typescript.contains("export const VERSION = \"build-42\";"));
+        assert!(typescript.contains("export const VERSION_MAJOR = 0;"));
+
+        assert_eq!(render("{{ version }}@{{ short_commit }}", &info("3.0.0")).unwrap(), "3.0.0@0123456");
+    }
+
+    #[test]
+    fn test_head_files_follow_the_branch() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let git_dir = temp_dir.path();
+        fs::create_dir_all(git_dir.join("refs/heads/feature")).unwrap();
+        fs::write(git_dir.join("HEAD"), "ref: refs/heads/feature/embed\n").unwrap();
+
+        // Packed only: the directory the loose ref would appear in
+        fs::write(git_dir.join("packed-refs"), "0123 refs/heads/feature/embed\n").unwrap();
+        assert_eq!(head_files(git_dir), vec![
+            git_dir.join("HEAD"),
+            git_dir.join("refs/heads/feature"),
+            git_dir.join("packed-refs"),
+        ]);
+
+        fs::write(git_dir.join("refs/heads/feature/embed"), "0123\n").unwrap();
+        assert_eq!(head_files(git_dir)[1], git_dir.join("refs/heads/feature/embed"));
+
+        // Detached
+        fs::write(git_dir.join("HEAD"), "0123\n").unwrap();
+        assert_eq!(head_files(git_dir), vec![git_dir.join("HEAD"), git_dir.join("packed-refs")]);
+    }
+
+    #[test]
+    fn test_write_embed_only_on_change() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("gen/version.rs");
+        assert!(write_embed(&path, RUST_TEMPLATE, &info("1.0.0")).unwrap());
+        assert!(!write_embed(&path, RUST_TEMPLATE, &info("1.0.0")).unwrap());
+        assert!(write_embed(&path, RUST_TEMPLATE, &info("1.0.1")).unwrap());
+
+        // A later build of the same version keeps the file and its date
+        let later = BuildInfo { build_date: "2024-06-02T08:30:00Z".to_string(), ..info("1.0.1") };
+        assert!(!write_embed(&path, RUST_TEMPLATE, &later).unwrap());
+        assert!(fs::read_to_string(&path).unwrap().contains("2024-05-01T12:00:00Z"));
+        let changed = BuildInfo { commit: "fedcba9876543210".to_string(), ..later };
+        assert!(write_embed(&path, RUST_TEMPLATE, &changed).unwrap());
+        assert!(fs::read_to_string(&path).unwrap().contains("2024-06-02T08:30:00Z"));
+    }
+}
@@ -0,0 +1,284 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::sync::OnceLock;
+
+use super::git::read_history;
+use super::tags::TagScheme;
+
+/// The plain three-part version
+pub const DEFAULT_FORMAT: &str = "{major}.{minor}.{patch}";
+
+/// Placeholders a version format may use, with what they stand for
+pub const PLACEHOLDERS: [(&str, &str); 14] = [
+    ("major", "major component"),
+    ("minor", "minor component"),
+    ("patch", "patch component"),
+    ("pre", "prerelease, with its leading '-' (empty for releases)"),
+    ("tag", "nearest release tag reachable from HEAD, without the tag prefix (0 without tags)"),
+    ("commits", "commits since that tag"),
+    ("sha", "full commit id of HEAD"),
+    ("short_sha", "seven-character commit id of HEAD"),
+    ("branch", "checked out branch (HEAD when detached)"),
+    ("yyyy", "four-digit year"),
+    ("yy", "two-digit year"),
+    ("mm", "month, zero-padded"),
+    ("dd", "day of the month, zero-padded"),
+    ("doy", "day of the year"),
+];
+
+fn placeholder_regex() -> &'static Regex {
+    static PLACEHOLDER: OnceLock<Regex> = OnceLock::new();
+    PLACEHOLDER.get_or_init(|| Regex::new(r"\{([a-z_]+)\}").unwrap())
+}
+
+/// Reject formats with unknown placeholders or without any placeholder
+pub fn validate_format(format: &str) -> Result<()> {
+    validate_template(format)?;
+    if !placeholder_regex().is_match(format) {
+        anyhow::bail!("Version format {} has no placeholders", format);
+    }
+    Ok(())
+}
+
+/// Reject templates with unknown placeholders; plain text is fine
+pub fn validate_template(template: &str) -> Result<()> {
+    for caps in placeholder_regex().captures_iter(template) {
+        let name = &caps[1];
+        if !PLACEHOLDERS.iter().any(|(known, _)| *known == name) {
+            anyhow::bail!("Unknown placeholder {{{}}} in {}", name, template);
+        }
+    }
+    Ok(())
+}
+
+/// Fold `version` into strict `MAJOR.MINOR.PATCH[-PRE][+BUILD]` form: the
+/// `v` prefix and leading zeros go, missing components become 0, components
+/// past the patch move into the build metadata and characters semver does not
+/// allow in identifiers become `-`, so `1.0.5.100+feature/x` becomes
+/// `1.0.5+100.feature-x`. Fails when a core component is not a number.
+pub fn fold_semver(version: &str) -> Result<String> {
+    let text = version.trim();
+    let text = text.strip_prefix('v').unwrap_or(text);
+    let (text, build) = match text.split_once('+') {
+        Some((text, build)) => (text, Some(build)),
+        None => (text, None),
+    };
+    let (core, pre) = match text.split_once('-') {
+        Some((core, pre)) => (core, Some(pre)),
+        None => (text, None),
+    };
+
+    let mut numbers = Vec::new();
+    for part in core.split('.') {
+        if part.is_empty() || !part.chars().all(|c| c.is_ascii_digit()) {
+            anyhow::bail!("{} is not a semantic version: {:?} is not a number", version, part);
+        }
+        numbers.push(strip_leading_zeros(part));
+    }
+    let extra = numbers.split_off(numbers.len().min(3));
+    numbers.resize(3, "0".to_string());
+
+    let mut folded = numbers.join(".");
+    if let Some(pre) = pre {
+        let identifiers = identifiers(version, pre)?;
+        let identifiers: Vec<String> = identifiers.into_iter()
+            .map(|id| if id.chars().all(|c| c.is_ascii_digit()) { strip_leading_zeros(&id) } else { id })
+            .collect();
+        folded = format!("{}-{}", folded, identifiers.join("."));
+    }
+    let mut build_identifiers = extra;
+    if let Some(build) = build {
+        build_identifiers.extend(identifiers(version, build)?);
+    }
+    if !build_identifiers.is_empty() {
+        folded = format!("{}+{}", folded, build_identifiers.join("."));
+    }
+
+    if super::conventional::SemVer::parse(&folded).is_none() {
+        anyhow::bail!("{} is not a semantic version: its components are out of range", version);
+    }
+    Ok(folded)
+}
+
+/// Fail unless `version` already is in strict semver form
+pub fn check_semver(version: &str) -> Result<()> {
+    let folded = fold_semver(version)?;
+    if folded != version.trim() {
+        anyhow::bail!("{} is not a strict semantic version (it folds to {})", version.trim(), folded);
+    }
+    Ok(())
+}
+
+/// Fail when `format`, with `build_metadata` appended, renders to something
+/// `fold_semver` cannot turn into a semantic version
+pub fn validate_semver_template(format: &str, build_metadata: Option<&str>) -> Result<()> {
+    let components = VersionComponents::example();
+    let mut version = components.render(format)?;
+    if let Some(build) = build_metadata {
+        version = format!("{}+{}", version, components.render_template(build)?);
+    }
+    fold_semver(&version)
+        .with_context(|| format!("Version format {} cannot give a semantic version", format))?;
+    Ok(())
+}
+
+fn strip_leading_zeros(number: &str) -> String {
+    let stripped = number.trim_start_matches('0');
+    if stripped.is_empty() { "0".to_string() } else { stripped.to_string() }
+}
+
+/// The dot-separated identifiers of a prerelease or build, with characters
+/// outside `[0-9A-Za-z-]` replaced
+fn identifiers(version: &str, part: &str) -> Result<Vec<String>> {
+    part.split('.')
+        .map(|id| {
+            if id.is_empty() {
+                anyhow::bail!("{} is not a semantic version: it has an empty identifier", version);
+            }
+            Ok(id.chars().map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '-' }).collect())
+        })
+        .collect()
+}
+
+/// Everything a version format can refer to
+#[derive(Debug, Clone)]
+pub struct VersionComponents {
+    pub major: String,
+    pub minor: u32,
+    pub patch: u32,
+    pub pre: Option<String>,
+    /// Version part of the nearest release tag
+    pub tag: Option<String>,
+    pub commits_since_tag: u32,
+    pub sha: Option<String>,
+    pub branch: Option<String>,
+    pub date: chrono::NaiveDate,
+}
+
+impl VersionComponents {
+    /// The given numeric components plus what git and the clock say now
+    pub fn collect(tags: &TagScheme, major: impl Into<String>, minor: u32, patch: u32) -> Result<Self> {
+        let tag = read_history(None, |repo| super::tags::nearest_tag(repo, tags))?;
+        let commits_since_tag = read_history(0, |repo| repo.commit_count(tag.as_deref()))?;
+        let tag = tag.map(|tag| tags.strip(&tag).to_string());
+        let sha = read_history(None, |repo| repo.head_commit().map(Some))?;
+        let branch = read_history(None, |repo| Ok(repo.current_branch().ok()))?;
+
+        Ok(Self {
+            major: major.into(),
+            minor,
+            patch,
+            pre: None,
+            tag,
+            commits_since_tag,
+            sha,
+            branch,
+            date: chrono::Local::now().date_naive(),
+        })
+    }
+
+    /// Made-up components for checking what a format renders to
+    pub fn example() -> Self {
+        Self {
+            major: "1".to_string(),
+            minor: 2,
+            patch: 3,
+            pre: None,
+            tag: Some("1.2.0".to_string()),
+            commits_since_tag: 4,
+            sha: Some("abcdef0123456789abcdef0123456789abcdef01".to_string()),
+            branch: Some("feature/example".to_string()),
+            date: chrono::NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+        }
+    }
+
+    fn value(&self, name: &str) -> Option<String> {
+        use chrono::Datelike;
+        let value = match name {
+            "major" => self.major.strip_prefix('v').unwrap_or(&self.major).to_string(),
+            "minor" => self.minor.to_string(),
+            "patch" => self.patch.to_string(),
+            "pre" => self.pre.as_ref().map(|pre| format!("-{}", pre)).unwrap_or_default(),
+            "tag" => self.tag.clone().unwrap_or_else(|| "0".to_string()),
+            "commits" => self.commits_since_tag.to_string(),
+            "sha" => self.sha.clone().unwrap_or_default(),
+            "short_sha" => self.sha.as_deref().map(|sha| sha[..sha.len().min(7)].to_string()).unwrap_or_default(),
+            "branch" => self.branch.clone().unwrap_or_else(|| "HEAD".to_string()),
+            "yyyy" => format!("{:04}", self.date.year()),
+            "yy" => format!("{:02}", self.date.year() % 100),
+            "mm" => format!("{:02}", self.date.month()),
+            "dd" => format!("{:02}", self.date.day()),
+            "doy" => self.date.ordinal().to_string(),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    /// Render a version format
+    pub fn render(&self, format: &str) -> Result<String> {
+        validate_format(format)?;
+        self.render_template(format)
+    }
+
+    /// Fill the placeholders of `template`, which may also be plain text
+    pub fn render_template(&self, template: &str) -> Result<String> {
+        validate_template(template)?;
+        Ok(placeholder_regex()
+            .replace_all(template, |caps: &regex::Captures| self.value(&caps[1]).unwrap_or_default())
+            .into_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn components() -> VersionComponents {
+        VersionComponents {
+            major: "v2".to_string(),
+            minor: 14,
+            patch: 3,
+            pre: None,
+            tag: Some("2.1.0".to_string()),
+            commits_since_tag: 5,
+            sha: Some("0123456789abcdef".to_string()),
+            branch: Some("main".to_string()),
+            date: chrono::NaiveDate::from_ymd_opt(2024, 3, 7).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_render_formats() {
+        let components = components();
+        assert_eq!(components.render(DEFAULT_FORMAT).unwrap(), "2.14.3");
+        assert_eq!(components.render("{tag}.{commits}+{short_sha}").unwrap(), "2.1.0.5+0123456");
+        assert_eq!(components.render("{yyyy}.{mm}.{patch}").unwrap(), "2024.03.3");
+        assert_eq!(components.render("{major}.{minor}.{patch}{pre}").unwrap(), "2.14.3");
+    }
+
+    #[test]
+    fn test_fold_semver() {
+        assert_eq!(fold_semver("1.0.5.100").unwrap(), "1.0.5+100");
+        assert_eq!(fold_semver("1.0.5.100+abc").unwrap(), "1.0.5+100.abc");
+        assert_eq!(fold_semver("v2024.03.7").unwrap(), "2024.3.7");
+        assert_eq!(fold_semver("24.67").unwrap(), "24.67.0");
+        assert_eq!(fold_semver("1.2.3-rc.01+feature/x").unwrap(), "1.2.3-rc.1+feature-x");
+        assert!(fold_semver("main.5").is_err());
+        assert!(fold_semver("1.2.3+").is_err());
+
+        assert!(check_semver("1.2.3-rc.1+abc").is_ok());
+        assert!(check_semver("1.0.5.100").is_err());
+
+        assert!(validate_semver_template("{tag}.{commits}", Some("{branch}")).is_ok());
+        assert!(validate_semver_template("{branch}.{patch}", None).is_err());
+        assert!(validate_semver_template("{short_sha}", None).is_err());
+    }
+
+    #[test]
+    fn test_validate_format() {
+        assert!(validate_format("{yy}.{doy}").is_ok());
+        assert!(validate_format("{major}.{build}").is_err());
+        assert!(validate_format("1.0.0").is_err());
+        assert!(validate_template("ci").is_ok());
+    }
+}
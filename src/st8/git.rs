@@ -0,0 +1,706 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Failures of repository access that callers may want to tell apart
+#[derive(Debug)]
+pub enum GitError {
+    NotARepository,
+    /// The repository exists but HEAD does not point at a commit yet
+    NoCommits,
+    /// HEAD is not on a branch
+    DetachedHead,
+    Other(String),
+}
+
+impl fmt::Display for GitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GitError::NotARepository => write!(f, "Not in a git repository"),
+            GitError::NoCommits => write!(f, "The repository has no commits yet"),
+            GitError::DetachedHead => write!(f, "HEAD is detached (not on a branch)"),
+            GitError::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for GitError {}
+
+impl From<git2::Error> for GitError {
+    fn from(e: git2::Error) -> Self {
+        match e.code() {
+            git2::ErrorCode::NotFound if e.class() == git2::ErrorClass::Repository => GitError::NotARepository,
+            git2::ErrorCode::UnbornBranch => GitError::NoCommits,
+            _ => GitError::Other(e.message().to_string()),
+        }
+    }
+}
+
+pub type GitResult<T> = std::result::Result<T, GitError>;
+
+enum Backend {
+    Library(git2::Repository),
+    /// The `git` executable, for repositories libgit2 cannot open
+    Cli,
+}
+
+thread_local! {
+    /// Where `GitRepo::discover` looks instead of the working directory, set
+    /// for the length of `with_root`
+    static DISCOVERY_ROOT: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+}
+
+/// Run `run` with `GitRepo::discover` finding the repository containing
+/// `root` rather than the working directory. Only this thread is affected,
+/// so library callers can work on several repositories at once.
+pub fn with_root<T>(root: &Path, run: impl FnOnce() -> T) -> T {
+    struct Restore(Option<PathBuf>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            DISCOVERY_ROOT.with(|cell| *cell.borrow_mut() = self.0.take());
+        }
+    }
+
+    let _restore = Restore(DISCOVERY_ROOT.with(|cell| cell.replace(Some(root.to_path_buf()))));
+    run()
+}
+
+/// The repository containing the current directory, read through libgit2
+/// with the `git` command line as a fallback
+pub struct GitRepo {
+    backend: Backend,
+    root: PathBuf,
+}
+
+impl GitRepo {
+    /// The repository containing the current directory, or the root set by
+    /// `with_root`
+    pub fn discover() -> GitResult<Self> {
+        if let Some(root) = DISCOVERY_ROOT.with(|cell| cell.borrow().clone()) {
+            return Self::discover_from(&root);
+        }
+        let cwd = std::env::current_dir().map_err(|e| GitError::Other(e.to_string()))?;
+        Self::discover_from(&cwd)
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub fn discover_from(path: &Path) -> GitResult<Self> {
+        match git2::Repository::discover(path) {
+            Ok(repo) => {
+                let Some(root) = repo.workdir().map(Path::to_path_buf) else {
+                    return Err(GitError::Other("Bare repositories are not supported".to_string()));
+                };
+                Ok(Self { backend: Backend::Library(repo), root })
+            }
+            Err(e) => match GitError::from(e) {
+                GitError::NotARepository => Err(GitError::NotARepository),
+                other => {
+                    // e.g. an extension or config libgit2 does not understand
+                    log::warn!("libgit2 could not open the repository ({}), using the git command", other);
+                    let root = cli_output(path, &["rev-parse", "--show-toplevel"])
+                        .map_err(|_| GitError::NotARepository)?;
+                    Ok(Self { backend: Backend::Cli, root: PathBuf::from(root.trim()) })
+                }
+            },
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Name of the checked out branch
+    pub fn current_branch(&self) -> GitResult<String> {
+        match &self.backend {
+            Backend::Library(repo) => {
+                if repo.head_detached()? {
+                    return Err(GitError::DetachedHead);
+                }
+                let head = repo.head()?;
+                Ok(head.shorthand().unwrap_or_default().to_string())
+            }
+            Backend::Cli => {
+                self.head_commit()?;
+                let branch = cli_output(&self.root, &["symbolic-ref", "--quiet", "--short", "HEAD"])
+                    .map_err(|_| GitError::DetachedHead)?;
+                Ok(branch.trim().to_string())
+            }
+        }
+    }
+
+    /// Id of the commit HEAD points at
+    pub fn head_commit(&self) -> GitResult<String> {
+        match &self.backend {
+            Backend::Library(repo) => Ok(repo.head()?.peel_to_commit()?.id().to_string()),
+            Backend::Cli => cli_output(&self.root, &["rev-parse", "--verify", "--quiet", "HEAD"])
+                .map(|id| id.trim().to_string())
+                .map_err(|_| GitError::NoCommits),
+        }
+    }
+
+    /// Commit id `rev` (a tag, branch or id) points at
+    pub fn resolve_commit(&self, rev: &str) -> GitResult<String> {
+        match &self.backend {
+            Backend::Library(repo) => Ok(repo.revparse_single(rev)?.peel_to_commit()?.id().to_string()),
+            Backend::Cli => cli_output(&self.root, &["rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", rev)])
+                .map(|id| id.trim().to_string()),
+        }
+    }
+
+    /// Whether commit `ancestor` is `descendant` or one of its ancestors
+    pub fn is_ancestor(&self, ancestor: &str, descendant: &str) -> GitResult<bool> {
+        match &self.backend {
+            Backend::Library(repo) => {
+                let ancestor = git2::Oid::from_str(ancestor)?;
+                let descendant = git2::Oid::from_str(descendant)?;
+                Ok(ancestor == descendant || repo.graph_descendant_of(descendant, ancestor)?)
+            }
+            Backend::Cli => Ok(cli_output(&self.root, &["merge-base", "--is-ancestor", ancestor, descendant]).is_ok()),
+        }
+    }
+
+    /// The repository's git directory, `.git` or a worktree's
+    pub fn git_dir(&self) -> GitResult<PathBuf> {
+        match &self.backend {
+            Backend::Library(repo) => Ok(repo.path().to_path_buf()),
+            Backend::Cli => {
+                let path = cli_output(&self.root, &["rev-parse", "--git-dir"])?;
+                Ok(self.root.join(path.trim()))
+            }
+        }
+    }
+
+    /// All tag names, highest version first
+    #[tracing::instrument(skip(self))]
+    pub fn tags(&self) -> GitResult<Vec<String>> {
+        let mut tags = match &self.backend {
+            Backend::Library(repo) => repo.tag_names(None)?
+                .iter()
+                .flatten()
+                .map(str::to_string)
+                .collect(),
+            Backend::Cli => cli_lines(&self.root, &["tag", "--list"])?,
+        };
+        sort_by_version_desc(&mut tags);
+        Ok(tags)
+    }
+
+    /// Tags whose commit is HEAD or one of its ancestors, highest version first
+    #[tracing::instrument(skip(self))]
+    pub fn tags_reachable_from_head(&self) -> GitResult<Vec<String>> {
+        match &self.backend {
+            Backend::Library(repo) => {
+                let head = repo.head()?.peel_to_commit()?.id();
+                let mut reachable = Vec::new();
+                for name in self.tags()? {
+                    let reference = format!("refs/tags/{}", name);
+                    let Ok(commit) = repo.revparse_single(&reference).and_then(|o| o.peel_to_commit()) else { continue };
+                    if commit.id() == head || repo.graph_descendant_of(head, commit.id())? {
+                        reachable.push(name);
+                    }
+                }
+                Ok(reachable)
+            }
+            Backend::Cli => {
+                self.head_commit()?;
+                let mut tags = cli_lines(&self.root, &["tag", "--merged", "HEAD"])?;
+                sort_by_version_desc(&mut tags);
+                Ok(tags)
+            }
+        }
+    }
+
+    /// The nearest tag reachable from HEAD, like `git describe --tags --abbrev=0`;
+    /// only tags matching the glob `pattern` when given
+    #[tracing::instrument(skip(self))]
+    pub fn nearest_tag(&self, pattern: Option<&str>) -> GitResult<Option<String>> {
+        match &self.backend {
+            Backend::Library(repo) => {
+                let mut options = git2::DescribeOptions::new();
+                options.describe_tags();
+                if let Some(pattern) = pattern {
+                    options.pattern(pattern);
+                }
+                let mut format = git2::DescribeFormatOptions::new();
+                format.abbreviated_size(0);
+                match repo.describe(&options) {
+                    Ok(describe) => Ok(Some(describe.format(Some(&format))?)),
+                    Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(None),
+                    Err(e) => Err(e.into()),
+                }
+            }
+            Backend::Cli => {
+                self.head_commit()?;
+                let mut args = vec!["describe", "--tags", "--abbrev=0"];
+                if let Some(pattern) = pattern {
+                    args.extend(["--match", pattern]);
+                }
+                Ok(cli_output(&self.root, &args)
+                    .ok()
+                    .map(|tag| tag.trim().to_string()))
+            }
+        }
+    }
+
+    /// (id, full message) of each commit after `since` (all commits when
+    /// `None`) up to HEAD, newest first
+    #[tracing::instrument(skip(self))]
+    pub fn commits_since(&self, since: Option<&str>) -> GitResult<Vec<(String, String)>> {
+        match &self.backend {
+            Backend::Library(repo) => {
+                let walk = self.walk(repo, since)?;
+                let mut commits = Vec::new();
+                for id in walk {
+                    let commit = repo.find_commit(id?)?;
+                    let message = String::from_utf8_lossy(commit.message_bytes()).trim().to_string();
+                    commits.push((commit.id().to_string(), message));
+                }
+                Ok(commits)
+            }
+            Backend::Cli => {
+                self.head_commit()?;
+                let log = cli_output(&self.root, &["log", "--format=%H%x1f%B%x1e", &cli_range(since)])?;
+                Ok(log.split('\x1e')
+                    .filter_map(|record| {
+                        let (hash, message) = record.trim_start().split_once('\x1f')?;
+                        Some((hash.to_string(), message.trim().to_string()))
+                    })
+                    .collect())
+            }
+        }
+    }
+
+    /// Like `commits_since`, but only the non-merge commits that changed
+    /// something under `path` (relative to the repository root)
+    #[tracing::instrument(skip(self))]
+    pub fn commits_touching(&self, since: Option<&str>, path: &Path) -> GitResult<Vec<(String, String)>> {
+        let pathspec = match path.to_string_lossy().trim_end_matches('/') {
+            "" => ".".to_string(),
+            other => other.to_string(),
+        };
+        match &self.backend {
+            Backend::Library(repo) => {
+                let mut options = git2::DiffOptions::new();
+                if pathspec != "." {
+                    options.pathspec(&pathspec);
+                }
+                let mut commits = Vec::new();
+                for id in self.walk(repo, since)? {
+                    let commit = repo.find_commit(id?)?;
+                    if commit.parent_count() > 1 {
+                        continue;
+                    }
+                    let parent_tree = match commit.parent(0) {
+                        Ok(parent) => Some(parent.tree()?),
+                        Err(_) => None,
+                    };
+                    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), Some(&mut options))?;
+                    if diff.deltas().len() > 0 {
+                        let message = String::from_utf8_lossy(commit.message_bytes()).trim().to_string();
+                        commits.push((commit.id().to_string(), message));
+                    }
+                }
+                Ok(commits)
+            }
+            Backend::Cli => {
+                self.head_commit()?;
+                let range = cli_range(since);
+                let log = cli_output(&self.root, &["log", "--no-merges", "--format=%H%x1f%B%x1e", &range, "--", &pathspec])?;
+                Ok(log.split('\x1e')
+                    .filter_map(|record| {
+                        let (hash, message) = record.trim_start().split_once('\x1f')?;
+                        Some((hash.to_string(), message.trim().to_string()))
+                    })
+                    .collect())
+            }
+        }
+    }
+
+    /// Number of commits after `since` (all commits when `None`) up to HEAD
+    #[tracing::instrument(skip(self))]
+    pub fn commit_count(&self, since: Option<&str>) -> GitResult<u32> {
+        match &self.backend {
+            Backend::Library(repo) => Ok(self.walk(repo, since)?.count() as u32),
+            Backend::Cli => {
+                self.head_commit()?;
+                cli_output(&self.root, &["rev-list", "--count", &cli_range(since)])?
+                    .trim()
+                    .parse()
+                    .map_err(|_| GitError::Other("Failed to parse commit count".to_string()))
+            }
+        }
+    }
+
+    /// Lines added plus lines removed by the non-merge commits after `since`,
+    /// like summing `git log --numstat`
+    #[tracing::instrument(skip(self))]
+    pub fn line_changes(&self, since: Option<&str>) -> GitResult<u32> {
+        match &self.backend {
+            Backend::Library(repo) => {
+                let mut total = 0u32;
+                for id in self.walk(repo, since)? {
+                    let commit = repo.find_commit(id?)?;
+                    if commit.parent_count() > 1 {
+                        continue;
+                    }
+                    let parent_tree = match commit.parent(0) {
+                        Ok(parent) => Some(parent.tree()?),
+                        Err(_) => None,
+                    };
+                    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+                    let stats = diff.stats()?;
+                    total = total
+                        .saturating_add(stats.insertions() as u32)
+                        .saturating_add(stats.deletions() as u32);
+                }
+                Ok(total)
+            }
+            Backend::Cli => {
+                self.head_commit()?;
+                let log = cli_output(&self.root, &["log", "--pretty=tformat:", "--numstat", &cli_range(since)])?;
+                let mut total = 0u32;
+                for line in log.lines() {
+                    let parts: Vec<&str> = line.split_whitespace().collect();
+                    if parts.len() >= 2 {
+                        if let (Ok(additions), Ok(deletions)) = (parts[0].parse::<u32>(), parts[1].parse::<u32>()) {
+                            total = total.saturating_add(additions).saturating_add(deletions);
+                        }
+                    }
+                }
+                Ok(total)
+            }
+        }
+    }
+
+    /// Paths, relative to the repository root, whose changes are staged
+    #[tracing::instrument(skip(self))]
+    pub fn staged_paths(&self) -> GitResult<Vec<String>> {
+        match &self.backend {
+            Backend::Library(repo) => {
+                let head_tree = repo.head().ok().and_then(|head| head.peel_to_tree().ok());
+                let diff = repo.diff_tree_to_index(head_tree.as_ref(), None, None)?;
+                Ok(delta_paths(&diff))
+            }
+            Backend::Cli => Ok(cli_output(&self.root, &["diff", "--cached", "--name-only"])?
+                .lines()
+                .map(str::to_string)
+                .collect()),
+        }
+    }
+
+    /// Paths, relative to the repository root, changed by the HEAD commit
+    pub fn head_commit_paths(&self) -> GitResult<Vec<String>> {
+        match &self.backend {
+            Backend::Library(repo) => {
+                let commit = repo.head()?.peel_to_commit()?;
+                let parent_tree = match commit.parent(0) {
+                    Ok(parent) => Some(parent.tree()?),
+                    Err(_) => None,
+                };
+                let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+                Ok(delta_paths(&diff))
+            }
+            Backend::Cli => {
+                self.head_commit()?;
+                Ok(cli_output(&self.root, &["diff-tree", "--no-commit-id", "--name-only", "-r", "--root", "HEAD"])?
+                    .lines()
+                    .map(str::to_string)
+                    .collect())
+            }
+        }
+    }
+
+    /// Where git looks for hooks: `core.hooksPath` when set (husky, lefthook),
+    /// otherwise the `hooks` directory of the shared git dir
+    pub fn hooks_dir(&self) -> GitResult<PathBuf> {
+        match &self.backend {
+            Backend::Library(repo) => match repo.config()?.get_path("core.hooksPath") {
+                Ok(path) => Ok(self.root.join(path)),
+                Err(_) => Ok(repo.commondir().join("hooks")),
+            },
+            Backend::Cli => {
+                let path = cli_output(&self.root, &["rev-parse", "--git-path", "hooks"])?;
+                Ok(self.root.join(path.trim()))
+            }
+        }
+    }
+
+    /// Working trees of the initialized submodules, nested ones included.
+    /// Always asks the git CLI, since libgit2 does not recurse.
+    pub fn submodules(&self) -> GitResult<Vec<PathBuf>> {
+        Ok(cli_lines(&self.root, &["submodule", "foreach", "--quiet", "--recursive", "echo \"$displaypath\""])?
+            .into_iter()
+            .map(|path| self.root.join(path))
+            .collect())
+    }
+
+    /// Whether tracked files have staged or unstaged changes (untracked files don't count)
+    #[tracing::instrument(skip(self))]
+    pub fn is_dirty(&self) -> GitResult<bool> {
+        match &self.backend {
+            Backend::Library(repo) => {
+                let mut options = git2::StatusOptions::new();
+                options.include_untracked(false).include_ignored(false);
+                Ok(!repo.statuses(Some(&mut options))?.is_empty())
+            }
+            Backend::Cli => Ok(!cli_output(&self.root, &["status", "--porcelain", "--untracked-files=no"])?
+                .trim()
+                .is_empty()),
+        }
+    }
+
+    /// Tags on `remote` (a name or URL), highest version first. Always asks
+    /// the git CLI, which has the user's credentials and transport setup.
+    #[tracing::instrument(skip(self))]
+    pub fn remote_tags(&self, remote: &str) -> GitResult<Vec<String>> {
+        let mut tags: Vec<String> = cli_lines(&self.root, &["ls-remote", "--tags", "--refs", remote])?
+            .into_iter()
+            .filter_map(|line| line.split_once("refs/tags/").map(|(_, name)| name.to_string()))
+            .collect();
+        sort_by_version_desc(&mut tags);
+        Ok(tags)
+    }
+
+    pub fn tag_exists(&self, name: &str) -> GitResult<bool> {
+        match &self.backend {
+            Backend::Library(repo) => Ok(repo.find_reference(&format!("refs/tags/{}", name)).is_ok()),
+            Backend::Cli => Ok(cli_output(&self.root, &["rev-parse", "--verify", "--quiet", &format!("refs/tags/{}", name)]).is_ok()),
+        }
+    }
+
+    /// Add `path` to the index
+    #[tracing::instrument(skip(self))]
+    pub fn stage(&self, path: &Path) -> GitResult<()> {
+        match &self.backend {
+            Backend::Library(repo) => {
+                let absolute = if path.is_absolute() {
+                    path.to_path_buf()
+                } else {
+                    std::env::current_dir().map_err(|e| GitError::Other(e.to_string()))?.join(path)
+                };
+                let root = self.root.canonicalize().unwrap_or_else(|_| self.root.clone());
+                let absolute = absolute.canonicalize().unwrap_or(absolute);
+                let relative = absolute.strip_prefix(&root)
+                    .map_err(|_| GitError::Other(format!("{} is outside the repository", path.display())))?;
+                let mut index = repo.index()?;
+                index.add_path(relative)?;
+                index.write()?;
+                Ok(())
+            }
+            Backend::Cli => {
+                cli_output(&self.root, &["add", "--", &path.to_string_lossy()])?;
+                Ok(())
+            }
+        }
+    }
+
+    fn walk<'r>(&self, repo: &'r git2::Repository, since: Option<&str>) -> GitResult<git2::Revwalk<'r>> {
+        let mut walk = repo.revwalk()?;
+        walk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+        walk.push_head()?;
+        if let Some(since) = since {
+            let base = repo.revparse_single(since)?.peel_to_commit()?;
+            walk.hide(base.id())?;
+        }
+        Ok(walk)
+    }
+}
+
+/// Run `read` against the current repository; outside a repository or before
+/// the first commit there is no history, so `empty` is returned instead
+pub fn read_history<T>(empty: T, read: impl FnOnce(&GitRepo) -> GitResult<T>) -> anyhow::Result<T> {
+    match GitRepo::discover().and_then(|repo| read(&repo)) {
+        Ok(value) => Ok(value),
+        Err(GitError::NotARepository | GitError::NoCommits) => Ok(empty),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn cli_range(since: Option<&str>) -> String {
+    match since {
+        Some(since) => format!("{}..HEAD", since),
+        None => "HEAD".to_string(),
+    }
+}
+
+/// The new path of each changed file, or the old one for deletions
+fn delta_paths(diff: &git2::Diff) -> Vec<String> {
+    diff.deltas()
+        .filter_map(|delta| delta.new_file().path().or_else(|| delta.old_file().path()))
+        .map(|path| path.to_string_lossy().to_string())
+        .collect()
+}
+
+#[tracing::instrument(skip_all, fields(args = %args.join(" ")))]
+fn cli_output(dir: &Path, args: &[&str]) -> GitResult<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| GitError::Other(format!("Failed to run git: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(GitError::Other(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    String::from_utf8(output.stdout)
+        .map_err(|_| GitError::Other(format!("Invalid UTF-8 in git {} output", args[0])))
+}
+
+fn cli_lines(dir: &Path, args: &[&str]) -> GitResult<Vec<String>> {
+    Ok(cli_output(dir, args)?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Order like `git tag --sort=-version:refname`: numeric runs compare as numbers
+fn sort_by_version_desc(tags: &mut [String]) {
+    fn key(tag: &str) -> Vec<(u64, String)> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut number = String::new();
+        for c in tag.chars() {
+            if c.is_ascii_digit() {
+                number.push(c);
+            } else {
+                if !number.is_empty() {
+                    parts.push((number.parse().unwrap_or(u64::MAX), std::mem::take(&mut text)));
+                    number.clear();
+                }
+                text.push(c);
+            }
+        }
+        parts.push((number.parse().unwrap_or(0), text));
+        parts
+    }
+    tags.sort_by_key(|tag| std::cmp::Reverse(key(tag)));
+}
+
+/// Repository fixtures for the tests of the st8 modules
+#[cfg(test)]
+pub(crate) mod test_repo {
+    use std::path::Path;
+    use std::process::Command;
+
+    /// Run git in `dir`, failing the test when it fails
+    pub fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git").args(args).current_dir(dir).output().unwrap().status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    /// A new repository in `dir` with a committer configured
+    pub fn init_repo(dir: &Path) {
+        git(dir, &["init", "-q"]);
+        git(dir, &["config", "user.name", "Test User"]);
+        git(dir, &["config", "user.email", "test@example.com"]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_repo::{git, init_repo};
+    use super::*;
+
+    #[test]
+    fn test_hooks_dir_respects_hooks_path() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        init_repo(temp_dir.path());
+
+        let repo = GitRepo::discover_from(temp_dir.path()).unwrap();
+        assert!(repo.hooks_dir().unwrap().ends_with(".git/hooks"));
+
+        git(temp_dir.path(), &["config", "core.hooksPath", ".husky"]);
+        let repo = GitRepo::discover_from(temp_dir.path()).unwrap();
+        assert_eq!(repo.hooks_dir().unwrap(), repo.root().join(".husky"));
+    }
+
+    #[test]
+    fn test_hooks_dir_in_worktrees_and_submodules() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let main = temp_dir.path().join("main");
+        let library = temp_dir.path().join("library");
+        for dir in [&main, &library] {
+            std::fs::create_dir_all(dir).unwrap();
+            init_repo(dir);
+            git(dir, &["commit", "-q", "--allow-empty", "-m", "first"]);
+        }
+        let main_hooks = GitRepo::discover_from(&main).unwrap().hooks_dir().unwrap();
+
+        // A linked worktree has a .git file and shares the main repository's hooks
+        let worktree = temp_dir.path().join("feature");
+        git(&main, &["worktree", "add", "-q", worktree.to_str().unwrap()]);
+        assert!(worktree.join(".git").is_file());
+        let repo = GitRepo::discover_from(&worktree).unwrap();
+        assert_eq!(repo.hooks_dir().unwrap().canonicalize().unwrap(), main_hooks.canonicalize().unwrap());
+
+        // A submodule keeps its hooks in the superproject's .git/modules
+        git(&main, &["-c", "protocol.file.allow=always", "submodule", "add", "-q", library.to_str().unwrap(), "lib"]);
+        let repo = GitRepo::discover_from(&main).unwrap();
+        let submodules = repo.submodules().unwrap();
+        assert_eq!(submodules.len(), 1);
+        assert!(submodules[0].ends_with("main/lib"));
+        let submodule = GitRepo::discover_from(&main.join("lib")).unwrap();
+        assert!(submodule.hooks_dir().unwrap().ends_with(".git/modules/lib/hooks"));
+    }
+
+    #[test]
+    fn test_version_sort() {
+        let mut tags = vec!["v1.9.0".to_string(), "v1.10.0".to_string(), "v1.2.3".to_string()];
+        sort_by_version_desc(&mut tags);
+        assert_eq!(tags, vec!["v1.10.0", "v1.9.0", "v1.2.3"]);
+    }
+
+    #[test]
+    fn test_no_commits_yet() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        init_repo(temp_dir.path());
+
+        let repo = GitRepo::discover_from(temp_dir.path()).unwrap();
+        assert!(matches!(repo.head_commit(), Err(GitError::NoCommits)));
+    }
+
+    #[test]
+    fn test_history_queries() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        init_repo(dir);
+        std::fs::write(dir.join("a.txt"), "one\ntwo\n").unwrap();
+        git(dir, &["add", "a.txt"]);
+        git(dir, &["commit", "-q", "-m", "first"]);
+        git(dir, &["tag", "v1.0.0"]);
+        std::fs::write(dir.join("a.txt"), "one\nthree\n").unwrap();
+        git(dir, &["commit", "-q", "-am", "feat: second"]);
+
+        let repo = GitRepo::discover_from(dir).unwrap();
+        assert_eq!(repo.commit_count(None).unwrap(), 2);
+        assert_eq!(repo.commit_count(Some("v1.0.0")).unwrap(), 1);
+        assert_eq!(repo.line_changes(None).unwrap(), 4);
+        assert_eq!(repo.line_changes(Some("v1.0.0")).unwrap(), 2);
+        assert_eq!(repo.nearest_tag(None).unwrap().as_deref(), Some("v1.0.0"));
+        assert_eq!(repo.nearest_tag(Some("release-*")).unwrap(), None);
+        assert_eq!(repo.tags_reachable_from_head().unwrap(), vec!["v1.0.0"]);
+        assert_eq!(repo.commits_since(Some("v1.0.0")).unwrap()[0].1, "feat: second");
+        assert_eq!(repo.commits_touching(None, Path::new("a.txt")).unwrap().len(), 2);
+        assert!(repo.commits_touching(Some("v1.0.0"), Path::new("b")).unwrap().is_empty());
+
+        assert!(repo.tag_exists("v1.0.0").unwrap());
+        assert!(!repo.tag_exists("v2.0.0").unwrap());
+        assert_eq!(repo.remote_tags(dir.to_str().unwrap()).unwrap(), vec!["v1.0.0"]);
+        assert!(!repo.is_dirty().unwrap());
+        std::fs::write(dir.join("a.txt"), "changed\n").unwrap();
+        assert!(repo.is_dirty().unwrap());
+        git(dir, &["checkout", "-q", "--", "a.txt"]);
+
+        let head = repo.head_commit().unwrap();
+        git(dir, &["checkout", "-q", &head]);
+        assert!(matches!(repo.current_branch(), Err(GitError::DetachedHead)));
+    }
+}
@@ -0,0 +1,193 @@
+use anyhow::Result;
+use sqlx::{Row, SqlitePool};
+use std::fs;
+use std::path::Path;
+
+use super::git::read_history;
+use super::lock::VersionLock;
+use super::preview::preview_version_update;
+use super::st8_common::{update_version_file, St8Config, VersionInfo};
+
+/// What wrote a version
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChangeSource {
+    /// `ws update`, usually from the git hook
+    Update,
+    /// `ws version bump`
+    Bump,
+    /// `ws version rollback`
+    Rollback,
+}
+
+impl std::str::FromStr for ChangeSource {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "update" => Ok(ChangeSource::Update),
+            "bump" => Ok(ChangeSource::Bump),
+            "rollback" => Ok(ChangeSource::Rollback),
+            _ => anyhow::bail!("Unknown version change source: {}", s),
+        }
+    }
+}
+
+impl std::fmt::Display for ChangeSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ChangeSource::Update => "update",
+            ChangeSource::Bump => "bump",
+            ChangeSource::Rollback => "rollback",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// One journaled version change
+#[derive(Debug, Clone)]
+pub struct VersionChange {
+    pub id: i64,
+    /// None for the first version written
+    pub old_version: Option<String>,
+    pub new_version: String,
+    /// HEAD when the change was made
+    pub commit: Option<String>,
+    /// Files written, relative to the project root where possible
+    pub files: Vec<String>,
+    pub source: ChangeSource,
+    /// Undone by a later rollback
+    pub rolled_back: bool,
+    pub recorded_at: String,
+}
+
+impl VersionChange {
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "id": self.id,
+            "old_version": self.old_version,
+            "new_version": self.new_version,
+            "commit": self.commit,
+            "files": self.files,
+            "source": self.source.to_string(),
+            "rolled_back": self.rolled_back,
+            "recorded_at": self.recorded_at
+        })
+    }
+}
+
+/// `update_version_file`, journaled and under the version lock: write
+/// `version_info` to the version and project files and record the change;
+/// returns whether anything changed
+#[tracing::instrument(skip_all, fields(version = %version_info.full_version))]
+pub fn apply_version(repo_root: &Path, version_info: &VersionInfo, config: &St8Config, source: ChangeSource) -> Result<bool> {
+    let _lock = VersionLock::acquire(repo_root)?;
+    let old_version = fs::read_to_string(&config.version_file).ok()
+        .map(|content| content.trim().to_string())
+        .filter(|content| !content.is_empty());
+    let files: Vec<String> = preview_version_update(version_info, config)?
+        .into_iter()
+        .map(|change| change.path.strip_prefix(repo_root).unwrap_or(&change.path).display().to_string())
+        .collect();
+
+    if !update_version_file(version_info, config)? {
+        return Ok(false);
+    }
+
+    let commit = read_history(None, |repo| repo.head_commit().map(Some))?;
+    with_pool(repo_root, |pool| async move {
+        sqlx::query(r#"
+            INSERT INTO version_history (old_version, new_version, commit_id, files, source)
+            VALUES (?, ?, ?, ?, ?)
+        "#)
+        .bind(old_version)
+        .bind(&version_info.full_version)
+        .bind(commit)
+        .bind(serde_json::to_string(&files)?)
+        .bind(source.to_string())
+        .execute(&pool)
+        .await?;
+        Ok(())
+    })?;
+    Ok(true)
+}
+
+/// Journaled changes, newest first
+pub fn load_history(repo_root: &Path, limit: Option<usize>) -> Result<Vec<VersionChange>> {
+    let limit = limit.map_or(-1, |limit| limit as i64);
+    with_pool(repo_root, |pool| async move {
+        let rows = sqlx::query(r#"
+            SELECT id, old_version, new_version, commit_id, files, source, rolled_back, recorded_at
+            FROM version_history
+            ORDER BY id DESC
+            LIMIT ?
+        "#)
+        .bind(limit)
+        .fetch_all(&pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(VersionChange {
+                    id: row.get("id"),
+                    old_version: row.get("old_version"),
+                    new_version: row.get("new_version"),
+                    commit: row.get("commit_id"),
+                    files: serde_json::from_str(&row.get::<String, _>("files")).unwrap_or_default(),
+                    source: row.get::<String, _>("source").parse()?,
+                    rolled_back: row.get("rolled_back"),
+                    recorded_at: row.get("recorded_at"),
+                })
+            })
+            .collect()
+    })
+}
+
+/// Restore the version before the latest change that has not been rolled
+/// back yet, across the version file and project files. Repeated rollbacks
+/// walk further back. Returns the change undone.
+pub fn rollback(repo_root: &Path, config: &St8Config) -> Result<VersionChange> {
+    let history = load_history(repo_root, None)?;
+    let change = history.into_iter()
+        .find(|change| change.source != ChangeSource::Rollback && !change.rolled_back)
+        .ok_or_else(|| anyhow::anyhow!("No version change to roll back"))?;
+    let previous = change.old_version.as_deref().ok_or_else(|| {
+        anyhow::anyhow!("{} is the first recorded version; there is nothing to roll back to", change.new_version)
+    })?;
+
+    apply_version(repo_root, &VersionInfo::from_version(previous), config, ChangeSource::Rollback)?;
+    let id = change.id;
+    with_pool(repo_root, |pool| async move {
+        sqlx::query("UPDATE version_history SET rolled_back = TRUE WHERE id = ?")
+            .bind(id)
+            .execute(&pool)
+            .await?;
+        Ok(())
+    })?;
+    log::info!("Rolled back version {} to {}", change.new_version, previous);
+    Ok(change)
+}
+
+pub(super) fn with_pool<T, F>(repo_root: &Path, query: impl FnOnce(SqlitePool) -> F) -> Result<T>
+where
+    F: std::future::Future<Output = Result<T>>,
+{
+    let db_path = repo_root.join(".ws/project.db");
+    let rt = tokio::runtime::Runtime::new()?;
+    rt.block_on(async {
+        let pool = super::super::entities::database::initialize_database(&db_path).await?;
+        query(pool).await
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_change_source_round_trip() {
+        for source in [ChangeSource::Update, ChangeSource::Bump, ChangeSource::Rollback] {
+            assert_eq!(source.to_string().parse::<ChangeSource>().unwrap(), source);
+        }
+        assert!("revert".parse::<ChangeSource>().is_err());
+    }
+}
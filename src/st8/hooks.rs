@@ -0,0 +1,339 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use super::git::GitRepo;
+
+pub const BLOCK_START: &str = "# === WS BLOCK START ===";
+pub const BLOCK_END: &str = "# === WS BLOCK END ===";
+
+/// First line of a hook file created by ws; never part of the block, which
+/// may be appended to a hook with its own
+pub const SHEBANG: &str = "#!/bin/sh";
+
+/// Line of the block recording the binary that installed it
+const BINARY_LINE: &str = "ws_bin=";
+
+/// Marker left in the git directory by a hook that ran over `max_hook_ms`
+pub const DEFERRED_FILE: &str = "ws-version-deferred";
+
+/// Message of the follow-up commit in separate commit mode
+pub const DEFAULT_COMMIT_MESSAGE: &str = "chore: bump version to {version}";
+
+/// How hooks commit the version files they update
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommitMode {
+    /// Stage them into the commit being made (amended in after the fact by
+    /// the post-commit hook)
+    #[default]
+    Include,
+    /// Commit them on their own after the commit being made
+    Separate,
+}
+
+impl std::str::FromStr for CommitMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "include" => Ok(CommitMode::Include),
+            "separate" => Ok(CommitMode::Separate),
+            _ => anyhow::bail!("Unknown commit mode: {} (expected include or separate)", s),
+        }
+    }
+}
+
+impl std::fmt::Display for CommitMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            CommitMode::Include => "include",
+            CommitMode::Separate => "separate",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The follow-up commit's message: `template` with `{version}` filled in,
+/// plus `[skip ci]` so CI does not run twice for one change
+pub fn bump_commit_message(template: &str, version: &str, skip_ci: bool) -> String {
+    let message = template.replace("{version}", version);
+    if skip_ci {
+        format!("{} [skip ci]", message)
+    } else {
+        message
+    }
+}
+
+/// Git hook that runs the version update
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HookType {
+    /// Update before committing and stage the result into the commit
+    PreCommit,
+    /// Update after committing and amend it in, so the commit carries its own version
+    PostCommit,
+    /// Update before pushing and stop the push when the version is not committed
+    PrePush,
+}
+
+impl HookType {
+    pub const ALL: [HookType; 3] = [HookType::PreCommit, HookType::PostCommit, HookType::PrePush];
+
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            HookType::PreCommit => "pre-commit",
+            HookType::PostCommit => "post-commit",
+            HookType::PrePush => "pre-push",
+        }
+    }
+
+    /// The WS block for this hook. It runs `exe`, or `ws` from PATH once
+    /// `exe` has moved, through a POSIX sh function so the same script works
+    /// with the sh bundled with Git for Windows. `WS_HOOK_AMENDING` is set
+    /// for the commits the hook makes itself, so they do not trigger it again.
+    /// With `require_bump`, the pre-push hook also stops pushes whose version
+    /// is not newer than the latest release tag on the remote being pushed to.
+    pub fn block(&self, exe: &Path, require_bump: bool) -> String {
+        let body = match self {
+            HookType::PreCommit => "ws update --git-add --hook pre-commit\n".to_string(),
+            HookType::PostCommit => "if [ -z \"$WS_HOOK_AMENDING\" ]; then\n\
+                 \x20   ws update --git-add --hook post-commit\n\
+                 \x20   if ! git diff --cached --quiet; then\n\
+                 \x20       WS_HOOK_AMENDING=1 git commit --amend --no-edit --no-verify --quiet\n\
+                 \x20   fi\n\
+                 fi\n".to_string(),
+            HookType::PrePush => format!(
+                "ws_head=$(git rev-parse HEAD)\n\
+                 ws update --git-add --hook pre-push\n\
+                 if [ \"$(git rev-parse HEAD)\" != \"$ws_head\" ]; then\n\
+                 \x20   echo \"ws: the version update was committed; push again to include it\" >&2\n\
+                 \x20   exit 1\n\
+                 fi\n\
+                 if ! git diff --cached --quiet; then\n\
+                 \x20   echo \"ws: version files were updated; commit them and push again\" >&2\n\
+                 \x20   exit 1\n\
+                 fi\n{}",
+                if require_bump { "ws version check-remote \"$1\" || exit 1\n" } else { "" }
+            ),
+        };
+        hook_script(exe, &body)
+    }
+}
+
+/// The sh WS block that runs `body`, with a shim defining `ws` as `exe`, or
+/// `ws` from PATH once `exe` has moved
+fn hook_script(exe: &Path, body: &str) -> String {
+    let shim = format!(
+        "{}\"{}\"\n\
+         [ -x \"$ws_bin\" ] || ws_bin=$(command -v ws 2>/dev/null)\n\
+         ws() {{\n\
+         \x20   if [ -n \"$ws_bin\" ]; then\n\
+         \x20       \"$ws_bin\" \"$@\"\n\
+         \x20   else\n\
+         \x20       echo \"ws: not found at its install location or on PATH; skipping (see 'ws git doctor')\" >&2\n\
+         \x20   fi\n\
+         }}\n",
+        BINARY_LINE,
+        shell_path(exe)
+    );
+    format!(
+        "{}\n# DO NOT EDIT THIS BLOCK MANUALLY\n# Use 'ws git uninstall' to remove this hook\n{}{}{}\n",
+        BLOCK_START, shim, body, BLOCK_END
+    )
+}
+
+/// Hook that annotates commit messages with the version, for `annotate_commit_msg`
+pub const ANNOTATE_HOOK: &str = "prepare-commit-msg";
+
+/// The WS block of the `prepare-commit-msg` hook, which passes the message
+/// file and its source on to `ws version annotate-commit-msg`; a failure
+/// there never stops the commit
+pub fn annotate_block(exe: &Path) -> String {
+    hook_script(exe, "ws version annotate-commit-msg \"$1\" \"$2\" || true\n")
+}
+
+/// `message` with `(version: <version>)` appended after its text and before
+/// the comment lines git adds for the editor; `None` when it already says so
+pub fn annotate_commit_message(message: &str, version: &str) -> Option<String> {
+    let annotation = format!("(version: {})", version);
+    if message.contains(&annotation) {
+        return None;
+    }
+    let lines: Vec<&str> = message.lines().collect();
+    let text_end = lines.iter().rposition(|line| !line.starts_with('#')).map_or(0, |i| i + 1);
+    let text = lines[..text_end].join("\n");
+    let text = text.trim_end();
+    let mut annotated = if text.is_empty() {
+        // An empty subject line is left for the editor
+        format!("\n\n{}\n", annotation)
+    } else {
+        format!("{}\n\n{}\n", text, annotation)
+    };
+    for comment in &lines[text_end..] {
+        annotated.push_str(comment);
+        annotated.push('\n');
+    }
+    Some(annotated)
+}
+
+/// Record that a hook left its version update for the next run
+pub fn defer_update(repo: &GitRepo) -> Result<()> {
+    std::fs::write(repo.git_dir()?.join(DEFERRED_FILE), chrono::Utc::now().to_rfc3339())?;
+    Ok(())
+}
+
+/// Whether an earlier hook left its version update for this run; clears the marker
+pub fn take_deferred(repo: &GitRepo) -> bool {
+    repo.git_dir().is_ok_and(|dir| std::fs::remove_file(dir.join(DEFERRED_FILE)).is_ok())
+}
+
+/// `path` as sh sees it: Windows paths such as `C:\tools\ws.exe` become
+/// `/c/tools/ws.exe`, the form Git for Windows' sh understands
+pub fn shell_path(path: &Path) -> String {
+    let path = path.display().to_string();
+    let bytes = path.as_bytes();
+    if bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && (bytes[2] == b'\\' || bytes[2] == b'/') {
+        format!("/{}/{}", (bytes[0] as char).to_ascii_lowercase(), path[3..].replace('\\', "/"))
+    } else {
+        path.replace('\\', "/")
+    }
+}
+
+/// The inverse of `shell_path` on Windows; other paths are returned as they are
+fn native_path(path: &str) -> PathBuf {
+    let bytes = path.as_bytes();
+    if cfg!(windows) && bytes.len() >= 3 && bytes[0] == b'/' && bytes[1].is_ascii_alphabetic() && bytes[2] == b'/' {
+        PathBuf::from(format!("{}:\\{}", (bytes[1] as char).to_ascii_uppercase(), path[3..].replace('/', "\\")))
+    } else {
+        PathBuf::from(path)
+    }
+}
+
+/// What a hook's content says about how it runs ws
+#[derive(Debug, Clone, PartialEq)]
+pub enum HookBinary {
+    /// A current block, with the binary recorded at install time
+    Portable(PathBuf),
+    /// A block from before the shim, running this fixed path
+    Fixed(PathBuf),
+    /// No WS block
+    Missing,
+}
+
+impl HookBinary {
+    pub fn of(content: &str) -> Self {
+        let Some(start) = content.find(BLOCK_START) else { return HookBinary::Missing };
+        let block = &content[start..];
+        for line in block.lines() {
+            if let Some(recorded) = line.strip_prefix(BINARY_LINE) {
+                return HookBinary::Portable(native_path(recorded.trim_matches('"')));
+            }
+            if let Some((binary, _)) = line.trim().split_once(" update --git-add") {
+                return HookBinary::Fixed(PathBuf::from(binary));
+            }
+        }
+        HookBinary::Missing
+    }
+}
+
+/// The binary a hook would run: the recorded one while it exists, else `ws`
+/// from PATH, as the block's shim decides
+pub fn resolve_binary(recorded: &Path) -> Option<PathBuf> {
+    if recorded.is_file() {
+        return Some(recorded.to_path_buf());
+    }
+    let names: &[&str] = if cfg!(windows) { &["ws.exe", "ws"] } else { &["ws"] };
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|candidate| candidate.is_file())
+}
+
+impl std::str::FromStr for HookType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "pre-commit" => Ok(HookType::PreCommit),
+            "post-commit" => Ok(HookType::PostCommit),
+            "pre-push" => Ok(HookType::PrePush),
+            _ => anyhow::bail!("Unknown hook type: {} (expected pre-commit, post-commit or pre-push)", s),
+        }
+    }
+}
+
+impl std::fmt::Display for HookType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.file_name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_hook_blocks() {
+        let exe = PathBuf::from("/usr/local/bin/ws");
+        let pre_commit = HookType::PreCommit.block(&exe, false);
+        assert!(pre_commit.starts_with(BLOCK_START));
+        assert!(pre_commit.contains("ws_bin=\"/usr/local/bin/ws\""));
+        assert!(pre_commit.contains("\nws update --git-add --hook pre-commit\n"));
+        assert!(pre_commit.contains(BLOCK_START) && pre_commit.contains(BLOCK_END));
+
+        let post_commit = HookType::PostCommit.block(&exe, false);
+        assert!(post_commit.contains("    WS_HOOK_AMENDING=1 git commit --amend --no-edit --no-verify"));
+
+        assert!(!HookType::PrePush.block(&exe, false).contains("check-remote"));
+        assert!(HookType::PrePush.block(&exe, true).contains("ws version check-remote \"$1\" || exit 1"));
+
+        assert_eq!("pre-push".parse::<HookType>().unwrap(), HookType::PrePush);
+        assert!("post-merge".parse::<HookType>().is_err());
+    }
+
+    #[test]
+    fn test_hook_binaries() {
+        assert_eq!(shell_path(Path::new(r"C:\Users\dev\.cargo\bin\ws.exe")), "/c/Users/dev/.cargo/bin/ws.exe");
+        assert_eq!(shell_path(Path::new("/usr/local/bin/ws")), "/usr/local/bin/ws");
+
+        let exe = PathBuf::from("/usr/local/bin/ws");
+        let portable = format!("#!/bin/sh\nmake lint\n{}", HookType::PreCommit.block(&exe, false));
+        assert_eq!(HookBinary::of(&portable), HookBinary::Portable(exe.clone()));
+        let fixed = format!("#!/bin/bash\n{}\n/opt/ws update --git-add\n{}\n", BLOCK_START, BLOCK_END);
+        assert_eq!(HookBinary::of(&fixed), HookBinary::Fixed(PathBuf::from("/opt/ws")));
+        assert_eq!(HookBinary::of("#!/bin/sh\nmake lint\n"), HookBinary::Missing);
+    }
+
+    #[test]
+    fn test_deferred_marker() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::process::Command::new("git").args(["init", "-q"]).current_dir(temp_dir.path()).output().unwrap();
+        let repo = GitRepo::discover_from(temp_dir.path()).unwrap();
+
+        assert!(!take_deferred(&repo));
+        defer_update(&repo).unwrap();
+        assert!(repo.git_dir().unwrap().join(DEFERRED_FILE).exists());
+        assert!(take_deferred(&repo));
+        assert!(!take_deferred(&repo));
+    }
+
+    #[test]
+    fn test_bump_commit_message() {
+        assert_eq!(bump_commit_message(DEFAULT_COMMIT_MESSAGE, "1.4.0", false), "chore: bump version to 1.4.0");
+        assert_eq!(bump_commit_message("release {version}", "2.0.0", true), "release 2.0.0 [skip ci]");
+        assert_eq!("separate".parse::<CommitMode>().unwrap(), CommitMode::Separate);
+        assert_eq!(CommitMode::default().to_string(), "include");
+    }
+
+    #[test]
+    fn test_annotate_commit_message() {
+        assert_eq!(annotate_commit_message("fix: parser\n", "1.2.3").unwrap(), "fix: parser\n\n(version: 1.2.3)\n");
+        let template = "\n# Please enter the commit message\n# Changes to be committed:\n";
+        assert_eq!(
+            annotate_commit_message(template, "1.2.3").unwrap(),
+            "\n\n(version: 1.2.3)\n# Please enter the commit message\n# Changes to be committed:\n"
+        );
+        assert_eq!(annotate_commit_message("fix: parser\n\n(version: 1.2.3)\n", "1.2.3"), None);
+        assert!(annotate_block(Path::new("/usr/local/bin/ws")).contains("\nws version annotate-commit-msg \"$1\" \"$2\" || true\n"));
+    }
+}
@@ -0,0 +1,68 @@
+use crate::file_lock::{holder, FileLock};
+use anyhow::Result;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Lock file kept in the project's `.ws` directory
+pub const LOCK_FILE: &str = "version.lock";
+
+/// How long to wait for another process to finish writing the version
+const LOCK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Advisory lock serializing version writes across processes, so hooks run
+/// by parallel commits (rebase scripts, CI bots) do not race on the version
+/// and project files. Released on drop, or by the operating system when the
+/// holder dies.
+#[derive(Debug)]
+pub struct VersionLock {
+    _lock: FileLock,
+}
+
+impl VersionLock {
+    pub fn acquire(repo_root: &Path) -> Result<Self> {
+        Self::acquire_with_timeout(repo_root, LOCK_TIMEOUT)
+    }
+
+    pub fn acquire_with_timeout(repo_root: &Path, timeout: Duration) -> Result<Self> {
+        let path = repo_root.join(".ws").join(LOCK_FILE);
+        let started = Instant::now();
+        let mut reported = false;
+
+        let lock = FileLock::acquire_timeout(&path, timeout, |holder| {
+            log::warn!("Version lock {} is held by process {}, waiting", path.display(), holder);
+            eprintln!("Waiting for another version update to finish (process {} holds {})", holder, path.display());
+            reported = true;
+        })?;
+        let Some(lock) = lock else {
+            anyhow::bail!(
+                "Timed out after {}s waiting for version lock {} (held by process {})",
+                timeout.as_secs(),
+                path.display(),
+                holder(&path)
+            );
+        };
+        if reported {
+            log::info!("Acquired version lock after {:?}", started.elapsed());
+        }
+        Ok(Self { _lock: lock })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_is_exclusive_until_dropped() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let lock = VersionLock::acquire(temp_dir.path()).unwrap();
+
+        let contended = VersionLock::acquire_with_timeout(temp_dir.path(), Duration::from_millis(100));
+        let message = contended.unwrap_err().to_string();
+        assert!(message.contains(&format!("held by process {}", std::process::id())), "{}", message);
+
+        drop(lock);
+        assert!(temp_dir.path().join(".ws").join(LOCK_FILE).exists());
+        assert!(VersionLock::acquire_with_timeout(temp_dir.path(), Duration::from_millis(100)).is_ok());
+    }
+}
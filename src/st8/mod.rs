@@ -1,5 +1,37 @@
+pub mod api;
+pub mod cache;
+pub mod changelog;
+pub mod channels;
+pub mod ci;
+pub mod config_file;
+pub mod conventional;
+pub mod embed;
+pub mod format;
+pub mod git;
+pub mod history;
+pub mod hooks;
+pub mod lock;
+pub mod npm;
+pub mod packages;
+pub mod policy;
+pub mod preview;
 pub mod st8_common;
+pub mod tags;
 pub mod templates;
 
-pub use st8_common::{St8Config, VersionInfo, detect_project_files, ProjectFile, ProjectFileType, update_version_file};
+pub use st8_common::{St8Config, DowngradePolicy, VersionInfo, VersionSource, detect_project_files, ProjectFile, ProjectFileType, update_version_file, current_version, version_files};
+pub use changelog::{ChangelogConfig, update_changelog};
+pub use channels::{ChannelConfig, ChannelVersion};
+pub use ci::CiProvider;
+pub use embed::{BuildInfo, EmbedLanguage};
+pub use format::{VersionComponents, DEFAULT_FORMAT};
+pub use git::{GitError, GitRepo};
+pub use history::{ChangeSource, VersionChange};
+pub use hooks::{CommitMode, HookType};
+pub use lock::VersionLock;
+pub use npm::NpmWorkspaceMode;
+pub use packages::{PackageConfig, PackageVersion, analyze_packages, update_packages};
+pub use preview::{FileChange, preview_version_update};
+pub use tags::TagScheme;
+pub use conventional::{BumpDecision, BumpLevel, ConventionalCommit, SemVer, VersionStrategy};
 pub use templates::{TemplateManager, TemplateConfig};
\ No newline at end of file
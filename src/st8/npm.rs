@@ -0,0 +1,152 @@
+//! Which `package.json` files of an npm workspace take the version: the
+//! root's `workspaces` globs (or `workspaces.packages`) name the members.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use super::policy::path_matcher;
+
+/// How a version bump treats the `package.json` files of an npm workspace
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NpmWorkspaceMode {
+    /// Only the root `package.json`; members keep their versions
+    #[default]
+    RootOnly,
+    /// The root and every member share the version
+    All,
+    /// Neither: members are released on their own as `packages`, and the
+    /// root (normally private) has no version of its own to bump
+    Independent,
+}
+
+impl std::str::FromStr for NpmWorkspaceMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "root-only" => Ok(NpmWorkspaceMode::RootOnly),
+            "all" => Ok(NpmWorkspaceMode::All),
+            "independent" => Ok(NpmWorkspaceMode::Independent),
+            _ => anyhow::bail!("Unknown npm workspace mode: {} (expected root-only, all or independent)", s),
+        }
+    }
+}
+
+impl std::fmt::Display for NpmWorkspaceMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            NpmWorkspaceMode::RootOnly => "root-only",
+            NpmWorkspaceMode::All => "all",
+            NpmWorkspaceMode::Independent => "independent",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The member patterns of the workspace rooted at `root`; empty when its
+/// `package.json` declares no workspaces
+pub fn workspace_patterns(root: &Path) -> Result<Vec<String>> {
+    let manifest_path = root.join("package.json");
+    if !manifest_path.is_file() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(&manifest_path)
+        .with_context(|| format!("Failed to read {}", manifest_path.display()))?;
+    let manifest: serde_json::Value = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse {}", manifest_path.display()))?;
+
+    // Yarn also accepts `{"packages": [...], "nohoist": [...]}`
+    let workspaces = manifest.get("workspaces");
+    let patterns = workspaces.and_then(|w| w.get("packages")).or(workspaces)
+        .and_then(|w| w.as_array())
+        .map(|patterns| patterns.iter()
+            .filter_map(|p| p.as_str())
+            .map(|p| p.trim_start_matches("./").trim_end_matches('/').to_string())
+            .filter(|p| !p.is_empty())
+            .collect())
+        .unwrap_or_default();
+    Ok(patterns)
+}
+
+/// The `package.json` of every workspace member under `root`, sorted
+pub fn workspace_members(root: &Path) -> Result<Vec<PathBuf>> {
+    let patterns = workspace_patterns(root)?;
+    if patterns.is_empty() {
+        return Ok(Vec::new());
+    }
+    let matcher = path_matcher(root, &patterns)?;
+
+    let mut members: Vec<PathBuf> = WalkDir::new(root)
+        .min_depth(1)
+        .into_iter()
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            !(entry.file_type().is_dir() && (name == "node_modules" || name.starts_with('.')))
+        })
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_dir())
+        .filter(|entry| {
+            let relative = entry.path().strip_prefix(root).unwrap_or(entry.path());
+            matcher.matched(relative, true).is_ignore()
+        })
+        .map(|entry| entry.path().join("package.json"))
+        .filter(|manifest| manifest.is_file())
+        .collect();
+    members.sort();
+    Ok(members)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_package(dir: &Path, content: &str) {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(dir.join("package.json"), content).unwrap();
+    }
+
+    #[test]
+    fn test_workspace_members() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        write_package(root, r#"{"name": "root", "version": "1.0.0", "workspaces": ["packages/*", "./tools/cli"]}"#);
+        write_package(&root.join("packages/a"), r#"{"name": "a", "version": "1.0.0"}"#);
+        write_package(&root.join("packages/b"), r#"{"name": "b", "version": "0.3.0"}"#);
+        write_package(&root.join("packages/a/node_modules/dep"), r#"{"name": "dep", "version": "9.9.9"}"#);
+        write_package(&root.join("tools/cli"), r#"{"name": "cli", "version": "1.0.0"}"#);
+        write_package(&root.join("tools/other"), r#"{"name": "other", "version": "1.0.0"}"#);
+        fs::create_dir_all(root.join("packages/empty")).unwrap();
+
+        let members = workspace_members(root).unwrap();
+        assert_eq!(members, vec![
+            root.join("packages/a/package.json"),
+            root.join("packages/b/package.json"),
+            root.join("tools/cli/package.json"),
+        ]);
+    }
+
+    #[test]
+    fn test_yarn_workspace_packages_and_exclusions() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        write_package(root, r#"{"private": true, "workspaces": {"packages": ["packages/*", "!packages/internal"]}}"#);
+        write_package(&root.join("packages/a"), r#"{"name": "a"}"#);
+        write_package(&root.join("packages/internal"), r#"{"name": "internal"}"#);
+
+        assert_eq!(workspace_members(root).unwrap(), vec![root.join("packages/a/package.json")]);
+    }
+
+    #[test]
+    fn test_no_workspaces() {
+        let temp_dir = TempDir::new().unwrap();
+        write_package(temp_dir.path(), r#"{"name": "single", "version": "1.0.0"}"#);
+        assert!(workspace_members(temp_dir.path()).unwrap().is_empty());
+        assert_eq!("all".parse::<NpmWorkspaceMode>().unwrap(), NpmWorkspaceMode::All);
+        assert!("members".parse::<NpmWorkspaceMode>().is_err());
+    }
+}
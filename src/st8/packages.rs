@@ -0,0 +1,264 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::conventional::{BumpDecision, BumpLevel, SemVer, VersionStrategy};
+use super::git::{read_history, GitRepo};
+use super::preview::FileChange;
+use super::st8_common::{detect_file_type, detect_project_files, update_project_files, updated_project_file_content, ProjectFile, VersionInfo};
+
+/// One independently versioned package of a monorepo
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackageConfig {
+    pub name: String,
+    /// Directory of the package, relative to the repository root
+    pub path: String,
+    /// Relative to the package directory
+    #[serde(default = "default_version_file")]
+    pub version_file: String,
+    #[serde(default = "default_auto_detect")]
+    pub auto_detect_project_files: bool,
+    /// Relative to the package directory
+    #[serde(default)]
+    pub project_files: Vec<String>,
+    /// Prefix of the package's release tags; `<name>/v` when unset
+    #[serde(default)]
+    pub tag_prefix: Option<String>,
+}
+
+fn default_version_file() -> String {
+    "version.txt".to_string()
+}
+
+fn default_auto_detect() -> bool {
+    true
+}
+
+impl PackageConfig {
+    pub fn new(name: &str, path: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            path: path.trim_end_matches('/').to_string(),
+            version_file: default_version_file(),
+            auto_detect_project_files: default_auto_detect(),
+            project_files: Vec::new(),
+            tag_prefix: None,
+        }
+    }
+
+    pub fn tag_prefix(&self) -> String {
+        self.tag_prefix.clone().unwrap_or_else(|| format!("{}/v", self.name))
+    }
+
+    pub fn tag_name(&self, version: &SemVer) -> String {
+        format!("{}{}", self.tag_prefix(), version)
+    }
+
+    pub fn version_file_path(&self, repo_root: &Path) -> PathBuf {
+        repo_root.join(&self.path).join(&self.version_file)
+    }
+
+    /// The version file, version detection and project files of this package
+    pub fn files(&self, repo_root: &Path) -> Vec<PathBuf> {
+        let package_root = repo_root.join(&self.path);
+        let mut files = vec![self.version_file_path(repo_root)];
+        files.extend(self.project_files(&package_root).into_iter().map(|f| f.path));
+        files.retain(|f| f.exists());
+        files
+    }
+
+    fn project_files(&self, package_root: &Path) -> Vec<ProjectFile> {
+        let mut files = Vec::new();
+        if self.auto_detect_project_files {
+            files.extend(detect_project_files(package_root).unwrap_or_default());
+        }
+        for file in &self.project_files {
+            let path = package_root.join(file);
+            if !path.exists() {
+                eprintln!("Warning: Configured project file not found: {}", path.display());
+                continue;
+            }
+            if let Some(file_type) = detect_file_type(&path) {
+                files.push(ProjectFile { path, file_type });
+            }
+        }
+        files
+    }
+}
+
+/// Where a package stands: its last release and the commits touching it since
+#[derive(Debug, Clone)]
+pub struct PackageVersion {
+    pub name: String,
+    pub path: String,
+    /// The version file, falling back to the last release
+    pub current: SemVer,
+    pub decision: BumpDecision,
+}
+
+impl PackageVersion {
+    /// Read the last `<tag prefix><version>` tag and the commits under the
+    /// package directory since it
+    pub fn analyze(package: &PackageConfig, repo_root: &Path, strategy: VersionStrategy) -> Result<Self> {
+        let prefix = package.tag_prefix();
+        let last_tag = read_history(None, |repo| {
+            Ok(repo.tags_reachable_from_head()?.into_iter().find(|tag| {
+                tag.strip_prefix(&prefix)
+                    .and_then(SemVer::parse)
+                    .is_some_and(|v| v.pre.is_none())
+            }))
+        })?;
+        let released = last_tag.as_deref()
+            .and_then(|tag| tag.strip_prefix(&prefix))
+            .and_then(SemVer::parse);
+        let in_file = fs::read_to_string(package.version_file_path(repo_root))
+            .ok()
+            .and_then(|content| SemVer::parse(&content));
+
+        let commits = read_history(Vec::new(), |repo| {
+            repo.commits_touching(last_tag.as_deref(), Path::new(&package.path))
+        })?;
+        let base_version = released.clone().or_else(|| in_file.clone()).unwrap_or_default();
+        let mut decision = BumpDecision::from_commits(last_tag, base_version, &commits);
+        // Under the count strategy every change is worth a release
+        if strategy == VersionStrategy::Count && !commits.is_empty() && decision.level == BumpLevel::None {
+            decision.level = BumpLevel::Patch;
+        }
+
+        Ok(Self {
+            name: package.name.clone(),
+            path: package.path.clone(),
+            current: in_file.or(released).unwrap_or_default(),
+            decision,
+        })
+    }
+
+    /// Whether commits changed the package since its last release
+    pub fn touched(&self) -> bool {
+        self.decision.commits_analyzed > 0
+    }
+
+    pub fn next_version(&self) -> SemVer {
+        if self.touched() {
+            self.decision.next_version()
+        } else {
+            self.current.clone()
+        }
+    }
+}
+
+/// Every configured package, analyzed
+pub fn analyze_packages(packages: &[PackageConfig], strategy: VersionStrategy) -> Result<Vec<PackageVersion>> {
+    let Ok(repo) = GitRepo::discover() else { return Ok(Vec::new()) };
+    packages.iter()
+        .map(|package| PackageVersion::analyze(package, repo.root(), strategy))
+        .collect()
+}
+
+/// Write `version` to the package's version and project files and stage
+/// them; returns the files written
+pub fn update_package(package: &PackageConfig, repo_root: &Path, version: &SemVer) -> Result<Vec<PathBuf>> {
+    let version_file = package.version_file_path(repo_root);
+    let current = fs::read_to_string(&version_file).unwrap_or_default();
+    if current.trim() == version.to_string() {
+        return Ok(Vec::new());
+    }
+
+    fs::write(&version_file, format!("{}\n", version))
+        .with_context(|| format!("Failed to write version to {}", version_file.display()))?;
+    GitRepo::discover()
+        .and_then(|repo| repo.stage(&version_file))
+        .map_err(|e| anyhow::anyhow!("Failed to stage {}: {}", version_file.display(), e))?;
+
+    let mut written = vec![version_file];
+    let project_files = package.project_files(&repo_root.join(&package.path));
+    let updated = update_project_files(&VersionInfo::from_semver(version), &project_files)?;
+    written.extend(updated.into_iter().map(PathBuf::from));
+    Ok(written)
+}
+
+/// The files `update_packages` would write for each touched package
+pub fn preview_packages(packages: &[PackageConfig], strategy: VersionStrategy) -> Result<Vec<(PackageVersion, Vec<FileChange>)>> {
+    let Ok(repo) = GitRepo::discover() else { return Ok(Vec::new()) };
+    let mut previews = Vec::new();
+    for package in packages {
+        let status = PackageVersion::analyze(package, repo.root(), strategy)?;
+        if !status.touched() {
+            continue;
+        }
+        let next = status.next_version();
+        let version_info = VersionInfo::from_semver(&next);
+        let mut changes = vec![FileChange::new(package.version_file_path(repo.root()), format!("{}\n", next))];
+        for project_file in package.project_files(&repo.root().join(&package.path)) {
+            let content = updated_project_file_content(&version_info, &project_file)?;
+            changes.push(FileChange::new(project_file.path, content));
+        }
+        changes.retain(FileChange::is_change);
+        previews.push((status, changes));
+    }
+    Ok(previews)
+}
+
+/// Bump every package touched since its last release; returns
+/// (package, new version) for each one bumped
+pub fn update_packages(packages: &[PackageConfig], strategy: VersionStrategy) -> Result<Vec<(String, SemVer)>> {
+    let Ok(repo) = GitRepo::discover() else { return Ok(Vec::new()) };
+    let mut bumped = Vec::new();
+    for package in packages {
+        let status = PackageVersion::analyze(package, repo.root(), strategy)?;
+        if !status.touched() {
+            continue;
+        }
+        let next = status.next_version();
+        if !update_package(package, repo.root(), &next)?.is_empty() {
+            log::info!("Updated package {} to {}", package.name, next);
+            bumped.push((package.name.clone(), next));
+        }
+    }
+    Ok(bumped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::st8::git::test_repo::{git, init_repo};
+
+    #[test]
+    fn test_package_defaults() {
+        let package: PackageConfig = serde_json::from_str(r#"{"name": "pkg-a", "path": "packages/a"}"#).unwrap();
+        assert_eq!(package.version_file, "version.txt");
+        assert!(package.auto_detect_project_files);
+        assert_eq!(package.tag_name(&SemVer::new(1, 2, 0)), "pkg-a/v1.2.0");
+    }
+
+    #[test]
+    fn test_only_touched_packages_are_bumped() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        init_repo(dir);
+        fs::create_dir_all(dir.join("a")).unwrap();
+        fs::create_dir_all(dir.join("b")).unwrap();
+        fs::write(dir.join("a/lib.rs"), "a").unwrap();
+        fs::write(dir.join("b/lib.rs"), "b").unwrap();
+        git(dir, &["add", "."]);
+        git(dir, &["commit", "-q", "-m", "initial"]);
+        git(dir, &["tag", "a/v1.0.0"]);
+        git(dir, &["tag", "b/v2.0.0"]);
+        fs::write(dir.join("a/lib.rs"), "a2").unwrap();
+        git(dir, &["commit", "-q", "-am", "feat(a): more a"]);
+
+        let a = PackageConfig::new("a", "a");
+        let b = PackageConfig::new("b", "b/");
+        let original_dir = std::env::current_dir().unwrap();
+        std::env::set_current_dir(dir).unwrap();
+        let statuses = analyze_packages(&[a, b], VersionStrategy::Conventional);
+        std::env::set_current_dir(original_dir).unwrap();
+
+        let statuses = statuses.unwrap();
+        assert!(statuses[0].touched());
+        assert_eq!(statuses[0].next_version().to_string(), "1.1.0");
+        assert!(!statuses[1].touched());
+        assert_eq!(statuses[1].next_version().to_string(), "2.0.0");
+    }
+}
@@ -0,0 +1,83 @@
+use anyhow::{Context, Result};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use regex::Regex;
+use std::path::Path;
+
+use super::git::read_history;
+use super::st8_common::St8Config;
+
+/// Why `ws update` should leave the version alone on this commit, or `None`
+/// when the configured branches and ignored paths allow a bump
+pub fn skip_reason(config: &St8Config) -> Result<Option<String>> {
+    if config.branches.is_empty() && config.ignore_paths.is_empty() {
+        return Ok(None);
+    }
+
+    if !config.branches.is_empty() {
+        let branch = read_history(None, |repo| Ok(repo.current_branch().ok()))?;
+        let branch = branch.as_deref().unwrap_or("HEAD");
+        if !config.branches.iter().any(|pattern| wildcard_matches(pattern, branch)) {
+            return Ok(Some(format!("branch {} is not one of {}", branch, config.branches.join(", "))));
+        }
+    }
+
+    if !config.ignore_paths.is_empty() {
+        // The commit being made, or after the fact (post-commit and pre-push
+        // hooks) the one just made
+        let (root, paths) = read_history((None, Vec::new()), |repo| {
+            let staged = repo.staged_paths()?;
+            let paths = if staged.is_empty() { repo.head_commit_paths()? } else { staged };
+            Ok((Some(repo.root().to_path_buf()), paths))
+        })?;
+        let Some(root) = root else { return Ok(None) };
+        if paths.is_empty() {
+            return Ok(None);
+        }
+
+        let matcher = path_matcher(&root, &config.ignore_paths)?;
+        if paths.iter().all(|path| matcher.matched_path_or_any_parents(path, false).is_ignore()) {
+            return Ok(Some(format!("only ignored paths changed ({})", config.ignore_paths.join(", "))));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Matcher for gitignore-style path patterns such as `docs/**` or `*.md`
+pub fn path_matcher(root: &Path, patterns: &[String]) -> Result<Gitignore> {
+    let mut builder = GitignoreBuilder::new(root);
+    for pattern in patterns {
+        builder.add_line(None, pattern)
+            .with_context(|| format!("Invalid ignore path pattern '{}'", pattern))?;
+    }
+    Ok(builder.build()?)
+}
+
+/// Whether `name` (a branch or tag) matches `pattern`, where `*` stands
+/// for any text
+pub fn wildcard_matches(pattern: &str, name: &str) -> bool {
+    let regex = format!("^{}$", regex::escape(pattern).replace(r"\*", ".*"));
+    Regex::new(&regex).is_ok_and(|regex| regex.is_match(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wildcard_matches() {
+        assert!(wildcard_matches("main", "main"));
+        assert!(!wildcard_matches("main", "maintenance"));
+        assert!(wildcard_matches("release/*", "release/1.4"));
+        assert!(!wildcard_matches("release/*", "feature/release"));
+    }
+
+    #[test]
+    fn test_path_matcher() {
+        let patterns = vec!["docs/**".to_string(), "*.md".to_string()];
+        let matcher = path_matcher(Path::new("/repo"), &patterns).unwrap();
+        assert!(matcher.matched_path_or_any_parents("docs/guide/setup.txt", false).is_ignore());
+        assert!(matcher.matched_path_or_any_parents("README.md", false).is_ignore());
+        assert!(!matcher.matched_path_or_any_parents("src/lib.rs", false).is_ignore());
+    }
+}
@@ -0,0 +1,97 @@
+use anyhow::Result;
+use std::fs;
+use std::path::PathBuf;
+
+use super::st8_common::{configured_project_files, updated_project_file_content, St8Config, VersionInfo};
+
+/// A file a version update would write, with its content before and after
+#[derive(Debug, Clone)]
+pub struct FileChange {
+    pub path: PathBuf,
+    pub before: String,
+    pub after: String,
+}
+
+impl FileChange {
+    /// The change writing `after` to `path` would make
+    pub fn new(path: PathBuf, after: String) -> Self {
+        let before = fs::read_to_string(&path).unwrap_or_default();
+        Self { path, before, after }
+    }
+
+    pub fn is_change(&self) -> bool {
+        self.before != self.after
+    }
+
+    /// Removed (`-`) and added (`+`) lines in file order, without context
+    pub fn diff_lines(&self) -> Vec<String> {
+        let old: Vec<&str> = self.before.lines().collect();
+        let new: Vec<&str> = self.after.lines().collect();
+
+        // Longest common subsequence table, built from the end
+        let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+        for i in (0..old.len()).rev() {
+            for j in (0..new.len()).rev() {
+                lcs[i][j] = if old[i] == new[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+
+        let mut lines = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < old.len() || j < new.len() {
+            if i < old.len() && j < new.len() && old[i] == new[j] {
+                i += 1;
+                j += 1;
+            } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+                lines.push(format!("-{}", old[i]));
+                i += 1;
+            } else {
+                lines.push(format!("+{}", new[j]));
+                j += 1;
+            }
+        }
+        lines
+    }
+}
+
+/// What `update_version_file` would write for `version_info`: the version
+/// file and the detected and configured project files that would change
+#[tracing::instrument(skip_all)]
+pub fn preview_version_update(version_info: &VersionInfo, config: &St8Config) -> Result<Vec<FileChange>> {
+    let mut changes = vec![FileChange::new(
+        PathBuf::from(&config.version_file),
+        format!("{}\n", version_info.full_version),
+    )];
+
+    for project_file in configured_project_files(config) {
+        match updated_project_file_content(version_info, &project_file) {
+            Ok(content) => changes.push(FileChange::new(project_file.path, content)),
+            Err(e) => log::warn!("Would fail to update {}: {}", project_file.path.display(), e),
+        }
+    }
+
+    changes.retain(FileChange::is_change);
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_lines() {
+        let change = FileChange {
+            path: PathBuf::from("Cargo.toml"),
+            before: "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n".to_string(),
+            after: "[package]\nname = \"demo\"\nversion = \"0.2.0\"\n".to_string(),
+        };
+        assert_eq!(change.diff_lines(), vec!["-version = \"0.1.0\"", "+version = \"0.2.0\""]);
+
+        let created = FileChange { path: PathBuf::from("version.txt"), before: String::new(), after: "1.0.0\n".to_string() };
+        assert_eq!(created.diff_lines(), vec!["+1.0.0"]);
+    }
+}
@@ -4,9 +4,96 @@ use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::process::Command;
 
-#[derive(Debug, Serialize, Deserialize)]
+use super::changelog::ChangelogConfig;
+use crate::config::WsConfig;
+use crate::logging::LoggingConfig;
+use super::channels::ChannelConfig;
+use super::conventional::{find_last_semver_tag, BumpDecision, SemVer, VersionStrategy};
+use super::format::{VersionComponents, DEFAULT_FORMAT};
+use super::git::{read_history, GitRepo};
+use super::hooks::{CommitMode, DEFAULT_COMMIT_MESSAGE};
+use super::npm::NpmWorkspaceMode;
+use super::packages::PackageConfig;
+use super::preview::{preview_version_update, FileChange};
+use super::tags::TagScheme;
+
+/// Where the count strategy takes the major version from
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VersionSource {
+    /// The major version stored in the project database
+    #[default]
+    Tag,
+    /// The version in the repository's root Cargo.toml
+    Cargo,
+    /// The version in the version file
+    File,
+}
+
+impl std::str::FromStr for VersionSource {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "tag" => Ok(VersionSource::Tag),
+            "cargo" => Ok(VersionSource::Cargo),
+            "file" => Ok(VersionSource::File),
+            _ => anyhow::bail!("Unknown version source: {} (expected tag, cargo or file)", s),
+        }
+    }
+}
+
+impl std::fmt::Display for VersionSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            VersionSource::Tag => "tag",
+            VersionSource::Cargo => "cargo",
+            VersionSource::File => "file",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// What an update does when the computed version is lower than one already
+/// in the version file or a project file
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DowngradePolicy {
+    /// Write the lower version
+    Allow,
+    /// Write the lower version with a warning
+    Warn,
+    /// Keep the files as they are
+    #[default]
+    Deny,
+}
+
+impl std::str::FromStr for DowngradePolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "allow" => Ok(DowngradePolicy::Allow),
+            "warn" => Ok(DowngradePolicy::Warn),
+            "deny" => Ok(DowngradePolicy::Deny),
+            _ => anyhow::bail!("Unknown downgrade policy: {} (expected allow, warn or deny)", s),
+        }
+    }
+}
+
+impl std::fmt::Display for DowngradePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            DowngradePolicy::Allow => "allow",
+            DowngradePolicy::Warn => "warn",
+            DowngradePolicy::Deny => "deny",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct St8Config {
     pub version: u32,
     pub enabled: bool,
@@ -15,12 +102,90 @@ pub struct St8Config {
     pub auto_detect_project_files: bool,
     #[serde(default)]
     pub project_files: Vec<String>,
+    #[serde(default)]
+    pub strategy: VersionStrategy,
+    /// Where the major version comes from; `cargo` and `file` make the
+    /// manifest or version file canonical
+    #[serde(default)]
+    pub source: VersionSource,
+    /// Template the version is rendered through, e.g. `{yyyy}.{mm}.{patch}`
+    #[serde(default = "default_format")]
+    pub format: String,
+    /// Label appended as `-<label>.N`, e.g. `rc`
+    #[serde(default)]
+    pub prerelease: Option<String>,
+    /// Template appended as `+<metadata>`, e.g. `{short_sha}`
+    #[serde(default)]
+    pub build_metadata: Option<String>,
+    /// Fold computed versions into strict `MAJOR.MINOR.PATCH[-PRE][+BUILD]`
+    /// form, e.g. `1.0.5.100` into `1.0.5+100`
+    #[serde(default)]
+    pub semver_strict: bool,
+    /// Whether an update may move the version files backwards, e.g. after
+    /// checking out a branch with an older tag
+    #[serde(default)]
+    pub downgrade_policy: DowngradePolicy,
+    #[serde(default)]
+    pub changelog: ChangelogConfig,
+    /// Independently versioned packages of a monorepo
+    #[serde(default)]
+    pub packages: Vec<PackageConfig>,
+    /// Branches `ws update` bumps on, e.g. `main`, `release/*`; all when empty
+    #[serde(default)]
+    pub branches: Vec<String>,
+    /// Gitignore-style patterns of paths whose changes alone do not bump, e.g. `docs/**`
+    #[serde(default)]
+    pub ignore_paths: Vec<String>,
+    /// Put in front of the version in release tags, e.g. `release-` or `myapp/v`
+    #[serde(default = "default_tag_prefix")]
+    pub tag_prefix: String,
+    /// Which tags are release tags, e.g. `myapp/*`; `<tag_prefix>*` when unset
+    #[serde(default)]
+    pub tag_pattern: Option<String>,
+    /// Whether hooks include version files in the commit or commit them after it
+    #[serde(default)]
+    pub commit_mode: CommitMode,
+    /// Message of the separate version commit, with `{version}` filled in
+    #[serde(default = "default_commit_message")]
+    pub commit_message: String,
+    /// Append `[skip ci]` to the separate version commit's message
+    #[serde(default)]
+    pub skip_ci: bool,
+    /// Have the `prepare-commit-msg` hook append `(version: X.Y.Z)` to the
+    /// message of commits that include the version files
+    #[serde(default)]
+    pub annotate_commit_msg: bool,
+    /// Time hooks may spend computing the version before they leave the
+    /// update to the next run; unlimited when unset
+    #[serde(default)]
+    pub max_hook_ms: Option<u64>,
+    /// Prerelease trains tagged alongside the stable versions, e.g. `beta`
+    #[serde(default)]
+    pub channels: Vec<ChannelConfig>,
+    /// Which `package.json` files of an npm workspace take the version
+    #[serde(default)]
+    pub npm_workspace_mode: NpmWorkspaceMode,
+    /// Level, rotation and location of the log file
+    #[serde(default)]
+    pub logging: LoggingConfig,
 }
 
 fn default_auto_detect() -> bool {
     true
 }
 
+fn default_format() -> String {
+    DEFAULT_FORMAT.to_string()
+}
+
+fn default_tag_prefix() -> String {
+    "v".to_string()
+}
+
+fn default_commit_message() -> String {
+    DEFAULT_COMMIT_MESSAGE.to_string()
+}
+
 impl Default for St8Config {
     fn default() -> Self {
         Self {
@@ -29,17 +194,236 @@ impl Default for St8Config {
             version_file: "version.txt".to_string(),
             auto_detect_project_files: true,
             project_files: Vec::new(),
+            strategy: VersionStrategy::Count,
+            source: VersionSource::Tag,
+            format: default_format(),
+            prerelease: None,
+            build_metadata: None,
+            semver_strict: false,
+            downgrade_policy: DowngradePolicy::Deny,
+            changelog: ChangelogConfig::default(),
+            packages: Vec::new(),
+            branches: Vec::new(),
+            ignore_paths: Vec::new(),
+            tag_prefix: default_tag_prefix(),
+            tag_pattern: None,
+            commit_mode: CommitMode::Include,
+            commit_message: default_commit_message(),
+            skip_ci: false,
+            annotate_commit_msg: false,
+            max_hook_ms: None,
+            channels: Vec::new(),
+            npm_workspace_mode: NpmWorkspaceMode::RootOnly,
+            logging: LoggingConfig::default(),
         }
     }
 }
 
+/// Comma-separated setting values, trimmed, without empty entries
+fn split_list(value: &str) -> Vec<String> {
+    value.split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// Keys accepted by `St8Config::show` and `St8Config::set`; `changelog.*`
+/// and `logging.*` keys go to the changelog and logging settings
+pub const CONFIG_KEYS: [&str; 22] = [
+    "enabled",
+    "version_file",
+    "auto_detect_project_files",
+    "project_files",
+    "strategy",
+    "source",
+    "format",
+    "prerelease",
+    "build_metadata",
+    "semver_strict",
+    "downgrade_policy",
+    "branches",
+    "ignore_paths",
+    "tag_prefix",
+    "tag_pattern",
+    "commit_mode",
+    "commit_message",
+    "skip_ci",
+    "annotate_commit_msg",
+    "max_hook_ms",
+    "channels",
+    "npm_workspace_mode",
+];
+
+/// Every key `St8Config::set` takes, nested ones included
+pub fn all_config_keys() -> Vec<String> {
+    CONFIG_KEYS.iter().map(|key| key.to_string())
+        .chain(ChangelogConfig::KEYS.iter().map(|key| format!("changelog.{}", key)))
+        .chain(LoggingConfig::KEYS.iter().map(|key| format!("logging.{}", key)))
+        .collect()
+}
+
 impl St8Config {
+    /// The value of one setting, as `show` prints it
+    pub fn get(&self, key: &str) -> Result<String> {
+        if let Some(changelog_key) = key.strip_prefix("changelog.") {
+            return self.changelog.get(changelog_key);
+        }
+        if let Some(logging_key) = key.strip_prefix("logging.") {
+            return self.logging.get(logging_key);
+        }
+        let value = match key {
+            "enabled" => self.enabled.to_string(),
+            "version_file" => self.version_file.clone(),
+            "auto_detect_project_files" => self.auto_detect_project_files.to_string(),
+            "project_files" => self.project_files.join(","),
+            "strategy" => self.strategy.to_string(),
+            "source" => self.source.to_string(),
+            "format" => self.format.clone(),
+            "prerelease" => self.prerelease.clone().unwrap_or_default(),
+            "build_metadata" => self.build_metadata.clone().unwrap_or_default(),
+            "semver_strict" => self.semver_strict.to_string(),
+            "downgrade_policy" => self.downgrade_policy.to_string(),
+            "branches" => self.branches.join(","),
+            "ignore_paths" => self.ignore_paths.join(","),
+            "tag_prefix" => self.tag_prefix.clone(),
+            "tag_pattern" => self.tag_scheme().pattern,
+            "commit_mode" => self.commit_mode.to_string(),
+            "commit_message" => self.commit_message.clone(),
+            "skip_ci" => self.skip_ci.to_string(),
+            "annotate_commit_msg" => self.annotate_commit_msg.to_string(),
+            "max_hook_ms" => self.max_hook_ms.map(|ms| ms.to_string()).unwrap_or_default(),
+            "channels" => self.channels.iter().map(|channel| channel.name.as_str()).collect::<Vec<_>>().join(","),
+            "npm_workspace_mode" => self.npm_workspace_mode.to_string(),
+            other => anyhow::bail!("Unknown config key: {} (expected one of {}, changelog.<key> or logging.<key>)", other, CONFIG_KEYS.join(", ")),
+        };
+        Ok(value)
+    }
+
+    /// Print one setting, or all of them when `key` is `None`
+    pub fn show(&self, key: Option<&str>) -> Result<()> {
+        match key {
+            Some(key) => println!("{}", self.get(key)?),
+            None => {
+                for key in CONFIG_KEYS {
+                    println!("{} = {}", key, self.get(key)?);
+                }
+                for key in ChangelogConfig::KEYS {
+                    println!("changelog.{} = {}", key, self.changelog.get(key)?);
+                }
+                for key in LoggingConfig::KEYS {
+                    println!("logging.{} = {}", key, self.logging.get(key)?);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Change one setting; an empty value clears the optional ones
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        if let Some(changelog_key) = key.strip_prefix("changelog.") {
+            return self.changelog.set(changelog_key, value);
+        }
+        if let Some(logging_key) = key.strip_prefix("logging.") {
+            return self.logging.set(logging_key, value);
+        }
+        let parse_bool = |value: &str| -> Result<bool> {
+            value.parse().with_context(|| format!("Expected true or false, got {}", value))
+        };
+        match key {
+            "enabled" => self.enabled = parse_bool(value)?,
+            "version_file" => {
+                if value.is_empty() {
+                    anyhow::bail!("version_file must not be empty");
+                }
+                self.version_file = value.to_string();
+            }
+            "auto_detect_project_files" => self.auto_detect_project_files = parse_bool(value)?,
+            "project_files" => self.project_files = split_list(value),
+            "strategy" => self.strategy = value.parse()?,
+            "source" => self.source = value.parse()?,
+            "format" => {
+                super::format::validate_format(value)?;
+                if self.semver_strict {
+                    super::format::validate_semver_template(value, self.build_metadata.as_deref())?;
+                }
+                self.format = value.to_string();
+            }
+            "prerelease" => self.prerelease = Some(value.to_string()).filter(|v| !v.is_empty()),
+            "build_metadata" => {
+                super::format::validate_template(value)?;
+                let build_metadata = Some(value.to_string()).filter(|v| !v.is_empty());
+                if self.semver_strict {
+                    super::format::validate_semver_template(&self.format, build_metadata.as_deref())?;
+                }
+                self.build_metadata = build_metadata;
+            }
+            "semver_strict" => {
+                let strict = parse_bool(value)?;
+                if strict {
+                    super::format::validate_semver_template(&self.format, self.build_metadata.as_deref())?;
+                }
+                self.semver_strict = strict;
+            }
+            "downgrade_policy" => self.downgrade_policy = value.parse()?,
+            "branches" => self.branches = split_list(value),
+            "ignore_paths" => {
+                let patterns = split_list(value);
+                super::policy::path_matcher(Path::new("."), &patterns)?;
+                self.ignore_paths = patterns;
+            }
+            "tag_prefix" => self.tag_prefix = value.to_string(),
+            "tag_pattern" => self.tag_pattern = Some(value.to_string()).filter(|v| !v.is_empty()),
+            "commit_mode" => self.commit_mode = value.parse()?,
+            "commit_message" => {
+                if value.trim().is_empty() {
+                    anyhow::bail!("commit_message must not be empty");
+                }
+                self.commit_message = value.to_string();
+            }
+            "skip_ci" => self.skip_ci = parse_bool(value)?,
+            "annotate_commit_msg" => self.annotate_commit_msg = parse_bool(value)?,
+            "max_hook_ms" => {
+                self.max_hook_ms = Some(value).filter(|v| !v.is_empty())
+                    .map(|v| v.parse().with_context(|| format!("Expected a number of milliseconds, got {}", v)))
+                    .transpose()?;
+            }
+            "channels" => {
+                // Channels set up in a config file keep their label and file
+                let existing = std::mem::take(&mut self.channels);
+                self.channels = split_list(value).into_iter()
+                    .map(|name| existing.iter().find(|c| c.name == name).cloned().unwrap_or_else(|| ChannelConfig::new(&name)))
+                    .collect();
+            }
+            "npm_workspace_mode" => self.npm_workspace_mode = value.parse()?,
+            other => anyhow::bail!("Unknown config key: {} (expected one of {}, changelog.<key> or logging.<key>)", other, CONFIG_KEYS.join(", ")),
+        }
+        Ok(())
+    }
+
+    /// Settings resolved from, lowest to highest: built-in defaults, the
+    /// user's config file (when the project's settings are created), the
+    /// project database and the repository's `.ws/version.*` file
     pub fn load(repo_root: &Path) -> Result<Self> {
         let db_path = repo_root.join(".ws/project.db");
         let rt = tokio::runtime::Runtime::new()?;
-        rt.block_on(async {
+        let mut config = rt.block_on(async {
             load_st8_config_from_db(&db_path).await
-        })
+        })?;
+        if let Some(path) = WsConfig::project_file(repo_root) {
+            config = super::config_file::apply_layer(&config, &WsConfig::read(&path)?.version, &path)?;
+        }
+        let mut config = super::config_file::apply_file(config, super::config_file::repo_config_file(repo_root))?;
+        for (key, value) in crate::config::version_env() {
+            config.set(&key, &value).with_context(|| format!("Invalid {}", crate::config::version_env_var(&key)))?;
+        }
+        Ok(config)
+    }
+
+    /// How stable release tags are named, for finding them and creating new
+    /// ones; the tags of channels are not among them
+    pub fn tag_scheme(&self) -> TagScheme {
+        TagScheme::new(&self.tag_prefix, self.tag_pattern.as_deref())
+            .excluding(self.channels.iter().map(|channel| channel.tag_pattern(&self.tag_prefix)).collect())
     }
 
     pub fn save(&self, repo_root: &Path) -> Result<()> {
@@ -67,19 +451,20 @@ pub struct VersionCalculationInfo {
     pub last_release_tag: Option<String>,
     pub git_root: Option<PathBuf>,
     pub calculation_method: String,
+    pub strategy: VersionStrategy,
+    /// Set for the conventional strategy
+    pub bump_decision: Option<BumpDecision>,
 }
 
 impl VersionInfo {
     pub fn calculate() -> Result<Self> {
-        let major_version = get_tag_version()?;
+        let tags = TagScheme::default();
+        let major_version = get_tag_version(&tags)?;
         let minor_version = get_commit_count_since_tag(&major_version)?;
         let patch_version = get_total_changes()?;
         
-        let full_version = format!("{}.{}.{}", 
-            major_version.strip_prefix('v').unwrap_or(&major_version),
-            minor_version,
-            patch_version
-        );
+        let full_version = VersionComponents::collect(&tags, major_version.as_str(), minor_version, patch_version)?
+            .render(DEFAULT_FORMAT)?;
 
         Ok(Self {
             major_version,
@@ -91,106 +476,254 @@ impl VersionInfo {
 
     /// Calculate version with database-stored major version
     pub fn calculate_with_major(major: u32) -> Result<Self> {
-        let minor_version = get_total_commit_count()?;
-        let patch_version = get_changes_since_last_release_tag(major)?;
-        
-        let full_version = format!("{}.{}.{}", major, minor_version, patch_version);
-        let major_version = format!("v{}", major);
+        Self::calculate_with_format(major, DEFAULT_FORMAT)
+    }
 
-        Ok(Self {
-            major_version,
-            minor_version,
-            patch_version,
+    /// Calculate version with database-stored major version, rendered
+    /// through `format`
+    pub fn calculate_with_format(major: u32, format: &str) -> Result<Self> {
+        let components = count_components(&TagScheme::default(), major)?;
+        Ok(Self::from_components(&components, components.render(format)?))
+    }
+
+    /// Calculate version with the configured strategy, format and suffixes;
+    /// `major` is only used by the count strategy, and only when the source
+    /// is `tag`; conventional versions continue from the last tag
+    pub fn calculate_for(config: &St8Config, major: u32) -> Result<Self> {
+        let tags = config.tag_scheme();
+        let components = match config.strategy {
+            VersionStrategy::Count => count_components(&tags, source_major(config)?.unwrap_or(major))?,
+            VersionStrategy::Conventional => {
+                let next = BumpDecision::analyze(&tags)?.next_version();
+                let mut components = VersionComponents::collect(&tags, next.major.to_string(), next.minor, next.patch)?;
+                components.pre = next.pre;
+                components
+            }
+        };
+        let mut full_version = append_suffixes(config, components.render(&config.format)?, &components)?;
+        if config.semver_strict {
+            full_version = super::format::fold_semver(&full_version)
+                .with_context(|| format!("Version format {} does not give a semantic version", config.format))?;
+        }
+        Ok(Self::from_components(&components, full_version))
+    }
+
+    fn from_components(components: &VersionComponents, full_version: String) -> Self {
+        Self {
+            major_version: format!("v{}", components.major),
+            minor_version: components.minor,
+            patch_version: components.patch,
             full_version,
-        })
+        }
+    }
+
+    pub fn from_semver(version: &SemVer) -> Self {
+        Self {
+            major_version: format!("v{}", version.major),
+            minor_version: version.minor,
+            patch_version: version.patch,
+            full_version: version.to_string(),
+        }
+    }
+
+    /// A version exactly as written, including custom formats that are not
+    /// semantic versions
+    pub fn from_version(version: &str) -> Self {
+        let mut version_info = Self::from_semver(&SemVer::parse(version).unwrap_or_default());
+        version_info.full_version = version.to_string();
+        version_info
     }
 
     /// Get calculation breakdown for debugging
-    pub fn get_calculation_info(major: u32) -> Result<VersionCalculationInfo> {
+    pub fn get_calculation_info(config: &St8Config, major: u32) -> Result<VersionCalculationInfo> {
+        let tags = config.tag_scheme();
+        let major = match config.strategy {
+            VersionStrategy::Count => source_major(config)?.unwrap_or(major),
+            VersionStrategy::Conventional => major,
+        };
+        let strategy = config.strategy;
         let total_commits = get_total_commit_count()?;
-        let changes_since_release = get_changes_since_last_release_tag(major)?;
-        let last_release_tag = find_last_release_tag(major)?;
+        let changes_since_release = get_changes_since_last_release_tag(&tags, major)?;
         let git_root = get_git_root().ok();
 
+        let (last_release_tag, calculation_method, bump_decision) = match strategy {
+            VersionStrategy::Count => (
+                find_last_release_tag(&tags, major)?,
+                format!("{} major + git commits + changes since release", match config.source {
+                    VersionSource::Tag => "Database",
+                    VersionSource::Cargo => "Cargo.toml",
+                    VersionSource::File => "Version file",
+                }),
+                None,
+            ),
+            VersionStrategy::Conventional => {
+                let decision = BumpDecision::analyze(&tags)?;
+                (
+                    decision.last_tag.clone(),
+                    "Last release tag + Conventional Commits since it".to_string(),
+                    Some(decision),
+                )
+            }
+        };
+
         Ok(VersionCalculationInfo {
             major_version: major,
             total_commits,
             changes_since_release,
             last_release_tag,
             git_root,
-            calculation_method: "Database major + git commits + changes since release".to_string(),
+            calculation_method,
+            strategy,
+            bump_decision,
         })
     }
 }
 
-fn get_tag_version() -> Result<String> {
-    let output = Command::new("git")
-        .args(["describe", "--tags", "--abbrev=0"])
-        .output();
-
-    match output {
-        Ok(output) if output.status.success() => {
-            let version = String::from_utf8(output.stdout)
-                .context("Invalid UTF-8 in git tag output")?
-                .trim()
-                .to_string();
-            Ok(version)
-        }
-        _ => Ok("v0".to_string()),
+/// The version the configured source holds, `None` for the `tag` source or
+/// when the manifest or version file does not exist yet
+pub fn source_version(config: &St8Config) -> Result<Option<SemVer>> {
+    let path = match config.source {
+        VersionSource::Tag => return Ok(None),
+        VersionSource::Cargo => get_git_root().unwrap_or_default().join("Cargo.toml"),
+        VersionSource::File => PathBuf::from(&config.version_file),
+    };
+    if !path.exists() {
+        log::warn!("{} does not exist; using the database major version", path.display());
+        return Ok(None);
     }
-}
 
-fn get_commit_count_since_tag(tag_version: &str) -> Result<u32> {
-    let output = if tag_version == "v0" {
-        Command::new("git")
-            .args(["rev-list", "--count", "HEAD"])
-            .output()
-            .context("Failed to run git rev-list command")?
-    } else {
-        let range = format!("{}..HEAD", tag_version);
-        Command::new("git")
-            .args(["rev-list", "--count", &range])
-            .output()
-            .context("Failed to run git rev-list command")?
+    let version = match config.source {
+        VersionSource::Cargo => cargo_manifest_version(&path)?,
+        _ => fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?
+            .trim()
+            .to_string(),
     };
+    SemVer::parse(&version)
+        .map(Some)
+        .with_context(|| format!("{} holds {}, which is not a version", path.display(), version))
+}
 
-    if !output.status.success() {
-        return Ok(0);
-    }
-
-    let count_str = String::from_utf8(output.stdout)
-        .context("Invalid UTF-8 in git rev-list output")?
-        .trim()
-        .to_string();
+fn source_major(config: &St8Config) -> Result<Option<u32>> {
+    Ok(source_version(config)?.map(|version| version.major))
+}
 
-    count_str.parse::<u32>()
-        .context("Failed to parse commit count")
+/// `[package].version` of a Cargo manifest, or `[workspace.package].version`
+/// when the package inherits it
+fn cargo_manifest_version(path: &Path) -> Result<String> {
+    let manifest: toml::Value = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?
+        .parse()
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    manifest.get("package").and_then(|p| p.get("version")).and_then(|v| v.as_str())
+        .or_else(|| manifest.get("workspace")
+            .and_then(|w| w.get("package"))
+            .and_then(|p| p.get("version"))
+            .and_then(|v| v.as_str()))
+        .map(str::to_string)
+        .with_context(|| format!("{} has no version", path.display()))
 }
 
-fn get_total_changes() -> Result<u32> {
-    let output = Command::new("git")
-        .args(["log", "--pretty=tformat:", "--numstat"])
-        .output()
-        .context("Failed to run git log command")?;
+fn count_components(tags: &TagScheme, major: u32) -> Result<VersionComponents> {
+    let minor_version = get_total_commit_count()?;
+    let patch_version = get_changes_since_last_release_tag(tags, major)?;
+    VersionComponents::collect(tags, major.to_string(), minor_version, patch_version)
+}
 
-    if !output.status.success() {
-        return Ok(0);
+/// Append the configured prerelease label and build metadata. The
+/// prerelease number carries over from the version file while the version
+/// stays the same, so it only moves through `ws version bump pre`.
+fn append_suffixes(config: &St8Config, version: String, components: &VersionComponents) -> Result<String> {
+    let mut version = version;
+    if let Some(label) = &config.prerelease {
+        let computed = SemVer::parse(&version);
+        if !computed.as_ref().is_some_and(|v| v.pre.is_some()) {
+            let previous = fs::read_to_string(&config.version_file).ok()
+                .and_then(|content| SemVer::parse(&content));
+            let number = match (computed, previous) {
+                (Some(computed), Some(previous))
+                    if (computed.major, computed.minor, computed.patch) == (previous.major, previous.minor, previous.patch)
+                        && previous.pre_label() == Some(label.as_str()) =>
+                {
+                    previous.pre.as_deref()
+                        .and_then(|pre| pre.rsplit('.').next())
+                        .and_then(|n| n.parse::<u32>().ok())
+                        .unwrap_or(1)
+                }
+                _ => 1,
+            };
+            version = format!("{}-{}.{}", version, label, number);
+        }
     }
+    if let Some(build) = &config.build_metadata {
+        version = format!("{}+{}", version, components.render_template(build)?);
+    }
+    Ok(version)
+}
 
-    let log_stat = String::from_utf8(output.stdout)
-        .context("Invalid UTF-8 in git log output")?;
-
-    let mut total = 0u32;
-    for line in log_stat.lines() {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() >= 2 {
-            if let (Ok(additions), Ok(deletions)) = (parts[0].parse::<u32>(), parts[1].parse::<u32>()) {
-                total = total.saturating_add(additions).saturating_add(deletions);
-            }
+/// The version file and project files `candidate` would move backwards,
+/// e.g. from `1.2.0-rc.3` to `1.2.0-rc.1`, with the version each holds now.
+/// Until the version file holds a version, project files are taken over as
+/// they are; those whose version cannot be read are left out.
+pub fn downgrades(config: &St8Config, candidate: &str) -> Vec<(PathBuf, SemVer)> {
+    let Some(candidate) = SemVer::parse(candidate) else { return Vec::new() };
+    let Some(version) = fs::read_to_string(&config.version_file).ok().and_then(|content| SemVer::parse(&content)) else {
+        return Vec::new();
+    };
+    let mut current = vec![(PathBuf::from(&config.version_file), version)];
+    for project_file in configured_project_files(config) {
+        if let Some(version) = project_file_version(&project_file) {
+            current.push((project_file.path, version));
         }
     }
+    current.retain(|(_, version)| version.cmp_precedence(&candidate) == std::cmp::Ordering::Greater);
+    current
+}
+
+/// The version a project file states, for the file types that state it in
+/// one place; `None` when it is missing, inherited or not a version
+pub(crate) fn project_file_version(project_file: &ProjectFile) -> Option<SemVer> {
+    let content = fs::read_to_string(&project_file.path).ok()?;
+    let capture = |pattern: &str| Regex::new(pattern).ok()?.captures(&content).map(|caps| caps[1].to_string());
+    let xml_text = |paths: &[&[&str]]| paths.iter().find_map(|path| xml_element_text(&content, path)).map(|range| content[range].trim().to_string());
+    let version = match project_file.file_type {
+        ProjectFileType::CargoToml => cargo_manifest_version(&project_file.path).ok()?,
+        ProjectFileType::PackageJson | ProjectFileType::ComposerJson => {
+            serde_json::from_str::<serde_json::Value>(&content).ok()?.get("version")?.as_str()?.to_string()
+        }
+        ProjectFileType::PyprojectToml => {
+            let parsed: toml::Value = content.parse().ok()?;
+            parsed.get("project").and_then(|project| project.get("version"))
+                .or_else(|| parsed.get("tool")?.get("poetry")?.get("version"))?
+                .as_str()?
+                .to_string()
+        }
+        ProjectFileType::SetupPy => capture(r#"\bversion\s*=\s*["']([^"'\n]*)["']"#)?,
+        ProjectFileType::PubspecYaml | ProjectFileType::ChartYaml => capture(r#"(?m)^version:[ \t]*["']?([^"'\s#]+)"#)?,
+        ProjectFileType::BuildGradle | ProjectFileType::BuildGradleKts => capture(r#"(?m)^[ \t]*version\s*=?\s*['"]([^'"\n]*)['"]"#)?,
+        ProjectFileType::MixExs => capture(r#"(?m)^[ \t]*@version\s+"([^"\n]*)""#)
+            .or_else(|| capture(r#"\bversion:\s*"([^"\n]*)""#))?,
+        ProjectFileType::PomXml => xml_text(&[&["project", "version"]])?,
+        ProjectFileType::Csproj | ProjectFileType::DirectoryBuildProps => {
+            xml_text(&[&["Project", "PropertyGroup", "Version"], &["Project", "PropertyGroup", "VersionPrefix"]])?
+        }
+        ProjectFileType::CargoLock | ProjectFileType::CMakeLists | ProjectFileType::SetupCfg => return None,
+    };
+    SemVer::parse(&version)
+}
+
+fn get_tag_version(tags: &TagScheme) -> Result<String> {
+    let tag = read_history(None, |repo| super::tags::nearest_tag(repo, tags))?;
+    Ok(tag.unwrap_or_else(|| "v0".to_string()))
+}
 
-    Ok(total)
+fn get_commit_count_since_tag(tag_version: &str) -> Result<u32> {
+    let since = (tag_version != "v0").then_some(tag_version);
+    read_history(0, |repo| repo.commit_count(since))
+}
+
+fn get_total_changes() -> Result<u32> {
+    read_history(0, |repo| super::cache::line_changes(repo, None))
 }
 
 pub fn update_version_file(version_info: &VersionInfo, config: &St8Config) -> Result<bool> {
@@ -213,20 +746,14 @@ pub fn update_version_file(version_info: &VersionInfo, config: &St8Config) -> Re
         .with_context(|| format!("Failed to write version to {}", version_file_path.display()))?;
 
     // Stage the version file
-    let output = Command::new("git")
-        .args(["add", version_file_path.to_str().unwrap()])
-        .output()
-        .context("Failed to stage version file")?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("Failed to stage version file: {}", stderr);
-    }
+    GitRepo::discover()
+        .and_then(|repo| repo.stage(&version_file_path))
+        .map_err(|e| anyhow::anyhow!("Failed to stage version file: {}", e))?;
 
     // Auto-detect and update project files if enabled
     if config.auto_detect_project_files {
         if let Ok(git_root) = get_git_root() {
-            match detect_project_files(&git_root) {
+            match detect_root_project_files(&git_root, config) {
                 Ok(project_files) => {
                     if !project_files.is_empty() {
                         match update_project_files(version_info, &project_files) {
@@ -289,9 +816,181 @@ pub fn update_version_file(version_info: &VersionInfo, config: &St8Config) -> Re
     Ok(true)
 }
 
-fn detect_file_type(path: &Path) -> Option<ProjectFileType> {
+/// The version last written to the version file, falling back to the last
+/// release tag and then 0.0.0
+pub fn current_version(config: &St8Config) -> Result<SemVer> {
+    let version_file_path = PathBuf::from(&config.version_file);
+    if let Ok(content) = fs::read_to_string(&version_file_path) {
+        if let Some(version) = SemVer::parse(&content) {
+            return Ok(version);
+        }
+    }
+
+    let tags = config.tag_scheme();
+    let tag = if is_git_repository() { find_last_semver_tag(&tags)? } else { None };
+    Ok(tag.as_deref().and_then(|tag| tags.version(tag)).unwrap_or_default())
+}
+
+/// The version project files should carry: the Cargo.toml version with the
+/// `cargo` source, otherwise the version file as written, or the last release
+/// tag when there is no version file
+pub fn canonical_version(config: &St8Config) -> Result<Option<String>> {
+    if config.source == VersionSource::Cargo {
+        if let Some(version) = source_version(config)? {
+            return Ok(Some(version.to_string()));
+        }
+    }
+    if let Ok(content) = fs::read_to_string(&config.version_file) {
+        let content = content.trim();
+        if !content.is_empty() {
+            return Ok(Some(content.to_string()));
+        }
+    }
+
+    let tags = config.tag_scheme();
+    let tag = if is_git_repository() { find_last_semver_tag(&tags)? } else { None };
+    Ok(tag.as_deref().and_then(|tag| tags.version(tag)).map(|version| version.to_string()))
+}
+
+/// What `ws version check` found: the canonical version and every problem
+/// with it or with the files that should carry it
+#[derive(Debug, Clone)]
+pub struct VersionCheck {
+    pub version: Option<String>,
+    pub problems: Vec<String>,
+}
+
+/// Check that the canonical version parses as a semantic version (in strict
+/// form with `semver_strict`), that the format can give one, and that the
+/// project files carry it
+pub fn check_version_files(config: &St8Config) -> Result<VersionCheck> {
+    let mut problems = Vec::new();
+    if config.semver_strict {
+        if let Err(e) = super::format::validate_semver_template(&config.format, config.build_metadata.as_deref()) {
+            problems.push(format!("{:#}", e));
+        }
+    }
+
+    let version = canonical_version(config)?;
+    match &version {
+        None => problems.push(format!("No version: {} is missing and there are no release tags", config.version_file)),
+        Some(version) => {
+            let valid = if config.semver_strict {
+                super::format::check_semver(version).map_err(|e| e.to_string())
+            } else {
+                SemVer::parse(version).map(|_| ()).ok_or_else(|| format!("{} is not a semantic version", version))
+            };
+            if let Err(problem) = valid {
+                problems.push(problem);
+            }
+            let git_root = get_git_root().unwrap_or_default();
+            for change in preview_version_update(&VersionInfo::from_version(version), config)? {
+                if change.path != Path::new(&config.version_file) {
+                    let path = change.path.strip_prefix(&git_root).unwrap_or(&change.path);
+                    problems.push(format!("{} does not carry version {}; run 'ws version sync'", path.display(), version));
+                }
+            }
+        }
+    }
+
+    Ok(VersionCheck { version, problems })
+}
+
+/// Files version management leaves in `repo_root` besides its hooks: the
+/// repository config file, the version lock, the line-change cache and the
+/// log directory, plus the version file with `include_version_file`. Only
+/// paths that exist are listed.
+pub fn version_artifacts(repo_root: &Path, config: &St8Config, include_version_file: bool) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = super::config_file::CONFIG_FILE_NAMES.iter()
+        .map(|name| repo_root.join(".ws").join(name))
+        .collect();
+    paths.push(repo_root.join(".ws").join(super::lock::LOCK_FILE));
+    if let Ok(git_dir) = GitRepo::discover_from(repo_root).and_then(|repo| repo.git_dir()) {
+        paths.push(git_dir.join(super::cache::CACHE_FILE));
+    }
+    if let Ok(log_dir) = crate::logging::log_dir(repo_root, config.logging.location) {
+        paths.push(log_dir);
+    }
+    if include_version_file {
+        paths.push(repo_root.join(&config.version_file));
+    }
+    paths.retain(|path| path.exists());
+    paths
+}
+
+/// The highest release tag on `remote`, with the version it names
+pub fn latest_remote_release(config: &St8Config, remote: &str) -> Result<Option<(String, SemVer)>> {
+    let tags = config.tag_scheme();
+    let remote_tags = GitRepo::discover()?
+        .remote_tags(remote)
+        .with_context(|| format!("Failed to list the tags of {}", remote))?;
+    Ok(remote_tags.into_iter()
+        .filter_map(|tag| tags.version(&tag).map(|version| (tag, version)))
+        .max_by(|a, b| a.1.cmp_precedence(&b.1)))
+}
+
+/// Rewrite the version file (when missing) and the detected and configured
+/// project files to the canonical version, without recomputing it. Returns
+/// the version and the changes, which are only previewed with `dry_run`.
+#[tracing::instrument(skip(config))]
+pub fn sync_version_files(config: &St8Config, dry_run: bool) -> Result<(String, Vec<FileChange>)> {
+    let version = canonical_version(config)?
+        .ok_or_else(|| anyhow::anyhow!("No version to sync: {} is missing and there are no release tags", config.version_file))?;
+    let changes = preview_version_update(&VersionInfo::from_version(&version), config)?;
+    if dry_run {
+        return Ok((version, changes));
+    }
+
+    for change in &changes {
+        fs::write(&change.path, &change.after)
+            .with_context(|| format!("Failed to write {}", change.path.display()))?;
+        if let Err(e) = GitRepo::discover().and_then(|repo| repo.stage(&change.path)) {
+            eprintln!("Warning: Failed to stage {}: {}", change.path.display(), e);
+        }
+    }
+    Ok((version, changes))
+}
+
+/// The detected and configured project files `update_version_file` updates
+pub(crate) fn configured_project_files(config: &St8Config) -> Vec<ProjectFile> {
+    let Ok(git_root) = get_git_root() else { return Vec::new() };
+    let mut files = Vec::new();
+    if config.auto_detect_project_files {
+        files.extend(detect_root_project_files(&git_root, config).unwrap_or_default());
+    }
+    for file_path in &config.project_files {
+        let path = git_root.join(file_path);
+        if path.exists() {
+            if let Some(file_type) = detect_file_type(&path) {
+                files.push(ProjectFile { path, file_type });
+            }
+        }
+    }
+    files
+}
+
+/// Every file `update_version_file` may write, for staging them together
+pub fn version_files(config: &St8Config) -> Vec<PathBuf> {
+    let mut files = vec![PathBuf::from(&config.version_file)];
+    if let Ok(git_root) = get_git_root() {
+        if config.auto_detect_project_files {
+            if let Ok(project_files) = detect_root_project_files(&git_root, config) {
+                files.extend(project_files.into_iter().map(|f| f.path));
+            }
+        }
+        files.extend(config.project_files.iter().map(|f| git_root.join(f)));
+        if config.changelog.enabled {
+            files.push(git_root.join(&config.changelog.path));
+        }
+    }
+    files.retain(|f| f.exists());
+    files
+}
+
+pub(crate) fn detect_file_type(path: &Path) -> Option<ProjectFileType> {
     match path.file_name()?.to_str()? {
         "Cargo.toml" => Some(ProjectFileType::CargoToml),
+        "Cargo.lock" => Some(ProjectFileType::CargoLock),
         "package.json" => Some(ProjectFileType::PackageJson),
         "pyproject.toml" => Some(ProjectFileType::PyprojectToml),
         "setup.py" => Some(ProjectFileType::SetupPy),
@@ -299,12 +998,18 @@ fn detect_file_type(path: &Path) -> Option<ProjectFileType> {
         "pubspec.yaml" => Some(ProjectFileType::PubspecYaml),
         "pom.xml" => Some(ProjectFileType::PomXml),
         "build.gradle" => Some(ProjectFileType::BuildGradle),
+        "build.gradle.kts" => Some(ProjectFileType::BuildGradleKts),
         "CMakeLists.txt" => Some(ProjectFileType::CMakeLists),
+        "setup.cfg" => Some(ProjectFileType::SetupCfg),
+        "Chart.yaml" => Some(ProjectFileType::ChartYaml),
+        "mix.exs" => Some(ProjectFileType::MixExs),
+        "Directory.Build.props" => Some(ProjectFileType::DirectoryBuildProps),
         _filename => {
             // Handle generic file types by extension
             if let Some(extension) = path.extension().and_then(|ext| ext.to_str()) {
                 match extension {
                     "json" => Some(ProjectFileType::PackageJson), // Treat all JSON files like package.json
+                    "csproj" => Some(ProjectFileType::Csproj),
                     _ => None,
                 }
             } else {
@@ -315,29 +1020,11 @@ fn detect_file_type(path: &Path) -> Option<ProjectFileType> {
 }
 
 pub fn is_git_repository() -> bool {
-    Command::new("git")
-        .args(["rev-parse", "--git-dir"])
-        .output()
-        .map(|output| output.status.success())
-        .unwrap_or(false)
+    GitRepo::discover().is_ok()
 }
 
 pub fn get_git_root() -> Result<PathBuf> {
-    let output = Command::new("git")
-        .args(["rev-parse", "--show-toplevel"])
-        .output()
-        .context("Failed to get git root directory")?;
-
-    if !output.status.success() {
-        anyhow::bail!("Not in a git repository");
-    }
-
-    let root = String::from_utf8(output.stdout)
-        .context("Invalid UTF-8 in git root output")?
-        .trim()
-        .to_string();
-
-    Ok(PathBuf::from(root))
+    Ok(GitRepo::discover()?.root().to_path_buf())
 }
 
 #[derive(Debug, Clone)]
@@ -349,6 +1036,8 @@ pub struct ProjectFile {
 #[derive(Debug, Clone, PartialEq)]
 pub enum ProjectFileType {
     CargoToml,
+    /// The versions of the workspace's own crates in it
+    CargoLock,
     PackageJson,
     PyprojectToml,
     SetupPy,
@@ -356,13 +1045,21 @@ pub enum ProjectFileType {
     PubspecYaml,
     PomXml,
     BuildGradle,
+    BuildGradleKts,
     CMakeLists,
+    SetupCfg,
+    ChartYaml,
+    /// Any `*.csproj`; a project may have several
+    Csproj,
+    MixExs,
+    DirectoryBuildProps,
 }
 
 impl ProjectFileType {
     pub fn file_name(&self) -> &'static str {
         match self {
             ProjectFileType::CargoToml => "Cargo.toml",
+            ProjectFileType::CargoLock => "Cargo.lock",
             ProjectFileType::PackageJson => "package.json",
             ProjectFileType::PyprojectToml => "pyproject.toml",
             ProjectFileType::SetupPy => "setup.py",
@@ -370,7 +1067,13 @@ impl ProjectFileType {
             ProjectFileType::PubspecYaml => "pubspec.yaml",
             ProjectFileType::PomXml => "pom.xml",
             ProjectFileType::BuildGradle => "build.gradle",
+            ProjectFileType::BuildGradleKts => "build.gradle.kts",
             ProjectFileType::CMakeLists => "CMakeLists.txt",
+            ProjectFileType::SetupCfg => "setup.cfg",
+            ProjectFileType::ChartYaml => "Chart.yaml",
+            ProjectFileType::Csproj => "*.csproj",
+            ProjectFileType::MixExs => "mix.exs",
+            ProjectFileType::DirectoryBuildProps => "Directory.Build.props",
         }
     }
 }
@@ -381,6 +1084,7 @@ pub fn detect_project_files(repo_root: &Path) -> Result<Vec<ProjectFile>> {
     // Define project file types to detect
     let file_types = [
         ProjectFileType::CargoToml,
+        ProjectFileType::CargoLock,
         ProjectFileType::PackageJson,
         ProjectFileType::PyprojectToml,
         ProjectFileType::SetupPy,
@@ -388,7 +1092,12 @@ pub fn detect_project_files(repo_root: &Path) -> Result<Vec<ProjectFile>> {
         ProjectFileType::PubspecYaml,
         ProjectFileType::PomXml,
         ProjectFileType::BuildGradle,
+        ProjectFileType::BuildGradleKts,
         ProjectFileType::CMakeLists,
+        ProjectFileType::SetupCfg,
+        ProjectFileType::ChartYaml,
+        ProjectFileType::MixExs,
+        ProjectFileType::DirectoryBuildProps,
     ];
     
     for file_type in &file_types {
@@ -401,6 +1110,40 @@ pub fn detect_project_files(repo_root: &Path) -> Result<Vec<ProjectFile>> {
         }
     }
     
+    // .csproj files are named after the project
+    let mut csproj_files: Vec<PathBuf> = fs::read_dir(repo_root)
+        .with_context(|| format!("Failed to read {}", repo_root.display()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "csproj"))
+        .collect();
+    csproj_files.sort();
+    project_files.extend(csproj_files.into_iter().map(|path| ProjectFile {
+        path,
+        file_type: ProjectFileType::Csproj,
+    }));
+    
+    Ok(project_files)
+}
+
+/// The project files detected at the repository root, with the members of
+/// an npm workspace added or its root `package.json` dropped as
+/// `npm_workspace_mode` says
+pub(crate) fn detect_root_project_files(repo_root: &Path, config: &St8Config) -> Result<Vec<ProjectFile>> {
+    let mut project_files = detect_project_files(repo_root)?;
+    match config.npm_workspace_mode {
+        NpmWorkspaceMode::RootOnly => {}
+        NpmWorkspaceMode::All => {
+            project_files.extend(super::npm::workspace_members(repo_root)?.into_iter().map(|path| ProjectFile {
+                path,
+                file_type: ProjectFileType::PackageJson,
+            }));
+        }
+        NpmWorkspaceMode::Independent => {
+            if !super::npm::workspace_patterns(repo_root)?.is_empty() {
+                project_files.retain(|f| f.file_type != ProjectFileType::PackageJson);
+            }
+        }
+    }
     Ok(project_files)
 }
 
@@ -413,13 +1156,8 @@ pub fn update_project_files(version_info: &VersionInfo, project_files: &[Project
                 updated_files.push(project_file.path.display().to_string());
                 
                 // Stage the updated file
-                let output = Command::new("git")
-                    .args(["add", project_file.path.to_str().unwrap()])
-                    .output()
-                    .context("Failed to stage updated project file")?;
-                
-                if !output.status.success() {
-                    eprintln!("Warning: Failed to stage {}", project_file.path.display());
+                if let Err(e) = GitRepo::discover().and_then(|repo| repo.stage(&project_file.path)) {
+                    eprintln!("Warning: Failed to stage {}: {}", project_file.path.display(), e);
                 }
             }
             Err(e) => {
@@ -432,11 +1170,25 @@ pub fn update_project_files(version_info: &VersionInfo, project_files: &[Project
 }
 
 fn update_project_file(version_info: &VersionInfo, project_file: &ProjectFile) -> Result<()> {
+    let updated_content = updated_project_file_content(version_info, project_file)?;
+    
+    fs::write(&project_file.path, updated_content)
+        .with_context(|| format!("Failed to write updated {}", project_file.path.display()))?;
+    
+    Ok(())
+}
+
+/// The content of `project_file` with its version set, without writing it
+pub(crate) fn updated_project_file_content(version_info: &VersionInfo, project_file: &ProjectFile) -> Result<String> {
     let content = fs::read_to_string(&project_file.path)
         .with_context(|| format!("Failed to read {}", project_file.path.display()))?;
     
     let updated_content = match project_file.file_type {
         ProjectFileType::CargoToml => update_cargo_toml(&content, &version_info.full_version)?,
+        ProjectFileType::CargoLock => {
+            let manifest_dir = project_file.path.parent().unwrap_or(Path::new("."));
+            update_cargo_lock(&content, &cargo_versioned_crates(manifest_dir)?, &version_info.full_version)?
+        }
         ProjectFileType::PackageJson => update_package_json(&content, &version_info.full_version)?,
         ProjectFileType::PyprojectToml => update_pyproject_toml(&content, &version_info.full_version)?,
         ProjectFileType::SetupPy => update_setup_py(&content, &version_info.full_version)?,
@@ -444,24 +1196,139 @@ fn update_project_file(version_info: &VersionInfo, project_file: &ProjectFile) -
         ProjectFileType::PubspecYaml => update_pubspec_yaml(&content, &version_info.full_version)?,
         ProjectFileType::PomXml => update_pom_xml(&content, &version_info.full_version)?,
         ProjectFileType::BuildGradle => update_build_gradle(&content, &version_info.full_version)?,
+        ProjectFileType::BuildGradleKts => update_build_gradle_kts(&content, &version_info.full_version)?,
         ProjectFileType::CMakeLists => update_cmake_lists(&content, &version_info.full_version)?,
+        ProjectFileType::SetupCfg => update_setup_cfg(&content, &version_info.full_version)?,
+        ProjectFileType::ChartYaml => update_chart_yaml(&content, &version_info.full_version)?,
+        ProjectFileType::Csproj | ProjectFileType::DirectoryBuildProps => update_msbuild_project(&content, &version_info.full_version)?,
+        ProjectFileType::MixExs => update_mix_exs(&content, &version_info.full_version)?,
     };
     
-    fs::write(&project_file.path, updated_content)
-        .with_context(|| format!("Failed to write updated {}", project_file.path.display()))?;
-    
-    Ok(())
+    Ok(updated_content)
 }
 
+/// `[package].version`, unless the package inherits it from the workspace,
+/// and `[workspace.package].version` when a workspace root shares one with
+/// its members. Formatting and comments are kept.
 fn update_cargo_toml(content: &str, version: &str) -> Result<String> {
-    let mut parsed: toml::Value = content.parse()
+    let mut manifest: toml_edit::DocumentMut = content.parse()
         .context("Failed to parse Cargo.toml")?;
     
-    if let Some(package) = parsed.get_mut("package").and_then(|p| p.as_table_mut()) {
-        package.insert("version".to_string(), toml::Value::String(version.to_string()));
+    if let Some(package) = manifest.get_mut("package").and_then(|p| p.as_table_like_mut()) {
+        if package.get("version").is_none_or(|v| v.is_str()) {
+            set_toml_version(package, version);
+        }
     }
     
-    Ok(toml::to_string(&parsed)?)
+    let shared = manifest.get_mut("workspace")
+        .and_then(|w| w.get_mut("package"))
+        .and_then(|p| p.as_table_like_mut())
+        .filter(|p| p.contains_key("version"));
+    if let Some(shared) = shared {
+        set_toml_version(shared, version);
+    }
+    
+    Ok(manifest.to_string())
+}
+
+/// Set `version`, keeping the spacing and comment around an existing value
+fn set_toml_version(table: &mut dyn toml_edit::TableLike, version: &str) {
+    match table.get_mut("version").and_then(|item| item.as_value_mut()) {
+        Some(value) => {
+            let decor = value.decor().clone();
+            *value = version.into();
+            *value.decor_mut() = decor;
+        }
+        None => {
+            table.insert("version", toml_edit::value(version));
+        }
+    }
+}
+
+/// Names of the crates in the workspace at `manifest_dir` that take the
+/// bumped version: the root package and the members inheriting
+/// `[workspace.package].version`
+fn cargo_versioned_crates(manifest_dir: &Path) -> Result<Vec<String>> {
+    let read_manifest = |dir: &Path| -> Result<toml::Value> {
+        let path = dir.join("Cargo.toml");
+        fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?
+            .parse()
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    };
+    let package_name = |manifest: &toml::Value| {
+        manifest.get("package").and_then(|p| p.get("name")).and_then(|n| n.as_str()).map(str::to_string)
+    };
+    
+    let root = read_manifest(manifest_dir)?;
+    let mut crates: Vec<String> = package_name(&root).into_iter().collect();
+    
+    let Some(workspace) = root.get("workspace") else { return Ok(crates) };
+    if workspace.get("package").and_then(|p| p.get("version")).is_none() {
+        return Ok(crates);
+    }
+    let patterns = |key: &str| -> Vec<String> {
+        workspace.get(key)
+            .and_then(|list| list.as_array())
+            .map(|list| list.iter().filter_map(|p| p.as_str()).map(str::to_string).collect())
+            .unwrap_or_default()
+    };
+    let excluded: Vec<PathBuf> = patterns("exclude").iter().map(|p| manifest_dir.join(p)).collect();
+    
+    for member in patterns("members").iter().flat_map(|pattern| cargo_member_dirs(manifest_dir, pattern)) {
+        if excluded.contains(&member) || member == manifest_dir {
+            continue;
+        }
+        let Ok(manifest) = read_manifest(&member) else { continue };
+        let inherits = manifest.get("package")
+            .and_then(|p| p.get("version"))
+            .and_then(|v| v.get("workspace"))
+            .and_then(|w| w.as_bool())
+            .unwrap_or(false);
+        if inherits {
+            crates.extend(package_name(&manifest));
+        }
+    }
+    Ok(crates)
+}
+
+/// Directories matched by a `[workspace].members` entry; `*` is only
+/// expanded in the last path component
+fn cargo_member_dirs(manifest_dir: &Path, pattern: &str) -> Vec<PathBuf> {
+    let pattern = pattern.trim_end_matches('/');
+    if !pattern.contains('*') {
+        return vec![manifest_dir.join(pattern)];
+    }
+    let (parent, name_pattern) = pattern.rsplit_once('/').unwrap_or(("", pattern));
+    let Ok(entries) = fs::read_dir(manifest_dir.join(parent)) else { return Vec::new() };
+    let mut dirs: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter(|entry| super::policy::wildcard_matches(name_pattern, &entry.file_name().to_string_lossy()))
+        .map(|entry| entry.path())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+/// The versions of `crates` in Cargo.lock, as `cargo update -w --offline`
+/// would write them after a bump. Registry and git packages with the same
+/// names are left alone.
+fn update_cargo_lock(content: &str, crates: &[String], version: &str) -> Result<String> {
+    let mut lock: toml_edit::DocumentMut = content.parse()
+        .context("Failed to parse Cargo.lock")?;
+    
+    if let Some(packages) = lock.get_mut("package").and_then(|p| p.as_array_of_tables_mut()) {
+        for package in packages.iter_mut() {
+            let local = !package.contains_key("source");
+            let bumped = package.get("name").and_then(|n| n.as_str()).is_some_and(|name| crates.iter().any(|c| c == name));
+            if local && bumped {
+                set_toml_version(package, version);
+            }
+        }
+    }
+    
+    Ok(lock.to_string())
 }
 
 fn update_package_json(content: &str, version: &str) -> Result<String> {
@@ -493,14 +1360,43 @@ fn update_pyproject_toml(content: &str, version: &str) -> Result<String> {
     Ok(toml::to_string(&parsed)?)
 }
 
+/// The `version` keyword argument, keeping its spacing and quotes
 fn update_setup_py(content: &str, version: &str) -> Result<String> {
-    let version_regex = Regex::new(r#"version\s*=\s*["'][^"']*["']"#)
+    let version_regex = Regex::new(r#"(\bversion\s*=\s*)(["'])[^"'\n]*["']"#)
         .context("Failed to create regex for setup.py")?;
     
-    let updated = version_regex.replace_all(content, &format!(r#"version="{}""#, version));
+    let updated = version_regex.replace_all(content, |caps: &regex::Captures| {
+        format!("{}{}{}{}", &caps[1], &caps[2], version, &caps[2])
+    });
     Ok(updated.to_string())
 }
 
+/// `version` in the `[metadata]` section; `attr:` and `file:` directives
+/// read the version from elsewhere and are left alone
+fn update_setup_cfg(content: &str, version: &str) -> Result<String> {
+    let version_regex = Regex::new(r"^(version[ \t]*[=:][ \t]*)(.*?)(\r?\n)?$")
+        .context("Failed to create regex for setup.cfg")?;
+    
+    let mut section = String::new();
+    let mut updated = String::with_capacity(content.len());
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') && trimmed.ends_with(']') {
+            section = trimmed[1..trimmed.len() - 1].trim().to_string();
+        } else if section == "metadata" {
+            if let Some(caps) = version_regex.captures(line) {
+                let value = caps[2].trim();
+                if !value.starts_with("attr:") && !value.starts_with("file:") {
+                    updated.push_str(&format!("{}{}{}", &caps[1], version, caps.get(3).map_or("", |m| m.as_str())));
+                    continue;
+                }
+            }
+        }
+        updated.push_str(line);
+    }
+    Ok(updated)
+}
+
 fn update_composer_json(content: &str, version: &str) -> Result<String> {
     let mut parsed: serde_json::Value = serde_json::from_str(content)
         .context("Failed to parse composer.json")?;
@@ -520,19 +1416,98 @@ fn update_pubspec_yaml(content: &str, version: &str) -> Result<String> {
     Ok(updated.to_string())
 }
 
+/// The project's own `<version>`, not the parent's or a dependency's. A
+/// project inheriting its version from the parent is left unchanged.
 fn update_pom_xml(content: &str, version: &str) -> Result<String> {
-    let version_regex = Regex::new(r"<version>[^<]*</version>")
-        .context("Failed to create regex for pom.xml")?;
-    
-    let updated = version_regex.replace(content, &format!("<version>{}</version>", version));
-    Ok(updated.to_string())
+    Ok(replace_xml_element(content, &[&["project", "version"]], version))
 }
 
+/// `<Version>`, or `<VersionPrefix>` when the project sets that instead, in
+/// a `.csproj` or `Directory.Build.props`
+fn update_msbuild_project(content: &str, version: &str) -> Result<String> {
+    Ok(replace_xml_element(content, &[&["Project", "PropertyGroup", "Version"], &["Project", "PropertyGroup", "VersionPrefix"]], version))
+}
+
+/// Replace the text of the first element found at one of `paths` (element
+/// names from the root down, tried in order) with `text`
+fn replace_xml_element(content: &str, paths: &[&[&str]], text: &str) -> String {
+    match paths.iter().find_map(|path| xml_element_text(content, path)) {
+        Some(range) => format!("{}{}{}", &content[..range.start], text, &content[range.end..]),
+        None => content.to_string(),
+    }
+}
+
+/// Byte range of the text of the first element at `path`, skipping
+/// comments, declarations and self-closing elements
+fn xml_element_text(content: &str, path: &[&str]) -> Option<std::ops::Range<usize>> {
+    let tag_regex = Regex::new(r"(?s)<!--.*?-->|<[?!][^>]*>|<(/?)([A-Za-z_][\w.:-]*)[^>]*?(/?)>").ok()?;
+    let mut open: Vec<&str> = Vec::new();
+    let mut start = None;
+    for caps in tag_regex.captures_iter(content) {
+        let (Some(tag), Some(name)) = (caps.get(0), caps.get(2)) else { continue };
+        if &caps[1] == "/" {
+            if let Some(start) = start.filter(|_| open == path) {
+                return Some(start..tag.start());
+            }
+            open.pop();
+        } else if &caps[3] != "/" {
+            open.push(name.as_str());
+            if open == path {
+                start = Some(tag.end());
+            }
+        }
+    }
+    None
+}
+
+/// `version = '...'` or `version '...'` statements, keeping their quotes;
+/// dependency coordinates are left alone
 fn update_build_gradle(content: &str, version: &str) -> Result<String> {
-    let version_regex = Regex::new(r#"version\s*=\s*['"][^'"]*['"]"#)
+    let version_regex = Regex::new(r#"(?m)^([ \t]*version\s*=?\s*)(['"])[^'"\n]*['"]"#)
         .context("Failed to create regex for build.gradle")?;
     
-    let updated = version_regex.replace_all(content, &format!(r#"version = '{}'"#, version));
+    let updated = version_regex.replace_all(content, |caps: &regex::Captures| {
+        format!("{}{}{}{}", &caps[1], &caps[2], version, &caps[2])
+    });
+    Ok(updated.to_string())
+}
+
+/// `version = "..."` assignments in the Kotlin DSL
+fn update_build_gradle_kts(content: &str, version: &str) -> Result<String> {
+    let version_regex = Regex::new(r#"(?m)^([ \t]*version\s*=\s*)"[^"\n]*""#)
+        .context("Failed to create regex for build.gradle.kts")?;
+    
+    let updated = version_regex.replace_all(content, |caps: &regex::Captures| {
+        format!("{}\"{}\"", &caps[1], version)
+    });
+    Ok(updated.to_string())
+}
+
+/// The chart's top-level `version`, keeping any quotes; `appVersion` and
+/// nested `version` keys (dependencies) are left alone
+fn update_chart_yaml(content: &str, version: &str) -> Result<String> {
+    let version_regex = Regex::new(r#"(?m)^(version:[ \t]*)("[^"\n]*"|'[^'\n]*'|[^\s#]+)"#)
+        .context("Failed to create regex for Chart.yaml")?;
+    
+    let updated = version_regex.replace(content, |caps: &regex::Captures| {
+        let quote = caps[2].chars().next().filter(|c| *c == '"' || *c == '\'').map(String::from).unwrap_or_default();
+        format!("{}{}{}{}", &caps[1], quote, version, quote)
+    });
+    Ok(updated.to_string())
+}
+
+/// The `@version` module attribute when the project defines one (and refers
+/// to it as `version: @version`), otherwise the `version:` keyword in `project/0`
+fn update_mix_exs(content: &str, version: &str) -> Result<String> {
+    let attribute_regex = Regex::new(r#"(?m)^([ \t]*@version\s+)"[^"\n]*""#)
+        .context("Failed to create regex for mix.exs")?;
+    let keyword_regex = Regex::new(r#"(\bversion:\s*)"[^"\n]*""#)
+        .context("Failed to create regex for mix.exs")?;
+    
+    let version_regex = if attribute_regex.is_match(content) { attribute_regex } else { keyword_regex };
+    let updated = version_regex.replace(content, |caps: &regex::Captures| {
+        format!("{}\"{}\"", &caps[1], version)
+    });
     Ok(updated.to_string())
 }
 
@@ -566,7 +1541,9 @@ async fn load_st8_config_from_db(db_path: &std::path::Path) -> Result<St8Config>
     
     // Try to get config from existing project
     let result = sqlx::query(r#"
-        SELECT version_file, auto_detect_project_files, project_files 
+        SELECT version_updates_enabled, version_file, auto_detect_project_files, project_files, version_strategy, version_source, version_format, prerelease, build_metadata, semver_strict, changelog, packages,
+               version_branches, version_ignore_paths, tag_prefix, tag_pattern, commit_mode, commit_message, skip_ci,
+               annotate_commit_msg, max_hook_ms, version_channels, npm_workspace_mode, version_logging, downgrade_policy
         FROM projects 
         LIMIT 1
     "#)
@@ -582,14 +1559,60 @@ async fn load_st8_config_from_db(db_path: &std::path::Path) -> Result<St8Config>
         
         Ok(St8Config {
             version: 1,
-            enabled: true, // Default to enabled since column removed
+            enabled: row.get::<bool, _>("version_updates_enabled"),
             version_file: row.get::<String, _>("version_file"),
             auto_detect_project_files: row.get::<bool, _>("auto_detect_project_files"),
             project_files,
+            strategy: row.get::<String, _>("version_strategy").parse().unwrap_or_default(),
+            source: row.get::<Option<String>, _>("version_source")
+                .and_then(|source| source.parse().ok())
+                .unwrap_or_default(),
+            format: row.get::<String, _>("version_format"),
+            prerelease: row.get::<Option<String>, _>("prerelease"),
+            build_metadata: row.get::<Option<String>, _>("build_metadata"),
+            semver_strict: row.get::<Option<bool>, _>("semver_strict").unwrap_or(false),
+            downgrade_policy: row.get::<Option<String>, _>("downgrade_policy")
+                .and_then(|policy| policy.parse().ok())
+                .unwrap_or_default(),
+            changelog: row.get::<Option<String>, _>("changelog")
+                .and_then(|json_str| serde_json::from_str(&json_str).ok())
+                .unwrap_or_default(),
+            packages: row.get::<Option<String>, _>("packages")
+                .and_then(|json_str| serde_json::from_str(&json_str).ok())
+                .unwrap_or_default(),
+            branches: row.get::<Option<String>, _>("version_branches")
+                .and_then(|json_str| serde_json::from_str(&json_str).ok())
+                .unwrap_or_default(),
+            ignore_paths: row.get::<Option<String>, _>("version_ignore_paths")
+                .and_then(|json_str| serde_json::from_str(&json_str).ok())
+                .unwrap_or_default(),
+            tag_prefix: row.get::<Option<String>, _>("tag_prefix").unwrap_or_else(default_tag_prefix),
+            tag_pattern: row.get::<Option<String>, _>("tag_pattern"),
+            commit_mode: row.get::<Option<String>, _>("commit_mode")
+                .and_then(|mode| mode.parse().ok())
+                .unwrap_or_default(),
+            commit_message: row.get::<Option<String>, _>("commit_message").unwrap_or_else(default_commit_message),
+            skip_ci: row.get::<Option<bool>, _>("skip_ci").unwrap_or(false),
+            annotate_commit_msg: row.get::<Option<bool>, _>("annotate_commit_msg").unwrap_or(false),
+            max_hook_ms: row.get::<Option<i64>, _>("max_hook_ms").map(|ms| ms.max(0) as u64),
+            channels: row.get::<Option<String>, _>("version_channels")
+                .and_then(|json_str| serde_json::from_str(&json_str).ok())
+                .unwrap_or_default(),
+            npm_workspace_mode: row.get::<Option<String>, _>("npm_workspace_mode")
+                .and_then(|mode| mode.parse().ok())
+                .unwrap_or_default(),
+            logging: row.get::<Option<String>, _>("version_logging")
+                .and_then(|json_str| serde_json::from_str(&json_str).ok())
+                .unwrap_or_default(),
         })
     } else {
         // No project exists, create default project with config
-        let default_config = St8Config::default();
+        let user_file = WsConfig::user_file();
+        let mut default_config = St8Config::default();
+        if user_file.is_file() {
+            default_config = super::config_file::apply_layer(&default_config, &WsConfig::read(&user_file)?.version, &user_file)?;
+        }
+        let default_config = super::config_file::apply_file(default_config, super::config_file::user_config_file())?;
         create_default_project_with_config(&pool, &default_config).await?;
         Ok(default_config)
     }
@@ -605,15 +1628,59 @@ async fn save_st8_config_to_db(db_path: &std::path::Path, config: &St8Config) ->
     
     sqlx::query(r#"
         UPDATE projects 
-        SET version_file = ?, 
+        SET version_updates_enabled = ?,
+            version_file = ?, 
             auto_detect_project_files = ?, 
             project_files = ?,
+            version_strategy = ?,
+            version_source = ?,
+            version_format = ?,
+            prerelease = ?,
+            build_metadata = ?,
+            semver_strict = ?,
+            changelog = ?,
+            packages = ?,
+            version_branches = ?,
+            version_ignore_paths = ?,
+            tag_prefix = ?,
+            tag_pattern = ?,
+            commit_mode = ?,
+            commit_message = ?,
+            skip_ci = ?,
+            annotate_commit_msg = ?,
+            max_hook_ms = ?,
+            version_channels = ?,
+            npm_workspace_mode = ?,
+            version_logging = ?,
+            downgrade_policy = ?,
             updated_at = datetime('now')
         WHERE id = (SELECT id FROM projects LIMIT 1)
     "#)
+    .bind(config.enabled)
     .bind(&config.version_file)
     .bind(config.auto_detect_project_files)
     .bind(project_files_json)
+    .bind(config.strategy.to_string())
+    .bind(config.source.to_string())
+    .bind(&config.format)
+    .bind(&config.prerelease)
+    .bind(&config.build_metadata)
+    .bind(config.semver_strict)
+    .bind(serde_json::to_string(&config.changelog)?)
+    .bind(serde_json::to_string(&config.packages)?)
+    .bind(serde_json::to_string(&config.branches)?)
+    .bind(serde_json::to_string(&config.ignore_paths)?)
+    .bind(&config.tag_prefix)
+    .bind(&config.tag_pattern)
+    .bind(config.commit_mode.to_string())
+    .bind(&config.commit_message)
+    .bind(config.skip_ci)
+    .bind(config.annotate_commit_msg)
+    .bind(config.max_hook_ms.map(|ms| ms as i64))
+    .bind(serde_json::to_string(&config.channels)?)
+    .bind(config.npm_workspace_mode.to_string())
+    .bind(serde_json::to_string(&config.logging)?)
+    .bind(config.downgrade_policy.to_string())
     .execute(&pool)
     .await?;
     
@@ -625,16 +1692,42 @@ async fn create_default_project_with_config(pool: &sqlx::SqlitePool, config: &St
     
     sqlx::query(r#"
         INSERT INTO projects (
-            id, name, description, status, version, major_version,
-            version_file, auto_detect_project_files, project_files
+            id, name, description, status, version, major_version, version_updates_enabled,
+            version_file, auto_detect_project_files, project_files, version_strategy, version_source, version_format, prerelease, build_metadata, semver_strict, changelog, packages,
+            version_branches, version_ignore_paths, tag_prefix, tag_pattern, commit_mode, commit_message, skip_ci,
+            annotate_commit_msg, max_hook_ms, version_channels, npm_workspace_mode, version_logging, downgrade_policy
         ) VALUES (
-            'P001', 'Default Project', 'Auto-created project', 'active', '0.1.0', 0,
-            ?, ?, ?
+            'P001', 'Default Project', 'Auto-created project', 'active', '0.1.0', 0, ?,
+            ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?,
+            ?, ?, ?, ?, ?, ?, ?,
+            ?, ?, ?, ?, ?, ?
         )
     "#)
+    .bind(config.enabled)
     .bind(&config.version_file)
     .bind(config.auto_detect_project_files)
     .bind(project_files_json)
+    .bind(config.strategy.to_string())
+    .bind(config.source.to_string())
+    .bind(&config.format)
+    .bind(&config.prerelease)
+    .bind(&config.build_metadata)
+    .bind(config.semver_strict)
+    .bind(serde_json::to_string(&config.changelog)?)
+    .bind(serde_json::to_string(&config.packages)?)
+    .bind(serde_json::to_string(&config.branches)?)
+    .bind(serde_json::to_string(&config.ignore_paths)?)
+    .bind(&config.tag_prefix)
+    .bind(&config.tag_pattern)
+    .bind(config.commit_mode.to_string())
+    .bind(&config.commit_message)
+    .bind(config.skip_ci)
+    .bind(config.annotate_commit_msg)
+    .bind(config.max_hook_ms.map(|ms| ms as i64))
+    .bind(serde_json::to_string(&config.channels)?)
+    .bind(config.npm_workspace_mode.to_string())
+    .bind(serde_json::to_string(&config.logging)?)
+    .bind(config.downgrade_policy.to_string())
     .execute(pool)
     .await?;
     
@@ -643,91 +1736,27 @@ async fn create_default_project_with_config(pool: &sqlx::SqlitePool, config: &St
 
 /// Get total commit count (each commit advances minor version)
 fn get_total_commit_count() -> Result<u32> {
-    let output = Command::new("git")
-        .args(["rev-list", "--count", "HEAD"])
-        .output()
-        .context("Failed to run git rev-list command")?;
-
-    if !output.status.success() {
-        return Ok(0);
-    }
-
-    let count_str = String::from_utf8(output.stdout)
-        .context("Invalid UTF-8 in git rev-list output")?
-        .trim()
-        .to_string();
-
-    count_str.parse::<u32>()
-        .context("Failed to parse commit count")
+    read_history(0, |repo| repo.commit_count(None))
 }
 
 /// Get changes since last release tag for this major version
-fn get_changes_since_last_release_tag(major: u32) -> Result<u32> {
-    let last_tag = find_last_release_tag(major)?;
-    
-    let output = if let Some(tag) = last_tag {
-        // Count changes since the last release tag
-        let range = format!("{}..HEAD", tag);
-        Command::new("git")
-            .args(["log", "--pretty=tformat:", "--numstat", &range])
-            .output()
-            .context("Failed to run git log command")?
-    } else {
-        // No release tags for this major version, count all changes
-        Command::new("git")
-            .args(["log", "--pretty=tformat:", "--numstat"])
-            .output()
-            .context("Failed to run git log command")?
-    };
-
-    if !output.status.success() {
-        return Ok(0);
-    }
-
-    let log_stat = String::from_utf8(output.stdout)
-        .context("Invalid UTF-8 in git log output")?;
-
-    let mut total = 0u32;
-    for line in log_stat.lines() {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() >= 2 {
-            if let (Ok(additions), Ok(deletions)) = (parts[0].parse::<u32>(), parts[1].parse::<u32>()) {
-                total = total.saturating_add(additions).saturating_add(deletions);
-            }
-        }
-    }
-
-    Ok(total)
+fn get_changes_since_last_release_tag(tags: &TagScheme, major: u32) -> Result<u32> {
+    // No release tags for this major version counts all changes
+    let last_tag = find_last_release_tag(tags, major)?;
+    read_history(0, |repo| super::cache::line_changes(repo, last_tag.as_deref()))
 }
 
-/// Find the most recent release tag for this major version (v{major}.*)
-fn find_last_release_tag(major: u32) -> Result<Option<String>> {
-    let output = Command::new("git")
-        .args(["tag", "--list", &format!("v{}.*", major), "--sort=-version:refname"])
-        .output()
-        .context("Failed to run git tag command")?;
-
-    if !output.status.success() {
-        return Ok(None);
-    }
-
-    let tags_output = String::from_utf8(output.stdout)
-        .context("Invalid UTF-8 in git tag output")?;
-
-    // Return the first (most recent) tag for this major version
-    for line in tags_output.lines() {
-        let tag = line.trim();
-        if !tag.is_empty() {
-            return Ok(Some(tag.to_string()));
-        }
-    }
-
-    Ok(None)
+/// Find the most recent release tag for this major version
+fn find_last_release_tag(tags: &TagScheme, major: u32) -> Result<Option<String>> {
+    read_history(None, |repo| {
+        Ok(repo.tags()?.into_iter().find(|tag| tags.version(tag).is_some_and(|v| v.major == major)))
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::logging::LogLocation;
     use tempfile::TempDir;
 
     #[test]
@@ -763,6 +1792,13 @@ mod tests {
         assert_eq!(version_info.full_version, "1.0.5.100");
     }
 
+    #[test]
+    fn test_version_info_from_version_keeps_custom_formats() {
+        assert_eq!(VersionInfo::from_version("1.4.0-rc.1").full_version, "1.4.0-rc.1");
+        assert_eq!(VersionInfo::from_version("2.1.0.5").full_version, "2.1.0.5");
+        assert_eq!(VersionInfo::from_version("2.1.0.5").major_version, "v0");
+    }
+
     #[test]
     fn test_is_git_repository() {
         // This test will pass if run in a git repository
@@ -781,6 +1817,8 @@ mod tests {
         assert_eq!(ProjectFileType::PomXml.file_name(), "pom.xml");
         assert_eq!(ProjectFileType::BuildGradle.file_name(), "build.gradle");
         assert_eq!(ProjectFileType::CMakeLists.file_name(), "CMakeLists.txt");
+        assert_eq!(ProjectFileType::BuildGradleKts.file_name(), "build.gradle.kts");
+        assert_eq!(ProjectFileType::DirectoryBuildProps.file_name(), "Directory.Build.props");
     }
 
     #[test]
@@ -799,6 +1837,12 @@ mod tests {
         let custom_json_path = temp_dir.path().join("custom.json");
         assert_eq!(detect_file_type(&custom_json_path), Some(ProjectFileType::PackageJson));
         
+        assert_eq!(detect_file_type(&temp_dir.path().join("build.gradle.kts")), Some(ProjectFileType::BuildGradleKts));
+        assert_eq!(detect_file_type(&temp_dir.path().join("setup.cfg")), Some(ProjectFileType::SetupCfg));
+        assert_eq!(detect_file_type(&temp_dir.path().join("Chart.yaml")), Some(ProjectFileType::ChartYaml));
+        assert_eq!(detect_file_type(&temp_dir.path().join("mix.exs")), Some(ProjectFileType::MixExs));
+        assert_eq!(detect_file_type(&temp_dir.path().join("App.csproj")), Some(ProjectFileType::Csproj));
+        
         // Test unknown file
         let unknown_path = temp_dir.path().join("unknown.txt");
         assert_eq!(detect_file_type(&unknown_path), None);
@@ -820,6 +1864,50 @@ mod tests {
         assert!(file_types.contains(&&ProjectFileType::PackageJson));
     }
 
+    #[test]
+    fn test_detect_project_files_finds_csproj() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("Web.csproj"), "<Project></Project>").unwrap();
+        fs::write(temp_dir.path().join("Api.csproj"), "<Project></Project>").unwrap();
+        fs::write(temp_dir.path().join("Directory.Build.props"), "<Project></Project>").unwrap();
+        fs::write(temp_dir.path().join("Chart.yaml"), "version: 0.1.0\n").unwrap();
+        
+        let project_files = detect_project_files(temp_dir.path()).unwrap();
+        let names: Vec<_> = project_files.iter().map(|f| f.path.file_name().unwrap().to_str().unwrap()).collect();
+        assert_eq!(names, ["Chart.yaml", "Directory.Build.props", "Api.csproj", "Web.csproj"]);
+    }
+
+    #[test]
+    fn test_npm_workspace_modes() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("package.json"), r#"{"name": "monorepo", "version": "1.0.0", "workspaces": ["packages/*"]}"#).unwrap();
+        for member in ["app", "lib"] {
+            fs::create_dir_all(root.join("packages").join(member)).unwrap();
+            fs::write(root.join("packages").join(member).join("package.json"),
+                      format!(r#"{{"name": "{}", "version": "0.4.0"}}"#, member)).unwrap();
+        }
+        let targeted = |mode: NpmWorkspaceMode| -> Vec<PathBuf> {
+            let config = St8Config { npm_workspace_mode: mode, ..St8Config::default() };
+            detect_root_project_files(root, &config).unwrap().into_iter()
+                .map(|f| f.path.strip_prefix(root).unwrap().to_path_buf())
+                .collect()
+        };
+
+        assert_eq!(targeted(NpmWorkspaceMode::RootOnly), [PathBuf::from("package.json")]);
+        assert_eq!(targeted(NpmWorkspaceMode::All), [
+            PathBuf::from("package.json"),
+            PathBuf::from("packages/app/package.json"),
+            PathBuf::from("packages/lib/package.json"),
+        ]);
+        assert!(targeted(NpmWorkspaceMode::Independent).is_empty());
+
+        let member = ProjectFile { path: root.join("packages/lib/package.json"), file_type: ProjectFileType::PackageJson };
+        let updated = updated_project_file_content(&VersionInfo::from_version("1.1.0"), &member).unwrap();
+        assert!(updated.contains("\"version\": \"1.1.0\""));
+        assert!(updated.contains("\"name\": \"lib\""));
+    }
+
     #[test]
     fn test_update_cargo_toml() {
         let content = r#"[package]
@@ -837,6 +1925,87 @@ serde = "1.0"
         assert!(updated.contains("serde = \"1.0\""));
     }
 
+    #[test]
+    fn test_update_cargo_toml_workspace() {
+        let content = r#"[workspace]
+members = ["crates/*"]
+
+[workspace.package]
+version = "0.1.0" # shared by the members
+edition = "2021"
+
+[package]
+name = "app"
+version.workspace = true
+"#;
+        
+        let updated = update_cargo_toml(content, "0.2.0").unwrap();
+        assert_eq!(updated, content.replace("version = \"0.1.0\"", "version = \"0.2.0\""));
+    }
+
+    #[test]
+    fn test_source_version() {
+        let temp_dir = TempDir::new().unwrap();
+        let manifest = temp_dir.path().join("Cargo.toml");
+        fs::write(&manifest, "[workspace.package]\nversion = \"3.1.4\"\n\n[package]\nname = \"app\"\nversion.workspace = true\n").unwrap();
+        assert_eq!(cargo_manifest_version(&manifest).unwrap(), "3.1.4");
+
+        let version_file = temp_dir.path().join("version.txt");
+        let config = St8Config {
+            source: VersionSource::File,
+            version_file: version_file.to_string_lossy().to_string(),
+            ..St8Config::default()
+        };
+        assert_eq!(source_version(&config).unwrap(), None);
+        fs::write(&version_file, "2.7.9\n").unwrap();
+        assert_eq!(source_major(&config).unwrap(), Some(2));
+
+        let config = St8Config { source: VersionSource::Tag, ..config };
+        assert_eq!(source_version(&config).unwrap(), None);
+        assert!("manifest".parse::<VersionSource>().is_err());
+    }
+
+    #[test]
+    fn test_update_cargo_lock() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("Cargo.toml"), "[workspace]\nmembers = [\"crates/*\"]\n\n[workspace.package]\nversion = \"0.1.0\"\n").unwrap();
+        for (name, version) in [("core", "version.workspace = true"), ("cli", "version = { workspace = true }"), ("legacy", "version = \"3.0.0\"")] {
+            fs::create_dir_all(root.join("crates").join(name)).unwrap();
+            fs::write(root.join("crates").join(name).join("Cargo.toml"), format!("[package]\nname = \"{}\"\n{}\n", name, version)).unwrap();
+        }
+        
+        let crates = cargo_versioned_crates(root).unwrap();
+        assert_eq!(crates, ["cli", "core"]);
+        
+        let lock = r#"version = 3
+
+[[package]]
+name = "cli"
+version = "0.1.0"
+dependencies = [
+ "core",
+]
+
+[[package]]
+name = "core"
+version = "0.1.0"
+
+[[package]]
+name = "core"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "legacy"
+version = "3.0.0"
+"#;
+        let updated = update_cargo_lock(lock, &crates, "0.2.0").unwrap();
+        assert_eq!(updated.matches("version = \"0.2.0\"").count(), 2);
+        assert!(updated.contains("version = \"0.1.0\"\nsource = \"registry+"));
+        assert!(updated.contains("version = \"3.0.0\""));
+    }
+
     #[test]
     fn test_update_package_json() {
         let content = r#"{
@@ -935,6 +2104,56 @@ environment:
         assert!(updated.contains("<artifactId>test-project</artifactId>"));
     }
 
+    #[test]
+    fn test_update_pom_xml_skips_parent_and_dependencies() {
+        let content = r#"<project>
+    <parent>
+        <artifactId>parent</artifactId>
+        <version>9.0.0</version>
+    </parent>
+    <!-- <version>0.0.0</version> -->
+    <artifactId>child</artifactId>
+    <version>1.0.0</version>
+    <dependencies>
+        <dependency><artifactId>lib</artifactId><version>4.2</version></dependency>
+    </dependencies>
+</project>
+"#;
+        
+        let updated = update_pom_xml(content, "1.1.0").unwrap();
+        assert_eq!(updated, content.replace("<version>1.0.0</version>", "<version>1.1.0</version>"));
+        
+        // A version inherited from the parent stays inherited
+        let inherited = "<project><parent><version>9.0.0</version></parent></project>";
+        assert_eq!(update_pom_xml(inherited, "1.1.0").unwrap(), inherited);
+    }
+
+    #[test]
+    fn test_update_msbuild_project() {
+        let content = r#"<Project Sdk="Microsoft.NET.Sdk">
+  <PropertyGroup>
+    <TargetFramework>net8.0</TargetFramework>
+  </PropertyGroup>
+  <PropertyGroup>
+    <Version>1.0.0</Version>
+  </PropertyGroup>
+  <ItemGroup>
+    <PackageReference Include="Newtonsoft.Json" Version="13.0.1" />
+  </ItemGroup>
+</Project>
+"#;
+        
+        let updated = update_msbuild_project(content, "1.2.0").unwrap();
+        assert_eq!(updated, content.replace("<Version>1.0.0</Version>", "<Version>1.2.0</Version>"));
+        
+        let prefixed = "<Project><PropertyGroup><VersionPrefix>1.0.0</VersionPrefix></PropertyGroup></Project>";
+        assert_eq!(update_msbuild_project(prefixed, "1.2.0").unwrap(), prefixed.replace("1.0.0", "1.2.0"));
+        
+        // Nothing to update in props that don't set a version
+        let unversioned = "<Project><PropertyGroup><Nullable>enable</Nullable></PropertyGroup></Project>";
+        assert_eq!(update_msbuild_project(unversioned, "1.2.0").unwrap(), unversioned);
+    }
+
     #[test]
     fn test_update_build_gradle() {
         let content = r#"plugins {
@@ -955,6 +2174,69 @@ repositories {
         assert!(updated.contains("group = 'com.example'"));
     }
 
+    #[test]
+    fn test_update_build_gradle_keeps_quotes_and_dependencies() {
+        let content = "version \"1.0.0\"\n\ndependencies {\n    implementation 'org.slf4j:slf4j-api:2.0.9'\n}\n";
+        let updated = update_build_gradle(content, "1.1.0").unwrap();
+        assert_eq!(updated, content.replace("1.0.0", "1.1.0"));
+    }
+
+    #[test]
+    fn test_update_build_gradle_kts() {
+        let content = r#"plugins {
+    kotlin("jvm") version "1.9.22"
+}
+
+group = "com.example"
+version = "1.0.0"
+"#;
+        
+        let updated = update_build_gradle_kts(content, "1.3.0").unwrap();
+        assert_eq!(updated, content.replace("version = \"1.0.0\"", "version = \"1.3.0\""));
+    }
+
+    #[test]
+    fn test_update_setup_cfg() {
+        let content = "[metadata]\nname = test-package\nversion = 0.1.0\n\n[options]\nversion = untouched\n";
+        let updated = update_setup_cfg(content, "0.2.0").unwrap();
+        assert_eq!(updated, "[metadata]\nname = test-package\nversion = 0.2.0\n\n[options]\nversion = untouched\n");
+        
+        let dynamic = "[metadata]\nversion = attr: test_package.__version__\n";
+        assert_eq!(update_setup_cfg(dynamic, "0.2.0").unwrap(), dynamic);
+    }
+
+    #[test]
+    fn test_update_chart_yaml() {
+        let content = r#"apiVersion: v2
+name: web
+version: "0.1.0"
+appVersion: "1.16.0"
+dependencies:
+  - name: redis
+    version: 18.1.0
+"#;
+        
+        let updated = update_chart_yaml(content, "0.2.0").unwrap();
+        assert_eq!(updated, content.replace("version: \"0.1.0\"", "version: \"0.2.0\""));
+    }
+
+    #[test]
+    fn test_update_mix_exs() {
+        let content = r#"defmodule App.MixProject do
+  use Mix.Project
+
+  def project do
+    [app: :app, version: "0.1.0", deps: [{:jason, "~> 1.4"}]]
+  end
+end
+"#;
+        let updated = update_mix_exs(content, "0.2.0").unwrap();
+        assert_eq!(updated, content.replace("0.1.0", "0.2.0"));
+        
+        let attribute = "  @version \"0.1.0\"\n\n  def project do\n    [app: :app, version: @version]\n  end\n";
+        assert_eq!(update_mix_exs(attribute, "0.2.0").unwrap(), attribute.replace("0.1.0", "0.2.0"));
+    }
+
     #[test]
     fn test_update_cmake_lists() {
         let content = r#"cmake_minimum_required(VERSION 3.16)
@@ -983,6 +2265,27 @@ set(CMAKE_CXX_STANDARD 17)
             version_file: "version.txt".to_string(),
             auto_detect_project_files: true,
             project_files: vec!["custom.toml".to_string()],
+            strategy: VersionStrategy::Conventional,
+            source: VersionSource::Cargo,
+            format: "{yyyy}.{mm}.{patch}".to_string(),
+            prerelease: Some("rc".to_string()),
+            build_metadata: None,
+            semver_strict: true,
+            downgrade_policy: DowngradePolicy::Warn,
+            changelog: ChangelogConfig::default(),
+            packages: Vec::new(),
+            branches: vec!["main".to_string(), "release/*".to_string()],
+            ignore_paths: vec!["docs/**".to_string()],
+            tag_prefix: "release-".to_string(),
+            tag_pattern: Some("release-*".to_string()),
+            commit_mode: CommitMode::Separate,
+            commit_message: "release {version}".to_string(),
+            skip_ci: true,
+            annotate_commit_msg: true,
+            max_hook_ms: Some(250),
+            channels: vec![ChannelConfig::new("beta")],
+            npm_workspace_mode: NpmWorkspaceMode::All,
+            logging: LoggingConfig { level: "warn".to_string(), location: LogLocation::Git, ..LoggingConfig::default() },
         };
         
         config.save(temp_dir.path()).unwrap();
@@ -990,6 +2293,26 @@ set(CMAKE_CXX_STANDARD 17)
         
         assert_eq!(loaded_config.auto_detect_project_files, true);
         assert_eq!(loaded_config.project_files, vec!["custom.toml"]);
+        assert_eq!(loaded_config.strategy, VersionStrategy::Conventional);
+        assert_eq!(loaded_config.source, VersionSource::Cargo);
+        assert_eq!(loaded_config.format, "{yyyy}.{mm}.{patch}");
+        assert_eq!(loaded_config.prerelease.as_deref(), Some("rc"));
+        assert_eq!(loaded_config.build_metadata, None);
+        assert!(loaded_config.semver_strict);
+        assert_eq!(loaded_config.downgrade_policy, DowngradePolicy::Warn);
+        assert_eq!(loaded_config.branches, vec!["main", "release/*"]);
+        assert_eq!(loaded_config.ignore_paths, vec!["docs/**"]);
+        assert_eq!(loaded_config.tag_prefix, "release-");
+        assert_eq!(loaded_config.tag_pattern.as_deref(), Some("release-*"));
+        assert_eq!(loaded_config.commit_mode, CommitMode::Separate);
+        assert_eq!(loaded_config.commit_message, "release {version}");
+        assert!(loaded_config.skip_ci);
+        assert!(loaded_config.annotate_commit_msg);
+        assert_eq!(loaded_config.max_hook_ms, Some(250));
+        assert_eq!(loaded_config.channels, vec![ChannelConfig::new("beta")]);
+        assert_eq!(loaded_config.npm_workspace_mode, NpmWorkspaceMode::All);
+        assert_eq!(loaded_config.logging.level, "warn");
+        assert_eq!(loaded_config.logging.location, LogLocation::Git);
     }
 
     #[test]
@@ -0,0 +1,103 @@
+use std::fmt::Display;
+
+use super::conventional::SemVer;
+use super::git::{GitRepo, GitResult};
+use super::policy::wildcard_matches;
+
+/// How the project's release tags are named, e.g. `v1.2.0`, `release-1.2`
+/// or `myapp/v3`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagScheme {
+    /// Put in front of the version in new tags and removed to read one
+    pub prefix: String,
+    /// Which tags are release tags, where `*` stands for any text
+    pub pattern: String,
+    /// Tags matching `pattern` that belong to another release channel
+    pub exclude: Vec<String>,
+}
+
+impl TagScheme {
+    /// `pattern` defaults to every tag starting with `prefix`
+    pub fn new(prefix: &str, pattern: Option<&str>) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            pattern: pattern.map(str::to_string).unwrap_or_else(|| format!("{}*", prefix)),
+            exclude: Vec::new(),
+        }
+    }
+
+    pub fn excluding(self, exclude: Vec<String>) -> Self {
+        Self { exclude, ..self }
+    }
+
+    pub fn matches(&self, tag: &str) -> bool {
+        wildcard_matches(&self.pattern, tag) && !self.exclude.iter().any(|pattern| wildcard_matches(pattern, tag))
+    }
+
+    /// The version a release tag names, `None` for other tags
+    pub fn version(&self, tag: &str) -> Option<SemVer> {
+        if !self.matches(tag) {
+            return None;
+        }
+        SemVer::parse(self.strip(tag))
+    }
+
+    /// The tag without the prefix
+    pub fn strip<'a>(&self, tag: &'a str) -> &'a str {
+        tag.strip_prefix(self.prefix.as_str()).unwrap_or(tag)
+    }
+
+    /// The name of the release tag for `version`
+    pub fn tag_name(&self, version: impl Display) -> String {
+        format!("{}{}", self.prefix, version)
+    }
+}
+
+/// The matching tag fewest commits behind HEAD, like `git describe --tags --abbrev=0`
+pub fn nearest_tag(repo: &GitRepo, tags: &TagScheme) -> GitResult<Option<String>> {
+    if tags.exclude.is_empty() {
+        return repo.nearest_tag(Some(&tags.pattern));
+    }
+    // git2 cannot exclude patterns, so measure the candidates
+    let mut nearest: Option<(u32, String)> = None;
+    for tag in repo.tags_reachable_from_head()?.into_iter().filter(|tag| tags.matches(tag)) {
+        let distance = repo.commit_count(Some(&tag))?;
+        if !nearest.as_ref().is_some_and(|(closest, _)| *closest <= distance) {
+            nearest = Some((distance, tag));
+        }
+    }
+    Ok(nearest.map(|(_, tag)| tag))
+}
+
+impl Default for TagScheme {
+    fn default() -> Self {
+        Self::new("v", None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tag_schemes() {
+        let default = TagScheme::default();
+        assert_eq!(default.version("v1.2.0"), Some(SemVer::new(1, 2, 0)));
+        assert_eq!(default.version("pkg/v1.2.0"), None);
+        assert_eq!(default.tag_name("1.3.0"), "v1.3.0");
+
+        let release = TagScheme::new("release-", None);
+        assert_eq!(release.version("release-1.2"), Some(SemVer::new(1, 2, 0)));
+        assert_eq!(release.version("v1.2.0"), None);
+        assert_eq!(release.tag_name("1.3.0"), "release-1.3.0");
+
+        let scoped = TagScheme::new("myapp/v", Some("myapp/*"));
+        assert_eq!(scoped.version("myapp/v3"), Some(SemVer::new(3, 0, 0)));
+        assert_eq!(scoped.version("otherapp/v4"), None);
+        assert_eq!(scoped.strip("myapp/v3.1.0"), "3.1.0");
+
+        let stable = TagScheme::default().excluding(vec!["v*-beta*".to_string()]);
+        assert!(stable.matches("v1.3.0"));
+        assert!(!stable.matches("v1.3.0-beta.2"));
+    }
+}
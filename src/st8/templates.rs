@@ -153,6 +153,18 @@ impl TemplateManager {
         Ok(rendered_files)
     }
     
+    /// Render all enabled templates without writing them, as (output path, content)
+    pub fn preview_all_templates(&self, version_info: &VersionInfo, project_name: Option<&str>) -> Result<Vec<(PathBuf, String)>> {
+        let context = self.create_template_context(version_info, project_name);
+        let mut previews = Vec::new();
+        for template_config in self.templates.values().filter(|t| t.enabled) {
+            let content = self.tera_engine.render(&template_config.name, &context)
+                .with_context(|| format!("Failed to render template: {}", template_config.name))?;
+            previews.push((PathBuf::from(&template_config.output_path), content));
+        }
+        Ok(previews)
+    }
+    
     /// Render a specific template
     pub fn render_template(&self, template_config: &TemplateConfig, context: &TeraContext) -> Result<String> {
         let rendered_content = self.tera_engine.render(&template_config.name, context)
@@ -0,0 +1,27 @@
+//! Span timings for diagnosing slow runs on big trees. With
+//! `ws --trace-output trace.json <command>` every span (refactor phases, file
+//! operations, git calls) is written in the Chrome trace event format, which
+//! chrome://tracing and https://ui.perfetto.dev open.
+
+use anyhow::{Context, Result};
+use std::path::Path;
+use tracing_chrome::{ChromeLayerBuilder, FlushGuard};
+use tracing_subscriber::prelude::*;
+
+/// Keeps the trace open; the file is complete once this is dropped
+pub struct TraceGuard {
+    _guard: FlushGuard,
+}
+
+/// Record spans to `path` until the returned guard is dropped
+pub fn init_trace_output(path: &Path) -> Result<TraceGuard> {
+    // The chrome layer panics when it cannot create the file
+    std::fs::File::create(path).with_context(|| format!("Failed to create trace file {}", path.display()))?;
+    let (layer, guard) = ChromeLayerBuilder::new().file(path).include_args(true).build();
+    tracing_subscriber::registry()
+        .with(layer)
+        .try_init()
+        .context("A trace subscriber is already installed")?;
+    log::info!("Writing trace to {}", path.display());
+    Ok(TraceGuard { _guard: guard })
+}
@@ -254,4 +254,44 @@ fn test_completion_setup_works_normally() {
     // Should have the help text in stdout
     let stdout = String::from_utf8_lossy(&output.get_output().stdout);
     assert!(stdout.contains("Shell completions are automatically set up"));
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_completion_scripts_complete_scrapped_items() {
+    let temp_dir = TempDir::new().unwrap();
+    let temp_home = temp_dir.path().to_str().unwrap();
+    
+    let mut cmd = Command::cargo_bin("ws").unwrap();
+    cmd.env("HOME", temp_home);
+    cmd.env("SHELL", "/bin/bash");
+    cmd.env_remove("WS_COMPLETIONS_LOADED");
+    cmd.arg("--help");
+    cmd.assert().success();
+    
+    let completion_file = PathBuf::from(temp_home)
+        .join(".local/share/bash-completion/completions/ws");
+    let content = fs::read_to_string(&completion_file).unwrap();
+    assert!(content.contains("ws unscrap --complete-items"));
+    assert!(content.contains("complete -F _ws_dynamic"));
+    
+    // The helper lists the names recorded in .scrap/.metadata.json
+    let project = temp_dir.path().join("project");
+    fs::create_dir(&project).unwrap();
+    fs::write(project.join("notes.txt"), "notes").unwrap();
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["scrap", "notes.txt"])
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(&project)
+        .assert()
+        .success();
+    
+    let output = Command::cargo_bin("ws")
+        .unwrap()
+        .args(["unscrap", "--complete-items"])
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(&project)
+        .assert()
+        .success();
+    assert_eq!(String::from_utf8_lossy(&output.get_output().stdout), "notes.txt\n");
+}
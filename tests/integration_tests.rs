@@ -100,6 +100,10 @@ fn test_basic_replacement() -> Result<()> {
         assume_yes: true,
         verbose: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         backup: false,
         files_only: false,
         dirs_only: false,
@@ -110,11 +114,31 @@ fn test_basic_replacement() -> Result<()> {
         include_patterns: vec![],
         format: workspace::cli::OutputFormat::Plain,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         progress: workspace::cli::ProgressMode::Never,
         ignore_case: false,
         use_regex: false,
         include_hidden: false,
         binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
 
     // Run refac
@@ -150,6 +174,10 @@ fn test_mandatory_validation() -> Result<()> {
         assume_yes: true,
         verbose: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         backup: false,
         files_only: false,
         dirs_only: false,
@@ -160,11 +188,31 @@ fn test_mandatory_validation() -> Result<()> {
         include_patterns: vec![],
         format: workspace::cli::OutputFormat::Plain,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         progress: workspace::cli::ProgressMode::Never,
         ignore_case: false,
         use_regex: false,
         include_hidden: false,
         binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
 
     // Run operation (validation is now mandatory and automatic)
@@ -200,6 +248,10 @@ fn test_case_sensitivity() -> Result<()> {
         assume_yes: true,
         verbose: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         backup: false,
         files_only: false,
         dirs_only: false,
@@ -210,11 +262,31 @@ fn test_case_sensitivity() -> Result<()> {
         include_patterns: vec![],
         format: workspace::cli::OutputFormat::Plain,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         progress: workspace::cli::ProgressMode::Never,
         ignore_case: false,
         use_regex: false,
         include_hidden: false,
         binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
 
     run_refac(args)?;
@@ -246,6 +318,10 @@ fn test_complex_nested_structure() -> Result<()> {
         assume_yes: true,
         verbose: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         backup: false,
         files_only: false,
         dirs_only: false,
@@ -256,11 +332,31 @@ fn test_complex_nested_structure() -> Result<()> {
         include_patterns: vec![],
         format: workspace::cli::OutputFormat::Plain,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         progress: workspace::cli::ProgressMode::Never,
         ignore_case: false,
         use_regex: false,
         include_hidden: false,
         binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
 
     run_refac(args)?;
@@ -298,6 +394,10 @@ fn test_files_only_mode() -> Result<()> {
         assume_yes: true,
         verbose: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         backup: false,
         files_only: true,
         dirs_only: false,
@@ -308,11 +408,31 @@ fn test_files_only_mode() -> Result<()> {
         include_patterns: vec![],
         format: workspace::cli::OutputFormat::Plain,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         progress: workspace::cli::ProgressMode::Never,
         ignore_case: false,
         use_regex: false,
         include_hidden: false,
         binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
 
     run_refac(args)?;
@@ -341,6 +461,10 @@ fn test_dirs_only_mode() -> Result<()> {
         assume_yes: true,
         verbose: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         backup: false,
         files_only: false,
         dirs_only: true,
@@ -351,11 +475,31 @@ fn test_dirs_only_mode() -> Result<()> {
         include_patterns: vec![],
         format: workspace::cli::OutputFormat::Plain,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         progress: workspace::cli::ProgressMode::Never,
         ignore_case: false,
         use_regex: false,
         include_hidden: false,
         binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
 
     run_refac(args)?;
@@ -387,6 +531,10 @@ fn test_names_only_mode() -> Result<()> {
         assume_yes: true,
         verbose: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         backup: false,
         files_only: false,
         dirs_only: false,
@@ -397,11 +545,31 @@ fn test_names_only_mode() -> Result<()> {
         include_patterns: vec![],
         format: workspace::cli::OutputFormat::Plain,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         progress: workspace::cli::ProgressMode::Never,
         ignore_case: false,
         use_regex: false,
         include_hidden: false,
         binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
 
     run_refac(args)?;
@@ -431,6 +599,10 @@ fn test_content_only_mode() -> Result<()> {
         assume_yes: true,
         verbose: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         backup: false,
         files_only: false,
         dirs_only: false,
@@ -441,11 +613,31 @@ fn test_content_only_mode() -> Result<()> {
         include_patterns: vec![],
         format: workspace::cli::OutputFormat::Plain,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         progress: workspace::cli::ProgressMode::Never,
         ignore_case: false,
         use_regex: false,
         include_hidden: false,
         binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
 
     run_refac(args)?;
@@ -480,6 +672,10 @@ fn test_binary_file_handling_default() -> Result<()> {
         assume_yes: true,
         verbose: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         backup: false,
         files_only: false,
         dirs_only: false,
@@ -490,11 +686,31 @@ fn test_binary_file_handling_default() -> Result<()> {
         include_patterns: vec![],
         format: workspace::cli::OutputFormat::Plain,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         progress: workspace::cli::ProgressMode::Never,
         ignore_case: false,
         use_regex: false,
         include_hidden: false,
         binary_names: false, // Default: binary files are NOT renamed
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
 
     run_refac(args)?;
@@ -533,6 +749,10 @@ fn test_binary_file_handling_with_flag() -> Result<()> {
         assume_yes: true,
         verbose: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         backup: false,
         files_only: false,
         dirs_only: false,
@@ -543,11 +763,31 @@ fn test_binary_file_handling_with_flag() -> Result<()> {
         include_patterns: vec![],
         format: workspace::cli::OutputFormat::Plain,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         progress: workspace::cli::ProgressMode::Never,
         ignore_case: false,
         use_regex: false,
         include_hidden: false,
         binary_names: true, // Enable binary file renaming
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
 
     run_refac(args)?;
@@ -592,6 +832,10 @@ fn test_binary_flag_cli_integration() -> Result<()> {
         assume_yes: true,
         verbose: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         backup: false,
         files_only: false,
         dirs_only: false,
@@ -602,11 +846,31 @@ fn test_binary_flag_cli_integration() -> Result<()> {
         include_patterns: vec![],
         format: workspace::cli::OutputFormat::Plain,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         progress: workspace::cli::ProgressMode::Never,
         ignore_case: false,
         use_regex: false,
         include_hidden: false,
         binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
 
     run_refac(args_default)?;
@@ -647,6 +911,10 @@ fn test_binary_names_flag_enables_renaming() -> Result<()> {
         assume_yes: true,
         verbose: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         backup: false,
         files_only: false,
         dirs_only: false,
@@ -657,11 +925,31 @@ fn test_binary_names_flag_enables_renaming() -> Result<()> {
         include_patterns: vec![],
         format: workspace::cli::OutputFormat::Plain,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         progress: workspace::cli::ProgressMode::Never,
         ignore_case: false,
         use_regex: false,
         include_hidden: false,
         binary_names: true, // Enable binary renaming
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
 
     run_refac(args)?;
@@ -703,6 +991,10 @@ fn test_mixed_binary_text_with_flag() -> Result<()> {
         assume_yes: true,
         verbose: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         backup: false,
         files_only: false,
         dirs_only: false,
@@ -713,11 +1005,31 @@ fn test_mixed_binary_text_with_flag() -> Result<()> {
         include_patterns: vec![],
         format: workspace::cli::OutputFormat::Plain,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         progress: workspace::cli::ProgressMode::Never,
         ignore_case: false,
         use_regex: false,
         include_hidden: false,
         binary_names: true,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
 
     run_refac(args)?;
@@ -759,6 +1071,10 @@ fn test_binary_flag_with_names_only_mode() -> Result<()> {
         assume_yes: true,
         verbose: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         backup: false,
         files_only: false,
         dirs_only: false,
@@ -769,11 +1085,31 @@ fn test_binary_flag_with_names_only_mode() -> Result<()> {
         include_patterns: vec![],
         format: workspace::cli::OutputFormat::Plain,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         progress: workspace::cli::ProgressMode::Never,
         ignore_case: false,
         use_regex: false,
         include_hidden: false,
         binary_names: true,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
 
     run_refac(args)?;
@@ -812,6 +1148,10 @@ fn test_binary_flag_with_content_only_mode() -> Result<()> {
         assume_yes: true,
         verbose: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         backup: false,
         files_only: false,
         dirs_only: false,
@@ -822,11 +1162,31 @@ fn test_binary_flag_with_content_only_mode() -> Result<()> {
         include_patterns: vec![],
         format: workspace::cli::OutputFormat::Plain,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         progress: workspace::cli::ProgressMode::Never,
         ignore_case: false,
         use_regex: false,
         include_hidden: false,
         binary_names: true, // This should have no effect in content_only mode
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
 
     run_refac(args)?;
@@ -871,6 +1231,10 @@ fn test_binary_flag_edge_cases() -> Result<()> {
         assume_yes: true,
         verbose: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         backup: false,
         files_only: false,
         dirs_only: false,
@@ -881,11 +1245,31 @@ fn test_binary_flag_edge_cases() -> Result<()> {
         include_patterns: vec![],
         format: workspace::cli::OutputFormat::Plain,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         progress: workspace::cli::ProgressMode::Never,
         ignore_case: false,
         use_regex: false,
         include_hidden: false,
         binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
 
     run_refac(args_default)?;
@@ -918,6 +1302,10 @@ fn test_binary_flag_edge_cases() -> Result<()> {
         assume_yes: true,
         verbose: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         backup: false,
         files_only: false,
         dirs_only: false,
@@ -928,11 +1316,31 @@ fn test_binary_flag_edge_cases() -> Result<()> {
         include_patterns: vec![],
         format: workspace::cli::OutputFormat::Plain,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         progress: workspace::cli::ProgressMode::Never,
         ignore_case: false,
         use_regex: false,
         include_hidden: false,
         binary_names: true,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
 
     run_refac(args_with_flag)?;
@@ -972,6 +1380,10 @@ fn test_max_depth() -> Result<()> {
         assume_yes: true,
         verbose: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         backup: false,
         files_only: false,
         dirs_only: false,
@@ -982,11 +1394,31 @@ fn test_max_depth() -> Result<()> {
         include_patterns: vec![],
         format: workspace::cli::OutputFormat::Plain,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         progress: workspace::cli::ProgressMode::Never,
         ignore_case: false,
         use_regex: false,
         include_hidden: false,
         binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
 
     run_refac(args)?;
@@ -1015,6 +1447,10 @@ fn test_backup_functionality() -> Result<()> {
         assume_yes: true,
         verbose: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         backup: true,
         files_only: false,
         dirs_only: false,
@@ -1025,11 +1461,31 @@ fn test_backup_functionality() -> Result<()> {
         include_patterns: vec![],
         format: workspace::cli::OutputFormat::Plain,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         progress: workspace::cli::ProgressMode::Never,
         ignore_case: false,
         use_regex: false,
         include_hidden: false,
         binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
 
     run_refac(args)?;
@@ -1082,6 +1538,10 @@ fn test_multiple_occurrences() -> Result<()> {
         assume_yes: true,
         verbose: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         backup: false,
         files_only: false,
         dirs_only: false,
@@ -1092,11 +1552,31 @@ fn test_multiple_occurrences() -> Result<()> {
         include_patterns: vec![],
         format: workspace::cli::OutputFormat::Plain,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         progress: workspace::cli::ProgressMode::Never,
         ignore_case: false,
         use_regex: false,
         include_hidden: false,
         binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
 
     run_refac(args)?;
@@ -1127,6 +1607,10 @@ fn test_hidden_files() -> Result<()> {
         assume_yes: true,
         verbose: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         backup: false,
         files_only: false,
         dirs_only: false,
@@ -1137,11 +1621,31 @@ fn test_hidden_files() -> Result<()> {
         include_patterns: vec![".*".to_string()], // Include hidden files
         format: workspace::cli::OutputFormat::Plain,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         progress: workspace::cli::ProgressMode::Never,
         ignore_case: false,
         use_regex: false,
-        include_hidden: false,
+        include_hidden: true,
         binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
 
     run_refac(args)?;
@@ -1156,6 +1660,72 @@ fn test_hidden_files() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_include_pattern_does_not_imply_hidden() -> Result<()> {
+    let args = |root: &Path| Args {
+        root_dir: root.to_path_buf(),
+        pattern: "oldname".to_string(),
+        substitute: "newname".to_string(),
+        assume_yes: true,
+        verbose: false,
+        follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
+        backup: false,
+        files_only: false,
+        dirs_only: false,
+        names_only: false,
+        content_only: false,
+        max_depth: 0,
+        exclude_patterns: vec![],
+        include_patterns: vec!["*".to_string()],
+        format: workspace::cli::OutputFormat::Plain,
+        threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
+        progress: workspace::cli::ProgressMode::Never,
+        ignore_case: false,
+        use_regex: false,
+        include_hidden: false,
+        binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
+    };
+
+    // Without --hidden, a wildcard include leaves hidden files alone
+    let temp_dir = TempDir::new()?;
+    fs::write(temp_dir.path().join(".oldname_hidden"), "oldname")?;
+    fs::write(temp_dir.path().join("oldname.txt"), "oldname")?;
+    run_refac(args(temp_dir.path()))?;
+    assert!(temp_dir.path().join("newname.txt").exists());
+    assert!(temp_dir.path().join(".oldname_hidden").exists());
+
+    // Unless the legacy inference is turned on
+    let temp_dir = TempDir::new()?;
+    fs::write(temp_dir.path().join(".oldname_hidden"), "oldname")?;
+    workspace::RenameEngine::new(args(temp_dir.path()))?.with_legacy_hidden_inference(true).execute()?;
+    assert_eq!(fs::read_to_string(temp_dir.path().join(".newname_hidden"))?, "newname");
+    Ok(())
+}
+
 #[test]
 fn test_exclude_patterns() -> Result<()> {
     let temp_dir = TempDir::new()?;
@@ -1173,6 +1743,10 @@ fn test_exclude_patterns() -> Result<()> {
         assume_yes: true,
         verbose: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         backup: false,
         files_only: false,
         dirs_only: false,
@@ -1183,11 +1757,31 @@ fn test_exclude_patterns() -> Result<()> {
         include_patterns: vec![],
         format: workspace::cli::OutputFormat::Plain,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         progress: workspace::cli::ProgressMode::Never,
         ignore_case: false,
         use_regex: false,
         include_hidden: false,
         binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
 
     run_refac(args)?;
@@ -1199,6 +1793,72 @@ fn test_exclude_patterns() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_exclude_containing() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    
+    File::create(temp_dir.path().join("oldname_plain.txt"))?
+        .write_all(b"oldname content")?;
+    File::create(temp_dir.path().join("oldname_frozen.txt"))?
+        .write_all(b"// DO NOT RENAME\noldname content")?;
+
+    let args = Args {
+        root_dir: temp_dir.path().to_path_buf(),
+        pattern: "oldname".to_string(),
+        substitute: "newname".to_string(),
+        assume_yes: true,
+        verbose: false,
+        follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
+        backup: false,
+        files_only: false,
+        dirs_only: false,
+        names_only: false,
+        content_only: false,
+        max_depth: 0,
+        exclude_patterns: vec![],
+        include_patterns: vec![],
+        format: workspace::cli::OutputFormat::Plain,
+        threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
+        progress: workspace::cli::ProgressMode::Never,
+        ignore_case: false,
+        use_regex: false,
+        include_hidden: false,
+        binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: Some("DO NOT (RENAME|EDIT)".to_string()),
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
+    };
+
+    run_refac(args)?;
+
+    // The marked file keeps both its name and its content
+    assert_eq!(fs::read_to_string(temp_dir.path().join("newname_plain.txt"))?, "newname content");
+    assert_eq!(fs::read_to_string(temp_dir.path().join("oldname_frozen.txt"))?, "// DO NOT RENAME\noldname content");
+
+    Ok(())
+}
+
 #[test]
 fn test_parallel_processing() -> Result<()> {
     let temp_dir = TempDir::new()?;
@@ -1216,6 +1876,10 @@ fn test_parallel_processing() -> Result<()> {
         assume_yes: true,
         verbose: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         backup: false,
         files_only: false,
         dirs_only: false,
@@ -1226,11 +1890,31 @@ fn test_parallel_processing() -> Result<()> {
         include_patterns: vec![],
         format: workspace::cli::OutputFormat::Plain,
         threads: 4, // Use multiple threads
+        io_profile: None,
+        adaptive_threads: false,
         progress: workspace::cli::ProgressMode::Never,
         ignore_case: false,
         use_regex: false,
         include_hidden: false,
         binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
 
     run_refac(args)?;
@@ -1270,6 +1954,10 @@ fn test_include_hidden_flag() -> Result<()> {
         assume_yes: true,
         verbose: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         backup: false,
         files_only: false,
         dirs_only: false,
@@ -1280,11 +1968,31 @@ fn test_include_hidden_flag() -> Result<()> {
         include_patterns: vec![],
         format: workspace::cli::OutputFormat::Plain,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         progress: workspace::cli::ProgressMode::Never,
         ignore_case: false,
         use_regex: false,
         include_hidden: true, // Enable hidden file processing
         binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
 
     run_refac(args)?;
@@ -1329,6 +2037,10 @@ fn test_include_hidden_flag_disabled() -> Result<()> {
         assume_yes: true,
         verbose: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         backup: false,
         files_only: false,
         dirs_only: false,
@@ -1339,11 +2051,31 @@ fn test_include_hidden_flag_disabled() -> Result<()> {
         include_patterns: vec![],
         format: workspace::cli::OutputFormat::Plain,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         progress: workspace::cli::ProgressMode::Never,
         ignore_case: false,
         use_regex: false,
         include_hidden: false, // Disable hidden file processing
         binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
 
     run_refac(args)?;
@@ -1378,6 +2110,10 @@ fn test_include_hidden_with_patterns() -> Result<()> {
         assume_yes: true,
         verbose: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         backup: false,
         files_only: false,
         dirs_only: false,
@@ -1388,11 +2124,31 @@ fn test_include_hidden_with_patterns() -> Result<()> {
         include_patterns: vec![],
         format: workspace::cli::OutputFormat::Plain,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         progress: workspace::cli::ProgressMode::Never,
         ignore_case: false,
         use_regex: false,
         include_hidden: true, // Enable hidden file processing
         binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
 
     run_refac(args)?;
@@ -1425,6 +2181,10 @@ fn test_content_only_allows_path_separators() -> Result<()> {
         assume_yes: true,
         verbose: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         backup: false,
         files_only: false,
         dirs_only: false,
@@ -1435,11 +2195,31 @@ fn test_content_only_allows_path_separators() -> Result<()> {
         include_patterns: vec![],
         format: workspace::cli::OutputFormat::Plain,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         progress: workspace::cli::ProgressMode::Never,
         ignore_case: false,
         use_regex: false,
         include_hidden: false,
         binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
     
     run_refac(args)?;
@@ -1472,6 +2252,10 @@ fn test_typescript_file_processing() -> Result<()> {
         assume_yes: true,
         verbose: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         backup: false,
         files_only: false,
         dirs_only: false,
@@ -1482,11 +2266,31 @@ fn test_typescript_file_processing() -> Result<()> {
         include_patterns: vec![],
         format: workspace::cli::OutputFormat::Plain,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         progress: workspace::cli::ProgressMode::Never,
         ignore_case: false,
         use_regex: false,
         include_hidden: false,
         binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
     
     run_refac(args)?;
@@ -1517,6 +2321,10 @@ fn test_mutual_exclusion_validation() -> Result<()> {
         assume_yes: true,
         verbose: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         backup: false,
         files_only: false,
         dirs_only: false,
@@ -1527,11 +2335,31 @@ fn test_mutual_exclusion_validation() -> Result<()> {
         include_patterns: vec![],
         format: workspace::cli::OutputFormat::Plain,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         progress: workspace::cli::ProgressMode::Never,
         ignore_case: false,
         use_regex: false,
         include_hidden: false,
         binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
     
     // Should fail during validation
@@ -1539,4 +2367,553 @@ fn test_mutual_exclusion_validation() -> Result<()> {
     assert!(result.is_err());
     
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(unix)]
+#[test]
+fn test_content_handler_for_binary_format() -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    use workspace::refac::handlers::ContentHandler;
+    use workspace::RenameEngine;
+
+    let temp_dir = TempDir::new()?;
+    let handler_dir = TempDir::new()?;
+    let script = handler_dir.path().join("nb-handler");
+    fs::write(&script, "#!/bin/sh\nsed \"s/$1/$2/g\"\n")?;
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755))?;
+
+    // NUL bytes make the built-in replacer treat the file as binary and skip it
+    File::create(temp_dir.path().join("data.nb"))?.write_all(b"\x00oldname cell\x00\n")?;
+    File::create(temp_dir.path().join("data.bin"))?.write_all(b"\x00oldname cell\x00\n")?;
+
+    let args = Args {
+        root_dir: temp_dir.path().to_path_buf(),
+        pattern: "oldname".to_string(),
+        substitute: "newname".to_string(),
+        assume_yes: true,
+        verbose: false,
+        follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
+        backup: false,
+        files_only: false,
+        dirs_only: false,
+        names_only: false,
+        content_only: true,
+        max_depth: 0,
+        exclude_patterns: vec![],
+        include_patterns: vec![],
+        format: workspace::cli::OutputFormat::Plain,
+        threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
+        progress: workspace::cli::ProgressMode::Never,
+        ignore_case: false,
+        use_regex: false,
+        include_hidden: false,
+        binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
+    };
+    RenameEngine::new(args)?
+        .with_handlers(vec![ContentHandler::new("*.nb", script.to_str().unwrap())])
+        .execute()?;
+
+    assert_eq!(fs::read(temp_dir.path().join("data.nb"))?, b"\x00newname cell\x00\n");
+    assert_eq!(fs::read(temp_dir.path().join("data.bin"))?, b"\x00oldname cell\x00\n");
+    Ok(())
+}
+
+#[test]
+fn test_token_mode() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    fs::write(temp_dir.path().join("user.rs"), "// user helpers\nfn user() -> &'static str { \"user\" }\nfn username() {}\n")?;
+    fs::write(temp_dir.path().join("user_list.py"), "user = load()  # user\n")?;
+
+    let args = Args {
+        root_dir: temp_dir.path().to_path_buf(),
+        pattern: "user".to_string(),
+        substitute: "account".to_string(),
+        assume_yes: true,
+        verbose: false,
+        follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
+        backup: false,
+        files_only: false,
+        dirs_only: false,
+        names_only: false,
+        content_only: false,
+        max_depth: 0,
+        exclude_patterns: vec![],
+        include_patterns: vec![],
+        format: workspace::cli::OutputFormat::Plain,
+        threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
+        progress: workspace::cli::ProgressMode::Never,
+        ignore_case: false,
+        use_regex: false,
+        include_hidden: false,
+        binary_names: false,
+        token_mode: true,
+        skip_literals: true,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
+    };
+    run_refac(args)?;
+
+    // `user` is a whole identifier in user.rs but not in user_list.py
+    let rust = fs::read_to_string(temp_dir.path().join("account.rs"))?;
+    assert_eq!(rust, "// user helpers\nfn account() -> &'static str { \"user\" }\nfn username() {}\n");
+    let python = fs::read_to_string(temp_dir.path().join("user_list.py"))?;
+    assert_eq!(python, "account = load()  # user\n");
+    Ok(())
+}
+
+#[test]
+fn test_report_html() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let report_dir = TempDir::new()?;
+    let report_path = report_dir.path().join("plan.html");
+    fs::write(temp_dir.path().join("oldname.txt"), "keep <this>\nuse oldname here\n")?;
+
+    let args = Args {
+        root_dir: temp_dir.path().to_path_buf(),
+        pattern: "oldname".to_string(),
+        substitute: "newname".to_string(),
+        assume_yes: true,
+        verbose: false,
+        follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
+        backup: false,
+        files_only: false,
+        dirs_only: false,
+        names_only: false,
+        content_only: false,
+        max_depth: 0,
+        exclude_patterns: vec![],
+        include_patterns: vec![],
+        format: workspace::cli::OutputFormat::Plain,
+        threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
+        progress: workspace::cli::ProgressMode::Never,
+        ignore_case: false,
+        use_regex: false,
+        include_hidden: false,
+        binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: Some(report_path.clone()),
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
+    };
+    run_refac(args)?;
+
+    let report = fs::read_to_string(&report_path)?;
+    assert!(report.starts_with("<!DOCTYPE html>"));
+    assert!(report.contains("<td><code>oldname.txt</code></td><td><code>newname.txt</code></td>"));
+    assert!(report.contains("- use oldname here"));
+    assert!(report.contains("+ use newname here"));
+
+    // Nothing is changed
+    assert!(!temp_dir.path().join("newname.txt").exists());
+    let content = fs::read_to_string(temp_dir.path().join("oldname.txt"))?;
+    assert_eq!(content, "keep <this>\nuse oldname here\n");
+    Ok(())
+}
+
+#[test]
+fn test_into_archives() -> Result<()> {
+    use std::io::Read;
+
+    let temp_dir = TempDir::new()?;
+    let zip_path = temp_dir.path().join("bundle.zip");
+    let mut writer = zip::ZipWriter::new(File::create(&zip_path)?);
+    let options = zip::write::FileOptions::default();
+    writer.start_file("docs/oldname.txt", options)?;
+    writer.write_all(b"about oldname\n")?;
+    writer.start_file("image.bin", options)?;
+    writer.write_all(&[0, 1, 2, b'o', b'l', b'd', 0])?;
+    writer.finish()?;
+
+    // A gzip file above the size limit is skipped
+    let mut encoder = flate2::write::GzEncoder::new(File::create(temp_dir.path().join("big.log.gz"))?, flate2::Compression::default());
+    encoder.write_all(&b"oldname\n".repeat(200_000))?;
+    encoder.finish()?;
+
+    let args = Args {
+        root_dir: temp_dir.path().to_path_buf(),
+        pattern: "oldname".to_string(),
+        substitute: "newname".to_string(),
+        assume_yes: true,
+        verbose: false,
+        follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
+        backup: false,
+        files_only: false,
+        dirs_only: false,
+        names_only: false,
+        content_only: false,
+        max_depth: 0,
+        exclude_patterns: vec![],
+        include_patterns: vec![],
+        format: workspace::cli::OutputFormat::Plain,
+        threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
+        progress: workspace::cli::ProgressMode::Never,
+        ignore_case: false,
+        use_regex: false,
+        include_hidden: false,
+        binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: true,
+        archive_size_limit: 1,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
+    };
+    run_refac(args)?;
+
+    let mut archive = zip::ZipArchive::new(File::open(&zip_path)?)?;
+    let mut content = String::new();
+    archive.by_name("docs/newname.txt")?.read_to_string(&mut content)?;
+    assert_eq!(content, "about newname\n");
+    let mut binary = Vec::new();
+    archive.by_name("image.bin")?.read_to_end(&mut binary)?;
+    assert_eq!(binary, [0, 1, 2, b'o', b'l', b'd', 0]);
+
+    let mut log = String::new();
+    flate2::read::GzDecoder::new(File::open(temp_dir.path().join("big.log.gz"))?).read_to_string(&mut log)?;
+    assert!(log.starts_with("oldname\n"));
+    Ok(())
+}
+
+#[test]
+fn test_update_imports() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::create_dir(root.join("src"))?;
+    fs::write(root.join("src/user.py"), "def load():\n    pass\n")?;
+    fs::write(root.join("src/main.py"), "import user\nfrom user import load\nusername = 1\n")?;
+    fs::write(root.join("src/user.rs"), "pub fn load() {}\n")?;
+    fs::write(root.join("src/lib.rs"), "mod user;\nuse crate::user::load;\nconst USER: &str = \"user\";\n")?;
+
+    let args = Args {
+        root_dir: root.to_path_buf(),
+        pattern: "user".to_string(),
+        substitute: "account".to_string(),
+        assume_yes: true,
+        verbose: false,
+        follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
+        backup: false,
+        files_only: false,
+        dirs_only: false,
+        names_only: true,
+        content_only: false,
+        max_depth: 0,
+        exclude_patterns: vec![],
+        include_patterns: vec![],
+        format: workspace::cli::OutputFormat::Plain,
+        threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
+        progress: workspace::cli::ProgressMode::Never,
+        ignore_case: false,
+        use_regex: false,
+        include_hidden: false,
+        binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: true,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
+    };
+    run_refac(args)?;
+
+    assert!(root.join("src/account.py").exists());
+    assert!(root.join("src/account.rs").exists());
+    // Only module references change, not other uses of the name
+    assert_eq!(fs::read_to_string(root.join("src/main.py"))?, "import account\nfrom account import load\nusername = 1\n");
+    assert_eq!(fs::read_to_string(root.join("src/lib.rs"))?, "mod account;\nuse crate::account::load;\nconst USER: &str = \"user\";\n");
+    Ok(())
+}
+
+#[test]
+fn test_stats() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    fs::write(temp_dir.path().join("oldname.txt"), "oldname\n")?;
+    fs::write(temp_dir.path().join("other.txt"), "nothing here\n")?;
+
+    let output = assert_cmd::Command::cargo_bin("ws")?
+        .args(["refactor", ".", "oldname", "newname", "--assume-yes", "--format", "json", "--threads", "2", "--stats"])
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_dir.path())
+        .output()?;
+    assert!(output.status.success());
+
+    // The counters go to stderr, apart from the JSON report on stdout
+    let stderr = String::from_utf8(output.stderr)?;
+    let stats = stderr.lines().find_map(|line| serde_json::from_str::<serde_json::Value>(line).ok()).unwrap();
+    assert_eq!(stats["files_walked"], 2);
+    assert_eq!(stats["bytes_rewritten"], "newname\n".len());
+    assert_eq!(stats["threads"], 2);
+    assert!(stats["detector"]["cache_hits"].as_u64().unwrap() > 0);
+    let phases: Vec<&str> = stats["phases"].as_array().unwrap().iter().map(|phase| phase["phase"].as_str().unwrap()).collect();
+    assert_eq!(phases, ["discovery", "collisions", "validation", "summary", "content", "imports", "renames"]);
+    assert!(stats["thread_utilization"].as_f64().is_some());
+    Ok(())
+}
+
+#[test]
+fn test_json_output_schema() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    fs::write(temp_dir.path().join("oldname.txt"), "oldname\n")?;
+    let ws = |args: &[&str]| -> Result<std::process::Output> {
+        Ok(assert_cmd::Command::cargo_bin("ws")?
+            .args(args)
+            .env("WS_COMPLETIONS_LOADED", "1")
+            .current_dir(temp_dir.path())
+            .output()?)
+    };
+    let documents = |output: &std::process::Output| -> Vec<serde_json::Value> {
+        serde_json::Deserializer::from_slice(&output.stdout)
+            .into_iter::<serde_json::Value>()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    };
+
+    let schema: serde_json::Value = serde_json::from_slice(&ws(&["refactor", "schema"])?.stdout)?;
+    assert_eq!(schema["properties"]["schema_version"]["const"], 1);
+
+    // Stdout holds only the summary and the final report; messages go to stderr
+    let output = ws(&["refactor", ".", "oldname", "newname", "--assume-yes", "--format", "json"])?;
+    assert!(output.status.success());
+    let docs = documents(&output);
+    assert_eq!(docs.len(), 2);
+    assert_eq!(docs[0]["kind"], "summary");
+    assert_eq!(docs[0]["summary"]["file_renames"], 1);
+    assert_eq!(docs[1]["kind"], "report");
+    assert!(docs.iter().all(|doc| doc["schema_version"] == 1));
+
+    // Collisions are reported as a document before the run stops
+    fs::write(temp_dir.path().join("b_newname.txt"), "")?;
+    fs::write(temp_dir.path().join("b_oldname.txt"), "")?;
+    let output = ws(&["refactor", ".", "oldname", "newname", "--assume-yes", "--format", "json"])?;
+    assert!(!output.status.success());
+    let docs = documents(&output);
+    assert_eq!(docs.len(), 1);
+    assert_eq!(docs[0]["kind"], "collisions");
+    assert_eq!(docs[0]["collisions"][0]["type"], "TargetAlreadyExists");
+    Ok(())
+}
+
+#[test]
+fn test_preview_server_applies_accepted_changes() -> Result<()> {
+    use std::io::{BufRead, BufReader, Read};
+    use std::net::TcpStream;
+
+    let temp_dir = TempDir::new()?;
+    fs::write(temp_dir.path().join("oldname.txt"), "oldname\n")?;
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin("ws"))
+        .args(["refactor", ".", "oldname", "newname", "preview", "--port", "0"])
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_dir.path())
+        .stdout(std::process::Stdio::piped())
+        .spawn()?;
+
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let address = loop {
+        let mut line = String::new();
+        assert!(stdout.read_line(&mut line)? > 0, "preview server exited before serving");
+        if let Some(start) = line.find("http://") {
+            let address = line[start + "http://".len()..].split('/').next().unwrap().to_string();
+            break address;
+        }
+    };
+    let request = |request: String| -> Result<String> {
+        let mut stream = TcpStream::connect(&address)?;
+        stream.write_all(request.as_bytes())?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+        Ok(response)
+    };
+
+    let page = request("GET / HTTP/1.1\r\nHost: localhost\r\n\r\n".to_string())?;
+    assert!(page.starts_with("HTTP/1.1 200 OK"));
+    assert!(page.contains("name=\"rename\" value=\"0\""));
+    assert!(page.contains("name=\"content\" value=\"0\""));
+
+    // Accept the rename, reject the content change
+    let body = "rename=0";
+    let response = request(format!(
+        "POST /apply HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/x-www-form-urlencoded\r\nContent-Length: {}\r\n\r\n{}",
+        body.len(),
+        body
+    ))?;
+    assert!(response.contains("Changes applied"));
+    assert!(child.wait()?.success());
+
+    assert!(!temp_dir.path().join("oldname.txt").exists());
+    assert_eq!(fs::read_to_string(temp_dir.path().join("newname.txt"))?, "oldname\n");
+    Ok(())
+}
+
+#[test]
+fn test_include_root_renames_root_last() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path().join("oldname-project");
+    fs::create_dir_all(root.join("oldname_dir"))?;
+    fs::write(root.join("oldname_dir/oldname.txt"), "oldname\n")?;
+
+    let output = assert_cmd::Command::cargo_bin("ws")?
+        .args(["refactor", "oldname-project", "oldname", "newname", "--assume-yes", "--format", "json", "--include-root"])
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_dir.path())
+        .output()?;
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let docs: Vec<serde_json::Value> = serde_json::Deserializer::from_slice(&output.stdout)
+        .into_iter::<serde_json::Value>()
+        .collect::<Result<_, _>>()?;
+
+    let new_root = temp_dir.path().canonicalize()?.join("newname-project");
+    assert_eq!(docs[0]["summary"]["directory_renames"], 2);
+    assert_eq!(docs[0]["new_root"], new_root.to_string_lossy().as_ref());
+    assert_eq!(docs[1]["new_root"], new_root.to_string_lossy().as_ref());
+    assert!(!root.exists());
+    assert_eq!(fs::read_to_string(new_root.join("newname_dir/newname.txt"))?, "newname\n");
+
+    // Without the flag the root keeps its name
+    fs::create_dir_all(temp_dir.path().join("oldname-other"))?;
+    assert_cmd::Command::cargo_bin("ws")?
+        .args(["refactor", "oldname-other", "oldname", "newname", "--assume-yes"])
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    assert!(temp_dir.path().join("oldname-other").exists());
+    Ok(())
+}
+
+#[test]
+fn test_verify_applied_plan() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let project = temp_dir.path().join("project");
+    fs::create_dir_all(project.join("oldname_dir"))?;
+    fs::write(project.join("oldname_dir/oldname.txt"), "oldname\n")?;
+    fs::write(project.join("notes.txt"), "see oldname\n")?;
+    let ws = |args: &[&str]| -> Result<std::process::Output> {
+        Ok(assert_cmd::Command::cargo_bin("ws")?
+            .args(args)
+            .env("WS_COMPLETIONS_LOADED", "1")
+            .current_dir(temp_dir.path())
+            .output()?)
+    };
+
+    let output = ws(&["refactor", "project", "oldname", "newname", "--assume-yes", "--format", "json"])?;
+    assert!(output.status.success());
+    let summary = serde_json::Deserializer::from_slice(&output.stdout).into_iter::<serde_json::Value>().next().unwrap()?;
+    assert_eq!(summary["pattern"], "oldname");
+    fs::write(temp_dir.path().join("plan.json"), serde_json::to_string(&summary)?)?;
+
+    let output = ws(&["refactor", "verify", "plan.json"])?;
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("all 4 check(s) passed"));
+
+    // A change that did not stick fails the check
+    fs::write(project.join("notes.txt"), "see oldname\n")?;
+    let output = ws(&["refactor", "verify", "plan.json", "--format", "json"])?;
+    assert!(!output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(report["kind"], "verification");
+    assert_eq!(report["result"], "fail");
+    assert_eq!(report["failed"], 1);
+    let failed: Vec<_> = report["checks"].as_array().unwrap().iter().filter(|check| check["passed"] == false).collect();
+    assert_eq!(failed[0]["check"], "content");
+    assert!(failed[0]["path"].as_str().unwrap().ends_with("notes.txt"));
+    Ok(())
+}
@@ -6,6 +6,7 @@ use std::path::Path;
 use std::thread;
 use std::time::Duration;
 use tempfile::TempDir;
+use workspace::refac::lock::RootLock;
 use workspace::{cli::Args, run_refac};
 
 /// Tests for concurrent operation safety and thread-related edge cases in refac tool
@@ -204,6 +205,41 @@ fn test_large_file_concurrent_processing() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_mixed_size_tree_processing() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+
+    // Many small files, a few medium ones and one above the chunking threshold
+    for i in 0..200 {
+        fs::write(temp_dir.path().join(format!("small_{}.txt", i)), format!("oldname {}\n", i))?;
+    }
+    let line = "oldname line with oldname_suffix and é\n";
+    for i in 0..3 {
+        fs::write(temp_dir.path().join(format!("medium_{}.txt", i)), line.repeat(5_000))?;
+    }
+    let large_lines = 9 * 1024 * 1024 / line.len() + 1;
+    fs::write(temp_dir.path().join("large.txt"), line.repeat(large_lines))?;
+
+    let args = Args {
+        threads: 4,
+        ..create_test_args(temp_dir.path(), "oldname", "newname")
+    };
+    run_refac(args)?;
+
+    let expected = "newname line with newname_suffix and é\n";
+    for i in 0..200 {
+        assert_eq!(fs::read_to_string(temp_dir.path().join(format!("small_{}.txt", i)))?, format!("newname {}\n", i));
+    }
+    for i in 0..3 {
+        assert_eq!(fs::read_to_string(temp_dir.path().join(format!("medium_{}.txt", i)))?, expected.repeat(5_000));
+    }
+    let large = fs::read_to_string(temp_dir.path().join("large.txt"))?;
+    assert_eq!(large.len(), expected.len() * large_lines);
+    assert!(large == expected.repeat(large_lines), "Large file was not replaced consistently");
+
+    Ok(())
+}
+
 #[test]
 fn test_mixed_operation_modes_concurrency() -> Result<()> {
     let temp_dir = TempDir::new()?;
@@ -445,6 +481,35 @@ fn test_concurrent_directory_tree_modification() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_runs_on_the_same_root_are_serialized() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path().canonicalize()?;
+    fs::write(root.join("oldname.txt"), "oldname")?;
+
+    // Another run holds the root
+    let lock = RootLock::acquire(&root, false, |_| {})?;
+    let error = run_refac(create_test_args(&root, "oldname", "newname")).unwrap_err();
+    assert!(error.to_string().contains("--wait"), "{}", error);
+    assert!(root.join("oldname.txt").exists());
+
+    // --wait goes ahead once it is released
+    let release = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(300));
+        drop(lock);
+    });
+    run_refac(Args { wait: true, ..create_test_args(&root, "oldname", "newname") })?;
+    release.join().unwrap();
+    assert_eq!(fs::read_to_string(root.join("newname.txt"))?, "newname");
+
+    // --no-lock ignores it
+    let _lock = RootLock::acquire(&root, false, |_| {})?;
+    run_refac(Args { no_lock: true, ..create_test_args(&root, "newname", "thirdname") })?;
+    assert!(root.join("thirdname.txt").exists());
+
+    Ok(())
+}
+
 // Helper function to create standardized test arguments
 fn create_test_args(root_dir: &Path, pattern: &str, substitute: &str) -> Args {
     Args {
@@ -454,6 +519,10 @@ fn create_test_args(root_dir: &Path, pattern: &str, substitute: &str) -> Args {
         assume_yes: true,
         verbose: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         backup: false,
         files_only: false,
         dirs_only: false,
@@ -464,10 +533,30 @@ fn create_test_args(root_dir: &Path, pattern: &str, substitute: &str) -> Args {
         include_patterns: vec![],
         format: workspace::cli::OutputFormat::Plain,
         threads: 1, // Will be overridden in individual tests
+        io_profile: None,
+        adaptive_threads: false,
         progress: workspace::cli::ProgressMode::Never,
         ignore_case: false,
         use_regex: false,
         include_hidden: false,
         binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     }
 }
\ No newline at end of file
@@ -49,6 +49,8 @@ fn test_diff_preview_basic_functionality() -> Result<()> {
         verbose: true,
         progress: ProgressMode::Never,  // Disable progress for cleaner test output
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         max_depth: 0,
         files_only: false,
         dirs_only: false,
@@ -59,9 +61,31 @@ fn test_diff_preview_basic_functionality() -> Result<()> {
         ignore_case: false,
         use_regex: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         include_hidden: false,
         backup: false,
         binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
 
     // Create rename engine
@@ -104,6 +128,8 @@ fn test_diff_preview_line_counting() -> Result<()> {
         verbose: true,
         progress: ProgressMode::Never,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         max_depth: 0,
         files_only: false,
         dirs_only: false,
@@ -114,9 +140,31 @@ fn test_diff_preview_line_counting() -> Result<()> {
         ignore_case: false,
         use_regex: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         include_hidden: false,
         backup: false,
         binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
 
     let engine = RenameEngine::new(args)?;
@@ -156,15 +204,39 @@ fn test_diff_preview_with_regex_patterns() -> Result<()> {
         verbose: true,
         progress: ProgressMode::Never,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         max_depth: 0,
         include_patterns: vec![],
         exclude_patterns: vec![],
         ignore_case: false,
         use_regex: true,  // Enable regex mode
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         include_hidden: false,
         backup: false,
         binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
 
     let engine = RenameEngine::new(args)?;
@@ -200,15 +272,39 @@ fn test_diff_preview_no_changes_scenario() -> Result<()> {
         verbose: true,
         progress: ProgressMode::Never,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         max_depth: 0,
         include_patterns: vec![],
         exclude_patterns: vec![],
         ignore_case: false,
         use_regex: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         include_hidden: false,
         backup: false,
         binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
 
     let engine = RenameEngine::new(args)?;
@@ -255,15 +351,39 @@ fn test_diff_preview_context_lines() -> Result<()> {
         verbose: true,
         progress: ProgressMode::Never,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         max_depth: 0,
         include_patterns: vec![],
         exclude_patterns: vec![],
         ignore_case: false,
         use_regex: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         include_hidden: false,
         backup: false,
         binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
 
     let engine = RenameEngine::new(args)?;
@@ -308,15 +428,39 @@ fn test_diff_preview_multiple_files_limit() -> Result<()> {
         verbose: true,
         progress: ProgressMode::Never,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         max_depth: 0,
         include_patterns: vec![],
         exclude_patterns: vec![],
         ignore_case: false,
         use_regex: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         include_hidden: false,
         backup: false,
         binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     };
 
     let engine = RenameEngine::new(args)?;
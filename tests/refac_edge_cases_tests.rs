@@ -102,6 +102,7 @@ fn test_directories_with_only_hidden_files() -> Result<()> {
 
     let args = Args {
         include_patterns: vec!["*".to_string()], // Include all files including hidden ones
+        include_hidden: true,
         ..create_test_args(temp_dir.path(), "oldname", "newname")
     };
     
@@ -506,6 +507,49 @@ fn test_interrupted_operation_recovery() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_output_dir_leaves_source_untouched() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let source = temp_dir.path().join("source");
+    fs::create_dir_all(source.join("oldname_dir/nested"))?;
+    fs::write(source.join("oldname_dir/oldname.txt"), "oldname content")?;
+    fs::write(source.join("oldname_dir/nested/plain.txt"), "unrelated")?;
+    fs::write(source.join(".hidden_oldname"), "oldname")?;
+    fs::create_dir(source.join("oldname_empty"))?;
+
+    // Read-only sources only need to be read
+    let readonly = source.join("oldname_readonly.sh");
+    fs::write(&readonly, "echo oldname")?;
+    fs::set_permissions(&readonly, fs::Permissions::from_mode(0o555))?;
+
+    let output = temp_dir.path().join("output");
+    let mut args = create_test_args(&source, "oldname", "newname");
+    args.output_dir = Some(output.clone());
+    run_refac(args)?;
+
+    assert_eq!(fs::read_to_string(output.join("newname_dir/newname.txt"))?, "newname content");
+    assert_eq!(fs::read_to_string(output.join("newname_dir/nested/plain.txt"))?, "unrelated");
+    assert!(output.join("newname_empty").is_dir());
+    assert_eq!(fs::read_to_string(output.join("newname_readonly.sh"))?, "echo newname");
+    assert_eq!(fs::metadata(output.join("newname_readonly.sh"))?.permissions().mode() & 0o777, 0o555);
+    // Hidden files are copied but, like in place, left alone
+    assert_eq!(fs::read_to_string(output.join(".hidden_oldname"))?, "oldname");
+
+    // The source is as it was
+    assert_eq!(fs::read_to_string(source.join("oldname_dir/oldname.txt"))?, "oldname content");
+    assert!(!source.join("newname_dir").exists());
+
+    // The output has to be a new or empty directory outside the root
+    let mut args = create_test_args(&source, "oldname", "newname");
+    args.output_dir = Some(output);
+    assert!(run_refac(args).is_err());
+    let mut args = create_test_args(&source, "oldname", "newname");
+    args.output_dir = Some(source.join("out"));
+    assert!(run_refac(args).is_err());
+
+    Ok(())
+}
+
 // Helper function to create standardized test arguments
 fn create_test_args(root_dir: &Path, pattern: &str, substitute: &str) -> Args {
     Args {
@@ -515,6 +559,10 @@ fn create_test_args(root_dir: &Path, pattern: &str, substitute: &str) -> Args {
         assume_yes: true,
         verbose: false,
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         backup: false,
         files_only: false,
         dirs_only: false,
@@ -525,10 +573,30 @@ fn create_test_args(root_dir: &Path, pattern: &str, substitute: &str) -> Args {
         include_patterns: vec![],
         format: workspace::cli::OutputFormat::Plain,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         progress: workspace::cli::ProgressMode::Never,
         ignore_case: false,
         use_regex: false,
         include_hidden: false,
         binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     }
 }
\ No newline at end of file
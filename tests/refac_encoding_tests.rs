@@ -282,6 +282,10 @@ fn create_test_args(root_dir: &Path, pattern: &str, substitute: &str) -> Args {
         assume_yes: true,
         verbose: true, // Enable verbose for better debugging
         follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
         backup: false,
         files_only: false,
         dirs_only: false,
@@ -292,10 +296,30 @@ fn create_test_args(root_dir: &Path, pattern: &str, substitute: &str) -> Args {
         include_patterns: vec![],
         format: workspace::cli::OutputFormat::Plain,
         threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
         progress: workspace::cli::ProgressMode::Never,
         ignore_case: false,
         use_regex: false,
         include_hidden: false,
         binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: None,
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
     }
 }
\ No newline at end of file
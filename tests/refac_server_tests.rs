@@ -0,0 +1,162 @@
+#![cfg(unix)]
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+use tempfile::TempDir;
+use workspace::cli::Args;
+use workspace::refac::server::serve;
+
+/// Tests for `ws refactor --serve`, driving the daemon over its socket like an editor plugin
+
+struct Client {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+    next_id: u64,
+}
+
+impl Client {
+    fn connect(socket: &Path) -> Result<Self> {
+        // The server binds the socket on its own thread
+        for _ in 0..100 {
+            if let Ok(stream) = UnixStream::connect(socket) {
+                return Ok(Self { reader: BufReader::new(stream.try_clone()?), writer: stream, next_id: 1 });
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        anyhow::bail!("Server did not start listening on {}", socket.display())
+    }
+
+    fn call(&mut self, method: &str, params: Value) -> Result<Value> {
+        let id = self.next_id;
+        self.next_id += 1;
+        writeln!(self.writer, "{}", json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params}))?;
+        let mut line = String::new();
+        self.reader.read_line(&mut line)?;
+        let response: Value = serde_json::from_str(&line)?;
+        assert_eq!(response["id"], json!(id));
+        Ok(response)
+    }
+}
+
+fn template_args(socket: &Path) -> Args {
+    Args {
+        root_dir: ".".into(),
+        pattern: String::new(),
+        substitute: String::new(),
+        assume_yes: true,
+        verbose: false,
+        follow_symlinks: false,
+        max_symlink_depth: 8,
+        include_root: false,
+        time_limit: None,
+        checkpoint: None,
+        backup: false,
+        files_only: false,
+        dirs_only: false,
+        names_only: false,
+        content_only: false,
+        max_depth: 0,
+        exclude_patterns: vec![],
+        include_patterns: vec![],
+        format: workspace::cli::OutputFormat::Plain,
+        threads: 1,
+        io_profile: None,
+        adaptive_threads: false,
+        progress: workspace::cli::ProgressMode::Never,
+        ignore_case: false,
+        use_regex: false,
+        include_hidden: false,
+        binary_names: false,
+        token_mode: false,
+        skip_literals: false,
+        report_html: None,
+        serve: Some(socket.to_path_buf()),
+        command: None,
+        into_archives: false,
+        archive_size_limit: 64,
+        update_imports: false,
+        durability: workspace::cli::Durability::Safe,
+        stats: false,
+        retry_locked: 0,
+        exclude_containing: None,
+        show_skipped: false,
+        case_collision_check: workspace::cli::CaseCollisionCheck::Auto,
+        output_dir: None,
+        wait: false,
+        no_lock: false,
+        on_error: workspace::cli::OnError::Continue,
+    }
+}
+
+#[test]
+fn test_plan_preview_execute_undo() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let socket_dir = TempDir::new()?;
+    let socket = socket_dir.path().join("refactor.sock");
+    let root = temp_dir.path();
+    fs::create_dir(root.join("oldname_dir"))?;
+    fs::write(root.join("oldname_dir/oldname.txt"), "first line\nuse oldname here\n")?;
+    fs::write(root.join("notes.txt"), "oldname\n")?;
+
+    let server = {
+        let socket = socket.clone();
+        thread::spawn(move || serve(&socket, template_args(&socket), Vec::new()))
+    };
+    let mut client = Client::connect(&socket)?;
+    let params = json!({"root": root, "pattern": "oldname", "substitute": "newname"});
+
+    let plan = client.call("plan", params.clone())?;
+    let result = &plan["result"];
+    assert_eq!(result["renames"].as_array().unwrap().len(), 2);
+    assert_eq!(result["content"].as_array().unwrap().len(), 2);
+    assert_eq!(result["collisions"], json!([]));
+    assert_eq!(result["cached"], json!(false));
+
+    // The second request reuses the discovery
+    let file = root.join("oldname_dir/oldname.txt");
+    let preview = client.call("preview", json!({"root": root, "pattern": "oldname", "substitute": "newname", "path": file}))?;
+    assert_eq!(preview["result"]["cached"], json!(true));
+    assert_eq!(
+        preview["result"]["files"],
+        json!([{"path": file, "lines": [{"line": 2, "before": "use oldname here", "after": "use newname here"}]}])
+    );
+
+    let execute = client.call("execute", params.clone())?;
+    assert_eq!(execute["result"], json!({"files_changed": 2, "renamed": 2}));
+    assert_eq!(fs::read_to_string(root.join("newname_dir/newname.txt"))?, "first line\nuse newname here\n");
+    assert_eq!(fs::read_to_string(root.join("notes.txt"))?, "newname\n");
+
+    let undo = client.call("undo", Value::Null)?;
+    assert_eq!(undo["result"], json!({"files_restored": 2, "renames_reverted": 2}));
+    assert!(!root.join("newname_dir").exists());
+    assert_eq!(fs::read_to_string(&file)?, "first line\nuse oldname here\n");
+    assert_eq!(fs::read_to_string(root.join("notes.txt"))?, "oldname\n");
+
+    // Errors come back as JSON-RPC errors
+    assert_eq!(client.call("undo", Value::Null)?["error"]["code"], json!(-32000));
+    assert_eq!(client.call("rename_everything", Value::Null)?["error"]["code"], json!(-32601));
+    assert_eq!(client.call("plan", json!({"root": root}))?["error"]["code"], json!(-32602));
+
+    client.call("shutdown", Value::Null)?;
+    server.join().unwrap()?;
+    assert!(!socket.exists());
+    Ok(())
+}
+
+#[test]
+fn test_serve_refuses_a_path_that_is_not_a_socket() -> Result<()> {
+    let socket_dir = TempDir::new()?;
+    let path = socket_dir.path().join("notes.txt");
+    fs::write(&path, "keep me")?;
+
+    let message = serve(&path, template_args(&path), Vec::new()).unwrap_err().to_string();
+    assert!(message.contains("is not a socket"), "{}", message);
+    assert_eq!(fs::read_to_string(&path)?, "keep me");
+    Ok(())
+}
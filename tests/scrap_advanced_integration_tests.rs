@@ -151,6 +151,43 @@ fn test_scrap_clean_dry_run() {
         .stdout(predicate::str::contains("Would remove 3 items older than 0 days"));
 }
 
+#[test]
+fn test_scrap_clean_retention_from_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    setup_scrap_with_items(temp_path);
+    fs::write(temp_path.join("ws.toml"), "[scrap]\nretention_days = 0\n").unwrap();
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["scrap", "clean", "--dry-run"])
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Would remove 3 items older than 0 days"));
+    
+    // The environment overrides ws.toml, and --days overrides both
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["scrap", "clean", "--dry-run"])
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .env("WS_SCRAP_RETENTION_DAYS", "5")
+        .current_dir(temp_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Would remove 0 items older than 5 days"));
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["scrap", "clean", "--dry-run", "--days", "9"])
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .env("WS_SCRAP_RETENTION_DAYS", "5")
+        .current_dir(temp_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("older than 9 days"));
+}
+
 #[test]
 fn test_scrap_clean_actual() {
     let temp_dir = TempDir::new().unwrap();
@@ -202,6 +239,44 @@ fn test_scrap_purge_with_force() {
     assert!(!temp_path.join(".scrap").join("testdir").exists());
 }
 
+#[test]
+fn test_scrap_purge_without_terminal() {
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    setup_scrap_with_items(temp_path);
+
+    // Nobody to ask: the question gets its default answer instead of hanging
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["scrap", "purge"])
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No terminal to ask, answering no"));
+    assert!(temp_path.join(".scrap").join("file1.txt").exists());
+
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["scrap", "purge", "--assume-no"])
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--assume-yes"));
+    assert!(temp_path.join(".scrap").join("file1.txt").exists());
+
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["--assume-yes", "scrap", "purge"])
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Purged 3 items from scrap folder"));
+    assert!(!temp_path.join(".scrap").join("file1.txt").exists());
+}
+
 #[test]
 fn test_scrap_purge_empty_folder() {
     let temp_dir = TempDir::new().unwrap();
@@ -557,4 +632,1468 @@ fn test_unscrap_custom_destination() {
     assert!(custom_file.exists());
     assert!(!test_file.exists());
     assert!(!temp_path.join(".scrap").join("test.txt").exists());
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_scrap_history_records_operations() {
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    
+    fs::write(temp_path.join("test.txt"), "test content").unwrap();
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .arg("scrap")
+        .arg("test.txt")
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success();
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .arg("unscrap")
+        .arg("test.txt")
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success();
+    
+    // One JSON line per operation
+    let history = fs::read_to_string(temp_path.join(".scrap").join(".history.jsonl")).unwrap();
+    assert_eq!(history.lines().count(), 2);
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .arg("scrap")
+        .arg("history")
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("scrap"))
+        .stdout(predicate::str::contains("unscrap"));
+    
+    // --limit keeps only the most recent operation
+    Command::cargo_bin("ws")
+        .unwrap()
+        .arg("scrap")
+        .arg("history")
+        .arg("--limit")
+        .arg("1")
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("unscrap"))
+        .stdout(predicate::str::is_match(r" scrap +1 item").unwrap().not())
+        .stdout(predicate::str::contains("item(s)").count(1));
+    
+    // A scrapped file named like a history log is left alone
+    fs::write(temp_path.join("history.jsonl"), "user data\n").unwrap();
+    for name in ["history.jsonl", "test.txt"] {
+        Command::cargo_bin("ws")
+            .unwrap()
+            .arg("scrap")
+            .arg(name)
+            .env("WS_COMPLETIONS_LOADED", "1")
+            .current_dir(temp_path)
+            .assert()
+            .success();
+    }
+    assert_eq!(fs::read_to_string(temp_path.join(".scrap/history.jsonl")).unwrap(), "user data\n");
+}
+
+#[test]
+fn test_scrap_deduplicates_identical_files() {
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    
+    fs::create_dir(temp_path.join("a")).unwrap();
+    fs::create_dir(temp_path.join("b")).unwrap();
+    fs::write(temp_path.join("a").join("same.txt"), "identical content").unwrap();
+    fs::write(temp_path.join("b").join("same.txt"), "identical content").unwrap();
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .arg("scrap")
+        .arg("a/same.txt")
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success();
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .arg("scrap")
+        .arg("b/same.txt")
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Identical to .scrap/same.txt"));
+    
+    // Both entries carry the same content hash
+    let metadata = fs::read_to_string(temp_path.join(".scrap").join(".metadata.json")).unwrap();
+    let parsed: serde_json::Value = serde_json::from_str(&metadata).unwrap();
+    assert_eq!(
+        parsed["entries"]["same.txt"]["content_hash"],
+        parsed["entries"]["same_1.txt"]["content_hash"]
+    );
+    
+    // Restoring one copy must not affect the other
+    Command::cargo_bin("ws")
+        .unwrap()
+        .arg("unscrap")
+        .arg("same_1.txt")
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success();
+    fs::write(temp_path.join("b").join("same.txt"), "edited").unwrap();
+    assert_eq!(
+        fs::read_to_string(temp_path.join(".scrap").join("same.txt")).unwrap(),
+        "identical content"
+    );
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .arg("scrap")
+        .arg("dedup")
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Deduplicated"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_scrapped_symlink_is_not_deduplicated() {
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    fs::write(temp_path.join("same.txt"), "identical content").unwrap();
+    fs::write(temp_path.join("target.txt"), "identical content").unwrap();
+    std::os::unix::fs::symlink("target.txt", temp_path.join("link.txt")).unwrap();
+
+    let ws = |args: &[&str]| {
+        Command::cargo_bin("ws")
+            .unwrap()
+            .args(args)
+            .env("WS_COMPLETIONS_LOADED", "1")
+            .current_dir(temp_path)
+            .assert()
+            .success()
+    };
+    ws(&["scrap", "same.txt"]);
+    ws(&["scrap", "link.txt"]).stdout(predicate::str::contains("Identical to").not());
+    ws(&["scrap", "dedup"]).stdout(predicate::str::contains("Deduplicated 0 items"));
+
+    // Still a link, hashed by where it points rather than by the file there
+    let link = temp_path.join(".scrap/link.txt");
+    assert!(link.is_symlink());
+    assert_eq!(fs::read_link(&link).unwrap(), Path::new("target.txt"));
+    let metadata: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(temp_path.join(".scrap/.metadata.json")).unwrap()).unwrap();
+    assert_ne!(metadata["entries"]["same.txt"]["content_hash"], metadata["entries"]["link.txt"]["content_hash"]);
+}
+
+#[test]
+fn test_unscrap_all_and_matching() {
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    setup_scrap_with_items(temp_path);
+    
+    // Occupy one destination so it is reported as a conflict
+    fs::write(temp_path.join("file2.log"), "new log").unwrap();
+    
+    // Dry run leaves everything in place
+    Command::cargo_bin("ws")
+        .unwrap()
+        .arg("unscrap")
+        .arg("--all")
+        .arg("--dry-run")
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Would restore 2 items, 1 conflicts skipped"));
+    assert!(temp_path.join(".scrap").join("file1.txt").exists());
+    
+    // Pattern restore only touches matching entries
+    Command::cargo_bin("ws")
+        .unwrap()
+        .arg("unscrap")
+        .arg("--matching")
+        .arg(r"\.txt$")
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Restored 1 items, 0 conflicts skipped, 0 failed"));
+    assert!(temp_path.join("file1.txt").exists());
+    assert!(temp_path.join(".scrap").join("testdir").exists());
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .arg("unscrap")
+        .arg("--all")
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Restored 1 items, 1 conflicts skipped, 0 failed"));
+    assert!(temp_path.join("testdir").join("nested.txt").exists());
+    assert_eq!(fs::read_to_string(temp_path.join("file2.log")).unwrap(), "new log");
+}
+
+#[test]
+fn test_unscrap_from_origin() {
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    let ws = |args: &[&str]| {
+        Command::cargo_bin("ws")
+            .unwrap()
+            .args(args)
+            .env("WS_COMPLETIONS_LOADED", "1")
+            .current_dir(temp_path)
+            .assert()
+            .success()
+    };
+    
+    // Two versions of notes.txt, the second stored as notes_1.txt
+    fs::write(temp_path.join("notes.txt"), "old").unwrap();
+    ws(&["scrap", "notes.txt"]);
+    fs::write(temp_path.join("notes.txt"), "new").unwrap();
+    ws(&["scrap", "notes.txt"]);
+    fs::create_dir(temp_path.join("logs")).unwrap();
+    fs::write(temp_path.join("logs").join("a.log"), "a").unwrap();
+    fs::write(temp_path.join("logs").join("b.log"), "b").unwrap();
+    ws(&["scrap", "logs/a.log", "logs/b.log"]);
+    
+    // Without a terminal the newest version is restored
+    ws(&["unscrap", "--from-origin", "./notes.txt"])
+        .stdout(predicate::str::contains("Restored notes_1.txt"));
+    assert_eq!(fs::read_to_string(temp_path.join("notes.txt")).unwrap(), "new");
+    assert!(temp_path.join(".scrap").join("notes.txt").exists());
+    
+    ws(&["unscrap", "--from-origin", "logs/*.log"]);
+    assert!(temp_path.join("logs").join("a.log").exists());
+    assert!(temp_path.join("logs").join("b.log").exists());
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["unscrap", "--from-origin", "missing.txt"])
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No item in scrap was originally at missing.txt"));
+}
+
+#[test]
+fn test_unscrap_rebase_moved_project() {
+    let temp_dir = TempDir::new().unwrap();
+    let old_root = temp_dir.path().join("old");
+    let new_root = temp_dir.path().join("new");
+    fs::create_dir_all(old_root.join("docs")).unwrap();
+    fs::write(old_root.join("docs/a.txt"), "a").unwrap();
+    fs::write(old_root.join("b.txt"), "b").unwrap();
+    let ws = |dir: &std::path::Path, args: &[&str]| {
+        Command::cargo_bin("ws")
+            .unwrap()
+            .args(args)
+            .env("WS_COMPLETIONS_LOADED", "1")
+            .current_dir(dir)
+            .assert()
+            .success()
+    };
+    
+    // Scrap by absolute path so the recorded original path points into old/
+    ws(&old_root, &["scrap", old_root.join("docs/a.txt").to_str().unwrap()]);
+    ws(&old_root, &["scrap", "b.txt"]);
+    fs::rename(&old_root, &new_root).unwrap();
+    
+    // The stored project root no longer matches, so paths follow the move
+    ws(&new_root, &["unscrap", "a.txt"])
+        .stdout(predicate::str::contains("Rebased"));
+    assert!(new_root.join("docs/a.txt").exists());
+    assert!(!old_root.exists());
+    
+    // An explicit --rebase covers paths recorded elsewhere
+    let metadata_path = new_root.join(".scrap/.metadata.json");
+    let mut metadata: serde_json::Value = serde_json::from_str(&fs::read_to_string(&metadata_path).unwrap()).unwrap();
+    metadata["entries"]["b.txt"]["original_path"] = serde_json::json!("/elsewhere/project/sub/b.txt");
+    fs::write(&metadata_path, serde_json::to_string(&metadata).unwrap()).unwrap();
+    ws(&new_root, &["unscrap", "b.txt", "--rebase", "/elsewhere/project", new_root.to_str().unwrap()]);
+    assert_eq!(fs::read_to_string(new_root.join("sub/b.txt")).unwrap(), "b");
+}
+
+#[test]
+fn test_scrap_versions() {
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    let ws = |args: &[&str]| {
+        Command::cargo_bin("ws")
+            .unwrap()
+            .args(args)
+            .env("WS_COMPLETIONS_LOADED", "1")
+            .current_dir(temp_path)
+            .assert()
+            .success()
+    };
+    
+    for content in ["one", "two", "three"] {
+        fs::write(temp_path.join("file.txt"), content).unwrap();
+        ws(&["scrap", "file.txt"]);
+    }
+    
+    ws(&["scrap", "versions", "file.txt"])
+        .stdout(predicate::str::contains("v1  file.txt"))
+        .stdout(predicate::str::contains("v2  file_1.txt"))
+        .stdout(predicate::str::contains("v3  file_2.txt"));
+    
+    // Any version's scrapped name works as the item name
+    ws(&["unscrap", "file_2.txt", "--version", "2"]);
+    assert_eq!(fs::read_to_string(temp_path.join("file.txt")).unwrap(), "two");
+    
+    // Numbers stay with their versions after one is restored
+    ws(&["scrap", "versions", "file.txt"])
+        .stdout(predicate::str::contains("v1  file.txt"))
+        .stdout(predicate::str::contains("v3  file_2.txt"))
+        .stdout(predicate::str::contains("file_1.txt").not());
+    
+    fs::remove_file(temp_path.join("file.txt")).unwrap();
+    ws(&["unscrap", "--from-origin", "file.txt", "--version", "1"]);
+    assert_eq!(fs::read_to_string(temp_path.join("file.txt")).unwrap(), "one");
+}
+
+#[test]
+fn test_scrap_compresses_large_items() {
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    let ws = |args: &[&str]| {
+        Command::cargo_bin("ws")
+            .unwrap()
+            .args(args)
+            .env("WS_COMPLETIONS_LOADED", "1")
+            .env("WS_SCRAP_COMPRESS_OVER_MB", "1")
+            .current_dir(temp_path)
+            .assert()
+            .success()
+    };
+    
+    let large = "build output\n".repeat(100_000);
+    fs::write(temp_path.join("build.log"), &large).unwrap();
+    fs::write(temp_path.join("small.txt"), "small").unwrap();
+    fs::create_dir_all(temp_path.join("target/debug")).unwrap();
+    fs::write(temp_path.join("target/debug/app"), &large).unwrap();
+    
+    ws(&["scrap", "build.log"]).stdout(predicate::str::contains(".scrap/build.log.zst"));
+    ws(&["scrap", "small.txt"]).stdout(predicate::str::contains(".scrap/small.txt"));
+    ws(&["scrap", "target"]).stdout(predicate::str::contains(".scrap/target.tar.zst"));
+    
+    let scrap_dir = temp_path.join(".scrap");
+    assert!(fs::metadata(scrap_dir.join("build.log.zst")).unwrap().len() < large.len() as u64 / 10);
+    assert!(scrap_dir.join("small.txt").is_file());
+    assert!(scrap_dir.join("target.tar.zst").is_file());
+    
+    // Restored by either name, back to the original content
+    ws(&["unscrap", "build.log"]);
+    assert_eq!(fs::read_to_string(temp_path.join("build.log")).unwrap(), large);
+    ws(&["unscrap", "target.tar.zst"]);
+    assert_eq!(fs::read_to_string(temp_path.join("target/debug/app")).unwrap(), large);
+    assert!(!scrap_dir.join("build.log.zst").exists());
+    assert!(!scrap_dir.join("target").exists());
+}
+
+#[test]
+fn test_scrap_tiers_old_items_into_archives() {
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    let ws = |args: &[&str]| {
+        Command::cargo_bin("ws")
+            .unwrap()
+            .args(args)
+            .env("WS_COMPLETIONS_LOADED", "1")
+            .current_dir(temp_path)
+            .assert()
+            .success()
+    };
+    
+    fs::write(temp_path.join("old.txt"), "old").unwrap();
+    fs::create_dir_all(temp_path.join("old_dir")).unwrap();
+    fs::write(temp_path.join("old_dir/inner.txt"), "inner").unwrap();
+    ws(&["scrap", "old.txt", "old_dir"]);
+    
+    // With everything past the age, the next scrap invocation moves it out of the live folder
+    fs::write(temp_path.join("ws.toml"), "[scrap]\ntier_after_days = 0\n").unwrap();
+    ws(&["scrap", "list"]).stderr(predicate::str::contains("Archived 2 items older than 0 days"));
+    let scrap_dir = temp_path.join(".scrap");
+    let tier = chrono::Utc::now().format("%Y-%m").to_string();
+    assert!(scrap_dir.join("archives").join(format!("{}.tar.zst", tier)).is_file());
+    assert!(!scrap_dir.join("old.txt").exists());
+    assert!(!scrap_dir.join("old_dir").exists());
+    
+    ws(&["scrap", "archive", "list"]).stdout(predicate::str::contains(format!("{}  2 items", tier)));
+    ws(&["scrap", "archive", "list", &tier])
+        .stdout(predicate::str::contains("old.txt (from"))
+        .stdout(predicate::str::contains("old_dir (from"));
+    
+    // Restored items are not tiered again, and unscrap puts them back
+    ws(&["scrap", "restore-archive", &format!(".scrap/archives/{}.tar.zst", tier)])
+        .stdout(predicate::str::contains("Restored 2 items"));
+    ws(&["scrap", "list"]).stderr(predicate::str::contains("Archived").not());
+    ws(&["unscrap", "old_dir"]);
+    assert_eq!(fs::read_to_string(temp_path.join("old_dir/inner.txt")).unwrap(), "inner");
+}
+
+#[test]
+fn test_unscrap_conflict_rename_and_backup() {
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    
+    fs::write(temp_path.join("test.txt"), "scrapped").unwrap();
+    Command::cargo_bin("ws")
+        .unwrap()
+        .arg("scrap")
+        .arg("test.txt")
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success();
+    fs::write(temp_path.join("test.txt"), "current").unwrap();
+    
+    // Default policy refuses to touch the existing file
+    Command::cargo_bin("ws")
+        .unwrap()
+        .arg("unscrap")
+        .arg("test.txt")
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .failure();
+    
+    // Rename restores alongside the existing file
+    Command::cargo_bin("ws")
+        .unwrap()
+        .arg("unscrap")
+        .arg("test.txt")
+        .arg("--on-conflict")
+        .arg("rename")
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(temp_path.join("test.txt")).unwrap(), "current");
+    assert_eq!(fs::read_to_string(temp_path.join("test.txt.restored-1")).unwrap(), "scrapped");
+    
+    // Backup moves the existing file into .scrap before restoring
+    Command::cargo_bin("ws")
+        .unwrap()
+        .arg("scrap")
+        .arg("test.txt.restored-1")
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success();
+    Command::cargo_bin("ws")
+        .unwrap()
+        .arg("unscrap")
+        .arg("test.txt.restored-1")
+        .arg("--to")
+        .arg("test.txt")
+        .arg("--on-conflict")
+        .arg("backup")
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Backed up existing test.txt to .scrap/test.txt"));
+    assert_eq!(fs::read_to_string(temp_path.join("test.txt")).unwrap(), "scrapped");
+    assert_eq!(fs::read_to_string(temp_path.join(".scrap").join("test.txt")).unwrap(), "current");
+}
+
+#[test]
+fn test_unscrap_and_purge_dry_run() {
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    setup_scrap_with_items(temp_path);
+    fs::write(temp_path.join("file1.txt"), "occupied").unwrap();
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .arg("unscrap")
+        .arg("file1.txt")
+        .arg("--dry-run")
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Conflict: file1.txt already exists"));
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .arg("unscrap")
+        .arg("file2.log")
+        .arg("--dry-run")
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Would restore file2.log to file2.log"));
+    assert!(!temp_path.join("file2.log").exists());
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .arg("scrap")
+        .arg("purge")
+        .arg("--dry-run")
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Would purge 3 items from scrap folder"));
+    assert!(temp_path.join(".scrap").join("file2.log").exists());
+}
+
+#[test]
+fn test_unscrap_merge_directory() {
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    
+    let project = temp_path.join("project");
+    fs::create_dir_all(project.join("sub")).unwrap();
+    fs::write(project.join("old.txt"), "old").unwrap();
+    fs::write(project.join("shared.txt"), "scrapped version").unwrap();
+    fs::write(project.join("sub").join("deep.txt"), "deep").unwrap();
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .arg("scrap")
+        .arg("project")
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success();
+    
+    // Directory recreated with new and overlapping content
+    fs::create_dir_all(project.join("sub")).unwrap();
+    fs::write(project.join("new.txt"), "new").unwrap();
+    fs::write(project.join("shared.txt"), "current version").unwrap();
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .arg("unscrap")
+        .arg("project")
+        .arg("--merge")
+        .arg("--on-conflict")
+        .arg("rename")
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2 moved, 1 conflicts resolved, 0 conflicts skipped"));
+    
+    assert_eq!(fs::read_to_string(project.join("old.txt")).unwrap(), "old");
+    assert_eq!(fs::read_to_string(project.join("new.txt")).unwrap(), "new");
+    assert_eq!(fs::read_to_string(project.join("sub").join("deep.txt")).unwrap(), "deep");
+    assert_eq!(fs::read_to_string(project.join("shared.txt")).unwrap(), "current version");
+    assert_eq!(fs::read_to_string(project.join("shared.txt.restored-1")).unwrap(), "scrapped version");
+    assert!(!temp_path.join(".scrap").join("project").exists());
+}
+
+#[test]
+fn test_unscrap_partial_merge_updates_checksum() {
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    
+    let project = temp_path.join("project");
+    fs::create_dir_all(&project).unwrap();
+    fs::write(project.join("old.txt"), "old").unwrap();
+    fs::write(project.join("shared.txt"), "scrapped version").unwrap();
+    let ws = |args: &[&str]| {
+        Command::cargo_bin("ws")
+            .unwrap()
+            .args(args)
+            .env("WS_COMPLETIONS_LOADED", "1")
+            .current_dir(temp_path)
+            .assert()
+    };
+    
+    ws(&["scrap", "project"]).success();
+    fs::create_dir_all(&project).unwrap();
+    fs::write(project.join("shared.txt"), "current version").unwrap();
+    ws(&["unscrap", "project", "--merge"])
+        .success()
+        .stdout(predicate::str::contains("1 moved, 0 conflicts resolved, 1 conflicts skipped"));
+    
+    // The skipped remainder is what the entry now describes
+    assert!(temp_path.join(".scrap/project/shared.txt").exists());
+    ws(&["scrap", "doctor"])
+        .success()
+        .stdout(predicate::str::contains("Scrap folder is healthy"));
+}
+
+#[test]
+fn test_scrap_doctor_reports_and_fixes_drift() {
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    setup_scrap_with_items(temp_path);
+    
+    let scrap_dir = temp_path.join(".scrap");
+    fs::remove_file(scrap_dir.join("file1.txt")).unwrap();
+    fs::write(scrap_dir.join("manual.txt"), "added by hand").unwrap();
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .arg("scrap")
+        .arg("doctor")
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Orphaned entry: file1.txt"))
+        .stdout(predicate::str::contains("Untracked item: manual.txt"))
+        .stdout(predicate::str::contains("Found 2 issues"));
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .arg("scrap")
+        .arg("doctor")
+        .arg("--fix")
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Repaired 2 issues"));
+    
+    // Corrupt metadata is backed up and regenerated from the folder
+    fs::write(scrap_dir.join(".metadata.json"), "{ not json").unwrap();
+    Command::cargo_bin("ws")
+        .unwrap()
+        .arg("scrap")
+        .arg("doctor")
+        .arg("--fix")
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Corrupt metadata"));
+    assert!(scrap_dir.join(".metadata.json.corrupt").exists());
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .arg("scrap")
+        .arg("doctor")
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Scrap folder is healthy (3 entries)"));
+}
+
+#[test]
+fn test_modified_scrap_items_are_reported() {
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    fs::write(temp_path.join("notes.txt"), "original").unwrap();
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .arg("scrap")
+        .arg("notes.txt")
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success();
+    
+    fs::write(temp_path.join(".scrap").join("notes.txt"), "tampered").unwrap();
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .arg("scrap")
+        .arg("doctor")
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Modified item: notes.txt"));
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .arg("scrap")
+        .arg("doctor")
+        .arg("--no-verify")
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Scrap folder is healthy (1 entries)"));
+    
+    // The item is still restored, with a warning
+    Command::cargo_bin("ws")
+        .unwrap()
+        .arg("unscrap")
+        .arg("notes.txt")
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("notes.txt was modified or corrupted"));
+    assert_eq!(fs::read_to_string(temp_path.join("notes.txt")).unwrap(), "tampered");
+}
+
+#[test]
+fn test_concurrent_scraps_keep_all_metadata_entries() {
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    fs::create_dir(temp_path.join(".scrap")).unwrap();
+    
+    let handles: Vec<_> = (0..8)
+        .map(|i| {
+            let dir = temp_path.to_path_buf();
+            let file_name = format!("parallel{}.txt", i);
+            fs::write(dir.join(&file_name), format!("content {}", i)).unwrap();
+            std::thread::spawn(move || {
+                Command::cargo_bin("ws")
+                    .unwrap()
+                    .arg("scrap")
+                    .arg(&file_name)
+                    .env("WS_COMPLETIONS_LOADED", "1")
+                    .current_dir(&dir)
+                    .assert()
+                    .success();
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    
+    let metadata_content = fs::read_to_string(temp_path.join(".scrap/.metadata.json")).unwrap();
+    let metadata: serde_json::Value = serde_json::from_str(&metadata_content).unwrap();
+    assert_eq!(metadata["entries"].as_object().unwrap().len(), 8);
+    // The lock file stays behind, free and never listed as an item
+    assert!(!metadata["entries"].as_object().unwrap().contains_key(".metadata.json.lock"));
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["scrap", "list"])
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(".metadata.json.lock").not());
+}
+
+#[test]
+fn test_scrap_archive_encrypt_and_restore() {
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    setup_scrap_with_items(temp_path);
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["scrap", "archive", "--encrypt", "--remove", "--output", "secret.tar.gz.enc"])
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .env("WS_SCRAP_PASSPHRASE", "correct horse")
+        .current_dir(temp_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Created archive: secret.tar.gz.enc"));
+    
+    // Archive is not a readable gzip stream
+    let archive = fs::read(temp_path.join("secret.tar.gz.enc")).unwrap();
+    assert!(archive.starts_with(b"WSSCRAP1"));
+    assert!(!temp_path.join(".scrap/file1.txt").exists());
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["scrap", "restore-archive", "secret.tar.gz.enc"])
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .env("WS_SCRAP_PASSPHRASE", "wrong")
+        .current_dir(temp_path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("wrong passphrase"));
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["scrap", "restore-archive", "secret.tar.gz.enc"])
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .env("WS_SCRAP_PASSPHRASE", "correct horse")
+        .current_dir(temp_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Restored 3 items"));
+    
+    assert_eq!(fs::read_to_string(temp_path.join(".scrap/file1.txt")).unwrap(), "content1");
+    let metadata = fs::read_to_string(temp_path.join(".scrap/.metadata.json")).unwrap();
+    assert!(metadata.contains("file1.txt"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_restore_archive_rejects_links_out_of_the_scrap_folder() {
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    fs::create_dir(temp_path.join("project")).unwrap();
+
+    let archive = fs::File::create(temp_path.join("evil.tar.gz")).unwrap();
+    let mut tar = tar::Builder::new(flate2::write::GzEncoder::new(archive, flate2::Compression::default()));
+    for (name, target) in [("scrap/escape", "../../outside"), ("scrap/absolute", "/tmp")] {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        tar.append_link(&mut header, name, target).unwrap();
+    }
+    let mut header = tar::Header::new_gnu();
+    header.set_size(7);
+    header.set_mode(0o644);
+    tar.append_data(&mut header, "scrap/escape/planted.txt", b"planted".as_slice()).unwrap();
+    let mut header = tar::Header::new_gnu();
+    header.set_size(2);
+    header.set_mode(0o644);
+    tar.append_data(&mut header, "scrap/kept.txt", b"ok".as_slice()).unwrap();
+    tar.into_inner().unwrap().finish().unwrap();
+
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["scrap", "restore-archive", "../evil.tar.gz"])
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path.join("project"))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Skipping escape: link escape points outside the scrap folder"))
+        .stdout(predicate::str::contains("Skipping absolute"))
+        .stdout(predicate::str::contains("Restored 1 items"));
+
+    let scrap = temp_path.join("project/.scrap");
+    assert_eq!(fs::read_to_string(scrap.join("kept.txt")).unwrap(), "ok");
+    assert!(!scrap.join("escape").exists() && !scrap.join("escape").is_symlink());
+    assert!(!scrap.join("absolute").is_symlink());
+    assert!(!temp_path.join("outside").exists());
+}
+
+#[test]
+fn test_scrap_refuses_protected_paths() {
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    fs::write(temp_path.join("Cargo.toml"), "[package]").unwrap();
+    fs::write(temp_path.join("secrets.env"), "TOKEN=1").unwrap();
+    fs::create_dir(temp_path.join(".scrap")).unwrap();
+    fs::write(temp_path.join(".scrap/.protected.json"), r#"["*.env"]"#).unwrap();
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["scrap", "Cargo.toml"])
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("protected pattern 'Cargo.toml'"));
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["scrap", "secrets.env"])
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--force-protected"));
+    assert!(temp_path.join("secrets.env").exists());
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["scrap", "Cargo.toml", "--force-protected"])
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success();
+    assert!(temp_path.join(".scrap/Cargo.toml").exists());
+    
+    // A scrapped file named like the old pattern file is an ordinary item
+    fs::write(temp_path.join("protected.json"), "{\"user\": true}").unwrap();
+    fs::write(temp_path.join("notes.txt"), "notes").unwrap();
+    for name in ["protected.json", "notes.txt"] {
+        Command::cargo_bin("ws")
+            .unwrap()
+            .args(["scrap", name])
+            .env("WS_COMPLETIONS_LOADED", "1")
+            .current_dir(temp_path)
+            .assert()
+            .success();
+    }
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["scrap", "list"])
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("protected.json"));
+}
+
+#[test]
+fn test_scrap_purge_and_archive_filters() {
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    fs::write(temp_path.join("notes.txt"), "small note").unwrap();
+    fs::write(temp_path.join("build.bin"), vec![0u8; 64 * 1024]).unwrap();
+    fs::write(temp_path.join("old.log"), "old log").unwrap();
+    for name in ["notes.txt", "build.bin", "old.log"] {
+        Command::cargo_bin("ws")
+            .unwrap()
+            .args(["scrap", name])
+            .env("WS_COMPLETIONS_LOADED", "1")
+            .current_dir(temp_path)
+            .assert()
+            .success();
+    }
+    
+    // Backdate old.log so it falls outside --older-than
+    let metadata_path = temp_path.join(".scrap/.metadata.json");
+    let mut metadata: serde_json::Value = serde_json::from_str(&fs::read_to_string(&metadata_path).unwrap()).unwrap();
+    metadata["entries"]["old.log"]["scrapped_at"] = serde_json::json!("2020-01-01T00:00:00Z");
+    fs::write(&metadata_path, serde_json::to_string(&metadata).unwrap()).unwrap();
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["scrap", "purge", "--dry-run", "--larger-than", "10K"])
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Would remove: build.bin"))
+        .stdout(predicate::str::contains("Would purge 1 items"));
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["scrap", "archive", "--matching", "\\.log$", "--older-than", "30", "--remove", "--output", "logs.tar.gz"])
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Removed archived items"));
+    assert!(temp_path.join("logs.tar.gz").exists());
+    assert!(!temp_path.join(".scrap/old.log").exists());
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["scrap", "purge", "--force", "--larger-than", "10K"])
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Purged 1 items"));
+    
+    // Only the small recent note is left, and its metadata entry survives
+    assert!(temp_path.join(".scrap/notes.txt").exists());
+    assert!(!temp_path.join(".scrap/build.bin").exists());
+    let metadata = fs::read_to_string(&metadata_path).unwrap();
+    assert!(metadata.contains("notes.txt"));
+    assert!(!metadata.contains("build.bin"));
+}
+
+#[test]
+fn test_scrap_list_filters() {
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    fs::write(temp_path.join("small.txt"), "small").unwrap();
+    fs::write(temp_path.join("large.bin"), vec![0u8; 64 * 1024]).unwrap();
+    fs::create_dir(temp_path.join("stale_dir")).unwrap();
+    fs::write(temp_path.join("stale_dir/file.txt"), "inside").unwrap();
+    for name in ["small.txt", "large.bin", "stale_dir"] {
+        Command::cargo_bin("ws")
+            .unwrap()
+            .args(["scrap", name])
+            .env("WS_COMPLETIONS_LOADED", "1")
+            .current_dir(temp_path)
+            .assert()
+            .success();
+    }
+    
+    let metadata_path = temp_path.join(".scrap/.metadata.json");
+    let mut metadata: serde_json::Value = serde_json::from_str(&fs::read_to_string(&metadata_path).unwrap()).unwrap();
+    metadata["entries"]["stale_dir"]["scrapped_at"] = serde_json::json!("2020-01-01T00:00:00Z");
+    fs::write(&metadata_path, serde_json::to_string(&metadata).unwrap()).unwrap();
+    
+    let list = |args: &[&str]| {
+        let output = Command::cargo_bin("ws")
+            .unwrap()
+            .args(["scrap", "list"])
+            .args(args)
+            .env("WS_COMPLETIONS_LOADED", "1")
+            .current_dir(temp_path)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+    
+    let out = list(&["--larger-than", "10K"]);
+    assert!(out.contains("large.bin"));
+    assert!(!out.contains("small.txt"));
+    
+    let out = list(&["--older-than", "7d"]);
+    assert!(out.contains("stale_dir"));
+    assert!(!out.contains("large.bin"));
+    
+    let out = list(&["--dirs-only"]);
+    assert!(out.contains("stale_dir"));
+    assert!(!out.contains("small.txt"));
+    
+    let out = list(&["--files-only"]);
+    assert!(out.contains("small.txt") && out.contains("large.bin"));
+    assert!(!out.contains("stale_dir"));
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["scrap", "list", "--files-only", "--dirs-only"])
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .failure();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_unscrap_preserves_mode_and_symlinks() {
+    use std::os::unix::fs::PermissionsExt;
+    
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    fs::write(temp_path.join("deploy.sh"), "#!/bin/sh\necho deploy\n").unwrap();
+    fs::set_permissions(temp_path.join("deploy.sh"), fs::Permissions::from_mode(0o750)).unwrap();
+    // Identical content, so it is deduplicated and restored through the copy path
+    fs::write(temp_path.join("deploy-copy.sh"), "#!/bin/sh\necho deploy\n").unwrap();
+    fs::set_permissions(temp_path.join("deploy-copy.sh"), fs::Permissions::from_mode(0o700)).unwrap();
+    std::os::unix::fs::symlink("deploy.sh", temp_path.join("current")).unwrap();
+    
+    for name in ["deploy.sh", "deploy-copy.sh", "current"] {
+        Command::cargo_bin("ws")
+            .unwrap()
+            .args(["scrap", name])
+            .env("WS_COMPLETIONS_LOADED", "1")
+            .current_dir(temp_path)
+            .assert()
+            .success();
+    }
+    
+    assert_eq!(fs::read_link(temp_path.join(".scrap/current")).unwrap(), Path::new("deploy.sh"));
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["unscrap", "--all"])
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success();
+    
+    let mode = |name: &str| fs::metadata(temp_path.join(name)).unwrap().permissions().mode() & 0o777;
+    assert_eq!(mode("deploy.sh"), 0o750);
+    assert_eq!(mode("deploy-copy.sh"), 0o700);
+    assert_eq!(fs::read_link(temp_path.join("current")).unwrap(), Path::new("deploy.sh"));
+}
+
+#[test]
+fn test_scrap_paths_from_stdin() {
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    for i in 0..5 {
+        fs::write(temp_path.join(format!("build{}.tmp", i)), "tmp").unwrap();
+    }
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["scrap", "--stdin"])
+        .write_stdin("build0.tmp\0build1.tmp\0build2.tmp\0")
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success();
+    
+    // Newline-delimited input; a missing path is reported without stopping the rest
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["scrap", "--stdin"])
+        .write_stdin("build3.tmp\nmissing.tmp\nbuild4.tmp\n")
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Path does not exist: missing.tmp"))
+        .stderr(predicate::str::contains("Failed to scrap 1 of 3 paths"));
+    
+    for i in 0..5 {
+        assert!(temp_path.join(format!(".scrap/build{}.tmp", i)).exists());
+    }
+    
+    // Each invocation is a single transaction in the history log
+    let history = fs::read_to_string(temp_path.join(".scrap/.history.jsonl")).unwrap();
+    assert_eq!(history.lines().count(), 2);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_scrap_non_utf8_path_from_stdin() {
+    use std::os::unix::ffi::OsStrExt;
+    
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    let name = std::ffi::OsStr::from_bytes(b"caf\xe9.txt");
+    fs::write(temp_path.join(name), "latin-1").unwrap();
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["scrap", "--stdin"])
+        .write_stdin(&b"caf\xe9.txt\0"[..])
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success();
+    
+    assert!(!temp_path.join(name).exists());
+}
+
+#[test]
+fn test_scrap_list_paging_and_columns() {
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    setup_scrap_with_items(temp_path);
+    
+    let output = Command::cargo_bin("ws")
+        .unwrap()
+        .args(["scrap", "list", "--sort", "name", "--reverse", "--limit", "2", "--columns", "name,size,origin"])
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout).to_string();
+    let lines: Vec<&str> = stdout.lines().collect();
+    
+    assert!(lines[0].starts_with("NAME"));
+    assert!(lines[0].contains("SIZE") && lines[0].contains("ORIGIN"));
+    assert!(lines[1].starts_with("testdir"));
+    assert!(lines[1].contains("14 B"));
+    assert!(lines[2].starts_with("file2.log"));
+    assert_eq!(lines[3], "Showing 1-2 of 3 items");
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["scrap", "list", "--sort", "name", "--offset", "2"])
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("testdir"))
+        .stdout(predicate::str::contains("file1.txt").not())
+        .stdout(predicate::str::contains("Showing 3-3 of 3 items"));
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["scrap", "list", "--columns", "name,tags"])
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .current_dir(temp_path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown column: tags"));
+}
+
+#[test]
+fn test_scrap_folder_library_api() {
+    use workspace::scrap::{ItemFilter, RestoreOptions};
+    use workspace::ScrapFolder;
+    
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    fs::write(temp_path.join("keep.txt"), "keep").unwrap();
+    fs::write(temp_path.join("drop.log"), "drop").unwrap();
+    
+    let folder = ScrapFolder::in_directory(temp_path);
+    assert!(!folder.exists());
+    
+    let entry = folder.scrap(&temp_path.join("keep.txt")).unwrap();
+    assert_eq!(entry.scrapped_name, "keep.txt");
+    folder.scrap(&temp_path.join("drop.log")).unwrap();
+    assert_eq!(folder.entries().unwrap().len(), 2);
+    assert_eq!(folder.find(".log").unwrap().len(), 1);
+    
+    let filter = ItemFilter {
+        matching: Some(regex::Regex::new(r"\.log$").unwrap()),
+        ..Default::default()
+    };
+    assert_eq!(folder.purge(&filter).unwrap(), vec!["drop.log".to_string()]);
+    
+    let restored = folder.restore("keep.txt", None, RestoreOptions::default()).unwrap();
+    assert_eq!(restored, temp_path.join("keep.txt"));
+    assert_eq!(fs::read_to_string(&restored).unwrap(), "keep");
+    assert!(folder.entries().unwrap().is_empty());
+    
+    // Archiving with remove drops exactly the archived items
+    fs::write(temp_path.join("old.log"), "old").unwrap();
+    fs::write(temp_path.join("notes.txt"), "notes").unwrap();
+    folder.scrap(&temp_path.join("old.log")).unwrap();
+    folder.scrap(&temp_path.join("notes.txt")).unwrap();
+    let archive = temp_path.join("logs.tar.gz");
+    let file = fs::File::create(&archive).unwrap();
+    let archived = folder.archive(file, |file| Ok(file.sync_all()?), &filter, &archive, true).unwrap();
+    assert_eq!(archived, vec!["old.log".to_string()]);
+    assert!(archive.exists());
+    let entries = folder.entries().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].scrapped_name, "notes.txt");
+    assert!(!temp_path.join(".scrap/old.log").exists());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_scrap_schedule_clean_print() {
+    let temp_dir = TempDir::new().unwrap();
+    
+    let mut cmd = Command::cargo_bin("ws").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .args(["scrap", "schedule-clean", "--days", "14", "--daily", "--print"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("OnCalendar=daily"))
+        .stdout(predicate::str::contains("scrap clean --days 14"))
+        .stdout(predicate::str::contains(format!("WorkingDirectory={}", temp_dir.path().display())));
+    
+    // --print never installs anything
+    assert!(!temp_dir.path().join(".scrap").exists());
+}
+
+#[test]
+fn test_scrap_export_import() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_a = temp_dir.path().join("a");
+    let project_b = temp_dir.path().join("b");
+    fs::create_dir_all(&project_a).unwrap();
+    fs::create_dir_all(&project_b).unwrap();
+    fs::write(project_a.join("notes.txt"), "notes").unwrap();
+    fs::write(project_b.join("notes.txt"), "other notes").unwrap();
+    let ws = |dir: &std::path::Path, args: &[&str]| {
+        Command::cargo_bin("ws")
+            .unwrap()
+            .args(args)
+            .env("WS_COMPLETIONS_LOADED", "1")
+            .current_dir(dir)
+            .assert()
+            .success()
+    };
+    
+    ws(&project_a, &["scrap", "notes.txt"]);
+    ws(&project_b, &["scrap", "notes.txt"]);
+    ws(&project_a, &["scrap", "export"])
+        .stdout(predicate::str::contains("Exported 1 entries"));
+    let manifest: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(project_a.join(".scrap/.manifest.json")).unwrap()).unwrap();
+    assert_eq!(manifest["entries"][0]["scrapped_name"], "notes.txt");
+    
+    // A dry run copies nothing
+    let source = project_a.join(".scrap");
+    ws(&project_b, &["scrap", "import", source.to_str().unwrap(), "--dry-run"])
+        .stdout(predicate::str::contains("Would import 1 entries"));
+    assert!(!project_b.join(".scrap/notes_1.txt").exists());
+    
+    // The clashing name gets a unique one and keeps its restore information
+    ws(&project_b, &["scrap", "import", source.to_str().unwrap()])
+        .stdout(predicate::str::contains("Imported 1 entries"));
+    assert_eq!(fs::read_to_string(project_b.join(".scrap/notes_1.txt")).unwrap(), "notes");
+    let metadata: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(project_b.join(".scrap/.metadata.json")).unwrap()).unwrap();
+    let original = metadata["entries"]["notes_1.txt"]["original_path"].as_str().unwrap();
+    assert!(original.ends_with("a/notes.txt"));
+    
+    // Importing again skips what is already there
+    ws(&project_b, &["scrap", "import", source.to_str().unwrap()])
+        .stdout(predicate::str::contains("Imported 0 entries"))
+        .stdout(predicate::str::contains("already in scrap"));
+}
+
+#[test]
+fn test_scrap_item_with_reserved_name() {
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    fs::write(temp_path.join("manifest.json"), "{\"user\": true}").unwrap();
+    let ws = |args: &[&str]| {
+        Command::cargo_bin("ws")
+            .unwrap()
+            .args(args)
+            .env("WS_COMPLETIONS_LOADED", "1")
+            .current_dir(temp_path)
+            .assert()
+            .success()
+    };
+
+    // The export manifest is a dot file, so it can't overwrite the item
+    ws(&["scrap", "manifest.json"]);
+    let stored = temp_path.join(".scrap/manifest.json");
+    assert_eq!(fs::read_to_string(&stored).unwrap(), "{\"user\": true}");
+    ws(&["scrap", "list"]).stdout(predicate::str::contains("manifest.json"));
+
+    ws(&["scrap", "export"]).stdout(predicate::str::contains("Exported 1 entries"));
+    assert_eq!(fs::read_to_string(&stored).unwrap(), "{\"user\": true}");
+    let manifest: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(temp_path.join(".scrap/.manifest.json")).unwrap()).unwrap();
+    assert_eq!(manifest["entries"][0]["scrapped_name"], "manifest.json");
+
+    // A file named like the manifest itself is stored under another name
+    fs::write(temp_path.join(".manifest.json"), "{}").unwrap();
+    ws(&["scrap", ".manifest.json"]);
+    assert_eq!(fs::read_to_string(temp_path.join(".scrap/.manifest_1.json")).unwrap(), "{}");
+
+    ws(&["unscrap", "manifest.json"]);
+    assert_eq!(fs::read_to_string(temp_path.join("manifest.json")).unwrap(), "{\"user\": true}");
+}
+
+#[test]
+fn test_scrap_purge_require_archived() {
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    fs::write(temp_path.join("saved.txt"), "saved").unwrap();
+    fs::write(temp_path.join("only-copy.txt"), "only copy").unwrap();
+    let ws = |args: &[&str]| {
+        Command::cargo_bin("ws")
+            .unwrap()
+            .args(args)
+            .env("WS_COMPLETIONS_LOADED", "1")
+            .current_dir(temp_path)
+            .assert()
+            .success()
+    };
+    
+    ws(&["scrap", "saved.txt", "only-copy.txt"]);
+    ws(&["scrap", "archive", "--output", "saved.tar.gz", "--matching", "saved"]);
+    let metadata: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(temp_path.join(".scrap/.metadata.json")).unwrap()).unwrap();
+    let archived_in = metadata["entries"]["saved.txt"]["archived_in"][0].as_str().unwrap();
+    assert!(archived_in.ends_with("saved.tar.gz"));
+    assert_eq!(metadata["entries"]["only-copy.txt"]["archived_in"], serde_json::json!([]));
+    
+    // Without the policy, purging warns about the item that exists nowhere else
+    ws(&["scrap", "purge", "--dry-run"])
+        .stderr(predicate::str::contains("1 items have never been archived: only-copy.txt"))
+        .stdout(predicate::str::contains("Would purge 2 items"));
+    
+    ws(&["scrap", "purge", "--force", "--require-archived"])
+        .stdout(predicate::str::contains("Keeping (never archived): only-copy.txt"))
+        .stdout(predicate::str::contains("Purged 1 items"));
+    assert!(!temp_path.join(".scrap/saved.txt").exists());
+    assert!(temp_path.join(".scrap/only-copy.txt").exists());
+    let metadata: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(temp_path.join(".scrap/.metadata.json")).unwrap()).unwrap();
+    assert!(metadata["entries"]["only-copy.txt"].is_object());
+    
+    ws(&["scrap", "clean", "--days", "0", "--require-archived"])
+        .stdout(predicate::str::contains("Removed 0 items"));
+    assert!(temp_path.join(".scrap/only-copy.txt").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_scrap_hooks_receive_item_details() {
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    fs::write(temp_path.join("ws.toml"), r#"
+[scrap]
+on_scrap = "cat > hook-scrap.json"
+on_purge = "echo \"$WS_SCRAP_EVENT $WS_SCRAP_COUNT\" > hook-purge.txt"
+on_clean = "exit 3"
+"#).unwrap();
+    fs::write(temp_path.join("notes.txt"), "notes").unwrap();
+    let ws = |args: &[&str]| {
+        Command::cargo_bin("ws")
+            .unwrap()
+            .args(args)
+            .env("WS_COMPLETIONS_LOADED", "1")
+            .current_dir(temp_path)
+            .assert()
+            .success()
+    };
+    
+    ws(&["scrap", "notes.txt"]);
+    let record: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(temp_path.join("hook-scrap.json")).unwrap()).unwrap();
+    assert_eq!(record["operation"], "scrap");
+    assert_eq!(record["items"][0]["scrapped_name"], "notes.txt");
+    
+    // A failing hook is reported without failing the operation
+    ws(&["scrap", "clean", "--days", "0"])
+        .stderr(predicate::str::contains("Scrap hook `exit 3` exited with"));
+    
+    fs::write(temp_path.join("more.txt"), "more").unwrap();
+    ws(&["scrap", "more.txt"]);
+    ws(&["scrap", "purge", "--force"]);
+    assert_eq!(fs::read_to_string(temp_path.join("hook-purge.txt")).unwrap().trim(), "purge 1");
+}
+
+#[test]
+fn test_scrap_dir_override_and_global() {
+    let temp_dir = TempDir::new().unwrap();
+    let temp_path = temp_dir.path();
+    let data_home = TempDir::new().unwrap();
+    fs::write(temp_path.join("a.txt"), "a").unwrap();
+    fs::write(temp_path.join("b.txt"), "b").unwrap();
+    let ws = |args: &[&str], scrap_dir: Option<&str>| {
+        let mut command = Command::cargo_bin("ws").unwrap();
+        command
+            .args(args)
+            .env("WS_COMPLETIONS_LOADED", "1")
+            .env("XDG_DATA_HOME", data_home.path())
+            .env_remove("SCRAP_DIR")
+            .current_dir(temp_path);
+        if let Some(dir) = scrap_dir {
+            command.env("SCRAP_DIR", dir);
+        }
+        command.assert().success()
+    };
+    
+    ws(&["scrap", "a.txt"], Some("elsewhere"));
+    assert!(temp_path.join("elsewhere/a.txt").exists());
+    assert!(!temp_path.join(".scrap").exists());
+    // Only .scrap folders are ignored
+    assert!(!temp_path.join(".gitignore").exists());
+    
+    ws(&["scrap", "--global", "b.txt"], None);
+    let global_dir = data_home.path().join("ws/scrap");
+    assert!(global_dir.join("b.txt").exists());
+    ws(&["scrap", "list", "--global"], None)
+        .stdout(predicate::str::contains("b.txt"));
+    
+    ws(&["unscrap", "--global", "b.txt"], None);
+    ws(&["unscrap", "a.txt"], Some("elsewhere"));
+    assert_eq!(fs::read_to_string(temp_path.join("a.txt")).unwrap(), "a");
+    assert_eq!(fs::read_to_string(temp_path.join("b.txt")).unwrap(), "b");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_scrap_explains_uncreatable_folder() {
+    use std::os::unix::fs::PermissionsExt;
+    
+    let temp_dir = TempDir::new().unwrap();
+    let locked = temp_dir.path().join("locked");
+    fs::create_dir(&locked).unwrap();
+    fs::write(locked.join("notes.txt"), "notes").unwrap();
+    fs::set_permissions(&locked, fs::Permissions::from_mode(0o555)).unwrap();
+    // Permissions don't stop root
+    if fs::create_dir(locked.join("probe")).is_ok() {
+        return;
+    }
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["scrap", "notes.txt"])
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .env_remove("SCRAP_DIR")
+        .current_dir(&locked)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Cannot create scrap folder"))
+        .stderr(predicate::str::contains("ws scrap --global"))
+        .stderr(predicate::str::contains("SCRAP_DIR="));
+    fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+}
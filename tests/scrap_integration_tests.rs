@@ -471,4 +471,72 @@ fn test_scrap_handles_empty_gitignore() {
     // Check .gitignore was updated correctly
     let contents = fs::read_to_string(&gitignore_path).unwrap();
     assert_eq!(contents, ".scrap/\n");
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_scrap_gitignore_in_nested_repository_directory() {
+    let temp_dir = TempDir::new().unwrap();
+    let repo = temp_dir.path().join("repo");
+    let nested = repo.join("crates/tool");
+    fs::create_dir_all(&nested).unwrap();
+    let home = temp_dir.path().join("home");
+    fs::create_dir(&home).unwrap();
+    
+    let git_init = std::process::Command::new("git")
+        .args(["init", "-q"])
+        .current_dir(&repo)
+        .status();
+    if !matches!(git_init, Ok(status) if status.success()) {
+        // git is not available
+        return;
+    }
+    
+    // Default (auto): the repository root .gitignore, no stray file in the nested directory
+    Command::cargo_bin("ws")
+        .unwrap()
+        .arg("scrap")
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .env("HOME", &home)
+        .env_remove("XDG_CONFIG_HOME")
+        .current_dir(&nested)
+        .assert()
+        .success();
+    assert!(fs::read_to_string(repo.join(".gitignore")).unwrap().contains(".scrap/"));
+    assert!(!nested.join(".gitignore").exists());
+    
+    // Configured to use .git/info/exclude instead
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["scrap", "config", "gitignore", "exclude"])
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .env("HOME", &home)
+        .env_remove("XDG_CONFIG_HOME")
+        .current_dir(&nested)
+        .assert()
+        .success();
+    
+    let other = repo.join("docs");
+    fs::create_dir(&other).unwrap();
+    Command::cargo_bin("ws")
+        .unwrap()
+        .arg("scrap")
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .env("HOME", &home)
+        .env_remove("XDG_CONFIG_HOME")
+        .current_dir(&other)
+        .assert()
+        .success();
+    assert!(fs::read_to_string(repo.join(".git/info/exclude")).unwrap().contains(".scrap/"));
+    assert!(!other.join(".gitignore").exists());
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["scrap", "config", "gitignore"])
+        .env("WS_COMPLETIONS_LOADED", "1")
+        .env("HOME", &home)
+        .env_remove("XDG_CONFIG_HOME")
+        .current_dir(&other)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("exclude"));
+}
@@ -326,6 +326,11 @@ fn test_st8_uninstall_hook_with_other_content() {
         .assert()
         .success();
     
+    // The block goes under the hook's own shebang
+    let installed = fs::read_to_string(&hook_file).unwrap();
+    assert!(installed.starts_with("#!/bin/bash\n"));
+    assert_eq!(installed.matches("#!").count(), 1);
+    
     // Uninstall st8 hook
     Command::cargo_bin("ws")
         .unwrap()
@@ -358,6 +363,48 @@ fn test_st8_uninstall_no_hook() {
         .stdout(predicate::str::contains("No pre-commit hook found"));
 }
 
+#[test]
+fn test_st8_uninstall_purge() {
+    let temp_dir = TempDir::new().unwrap();
+    setup_git_repo(temp_dir.path()).unwrap();
+    let ws = |args: &[&str]| {
+        Command::cargo_bin("ws")
+            .unwrap()
+            .args(args)
+            .current_dir(temp_dir.path())
+            .assert()
+            .success()
+    };
+    
+    ws(&["git", "install"]);
+    fs::create_dir_all(temp_dir.path().join(".ws")).unwrap();
+    fs::write(temp_dir.path().join(".ws/version.toml"), "tag_prefix = \"v\"\n").unwrap();
+    fs::write(temp_dir.path().join(".git/ws-version-cache"), "{}").unwrap();
+    fs::write(temp_dir.path().join("version.txt"), "1.0.0").unwrap();
+    
+    // The version file stays unless asked for
+    ws(&["git", "uninstall", "--purge"])
+        .stdout(predicate::str::contains("Removed .git/hooks/pre-commit"))
+        .stdout(predicate::str::contains("Removed .ws/version.toml"))
+        .stdout(predicate::str::contains("Purged"));
+    assert!(!temp_dir.path().join(".git/hooks/pre-commit").exists());
+    assert!(!temp_dir.path().join(".ws/version.toml").exists());
+    assert!(!temp_dir.path().join(".git/ws-version-cache").exists());
+    assert!(temp_dir.path().join("version.txt").exists());
+    
+    ws(&["git", "uninstall", "--purge", "--remove-version-file"])
+        .stdout(predicate::str::contains("Removed version.txt"));
+    assert!(!temp_dir.path().join("version.txt").exists());
+    
+    // --remove-version-file alone is refused
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["git", "uninstall", "--remove-version-file"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .failure();
+}
+
 #[test]
 fn test_st8_default_behavior_install() {
     let temp_dir = TempDir::new().unwrap();
@@ -946,4 +993,1388 @@ fn test_st8_install_hook_includes_git_add() {
     let hook_content = fs::read_to_string(&hook_file).unwrap();
     assert!(hook_content.contains("update --git-add"), 
            "Hook should include --git-add flag, got: {}", hook_content);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_version_conventional_strategy() {
+    let temp_dir = TempDir::new().unwrap();
+    setup_git_repo(temp_dir.path()).unwrap();
+    fs::write(temp_dir.path().join("Cargo.toml"), "[package]\nname = \"demo\"\nversion = \"1.2.0\"\n").unwrap();
+    fs::create_dir_all(temp_dir.path().join(".ws")).unwrap();
+    
+    std::process::Command::new("git")
+        .args(["tag", "v1.2.0"])
+        .current_dir(temp_dir.path())
+        .output()
+        .unwrap();
+    for message in ["fix: handle empty input", "feat(cli): add --json", "docs: explain flags"] {
+        std::process::Command::new("git")
+            .args(["commit", "--allow-empty", "-m", message])
+            .current_dir(temp_dir.path())
+            .output()
+            .unwrap();
+    }
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "strategy", "conventional"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "show"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1.3.0"))
+        .stdout(predicate::str::contains("conventional"))
+        .stdout(predicate::str::contains("minor bump from 3 commits since v1.2.0"));
+    
+    // Tagging again without a feat/fix/breaking commit is refused
+    std::process::Command::new("git")
+        .args(["tag", "v1.3.0"])
+        .current_dir(temp_dir.path())
+        .output()
+        .unwrap();
+    std::process::Command::new("git")
+        .args(["commit", "--allow-empty", "-m", "docs: typo"])
+        .current_dir(temp_dir.path())
+        .output()
+        .unwrap();
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "tag", "--allow-dirty"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Nothing to release: no feat/fix/breaking commits among 1 since v1.3.0"));
+}
+
+#[test]
+fn test_version_bump_commits_and_tags() {
+    let temp_dir = TempDir::new().unwrap();
+    setup_git_repo(temp_dir.path()).unwrap();
+    fs::write(temp_dir.path().join("Cargo.toml"), "[package]\nname = \"demo\"\nversion = \"1.2.3\"\n").unwrap();
+    fs::write(temp_dir.path().join("version.txt"), "1.2.3\n").unwrap();
+    fs::create_dir_all(temp_dir.path().join(".ws")).unwrap();
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "bump", "minor", "--dry-run"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1.3.0"));
+    assert_eq!(fs::read_to_string(temp_dir.path().join("version.txt")).unwrap().trim(), "1.2.3");
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "bump", "minor", "--commit", "--tag"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    
+    assert_eq!(fs::read_to_string(temp_dir.path().join("version.txt")).unwrap().trim(), "1.3.0");
+    assert!(fs::read_to_string(temp_dir.path().join("Cargo.toml")).unwrap().contains("version = \"1.3.0\""));
+    
+    let tags = std::process::Command::new("git")
+        .args(["tag", "--list"])
+        .current_dir(temp_dir.path())
+        .output()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&tags.stdout).contains("v1.3.0"));
+    
+    let log = std::process::Command::new("git")
+        .args(["log", "-1", "--format=%s"])
+        .current_dir(temp_dir.path())
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&log.stdout).trim(), "chore(release): 1.3.0");
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "bump", "pre", "rc"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(temp_dir.path().join("version.txt")).unwrap().trim(), "1.3.1-rc.1");
+}
+
+#[test]
+fn test_version_bump_writes_changelog() {
+    let temp_dir = TempDir::new().unwrap();
+    setup_git_repo(temp_dir.path()).unwrap();
+    fs::write(temp_dir.path().join("Cargo.toml"), "[package]\nname = \"demo\"\nversion = \"1.2.0\"\n").unwrap();
+    fs::create_dir_all(temp_dir.path().join(".ws")).unwrap();
+    
+    std::process::Command::new("git")
+        .args(["tag", "v1.2.0"])
+        .current_dir(temp_dir.path())
+        .output()
+        .unwrap();
+    for message in ["feat(cli): add --json", "fix: handle empty input", "ci: cache builds"] {
+        std::process::Command::new("git")
+            .args(["commit", "--allow-empty", "-m", message])
+            .current_dir(temp_dir.path())
+            .output()
+            .unwrap();
+    }
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "changelog", "config", "enabled", "true"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "changelog", "--dry-run"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("## [Unreleased]"))
+        .stdout(predicate::str::contains("**cli:** add --json"));
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "bump", "minor", "--commit"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    
+    let changelog = fs::read_to_string(temp_dir.path().join("CHANGELOG.md")).unwrap();
+    assert!(changelog.starts_with("# Changelog"));
+    assert!(changelog.contains("## [1.3.0] - "));
+    assert!(changelog.contains("### Added"));
+    assert!(changelog.contains("### Fixed"));
+    assert!(!changelog.contains("cache builds"));
+    
+    let committed = std::process::Command::new("git")
+        .args(["show", "--name-only", "--format=", "HEAD"])
+        .current_dir(temp_dir.path())
+        .output()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&committed.stdout).contains("CHANGELOG.md"));
+}
+
+#[test]
+fn test_version_tag_guards() {
+    let temp_dir = TempDir::new().unwrap();
+    setup_git_repo(temp_dir.path()).unwrap();
+    fs::write(temp_dir.path().join("Cargo.toml"), "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n").unwrap();
+    fs::create_dir_all(temp_dir.path().join(".ws")).unwrap();
+    
+    // Uncommitted change to a tracked file
+    fs::write(temp_dir.path().join("README.md"), "# Changed").unwrap();
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "tag"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("uncommitted changes"));
+    
+    std::process::Command::new("git")
+        .args(["checkout", "--", "README.md"])
+        .current_dir(temp_dir.path())
+        .output()
+        .unwrap();
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "tag"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Created git tag: v0."));
+    
+    let tag_type = std::process::Command::new("git")
+        .args(["for-each-ref", "--format=%(objecttype)", "refs/tags"])
+        .current_dir(temp_dir.path())
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&tag_type.stdout).trim(), "tag");
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "tag"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already exists"));
+}
+
+#[test]
+fn test_git_install_respects_hooks_path() {
+    let temp_dir = TempDir::new().unwrap();
+    setup_git_repo(temp_dir.path()).unwrap();
+    std::process::Command::new("git")
+        .args(["config", "core.hooksPath", ".husky"])
+        .current_dir(temp_dir.path())
+        .output()
+        .unwrap();
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["git", "install", "--hook", "post-commit"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Git hook installed successfully"));
+    
+    let hook = fs::read_to_string(temp_dir.path().join(".husky/post-commit")).unwrap();
+    assert!(hook.contains("WS_HOOK_AMENDING"));
+    assert!(!temp_dir.path().join(".git/hooks/pre-commit").exists());
+    
+    // Switching hooks moves the block
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["git", "install", "--hook", "pre-push"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    assert!(temp_dir.path().join(".husky/pre-push").exists());
+    assert!(!temp_dir.path().join(".husky/post-commit").exists());
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["git", "uninstall"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    assert!(!temp_dir.path().join(".husky/pre-push").exists());
+}
+
+#[test]
+fn test_version_packages() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    setup_git_repo(dir).unwrap();
+    fs::write(dir.join("Cargo.toml"), "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n").unwrap();
+    fs::create_dir_all(dir.join(".ws")).unwrap();
+    fs::create_dir_all(dir.join("packages/a")).unwrap();
+    fs::create_dir_all(dir.join("packages/b")).unwrap();
+    fs::write(dir.join("packages/a/lib.txt"), "a").unwrap();
+    fs::write(dir.join("packages/b/lib.txt"), "b").unwrap();
+    let git = |args: &[&str]| {
+        std::process::Command::new("git").args(args).current_dir(dir).output().unwrap();
+    };
+    git(&["add", "packages"]);
+    git(&["commit", "-m", "chore: add packages"]);
+    git(&["tag", "pkg-a/v1.0.0"]);
+    git(&["tag", "pkg-b/v2.0.0"]);
+    fs::write(dir.join("packages/a/lib.txt"), "a2").unwrap();
+    git(&["commit", "-am", "fix: a"]);
+    
+    for (name, path) in [("pkg-a", "packages/a"), ("pkg-b", "packages/b")] {
+        Command::cargo_bin("ws")
+            .unwrap()
+            .args(["version", "packages", "add", name, path])
+            .current_dir(dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(format!("{}/v", name)));
+    }
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "packages"])
+        .current_dir(dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1.0.0 → 1.0.1"))
+        .stdout(predicate::str::contains("pkg-b (packages/b): 2.0.0"));
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["update", "--no-git"])
+        .current_dir(dir)
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(dir.join("packages/a/version.txt")).unwrap().trim(), "1.0.1");
+    assert!(!dir.join("packages/b/version.txt").exists());
+}
+
+#[test]
+fn test_version_format_template() {
+    let temp_dir = TempDir::new().unwrap();
+    setup_git_repo(temp_dir.path()).unwrap();
+    fs::write(temp_dir.path().join("Cargo.toml"), "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n").unwrap();
+    fs::create_dir_all(temp_dir.path().join(".ws")).unwrap();
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "format", "{major}.{build}"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown placeholder {build}"));
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "format", "{yyyy}.{mm}.{patch}"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    
+    let calver_prefix = chrono::Local::now().format("%Y.%m.").to_string();
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "show"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(calver_prefix));
+}
+
+#[test]
+fn test_prerelease_never_moves_backwards() {
+    let temp_dir = TempDir::new().unwrap();
+    setup_git_repo(temp_dir.path()).unwrap();
+    fs::write(temp_dir.path().join("Cargo.toml"), "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n").unwrap();
+    fs::create_dir_all(temp_dir.path().join(".ws")).unwrap();
+    
+    for args in [["version", "strategy", "conventional"], ["version", "prerelease", "rc"]] {
+        Command::cargo_bin("ws")
+            .unwrap()
+            .args(args)
+            .current_dir(temp_dir.path())
+            .assert()
+            .success();
+    }
+    
+    // The prerelease number on disk is kept rather than restarting at rc.1
+    fs::write(temp_dir.path().join("version.txt"), "0.0.0-rc.3\n").unwrap();
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["update", "--no-git"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(temp_dir.path().join("version.txt")).unwrap().trim(), "0.0.0-rc.3");
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "bump", "pre"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("0.0.0-rc.4"));
+}
+
+#[test]
+fn test_update_downgrade_policy() {
+    let temp_dir = TempDir::new().unwrap();
+    setup_git_repo(temp_dir.path()).unwrap();
+    fs::write(temp_dir.path().join("Cargo.toml"), "[package]\nname = \"demo\"\nversion = \"9.0.0\"\n").unwrap();
+    fs::write(temp_dir.path().join("version.txt"), "9.0.0\n").unwrap();
+    fs::create_dir_all(temp_dir.path().join(".ws")).unwrap();
+
+    // Denied by default: both files keep the newer version
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["update", "--no-git"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Keeping 9.0.0"))
+        .stdout(predicate::str::contains("Cargo.toml holds 9.0.0"));
+    assert_eq!(fs::read_to_string(temp_dir.path().join("version.txt")).unwrap().trim(), "9.0.0");
+    assert!(fs::read_to_string(temp_dir.path().join("Cargo.toml")).unwrap().contains("version = \"9.0.0\""));
+
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "config", "set", "downgrade_policy", "sideways"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown downgrade policy"));
+
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "config", "set", "downgrade_policy", "warn"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["update", "--no-git"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("back from 9.0.0"));
+    assert_ne!(fs::read_to_string(temp_dir.path().join("version.txt")).unwrap().trim(), "9.0.0");
+}
+
+#[test]
+fn test_git_status_json() {
+    let temp_dir = TempDir::new().unwrap();
+    setup_git_repo(temp_dir.path()).unwrap();
+    fs::write(temp_dir.path().join("Cargo.toml"), "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n").unwrap();
+    fs::create_dir_all(temp_dir.path().join(".ws")).unwrap();
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["git", "install"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    
+    let output = Command::cargo_bin("ws")
+        .unwrap()
+        .args(["git", "status", "--format", "json"])
+        .current_dir(temp_dir.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(report["version"].is_string());
+    assert!(report["components"]["minor"].is_u64());
+    assert_eq!(report["config"]["version_file"], "version.txt");
+    assert_eq!(report["hooks"]["installed"][0], "pre-commit");
+    assert!(report["project_files"].as_array().unwrap().iter().any(|f| f["type"] == "Cargo.toml"));
+}
+
+#[test]
+fn test_version_ci_github_outputs() {
+    let temp_dir = TempDir::new().unwrap();
+    setup_git_repo(temp_dir.path()).unwrap();
+    fs::write(temp_dir.path().join("Cargo.toml"), "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n").unwrap();
+    fs::create_dir_all(temp_dir.path().join(".ws")).unwrap();
+    let github_output = temp_dir.path().join("github_output");
+    let github_env = temp_dir.path().join("github_env");
+    fs::write(&github_output, "earlier=step\n").unwrap();
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "ci"])
+        .env("GITHUB_ACTIONS", "true")
+        .env("GITHUB_OUTPUT", &github_output)
+        .env("GITHUB_ENV", &github_env)
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("VERSION=0."));
+    
+    let outputs = fs::read_to_string(&github_output).unwrap();
+    assert!(outputs.starts_with("earlier=step\nversion=0."));
+    assert!(outputs.contains("version_major=0\n"));
+    assert!(fs::read_to_string(&github_env).unwrap().starts_with("VERSION=0."));
+}
+
+#[test]
+fn test_version_embed() {
+    let temp_dir = TempDir::new().unwrap();
+    setup_git_repo(temp_dir.path()).unwrap();
+    fs::write(temp_dir.path().join("Cargo.toml"), "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n").unwrap();
+    fs::create_dir_all(temp_dir.path().join(".ws")).unwrap();
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "embed", "include/version.h"])
+        .env("SOURCE_DATE_EPOCH", "1714564800")
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Wrote include/version.h"));
+    let header = fs::read_to_string(temp_dir.path().join("include/version.h")).unwrap();
+    assert!(header.contains("#define VERSION \"0."));
+    assert!(header.contains("#define VERSION_BUILD_DATE \"2024-05-01T12:00:00Z\""));
+    
+    fs::write(temp_dir.path().join("version.go.tera"), "package version\nconst Version = \"{{ version }}\"\n").unwrap();
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "embed", "version.go", "--template", "version.go.tera"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    assert!(fs::read_to_string(temp_dir.path().join("version.go")).unwrap().contains("const Version = \"0."));
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "embed", "version.go"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("pass --language or --template"));
+}
+
+#[test]
+fn test_update_dry_run_writes_nothing() {
+    let temp_dir = TempDir::new().unwrap();
+    setup_git_repo(temp_dir.path()).unwrap();
+    let cargo_toml = "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n";
+    fs::write(temp_dir.path().join("Cargo.toml"), cargo_toml).unwrap();
+    fs::create_dir_all(temp_dir.path().join(".ws")).unwrap();
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["update", "--dry-run"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Current version: (none)"))
+        .stdout(predicate::str::contains("Next version:"))
+        .stdout(predicate::str::contains("version.txt"))
+        .stdout(predicate::str::contains("-version = \"0.1.0\""));
+    
+    assert!(!temp_dir.path().join("version.txt").exists());
+    assert_eq!(fs::read_to_string(temp_dir.path().join("Cargo.toml")).unwrap(), cargo_toml);
+}
+
+#[test]
+fn test_version_config_and_disable() {
+    let temp_dir = TempDir::new().unwrap();
+    setup_git_repo(temp_dir.path()).unwrap();
+    fs::write(temp_dir.path().join("Cargo.toml"), "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n").unwrap();
+    fs::create_dir_all(temp_dir.path().join(".ws")).unwrap();
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "config", "set", "version_file", "VERSION"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "config", "get", "version_file"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout("VERSION\n");
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "config", "set", "colour", "blue"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown config key: colour"));
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "disable"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["update", "--no-git"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Version updates are disabled"));
+    assert!(!temp_dir.path().join("VERSION").exists());
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "enable"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["update", "--no-git"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    assert!(temp_dir.path().join("VERSION").exists());
+}
+
+#[test]
+fn test_version_init_non_interactive() {
+    let temp_dir = TempDir::new().unwrap();
+    setup_git_repo(temp_dir.path()).unwrap();
+    fs::write(temp_dir.path().join("Cargo.toml"), "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n").unwrap();
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "init", "--yes", "--strategy", "conventional", "--hook", "pre-commit", "--initial-tag"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Cargo.toml"))
+        .stdout(predicate::str::contains("Created git tag: v0.1.0"));
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "config", "get", "strategy"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout("conventional\n");
+    assert!(temp_dir.path().join(".git/hooks/pre-commit").exists());
+    
+    let tags = std::process::Command::new("git")
+        .args(["tag", "--list"])
+        .current_dir(temp_dir.path())
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&tags.stdout).trim(), "v0.1.0");
+}
+
+#[test]
+fn test_version_history_and_rollback() {
+    let temp_dir = TempDir::new().unwrap();
+    setup_git_repo(temp_dir.path()).unwrap();
+    fs::write(temp_dir.path().join("Cargo.toml"), "[package]\nname = \"demo\"\nversion = \"1.0.0\"\n").unwrap();
+    fs::write(temp_dir.path().join("version.txt"), "1.0.0\n").unwrap();
+    fs::create_dir_all(temp_dir.path().join(".ws")).unwrap();
+    
+    for level in ["minor", "patch"] {
+        Command::cargo_bin("ws")
+            .unwrap()
+            .args(["version", "bump", level])
+            .current_dir(temp_dir.path())
+            .assert()
+            .success();
+    }
+    assert_eq!(fs::read_to_string(temp_dir.path().join("version.txt")).unwrap(), "1.1.1\n");
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "history", "--format", "json"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"new_version\": \"1.1.1\""))
+        .stdout(predicate::str::contains("\"old_version\": \"1.0.0\""))
+        .stdout(predicate::str::contains("Cargo.toml"));
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "rollback"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Rolled back 1.1.1 → 1.1.0"));
+    assert_eq!(fs::read_to_string(temp_dir.path().join("version.txt")).unwrap(), "1.1.0\n");
+    assert!(fs::read_to_string(temp_dir.path().join("Cargo.toml")).unwrap().contains("version = \"1.1.0\""));
+    
+    // A second rollback walks further back instead of undoing the first
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "rollback"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(temp_dir.path().join("version.txt")).unwrap(), "1.0.0\n");
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "rollback"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No version change to roll back"));
+}
+
+#[test]
+fn test_update_branch_and_path_policies() {
+    let temp_dir = TempDir::new().unwrap();
+    setup_git_repo(temp_dir.path()).unwrap();
+    fs::write(temp_dir.path().join("Cargo.toml"), "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n").unwrap();
+    fs::create_dir_all(temp_dir.path().join(".ws")).unwrap();
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "config", "set", "branches", "release/*"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["update", "--no-git"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("is not one of release/*"));
+    assert!(!temp_dir.path().join("version.txt").exists());
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "config", "set", "branches", ""])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "config", "set", "ignore_paths", "docs/**,*.md"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    
+    // Doc-only changes leave the version alone
+    fs::create_dir_all(temp_dir.path().join("docs")).unwrap();
+    fs::write(temp_dir.path().join("docs/guide.txt"), "guide").unwrap();
+    fs::write(temp_dir.path().join("CHANGES.md"), "changes").unwrap();
+    std::process::Command::new("git")
+        .args(["add", "docs/guide.txt", "CHANGES.md"])
+        .current_dir(temp_dir.path())
+        .output()
+        .unwrap();
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["update", "--no-git"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("only ignored paths changed"));
+    assert!(!temp_dir.path().join("version.txt").exists());
+    
+    std::process::Command::new("git")
+        .args(["add", "Cargo.toml"])
+        .current_dir(temp_dir.path())
+        .output()
+        .unwrap();
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["update", "--no-git"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    assert!(temp_dir.path().join("version.txt").exists());
+}
+
+#[test]
+fn test_layered_config_files() {
+    let temp_dir = TempDir::new().unwrap();
+    let user_config = TempDir::new().unwrap();
+    setup_git_repo(temp_dir.path()).unwrap();
+    fs::write(temp_dir.path().join("Cargo.toml"), "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n").unwrap();
+    fs::create_dir_all(temp_dir.path().join(".ws")).unwrap();
+    fs::create_dir_all(user_config.path().join("ws")).unwrap();
+    fs::write(user_config.path().join("ws/version.yaml"), "version_file: VERSION\nstrategy: conventional\n").unwrap();
+    fs::write(temp_dir.path().join(".ws/version.toml"), "strategy = \"count\"\n").unwrap();
+    
+    // User defaults seed the new project, the repository file wins over both
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "config", "get", "version_file"])
+        .env("XDG_CONFIG_HOME", user_config.path())
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout("VERSION\n");
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "config", "get", "strategy"])
+        .env("XDG_CONFIG_HOME", user_config.path())
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout("count\n");
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "config", "set", "strategy", "conventional"])
+        .env("XDG_CONFIG_HOME", user_config.path())
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("takes precedence"));
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "config", "schema"])
+        .current_dir(user_config.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"ignore_paths\""));
+}
+
+#[test]
+fn test_version_sync_rewrites_disagreeing_files() {
+    let temp_dir = TempDir::new().unwrap();
+    setup_git_repo(temp_dir.path()).unwrap();
+    fs::write(temp_dir.path().join("Cargo.toml"), "[package]\nname = \"demo\"\nversion = \"1.0.0\"\n").unwrap();
+    fs::write(temp_dir.path().join("package.json"), "{\n  \"name\": \"demo\",\n  \"version\": \"1.1.0\"\n}\n").unwrap();
+    fs::write(temp_dir.path().join("version.txt"), "1.2.0\n").unwrap();
+    fs::create_dir_all(temp_dir.path().join(".ws")).unwrap();
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "sync", "--dry-run"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("+version = \"1.2.0\""));
+    assert!(fs::read_to_string(temp_dir.path().join("Cargo.toml")).unwrap().contains("version = \"1.0.0\""));
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "sync"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Synced 2 files to 1.2.0"));
+    assert!(fs::read_to_string(temp_dir.path().join("Cargo.toml")).unwrap().contains("version = \"1.2.0\""));
+    assert!(fs::read_to_string(temp_dir.path().join("package.json")).unwrap().contains("\"version\": \"1.2.0\""));
+    assert_eq!(fs::read_to_string(temp_dir.path().join("version.txt")).unwrap(), "1.2.0\n");
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "sync"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("already carry 1.2.0"));
+}
+
+#[test]
+fn test_version_check_and_semver_strict() {
+    let temp_dir = TempDir::new().unwrap();
+    setup_git_repo(temp_dir.path()).unwrap();
+    fs::write(temp_dir.path().join("Cargo.toml"), "[package]\nname = \"demo\"\nversion = \"1.0.5\"\n").unwrap();
+    fs::write(temp_dir.path().join("version.txt"), "1.0.5.100\n").unwrap();
+    fs::create_dir_all(temp_dir.path().join(".ws")).unwrap();
+    let ws = |args: &[&str]| {
+        Command::cargo_bin("ws")
+            .unwrap()
+            .args(args)
+            .current_dir(temp_dir.path())
+            .assert()
+    };
+    
+    ws(&["version", "check"])
+        .failure()
+        .stdout(predicate::str::contains("1.0.5.100 is not a semantic version"))
+        .stdout(predicate::str::contains("Cargo.toml does not carry version 1.0.5.100"));
+    
+    // A format whose core is not numeric cannot be made strict
+    ws(&["version", "format", "{branch}.{patch}"]).success();
+    ws(&["version", "config", "set", "semver_strict", "true"])
+        .failure()
+        .stderr(predicate::str::contains("cannot give a semantic version"));
+    
+    // Extra components fold into the build metadata
+    ws(&["version", "format", "{major}.{minor}.{patch}.{commits}"]).success();
+    ws(&["version", "config", "set", "semver_strict", "true"]).success();
+    ws(&["version", "format", "{short_sha}"])
+        .failure()
+        .stderr(predicate::str::contains("cannot give a semantic version"));
+    ws(&["update", "--no-git"]).success();
+    let version = fs::read_to_string(temp_dir.path().join("version.txt")).unwrap();
+    assert!(version.trim().contains('+'), "{} was not folded", version);
+    
+    ws(&["version", "check"])
+        .success()
+        .stdout(predicate::str::contains("is a strict semantic version"));
+}
+
+#[test]
+fn test_custom_tag_prefix_and_pattern() {
+    let temp_dir = TempDir::new().unwrap();
+    setup_git_repo(temp_dir.path()).unwrap();
+    fs::create_dir_all(temp_dir.path().join(".ws")).unwrap();
+    fs::write(temp_dir.path().join("Cargo.toml"), "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n").unwrap();
+    let git = |args: &[&str]| {
+        std::process::Command::new("git").args(args).current_dir(temp_dir.path()).output().unwrap();
+    };
+    git(&["tag", "release-1.2"]);
+    git(&["tag", "otherapp/v9.0.0"]);
+    git(&["commit", "--allow-empty", "-m", "feat: add export"]);
+    
+    for (key, value) in [("strategy", "conventional"), ("tag_prefix", "release-"), ("tag_pattern", "release-*")] {
+        Command::cargo_bin("ws")
+            .unwrap()
+            .args(["version", "config", "set", key, value])
+            .current_dir(temp_dir.path())
+            .assert()
+            .success();
+    }
+    
+    // Only release-* tags count, so the version continues from release-1.2
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "show"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1.3.0"))
+        .stdout(predicate::str::contains("since release-1.2"));
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "tag"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("release-1.3.0"));
+}
+
+#[test]
+fn test_release_channels() {
+    let temp_dir = TempDir::new().unwrap();
+    setup_git_repo(temp_dir.path()).unwrap();
+    fs::create_dir_all(temp_dir.path().join(".ws")).unwrap();
+    fs::write(temp_dir.path().join("Cargo.toml"), "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n").unwrap();
+    let git = |args: &[&str]| {
+        std::process::Command::new("git").args(args).current_dir(temp_dir.path()).output().unwrap();
+    };
+    git(&["tag", "v1.2.0"]);
+    git(&["commit", "--allow-empty", "-m", "feat: add export"]);
+    
+    for (key, value) in [("strategy", "conventional"), ("channels", "beta")] {
+        Command::cargo_bin("ws")
+            .unwrap()
+            .args(["version", "config", "set", key, value])
+            .current_dir(temp_dir.path())
+            .assert()
+            .success();
+    }
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "show", "--channel", "beta"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1.3.0-beta.1"))
+        .stdout(predicate::str::contains("no tags yet"));
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "tag", "--channel", "beta", "--allow-dirty"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("v1.3.0-beta.1"));
+    git(&["commit", "--allow-empty", "-m", "fix: handle empty export"]);
+    
+    // Beta tags continue their own train and leave the stable version alone
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "show", "--channel", "beta"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1.3.0-beta.2"));
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "show"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1.3.0"))
+        .stdout(predicate::str::contains("beta").not());
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["update", "--channel", "beta", "--no-git"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    let beta_version = fs::read_to_string(temp_dir.path().join("version-beta.txt")).unwrap();
+    assert_eq!(beta_version.trim(), "1.3.0-beta.2");
+    assert!(!temp_dir.path().join("version.txt").exists());
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "show", "--channel", "alpha"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No channel named alpha"));
+}
+
+#[test]
+fn test_separate_commit_mode() {
+    let temp_dir = TempDir::new().unwrap();
+    setup_git_repo(temp_dir.path()).unwrap();
+    fs::create_dir_all(temp_dir.path().join(".ws")).unwrap();
+    fs::write(temp_dir.path().join("Cargo.toml"), "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n").unwrap();
+    let git = |args: &[&str]| {
+        let output = std::process::Command::new("git").args(args).current_dir(temp_dir.path()).output().unwrap();
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+    
+    for (key, value) in [("commit_mode", "separate"), ("skip_ci", "true")] {
+        Command::cargo_bin("ws")
+            .unwrap()
+            .args(["version", "config", "set", key, value])
+            .current_dir(temp_dir.path())
+            .assert()
+            .success();
+    }
+    
+    // The pre-commit hook cannot commit while a commit is in progress
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["update", "--git-add", "--hook", "pre-commit"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("commit_mode is separate"));
+    assert!(!temp_dir.path().join("version.txt").exists());
+    
+    // The post-commit hook commits the version files after the commit
+    let commits_before = git(&["rev-list", "--count", "HEAD"]);
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["update", "--git-add", "--hook", "post-commit"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    let version = fs::read_to_string(temp_dir.path().join("version.txt")).unwrap();
+    assert_eq!(git(&["log", "-1", "--format=%s"]), format!("chore: bump version to {} [skip ci]", version.trim()));
+    assert_eq!(git(&["rev-list", "--count", "HEAD"]).parse::<u32>().unwrap(), commits_before.parse::<u32>().unwrap() + 1);
+    assert_eq!(git(&["log", "-1", "--format=%s", "HEAD~1"]), "Initial commit");
+}
+
+#[test]
+fn test_annotate_commit_msg() {
+    let temp_dir = TempDir::new().unwrap();
+    setup_git_repo(temp_dir.path()).unwrap();
+    fs::create_dir_all(temp_dir.path().join(".ws")).unwrap();
+    fs::write(temp_dir.path().join("Cargo.toml"), "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n").unwrap();
+    let message_file = temp_dir.path().join("COMMIT_EDITMSG");
+    let annotate = || {
+        Command::cargo_bin("ws")
+            .unwrap()
+            .args(["version", "annotate-commit-msg"])
+            .arg(&message_file)
+            .arg("message")
+            .current_dir(temp_dir.path())
+            .assert()
+            .success();
+        fs::read_to_string(&message_file).unwrap()
+    };
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["update", "--git-add", "--hook", "pre-commit"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    let version = fs::read_to_string(temp_dir.path().join("version.txt")).unwrap();
+    
+    // Off by default
+    fs::write(&message_file, "feat: add parser\n").unwrap();
+    assert_eq!(annotate(), "feat: add parser\n");
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["git", "install", "--annotate-commit-msg"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    assert!(temp_dir.path().join(".git/hooks/prepare-commit-msg").exists());
+    let expected = format!("feat: add parser\n\n(version: {})\n", version.trim());
+    assert_eq!(annotate(), expected);
+    // Only once
+    assert_eq!(annotate(), expected);
+    
+    // Commits without the version files are left alone
+    std::process::Command::new("git").args(["reset", "-q"]).current_dir(temp_dir.path()).output().unwrap();
+    fs::write(&message_file, "docs: typo\n").unwrap();
+    assert_eq!(annotate(), "docs: typo\n");
+}
+
+#[test]
+fn test_version_check_remote() {
+    let temp_dir = TempDir::new().unwrap();
+    let remote_dir = TempDir::new().unwrap();
+    setup_git_repo(temp_dir.path()).unwrap();
+    fs::create_dir_all(temp_dir.path().join(".ws")).unwrap();
+    fs::write(temp_dir.path().join("Cargo.toml"), "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n").unwrap();
+    let git = |args: &[&str]| {
+        std::process::Command::new("git").args(args).current_dir(temp_dir.path()).output().unwrap();
+    };
+    std::process::Command::new("git").args(["init", "--bare", "-q"]).current_dir(remote_dir.path()).output().unwrap();
+    git(&["remote", "add", "origin", remote_dir.path().to_str().unwrap()]);
+    
+    // Without release tags on the remote there is nothing to compare with
+    fs::write(temp_dir.path().join("version.txt"), "1.2.0\n").unwrap();
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "check-remote"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No release tags on origin"));
+    
+    git(&["tag", "v1.2.0"]);
+    git(&["push", "-q", "origin", "v1.2.0"]);
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "check-remote"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not newer than the latest release on origin (v1.2.0)"));
+    
+    fs::write(temp_dir.path().join("version.txt"), "1.2.1\n").unwrap();
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "check-remote", "origin"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("newer than v1.2.0"));
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["git", "install", "--hook", "pre-push", "--require-bump"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    let hook = fs::read_to_string(temp_dir.path().join(".git/hooks/pre-push")).unwrap();
+    assert!(hook.contains("version check-remote \"$1\" || exit 1"));
+}
+
+#[test]
+fn test_git_doctor() {
+    let temp_dir = TempDir::new().unwrap();
+    setup_git_repo(temp_dir.path()).unwrap();
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["git", "doctor"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No ws hook installed"));
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["git", "install"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    let hook_file = temp_dir.path().join(".git/hooks/pre-commit");
+    let hook = fs::read_to_string(&hook_file).unwrap();
+    assert!(hook.starts_with("#!/bin/sh\n"));
+    assert!(hook.contains("command -v ws"));
+    
+    // Reinstalling keeps a single shebang
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["git", "install", "--force"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(&hook_file).unwrap().matches("#!").count(), 1);
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["git", "doctor"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Hooks are working"));
+    
+    // Hooks from before the shim run a fixed path
+    fs::write(&hook_file, "#!/bin/bash\n# === WS BLOCK START ===\n/nonexistent/ws update --git-add\n# === WS BLOCK END ===\n").unwrap();
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["git", "doctor"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("by absolute path"));
+}
+
+#[test]
+fn test_install_hooks_in_worktree_and_submodules() {
+    let temp_dir = TempDir::new().unwrap();
+    let main = temp_dir.path().join("main");
+    let library = temp_dir.path().join("library");
+    for dir in [&main, &library] {
+        fs::create_dir_all(dir).unwrap();
+        setup_git_repo(dir).unwrap();
+    }
+    let git = |args: &[&str]| {
+        std::process::Command::new("git").args(args).current_dir(&main).output().unwrap();
+    };
+    git(&["-c", "protocol.file.allow=always", "submodule", "add", library.to_str().unwrap(), "lib"]);
+    git(&["commit", "-m", "Add library"]);
+    let worktree = temp_dir.path().join("feature");
+    git(&["worktree", "add", worktree.to_str().unwrap()]);
+    
+    // Installing from a linked worktree writes to the shared hooks directory
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["git", "install", "--recursive"])
+        .current_dir(&worktree)
+        .assert()
+        .success();
+    assert!(fs::read_to_string(main.join(".git/hooks/pre-commit")).unwrap().contains("WS BLOCK START"));
+    assert!(!worktree.join(".git/hooks").exists());
+    
+    // The worktree has no submodules checked out, the main repository does
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["git", "install", "--recursive", "--force"])
+        .current_dir(&main)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Submodule"));
+    assert!(fs::read_to_string(main.join(".git/modules/lib/hooks/pre-commit")).unwrap().contains("WS BLOCK START"));
+}
+
+#[test]
+fn test_git_status_check() {
+    let temp_dir = TempDir::new().unwrap();
+    setup_git_repo(temp_dir.path()).unwrap();
+    fs::create_dir_all(temp_dir.path().join(".ws")).unwrap();
+    fs::write(temp_dir.path().join("version.txt"), "1.2.0\n").unwrap();
+    fs::write(temp_dir.path().join("Cargo.toml"), "[package]\nname = \"demo\"\nversion = \"1.1.0\"\n").unwrap();
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["git", "status", "--check"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("No ws git hook is installed"))
+        .stdout(predicate::str::contains("Cargo.toml does not carry version 1.2.0"));
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["git", "install"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["git", "status", "--check", "--format", "json"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("\"passed\": false"));
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "sync"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["git", "status", "--check"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("versions in sync"));
+    
+    fs::write(temp_dir.path().join(".ws/version.toml"), "no_such_setting = true\n").unwrap();
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["git", "status", "--check"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("Configuration is invalid"));
+}
+
+#[test]
+fn test_logging_settings() {
+    let temp_dir = TempDir::new().unwrap();
+    setup_git_repo(temp_dir.path()).unwrap();
+    fs::create_dir_all(temp_dir.path().join(".ws")).unwrap();
+    fs::write(temp_dir.path().join("Cargo.toml"), "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n").unwrap();
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "config", "set", "logging.level", "loud"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown log level"));
+    
+    for (key, value) in [("logging.location", "git"), ("logging.max_size_mb", "1"), ("logging.max_files", "3")] {
+        Command::cargo_bin("ws")
+            .unwrap()
+            .args(["version", "config", "set", key, value])
+            .current_dir(temp_dir.path())
+            .assert()
+            .success();
+    }
+    fs::remove_dir_all(temp_dir.path().join(".ws/logs")).ok();
+    
+    // The log moves into the git directory, out of git status
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["update", "--no-git"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    assert!(temp_dir.path().join(".git/ws/logs/ws.log").exists());
+    assert!(!temp_dir.path().join(".ws/logs/ws.log").exists());
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "config", "get", "logging.location"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("git"));
+}
+
+#[test]
+fn test_shared_config_file_and_environment() {
+    let temp_dir = TempDir::new().unwrap();
+    setup_git_repo(temp_dir.path()).unwrap();
+    fs::create_dir_all(temp_dir.path().join(".ws")).unwrap();
+    fs::write(temp_dir.path().join("Cargo.toml"), "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n").unwrap();
+    fs::write(temp_dir.path().join("ws.toml"), "[version]\nstrategy = \"conventional\"\ntag_prefix = \"release-\"\n").unwrap();
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "config", "get", "strategy"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("conventional"));
+    
+    // .ws/version.toml is more specific than ws.toml, the environment beats both
+    fs::write(temp_dir.path().join(".ws/version.toml"), "tag_prefix = \"v\"\n").unwrap();
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "config", "get", "tag_prefix"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::diff("v\n"));
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "config", "get", "strategy"])
+        .env("WS_VERSION_STRATEGY", "count")
+        .current_dir(temp_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("count"));
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["version", "config", "get", "strategy"])
+        .env("WS_VERSION_STRATEGY", "semver")
+        .current_dir(temp_dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("WS_VERSION_STRATEGY"));
+}
+
+#[test]
+fn test_trace_output() {
+    let temp_dir = TempDir::new().unwrap();
+    setup_git_repo(temp_dir.path()).unwrap();
+    fs::create_dir_all(temp_dir.path().join(".ws")).unwrap();
+    fs::write(temp_dir.path().join("Cargo.toml"), "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n").unwrap();
+    
+    Command::cargo_bin("ws")
+        .unwrap()
+        .args(["update", "--no-git", "--trace-output", "trace.json"])
+        .current_dir(temp_dir.path())
+        .assert()
+        .success();
+    
+    let trace: serde_json::Value = serde_json::from_str(&fs::read_to_string(temp_dir.path().join("trace.json")).unwrap()).unwrap();
+    let names: Vec<&str> = trace.as_array().unwrap().iter().filter_map(|event| event["name"].as_str()).collect();
+    for span in ["ws", "update_state", "apply_version", "commit_count"] {
+        assert!(names.contains(&span), "no {} span in {:?}", span, names);
+    }
+}